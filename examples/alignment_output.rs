@@ -0,0 +1,45 @@
+use genedex::{
+    FmIndexConfig, alphabet,
+    io_align::{AlignmentFormat, AlignmentRecord, AlignmentWriter, Strand},
+};
+
+fn main() {
+    // This example shows how to report the hits of queries as SAM records. This requires the io-align feature.
+
+    let records = [("chr1", b"ACGTACGTTAGC".as_slice()), ("chr2", b"TTAGCAC")];
+    let index =
+        FmIndexConfig::<i32>::new().construct_index_from_records(records, alphabet::ascii_dna());
+
+    let mut writer = AlignmentWriter::new(
+        Vec::new(),
+        AlignmentFormat::Sam,
+        &index,
+        index.text_names().unwrap(),
+    )
+    .unwrap();
+
+    let reads = [("read1", b"TAGC".as_slice()), ("read2", b"GTAC")];
+
+    for (read_name, read) in reads {
+        for hit in index.locate(read) {
+            let record = AlignmentRecord::exact(read_name, read.len(), hit, Strand::Forward);
+            writer.write_record(&record).unwrap();
+        }
+    }
+
+    let output = String::from_utf8(writer.into_inner()).unwrap();
+    print!("{output}");
+
+    // two header lines for the texts and one record per hit
+    assert_eq!(
+        output
+            .lines()
+            .filter(|line| line.starts_with("@SQ"))
+            .count(),
+        2
+    );
+    assert_eq!(
+        output.lines().filter(|line| !line.starts_with('@')).count(),
+        3
+    );
+}
@@ -0,0 +1,50 @@
+use genedex::{
+    bench,
+    text_with_rank_support::{
+        Block64, Block512, CondensedTextWithRankSupport, FlatTextWithRankSupport,
+    },
+};
+
+fn main() {
+    // This example compares the running times of rank queries using the different block configurations.
+    // The vectorized kernels of Block512 are selected at runtime. They can additionally be inlined when the
+    // respective target features are enabled at compile time, for example by running:
+    //
+    // RUSTFLAGS="-C target-cpu=native" cargo run --release --example block_benchmark --features bench
+
+    let text_len = 1 << 20;
+    let num_queries = 1 << 20;
+
+    for alphabet_size in [4, 20] {
+        let (flat64, flat64_time) = bench::measure_rank::<u32, FlatTextWithRankSupport<u32, Block64>>(
+            text_len,
+            alphabet_size,
+            num_queries,
+            42,
+        );
+        let (flat512, flat512_time) = bench::measure_rank::<
+            u32,
+            FlatTextWithRankSupport<u32, Block512>,
+        >(text_len, alphabet_size, num_queries, 42);
+        let (condensed64, condensed64_time) = bench::measure_rank::<
+            u32,
+            CondensedTextWithRankSupport<u32, Block64>,
+        >(text_len, alphabet_size, num_queries, 42);
+        let (condensed512, condensed512_time) = bench::measure_rank::<
+            u32,
+            CondensedTextWithRankSupport<u32, Block512>,
+        >(text_len, alphabet_size, num_queries, 42);
+
+        // all configurations answer the same queries
+        assert!(
+            [flat512, condensed64, condensed512]
+                .iter()
+                .all(|&sum| sum == flat64)
+        );
+
+        println!(
+            "alphabet size {alphabet_size}: flat Block64 {flat64_time:?}, flat Block512 {flat512_time:?}, \
+            condensed Block64 {condensed64_time:?}, condensed Block512 {condensed512_time:?}"
+        );
+    }
+}
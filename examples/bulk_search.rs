@@ -0,0 +1,44 @@
+use genedex::{FmIndexConfig, alphabet};
+
+fn main() {
+    // This example shows the functions for searching many queries at once and for handling many hits.
+
+    let texts = [b"ACGTACGTACGT".as_slice(), b"TTACGGACGT"];
+    let index = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(4)
+        .construct_index(texts, alphabet::ascii_dna());
+
+    // The queries of the bulk functions are searched in an interleaved way, which is faster than searching
+    // them one after another. The results are returned in the order of the queries.
+    let queries = [b"ACGT".as_slice(), b"GGA", b"CCC", b"ACGT"];
+
+    let counts: Vec<_> = index.count_many(queries).collect();
+    assert_eq!(counts, [4, 1, 0, 4]);
+
+    // When the queries contain many duplicates, every distinct query can be searched only once.
+    let deduplicated_counts: Vec<_> = index.count_many_deduplicated(queries).collect();
+    assert_eq!(deduplicated_counts, counts);
+
+    for (query_id, hits) in index.locate_many_deduplicated(queries).enumerate() {
+        let mut hits: Vec<_> = hits.collect();
+        hits.sort();
+
+        let mut expected_hits: Vec<_> = index.locate(queries[query_id]).collect();
+        expected_hits.sort();
+
+        assert_eq!(hits, expected_hits);
+    }
+
+    // A hit set allows deciding how to resolve the hits of a query after counting them.
+    let hit_set = index.hit_set(b"ACG");
+    assert_eq!(hit_set.count(), 5);
+
+    // For example, only the first hits (by text id and position) can be reported.
+    let first_hits = hit_set.take_sorted(2);
+    assert_eq!(first_hits[0].text_id, 0);
+    assert_eq!(first_hits[0].position, 0);
+    assert_eq!(first_hits[1].position, 4);
+
+    // Or all hits are resolved in parallel.
+    assert_eq!(hit_set.resolve_all_par().len(), 5);
+}
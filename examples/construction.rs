@@ -0,0 +1,60 @@
+use genedex::{
+    AnyFmIndex, FmIndexConfig, PerformancePriority, TextSampling, alphabet,
+    text_with_rank_support::{Block512, FlatTextWithRankSupport, PackedTextWithRankSupport},
+};
+
+fn main() {
+    // This example shows the different ways to configure the construction of the FM-Index.
+
+    let records = [
+        ("chr1", b"ACGTACGTTAGC".as_slice()),
+        ("chr2", b"TTAGCAC"),
+        ("plasmid", b"GGACGT"),
+    ];
+    let texts = records.map(|(_, text)| text);
+    let query = b"ACG";
+
+    // The default configuration uses i32 to store the suffix array and the condensed text with rank support.
+    let default_index = FmIndexConfig::<i32>::new().construct_index(texts, alphabet::ascii_dna());
+    assert_eq!(default_index.count(query), 3);
+
+    // Named records can be used to report hits with the name of the text.
+    let named_index =
+        FmIndexConfig::<i32>::new().construct_index_from_records(records, alphabet::ascii_dna());
+
+    for hit in named_index.locate(query) {
+        println!(
+            "Found query in {} at position {}.",
+            named_index.text_name(hit.text_id).unwrap(),
+            hit.position
+        );
+    }
+
+    // The index storage type and the text with rank support implementation can be selected
+    // using the type parameters of the config.
+    let flat_index = FmIndexConfig::<u32, FlatTextWithRankSupport<u32, Block512>>::new()
+        .suffix_array_sampling_rate(8)
+        .lookup_table_depth(4)
+        .construction_performance_priority(PerformancePriority::HighSpeed)
+        .construct_index(texts, alphabet::ascii_dna());
+    assert_eq!(flat_index.count(query), 3);
+
+    let packed_index = FmIndexConfig::<i64, PackedTextWithRankSupport<i64>>::new()
+        .construction_performance_priority(PerformancePriority::LowMemory)
+        .construct_index(texts, alphabet::ascii_dna());
+    assert_eq!(packed_index.count(query), 3);
+
+    // The implementation can also be selected at runtime. The result is an AnyFmIndex.
+    let any_index = FmIndexConfig::<i32>::new()
+        .auto_rank_support(PerformancePriority::Balanced)
+        .construct_index(texts, alphabet::ascii_dna());
+    assert!(matches!(any_index, AnyFmIndex::I32Condensed64(_)));
+    assert_eq!(any_index.count(query), 3);
+
+    // Only a subset of the texts can be indexed, e.g. to create a small index for tests.
+    let sampled_index = FmIndexConfig::<i32>::new()
+        .text_sampling(TextSampling::EveryNth(2))
+        .construct_index(texts, alphabet::ascii_dna());
+    assert_eq!(sampled_index.num_texts(), 2);
+    assert_eq!(sampled_index.original_text_id(1), 2);
+}
@@ -0,0 +1,25 @@
+use genedex::{AnyFmIndex, FmIndex, FmIndexConfig, alphabet};
+
+fn main() {
+    // This example shows how to store an index in a file and load it again. This requires the savefile feature,
+    // which is enabled by default.
+
+    let texts = [b"ACGTACGTACGT".as_slice(), b"TTACGGACGT"];
+    let index = FmIndexConfig::<i32>::new().construct_index(texts, alphabet::ascii_dna());
+
+    let filepath = std::env::temp_dir().join(format!("genedex_example_{}.fmi", std::process::id()));
+
+    index.save_to_file(&filepath).unwrap();
+
+    // The type of the loaded index has to match the type of the stored index.
+    let loaded_index = FmIndex::<i32>::load_from_file(&filepath).unwrap();
+    assert_eq!(loaded_index.count(b"ACG"), index.count(b"ACG"));
+    assert_eq!(loaded_index.extract_text(1), b"TTACGGACGT");
+
+    // If the type is not known, the variant of the index can be detected when loading it.
+    let any_index = AnyFmIndex::load_from_file(&filepath).unwrap();
+    assert!(matches!(any_index, AnyFmIndex::I32Condensed64(_)));
+    assert_eq!(any_index.count(b"ACG"), index.count(b"ACG"));
+
+    std::fs::remove_file(&filepath).unwrap();
+}
@@ -25,8 +25,16 @@ pub struct Alphabet {
     io_to_dense_representation_table: Vec<u8>,
     dense_to_io_representation_table: Vec<u8>,
     num_io_symbols_not_searcheable: usize,
+    // compact code in 0..num_searchable_dense_symbols for every dense symbol, or NOT_SEARCHABLE
+    #[cfg_attr(feature = "savefile", savefile_versions = "4..")]
+    searchable_codes: Vec<u8>,
+    // complement of every dense symbol (the sentinel is its own complement), empty if no complement is defined
+    #[cfg_attr(feature = "savefile", savefile_versions = "6..")]
+    dense_complement: Vec<u8>,
 }
 
+const NOT_SEARCHABLE: u8 = u8::MAX;
+
 impl Alphabet {
     /// Construct an alphabet from symbols in IO representation.
     ///
@@ -148,6 +156,60 @@ impl Alphabet {
         )
     }
 
+    /// Construct an alphabet from the symbols that occur in `texts`, and classify rare symbols as not searchable.
+    ///
+    /// Every distinct byte of the texts becomes a symbol of the alphabet (case-sensitive). Symbols whose share of
+    /// all symbols of the texts is smaller than `min_searchable_frequency`, or that are contained in
+    /// `non_searchable_symbols`, are classified as not searchable. This keeps rare symbols such as `N` out of
+    /// the lookup table, which reduces its memory usage for a given depth (see
+    /// [`FmIndexConfig::lookup_table_depth`](crate::FmIndexConfig::lookup_table_depth)).
+    ///
+    /// The searchable symbols are ordered by their IO representation, followed by the non-searchable symbols.
+    /// Panics if no symbol is searchable.
+    ///
+    /// ```
+    /// use genedex::Alphabet;
+    /// let texts = [b"ACGTACGTNACGTACGTACGTAAAACCCGGGTTTR".as_slice(), b"ACGTNN"];
+    ///
+    /// let alphabet = Alphabet::from_texts(texts, 0.05, b"N");
+    /// assert_eq!(alphabet.num_dense_symbols(), 7);
+    /// assert_eq!(alphabet.num_searchable_dense_symbols(), 4);
+    /// assert_eq!(alphabet.dense_to_io_representation(5), b'N');
+    /// ```
+    pub fn from_texts<T: AsRef<[u8]>>(
+        texts: impl IntoIterator<Item = T>,
+        min_searchable_frequency: f64,
+        non_searchable_symbols: &[u8],
+    ) -> Self {
+        let mut symbol_counts = [0usize; 256];
+
+        for text in texts {
+            for &symbol in text.as_ref() {
+                symbol_counts[symbol as usize] += 1;
+            }
+        }
+
+        let total_count: usize = symbol_counts.iter().sum();
+
+        let (searchable, not_searchable): (Vec<_>, Vec<_>) = (0..=255u8)
+            .filter(|&symbol| symbol_counts[symbol as usize] > 0)
+            .partition(|&symbol| {
+                let frequency = symbol_counts[symbol as usize] as f64 / total_count as f64;
+
+                frequency >= min_searchable_frequency && !non_searchable_symbols.contains(&symbol)
+            });
+
+        assert!(
+            !searchable.is_empty(),
+            "At least one symbol of the texts must be searchable."
+        );
+
+        Self::from_io_symbols(
+            searchable.iter().chain(&not_searchable),
+            not_searchable.len(),
+        )
+    }
+
     fn new(
         io_to_dense_representation_table: Vec<u8>,
         dense_to_io_representation_table: Vec<u8>,
@@ -184,10 +246,196 @@ impl Alphabet {
             "Invalid alphabet. there must be at least one searchable symbol."
         );
 
-        Self {
+        let num_searchable = size - num_io_symbols_not_searcheable - 1;
+        let searchable_codes = searchable_codes_from_flags(
+            (0..size).map(|dense_symbol| dense_symbol <= num_searchable),
+        );
+
+        let mut alphabet = Self {
             io_to_dense_representation_table,
             dense_to_io_representation_table,
             num_io_symbols_not_searcheable,
+            searchable_codes,
+            dense_complement: Vec::new(),
+        };
+
+        alphabet.dense_complement = alphabet.nucleotide_dense_complement();
+
+        alphabet
+    }
+
+    /// Marks the given symbols in IO representation as not searchable, wherever they are in the dense representation.
+    ///
+    /// By default, the non-searchable symbols are the last symbols of the dense representation. This function allows
+    /// choosing the order of the dense representation (which determines the order of [`FmIndex::locate`](crate::FmIndex::locate))
+    /// independently of which symbols are searchable. The lookup table always uses a compact code for the searchable
+    /// symbols (see [`Self::searchable_code`]), so its size only depends on the number of searchable symbols.
+    ///
+    /// Panics if a symbol is not part of the alphabet or if no searchable symbol remains.
+    ///
+    /// ```
+    /// use genedex::Alphabet;
+    /// let alphabet = Alphabet::from_io_symbols(b"ACGNT", 0).with_non_searchable_io_symbols(b"N");
+    ///
+    /// assert_eq!(alphabet.num_searchable_dense_symbols(), 4);
+    /// assert!(!alphabet.is_searchable(alphabet.io_to_dense_representation(b'N')));
+    /// assert_eq!(alphabet.searchable_code(alphabet.io_to_dense_representation(b'T')), Some(3));
+    /// ```
+    pub fn with_non_searchable_io_symbols(mut self, symbols: &[u8]) -> Self {
+        let mut is_searchable: Vec<_> = (0..self.num_dense_symbols() as u8)
+            .map(|dense_symbol| self.is_searchable(dense_symbol))
+            .collect();
+
+        for &symbol in symbols {
+            is_searchable[self.io_to_dense_representation(symbol) as usize] = false;
+        }
+
+        let num_searchable = is_searchable.iter().filter(|&&s| s).count();
+
+        assert!(
+            num_searchable > 0,
+            "Invalid alphabet. there must be at least one searchable symbol."
+        );
+
+        self.num_io_symbols_not_searcheable = self.num_dense_symbols() - num_searchable - 1;
+        self.searchable_codes = searchable_codes_from_flags(is_searchable);
+
+        self
+    }
+
+    /// Defines the complement of every symbol, for example to search the reverse strand of DNA.
+    ///
+    /// The complement is given as pairs of symbols in IO representation that are complements of each other.
+    /// A symbol can be its own complement, like `N` for DNA. Ambiguous IO symbols only need to be given once.
+    /// Panics if a symbol is not part of the alphabet, if the complement of a symbol is defined inconsistently
+    /// or if a symbol has no complement.
+    ///
+    /// The DNA alphabets of this library already define the usual complement of nucleotides. Alphabets that only
+    /// consist of the nucleotides `A`, `C`, `G`, `T` and `N` also get this complement automatically. The complement
+    /// is needed for the [`FmdIndex`](crate::FmdIndex) and for searching both strands with the FM-Index
+    /// (see [`FmIndex::count_with_revcomp`](crate::FmIndex::count_with_revcomp)).
+    ///
+    /// ```
+    /// use genedex::Alphabet;
+    /// let rna = Alphabet::from_ambiguous_io_symbols([b"Aa", b"Cc", b"Gg", b"Uu"], 0)
+    ///     .with_complement([(b'A', b'U'), (b'C', b'G')]);
+    ///
+    /// assert_eq!(rna.complement(b'u'), Some(b'A'));
+    /// assert_eq!(rna.reverse_complement(b"AACGU"), b"ACGUU");
+    /// ```
+    pub fn with_complement(mut self, complement_pairs: impl IntoIterator<Item = (u8, u8)>) -> Self {
+        const UNDEFINED: u8 = u8::MAX;
+
+        let mut dense_complement = vec![UNDEFINED; self.num_dense_symbols()];
+        dense_complement[0] = 0;
+
+        for (first, second) in complement_pairs {
+            let first = self.io_to_dense_representation(first);
+            let second = self.io_to_dense_representation(second);
+
+            for (symbol, complement) in [(first, second), (second, first)] {
+                let entry = &mut dense_complement[symbol as usize];
+
+                assert!(
+                    *entry == UNDEFINED || *entry == complement,
+                    "the complement of a symbol must be defined consistently"
+                );
+
+                *entry = complement;
+            }
+        }
+
+        assert!(
+            !dense_complement.contains(&UNDEFINED),
+            "every symbol of the alphabet must have a complement"
+        );
+
+        self.dense_complement = dense_complement;
+
+        self
+    }
+
+    /// Returns whether a complement is defined for the symbols of this alphabet, see [`Self::with_complement`].
+    ///
+    /// Alphabets of indices that were saved by older versions of this library only have a complement if they
+    /// consist of nucleotides.
+    pub fn has_complement(&self) -> bool {
+        !self.dense_complement.is_empty()
+    }
+
+    /// Returns the complement of a symbol in IO representation, or `None` if the symbol is not part of the alphabet
+    /// or the alphabet has no complement.
+    ///
+    /// The complement is returned as the first IO symbol of its dense symbol. For example, the complement of `a` in
+    /// [`ascii_dna`] is `T`.
+    pub fn complement(&self, symbol: u8) -> Option<u8> {
+        let dense_symbol = self.try_io_to_dense_representation(symbol)?;
+
+        self.dense_complement
+            .get(dense_symbol as usize)
+            .map(|&complement| self.dense_to_io_representation(complement))
+    }
+
+    /// Returns the reverse complement of `text` in IO representation, see [`Self::complement`].
+    ///
+    /// Panics if the alphabet has no complement or if `text` contains symbols that are not part of the alphabet.
+    pub fn reverse_complement(&self, text: &[u8]) -> Vec<u8> {
+        assert!(self.has_complement(), "the alphabet has no complement");
+
+        text.iter()
+            .rev()
+            .map(|&symbol| {
+                self.complement(symbol)
+                    .expect("symbol in io representation should be valid")
+            })
+            .collect()
+    }
+
+    pub(crate) fn dense_complement(&self) -> Option<&[u8]> {
+        self.has_complement()
+            .then_some(self.dense_complement.as_slice())
+    }
+
+    // the usual complement of nucleotides if every symbol is a nucleotide, otherwise empty
+    fn nucleotide_dense_complement(&self) -> Vec<u8> {
+        (0..self.num_dense_symbols() as u8)
+            .map(|dense_symbol| {
+                if dense_symbol == 0 {
+                    return Some(0);
+                }
+
+                let complement =
+                    nucleotide_complement(self.dense_to_io_representation(dense_symbol))?;
+                self.try_io_to_dense_representation(complement)
+            })
+            .collect::<Option<_>>()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn heap_size_bytes(&self) -> usize {
+        self.io_to_dense_representation_table.len()
+            + self.dense_to_io_representation_table.len()
+            + self.searchable_codes.len()
+            + self.dense_complement.len()
+    }
+
+    // indices saved by older versions of this library don't contain the codes, their non-searchable symbols
+    // are always the last symbols of the dense representation
+    #[cfg(feature = "savefile")]
+    pub(crate) fn restore_missing_searchable_codes(&mut self) {
+        if self.searchable_codes.is_empty() {
+            let num_searchable = self.num_searchable_dense_symbols();
+            self.searchable_codes = searchable_codes_from_flags(
+                (0..self.num_dense_symbols()).map(|dense_symbol| dense_symbol <= num_searchable),
+            );
+        }
+    }
+
+    // indices saved by older versions of this library don't contain the complement
+    #[cfg(feature = "savefile")]
+    pub(crate) fn restore_missing_complement(&mut self) {
+        if self.dense_complement.is_empty() {
+            self.dense_complement = self.nucleotide_dense_complement();
         }
     }
 
@@ -233,6 +481,34 @@ impl Alphabet {
             })
     }
 
+    /// Returns whether a symbol in dense representation is allowed to be part of queries. The sentinel is never searchable.
+    pub fn is_searchable(&self, dense_symbol: u8) -> bool {
+        self.searchable_code(dense_symbol).is_some()
+    }
+
+    /// Returns the compact code of a searchable symbol in dense representation, or `None` if it is not searchable.
+    ///
+    /// The searchable symbols are numbered by `0, ... , s-1` in the order of their dense representation, where `s` is
+    /// the number of searchable dense symbols. This code is used as the key of the lookup table of the FM-Index.
+    pub fn searchable_code(&self, dense_symbol: u8) -> Option<u8> {
+        self.searchable_codes
+            .get(dense_symbol as usize)
+            .copied()
+            .filter(|&code| code != NOT_SEARCHABLE)
+    }
+
+    /// Returns the symbol in dense representation for a compact code of a searchable symbol (see [`Self::searchable_code`]).
+    ///
+    /// Panics if `code` is not smaller than the number of searchable dense symbols.
+    pub fn dense_symbol_of_searchable_code(&self, code: u8) -> u8 {
+        assert!(code != NOT_SEARCHABLE, "invalid searchable code");
+
+        self.searchable_codes
+            .iter()
+            .position(|&c| c == code)
+            .expect("invalid searchable code") as u8
+    }
+
     pub fn num_dense_symbols(&self) -> usize {
         self.dense_to_io_representation_table.len() + 1
     }
@@ -241,26 +517,100 @@ impl Alphabet {
         self.num_dense_symbols() - self.num_io_symbols_not_searcheable - 1
     }
 
+    /// Returns `true` if texts and queries of both alphabets can be used interchangeably by the FM-Index.
+    ///
+    /// This is the case if both alphabets have the same dense symbols, the same dense symbols are searchable and
+    /// every IO symbol that is part of both alphabets has the same dense representation in both of them. Other IO
+    /// symbols are allowed to differ. For example, an alphabet of RNA bases is compatible with an alphabet of DNA
+    /// bases, if the `U` and `T` bases have the same dense representation.
+    ///
+    /// ```
+    /// use genedex::{Alphabet, alphabet};
+    /// let rna = Alphabet::from_ambiguous_io_symbols([b"Aa", b"Cc", b"Gg", b"Uu"], 0);
+    /// assert!(rna.is_compatible_with(&alphabet::ascii_dna()));
+    /// assert!(!rna.is_compatible_with(&alphabet::ascii_dna_with_n()));
+    ///
+    /// // A and T are swapped
+    /// let swapped = Alphabet::from_ambiguous_io_symbols([b"Tt", b"Cc", b"Gg", b"Aa"], 0);
+    /// assert!(!swapped.is_compatible_with(&alphabet::ascii_dna()));
+    /// ```
+    pub fn is_compatible_with(&self, other: &Alphabet) -> bool {
+        let shared_io_symbols_agree = self
+            .io_to_dense_representation_table
+            .iter()
+            .zip(&other.io_to_dense_representation_table)
+            .all(|(&dense_symbol, &other_dense_symbol)| {
+                dense_symbol == 0 || other_dense_symbol == 0 || dense_symbol == other_dense_symbol
+            });
+
+        self.num_dense_symbols() == other.num_dense_symbols()
+            && self.searchable_codes == other.searchable_codes
+            && shared_io_symbols_agree
+    }
+
     /// Currently always `true`. Might change in the future.
     pub fn contains_sentinel_in_dense_representation(&self) -> bool {
         true
     }
 }
 
+fn nucleotide_complement(symbol: u8) -> Option<u8> {
+    let complement = match symbol.to_ascii_uppercase() {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' => b'A',
+        b'N' => b'N',
+        _ => return None,
+    };
+
+    if symbol.is_ascii_lowercase() {
+        Some(complement.to_ascii_lowercase())
+    } else {
+        Some(complement)
+    }
+}
+
+fn searchable_codes_from_flags(is_searchable: impl IntoIterator<Item = bool>) -> Vec<u8> {
+    let mut next_code = 0;
+
+    is_searchable
+        .into_iter()
+        .enumerate()
+        .map(|(dense_symbol, is_searchable)| {
+            // the sentinel is never searchable
+            if dense_symbol != 0 && is_searchable {
+                next_code += 1;
+                next_code - 1
+            } else {
+                NOT_SEARCHABLE
+            }
+        })
+        .collect()
+}
+
 /// Includes only the four bases of DNA A, C, G and T (case-insensitive).
+///
+/// The complement of the bases is defined (see [`Alphabet::with_complement`]).
 pub fn ascii_dna() -> Alphabet {
     Alphabet::from_ambiguous_io_symbols([b"Aa", b"Cc", b"Gg", b"Tt"], 0)
+        .with_complement(DNA_COMPLEMENT)
 }
 
 /// Includes the four bases of DNA A, C, G and T, and the N character (case-insensitive). The N character is not allowed to be searched.
+///
+/// The complement of the bases is defined, N is its own complement (see [`Alphabet::with_complement`]).
 pub fn ascii_dna_with_n() -> Alphabet {
     Alphabet::from_ambiguous_io_symbols([b"Aa", b"Cc", b"Gg", b"Tt", b"Nn"], 1)
+        .with_complement(DNA_WITH_N_COMPLEMENT)
 }
 
 /// Includes all values of the IUPAC standard (or .fasta format) for DNA bases, except for gaps (case-insensitive).
 ///
 /// All symbols are allowed to be searched, but the "degenerate" symbols are not resolved to match their base symbols.
 /// For example, M means "A or C", but an M in the searched query does not match at an A or C of the indexed texts.
+///
+/// The complement of all symbols is defined, e.g. the complement of M ("A or C") is K ("T or G").
 pub fn ascii_dna_iupac() -> Alphabet {
     Alphabet::from_ambiguous_io_symbols(
         [
@@ -269,6 +619,14 @@ pub fn ascii_dna_iupac() -> Alphabet {
         ],
         0,
     )
+    .with_complement(DNA_WITH_N_COMPLEMENT.into_iter().chain([
+        (b'R', b'Y'),
+        (b'K', b'M'),
+        (b'S', b'S'),
+        (b'W', b'W'),
+        (b'B', b'V'),
+        (b'D', b'H'),
+    ]))
 }
 
 /// Functionally equivalent to the DNA with N alphabet, but allows other IUPAC DNA
@@ -284,8 +642,13 @@ pub fn ascii_dna_iupac_as_dna_with_n() -> Alphabet {
         ],
         1,
     )
+    .with_complement(DNA_WITH_N_COMPLEMENT)
 }
 
+const DNA_COMPLEMENT: [(u8, u8); 2] = [(b'A', b'T'), (b'C', b'G')];
+
+const DNA_WITH_N_COMPLEMENT: [(u8, u8); 3] = [(b'A', b'T'), (b'C', b'G'), (b'N', b'N')];
+
 /// Includes only values that correspond to single amino acids in the IUPAC standard (case-insensitive).
 pub fn ascii_amino_acid() -> Alphabet {
     Alphabet::from_ambiguous_io_symbols(
@@ -383,6 +746,117 @@ mod tests {
         assert_eq!(roman.num_searchable_dense_symbols(), 26);
     }
 
+    #[test]
+    fn interleaved_non_searchable_symbols() {
+        let alphabet = Alphabet::from_ambiguous_io_symbols([b"Aa", b"Nn", b"Cc", b"Xx", b"Gg"], 0)
+            .with_non_searchable_io_symbols(b"nX");
+
+        assert_eq!(alphabet.num_dense_symbols(), 6);
+        assert_eq!(alphabet.num_searchable_dense_symbols(), 3);
+
+        let codes: Vec<_> = (0..6).map(|s| alphabet.searchable_code(s)).collect();
+        assert_eq!(codes, [None, Some(0), None, Some(1), None, Some(2)]);
+
+        for code in 0..3 {
+            let dense_symbol = alphabet.dense_symbol_of_searchable_code(code);
+            assert_eq!(alphabet.searchable_code(dense_symbol), Some(code));
+        }
+
+        roundabout(alphabet);
+    }
+
+    #[test]
+    fn compatibility() {
+        let dna = ascii_dna();
+
+        assert!(dna.is_compatible_with(&dna));
+        assert!(dna.is_compatible_with(&Alphabet::from_io_symbols(b"ACGU", 0)));
+        assert!(ascii_dna_with_n().is_compatible_with(&Alphabet::from_io_symbols(b"ACGTX", 1)));
+
+        // same number of symbols, but different dense representations of shared IO symbols
+        assert!(!dna.is_compatible_with(&Alphabet::from_io_symbols(b"CAGT", 0)));
+        assert!(!dna.is_compatible_with(&Alphabet::from_io_symbols(b"ACTG", 0)));
+
+        // same number of searchable symbols, but different searchable dense symbols
+        let interleaved =
+            Alphabet::from_io_symbols(b"ANCG", 0).with_non_searchable_io_symbols(b"N");
+        let non_searchable_last = Alphabet::from_io_symbols(b"ACGN", 1);
+        assert_eq!(
+            interleaved.num_searchable_dense_symbols(),
+            non_searchable_last.num_searchable_dense_symbols()
+        );
+        assert!(!interleaved.is_compatible_with(&non_searchable_last));
+    }
+
+    #[test]
+    fn nucleotide_complement_of_custom_alphabets() {
+        let dna = Alphabet::from_io_symbols(b"ACGTN", 1);
+        assert!(dna.has_complement());
+        assert_eq!(dna.reverse_complement(b"AACGN"), b"NCGTT");
+
+        let lowercase = Alphabet::from_io_symbols(b"acgt", 0);
+        assert_eq!(lowercase.reverse_complement(b"aacg"), b"cgtt");
+
+        // U is not a nucleotide of DNA, so no complement is defined
+        assert!(!Alphabet::from_io_symbols(b"ACGU", 0).has_complement());
+        assert!(!Alphabet::from_io_symbols(b"ACG", 0).has_complement());
+    }
+
+    #[cfg(feature = "savefile")]
+    #[test]
+    fn restore_missing_complement() {
+        let mut dna = ascii_dna_with_n();
+        dna.dense_complement.clear();
+        dna.restore_missing_complement();
+
+        assert!(dna == ascii_dna_with_n());
+
+        let mut amino_acids = ascii_amino_acid();
+        amino_acids.restore_missing_complement();
+
+        assert!(!amino_acids.has_complement());
+    }
+
+    #[test]
+    fn complement() {
+        let dna = ascii_dna_iupac();
+
+        assert!(dna.has_complement());
+        assert_eq!(
+            dna.reverse_complement(b"ACGTNmkRyBdhvsw"),
+            b"WSBDHVRYMKNACGT"
+        );
+        assert_eq!(dna.complement(b'x'), None);
+        assert_eq!(
+            ascii_dna_iupac_as_dna_with_n().reverse_complement(b"Rac"),
+            b"GTN"
+        );
+        assert!(!ascii_amino_acid().has_complement());
+    }
+
+    #[test]
+    #[should_panic(expected = "must have a complement")]
+    fn incomplete_complement() {
+        Alphabet::from_io_symbols(b"ACGT", 0).with_complement([(b'A', b'T')]);
+    }
+
+    #[test]
+    fn alphabet_from_texts() {
+        let texts = [b"aaaabbbbccccdx".as_slice(), b"xxaabbe"];
+        let alphabet = Alphabet::from_texts(texts, 0.1, b"x");
+
+        assert_eq!(alphabet.num_dense_symbols(), 7);
+        assert_eq!(alphabet.num_searchable_dense_symbols(), 3);
+
+        let dense_to_io: Vec<_> = (1..7)
+            .map(|symbol| alphabet.dense_to_io_representation(symbol))
+            .collect();
+        assert_eq!(dense_to_io, b"abcdex");
+
+        assert!(alphabet.try_io_to_dense_representation(b'f').is_none());
+        roundabout(alphabet);
+    }
+
     #[test]
     fn construct_alphabets() {
         let dna = ascii_dna();
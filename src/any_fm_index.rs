@@ -0,0 +1,814 @@
+use crate::{
+    Alphabet, ApproximateHit, ComponentHashes, ConstructionParams, ContainmentReport,
+    CoverageTracks, EmptyTextPolicy, FmIndex, FmIndexConfig, HeapSizes, Hit, HitPair, IndexStorage,
+    MatchingStatistic, MismatchHit, MotifHit, NearestString, PatternTooAmbiguous,
+    PerformancePriority, Pipeline, QueryCoverage, StrandedHit, TextRanges, TextSampling, Wildcards,
+    text_with_rank_support::{
+        Block64, Block512, CondensedTextWithRankSupport, FlatTextWithRankSupport,
+        PackedTextWithRankSupport, WaveletTreeTextWithRankSupport,
+    },
+};
+
+/// An FM-Index whose index storage and text with rank support implementation are selected at runtime.
+///
+/// This allows applications to use different variants of the FM-Index without being generic over
+/// the [`IndexStorage`] and [`TextWithRankSupport`](crate::TextWithRankSupport) types. This is especially
+/// useful when an application loads whatever index file a user provides, see [`Self::load_from_file`].
+///
+/// The query API of [`FmIndex`] is forwarded to the underlying index. Iterators are returned as trait objects.
+/// Functions that return [`Cursor`](crate::Cursor)s or other types that depend on the concrete variant are
+/// not forwarded. For those, the enum can be matched to obtain the concrete index.
+///
+/// It can be created using [`FmIndexConfig::auto_rank_support`] or converted from a concrete index using
+/// [`From`]. The variants of [`CondensedTextWithRankSupport`] with a `U8` or `U32` suffix use the respective
+/// [`BlockOffset`](crate::text_with_rank_support::BlockOffset) type, the others use the default.
+pub enum AnyFmIndex {
+    I32Condensed64(FmIndex<i32, CondensedTextWithRankSupport<i32, Block64>>),
+    I32Condensed512(FmIndex<i32, CondensedTextWithRankSupport<i32, Block512>>),
+    I32Condensed64U8(FmIndex<i32, CondensedTextWithRankSupport<i32, Block64, u8>>),
+    #[cfg(target_pointer_width = "64")]
+    I32Condensed64U32(FmIndex<i32, CondensedTextWithRankSupport<i32, Block64, u32>>),
+    #[cfg(target_pointer_width = "64")]
+    I32Condensed512U32(FmIndex<i32, CondensedTextWithRankSupport<i32, Block512, u32>>),
+    I32Flat64(FmIndex<i32, FlatTextWithRankSupport<i32, Block64>>),
+    I32Flat512(FmIndex<i32, FlatTextWithRankSupport<i32, Block512>>),
+    I32Packed(FmIndex<i32, PackedTextWithRankSupport<i32>>),
+    I32WaveletTree(FmIndex<i32, WaveletTreeTextWithRankSupport<i32>>),
+    U32Condensed64(FmIndex<u32, CondensedTextWithRankSupport<u32, Block64>>),
+    U32Condensed512(FmIndex<u32, CondensedTextWithRankSupport<u32, Block512>>),
+    U32Condensed64U8(FmIndex<u32, CondensedTextWithRankSupport<u32, Block64, u8>>),
+    #[cfg(target_pointer_width = "64")]
+    U32Condensed64U32(FmIndex<u32, CondensedTextWithRankSupport<u32, Block64, u32>>),
+    #[cfg(target_pointer_width = "64")]
+    U32Condensed512U32(FmIndex<u32, CondensedTextWithRankSupport<u32, Block512, u32>>),
+    U32Flat64(FmIndex<u32, FlatTextWithRankSupport<u32, Block64>>),
+    U32Flat512(FmIndex<u32, FlatTextWithRankSupport<u32, Block512>>),
+    U32Packed(FmIndex<u32, PackedTextWithRankSupport<u32>>),
+    U32WaveletTree(FmIndex<u32, WaveletTreeTextWithRankSupport<u32>>),
+    I64Condensed64(FmIndex<i64, CondensedTextWithRankSupport<i64, Block64>>),
+    I64Condensed512(FmIndex<i64, CondensedTextWithRankSupport<i64, Block512>>),
+    I64Condensed64U8(FmIndex<i64, CondensedTextWithRankSupport<i64, Block64, u8>>),
+    #[cfg(target_pointer_width = "64")]
+    I64Condensed64U32(FmIndex<i64, CondensedTextWithRankSupport<i64, Block64, u32>>),
+    #[cfg(target_pointer_width = "64")]
+    I64Condensed512U32(FmIndex<i64, CondensedTextWithRankSupport<i64, Block512, u32>>),
+    I64Flat64(FmIndex<i64, FlatTextWithRankSupport<i64, Block64>>),
+    I64Flat512(FmIndex<i64, FlatTextWithRankSupport<i64, Block512>>),
+    I64Packed(FmIndex<i64, PackedTextWithRankSupport<i64>>),
+    I64WaveletTree(FmIndex<i64, WaveletTreeTextWithRankSupport<i64>>),
+}
+
+// applies the same expression to the concrete index of every variant
+macro_rules! dispatch {
+    ($self:expr, $index:ident => $body:expr) => {
+        match $self {
+            AnyFmIndex::I32Condensed64($index) => $body,
+            AnyFmIndex::I32Condensed512($index) => $body,
+            AnyFmIndex::I32Condensed64U8($index) => $body,
+            #[cfg(target_pointer_width = "64")]
+            AnyFmIndex::I32Condensed64U32($index) => $body,
+            #[cfg(target_pointer_width = "64")]
+            AnyFmIndex::I32Condensed512U32($index) => $body,
+            AnyFmIndex::I32Flat64($index) => $body,
+            AnyFmIndex::I32Flat512($index) => $body,
+            AnyFmIndex::I32Packed($index) => $body,
+            AnyFmIndex::I32WaveletTree($index) => $body,
+            AnyFmIndex::U32Condensed64($index) => $body,
+            AnyFmIndex::U32Condensed512($index) => $body,
+            AnyFmIndex::U32Condensed64U8($index) => $body,
+            #[cfg(target_pointer_width = "64")]
+            AnyFmIndex::U32Condensed64U32($index) => $body,
+            #[cfg(target_pointer_width = "64")]
+            AnyFmIndex::U32Condensed512U32($index) => $body,
+            AnyFmIndex::U32Flat64($index) => $body,
+            AnyFmIndex::U32Flat512($index) => $body,
+            AnyFmIndex::U32Packed($index) => $body,
+            AnyFmIndex::U32WaveletTree($index) => $body,
+            AnyFmIndex::I64Condensed64($index) => $body,
+            AnyFmIndex::I64Condensed512($index) => $body,
+            AnyFmIndex::I64Condensed64U8($index) => $body,
+            #[cfg(target_pointer_width = "64")]
+            AnyFmIndex::I64Condensed64U32($index) => $body,
+            #[cfg(target_pointer_width = "64")]
+            AnyFmIndex::I64Condensed512U32($index) => $body,
+            AnyFmIndex::I64Flat64($index) => $body,
+            AnyFmIndex::I64Flat512($index) => $body,
+            AnyFmIndex::I64Packed($index) => $body,
+            AnyFmIndex::I64WaveletTree($index) => $body,
+        }
+    };
+}
+
+// tries to load the variants in the given order and returns the first one whose schema fits the file
+#[cfg(feature = "savefile")]
+macro_rules! load_first_matching_variant {
+    ($filepath:ident, $($(#[$attr:meta])* $variant:ident: $index_type:ty),*) => {{
+        $(
+            $(#[$attr])*
+            match <$index_type>::load_from_file($filepath) {
+                Ok(index) => return Ok(AnyFmIndex::$variant(index)),
+                Err(savefile::SavefileError::IncompatibleSchema { .. }) => {}
+                Err(err) => return Err(err),
+            }
+        )*
+
+        Err(savefile::SavefileError::IncompatibleSchema {
+            message: "the file does not contain any of the supported FM-Index variants".to_string(),
+        })
+    }};
+}
+
+impl AnyFmIndex {
+    /// See [`FmIndex::count`].
+    pub fn count(&self, query: &[u8]) -> usize {
+        dispatch!(self, index => index.count(query))
+    }
+
+    /// See [`FmIndex::count_with_match_length`].
+    pub fn count_with_match_length(&self, query: &[u8]) -> (usize, usize) {
+        dispatch!(self, index => index.count_with_match_length(query))
+    }
+
+    /// See [`FmIndex::matching_statistics`].
+    pub fn matching_statistics(&self, query: &[u8]) -> Vec<MatchingStatistic> {
+        dispatch!(self, index => index.matching_statistics(query))
+    }
+
+    /// See [`FmIndex::match_profile`].
+    pub fn match_profile(&self, query: &[u8], chunk_len: usize) -> Vec<(usize, MatchingStatistic)> {
+        dispatch!(self, index => index.match_profile(query, chunk_len))
+    }
+
+    /// See [`FmIndex::count_chunked`].
+    pub fn count_chunked(&self, query: &[u8], chunk_len: usize) -> Vec<usize> {
+        dispatch!(self, index => index.count_chunked(query, chunk_len))
+    }
+
+    /// See [`FmIndex::count_many`].
+    pub fn count_many<'a, Q: AsRef<[u8]> + 'a>(
+        &'a self,
+        queries: impl IntoIterator<Item = Q> + 'a,
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        dispatch!(self, index => Box::new(index.count_many(queries)))
+    }
+
+    /// See [`FmIndex::count_many_deduplicated`].
+    pub fn count_many_deduplicated<'a, Q: AsRef<[u8]> + 'a>(
+        &'a self,
+        queries: impl IntoIterator<Item = Q> + 'a,
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        dispatch!(self, index => Box::new(index.count_many_deduplicated(queries)))
+    }
+
+    /// See [`FmIndex::locate`].
+    pub fn locate<'a>(&'a self, query: &'a [u8]) -> Box<dyn Iterator<Item = Hit> + 'a> {
+        dispatch!(self, index => Box::new(index.locate(query)))
+    }
+
+    /// See [`FmIndex::contains_text`].
+    pub fn contains_text(&self, record: &[u8]) -> Option<usize> {
+        dispatch!(self, index => index.contains_text(record))
+    }
+
+    /// See [`FmIndex::contains_texts`].
+    pub fn contains_texts<'a, Q: AsRef<[u8]> + 'a>(
+        &'a self,
+        records: impl IntoIterator<Item = Q> + 'a,
+    ) -> Box<dyn Iterator<Item = Option<usize>> + 'a> {
+        dispatch!(self, index => Box::new(index.contains_texts(records)))
+    }
+
+    /// See [`FmIndex::locate_prefix_matches`].
+    pub fn locate_prefix_matches<'a>(
+        &'a self,
+        query: &'a [u8],
+    ) -> Box<dyn Iterator<Item = Hit> + 'a> {
+        dispatch!(self, index => Box::new(index.locate_prefix_matches(query)))
+    }
+
+    /// See [`FmIndex::locate_suffix_matches`].
+    pub fn locate_suffix_matches<'a>(
+        &'a self,
+        query: &'a [u8],
+    ) -> Box<dyn Iterator<Item = Hit> + 'a> {
+        dispatch!(self, index => Box::new(index.locate_suffix_matches(query)))
+    }
+
+    /// See [`FmIndex::count_with_revcomp`].
+    pub fn count_with_revcomp(&self, query: &[u8]) -> usize {
+        dispatch!(self, index => index.count_with_revcomp(query))
+    }
+
+    /// See [`FmIndex::locate_with_revcomp`].
+    pub fn locate_with_revcomp<'a>(
+        &'a self,
+        query: &[u8],
+    ) -> Box<dyn Iterator<Item = StrandedHit> + 'a> {
+        dispatch!(self, index => Box::new(index.locate_with_revcomp(query)))
+    }
+
+    /// See [`FmIndex::locate_filtered`].
+    pub fn locate_filtered<'a>(
+        &'a self,
+        query: &[u8],
+        text_id_filter: impl FnMut(usize) -> bool + 'a,
+    ) -> Box<dyn Iterator<Item = Hit> + 'a> {
+        dispatch!(self, index => Box::new(index.locate_filtered(query, text_id_filter)))
+    }
+
+    /// See [`FmIndex::disable_text`].
+    pub fn disable_text(&mut self, text_id: usize) -> bool {
+        dispatch!(self, index => index.disable_text(text_id))
+    }
+
+    /// See [`FmIndex::enable_text`].
+    pub fn enable_text(&mut self, text_id: usize) -> bool {
+        dispatch!(self, index => index.enable_text(text_id))
+    }
+
+    /// See [`FmIndex::enable_all_texts`].
+    pub fn enable_all_texts(&mut self) {
+        dispatch!(self, index => index.enable_all_texts())
+    }
+
+    /// See [`FmIndex::is_text_disabled`].
+    pub fn is_text_disabled(&self, text_id: usize) -> bool {
+        dispatch!(self, index => index.is_text_disabled(text_id))
+    }
+
+    /// See [`FmIndex::disabled_texts`].
+    pub fn disabled_texts<'a>(&'a self) -> Box<dyn Iterator<Item = usize> + 'a> {
+        dispatch!(self, index => Box::new(index.disabled_texts()))
+    }
+
+    /// See [`FmIndex::count_pattern`].
+    pub fn count_pattern(&self, pattern: &[u8]) -> usize {
+        dispatch!(self, index => index.count_pattern(pattern))
+    }
+
+    /// See [`FmIndex::try_count_pattern`].
+    pub fn try_count_pattern(
+        &self,
+        pattern: &[u8],
+        max_branches: usize,
+    ) -> Result<usize, PatternTooAmbiguous> {
+        dispatch!(self, index => index.try_count_pattern(pattern, max_branches))
+    }
+
+    /// See [`FmIndex::locate_pattern`].
+    pub fn locate_pattern<'a>(&'a self, pattern: &[u8]) -> Box<dyn Iterator<Item = Hit> + 'a> {
+        dispatch!(self, index => Box::new(index.locate_pattern(pattern)))
+    }
+
+    /// See [`FmIndex::try_locate_pattern`].
+    pub fn try_locate_pattern<'a>(
+        &'a self,
+        pattern: &[u8],
+        max_branches: usize,
+    ) -> Result<Box<dyn Iterator<Item = Hit> + 'a>, PatternTooAmbiguous> {
+        dispatch!(self, index => index
+            .try_locate_pattern(pattern, max_branches)
+            .map(|hits| Box::new(hits) as Box<dyn Iterator<Item = Hit>>))
+    }
+
+    /// See [`FmIndex::try_count_with_wildcards`].
+    pub fn try_count_with_wildcards(
+        &self,
+        query: &[u8],
+        wildcards: &Wildcards,
+    ) -> Result<usize, PatternTooAmbiguous> {
+        dispatch!(self, index => index.try_count_with_wildcards(query, wildcards))
+    }
+
+    /// See [`FmIndex::try_count_many_with_wildcards`].
+    pub fn try_count_many_with_wildcards<'a, Q: AsRef<[u8]> + 'a>(
+        &'a self,
+        queries: impl IntoIterator<Item = Q> + 'a,
+        wildcards: &'a Wildcards,
+    ) -> Box<dyn Iterator<Item = Result<usize, PatternTooAmbiguous>> + 'a> {
+        dispatch!(self, index => Box::new(index.try_count_many_with_wildcards(queries, wildcards)))
+    }
+
+    /// See [`FmIndex::try_locate_with_wildcards`].
+    pub fn try_locate_with_wildcards<'a>(
+        &'a self,
+        query: &[u8],
+        wildcards: &Wildcards,
+    ) -> Result<Box<dyn Iterator<Item = Hit> + 'a>, PatternTooAmbiguous> {
+        dispatch!(self, index => index
+            .try_locate_with_wildcards(query, wildcards)
+            .map(|hits| Box::new(hits) as Box<dyn Iterator<Item = Hit>>))
+    }
+
+    /// See [`FmIndex::locate_prosite_motif`].
+    pub fn locate_prosite_motif(&self, motif: &[u8]) -> Vec<MotifHit> {
+        dispatch!(self, index => index.locate_prosite_motif(motif))
+    }
+
+    /// See [`FmIndex::try_locate_prosite_motif`].
+    pub fn try_locate_prosite_motif(
+        &self,
+        motif: &[u8],
+        max_branches: usize,
+    ) -> Result<Vec<MotifHit>, PatternTooAmbiguous> {
+        dispatch!(self, index => index.try_locate_prosite_motif(motif, max_branches))
+    }
+
+    /// See [`FmIndex::locate_approximate`].
+    pub fn locate_approximate(
+        &self,
+        query: &[u8],
+        max_edit_distance: usize,
+    ) -> Vec<ApproximateHit> {
+        dispatch!(self, index => index.locate_approximate(query, max_edit_distance))
+    }
+
+    /// See [`FmIndex::search_with_seeds`].
+    pub fn search_with_seeds(&self, query: &[u8], max_mismatches: usize) -> Vec<MismatchHit> {
+        dispatch!(self, index => index.search_with_seeds(query, max_mismatches))
+    }
+
+    /// See [`FmIndex::nearest_strings`].
+    pub fn nearest_strings(
+        &self,
+        query: &[u8],
+        max_edit_distance: usize,
+        max_results: usize,
+    ) -> Vec<NearestString> {
+        dispatch!(self, index => index.nearest_strings(query, max_edit_distance, max_results))
+    }
+
+    /// See [`FmIndex::nearest_texts`].
+    pub fn nearest_texts(
+        &self,
+        query: &[u8],
+        max_edit_distance: usize,
+        max_results: usize,
+    ) -> Vec<NearestString> {
+        dispatch!(self, index => index.nearest_texts(query, max_edit_distance, max_results))
+    }
+
+    /// See [`FmIndex::locate_with_sa_index`].
+    pub fn locate_with_sa_index<'a>(
+        &'a self,
+        query: &'a [u8],
+    ) -> Box<dyn Iterator<Item = (usize, Hit)> + 'a> {
+        dispatch!(self, index => Box::new(index.locate_with_sa_index(query)))
+    }
+
+    /// See [`FmIndex::locate_many`].
+    pub fn locate_many<'a, Q: AsRef<[u8]> + 'a>(
+        &'a self,
+        queries: impl IntoIterator<Item = Q> + 'a,
+    ) -> Box<dyn Iterator<Item = Box<dyn Iterator<Item = Hit> + 'a>> + 'a> {
+        dispatch!(self, index => Box::new(
+            index
+                .locate_many(queries)
+                .map(|hits| Box::new(hits) as Box<dyn Iterator<Item = Hit>>)
+        ))
+    }
+
+    /// See [`FmIndex::locate_many_deduplicated`].
+    pub fn locate_many_deduplicated<'a, Q: AsRef<[u8]> + 'a>(
+        &'a self,
+        queries: impl IntoIterator<Item = Q> + 'a,
+    ) -> Box<dyn Iterator<Item = Box<dyn Iterator<Item = Hit> + 'a>> + 'a> {
+        dispatch!(self, index => Box::new(
+            index
+                .locate_many_deduplicated(queries)
+                .map(|hits| Box::new(hits) as Box<dyn Iterator<Item = Hit>>)
+        ))
+    }
+
+    /// See [`FmIndex::locate_many_pipelined`].
+    pub fn locate_many_pipelined<Q, T, F>(
+        &self,
+        queries: impl IntoIterator<Item = Q>,
+        pipeline: &Pipeline,
+        postprocess: F,
+    ) -> Vec<T>
+    where
+        Q: AsRef<[u8]> + Sync,
+        T: Send,
+        F: Fn(usize, &Q, Vec<Hit>) -> T + Sync,
+    {
+        dispatch!(self, index => index.locate_many_pipelined(queries, pipeline, postprocess))
+    }
+
+    /// See [`FmIndex::locate_as_ranges`].
+    pub fn locate_as_ranges(&self, query: &[u8], merge_gap: usize) -> Vec<TextRanges> {
+        dispatch!(self, index => index.locate_as_ranges(query, merge_gap))
+    }
+
+    /// See [`FmIndex::render_hits`].
+    pub fn render_hits(
+        &self,
+        query: &[u8],
+        hits: impl IntoIterator<Item = Hit>,
+        flank_len: usize,
+    ) -> String {
+        dispatch!(self, index => index.render_hits(query, hits, flank_len))
+    }
+
+    /// See [`FmIndex::locate_pairs`].
+    pub fn locate_pairs(
+        &self,
+        first_query: &[u8],
+        second_query: &[u8],
+        min_distance: usize,
+        max_distance: usize,
+    ) -> Vec<HitPair> {
+        dispatch!(self, index => index.locate_pairs(first_query, second_query, min_distance, max_distance))
+    }
+
+    /// See [`FmIndex::coverage`].
+    pub fn coverage(&self, query: &[u8], k: usize) -> QueryCoverage {
+        dispatch!(self, index => index.coverage(query, k))
+    }
+
+    /// See [`FmIndex::containment_report`].
+    pub fn containment_report<Q: AsRef<[u8]>>(
+        &self,
+        query_texts: impl IntoIterator<Item = Q>,
+        min_len: usize,
+    ) -> Vec<ContainmentReport> {
+        dispatch!(self, index => index.containment_report(query_texts, min_len))
+    }
+
+    /// See [`FmIndex::score_sequence`].
+    pub fn score_sequence(&self, query: &[u8], order: usize) -> f64 {
+        dispatch!(self, index => index.score_sequence(query, order))
+    }
+
+    /// See [`FmIndex::iter_kmers`].
+    pub fn iter_kmers(&self, k: usize) -> Box<dyn Iterator<Item = (Vec<u8>, usize)> + '_> {
+        dispatch!(self, index => Box::new(index.iter_kmers(k)))
+    }
+
+    /// See [`FmIndex::locate_many_into_coverage`].
+    pub fn locate_many_into_coverage<Q: AsRef<[u8]>>(
+        &self,
+        queries: impl IntoIterator<Item = Q>,
+        coverage_tracks: &mut CoverageTracks,
+    ) {
+        dispatch!(self, index => index.locate_many_into_coverage(queries, coverage_tracks))
+    }
+
+    /// See [`FmIndex::count_per_file`].
+    pub fn count_per_file(&self, query: &[u8]) -> Vec<usize> {
+        dispatch!(self, index => index.count_per_file(query))
+    }
+
+    /// See [`FmIndex::add_bookmark`].
+    pub fn add_bookmark(&mut self, name: impl Into<String>, query: &[u8]) {
+        dispatch!(self, index => index.add_bookmark(name, query))
+    }
+
+    /// See [`FmIndex::remove_bookmark`].
+    pub fn remove_bookmark(&mut self, name: &str) -> bool {
+        dispatch!(self, index => index.remove_bookmark(name))
+    }
+
+    /// See [`FmIndex::bookmark_names`].
+    pub fn bookmark_names<'a>(&'a self) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        dispatch!(self, index => Box::new(index.bookmark_names()))
+    }
+
+    /// See [`FmIndex::attach_query_alphabet`].
+    pub fn attach_query_alphabet(&mut self, alphabet: Alphabet) -> Alphabet {
+        dispatch!(self, index => index.attach_query_alphabet(alphabet))
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        dispatch!(self, index => index.alphabet())
+    }
+
+    pub fn num_texts(&self) -> usize {
+        dispatch!(self, index => index.num_texts())
+    }
+
+    /// See [`FmIndex::construction_params`].
+    pub fn construction_params(&self) -> Option<&ConstructionParams> {
+        dispatch!(self, index => index.construction_params())
+    }
+
+    /// See [`FmIndex::bwt_iter`].
+    pub fn bwt_iter<'a>(&'a self) -> Box<dyn Iterator<Item = u8> + 'a> {
+        dispatch!(self, index => Box::new(index.bwt_iter()))
+    }
+
+    /// See [`FmIndex::sampled_suffix_array_entries`].
+    pub fn sampled_suffix_array_entries<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = (usize, usize)> + 'a> {
+        dispatch!(self, index => Box::new(index.sampled_suffix_array_entries()))
+    }
+
+    /// See [`FmIndex::empty_text_policy`].
+    pub fn empty_text_policy(&self) -> EmptyTextPolicy {
+        dispatch!(self, index => index.empty_text_policy())
+    }
+
+    /// See [`FmIndex::text_name`].
+    pub fn text_name(&self, text_id: usize) -> Option<&str> {
+        dispatch!(self, index => index.text_name(text_id))
+    }
+
+    /// See [`FmIndex::text_names`].
+    pub fn text_names(&self) -> Option<&[String]> {
+        dispatch!(self, index => index.text_names())
+    }
+
+    /// See [`FmIndex::text_sampling`].
+    pub fn text_sampling(&self) -> TextSampling {
+        dispatch!(self, index => index.text_sampling())
+    }
+
+    /// See [`FmIndex::original_text_id`].
+    pub fn original_text_id(&self, text_id: usize) -> usize {
+        dispatch!(self, index => index.original_text_id(text_id))
+    }
+
+    /// See [`FmIndex::original_text_ids`].
+    pub fn original_text_ids(&self) -> Option<&[usize]> {
+        dispatch!(self, index => index.original_text_ids())
+    }
+
+    /// See [`FmIndex::num_files`].
+    pub fn num_files(&self) -> usize {
+        dispatch!(self, index => index.num_files())
+    }
+
+    /// See [`FmIndex::file_id`].
+    pub fn file_id(&self, text_id: usize) -> usize {
+        dispatch!(self, index => index.file_id(text_id))
+    }
+
+    /// See [`FmIndex::text_ids_of_file`].
+    pub fn text_ids_of_file(&self, file_id: usize) -> std::ops::Range<usize> {
+        dispatch!(self, index => index.text_ids_of_file(file_id))
+    }
+
+    /// See [`FmIndex::text_len`].
+    pub fn text_len(&self, text_id: usize) -> usize {
+        dispatch!(self, index => index.text_len(text_id))
+    }
+
+    /// See [`FmIndex::total_text_len`].
+    pub fn total_text_len(&self) -> usize {
+        dispatch!(self, index => index.total_text_len())
+    }
+
+    /// See [`FmIndex::extract_text`].
+    pub fn extract_text(&self, text_id: usize) -> Vec<u8> {
+        dispatch!(self, index => index.extract_text(text_id))
+    }
+
+    /// See [`FmIndex::subset`]. The new index is of the same variant as this one.
+    pub fn subset(&self, text_ids: &[usize]) -> Self {
+        dispatch!(self, index => index.subset(text_ids).into())
+    }
+
+    /// See [`FmIndex::lock_memory`].
+    pub fn lock_memory(&self) -> std::io::Result<()> {
+        dispatch!(self, index => index.lock_memory())
+    }
+
+    /// See [`FmIndex::unlock_memory`].
+    pub fn unlock_memory(&self) -> std::io::Result<()> {
+        dispatch!(self, index => index.unlock_memory())
+    }
+
+    /// See [`FmIndex::advise_memory`].
+    #[cfg(feature = "memory-advice")]
+    pub fn advise_memory(&self, advice: &crate::IndexMemoryAdvice) -> std::io::Result<()> {
+        dispatch!(self, index => index.advise_memory(advice))
+    }
+
+    /// See [`FmIndex::component_hashes`].
+    pub fn component_hashes(&self) -> ComponentHashes {
+        dispatch!(self, index => index.component_hashes())
+    }
+
+    /// See [`FmIndex::heap_size_bytes`].
+    pub fn heap_size_bytes(&self) -> HeapSizes {
+        dispatch!(self, index => index.heap_size_bytes())
+    }
+
+    /// Loads an index of any of the supported variants from a file that was created by [`FmIndex::save_to_file`]
+    /// or [`Self::save_to_file`]. The variant is detected automatically.
+    ///
+    /// The detection is based on the schema stored in the file, which is checked before the data of the index
+    /// is read. Therefore, the detection is cheap.
+    #[cfg(feature = "savefile")]
+    pub fn load_from_file(
+        filepath: impl AsRef<std::path::Path>,
+    ) -> Result<Self, savefile::SavefileError> {
+        let filepath = filepath.as_ref();
+
+        load_first_matching_variant!(
+            filepath,
+            I32Condensed64: FmIndex<i32, CondensedTextWithRankSupport<i32, Block64>>,
+            I32Condensed512: FmIndex<i32, CondensedTextWithRankSupport<i32, Block512>>,
+            I32Condensed64U8: FmIndex<i32, CondensedTextWithRankSupport<i32, Block64, u8>>,
+            #[cfg(target_pointer_width = "64")]
+            I32Condensed64U32: FmIndex<i32, CondensedTextWithRankSupport<i32, Block64, u32>>,
+            #[cfg(target_pointer_width = "64")]
+            I32Condensed512U32: FmIndex<i32, CondensedTextWithRankSupport<i32, Block512, u32>>,
+            I32Flat64: FmIndex<i32, FlatTextWithRankSupport<i32, Block64>>,
+            I32Flat512: FmIndex<i32, FlatTextWithRankSupport<i32, Block512>>,
+            I32Packed: FmIndex<i32, PackedTextWithRankSupport<i32>>,
+            I32WaveletTree: FmIndex<i32, WaveletTreeTextWithRankSupport<i32>>,
+            U32Condensed64: FmIndex<u32, CondensedTextWithRankSupport<u32, Block64>>,
+            U32Condensed512: FmIndex<u32, CondensedTextWithRankSupport<u32, Block512>>,
+            U32Condensed64U8: FmIndex<u32, CondensedTextWithRankSupport<u32, Block64, u8>>,
+            #[cfg(target_pointer_width = "64")]
+            U32Condensed64U32: FmIndex<u32, CondensedTextWithRankSupport<u32, Block64, u32>>,
+            #[cfg(target_pointer_width = "64")]
+            U32Condensed512U32: FmIndex<u32, CondensedTextWithRankSupport<u32, Block512, u32>>,
+            U32Flat64: FmIndex<u32, FlatTextWithRankSupport<u32, Block64>>,
+            U32Flat512: FmIndex<u32, FlatTextWithRankSupport<u32, Block512>>,
+            U32Packed: FmIndex<u32, PackedTextWithRankSupport<u32>>,
+            U32WaveletTree: FmIndex<u32, WaveletTreeTextWithRankSupport<u32>>,
+            I64Condensed64: FmIndex<i64, CondensedTextWithRankSupport<i64, Block64>>,
+            I64Condensed512: FmIndex<i64, CondensedTextWithRankSupport<i64, Block512>>,
+            I64Condensed64U8: FmIndex<i64, CondensedTextWithRankSupport<i64, Block64, u8>>,
+            #[cfg(target_pointer_width = "64")]
+            I64Condensed64U32: FmIndex<i64, CondensedTextWithRankSupport<i64, Block64, u32>>,
+            #[cfg(target_pointer_width = "64")]
+            I64Condensed512U32: FmIndex<i64, CondensedTextWithRankSupport<i64, Block512, u32>>,
+            I64Flat64: FmIndex<i64, FlatTextWithRankSupport<i64, Block64>>,
+            I64Flat512: FmIndex<i64, FlatTextWithRankSupport<i64, Block512>>,
+            I64Packed: FmIndex<i64, PackedTextWithRankSupport<i64>>,
+            I64WaveletTree: FmIndex<i64, WaveletTreeTextWithRankSupport<i64>>
+        )
+    }
+
+    #[cfg(feature = "savefile")]
+    pub fn save_to_writer(
+        &self,
+        writer: &mut impl std::io::Write,
+    ) -> Result<(), savefile::SavefileError> {
+        dispatch!(self, index => index.save_to_writer(writer))
+    }
+
+    #[cfg(feature = "savefile")]
+    pub fn save_to_file(
+        &self,
+        filepath: impl AsRef<std::path::Path>,
+    ) -> Result<(), savefile::SavefileError> {
+        dispatch!(self, index => index.save_to_file(filepath))
+    }
+}
+
+macro_rules! impl_from_concrete_index {
+    ($($(#[$attr:meta])* $variant:ident: $index_type:ty),*) => {
+        $(
+            $(#[$attr])*
+            impl From<$index_type> for AnyFmIndex {
+                fn from(index: $index_type) -> Self {
+                    Self::$variant(index)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_concrete_index!(
+    I32Condensed64: FmIndex<i32, CondensedTextWithRankSupport<i32, Block64>>,
+    I32Condensed512: FmIndex<i32, CondensedTextWithRankSupport<i32, Block512>>,
+    I32Condensed64U8: FmIndex<i32, CondensedTextWithRankSupport<i32, Block64, u8>>,
+    #[cfg(target_pointer_width = "64")]
+    I32Condensed64U32: FmIndex<i32, CondensedTextWithRankSupport<i32, Block64, u32>>,
+    #[cfg(target_pointer_width = "64")]
+    I32Condensed512U32: FmIndex<i32, CondensedTextWithRankSupport<i32, Block512, u32>>,
+    I32Flat64: FmIndex<i32, FlatTextWithRankSupport<i32, Block64>>,
+    I32Flat512: FmIndex<i32, FlatTextWithRankSupport<i32, Block512>>,
+    I32Packed: FmIndex<i32, PackedTextWithRankSupport<i32>>,
+    I32WaveletTree: FmIndex<i32, WaveletTreeTextWithRankSupport<i32>>,
+    U32Condensed64: FmIndex<u32, CondensedTextWithRankSupport<u32, Block64>>,
+    U32Condensed512: FmIndex<u32, CondensedTextWithRankSupport<u32, Block512>>,
+    U32Condensed64U8: FmIndex<u32, CondensedTextWithRankSupport<u32, Block64, u8>>,
+    #[cfg(target_pointer_width = "64")]
+    U32Condensed64U32: FmIndex<u32, CondensedTextWithRankSupport<u32, Block64, u32>>,
+    #[cfg(target_pointer_width = "64")]
+    U32Condensed512U32: FmIndex<u32, CondensedTextWithRankSupport<u32, Block512, u32>>,
+    U32Flat64: FmIndex<u32, FlatTextWithRankSupport<u32, Block64>>,
+    U32Flat512: FmIndex<u32, FlatTextWithRankSupport<u32, Block512>>,
+    U32Packed: FmIndex<u32, PackedTextWithRankSupport<u32>>,
+    U32WaveletTree: FmIndex<u32, WaveletTreeTextWithRankSupport<u32>>,
+    I64Condensed64: FmIndex<i64, CondensedTextWithRankSupport<i64, Block64>>,
+    I64Condensed512: FmIndex<i64, CondensedTextWithRankSupport<i64, Block512>>,
+    I64Condensed64U8: FmIndex<i64, CondensedTextWithRankSupport<i64, Block64, u8>>,
+    #[cfg(target_pointer_width = "64")]
+    I64Condensed64U32: FmIndex<i64, CondensedTextWithRankSupport<i64, Block64, u32>>,
+    #[cfg(target_pointer_width = "64")]
+    I64Condensed512U32: FmIndex<i64, CondensedTextWithRankSupport<i64, Block512, u32>>,
+    I64Flat64: FmIndex<i64, FlatTextWithRankSupport<i64, Block64>>,
+    I64Flat512: FmIndex<i64, FlatTextWithRankSupport<i64, Block512>>,
+    I64Packed: FmIndex<i64, PackedTextWithRankSupport<i64>>,
+    I64WaveletTree: FmIndex<i64, WaveletTreeTextWithRankSupport<i64>>
+);
+
+/// A configuration that selects the text with rank support implementation automatically.
+///
+/// It is created by [`FmIndexConfig::auto_rank_support`].
+pub struct AutoRankSupportConfig<I> {
+    pub(crate) config: FmIndexConfig<I>,
+    pub(crate) preference: PerformancePriority,
+}
+
+impl<I: IndexStorage> AutoRankSupportConfig<I>
+where
+    AnyFmIndex: From<FmIndex<I, CondensedTextWithRankSupport<I, Block64>>>
+        + From<FmIndex<I, CondensedTextWithRankSupport<I, Block512>>>
+        + From<FmIndex<I, FlatTextWithRankSupport<I, Block64>>>
+        + From<FmIndex<I, WaveletTreeTextWithRankSupport<I>>>,
+{
+    /// Construct the FM-Index, using the implementation that fits the alphabet and the preference best.
+    ///
+    /// The number of threads for the build procedure is controlled by [`rayon`].
+    pub fn construct_index<T: AsRef<[u8]>>(
+        self,
+        texts: impl IntoIterator<Item = T>,
+        alphabet: Alphabet,
+    ) -> AnyFmIndex {
+        let config = self.config;
+        let num_dense_symbols = alphabet.num_dense_symbols();
+
+        match (self.preference, num_dense_symbols) {
+            (PerformancePriority::HighSpeed, 0..=8) => config
+                .with_rank_support::<FlatTextWithRankSupport<I, Block64>>()
+                .construct_index(texts, alphabet)
+                .into(),
+            (PerformancePriority::HighSpeed, _) | (PerformancePriority::Balanced, 0..=16) => config
+                .with_rank_support::<CondensedTextWithRankSupport<I, Block64>>()
+                .construct_index(texts, alphabet)
+                .into(),
+            (PerformancePriority::Balanced, _) | (PerformancePriority::LowMemory, 0..=16) => config
+                .with_rank_support::<CondensedTextWithRankSupport<I, Block512>>()
+                .construct_index(texts, alphabet)
+                .into(),
+            (PerformancePriority::LowMemory, _) => config
+                .with_rank_support::<WaveletTreeTextWithRankSupport<I>>()
+                .construct_index(texts, alphabet)
+                .into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet;
+
+    #[test]
+    fn auto_rank_support_selection() {
+        let texts = [b"ACGTACGTNNACGA".as_slice(), b"TTACG"];
+
+        let expected = [
+            (
+                PerformancePriority::HighSpeed,
+                "FlatTextWithRankSupport",
+                "Block64",
+            ),
+            (
+                PerformancePriority::Balanced,
+                "CondensedTextWithRankSupport",
+                "Block64",
+            ),
+            (
+                PerformancePriority::LowMemory,
+                "CondensedTextWithRankSupport",
+                "Block512",
+            ),
+        ];
+
+        for (preference, rank_support_name, block_name) in expected {
+            let index = FmIndexConfig::<i32>::new()
+                .auto_rank_support(preference)
+                .construct_index(texts, alphabet::ascii_dna_with_n());
+
+            let params = index.construction_params().unwrap();
+            assert_eq!(params.text_with_rank_support, rank_support_name);
+            assert_eq!(params.block, block_name);
+
+            assert_eq!(index.count(b"ACG"), 4);
+            assert_eq!(index.locate(b"ACG").count(), 4);
+            assert_eq!(index.num_texts(), 2);
+        }
+
+        let index = FmIndexConfig::<u32>::new()
+            .auto_rank_support(PerformancePriority::HighSpeed)
+            .construct_index(texts, alphabet::ascii_amino_acid_iupac());
+
+        assert!(matches!(index, AnyFmIndex::U32Condensed64(_)));
+
+        let index = FmIndexConfig::<u32>::new()
+            .auto_rank_support(PerformancePriority::LowMemory)
+            .construct_index(texts, alphabet::ascii_printable());
+
+        assert!(matches!(index, AnyFmIndex::U32WaveletTree(_)));
+        assert_eq!(index.count(b"ACG"), 4);
+    }
+}
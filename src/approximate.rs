@@ -0,0 +1,212 @@
+use std::{collections::HashMap, fmt, ops::Range};
+
+use crate::{
+    Alphabet, Cursor, FmIndex, HalfOpenInterval, IndexStorage, pattern,
+    text_with_rank_support::TextWithRankSupport,
+};
+
+/// An approximate occurrence of a query, found by [`FmIndex::locate_approximate`].
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ApproximateHit {
+    pub text_id: usize,
+    /// The matched positions in the text. Its length can differ from the query length due to insertions and deletions.
+    pub range: Range<usize>,
+    /// The number of substitutions, insertions and deletions needed to transform the query into the matched text.
+    pub edit_distance: usize,
+}
+
+impl fmt::Display for ApproximateHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "text {}, positions {}..{}, edit distance {}",
+            self.text_id, self.range.start, self.range.end, self.edit_distance
+        )
+    }
+}
+
+// Edit distance computation for a query against texts that are extended at the front, as in a backward search.
+pub(crate) struct Search {
+    dense_query: Vec<Option<u8>>,
+    pub(crate) symbols: Vec<u8>,
+    pub(crate) max_edit_distance: usize,
+}
+
+pub(crate) fn locate_approximate<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+    max_edit_distance: usize,
+) -> Vec<ApproximateHit> {
+    let search = Search::new(index.alphabet(), query, max_edit_distance);
+
+    let mut matches = Vec::new();
+    search.extend(index.cursor_empty(), &search.initial_column(), &mut matches);
+
+    // a single occurrence of the query can be matched with texts that differ in their start position,
+    // only the best of them is reported for every end position
+    let mut best_per_end: HashMap<(usize, usize), ApproximateHit> = HashMap::new();
+
+    for (interval, text_len, edit_distance) in matches {
+        for hit in index.locate_interval(interval) {
+            let approximate_hit = ApproximateHit {
+                text_id: hit.text_id,
+                range: hit.position..hit.position + text_len,
+                edit_distance,
+            };
+
+            best_per_end
+                .entry((hit.text_id, approximate_hit.range.end))
+                .and_modify(|best| {
+                    if (edit_distance, approximate_hit.range.start)
+                        < (best.edit_distance, best.range.start)
+                    {
+                        *best = approximate_hit.clone();
+                    }
+                })
+                .or_insert(approximate_hit);
+        }
+    }
+
+    let mut hits: Vec<_> = best_per_end.into_values().collect();
+    hits.sort_unstable_by_key(|hit| (hit.text_id, hit.range.start, hit.range.end));
+
+    hits
+}
+
+impl Search {
+    pub(crate) fn new(alphabet: &Alphabet, query: &[u8], max_edit_distance: usize) -> Self {
+        // symbols that are not searchable never match and have to be substituted
+        let dense_query = query
+            .iter()
+            .map(|&symbol| {
+                alphabet
+                    .try_io_to_dense_representation(symbol)
+                    .filter(|&dense_symbol| alphabet.is_searchable(dense_symbol))
+            })
+            .collect();
+
+        Self {
+            dense_query,
+            symbols: pattern::searchable_symbols(alphabet).collect(),
+            max_edit_distance,
+        }
+    }
+
+    // Entry j of a column is the edit distance between the suffix of length j of the query and the text
+    // that was searched so far. Entry 0 is the length of the text.
+    pub(crate) fn initial_column(&self) -> Vec<usize> {
+        (0..=self.dense_query.len()).collect()
+    }
+
+    // Computes the column for the text that is extended by `symbol` at the front.
+    pub(crate) fn next_column(&self, column: &[usize], symbol: u8, next_column: &mut [usize]) {
+        let query_len = self.dense_query.len();
+
+        next_column[0] = column[0] + 1;
+
+        for j in 1..=query_len {
+            let substitution_cost = (self.dense_query[query_len - j] != Some(symbol)) as usize;
+
+            next_column[j] = (column[j - 1] + substitution_cost)
+                .min(column[j] + 1)
+                .min(next_column[j - 1] + 1);
+        }
+    }
+
+    // Depth-first search over all texts that occur in the index, extended at the front. The edit distances are
+    // computed column by column of the dynamic programming matrix, from the back of the query to the front.
+    // The suffix array intervals of matches are collected with the length of the matched text and the edit distance.
+    fn extend<I: IndexStorage, R: TextWithRankSupport<I>>(
+        &self,
+        cursor: Cursor<'_, I, R>,
+        column: &[usize],
+        matches: &mut Vec<(HalfOpenInterval, usize, usize)>,
+    ) {
+        let query_len = self.dense_query.len();
+        let text_len = column[0];
+
+        if text_len > 0 && column[query_len] <= self.max_edit_distance {
+            matches.push((cursor.interval(), text_len, column[query_len]));
+        }
+
+        let mut next_column = vec![0; column.len()];
+
+        for (symbol, next_cursor) in cursor.extend_all_without_alphabet_translation() {
+            if next_cursor.count() == 0 {
+                continue;
+            }
+
+            self.next_column(column, symbol, &mut next_column);
+
+            // the edit distance can't decrease when more text is added
+            if *next_column.iter().min().unwrap() <= self.max_edit_distance {
+                self.extend(next_cursor, &next_column, matches);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+        let mut column: Vec<_> = (0..=b.len()).collect();
+
+        for (i, &a_symbol) in a.iter().enumerate() {
+            let mut next_column = vec![i + 1; b.len() + 1];
+
+            for (j, &b_symbol) in b.iter().enumerate() {
+                next_column[j + 1] = (column[j] + (a_symbol != b_symbol) as usize)
+                    .min(column[j + 1] + 1)
+                    .min(next_column[j] + 1);
+            }
+
+            column = next_column;
+        }
+
+        column[b.len()]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn approximate_search_agrees_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 1..40), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..8),
+            max_edit_distance in 0usize..3,
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_index(&texts, alphabet::ascii_dna_with_n());
+
+            let mut expected = Vec::new();
+
+            for (text_id, text) in texts.iter().enumerate() {
+                for end in 1..=text.len() {
+                    let best = (0..end)
+                        .map(|start| (edit_distance(&query, &text[start..end]), start))
+                        .min()
+                        .unwrap();
+
+                    if best.0 <= max_edit_distance {
+                        expected.push(ApproximateHit {
+                            text_id,
+                            range: best.1..end,
+                            edit_distance: best.0,
+                        });
+                    }
+                }
+            }
+
+            expected.sort_unstable_by_key(|hit| (hit.text_id, hit.range.start, hit.range.end));
+
+            prop_assert_eq!(index.locate_approximate(&query, max_edit_distance), expected);
+        }
+    }
+}
@@ -14,6 +14,8 @@ pub(crate) struct BatchComputedCursors<'a, I, R, Q, QS, const N: usize> {
     curr_batch_size: usize,
     queries_iter: QS,
     buffers: Buffers<Q, N>,
+    // if set, the search starts at the ends of the texts instead of the lookup table
+    anchored_at_text_ends: bool,
 }
 
 impl<'a, I, R, Q, QS, const N: usize> BatchComputedCursors<'a, I, R, Q, QS, N>
@@ -30,6 +32,16 @@ where
             curr_batch_size: 0,
             queries_iter,
             buffers: Buffers::new(),
+            anchored_at_text_ends: false,
+        }
+    }
+
+    // The cursors of this iterator only contain the occurrences of the queries that are directly followed by
+    // the end of a text (a sentinel).
+    pub(crate) fn new_anchored_at_text_ends(index: &'a FmIndex<I, R>, queries_iter: QS) -> Self {
+        Self {
+            anchored_at_text_ends: true,
+            ..Self::new(index, queries_iter)
         }
     }
 
@@ -46,10 +58,20 @@ where
             self.curr_batch_size += 1;
         }
 
-        self.batched_lookup_jumps();
-
         // this idx is counting from the front and has to be reversed for the actual backwards seach
-        let mut next_idx_in_queries = self.index.lookup_tables.max_depth();
+        let mut next_idx_in_queries = if self.anchored_at_text_ends {
+            // the suffixes that only consist of a sentinel are the first rows of the suffix array
+            self.buffers.intervals[..self.curr_batch_size].fill(HalfOpenInterval {
+                start: 0,
+                end: self.index.num_texts(),
+            });
+
+            0
+        } else {
+            self.batched_lookup_jumps();
+
+            self.index.lookup_tables.max_depth()
+        };
 
         let mut num_remaining_unfinished_queries = self.curr_batch_size;
 
@@ -198,6 +220,116 @@ where
     }
 }
 
+// Batches are only efficient if their queries need a similar number of LF-mapping steps, because the batch
+// is computed until its longest query is finished. Therefore, windows of queries are inspected before they are
+// searched. If the queries of a window fall into different length classes, they are searched in the order of
+// their lengths and the cursors are restored to the original order afterwards. Otherwise, the window is searched
+// directly in batches.
+pub(crate) struct LengthGroupedCursors<'a, I, R, Q, QS, const N: usize> {
+    index: &'a FmIndex<I, R>,
+    queries_iter: QS,
+    window: Vec<Q>,
+    cursors: Vec<Cursor<'a, I, R>>,
+    next_idx_in_window: usize,
+}
+
+impl<'a, I, R, Q, QS, const N: usize> LengthGroupedCursors<'a, I, R, Q, QS, N>
+where
+    I: IndexStorage,
+    R: TextWithRankSupport<I>,
+    QS: Iterator<Item = Q>,
+    Q: AsRef<[u8]>,
+{
+    // large enough to form multiple full batches per length class, small enough to keep the search lazy
+    const WINDOW_SIZE: usize = 16 * N;
+
+    pub(crate) fn new(index: &'a FmIndex<I, R>, queries_iter: QS) -> Self {
+        Self {
+            index,
+            queries_iter,
+            window: Vec::new(),
+            cursors: Vec::new(),
+            next_idx_in_window: 0,
+        }
+    }
+
+    fn compute_next_window(&mut self) {
+        self.next_idx_in_window = 0;
+        self.window.clear();
+        self.window
+            .extend(self.queries_iter.by_ref().take(Self::WINDOW_SIZE));
+
+        self.cursors.clear();
+
+        let mut length_classes = self.window.iter().map(|query| self.length_class(query));
+
+        let Some(first_class) = length_classes.next() else {
+            return;
+        };
+
+        if length_classes.all(|class| class == first_class) {
+            self.cursors
+                .extend(BatchComputedCursors::<_, _, _, _, N>::new(
+                    self.index,
+                    self.window.iter(),
+                ));
+
+            return;
+        }
+
+        let mut order: Vec<_> = (0..self.window.len()).collect();
+        order.sort_by_key(|&i| self.window[i].as_ref().len());
+
+        self.cursors
+            .resize(self.window.len(), self.index.cursor_empty());
+
+        let sorted_cursors = BatchComputedCursors::<_, _, _, _, N>::new(
+            self.index,
+            order.iter().map(|&i| &self.window[i]),
+        );
+
+        for (&i, cursor) in order.iter().zip(sorted_cursors) {
+            self.cursors[i] = cursor;
+        }
+    }
+
+    // queries that are answered by the lookup table alone form the first class, the others are grouped by the
+    // magnitude of their number of LF-mapping steps
+    fn length_class(&self, query: &Q) -> u32 {
+        let num_lf_mapping_steps = query
+            .as_ref()
+            .len()
+            .saturating_sub(self.index.lookup_tables.max_depth());
+
+        if num_lf_mapping_steps == 0 {
+            0
+        } else {
+            num_lf_mapping_steps.ilog2() + 1
+        }
+    }
+}
+
+impl<'a, I, R, Q, QS, const N: usize> Iterator for LengthGroupedCursors<'a, I, R, Q, QS, N>
+where
+    I: IndexStorage,
+    R: TextWithRankSupport<I>,
+    QS: Iterator<Item = Q>,
+    Q: AsRef<[u8]>,
+{
+    type Item = Cursor<'a, I, R>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_idx_in_window == self.cursors.len() {
+            self.compute_next_window();
+        }
+
+        let cursor = self.cursors.get(self.next_idx_in_window).copied()?;
+        self.next_idx_in_window += 1;
+
+        Some(cursor)
+    }
+}
+
 // the 4 buffers are used to store different values throughout the batched search
 pub(crate) struct Buffers<Q, const N: usize> {
     pub(crate) intervals: [HalfOpenInterval; N],
@@ -233,3 +365,38 @@ impl<Q, const N: usize> Buffers<Q, N> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn mixed_lengths_agree_with_single_searches(
+            text in prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 300..600),
+            query_ranges in prop::collection::vec((0usize..300, prop::sample::select([1usize, 3, 12, 40, 300].as_slice())), 1..1500),
+            lookup_table_depth in 0usize..5,
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .lookup_table_depth(lookup_table_depth)
+                .construct_index([&text], alphabet::ascii_dna());
+
+            let queries: Vec<_> = query_ranges
+                .into_iter()
+                .map(|(start, len)| &text[start..(start + len).min(text.len())])
+                .collect();
+
+            let counts: Vec<_> = index.count_many(&queries).collect();
+            let expected_counts: Vec<_> = queries.iter().map(|query| index.count(query)).collect();
+
+            prop_assert_eq!(counts, expected_counts);
+
+            for (hits, query) in index.locate_many(&queries).zip(&queries) {
+                prop_assert!(hits.eq(index.locate(query)));
+            }
+        }
+    }
+}
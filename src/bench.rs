@@ -0,0 +1,253 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    Alphabet, FmIndex, FmIndexConfig, IndexStorage, alphabet, random::SplitMix64,
+    text_with_rank_support::TextWithRankSupport,
+};
+
+const DNA_SYMBOLS: &[u8] = b"ACGT";
+const AMINO_ACID_SYMBOLS: &[u8] = b"ACDEFGHIKLMNPQRSTVWY";
+
+/// A reproducible set of texts and queries for measuring the performance of the FM-Index.
+///
+/// The same parameters always produce the same workload, independent of the platform and the version of this library.
+/// The texts are given in IO representation of the alphabet of the workload.
+#[derive(Clone)]
+pub struct Workload {
+    pub name: &'static str,
+    pub alphabet: Alphabet,
+    pub texts: Vec<Vec<u8>>,
+    pub queries: Vec<Vec<u8>>,
+}
+
+impl Workload {
+    pub fn total_text_len(&self) -> usize {
+        self.texts.iter().map(|t| t.len()).sum()
+    }
+}
+
+/// Uniformly random DNA, split into texts of at most 1 MiB, like a set of contigs.
+///
+/// The queries are substrings of length 20 sampled from the texts, such that every query has at least one hit.
+pub fn uniform_dna(total_text_len: usize, num_queries: usize, seed: u64) -> Workload {
+    let mut rng = SplitMix64::new(seed);
+    let texts = random_texts(DNA_SYMBOLS, total_text_len, 1 << 20, &mut rng);
+    let queries = sample_queries(&texts, num_queries, 20, &mut rng);
+
+    Workload {
+        name: "uniform_dna",
+        alphabet: alphabet::ascii_dna(),
+        texts,
+        queries,
+    }
+}
+
+/// A highly repetitive set of DNA texts, similar to a pangenome.
+///
+/// `num_haplotypes` copies of a random base genome are created, and each copy is mutated with single nucleotide
+/// substitutions at a rate of 0.1%. The total length of the texts is roughly `total_text_len`.
+/// The queries are substrings of length 20 sampled from the texts, such that most of them have many hits.
+pub fn pangenome_like(
+    total_text_len: usize,
+    num_haplotypes: usize,
+    num_queries: usize,
+    seed: u64,
+) -> Workload {
+    assert!(num_haplotypes > 0, "at least one haplotype is needed");
+
+    let mut rng = SplitMix64::new(seed);
+    let base_genome = random_text(DNA_SYMBOLS, total_text_len / num_haplotypes, &mut rng);
+
+    let texts: Vec<_> = (0..num_haplotypes)
+        .map(|_| {
+            let mut haplotype = base_genome.clone();
+
+            for symbol in haplotype.iter_mut() {
+                if rng.next_below(1000) == 0 {
+                    *symbol = DNA_SYMBOLS[rng.next_below(DNA_SYMBOLS.len())];
+                }
+            }
+
+            haplotype
+        })
+        .collect();
+
+    let queries = sample_queries(&texts, num_queries, 20, &mut rng);
+
+    Workload {
+        name: "pangenome_like",
+        alphabet: alphabet::ascii_dna(),
+        texts,
+        queries,
+    }
+}
+
+/// Uniformly random protein sequences of length 400, like a protein database.
+///
+/// The queries are substrings of length 8 sampled from the texts.
+pub fn protein(total_text_len: usize, num_queries: usize, seed: u64) -> Workload {
+    let mut rng = SplitMix64::new(seed);
+    let texts = random_texts(AMINO_ACID_SYMBOLS, total_text_len, 400, &mut rng);
+    let queries = sample_queries(&texts, num_queries, 8, &mut rng);
+
+    Workload {
+        name: "protein",
+        alphabet: alphabet::ascii_amino_acid(),
+        texts,
+        queries,
+    }
+}
+
+/// Constructs an index for the texts of the workload and measures the wall time of the construction.
+pub fn measure_construction<I: IndexStorage, R: TextWithRankSupport<I>>(
+    config: FmIndexConfig<I, R>,
+    workload: &Workload,
+) -> (FmIndex<I, R>, Duration) {
+    let start = Instant::now();
+    let index = config.construct_index(&workload.texts, workload.alphabet.clone());
+
+    (index, start.elapsed())
+}
+
+/// Counts all queries of the workload using [`FmIndex::count_many`] and measures the wall time.
+///
+/// Returns the total number of hits, which can be used to check that all indices agree.
+pub fn measure_count<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    workload: &Workload,
+) -> (usize, Duration) {
+    let start = Instant::now();
+    let total_count = index.count_many(&workload.queries).sum();
+
+    (total_count, start.elapsed())
+}
+
+/// Locates all queries of the workload using [`FmIndex::locate_many`] and measures the wall time.
+///
+/// Returns the total number of hits, which can be used to check that all indices agree.
+pub fn measure_locate<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    workload: &Workload,
+) -> (usize, Duration) {
+    let start = Instant::now();
+    let total_count = index
+        .locate_many(&workload.queries)
+        .map(|hits| hits.count())
+        .sum();
+
+    (total_count, start.elapsed())
+}
+
+/// Executes `num_queries` rank queries at random positions of a uniformly random text and measures the wall time.
+///
+/// The text has length `text_len` and contains the dense symbols `0..alphabet_size`. This can be used to compare the
+/// implementations of [`TextWithRankSupport`] and the [`Block`](crate::text_with_rank_support::Block) configurations,
+/// also with and without `-C target-cpu=native`, which allows inlining the vectorized kernels of the blocks.
+/// The construction is not included in the measured time.
+///
+/// Returns the sum of all ranks, which can be used to check that all implementations agree.
+pub fn measure_rank<I: IndexStorage, R: TextWithRankSupport<I>>(
+    text_len: usize,
+    alphabet_size: usize,
+    num_queries: usize,
+    seed: u64,
+) -> (usize, Duration) {
+    assert!(alphabet_size > 0 && alphabet_size <= 256);
+
+    let mut rng = SplitMix64::new(seed);
+    let text: Vec<_> = (0..text_len)
+        .map(|_| rng.next_below(alphabet_size) as u8)
+        .collect();
+    let text_with_rank_support = R::construct(&text, alphabet_size);
+
+    let queries: Vec<_> = (0..num_queries)
+        .map(|_| {
+            (
+                rng.next_below(alphabet_size) as u8,
+                rng.next_below(text_len + 1),
+            )
+        })
+        .collect();
+
+    let start = Instant::now();
+    let rank_sum = queries
+        .iter()
+        .map(|&(symbol, idx)| text_with_rank_support.rank(symbol, idx))
+        .sum();
+
+    (rank_sum, start.elapsed())
+}
+
+fn random_texts(
+    symbols: &[u8],
+    total_text_len: usize,
+    max_text_len: usize,
+    rng: &mut SplitMix64,
+) -> Vec<Vec<u8>> {
+    let mut texts = Vec::new();
+    let mut remaining_len = total_text_len;
+
+    while remaining_len > 0 {
+        let text_len = remaining_len.min(max_text_len);
+        texts.push(random_text(symbols, text_len, rng));
+        remaining_len -= text_len;
+    }
+
+    texts
+}
+
+fn random_text(symbols: &[u8], len: usize, rng: &mut SplitMix64) -> Vec<u8> {
+    (0..len)
+        .map(|_| symbols[rng.next_below(symbols.len())])
+        .collect()
+}
+
+// texts that are shorter than the queries are skipped
+fn sample_queries(
+    texts: &[Vec<u8>],
+    num_queries: usize,
+    query_len: usize,
+    rng: &mut SplitMix64,
+) -> Vec<Vec<u8>> {
+    let long_enough_texts: Vec<_> = texts.iter().filter(|t| t.len() >= query_len).collect();
+
+    if long_enough_texts.is_empty() {
+        return Vec::new();
+    }
+
+    (0..num_queries)
+        .map(|_| {
+            let text = long_enough_texts[rng.next_below(long_enough_texts.len())];
+            let start = rng.next_below(text.len() - query_len + 1);
+            text[start..start + query_len].to_vec()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workloads_are_reproducible_and_searchable() {
+        for workload_fn in [
+            |seed| uniform_dna(3_000, 50, seed),
+            |seed| pangenome_like(3_000, 4, 50, seed),
+            |seed| protein(3_000, 50, seed),
+        ] {
+            let workload = workload_fn(42);
+            assert_eq!(workload.texts, workload_fn(42).texts);
+            assert_eq!(workload.queries, workload_fn(42).queries);
+            assert_ne!(workload.texts, workload_fn(43).texts);
+
+            assert_eq!(workload.queries.len(), 50);
+
+            let (index, _) = measure_construction(FmIndexConfig::<i32>::new(), &workload);
+            let (total_count, _) = measure_count(&index, &workload);
+            let (total_located, _) = measure_locate(&index, &workload);
+
+            assert_eq!(total_count, total_located);
+            assert!(index.count_many(&workload.queries).all(|count| count > 0));
+        }
+    }
+}
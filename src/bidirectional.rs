@@ -0,0 +1,394 @@
+use crate::{
+    Alphabet, ApproximateHit, Cursor, FmIndex, FmIndexConfig, HalfOpenInterval, Hit, IndexStorage,
+    check_empty_text_policy, construction,
+    search_schemes::{self, SearchScheme},
+    text_with_rank_support::{Block64, CondensedTextWithRankSupport, TextWithRankSupport},
+};
+
+/// An FM-Index of a set of texts together with an FM-Index of the reversed texts.
+///
+/// The [`BidirectionalCursor`] maintains the suffix array intervals of the searched query in the forward index and of
+/// the reversed query in the reverse index. This allows extending the query at the front and at the back, which is the
+/// basis of search schemes for approximate search and of seed extension algorithms.
+///
+/// The index needs about twice the memory of a single FM-Index. Hits are located using the forward index.
+///
+/// ```
+/// use genedex::{FmIndexConfig, alphabet};
+///
+/// let index = FmIndexConfig::<i32>::new()
+///     .construct_bidirectional_index([b"ACGTACGT".as_slice(), b"CGGA"], alphabet::ascii_dna());
+///
+/// let mut cursor = index.cursor_for_query(b"G");
+/// cursor.extend_query_front(b'C');
+/// cursor.extend_query_back(b'T');
+///
+/// assert_eq!(cursor.count(), 2);
+/// assert_eq!(cursor.count(), index.count(b"CGT"));
+/// ```
+#[derive(Clone)]
+pub struct BidirectionalFmIndex<I, R = CondensedTextWithRankSupport<I, Block64>> {
+    forward_index: FmIndex<I, R>,
+    reverse_index: FmIndex<I, R>,
+}
+
+impl<I: IndexStorage, R: TextWithRankSupport<I>> BidirectionalFmIndex<I, R> {
+    pub(crate) fn new<T: AsRef<[u8]>>(
+        texts: impl IntoIterator<Item = T>,
+        alphabet: Alphabet,
+        config: FmIndexConfig<I, R>,
+    ) -> Self {
+        let texts = check_empty_text_policy(texts, config.empty_text_policy);
+        let (forward_data_structures, reverse_data_structures) =
+            construction::create_forward_and_reverse_data_structures(texts, &config, &alphabet);
+
+        let reverse_index =
+            FmIndex::from_data_structures(reverse_data_structures, alphabet.clone(), &config);
+        let forward_index =
+            FmIndex::from_data_structures(forward_data_structures, alphabet, &config);
+
+        Self {
+            forward_index,
+            reverse_index,
+        }
+    }
+
+    /// Returns the number of occurrences of `query` in the set of indexed texts, see [`FmIndex::count`].
+    pub fn count(&self, query: &[u8]) -> usize {
+        self.forward_index.count(query)
+    }
+
+    /// Returns the occurrences of `query` in the set of indexed texts, see [`FmIndex::locate`].
+    pub fn locate(&self, query: &[u8]) -> impl Iterator<Item = Hit> {
+        self.forward_index.locate(query)
+    }
+
+    /// Returns the occurrences of `query` with at most the number of substitutions allowed by `scheme`,
+    /// sorted by text id and position.
+    ///
+    /// Every search of the scheme is executed as a depth-first search over extensions of a [`BidirectionalCursor`].
+    /// The parts of the query are searched in the order of the search, and a symbol different from the query can only
+    /// be used while the accumulated number of errors stays within the bounds of the search. Hits that are found by
+    /// multiple searches are only reported once. The number of substitutions of a hit is reported as its
+    /// [`edit_distance`](ApproximateHit::edit_distance).
+    ///
+    /// Panics if the query is shorter than the number of parts of the scheme.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet, search_schemes::SearchScheme};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_bidirectional_index([b"ACGTACGTTTGACCA".as_slice()], alphabet::ascii_dna());
+    ///
+    /// let hits = index.locate_with_search_scheme(b"ACGTTA", &SearchScheme::zero_one_star_zero(1));
+    ///
+    /// assert_eq!(hits.len(), 1);
+    /// assert_eq!((hits[0].range.clone(), hits[0].edit_distance), (4..10, 1));
+    /// ```
+    pub fn locate_with_search_scheme(
+        &self,
+        query: &[u8],
+        scheme: &SearchScheme,
+    ) -> Vec<ApproximateHit> {
+        search_schemes::locate_with_search_scheme(self, query, scheme)
+    }
+
+    /// Returns a cursor to the index with the empty query currently searched.
+    ///
+    /// See [`BidirectionalCursor`] for details. Running time is in `O(1)`.
+    pub fn cursor_empty<'a>(&'a self) -> BidirectionalCursor<'a, I, R> {
+        BidirectionalCursor {
+            index: self,
+            forward_start: 0,
+            reverse_start: 0,
+            size: self.forward_index.total_text_len(),
+            query_len: 0,
+        }
+    }
+
+    /// Returns a cursor to the index with `query` currently searched.
+    ///
+    /// The query is searched by extending an empty cursor at the front, so the lookup table can't be used.
+    pub fn cursor_for_query<'a>(&'a self, query: &[u8]) -> BidirectionalCursor<'a, I, R> {
+        let mut cursor = self.cursor_empty();
+
+        for &symbol in query.iter().rev() {
+            cursor.extend_query_front(symbol);
+        }
+
+        cursor
+    }
+
+    pub fn num_texts(&self) -> usize {
+        self.forward_index.num_texts()
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        self.forward_index.alphabet()
+    }
+
+    /// The FM-Index of the texts.
+    pub fn forward_index(&self) -> &FmIndex<I, R> {
+        &self.forward_index
+    }
+
+    /// The FM-Index of the reversed texts. The text ids are the same as in the forward index.
+    pub fn reverse_index(&self) -> &FmIndex<I, R> {
+        &self.reverse_index
+    }
+
+    /// Returns the FM-Index of the texts and the FM-Index of the reversed texts, for custom search procedures that
+    /// own the two indices separately.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let (forward_index, reverse_index) = FmIndexConfig::<i32>::new()
+    ///     .construct_bidirectional_index([b"ACGTTG".as_slice(), b"CGGA"], alphabet::ascii_dna())
+    ///     .into_forward_and_reverse_indices();
+    ///
+    /// assert_eq!(forward_index.count(b"GTT"), 1);
+    /// assert_eq!(reverse_index.count(b"TTG"), 1);
+    /// assert_eq!(reverse_index.count(b"GGC"), 1);
+    /// ```
+    pub fn into_forward_and_reverse_indices(self) -> (FmIndex<I, R>, FmIndex<I, R>) {
+        (self.forward_index, self.reverse_index)
+    }
+}
+
+/// A cursor to the [`BidirectionalFmIndex`] that can extend the searched query at both ends.
+///
+/// Like the [`Cursor`], it implicitly maintains a currently searched query. Symbols can be added to the front
+/// and to the back of the query in any order.
+///
+/// Internally, the cursor stores the suffix array interval of the query in the forward index and the interval
+/// of the reversed query in the reverse index, which always have the same size. When one of the intervals is
+/// updated using the LF-mapping, the other one is updated using the sizes of the intervals of all possible extensions.
+pub struct BidirectionalCursor<'a, I, R> {
+    index: &'a BidirectionalFmIndex<I, R>,
+    forward_start: usize,
+    reverse_start: usize,
+    size: usize,
+    query_len: usize,
+}
+
+// the derive is too restrictive
+impl<'a, I, R> Clone for BidirectionalCursor<'a, I, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, I, R> Copy for BidirectionalCursor<'a, I, R> {}
+
+impl<'a, I: IndexStorage, R: TextWithRankSupport<I>> BidirectionalCursor<'a, I, R> {
+    /// Extends the currently searched query at the front by one symbol.
+    ///
+    /// The running time is in O(σ), where σ is the number of dense symbols of the alphabet.
+    pub fn extend_query_front(&mut self, symbol: u8) {
+        let symbol = self.index.alphabet().io_to_dense_representation(symbol);
+
+        self.extend_front_without_alphabet_translation(symbol);
+    }
+
+    pub(crate) fn extend_front_without_alphabet_translation(&mut self, symbol: u8) {
+        (self.forward_start, self.reverse_start, self.size) = extend_front(
+            &self.index.forward_index,
+            self.forward_start,
+            self.reverse_start,
+            self.size,
+            symbol,
+        );
+        self.query_len += 1;
+    }
+
+    /// Extends the currently searched query at the back by one symbol.
+    ///
+    /// The running time is in O(σ), where σ is the number of dense symbols of the alphabet.
+    pub fn extend_query_back(&mut self, symbol: u8) {
+        let symbol = self.index.alphabet().io_to_dense_representation(symbol);
+
+        self.extend_back_without_alphabet_translation(symbol);
+    }
+
+    pub(crate) fn extend_back_without_alphabet_translation(&mut self, symbol: u8) {
+        (self.reverse_start, self.forward_start, self.size) = extend_front(
+            &self.index.reverse_index,
+            self.reverse_start,
+            self.forward_start,
+            self.size,
+            symbol,
+        );
+        self.query_len += 1;
+    }
+
+    /// Returns the number of occurrences of the currently searched query in the set of indexed texts.
+    ///
+    /// The running time is in O(1).
+    pub fn count(&self) -> usize {
+        self.size
+    }
+
+    /// The length of the currently searched query.
+    pub fn query_len(&self) -> usize {
+        self.query_len
+    }
+
+    /// Returns the occurrences of the currently searched query in the set of indexed texts, see [`Cursor::locate`].
+    pub fn locate(&self) -> impl Iterator<Item = Hit> + use<'a, I, R> {
+        self.index
+            .forward_index
+            .locate_interval(self.forward_cursor().interval())
+    }
+
+    /// Returns a [`Cursor`] to the forward index with the currently searched query.
+    pub fn forward_cursor(&self) -> Cursor<'a, I, R> {
+        Cursor {
+            index: &self.index.forward_index,
+            interval: HalfOpenInterval {
+                start: self.forward_start,
+                end: self.forward_start + self.size,
+            },
+        }
+    }
+
+    /// Returns a [`Cursor`] to the reverse index with the reversed currently searched query.
+    pub fn reverse_cursor(&self) -> Cursor<'a, I, R> {
+        Cursor {
+            index: &self.index.reverse_index,
+            interval: HalfOpenInterval {
+                start: self.reverse_start,
+                end: self.reverse_start + self.size,
+            },
+        }
+    }
+}
+
+// extends the query at the front in `index` and at the back in the other index, returns the new starts and size
+fn extend_front<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    start: usize,
+    other_start: usize,
+    size: usize,
+    symbol: u8,
+) -> (usize, usize, usize) {
+    if size == 0 {
+        return (start, other_start, size);
+    }
+
+    let end = start + size;
+
+    // the interval in the other index is partitioned by the symbol that follows the reversed query.
+    // Sentinels come first, then the other symbols in the order of their dense representation.
+    let mut num_non_sentinel = 0;
+    let mut num_smaller = 0;
+    let mut new_size = 0;
+
+    for other_symbol in 1..index.alphabet.num_dense_symbols() as u8 {
+        let other_size = index.text_with_rank_support.rank(other_symbol, end)
+            - index.text_with_rank_support.rank(other_symbol, start);
+
+        num_non_sentinel += other_size;
+
+        if other_symbol < symbol {
+            num_smaller += other_size;
+        } else if other_symbol == symbol {
+            new_size = other_size;
+        }
+    }
+
+    let num_sentinel = size - num_non_sentinel;
+
+    (
+        index.lf_mapping_step(symbol, start),
+        other_start + num_sentinel + num_smaller,
+        new_size,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet;
+    use proptest::prelude::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn shared_construction_agrees_with_separate_indices(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 0..60), 1..4),
+            suffix_array_sampling_rate in 1usize..5,
+        ) {
+            let config = || FmIndexConfig::<i32>::new().suffix_array_sampling_rate(suffix_array_sampling_rate);
+            let (forward_index, reverse_index) = config()
+                .construct_bidirectional_index(&texts, alphabet::ascii_dna_with_n())
+                .into_forward_and_reverse_indices();
+
+            let reversed_texts: Vec<Vec<u8>> = texts
+                .iter()
+                .map(|text| text.iter().rev().copied().collect())
+                .collect();
+
+            let expected_forward_index = config().construct_index(&texts, alphabet::ascii_dna_with_n());
+            let expected_reverse_index = config().construct_index(&reversed_texts, alphabet::ascii_dna_with_n());
+
+            prop_assert_eq!(forward_index.component_hashes(), expected_forward_index.component_hashes());
+            prop_assert_eq!(reverse_index.component_hashes(), expected_reverse_index.component_hashes());
+        }
+
+        #[test]
+        fn bidirectional_search_agrees_with_unidirectional(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..60), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 1..6),
+            extend_back in prop::collection::vec(any::<bool>(), 6),
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .construct_bidirectional_index(&texts, alphabet::ascii_dna_with_n());
+
+            // build the query from a seed symbol in the middle, extending at both sides in a random order
+            let mut cursor = index.cursor_empty();
+            let (mut front, mut back) = (query.len() / 2, query.len() / 2);
+            cursor.extend_query_back(query[back]);
+            back += 1;
+
+            for &prefer_back in &extend_back {
+                if (prefer_back && back < query.len()) || front == 0 {
+                    if back == query.len() {
+                        break;
+                    }
+                    cursor.extend_query_back(query[back]);
+                    back += 1;
+                } else {
+                    front -= 1;
+                    cursor.extend_query_front(query[front]);
+                }
+            }
+
+            let searched = &query[front..back];
+            let reversed: Vec<_> = searched.iter().rev().copied().collect();
+
+            prop_assert_eq!(cursor.query_len(), searched.len());
+            prop_assert_eq!(cursor.count(), index.count(searched));
+
+            // the positions of empty intervals depend on the search order
+            if cursor.count() > 0 {
+                prop_assert_eq!(
+                    cursor.forward_cursor().interval(),
+                    index.forward_index().cursor_for_query(searched).interval()
+                );
+                prop_assert_eq!(
+                    cursor.reverse_cursor().interval(),
+                    index.reverse_index().cursor_for_query(&reversed).interval()
+                );
+            }
+
+            let mut hits: Vec<_> = cursor.locate().collect();
+            hits.sort();
+            let mut expected_hits: Vec<_> = index.locate(searched).collect();
+            expected_hits.sort();
+
+            prop_assert_eq!(hits, expected_hits);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::HalfOpenInterval;
+
+// Named suffix array intervals that are stored together with the index, sorted by name.
+// The intervals are the raw intervals of cursors, such that cursors can be restored from them.
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Bookmarks {
+    entries: Vec<Bookmark>,
+}
+
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[derive(Debug, Clone)]
+struct Bookmark {
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+impl Bookmarks {
+    pub(crate) fn heap_size_bytes(&self) -> usize {
+        size_of_val(self.entries.as_slice())
+            + self
+                .entries
+                .iter()
+                .map(|bookmark| bookmark.name.len())
+                .sum::<usize>()
+    }
+
+    fn position(&self, name: &str) -> Result<usize, usize> {
+        self.entries
+            .binary_search_by(|bookmark| bookmark.name.as_str().cmp(name))
+    }
+
+    // replaces the interval of an existing bookmark with the same name
+    pub(crate) fn insert(&mut self, name: String, interval: HalfOpenInterval) {
+        let bookmark = Bookmark {
+            start: interval.start,
+            end: interval.end,
+            name,
+        };
+
+        match self.position(&bookmark.name) {
+            Ok(idx) => self.entries[idx] = bookmark,
+            Err(idx) => self.entries.insert(idx, bookmark),
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<HalfOpenInterval> {
+        self.position(name).ok().map(|idx| HalfOpenInterval {
+            start: self.entries[idx].start,
+            end: self.entries[idx].end,
+        })
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> bool {
+        self.position(name)
+            .map(|idx| self.entries.remove(idx))
+            .is_ok()
+    }
+
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|bookmark| bookmark.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_replace_and_remove() {
+        let mut bookmarks = Bookmarks::default();
+
+        bookmarks.insert("b".to_string(), HalfOpenInterval { start: 1, end: 3 });
+        bookmarks.insert("a".to_string(), HalfOpenInterval { start: 0, end: 0 });
+        bookmarks.insert("b".to_string(), HalfOpenInterval { start: 2, end: 5 });
+
+        assert_eq!(bookmarks.names().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(
+            bookmarks.get("b"),
+            Some(HalfOpenInterval { start: 2, end: 5 })
+        );
+        assert_eq!(bookmarks.get("c"), None);
+
+        assert!(bookmarks.remove("a"));
+        assert!(!bookmarks.remove("a"));
+        assert_eq!(bookmarks.names().collect::<Vec<_>>(), ["b"]);
+    }
+}
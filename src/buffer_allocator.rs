@@ -0,0 +1,330 @@
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    sync::Arc,
+};
+
+use bytemuck::Pod;
+
+/// An allocator for the large temporary buffers that are used during the construction of the FM-Index.
+///
+/// The construction needs a few buffers that are as large as the concatenated texts or even several times larger,
+/// such as the buffer of the suffix array. For large inputs, these buffers can take up multiple gigabytes. A custom
+/// allocator can be supplied via [`FmIndexConfig::buffer_allocator`](crate::FmIndexConfig::buffer_allocator) to route
+/// these buffers to arena, NUMA-bound or hugepage allocators. The allocator is either any type that implements
+/// [`GlobalAlloc`], which can carry state like a handle to an arena, or a simple pair of functions to allocate and
+/// free memory. Most buffers have to be zeroed, so [`GlobalAlloc::alloc_zeroed`] is used to allocate them. Allocators
+/// that can provide zeroed memory cheaply, like `mmap`, should override it.
+///
+/// By default, the global allocator of the program is used. Smaller data structures and the final data structures of the
+/// index are always allocated using the global allocator.
+///
+/// ```
+/// use std::alloc::{GlobalAlloc, Layout, System};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use genedex::{BufferAllocator, FmIndexConfig, alphabet};
+///
+/// struct MyAllocator {
+///     num_allocated_bytes: AtomicUsize,
+/// }
+///
+/// // SAFETY: the functions forward to the system allocator
+/// unsafe impl GlobalAlloc for MyAllocator {
+///     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+///         // e.g. mmap with hugepages here
+///         self.num_allocated_bytes.fetch_add(layout.size(), Ordering::Relaxed);
+///         unsafe { System.alloc(layout) }
+///     }
+///
+///     unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+///         self.num_allocated_bytes.fetch_add(layout.size(), Ordering::Relaxed);
+///         unsafe { System.alloc_zeroed(layout) }
+///     }
+///
+///     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+///         unsafe { System.dealloc(ptr, layout) }
+///     }
+/// }
+///
+/// let allocator = BufferAllocator::from_global_alloc(MyAllocator {
+///     num_allocated_bytes: AtomicUsize::new(0),
+/// });
+///
+/// let index = FmIndexConfig::<i32>::new()
+///     .buffer_allocator(allocator)
+///     .construct_index([b"ACGT"], alphabet::ascii_dna());
+/// ```
+#[derive(Clone)]
+pub struct BufferAllocator {
+    inner: AllocatorKind,
+}
+
+#[derive(Clone)]
+enum AllocatorKind {
+    Global,
+    Functions {
+        alloc: unsafe fn(Layout) -> *mut u8,
+        dealloc: unsafe fn(*mut u8, Layout),
+    },
+    Custom(Arc<dyn GlobalAlloc + Send + Sync>),
+}
+
+impl BufferAllocator {
+    /// Creates an allocator from a pair of functions to allocate and free memory.
+    ///
+    /// # Safety
+    ///
+    /// The functions have to follow the same contract as [`GlobalAlloc::alloc`](std::alloc::GlobalAlloc::alloc)
+    /// and [`GlobalAlloc::dealloc`](std::alloc::GlobalAlloc::dealloc). In particular, the returned memory has to
+    /// fulfill the size and alignment requirements of the layout, and `dealloc` has to accept every pointer returned
+    /// by `alloc`, together with the layout used for the allocation. `alloc` may return a null pointer to signal
+    /// an allocation failure. Both functions are called from multiple threads.
+    /// They are never called with layouts of size zero.
+    ///
+    /// Since plain functions can't provide zeroed memory, the memory returned by `alloc` is zeroed by the
+    /// construction where necessary. Use [`Self::from_global_alloc`] for allocators with state or a cheaper
+    /// way to obtain zeroed memory.
+    pub const unsafe fn new(
+        alloc: unsafe fn(Layout) -> *mut u8,
+        dealloc: unsafe fn(*mut u8, Layout),
+    ) -> Self {
+        Self {
+            inner: AllocatorKind::Functions { alloc, dealloc },
+        }
+    }
+
+    /// Creates an allocator from an implementation of [`GlobalAlloc`], which is shared by clones of this allocator.
+    ///
+    /// The contract of the allocator is given by the (unsafe) [`GlobalAlloc`] trait. Its functions are called from
+    /// multiple threads and never with layouts of size zero. `realloc` is not used.
+    pub fn from_global_alloc(allocator: impl GlobalAlloc + Send + Sync + 'static) -> Self {
+        Self {
+            inner: AllocatorKind::Custom(Arc::new(allocator)),
+        }
+    }
+
+    /// The global allocator of the program. This is the default.
+    pub const fn global() -> Self {
+        Self {
+            inner: AllocatorKind::Global,
+        }
+    }
+
+    // SAFETY: the layout must have non-zero size
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        match &self.inner {
+            AllocatorKind::Global => unsafe { std::alloc::alloc_zeroed(layout) },
+            AllocatorKind::Functions { alloc, .. } => {
+                let ptr = unsafe { alloc(layout) };
+
+                if !ptr.is_null() {
+                    // SAFETY: the allocation is valid for layout.size() bytes
+                    unsafe { ptr.write_bytes(0, layout.size()) };
+                }
+
+                ptr
+            }
+            AllocatorKind::Custom(allocator) => unsafe { allocator.alloc_zeroed(layout) },
+        }
+    }
+
+    // SAFETY: the pointer must have been allocated by this allocator with the same layout
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        match &self.inner {
+            AllocatorKind::Global => unsafe { std::alloc::dealloc(ptr, layout) },
+            AllocatorKind::Functions { dealloc, .. } => unsafe { dealloc(ptr, layout) },
+            AllocatorKind::Custom(allocator) => unsafe { allocator.dealloc(ptr, layout) },
+        }
+    }
+}
+
+impl fmt::Debug for BufferAllocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.inner {
+            AllocatorKind::Global => "Global",
+            AllocatorKind::Functions { .. } => "Functions",
+            AllocatorKind::Custom(_) => "Custom",
+        };
+
+        f.debug_tuple("BufferAllocator").field(&kind).finish()
+    }
+}
+
+impl Default for BufferAllocator {
+    fn default() -> Self {
+        Self::global()
+    }
+}
+
+// A fixed capacity buffer allocated using a BufferAllocator. It is like a Vec that can never reallocate.
+// This has to be public, because it appears in the signature of the IndexStorage trait.
+#[doc(hidden)]
+pub struct LargeBuffer<T> {
+    ptr: NonNull<T>,
+    len: usize,
+    capacity: usize,
+    allocator: BufferAllocator,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> LargeBuffer<T> {
+    pub(crate) fn new_zeroed(capacity: usize, allocator: &BufferAllocator) -> Self {
+        let layout = Layout::array::<T>(capacity).expect("buffer size should not overflow");
+
+        let ptr = if layout.size() == 0 {
+            NonNull::dangling()
+        } else {
+            // SAFETY: the layout has non-zero size. All zero bytes are valid values of T (Pod)
+            let ptr = unsafe { allocator.alloc_zeroed(layout) };
+
+            let Some(ptr) = NonNull::new(ptr.cast::<T>()) else {
+                std::alloc::handle_alloc_error(layout);
+            };
+
+            ptr
+        };
+
+        Self {
+            ptr,
+            len: capacity,
+            capacity,
+            allocator: allocator.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn allocator(&self) -> &BufferAllocator {
+        &self.allocator
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    // panics if there is no remaining capacity
+    pub(crate) fn push(&mut self, value: T) {
+        assert!(self.len < self.capacity, "large buffer capacity exceeded");
+
+        // SAFETY: the index is within the allocation
+        unsafe { self.ptr.as_ptr().add(self.len).write(value) };
+        self.len += 1;
+    }
+}
+
+impl<T> Deref for LargeBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        // SAFETY: the first len values are initialized and the pointer is valid (or dangling for empty buffers)
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> DerefMut for LargeBuffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        // SAFETY: see deref
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> AsMut<[T]> for LargeBuffer<T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T> Drop for LargeBuffer<T> {
+    fn drop(&mut self) {
+        let layout = Layout::array::<T>(self.capacity).unwrap();
+
+        if layout.size() != 0 {
+            // SAFETY: the pointer was allocated in new_zeroed using the same allocator and layout
+            unsafe { self.allocator.dealloc(self.ptr.as_ptr().cast(), layout) };
+        }
+    }
+}
+
+// SAFETY: the buffer uniquely owns its values, like a Vec
+unsafe impl<T: Send> Send for LargeBuffer<T> {}
+unsafe impl<T: Sync> Sync for LargeBuffer<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NUM_LIVE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe fn counting_alloc(layout: Layout) -> *mut u8 {
+        NUM_LIVE_ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        unsafe { std::alloc::alloc(layout) }
+    }
+
+    unsafe fn counting_dealloc(ptr: *mut u8, layout: Layout) {
+        NUM_LIVE_ALLOCATIONS.fetch_sub(1, Ordering::SeqCst);
+        unsafe { std::alloc::dealloc(ptr, layout) }
+    }
+
+    #[test]
+    fn custom_allocator_is_used() {
+        let allocator = unsafe { BufferAllocator::new(counting_alloc, counting_dealloc) };
+
+        let mut buffer = LargeBuffer::<u32>::new_zeroed(10, &allocator);
+        let empty_buffer = LargeBuffer::<u8>::new_zeroed(0, &allocator);
+
+        assert_eq!(NUM_LIVE_ALLOCATIONS.load(Ordering::SeqCst), 1);
+        assert!(buffer.iter().all(|&v| v == 0));
+        assert!(empty_buffer.is_empty());
+
+        buffer.truncate(8);
+        buffer.push(3);
+        assert_eq!(buffer.len(), 9);
+        assert_eq!(buffer[8], 3);
+
+        drop(buffer);
+        drop(empty_buffer);
+
+        assert_eq!(NUM_LIVE_ALLOCATIONS.load(Ordering::SeqCst), 0);
+    }
+
+    #[derive(Default)]
+    struct AllocationCounts {
+        num_zeroed_allocations: AtomicUsize,
+        num_deallocations: AtomicUsize,
+    }
+
+    struct ZeroedCountingAllocator(Arc<AllocationCounts>);
+
+    unsafe impl GlobalAlloc for ZeroedCountingAllocator {
+        unsafe fn alloc(&self, _layout: Layout) -> *mut u8 {
+            panic!("buffers should be allocated zeroed")
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            self.0.num_zeroed_allocations.fetch_add(1, Ordering::SeqCst);
+            unsafe { std::alloc::alloc_zeroed(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.0.num_deallocations.fetch_add(1, Ordering::SeqCst);
+            unsafe { std::alloc::dealloc(ptr, layout) }
+        }
+    }
+
+    #[test]
+    fn global_alloc_is_used_zeroed() {
+        let counts = Arc::new(AllocationCounts::default());
+        let allocator =
+            BufferAllocator::from_global_alloc(ZeroedCountingAllocator(Arc::clone(&counts)));
+
+        let buffer = LargeBuffer::<u64>::new_zeroed(100, &allocator.clone());
+        assert!(buffer.iter().all(|&v| v == 0));
+        assert_eq!(counts.num_zeroed_allocations.load(Ordering::SeqCst), 1);
+
+        drop(buffer);
+        assert_eq!(counts.num_deallocations.load(Ordering::SeqCst), 1);
+    }
+}
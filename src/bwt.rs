@@ -0,0 +1,474 @@
+use std::collections::{BTreeMap, HashMap};
+
+use num_traits::NumCast;
+
+use crate::{
+    Alphabet, BufferAllocator, IndexStorage,
+    buffer_allocator::LargeBuffer,
+    construction::{
+        self,
+        slice_compression::{HalfBytesCompression, NoSliceCompression, SliceCompression},
+    },
+};
+
+/// The Burrows-Wheeler transform (BWT) of a set of texts, in dense representation.
+///
+/// The texts are concatenated with a sentinel (dense symbol `0`) after each text, like in the FM-Index of this library.
+/// The BWT has the same length as the concatenated text and contains one sentinel for each text. How the
+/// sentinels of different texts compare to each other is determined by the [`SentinelOrder`].
+///
+/// The BWT can optionally be stored half-byte compressed, such that two symbols are stored in a single byte.
+/// This is only possible for alphabets with 16 or less dense symbols.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bwt {
+    data: Vec<u8>,
+    len: usize,
+    is_half_byte_compressed: bool,
+    sentinel_suffix_array_values: BTreeMap<usize, usize>,
+}
+
+impl Bwt {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_half_byte_compressed(&self) -> bool {
+        self.is_half_byte_compressed
+    }
+
+    /// The raw data of the BWT.
+    ///
+    /// If the BWT is half-byte compressed, every byte contains two symbols, the first one in the upper four bits.
+    /// In that case, the last four bits of the data might not belong to the BWT. The [`packing`](crate::packing)
+    /// module contains functions to work with such data.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Panics if `idx` is not smaller than the length of the BWT.
+    pub fn symbol_at(&self, idx: usize) -> u8 {
+        assert!(idx < self.len, "index out of bounds");
+
+        if self.is_half_byte_compressed {
+            HalfBytesCompression::get(idx, &self.data)
+        } else {
+            NoSliceCompression::get(idx, &self.data)
+        }
+    }
+
+    /// Iterates over the symbols of the BWT in dense representation.
+    pub fn iter(&self) -> impl Iterator<Item = u8> {
+        let symbols: Box<dyn Iterator<Item = u8>> = if self.is_half_byte_compressed {
+            Box::new(HalfBytesCompression::iter(&self.data))
+        } else {
+            Box::new(NoSliceCompression::iter(&self.data))
+        };
+
+        symbols.take(self.len)
+    }
+
+    /// Maps each row of the BWT that contains a sentinel to the suffix array value of the row.
+    ///
+    /// The suffix array value is an index into the concatenated text. For every text, there is one such row.
+    /// These values are needed, because the implicit sentinel at the end of the concatenated text breaks
+    /// the LF-mapping for the sentinel rows.
+    pub fn sentinel_suffix_array_values(&self) -> &BTreeMap<usize, usize> {
+        &self.sentinel_suffix_array_values
+    }
+}
+
+/// Determines how the sentinels of different texts compare to each other during the construction of a [`Bwt`].
+///
+/// Every sentinel is smaller than all other symbols. Different orders of the sentinels lead to different BWTs,
+/// because the order of the sentinels decides the order of suffixes that are equal up to their sentinels,
+/// such as the suffixes `"A$"` of the texts `"CA"` and `"GA"`. The symbols at the positions of the sentinels differ,
+/// too. To exchange BWTs with other tools byte by byte, the order of those tools has to be chosen.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SentinelOrder {
+    /// All sentinels are equal, and ties are broken by the concatenated text that follows the sentinels.
+    /// The sentinel at the end of the last text is the smallest one.
+    ///
+    /// This is the order of the FM-Index of this library, and of the BWT of the concatenated texts when
+    /// every sentinel is treated as the same symbol.
+    #[default]
+    ByFollowingTexts,
+    /// The sentinel of text `i` is smaller than the sentinel of text `j`, if `i < j`.
+    ///
+    /// This is the order used by many tools for BWTs of string collections, for example ropebwt2 and BEETL.
+    ByTextId,
+    /// The sentinel of text `i` has the rank `ranks[i]`, where lower ranks compare less.
+    ///
+    /// The ranks must be a permutation of `0..num_texts`, otherwise the construction panics.
+    ByRank(Vec<usize>),
+}
+
+/// Constructs the BWT of the given texts, using [`SentinelOrder::ByFollowingTexts`].
+///
+/// The texts are supplied in IO representation of `alphabet`. The construction uses the same
+/// fast and parallel algorithms as the construction of the FM-Index. The number of threads
+/// is controlled by [`rayon`]. The index storage type `I` determines the maximum total length of the texts
+/// and the memory usage during construction (see [`IndexStorage`]).
+///
+/// If `half_byte_compress` is `true`, the BWT is constructed with less memory and stored half-byte compressed.
+/// Panics in that case if the alphabet has more than 16 dense symbols.
+///
+/// ```
+/// use genedex::{alphabet, bwt};
+///
+/// let alphabet = alphabet::ascii_dna();
+/// let bwt = bwt::construct_bwt::<i32, _>([b"ACGT".as_slice(), b"AC"], &alphabet, false);
+///
+/// let bwt_io: Vec<_> = bwt
+///     .iter()
+///     .map(|s| alphabet.try_dense_to_io_representation(s).unwrap_or(b'$'))
+///     .collect();
+///
+/// assert_eq!(bwt_io, b"CT$$AACG");
+/// ```
+pub fn construct_bwt<I: IndexStorage, T: AsRef<[u8]>>(
+    texts: impl IntoIterator<Item = T>,
+    alphabet: &Alphabet,
+    half_byte_compress: bool,
+) -> Bwt {
+    construct_bwt_with_sentinel_order::<I, T>(
+        texts,
+        alphabet,
+        half_byte_compress,
+        &SentinelOrder::ByFollowingTexts,
+    )
+}
+
+/// Constructs the BWT of the given texts, with the sentinels ordered according to `sentinel_order`.
+///
+/// See [`construct_bwt`] for the other parameters. The values of [`Bwt::sentinel_suffix_array_values`]
+/// always refer to the concatenation of the texts in the order in which they are given, independent of
+/// the sentinel order.
+///
+/// ```
+/// use genedex::{alphabet, bwt::{self, SentinelOrder}};
+///
+/// let alphabet = alphabet::ascii_dna();
+/// let texts = [b"CA".as_slice(), b"GA"];
+///
+/// let to_io = |bwt: bwt::Bwt| -> Vec<u8> {
+///     bwt.iter()
+///         .map(|s| alphabet.try_dense_to_io_representation(s).unwrap_or(b'$'))
+///         .collect()
+/// };
+///
+/// let by_id = bwt::construct_bwt_with_sentinel_order::<i32, _>(texts, &alphabet, false, &SentinelOrder::ByTextId);
+/// let by_rank = bwt::construct_bwt_with_sentinel_order::<i32, _>(
+///     texts,
+///     &alphabet,
+///     false,
+///     &SentinelOrder::ByRank(vec![1, 0]),
+/// );
+///
+/// assert_eq!(to_io(by_id), b"AACG$$");
+/// assert_eq!(to_io(by_rank), b"AAGC$$");
+/// ```
+pub fn construct_bwt_with_sentinel_order<I: IndexStorage, T: AsRef<[u8]>>(
+    texts: impl IntoIterator<Item = T>,
+    alphabet: &Alphabet,
+    half_byte_compress: bool,
+    sentinel_order: &SentinelOrder,
+) -> Bwt {
+    assert!(
+        !half_byte_compress || alphabet.num_dense_symbols() <= 16,
+        "half-byte compression is only possible for alphabets with at most 16 dense symbols"
+    );
+
+    let texts: Vec<_> = texts.into_iter().collect();
+
+    // the texts are concatenated in the order of their sentinels, such that the sentinel order can be realized by
+    // a generalized suffix array, in which the sentinels are unique and ordered by their positions
+    let text_order = match sentinel_order {
+        SentinelOrder::ByFollowingTexts | SentinelOrder::ByTextId => (0..texts.len()).collect(),
+        SentinelOrder::ByRank(ranks) => text_order_from_ranks(ranks, texts.len()),
+    };
+
+    let reordered_texts: Vec<&[u8]> = text_order
+        .iter()
+        .map(|&text_id| texts[text_id].as_ref())
+        .collect();
+
+    let allocator = &BufferAllocator::global();
+
+    let (mut text, mut frequency_table, _) = construction::create_concatenated_densely_encoded_text(
+        &reordered_texts,
+        alphabet,
+        allocator,
+    );
+
+    assert!(text.len() <= <usize as NumCast>::from(I::max_value()).unwrap());
+
+    let suffix_array_data = if *sentinel_order == SentinelOrder::ByFollowingTexts {
+        I::construct_libsais_suffix_array(&text, &mut frequency_table, false, allocator)
+    } else {
+        construct_generalized_suffix_array::<I>(&reordered_texts, text.len(), alphabet, allocator)
+    };
+    let suffix_array: &[I::LibsaisOutput] = bytemuck::cast_slice(&suffix_array_data);
+
+    let mut bwt_buffer = LargeBuffer::new_zeroed(0, allocator);
+
+    let (bwt, text_border_lookup, len) = construction::bwt::bwt_from_suffix_array::<_, I>(
+        suffix_array,
+        &mut text,
+        &mut bwt_buffer,
+        half_byte_compress,
+    );
+
+    // the suffix array values of the sentinel rows are the starts of the texts. they are translated from the
+    // reordered concatenation to the concatenation in the original order
+    let text_starts = |text_ids: &mut dyn Iterator<Item = usize>| -> Vec<usize> {
+        text_ids
+            .scan(0, |start, text_id| {
+                let text_start = *start;
+                *start += texts[text_id].as_ref().len() + 1;
+                Some(text_start)
+            })
+            .collect()
+    };
+
+    let original_text_starts = text_starts(&mut (0..texts.len()));
+    let reordered_to_original_text_start: HashMap<_, _> =
+        text_starts(&mut text_order.iter().copied())
+            .into_iter()
+            .zip(&text_order)
+            .map(|(reordered_start, &text_id)| (reordered_start, original_text_starts[text_id]))
+            .collect();
+
+    let sentinel_suffix_array_values = text_border_lookup
+        .into_iter()
+        .map(|(row, value)| {
+            let value = <usize as NumCast>::from(value).unwrap();
+            (row, reordered_to_original_text_start[&value])
+        })
+        .collect();
+
+    Bwt {
+        data: bwt.to_vec(),
+        len,
+        is_half_byte_compressed: half_byte_compress,
+        sentinel_suffix_array_values,
+    }
+}
+
+// libsais doesn't support empty texts in the generalized suffix array. Therefore, it is constructed for the
+// non-empty texts and the positions of the sentinels of the empty texts are inserted afterwards. This is simple,
+// because the suffixes that start with a sentinel occupy the first rows in the order of the sentinels.
+fn construct_generalized_suffix_array<I: IndexStorage>(
+    texts: &[&[u8]],
+    total_text_len: usize,
+    alphabet: &Alphabet,
+    allocator: &BufferAllocator,
+) -> LargeBuffer<u32> {
+    let non_empty_text_ids: Vec<_> = (0..texts.len())
+        .filter(|&text_id| !texts[text_id].is_empty())
+        .collect();
+
+    let non_empty_suffix_array_data = (!non_empty_text_ids.is_empty()).then(|| {
+        let (non_empty_text, mut frequency_table, non_empty_sentinel_indices) =
+            construction::create_concatenated_densely_encoded_text(
+                non_empty_text_ids.iter().map(|&text_id| texts[text_id]),
+                alphabet,
+                allocator,
+            );
+
+        let suffix_array_data = I::construct_libsais_suffix_array(
+            &non_empty_text,
+            &mut frequency_table,
+            true,
+            allocator,
+        );
+
+        (suffix_array_data, non_empty_sentinel_indices)
+    });
+
+    if non_empty_text_ids.len() == texts.len() {
+        return non_empty_suffix_array_data.unwrap().0;
+    }
+
+    let mut suffix_array_data = LargeBuffer::new_zeroed(
+        total_text_len * size_of::<I::LibsaisOutput>() / size_of::<u32>(),
+        allocator,
+    );
+    let suffix_array: &mut [I::LibsaisOutput] = bytemuck::cast_slice_mut(&mut suffix_array_data);
+
+    let mut text_starts = Vec::with_capacity(texts.len());
+    let mut text_start = 0;
+
+    for (row, text) in texts.iter().enumerate() {
+        text_starts.push(text_start);
+        text_start += text.len() + 1;
+        suffix_array[row] = <I::LibsaisOutput as NumCast>::from(text_start - 1).unwrap();
+    }
+
+    let Some((non_empty_suffix_array_data, non_empty_sentinel_indices)) =
+        non_empty_suffix_array_data
+    else {
+        return suffix_array_data;
+    };
+
+    let non_empty_suffix_array: &[I::LibsaisOutput] =
+        bytemuck::cast_slice(&non_empty_suffix_array_data);
+
+    for (target, &value) in suffix_array[texts.len()..]
+        .iter_mut()
+        .zip(&non_empty_suffix_array[non_empty_text_ids.len()..])
+    {
+        let value = <usize as NumCast>::from(value).unwrap();
+        let non_empty_id = non_empty_sentinel_indices.partition_point(|&sentinel| sentinel < value);
+        let non_empty_text_start = non_empty_id
+            .checked_sub(1)
+            .map_or(0, |previous_id| non_empty_sentinel_indices[previous_id] + 1);
+
+        *target = <I::LibsaisOutput as NumCast>::from(
+            text_starts[non_empty_text_ids[non_empty_id]] + value - non_empty_text_start,
+        )
+        .unwrap();
+    }
+
+    suffix_array_data
+}
+
+fn text_order_from_ranks(ranks: &[usize], num_texts: usize) -> Vec<usize> {
+    assert_eq!(
+        ranks.len(),
+        num_texts,
+        "the number of sentinel ranks must be equal to the number of texts"
+    );
+
+    let mut text_order = vec![usize::MAX; num_texts];
+
+    for (text_id, &rank) in ranks.iter().enumerate() {
+        assert!(
+            rank < num_texts && text_order[rank] == usize::MAX,
+            "the sentinel ranks must be a permutation of 0..num_texts"
+        );
+
+        text_order[rank] = text_id;
+    }
+
+    text_order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet;
+
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn compressed_and_uncompressed_agree(
+            texts in prop::collection::vec(
+                prop::collection::vec((0usize..4).prop_map(|i| b"ACGT"[i]), 0..200),
+                1..4
+            ),
+        ) {
+            let alphabet = alphabet::ascii_dna();
+
+            let bwt = construct_bwt::<i32, _>(&texts, &alphabet, false);
+            let compressed_bwt = construct_bwt::<u32, _>(&texts, &alphabet, true);
+
+            let total_len = texts.iter().map(|t| t.len() + 1).sum::<usize>();
+
+            prop_assert_eq!(bwt.len(), total_len);
+            prop_assert_eq!(bwt.as_bytes().len(), total_len);
+            prop_assert!(compressed_bwt.is_half_byte_compressed());
+            prop_assert_eq!(bwt.iter().collect::<Vec<_>>(), compressed_bwt.iter().collect::<Vec<_>>());
+            prop_assert_eq!(bwt.sentinel_suffix_array_values(), compressed_bwt.sentinel_suffix_array_values());
+            prop_assert_eq!(bwt.sentinel_suffix_array_values().len(), texts.len());
+
+            for &row in bwt.sentinel_suffix_array_values().keys() {
+                prop_assert_eq!(bwt.symbol_at(row), 0);
+            }
+        }
+
+        #[test]
+        fn sentinel_orders_agree_with_naive(
+            texts in prop::collection::vec(
+                prop::collection::vec((0usize..4).prop_map(|i| b"ACGT"[i]), 0..30),
+                1..6
+            ),
+            seed in any::<u64>(),
+        ) {
+            let alphabet = alphabet::ascii_dna();
+            let num_texts = texts.len();
+
+            let mut ranks: Vec<_> = (0..num_texts).collect();
+            ranks.sort_by_key(|&rank| (rank as u64 + 1).wrapping_mul(seed) % 1000003);
+
+            for (sentinel_order, sentinel_ranks) in [
+                (SentinelOrder::ByTextId, (0..num_texts).collect()),
+                (SentinelOrder::ByRank(ranks.clone()), ranks),
+            ] {
+                // every suffix is followed by the sentinel of its text, which is unique
+                let mut suffixes = Vec::new();
+                let mut text_start = 0;
+
+                for (text_id, text) in texts.iter().enumerate() {
+                    let dense_text: Vec<_> = text.iter().map(|&s| alphabet.io_to_dense_representation(s)).collect();
+
+                    for position in 0..=text.len() {
+                        let mut key: Vec<_> = dense_text[position..].iter().map(|&s| (1, s as usize)).collect();
+                        key.push((0, sentinel_ranks[text_id]));
+
+                        let preceding_symbol = if position == 0 { 0 } else { dense_text[position - 1] };
+                        suffixes.push((key, preceding_symbol, text_start + position));
+                    }
+
+                    text_start += text.len() + 1;
+                }
+
+                suffixes.sort();
+
+                let bwt = construct_bwt_with_sentinel_order::<i32, _>(&texts, &alphabet, false, &sentinel_order);
+
+                let expected_bwt: Vec<_> = suffixes.iter().map(|&(_, symbol, _)| symbol).collect();
+                let expected_sentinel_values: BTreeMap<_, _> = suffixes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, symbol, _))| *symbol == 0)
+                    .map(|(row, &(_, _, value))| (row, value))
+                    .collect();
+
+                prop_assert_eq!(bwt.iter().collect::<Vec<_>>(), expected_bwt);
+                prop_assert_eq!(bwt.sentinel_suffix_array_values(), &expected_sentinel_values);
+            }
+        }
+    }
+
+    #[test]
+    fn default_order_is_unchanged() {
+        let alphabet = alphabet::ascii_dna();
+        let texts = [b"ACGT".as_slice(), b"AC", b"GTA"];
+
+        assert_eq!(
+            construct_bwt::<i32, _>(texts, &alphabet, false),
+            construct_bwt_with_sentinel_order::<i32, _>(
+                texts,
+                &alphabet,
+                false,
+                &SentinelOrder::default()
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "permutation")]
+    fn invalid_ranks() {
+        construct_bwt_with_sentinel_order::<i32, _>(
+            [b"AC".as_slice(), b"GT"],
+            &alphabet::ascii_dna(),
+            false,
+            &SentinelOrder::ByRank(vec![1, 1]),
+        );
+    }
+}
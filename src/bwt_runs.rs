@@ -0,0 +1,228 @@
+use rayon::prelude::*;
+use std::io;
+
+use crate::{
+    construction::slice_compression::SliceCompression, memory_region::MemoryRegion,
+    text_with_rank_support::TextBlocks,
+};
+
+const MIN_ROWS_PER_THREAD: usize = 1 << 16;
+const WORDS_PER_CHUNK: usize = 1 << 10;
+
+/// Information about the runs of equal symbols in the BWT of an index, collected during its construction.
+///
+/// The number of runs `r` is a measure of the repetitiveness of the indexed texts. Run-length compressed
+/// indices like the r-index need space proportional to `r` instead of the total length of the texts.
+/// The sentinels of the texts are part of the BWT and also form runs.
+///
+/// By default, only the number of runs is stored. When enabled using
+/// [`FmIndexConfig::record_bwt_run_boundaries`](crate::FmIndexConfig::record_bwt_run_boundaries),
+/// the rows of the BWT where runs start are also stored, using one bit per row.
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BwtRuns {
+    bwt_len: usize,
+    num_runs: usize,
+    // bit i is set if a run starts in row i
+    run_start_bits: Option<Vec<u64>>,
+}
+
+impl BwtRuns {
+    /// The number of runs `r`.
+    pub fn num_runs(&self) -> usize {
+        self.num_runs
+    }
+
+    /// The length of the BWT, which is the total length of the texts plus the number of texts.
+    pub fn bwt_len(&self) -> usize {
+        self.bwt_len
+    }
+
+    /// The length of the BWT divided by the number of runs. Larger values indicate more repetitive texts.
+    pub fn average_run_length(&self) -> f64 {
+        if self.num_runs == 0 {
+            0.0
+        } else {
+            self.bwt_len as f64 / self.num_runs as f64
+        }
+    }
+
+    pub fn has_run_boundaries(&self) -> bool {
+        self.run_start_bits.is_some()
+    }
+
+    /// Returns whether a run starts at `row` of the BWT, or `None` if the boundaries were not recorded.
+    pub fn is_run_start(&self, row: usize) -> Option<bool> {
+        assert!(row < self.bwt_len);
+
+        self.run_start_bits
+            .as_ref()
+            .map(|bits| (bits[row / 64] >> (row % 64)) & 1 == 1)
+    }
+
+    /// Returns the rows of the BWT where runs start in ascending order, or `None` if the boundaries were not recorded.
+    pub fn run_starts(&self) -> Option<impl Iterator<Item = usize> + '_> {
+        self.run_start_bits.as_ref().map(|bits| {
+            bits.iter().enumerate().flat_map(|(word_idx, &word)| {
+                let mut remaining = word;
+
+                std::iter::from_fn(move || {
+                    if remaining == 0 {
+                        return None;
+                    }
+
+                    let bit_idx = remaining.trailing_zeros() as usize;
+                    remaining &= remaining - 1;
+
+                    Some(word_idx * 64 + bit_idx)
+                })
+            })
+        })
+    }
+
+    pub(crate) fn memory_region(&self) -> Option<MemoryRegion> {
+        self.run_start_bits
+            .as_ref()
+            .map(|bits| MemoryRegion::of_slice(bits))
+    }
+}
+
+pub(crate) fn collect_bwt_runs<S: SliceCompression>(
+    bwt: &[u8],
+    bwt_len: usize,
+    record_boundaries: bool,
+) -> BwtRuns {
+    let is_run_start = |row: usize| row == 0 || S::get(row, bwt) != S::get(row - 1, bwt);
+
+    if !record_boundaries {
+        let num_runs = (0..bwt_len)
+            .into_par_iter()
+            .with_min_len(MIN_ROWS_PER_THREAD)
+            .filter(|&row| is_run_start(row))
+            .count();
+
+        return BwtRuns {
+            bwt_len,
+            num_runs,
+            run_start_bits: None,
+        };
+    }
+
+    let mut run_start_bits = vec![0u64; bwt_len.div_ceil(64)];
+
+    run_start_bits
+        .par_chunks_mut(WORDS_PER_CHUNK)
+        .enumerate()
+        .for_each(|(chunk_idx, words)| {
+            let first_row = chunk_idx * WORDS_PER_CHUNK * 64;
+            let end_row = (first_row + words.len() * 64).min(bwt_len);
+
+            for row in first_row..end_row {
+                if is_run_start(row) {
+                    words[(row - first_row) / 64] |= 1 << (row % 64);
+                }
+            }
+        });
+
+    let num_runs = run_start_bits
+        .iter()
+        .map(|word| word.count_ones() as usize)
+        .sum();
+
+    BwtRuns {
+        bwt_len,
+        num_runs,
+        run_start_bits: Some(run_start_bits),
+    }
+}
+
+// like collect_bwt_runs, but for a BWT that is read block by block
+pub(crate) fn collect_bwt_runs_from_text_blocks(
+    bwt: &impl TextBlocks,
+    record_boundaries: bool,
+) -> io::Result<BwtRuns> {
+    let bwt_len = bwt.len();
+    let mut run_start_bits = record_boundaries.then(|| vec![0u64; bwt_len.div_ceil(64)]);
+    let mut num_runs = 0;
+    let mut prev_symbol = None;
+
+    bwt.try_for_each_block(bwt.max_block_len(), |start, block| {
+        for (idx, &symbol) in block.iter().enumerate() {
+            if prev_symbol != Some(symbol) {
+                num_runs += 1;
+
+                if let Some(bits) = &mut run_start_bits {
+                    let row = start + idx;
+                    bits[row / 64] |= 1 << (row % 64);
+                }
+            }
+
+            prev_symbol = Some(symbol);
+        }
+    })?;
+
+    Ok(BwtRuns {
+        bwt_len,
+        num_runs,
+        run_start_bits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construction::slice_compression::{
+        HalfBytesCompression, NoSliceCompression, half_byte_compress_text,
+    };
+    use crate::text_with_rank_support::SliceTextBlocks;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn runs_agree_with_naive(
+            bwt in prop::collection::vec(0u8..4, 0..3000),
+            record_boundaries in any::<bool>(),
+        ) {
+            let expected_run_starts: Vec<_> = (0..bwt.len())
+                .filter(|&row| row == 0 || bwt[row] != bwt[row - 1])
+                .collect();
+
+            let runs = collect_bwt_runs::<NoSliceCompression>(&bwt, bwt.len(), record_boundaries);
+
+            let mut compressed = bwt.clone();
+            if compressed.len() % 2 == 1 {
+                compressed.push(1);
+            }
+            half_byte_compress_text(&mut compressed);
+            let compressed_len = compressed.len() / 2;
+
+            let runs_compressed = collect_bwt_runs::<HalfBytesCompression>(
+                &compressed[..compressed_len],
+                bwt.len(),
+                record_boundaries,
+            );
+
+            prop_assert_eq!(&runs, &runs_compressed);
+
+            let text_blocks = SliceTextBlocks { text: &bwt, max_block_len: 100 };
+            let runs_from_blocks = collect_bwt_runs_from_text_blocks(&text_blocks, record_boundaries).unwrap();
+            prop_assert_eq!(&runs, &runs_from_blocks);
+            prop_assert_eq!(runs.num_runs(), expected_run_starts.len());
+            prop_assert_eq!(runs.has_run_boundaries(), record_boundaries);
+
+            if record_boundaries {
+                let run_starts: Vec<_> = runs.run_starts().unwrap().collect();
+                prop_assert_eq!(run_starts, expected_run_starts);
+
+                for row in 0..bwt.len() {
+                    prop_assert_eq!(
+                        runs.is_run_start(row),
+                        Some(row == 0 || bwt[row] != bwt[row - 1])
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,402 @@
+use num_traits::{Bounded, NumCast};
+
+use crate::{
+    Alphabet, EmptyTextPolicy, FmIndexConfig, IndexStorage, construction,
+    text_with_rank_support::{
+        Block64, Block512, CondensedTextWithRankSupport, FlatTextWithRankSupport,
+        PackedTextWithRankSupport, TextWithRankSupport, WaveletTreeTextWithRankSupport,
+    },
+};
+
+/// A report about texts that should be indexed, created by [`FmIndexConfig::check_inputs`] before the construction.
+///
+/// It can be used to find out whether the texts fit into the index storage type of the configuration, and to
+/// choose the variant of the index based on the expected memory usage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputCheck {
+    pub num_texts: usize,
+    /// The sum of the lengths of the texts, including one sentinel per text.
+    pub total_text_len: usize,
+    /// The maximum total text length that is supported by the index storage type of the configuration.
+    pub max_total_text_len: usize,
+    /// The smallest [`IndexStorage`] type that supports the texts, for example `"u32"`. It is `None` if the
+    /// texts are too large for all of them.
+    pub recommended_index_storage: Option<&'static str>,
+    /// The estimated sizes of the supported variants of the index (see [`AnyFmIndex`](crate::AnyFmIndex)),
+    /// using the suffix array sampling rate and lookup table depth of the configuration. Variants with index storage
+    /// types that don't support the texts are included, too.
+    pub size_estimates: Vec<IndexSizeEstimate>,
+    /// The ids of the empty texts, if they are rejected by the [`EmptyTextPolicy`] of the configuration. The
+    /// construction panics if this is not empty.
+    pub rejected_text_ids: Vec<usize>,
+}
+
+impl InputCheck {
+    /// Returns `true` if the texts can be indexed using the index storage type of the configuration.
+    pub fn fits(&self) -> bool {
+        self.total_text_len <= self.max_total_text_len
+    }
+}
+
+/// The estimated size of a variant of the index, as part of an [`InputCheck`].
+///
+/// The estimate includes the text with rank support, the sampled suffix array, the lookup table and the text
+/// borders. It does not include the memory needed during the construction, which is usually a lot larger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexSizeEstimate {
+    /// For example `"u32"`.
+    pub index_storage: &'static str,
+    /// For example `"CondensedTextWithRankSupport"`.
+    pub text_with_rank_support: &'static str,
+    /// For example `"Block64"`.
+    pub block: &'static str,
+    /// Whether the index storage type supports the total length of the texts.
+    pub fits: bool,
+    pub num_bytes: usize,
+}
+
+pub(crate) fn check_inputs<I: IndexStorage, R: TextWithRankSupport<I>, T: AsRef<[u8]>>(
+    config: &FmIndexConfig<I, R>,
+    texts: impl IntoIterator<Item = T>,
+    alphabet: &Alphabet,
+) -> InputCheck {
+    let mut num_texts = 0;
+    let mut total_text_len = 0;
+    let mut rejected_text_ids = Vec::new();
+
+    for text in texts {
+        if config.empty_text_policy == EmptyTextPolicy::Reject && text.as_ref().is_empty() {
+            rejected_text_ids.push(num_texts);
+        }
+
+        num_texts += 1;
+        total_text_len += text.as_ref().len() + 1;
+    }
+
+    let recommended_index_storage = if total_text_len <= max_total_text_len::<i32>() {
+        Some(i32::NAME)
+    } else if total_text_len <= max_total_text_len::<u32>() {
+        Some(u32::NAME)
+    } else if total_text_len <= max_total_text_len::<i64>() {
+        Some(i64::NAME)
+    } else {
+        None
+    };
+
+    let params = SizeParams::new(config, num_texts, total_text_len, alphabet);
+
+    let mut size_estimates = Vec::new();
+    params.add_size_estimates::<i32>(&mut size_estimates);
+    params.add_size_estimates::<u32>(&mut size_estimates);
+    params.add_size_estimates::<i64>(&mut size_estimates);
+
+    InputCheck {
+        num_texts,
+        total_text_len,
+        max_total_text_len: max_total_text_len::<I>(),
+        recommended_index_storage,
+        size_estimates,
+        rejected_text_ids,
+    }
+}
+
+// the per-text components are not included, because the number of texts is unknown
+pub(crate) fn estimate_index_memory<I: IndexStorage, R: TextWithRankSupport<I>>(
+    config: &FmIndexConfig<I, R>,
+    total_text_len: usize,
+    alphabet: &Alphabet,
+) -> usize {
+    SizeParams::new(config, 0, total_text_len, alphabet).index_size::<I, R>()
+}
+
+// The construction keeps the concatenated text (which might also contain the half-byte compressed BWT) alive
+// until the text with rank support is constructed. The peak is either reached while the suffix array is sampled,
+// when the full suffix array and the copy of the samples exist, or while the text with rank support is constructed.
+// The lookup tables are filled after the temporary buffers are freed.
+pub(crate) fn estimate_construction_memory<I: IndexStorage, R: TextWithRankSupport<I>>(
+    config: &FmIndexConfig<I, R>,
+    total_text_len: usize,
+    alphabet: &Alphabet,
+) -> usize {
+    let params = SizeParams::new(config, 0, total_text_len, alphabet);
+
+    // one extra byte to allow padding the text to an even length for the slice compression
+    let text_size = total_text_len + 1;
+    let bwt_size = if construction::use_slice_compression(config, alphabet) {
+        0
+    } else {
+        total_text_len
+    };
+    let suffix_array_size =
+        total_text_len * I::suffix_array_construction_bytes_per_symbol(config.performance_priority);
+
+    let sampling_peak =
+        text_size + bwt_size + suffix_array_size + params.sampled_suffix_array_size::<I>();
+    let rank_support_peak = text_size
+        + bwt_size
+        + params.sampled_suffix_array_size::<I>()
+        + params.text_with_rank_support_size::<I, R>();
+
+    sampling_peak
+        .max(rank_support_peak)
+        .max(params.index_size::<I, R>())
+}
+
+fn max_total_text_len<I: IndexStorage>() -> usize {
+    <usize as NumCast>::from(<I as Bounded>::max_value()).unwrap_or(usize::MAX)
+}
+
+struct SizeParams {
+    num_texts: usize,
+    total_text_len: usize,
+    alphabet_size: usize,
+    num_searchable_symbols: usize,
+    suffix_array_sampling_rate: usize,
+    lookup_table_depth: usize,
+}
+
+impl SizeParams {
+    fn new<I, R>(
+        config: &FmIndexConfig<I, R>,
+        num_texts: usize,
+        total_text_len: usize,
+        alphabet: &Alphabet,
+    ) -> Self {
+        Self {
+            num_texts,
+            total_text_len,
+            alphabet_size: alphabet.num_dense_symbols(),
+            num_searchable_symbols: alphabet.num_searchable_dense_symbols(),
+            suffix_array_sampling_rate: config.suffix_array_sampling_rate,
+            lookup_table_depth: config.lookup_table_depth,
+        }
+    }
+
+    fn add_size_estimates<I: IndexStorage>(&self, size_estimates: &mut Vec<IndexSizeEstimate>) {
+        size_estimates.push(self.estimate::<I, CondensedTextWithRankSupport<I, Block64>>());
+        size_estimates.push(self.estimate::<I, CondensedTextWithRankSupport<I, Block512>>());
+        size_estimates.push(self.estimate::<I, FlatTextWithRankSupport<I, Block64>>());
+        size_estimates.push(self.estimate::<I, FlatTextWithRankSupport<I, Block512>>());
+
+        // the packed implementation only supports small alphabets
+        if self.alphabet_size <= 16 {
+            size_estimates.push(self.estimate::<I, PackedTextWithRankSupport<I>>());
+        }
+
+        // this is an upper bound, because the size of the wavelet tree depends on the symbol frequencies
+        size_estimates.push(self.estimate::<I, WaveletTreeTextWithRankSupport<I>>());
+    }
+
+    fn estimate<I: IndexStorage, R: TextWithRankSupport<I>>(&self) -> IndexSizeEstimate {
+        IndexSizeEstimate {
+            index_storage: I::NAME,
+            text_with_rank_support: R::NAME,
+            block: R::BLOCK_NAME,
+            fits: self.total_text_len <= max_total_text_len::<I>(),
+            num_bytes: self.index_size::<I, R>(),
+        }
+    }
+
+    fn index_size<I: IndexStorage, R: TextWithRankSupport<I>>(&self) -> usize {
+        let num_lookup_table_entries: usize = (0..=self.lookup_table_depth)
+            .map(|depth| self.num_searchable_symbols.saturating_pow(depth as u32))
+            .sum();
+        let lookup_table_size = num_lookup_table_entries * 2 * size_of::<I>();
+
+        let text_borders_size = self.num_texts * size_of::<usize>();
+
+        self.text_with_rank_support_size::<I, R>()
+            + self.sampled_suffix_array_size::<I>()
+            + lookup_table_size
+            + text_borders_size
+    }
+
+    fn text_with_rank_support_size<I: IndexStorage, R: TextWithRankSupport<I>>(&self) -> usize {
+        R::estimated_size_in_bytes(self.total_text_len, self.alphabet_size)
+    }
+
+    fn sampled_suffix_array_size<I: IndexStorage>(&self) -> usize {
+        self.total_text_len
+            .div_ceil(self.suffix_array_sampling_rate)
+            * size_of::<I>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BufferAllocator, FmIndex, PerformancePriority, alphabet};
+    use std::alloc::Layout;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn actual_size<I: IndexStorage, R: TextWithRankSupport<I>>(index: &FmIndex<I, R>) -> usize {
+        index
+            .memory_regions()
+            .iter()
+            .map(|region| region.len())
+            .sum()
+    }
+
+    #[test]
+    fn estimates_match_constructed_indices() {
+        let texts = [
+            b"ACGTACGTAC".repeat(5000),
+            b"TTGCA".repeat(3000),
+            b"GATTACA".to_vec(),
+        ];
+
+        let config = FmIndexConfig::<i32>::new().suffix_array_sampling_rate(3);
+        let check = config.check_inputs(&texts, &alphabet::ascii_dna());
+
+        assert_eq!(check.num_texts, 3);
+        assert_eq!(check.total_text_len, 50000 + 15000 + 7 + 3);
+        assert!(check.fits());
+        assert_eq!(check.recommended_index_storage, Some("i32"));
+        assert_eq!(check.size_estimates.len(), 18);
+
+        let condensed = config.construct_index(&texts, alphabet::ascii_dna());
+        let flat = FmIndexConfig::<i64, FlatTextWithRankSupport<i64, Block512>>::new()
+            .suffix_array_sampling_rate(3)
+            .construct_index(&texts, alphabet::ascii_dna());
+        let packed = FmIndexConfig::<u32, PackedTextWithRankSupport<u32>>::new()
+            .suffix_array_sampling_rate(3)
+            .construct_index(&texts, alphabet::ascii_dna());
+
+        let estimate = |index_storage, text_with_rank_support, block| {
+            check
+                .size_estimates
+                .iter()
+                .find(|estimate| {
+                    (
+                        estimate.index_storage,
+                        estimate.text_with_rank_support,
+                        estimate.block,
+                    ) == (index_storage, text_with_rank_support, block)
+                })
+                .unwrap()
+                .num_bytes
+        };
+
+        // the memory regions don't contain the lookup table and text borders, which are tiny here
+        for (estimated, actual) in [
+            (
+                estimate("i32", "CondensedTextWithRankSupport", "Block64"),
+                actual_size(&condensed),
+            ),
+            (
+                estimate("i64", "FlatTextWithRankSupport", "Block512"),
+                actual_size(&flat),
+            ),
+            (
+                estimate("u32", "PackedTextWithRankSupport", "none"),
+                actual_size(&packed),
+            ),
+        ] {
+            assert!(
+                estimated.abs_diff(actual) * 100 <= actual,
+                "{estimated} vs {actual}"
+            );
+        }
+    }
+
+    static LIVE_BUFFER_BYTES: AtomicUsize = AtomicUsize::new(0);
+    static PEAK_BUFFER_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe fn measuring_alloc(layout: Layout) -> *mut u8 {
+        let live = LIVE_BUFFER_BYTES.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK_BUFFER_BYTES.fetch_max(live, Ordering::SeqCst);
+        unsafe { std::alloc::alloc(layout) }
+    }
+
+    unsafe fn measuring_dealloc(ptr: *mut u8, layout: Layout) {
+        LIVE_BUFFER_BYTES.fetch_sub(layout.size(), Ordering::SeqCst);
+        unsafe { std::alloc::dealloc(ptr, layout) }
+    }
+
+    fn check_construction_estimate<I: IndexStorage, R: TextWithRankSupport<I>>(
+        config: FmIndexConfig<I, R>,
+        texts: &[Vec<u8>],
+    ) {
+        let total_text_len = texts.iter().map(|text| text.len() + 1).sum();
+        let estimate = config.estimate_construction_memory(total_text_len, &alphabet::ascii_dna());
+        let index_estimate = config.estimate_index_memory(total_text_len, &alphabet::ascii_dna());
+
+        PEAK_BUFFER_BYTES.store(0, Ordering::SeqCst);
+        let allocator = unsafe { BufferAllocator::new(measuring_alloc, measuring_dealloc) };
+        let index = config
+            .buffer_allocator(allocator)
+            .construct_index(texts, alphabet::ascii_dna());
+
+        // the temporary buffers of the construction are allocated using the buffer allocator, while the
+        // data structures of the index are allocated using the global allocator
+        let peak_buffer_bytes = PEAK_BUFFER_BYTES.load(Ordering::SeqCst);
+        let heap_sizes = index.heap_size_bytes();
+        let index_size =
+            heap_sizes.text_with_rank_support + heap_sizes.suffix_array + heap_sizes.lookup_tables;
+
+        assert!(
+            index_estimate.abs_diff(index_size) * 100 <= index_size,
+            "{index_estimate} vs {index_size}"
+        );
+        assert!(
+            estimate >= peak_buffer_bytes,
+            "{estimate} vs {peak_buffer_bytes}"
+        );
+        assert!(estimate >= index_estimate);
+        assert!(
+            estimate <= peak_buffer_bytes + index_estimate,
+            "{estimate} vs {peak_buffer_bytes} + {index_estimate}"
+        );
+    }
+
+    #[test]
+    fn construction_estimates_bound_temporary_buffers() {
+        let texts = [b"ACGTTGCAAC".repeat(4000), b"GATTACA".repeat(2000)];
+
+        check_construction_estimate(FmIndexConfig::<i32>::new(), &texts);
+        check_construction_estimate(
+            FmIndexConfig::<i32>::new()
+                .construction_performance_priority(PerformancePriority::HighSpeed)
+                .suffix_array_sampling_rate(2),
+            &texts,
+        );
+        check_construction_estimate(
+            FmIndexConfig::<u32, FlatTextWithRankSupport<u32, Block512>>::new()
+                .construction_performance_priority(PerformancePriority::LowMemory),
+            &texts,
+        );
+        check_construction_estimate(FmIndexConfig::<i64>::new().lookup_table_depth(5), &texts);
+    }
+
+    #[test]
+    fn rejected_empty_texts() {
+        let texts = [b"ACGT".as_slice(), b"", b"TT", b""];
+        let alphabet = alphabet::ascii_dna();
+
+        let keep = FmIndexConfig::<i32>::new().check_inputs(texts, &alphabet);
+        assert!(keep.rejected_text_ids.is_empty());
+
+        let reject = FmIndexConfig::<i32>::new()
+            .empty_text_policy(EmptyTextPolicy::Reject)
+            .check_inputs(texts, &alphabet);
+        assert_eq!(reject.rejected_text_ids, [1, 3]);
+        assert_eq!(reject.num_texts, 4);
+    }
+
+    #[test]
+    fn too_large_for_i32() {
+        let config = FmIndexConfig::<i32>::new();
+        let text = vec![b'A'; 1 << 20];
+        let texts = std::iter::repeat_n(&text, 1 << 11);
+        let check = config.check_inputs(texts, &alphabet::ascii_dna());
+
+        assert!(!check.fits());
+        assert_eq!(check.recommended_index_storage, Some("u32"));
+        assert!(
+            check
+                .size_estimates
+                .iter()
+                .all(|estimate| estimate.fits == (estimate.index_storage != "i32"))
+        );
+    }
+}
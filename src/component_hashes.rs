@@ -0,0 +1,167 @@
+use num_traits::NumCast;
+use rayon::prelude::*;
+
+use crate::{FmIndex, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+// the BWT is hashed in chunks of this many symbols in parallel. it must never change, because it influences the hashes.
+const CHUNK_LEN: usize = 1 << 20;
+
+/// Content hashes of the large components of an index, returned by [`FmIndex::component_hashes`].
+///
+/// They can be used as keys for artifacts that were derived from an index (e.g. precomputed seed tables),
+/// to detect when an artifact no longer matches the index. The hashes only depend on the logical content of the
+/// components, not on their memory layout. Therefore, they are equal on all platforms. They are not cryptographic
+/// hashes.
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ComponentHashes {
+    /// Hash of the text with rank support. It only depends on the BWT, so it is the same for all
+    /// implementations of [`TextWithRankSupport`] and block configurations.
+    pub text_with_rank_support: u64,
+    /// Hash of the sampled suffix array, including its sampling rate.
+    pub suffix_array: u64,
+}
+
+pub(crate) fn component_hashes<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+) -> ComponentHashes {
+    ComponentHashes {
+        text_with_rank_support: hash_text_with_rank_support(&index.text_with_rank_support),
+        suffix_array: hash_suffix_array(index),
+    }
+}
+
+fn hash_text_with_rank_support<I: IndexStorage, R: TextWithRankSupport<I>>(
+    text_with_rank_support: &R,
+) -> u64 {
+    let text_len = text_with_rank_support.text_len();
+
+    let chunk_hashes: Vec<_> = (0..text_len.div_ceil(CHUNK_LEN))
+        .into_par_iter()
+        .map(|chunk_idx| {
+            let start = chunk_idx * CHUNK_LEN;
+            let end = (start + CHUNK_LEN).min(text_len);
+            let mut hasher = StableHasher::new();
+
+            // eight symbols are packed into every hashed word
+            for word_start in (start..end).step_by(8) {
+                let mut word = 0;
+
+                for (i, idx) in (word_start..(word_start + 8).min(end)).enumerate() {
+                    word |= (text_with_rank_support.symbol_at(idx) as u64) << (i * 8);
+                }
+
+                hasher.write(word);
+            }
+
+            hasher.finish()
+        })
+        .collect();
+
+    let mut hasher = StableHasher::new();
+    hasher.write(text_len as u64);
+
+    for chunk_hash in chunk_hashes {
+        hasher.write(chunk_hash);
+    }
+
+    hasher.finish()
+}
+
+fn hash_suffix_array<I: IndexStorage, R: TextWithRankSupport<I>>(index: &FmIndex<I, R>) -> u64 {
+    let suffix_array = &index.suffix_array;
+    let mut hasher = StableHasher::new();
+
+    hasher.write(suffix_array.sampling_rate() as u64);
+
+    let samples = suffix_array.samples();
+    hasher.write(samples.len() as u64);
+
+    for &sample in samples {
+        hasher.write(<u64 as NumCast>::from(sample).unwrap());
+    }
+
+    for (row, value) in suffix_array.text_border_entries() {
+        hasher.write(row as u64);
+        hasher.write(value as u64);
+    }
+
+    hasher.finish()
+}
+
+// A simple multiplicative hash of u64 words, followed by the SplitMix64 finalizer to spread the bits
+// over the whole range of u64.
+struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    fn new() -> Self {
+        Self {
+            state: 0xcbf29ce484222325,
+        }
+    }
+
+    fn write(&mut self, word: u64) {
+        self.state = (self.state.rotate_left(5) ^ word).wrapping_mul(0x517cc1b727220a95);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = self.state;
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+        hash ^ (hash >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, FmIndexFlat512, FmIndexWaveletTree, alphabet};
+
+    #[test]
+    fn hashes_depend_on_content() {
+        let texts = [b"ACGTACGTTTGACA".as_slice(), b"GGATC"];
+        let config = FmIndexConfig::<i32>::new().suffix_array_sampling_rate(3);
+
+        let index = config.clone().construct_index(texts, alphabet::ascii_dna());
+        let hashes = index.component_hashes();
+
+        assert_eq!(
+            config
+                .clone()
+                .construct_index(texts, alphabet::ascii_dna())
+                .component_hashes(),
+            hashes
+        );
+
+        let flat_index: FmIndexFlat512<i32> = config
+            .clone()
+            .with_rank_support()
+            .construct_index(texts, alphabet::ascii_dna());
+        let wavelet_tree_index: FmIndexWaveletTree<i32> = config
+            .clone()
+            .with_rank_support()
+            .construct_index(texts, alphabet::ascii_dna());
+        assert_eq!(flat_index.component_hashes(), hashes);
+        assert_eq!(wavelet_tree_index.component_hashes(), hashes);
+
+        let resampled_index = FmIndexConfig::<i32>::new()
+            .suffix_array_sampling_rate(2)
+            .construct_index(texts, alphabet::ascii_dna());
+        let resampled_hashes = resampled_index.component_hashes();
+        assert_eq!(
+            resampled_hashes.text_with_rank_support,
+            hashes.text_with_rank_support
+        );
+        assert_ne!(resampled_hashes.suffix_array, hashes.suffix_array);
+
+        let other_index = config.construct_index([b"ACGTACGTTTGACA"], alphabet::ascii_dna());
+        let other_hashes = other_index.component_hashes();
+        assert_ne!(
+            other_hashes.text_with_rank_support,
+            hashes.text_with_rank_support
+        );
+        assert_ne!(other_hashes.suffix_array, hashes.suffix_array);
+    }
+}
@@ -1,15 +1,33 @@
 use crate::{
-    Alphabet, FmIndex, IndexStorage,
+    Alphabet, AutoRankSupportConfig, BidirectionalFmIndex, BufferAllocator, FmIndex, FmdIndex,
+    IndexStorage, InputCheck, MultiVolumeFmIndex, SemiExternalConstruction, capacity, fasta,
+    file_groups::FileGroups,
+    random::SplitMix64,
+    semi_external,
     text_with_rank_support::{Block64, CondensedTextWithRankSupport, TextWithRankSupport},
 };
-use std::marker::PhantomData;
+use std::{fs::File, io::BufReader, marker::PhantomData, path::Path, sync::Arc};
+
+type ProgressCallback = Arc<dyn Fn(ConstructionStage, f32) + Send + Sync>;
 
 /// A builder-like API to configure and construct the FM-Index.
-#[derive(Clone, Copy)]
+///
+/// The configuration is `Clone`, but not `Copy`, because it can own a progress callback and a thread pool (see
+/// [`Self::progress_callback`] and [`Self::thread_pool`]).
+#[derive(Clone)]
 pub struct FmIndexConfig<I, R = CondensedTextWithRankSupport<I, Block64>> {
     pub(crate) suffix_array_sampling_rate: usize,
     pub(crate) lookup_table_depth: usize,
     pub(crate) performance_priority: PerformancePriority,
+    pub(crate) slice_compression_mode: SliceCompressionMode,
+    pub(crate) record_bwt_run_boundaries: bool,
+    pub(crate) buffer_allocator: BufferAllocator,
+    pub(crate) empty_query_matches: EmptyQueryMatches,
+    pub(crate) empty_text_policy: EmptyTextPolicy,
+    pub(crate) max_volume_len: usize,
+    pub(crate) text_sampling: TextSampling,
+    pub(crate) progress_callback: Option<ProgressCallback>,
+    pub(crate) thread_pool: Option<Arc<rayon::ThreadPool>>,
     _index_storage_marker: PhantomData<I>,
     _block_marker: PhantomData<R>,
 }
@@ -57,15 +75,534 @@ impl<I: IndexStorage, R: TextWithRankSupport<I>> FmIndexConfig<I, R> {
         }
     }
 
+    /// See [`SliceCompressionMode`] for details. The default is [`Auto`](SliceCompressionMode::Auto).
+    ///
+    /// This allows overriding the decision that is otherwise derived from the [`PerformancePriority`],
+    /// without changing the other sub-algorithms of the construction.
+    pub fn slice_compression(self, slice_compression_mode: SliceCompressionMode) -> Self {
+        Self {
+            slice_compression_mode,
+            ..self
+        }
+    }
+
+    /// Store the rows of the BWT where runs of equal symbols start, using one additional bit per row.
+    /// The default is `false`.
+    ///
+    /// The number of runs is always available via [`FmIndex::bwt_runs`]. The boundaries are useful to
+    /// experiment with run-based suffix array sampling.
+    pub fn record_bwt_run_boundaries(self, record_bwt_run_boundaries: bool) -> Self {
+        Self {
+            record_bwt_run_boundaries,
+            ..self
+        }
+    }
+
+    /// See [`BufferAllocator`] for details. The default is the global allocator.
+    pub fn buffer_allocator(self, buffer_allocator: BufferAllocator) -> Self {
+        Self {
+            buffer_allocator,
+            ..self
+        }
+    }
+
+    /// Sets a function that is called to report the progress of the construction. By default, nothing is reported.
+    ///
+    /// The function is called with the current [`ConstructionStage`] and the fraction of the stage that is done,
+    /// at least at the start (`0.0`) and at the end (`1.0`) of every stage. Currently, only the filling of the lookup
+    /// tables also reports the progress in between. All other stages, including the construction of the suffix
+    /// array and the BWT, which usually take most of the time, only report their start and end. The stages are reported
+    /// in the order in which they are executed, and some stages are skipped by some construction methods. For
+    /// example, [`FmIndex::from_bwt`] doesn't construct the suffix array. Indices that consist of multiple FM-Indices,
+    /// such as a [`BidirectionalFmIndex`], report the stages for each of them.
+    ///
+    /// The function may capture state, like a progress bar. It is shared by clones of the configuration and called
+    /// from the threads of the construction, so it has to be `Send` and `Sync`.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use genedex::{ConstructionStage, FmIndexConfig, alphabet};
+    ///
+    /// let stages = Arc::new(Mutex::new(Vec::new()));
+    /// let recorded_stages = Arc::clone(&stages);
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .lookup_table_depth(3)
+    ///     .progress_callback(move |stage, fraction| {
+    ///         if fraction == 0.0 {
+    ///             recorded_stages.lock().unwrap().push(stage);
+    ///         }
+    ///     })
+    ///     .construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+    ///
+    /// assert_eq!(
+    ///     *stages.lock().unwrap(),
+    ///     [
+    ///         ConstructionStage::EncodeTexts,
+    ///         ConstructionStage::ConstructSuffixArray,
+    ///         ConstructionStage::ConstructBwt,
+    ///         ConstructionStage::SampleSuffixArray,
+    ///         ConstructionStage::ConstructRankSupport,
+    ///         ConstructionStage::FillLookupTables,
+    ///     ]
+    /// );
+    /// ```
+    pub fn progress_callback(
+        self,
+        progress_callback: impl Fn(ConstructionStage, f32) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            progress_callback: Some(Arc::new(progress_callback)),
+            ..self
+        }
+    }
+
+    /// Run the construction in a separate [`rayon`] thread pool with `num_threads` threads. By default, the global
+    /// thread pool of [`rayon`] is used.
+    ///
+    /// This bounds the parallelism of a single construction, for example when indices are constructed per request
+    /// in a server. The thread pool is created once by this function and shared by clones of the configuration, so
+    /// all constructions of a configuration run in the same thread pool. It is not used by the queries of the
+    /// constructed index. Use [`Self::thread_pool`] to share an existing thread pool.
+    pub fn num_threads(self, num_threads: usize) -> Self {
+        assert!(
+            num_threads > 0,
+            "the number of threads of the construction must be greater than 0"
+        );
+
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .expect("thread pool for the construction");
+
+        self.thread_pool(Arc::new(thread_pool))
+    }
+
+    /// Run the construction in the given [`rayon`] thread pool, see [`Self::num_threads`].
+    pub fn thread_pool(self, thread_pool: Arc<rayon::ThreadPool>) -> Self {
+        Self {
+            thread_pool: Some(thread_pool),
+            ..self
+        }
+    }
+
+    /// See [`EmptyQueryMatches`] for details. The default is [`AllPositions`](EmptyQueryMatches::AllPositions).
+    pub fn empty_query_matches(self, empty_query_matches: EmptyQueryMatches) -> Self {
+        Self {
+            empty_query_matches,
+            ..self
+        }
+    }
+
+    /// See [`EmptyTextPolicy`] for details. The default is [`Keep`](EmptyTextPolicy::Keep).
+    pub fn empty_text_policy(self, empty_text_policy: EmptyTextPolicy) -> Self {
+        Self {
+            empty_text_policy,
+            ..self
+        }
+    }
+
+    /// The maximum total length of the texts of a single volume of a [`MultiVolumeFmIndex`], including one
+    /// sentinel per text. The default and upper limit is the maximum value of the index storage type `I`.
+    ///
+    /// This only affects [`Self::construct_multi_volume_index`]. Smaller volumes need less memory during
+    /// the construction. Panics if `max_volume_len` is `0`.
+    pub fn max_volume_len(self, max_volume_len: usize) -> Self {
+        assert!(max_volume_len > 0, "max_volume_len must be greater than 0");
+
+        Self {
+            max_volume_len,
+            ..self
+        }
+    }
+
+    /// See [`TextSampling`] for details. The default is [`All`](TextSampling::All).
+    ///
+    /// This only affects [`Self::construct_index`], [`Self::construct_index_from_dense_texts`],
+    /// [`Self::construct_index_semi_external`], [`Self::construct_index_from_records`] and
+    /// [`Self::construct_index_from_fasta_files`]. Panics if the sampling parameter is `0`.
+    pub fn text_sampling(self, text_sampling: TextSampling) -> Self {
+        match text_sampling {
+            TextSampling::All => {}
+            TextSampling::EveryNth(n) => assert!(n > 0, "n must be greater than 0"),
+            TextSampling::Random { one_in, .. } => {
+                assert!(one_in > 0, "one_in must be greater than 0")
+            }
+        }
+
+        Self {
+            text_sampling,
+            ..self
+        }
+    }
+
+    /// Select the [`TextWithRankSupport`] implementation at runtime, based on the alphabet of the texts and `preference`.
+    ///
+    /// The resulting index is an [`AnyFmIndex`](crate::AnyFmIndex), such that applications do not need to be generic
+    /// over the implementation. With [`HighSpeed`](PerformancePriority::HighSpeed), the flat implementation is used
+    /// for small alphabets. With [`Balanced`](PerformancePriority::Balanced), the condensed implementation is used,
+    /// with larger blocks for alphabets with more than 16 dense symbols. With [`LowMemory`](PerformancePriority::LowMemory),
+    /// the condensed implementation with larger blocks is used for small alphabets and the wavelet tree implementation
+    /// for alphabets with more than 16 dense symbols.
+    ///
+    /// The preference only affects the selection of the implementation. The priority of the construction algorithm
+    /// is still configured using [`Self::construction_performance_priority`].
+    pub fn auto_rank_support(self, preference: PerformancePriority) -> AutoRankSupportConfig<I> {
+        AutoRankSupportConfig {
+            config: self.with_rank_support(),
+            preference,
+        }
+    }
+
+    /// Reports the total length of the texts, whether it is supported by the index storage type `I`, the estimated
+    /// sizes of the different variants of the index and the smallest suitable index storage type. It also reports
+    /// the empty texts that are rejected by the [`EmptyTextPolicy`] of the configuration.
+    ///
+    /// Only the lengths of the texts are inspected, so this is much faster than the construction. It can be used to
+    /// choose the configuration before committing to the construction, which panics if the texts are too large for `I`
+    /// or if a text is rejected.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let texts = [b"ACGTACGT".as_slice(), b"TTGCA"];
+    /// let config = FmIndexConfig::<i32>::new();
+    /// let check = config.check_inputs(texts, &alphabet::ascii_dna());
+    ///
+    /// assert_eq!(check.total_text_len, 15);
+    /// assert!(check.fits());
+    /// assert_eq!(check.recommended_index_storage, Some("i32"));
+    ///
+    /// let smallest = check.size_estimates.iter().min_by_key(|estimate| estimate.num_bytes).unwrap();
+    /// println!("{} with {} bytes", smallest.text_with_rank_support, smallest.num_bytes);
+    /// ```
+    pub fn check_inputs<T: AsRef<[u8]>>(
+        &self,
+        texts: impl IntoIterator<Item = T>,
+        alphabet: &Alphabet,
+    ) -> InputCheck {
+        capacity::check_inputs(self, texts, alphabet)
+    }
+
+    /// Returns an estimate of the peak memory usage of [`construct_index`](Self::construct_index) in bytes, for
+    /// texts with the given total length, including one sentinel per text (see [`InputCheck::total_text_len`]).
+    ///
+    /// The estimate depends on the index storage type, the text with rank support, the suffix array sampling rate,
+    /// the lookup table depth, the performance priority and the slice compression mode. It covers the buffers of the
+    /// construction, like the suffix array, and the data structures of the index, but not the texts that are passed
+    /// to the construction. Small data structures of which the size depends on the number of texts are not included.
+    ///
+    /// Panics in the same cases as the construction, e.g. if slice compression is forced for a large alphabet.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, PerformancePriority, alphabet};
+    ///
+    /// let genome_len = 3_300_000_000;
+    /// let config = || FmIndexConfig::<u32>::new().suffix_array_sampling_rate(4);
+    ///
+    /// let high_speed = config()
+    ///     .construction_performance_priority(PerformancePriority::HighSpeed)
+    ///     .estimate_construction_memory(genome_len, &alphabet::ascii_dna());
+    /// let low_memory = config()
+    ///     .construction_performance_priority(PerformancePriority::LowMemory)
+    ///     .estimate_construction_memory(genome_len, &alphabet::ascii_dna());
+    ///
+    /// assert!(low_memory < high_speed);
+    /// assert!(config().estimate_index_memory(genome_len, &alphabet::ascii_dna()) < low_memory);
+    /// ```
+    pub fn estimate_construction_memory(
+        &self,
+        total_text_len: usize,
+        alphabet: &Alphabet,
+    ) -> usize {
+        capacity::estimate_construction_memory(self, total_text_len, alphabet)
+    }
+
+    /// Returns an estimate of the size of the constructed index in bytes, for texts with the given total length,
+    /// including one sentinel per text.
+    ///
+    /// Like the [`IndexSizeEstimate`](crate::IndexSizeEstimate)s of [`check_inputs`](Self::check_inputs), this
+    /// includes the text with rank support, the sampled suffix array and the lookup tables. Small data structures of
+    /// which the size depends on the number of texts are not included.
+    pub fn estimate_index_memory(&self, total_text_len: usize, alphabet: &Alphabet) -> usize {
+        capacity::estimate_index_memory(self, total_text_len, alphabet)
+    }
+
     /// Construct the FM-Index.
     ///
-    /// The number of threads for the build procedure is controlled by [`rayon`].
+    /// The number of threads for the build procedure is controlled by [`rayon`], see also [`Self::num_threads`].
+    /// The constructed index, and therefore also the saved index file, does not depend on the number of threads.
+    ///
+    /// Panics if a text is empty and the [`EmptyTextPolicy`] of the configuration is
+    /// [`Reject`](EmptyTextPolicy::Reject). Use [`Self::check_inputs`] to find such texts before the construction.
     pub fn construct_index<T: AsRef<[u8]>>(
         self,
         texts: impl IntoIterator<Item = T>,
         alphabet: Alphabet,
     ) -> FmIndex<I, R> {
-        FmIndex::new(texts, alphabet, self)
+        let text_sampling = self.text_sampling;
+        let mut original_text_ids = Vec::new();
+        let texts = text_sampling.select(texts, &mut original_text_ids);
+
+        let mut index = FmIndex::new(texts, alphabet, self);
+        index.record_text_sampling(text_sampling, original_text_ids);
+
+        index
+    }
+
+    /// Construct the FM-Index from texts that are already in the dense representation of the alphabet.
+    ///
+    /// The symbols of the texts must be in the range `1..k`, where `k` is [`Alphabet::num_dense_symbols`], because
+    /// `0` is the sentinel. The translation of the texts from IO to dense representation is skipped, which saves
+    /// a pass over the texts when they are stored densely anyway. Otherwise, this is the same as
+    /// [`Self::construct_index`]. Panics if a symbol is outside of this range.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let alphabet = alphabet::ascii_dna();
+    /// let dense_text: Vec<_> = b"ACGTACGT"
+    ///     .iter()
+    ///     .map(|&symbol| alphabet.io_to_dense_representation(symbol))
+    ///     .collect();
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index_from_dense_texts([dense_text], alphabet);
+    ///
+    /// assert_eq!(index.count(b"CGT"), 2);
+    /// ```
+    pub fn construct_index_from_dense_texts<T: AsRef<[u8]>>(
+        self,
+        texts: impl IntoIterator<Item = T>,
+        alphabet: Alphabet,
+    ) -> FmIndex<I, R> {
+        let text_sampling = self.text_sampling;
+        let mut original_text_ids = Vec::new();
+        let texts = text_sampling.select(texts, &mut original_text_ids);
+
+        let mut index = FmIndex::new_from_dense_texts(texts, alphabet, self);
+        index.record_text_sampling(text_sampling, original_text_ids);
+
+        index
+    }
+
+    /// Construct the FM-Index without holding the suffix array of all texts in memory, for texts that are too
+    /// large for [`Self::construct_index`] on the available machine.
+    ///
+    /// The texts are pulled from the iterator in chunks and the BWTs of the chunks are merged in a temporary file,
+    /// see [`SemiExternalConstruction`] for details. The merging needs a pass over the temporary file per chunk,
+    /// so the running time grows with the number of chunks. The resulting index contains the same texts as the one
+    /// of [`Self::construct_index`], but the sentinels are ordered by text id (see
+    /// [`SentinelOrder::ByTextId`](crate::bwt::SentinelOrder::ByTextId)). Therefore, the suffix array order of
+    /// hits can differ for suffixes that are equal up to the ends of their texts.
+    ///
+    /// Settings of the configuration that only affect the suffix array construction are ignored. Returns an error
+    /// if the temporary files can't be written or read, and an error of kind
+    /// [`InvalidInput`](std::io::ErrorKind::InvalidInput) as soon as the total length of the texts (including one
+    /// sentinel per text) exceeds the maximum value of the index storage type. Panics for rejected empty texts,
+    /// like [`Self::construct_index`].
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, SemiExternalConstruction, alphabet};
+    ///
+    /// let texts = [b"ACGTACGT".as_slice(), b"TTACG", b"GGACGTT"];
+    /// let semi_external = SemiExternalConstruction::new(std::env::temp_dir(), 100);
+    /// assert_eq!(semi_external.max_chunk_len(), 8);
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_index_semi_external(texts, alphabet::ascii_dna(), &semi_external)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(index.count(b"ACG"), 4);
+    /// ```
+    pub fn construct_index_semi_external<T: AsRef<[u8]>>(
+        self,
+        texts: impl IntoIterator<Item = T>,
+        alphabet: Alphabet,
+        semi_external: &SemiExternalConstruction,
+    ) -> std::io::Result<FmIndex<I, R>> {
+        let text_sampling = self.text_sampling;
+        let mut original_text_ids = Vec::new();
+        let texts = text_sampling.select(texts, &mut original_text_ids);
+
+        let mut index =
+            semi_external::construct_index_semi_external(texts, alphabet, self, semi_external)?;
+        index.record_text_sampling(text_sampling, original_text_ids);
+
+        Ok(index)
+    }
+
+    /// Construct the FM-Index from named texts, such as the records of an assembly.
+    ///
+    /// The texts are numbered in the order of the records, and the names are stored inside the index. They can be
+    /// obtained using [`FmIndex::text_name`], e.g. to report hits with the names of the texts. Names don't have to
+    /// be unique. Empty texts are handled according to the [`EmptyTextPolicy`] of the configuration, and the
+    /// construction panics for rejected texts, like [`Self::construct_index`].
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let records = [("chr1", b"ACGTACGT".as_slice()), ("contig_7", b""), ("chr2", b"TTACG")];
+    /// let index = FmIndexConfig::<i32>::new().construct_index_from_records(records, alphabet::ascii_dna());
+    ///
+    /// let mut names: Vec<_> = index
+    ///     .locate(b"ACG")
+    ///     .map(|hit| index.text_name(hit.text_id).unwrap())
+    ///     .collect();
+    /// names.sort();
+    ///
+    /// assert_eq!(names, ["chr1", "chr1", "chr2"]);
+    /// assert_eq!(index.text_name(1), Some("contig_7"));
+    /// ```
+    pub fn construct_index_from_records<N: Into<String>, T: AsRef<[u8]>>(
+        self,
+        records: impl IntoIterator<Item = (N, T)>,
+        alphabet: Alphabet,
+    ) -> FmIndex<I, R> {
+        let text_sampling = self.text_sampling;
+        let mut names = Vec::new();
+        let mut original_text_ids = Vec::new();
+
+        let texts = text_sampling
+            .select(records, &mut original_text_ids)
+            .map(|(name, text)| {
+                names.push(name.into());
+                text
+            });
+
+        let mut index = FmIndex::new(texts, alphabet, self);
+        index.text_names = names;
+        index.record_text_sampling(text_sampling, original_text_ids);
+
+        index
+    }
+
+    /// Construct a [`MultiVolumeFmIndex`] of the texts, which consists of one or more FM-Indices (volumes).
+    ///
+    /// The texts are assigned to volumes in order, and a new volume is started when the total length of the
+    /// texts of the current volume (including one sentinel per text) would exceed the maximum that can be
+    /// represented by the index storage type `I` (see also [`Self::max_volume_len`]). This allows indexing
+    /// collections that are too large for [`Self::construct_index`] with a smaller index storage type.
+    /// Only the texts of a single volume are kept in memory at a time.
+    ///
+    /// Panics if a single text does not fit into a volume or if a text is rejected by the [`EmptyTextPolicy`].
+    pub fn construct_multi_volume_index<T: AsRef<[u8]>>(
+        self,
+        texts: impl IntoIterator<Item = T>,
+        alphabet: Alphabet,
+    ) -> MultiVolumeFmIndex<I, R> {
+        MultiVolumeFmIndex::new(texts, alphabet, self)
+    }
+
+    /// Construct a [`BidirectionalFmIndex`] of the texts, which consists of an FM-Index of the texts and
+    /// an FM-Index of the reversed texts.
+    ///
+    /// Both indices are constructed using this configuration. The texts are encoded only once, and the frequency
+    /// table of the symbols and the text borders are shared by both constructions, because they are the same for
+    /// the reversed texts. During the construction of the first index, the encoded reversed texts are kept in
+    /// memory, too. Use [`BidirectionalFmIndex::into_forward_and_reverse_indices`] to obtain the two indices
+    /// separately. Panics for rejected empty texts, like [`Self::construct_index`].
+    pub fn construct_bidirectional_index<T: AsRef<[u8]>>(
+        self,
+        texts: impl IntoIterator<Item = T>,
+        alphabet: Alphabet,
+    ) -> BidirectionalFmIndex<I, R> {
+        BidirectionalFmIndex::new(texts, alphabet, self)
+    }
+
+    /// Construct an [`FmdIndex`] of the texts and their reverse complements.
+    ///
+    /// Panics if the alphabet has no complement (see [`FmdIndex`] for details) or if a text is rejected by the
+    /// [`EmptyTextPolicy`]. Alphabets that only consist of nucleotides always have a complement.
+    pub fn construct_fmd_index<T: AsRef<[u8]>>(
+        self,
+        texts: impl IntoIterator<Item = T>,
+        alphabet: Alphabet,
+    ) -> FmdIndex<I, R> {
+        FmdIndex::new(texts, alphabet, self)
+    }
+
+    /// Construct the FM-Index from the records of one or more FASTA files.
+    ///
+    /// Every record of every file becomes a text of the index. The texts are numbered in the order of the
+    /// files and of the records inside the files. The index remembers which texts belong to which file,
+    /// such that hits can be resolved to files using [`FmIndex::file_id`], and results can be aggregated
+    /// per file, e.g. using [`FmIndex::count_per_file`]. This is useful when each file contains a
+    /// genome with multiple contigs.
+    ///
+    /// The first word of the header of every record is stored as the name of its text (see [`FmIndex::text_name`]).
+    /// Records with empty sequences are handled according to the [`EmptyTextPolicy`] of the configuration, and the
+    /// construction panics for rejected records.
+    /// All sequences are read into memory before the construction starts.
+    pub fn construct_index_from_fasta_files(
+        self,
+        filepaths: impl IntoIterator<Item = impl AsRef<Path>>,
+        alphabet: Alphabet,
+    ) -> std::io::Result<FmIndex<I, R>> {
+        let mut records = Vec::new();
+        let mut num_texts_per_file = Vec::new();
+
+        for filepath in filepaths {
+            let reader = BufReader::new(File::open(filepath)?);
+            let file_records = fasta::read_records(reader)?;
+
+            num_texts_per_file.push(file_records.len());
+            records.extend(file_records);
+        }
+
+        let mut index = self.construct_index_from_records(records, alphabet);
+
+        // the files keep their positions, but only the sampled texts are counted
+        if let Some(original_text_ids) = index.original_text_ids() {
+            let mut file_start = 0;
+
+            for num_texts in &mut num_texts_per_file {
+                let file_end = file_start + *num_texts;
+                *num_texts = original_text_ids.partition_point(|&id| id < file_end)
+                    - original_text_ids.partition_point(|&id| id < file_start);
+                file_start = file_end;
+            }
+        }
+
+        index.file_groups = FileGroups::from_num_texts_per_file(num_texts_per_file);
+
+        Ok(index)
+    }
+}
+
+impl<I, R> FmIndexConfig<I, R> {
+    pub(crate) fn with_rank_support<R2>(&self) -> FmIndexConfig<I, R2> {
+        FmIndexConfig {
+            suffix_array_sampling_rate: self.suffix_array_sampling_rate,
+            lookup_table_depth: self.lookup_table_depth,
+            performance_priority: self.performance_priority,
+            slice_compression_mode: self.slice_compression_mode,
+            record_bwt_run_boundaries: self.record_bwt_run_boundaries,
+            buffer_allocator: self.buffer_allocator.clone(),
+            empty_query_matches: self.empty_query_matches,
+            empty_text_policy: self.empty_text_policy,
+            max_volume_len: self.max_volume_len,
+            text_sampling: self.text_sampling,
+            progress_callback: self.progress_callback.clone(),
+            thread_pool: self.thread_pool.clone(),
+            _index_storage_marker: PhantomData,
+            _block_marker: PhantomData,
+        }
+    }
+
+    // The generic texts of the construction methods are not required to be Send, so the thread pool can only be
+    // entered by the parallel parts of the construction. Nested calls run directly, because they are already
+    // executed by a thread of the pool.
+    pub(crate) fn in_thread_pool<T: Send>(&self, op: impl FnOnce() -> T + Send) -> T {
+        match &self.thread_pool {
+            Some(thread_pool) => thread_pool.install(op),
+            None => op(),
+        }
+    }
+
+    pub(crate) fn report_progress(&self, stage: ConstructionStage, fraction: f32) {
+        if let Some(progress_callback) = &self.progress_callback {
+            progress_callback(stage, fraction);
+        }
     }
 }
 
@@ -75,23 +612,51 @@ impl<I: IndexStorage, R: TextWithRankSupport<I>> Default for FmIndexConfig<I, R>
             suffix_array_sampling_rate: 4,
             lookup_table_depth: 0,
             performance_priority: PerformancePriority::Balanced,
+            slice_compression_mode: SliceCompressionMode::Auto,
+            record_bwt_run_boundaries: false,
+            buffer_allocator: BufferAllocator::global(),
+            empty_query_matches: EmptyQueryMatches::AllPositions,
+            empty_text_policy: EmptyTextPolicy::Keep,
+            max_volume_len: usize::MAX,
+            text_sampling: TextSampling::All,
+            progress_callback: None,
+            thread_pool: None,
             _index_storage_marker: PhantomData,
             _block_marker: PhantomData,
         }
     }
 }
 
+/// The stages of the construction of the FM-Index, reported to the function set by
+/// [`FmIndexConfig::progress_callback`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstructionStage {
+    /// Translating the texts into the dense representation and concatenating them.
+    EncodeTexts,
+    /// Usually the largest part of the running time. Only the start and end of this stage are reported.
+    ConstructSuffixArray,
+    ConstructBwt,
+    SampleSuffixArray,
+    /// Constructing the text with rank support data structure from the BWT.
+    ConstructRankSupport,
+    FillLookupTables,
+}
+
 /// This enum can be supplied to the [`FmIndexConfig`] to select different sub-algorithms during the
 /// construction of the FM-Index.
 ///
 /// The default is [`Balanced`](PerformancePriority::Balanced). For very large texts over small alphabets,
 /// [`Balanced`](PerformancePriority::Balanced) can actually be faster than [`HighSpeed`](PerformancePriority::HighSpeed).
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PerformancePriority {
     HighSpeed,
     /// For alphabets with 16 or less symbols in dense encoding, the temporary concatenated text and
     /// the BWT buffer can be compressed to use less memory while constructing the BWT. This reduces the peak memory usage
     /// of the construction by about 10-15% and only takes a small amount of additional running time.
+    /// The compression can also be selected independently using [`FmIndexConfig::slice_compression`].
     Balanced,
     /// In addition to the space improvements of the `Balanced` variant, a much slower, not parallel suffix
     /// array construction algorithm will be used for `u32`-based FM-Indices.
@@ -101,9 +666,174 @@ pub enum PerformancePriority {
     LowMemory,
 }
 
+/// This enum can be supplied to the [`FmIndexConfig`] to select which occurrences of the empty query are reported.
+///
+/// Every text ends with an implicit sentinel. Non-empty queries never match the sentinels, so their occurrences are
+/// the same under all definitions. The empty query, however, matches at every position of the texts, and different
+/// naive definitions disagree about whether the position directly after the end of a text (which is adjacent to the
+/// sentinel) is an occurrence. For example, `text.windows(0)` in Rust yields `text.len() + 1` empty windows,
+/// while other definitions only use the `text.len()` positions of the text or no positions at all.
+///
+/// The selected definition affects all functions that report the number of occurrences or the hits of the empty
+/// query, including cursors with the empty query. It is stored inside the index and can be obtained using
+/// [`FmIndex::empty_query_matches`], such that reference implementations can be aligned with the index.
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "mem_dbg", copy_type)]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EmptyQueryMatches {
+    /// Every position `0..=text.len()` of every text is an occurrence, including the position after the end of the
+    /// text. This is the natural definition of the FM-Index, where every suffix of the texts, including the ones that
+    /// only consist of a sentinel, is prefixed by the empty query.
+    #[default]
+    AllPositions,
+    /// Every position `0..text.len()` of every text is an occurrence. The positions after the ends of the texts
+    /// are not reported.
+    TextPositions,
+    /// The empty query has no occurrences.
+    NoPositions,
+}
+
+/// This enum can be supplied to the [`FmIndexConfig`] to select how texts of length `0` are handled.
+///
+/// Empty texts are common in real data, for example as empty contigs of assemblies. If they are filtered out before
+/// the construction, the ids of all following texts shift, which is a common source of errors when hits are
+/// reported with the ids or names of the original records. The policy is stored inside the index and can be
+/// obtained using [`FmIndex::empty_text_policy`].
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EmptyTextPolicy {
+    /// Empty texts are indexed like all other texts. They have a text id, such that the ids of the texts are
+    /// the same as the positions of the records in the input, but they never contain an occurrence of a non-empty
+    /// query.
+    #[default]
+    Keep,
+    /// The construction panics if one of the texts is empty. [`FmIndexConfig::check_inputs`] reports the ids of
+    /// such texts before the construction.
+    Reject,
+}
+
+/// This enum can be supplied to the [`FmIndexConfig`] to index only a deterministic subset of the texts.
+///
+/// This allows building small indices of enormous read sets quickly, e.g. for an exploratory analysis before
+/// committing to the construction of the full index. The texts of the index are numbered consecutively, and the
+/// ids of the selected texts in the input can be obtained using [`FmIndex::original_text_id`]. The sampling is
+/// stored inside the index and can be obtained using [`FmIndex::text_sampling`].
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TextSampling {
+    /// All texts are indexed.
+    #[default]
+    All,
+    /// Only the texts with ids `0`, `n`, `2n`, ... of the input are indexed.
+    EveryNth(usize),
+    /// Every text is indexed with a probability of `1 / one_in`, independently of the other texts. The same seed
+    /// always selects the same texts of the same input, independent of the platform and the version of this library.
+    Random { one_in: usize, seed: u64 },
+}
+
+impl TextSampling {
+    // returns the selected items and stores their ids in the input into original_ids
+    fn select<'a, X>(
+        self,
+        items: impl IntoIterator<Item = X> + 'a,
+        original_ids: &'a mut Vec<usize>,
+    ) -> impl Iterator<Item = X> + 'a {
+        let mut rng = match self {
+            TextSampling::Random { seed, .. } => Some(SplitMix64::new(seed)),
+            _ => None,
+        };
+
+        items
+            .into_iter()
+            .enumerate()
+            .filter(move |&(id, _)| match self {
+                TextSampling::All => true,
+                TextSampling::EveryNth(n) => id % n == 0,
+                TextSampling::Random { one_in, .. } => {
+                    rng.as_mut().unwrap().next_below(one_in) == 0
+                }
+            })
+            .map(move |(id, item)| {
+                original_ids.push(id);
+                item
+            })
+    }
+}
+
+/// This enum can be supplied to the [`FmIndexConfig`] to control whether the temporary concatenated text and
+/// the BWT buffer are compressed during the construction (see [`PerformancePriority::Balanced`]).
+///
+/// The compression reduces the peak memory usage of the construction, but takes a little additional running time.
+/// It has no influence on the resulting index. The default is [`Auto`](SliceCompressionMode::Auto).
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SliceCompressionMode {
+    /// Compress if the alphabet has 16 or less symbols in dense encoding and the performance priority is not
+    /// [`HighSpeed`](PerformancePriority::HighSpeed).
+    #[default]
+    Auto,
+    /// Always compress. The construction panics if the alphabet has more than 16 symbols in dense encoding.
+    On,
+    /// Never compress.
+    Off,
+}
+
+/// The parameters that were used to construct an FM-Index.
+///
+/// They are stored inside the index and also persisted when the index is saved to a file. This helps
+/// understanding performance differences between index files that otherwise look identical.
+/// The values can be obtained using [`FmIndex::construction_params`].
+///
+/// New parameters are added as fields when the construction gains new options, so this struct can't be created
+/// using a struct expression outside of this library.
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConstructionParams {
+    pub suffix_array_sampling_rate: usize,
+    pub lookup_table_depth: usize,
+    pub performance_priority: PerformancePriority,
+    /// [`Auto`](SliceCompressionMode::Auto) for indices that were saved by older versions of this library.
+    #[cfg_attr(feature = "savefile", savefile_versions = "10..")]
+    pub slice_compression_mode: SliceCompressionMode,
+    /// For example `"u32"`.
+    pub index_storage: String,
+    /// For example `"CondensedTextWithRankSupport"`.
+    pub text_with_rank_support: String,
+    /// For example `"Block64"`.
+    pub block: String,
+    /// The version of this library that constructed the index.
+    pub crate_version: String,
+}
+
+impl ConstructionParams {
+    pub(crate) fn from_config<I: IndexStorage, R: TextWithRankSupport<I>>(
+        config: &FmIndexConfig<I, R>,
+    ) -> Self {
+        Self {
+            suffix_array_sampling_rate: config.suffix_array_sampling_rate,
+            lookup_table_depth: config.lookup_table_depth,
+            performance_priority: config.performance_priority,
+            slice_compression_mode: config.slice_compression_mode,
+            index_storage: I::NAME.to_string(),
+            text_with_rank_support: R::NAME.to_string(),
+            block: R::BLOCK_NAME.to_string(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::text_with_rank_support::{Block512, FlatTextWithRankSupport};
 
     #[test]
     fn basic_config() {
@@ -115,4 +845,69 @@ mod tests {
             .suffix_array_sampling_rate(8)
             .construct_index(texts, alphabet);
     }
+
+    #[test]
+    fn construction_params() {
+        let texts = [b"ACGT"];
+        let alphabet = crate::alphabet::ascii_dna();
+
+        let index = FmIndexConfig::<u32, FlatTextWithRankSupport<u32, Block512>>::new()
+            .lookup_table_depth(3)
+            .suffix_array_sampling_rate(2)
+            .construction_performance_priority(PerformancePriority::HighSpeed)
+            .construct_index(texts, alphabet);
+
+        let expected = ConstructionParams {
+            suffix_array_sampling_rate: 2,
+            lookup_table_depth: 3,
+            performance_priority: PerformancePriority::HighSpeed,
+            slice_compression_mode: SliceCompressionMode::Auto,
+            index_storage: "u32".to_string(),
+            text_with_rank_support: "FlatTextWithRankSupport".to_string(),
+            block: "Block512".to_string(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+
+        assert_eq!(index.construction_params(), Some(&expected));
+    }
+
+    #[test]
+    fn slice_compression_modes_agree() {
+        let texts = [b"ACGTACGTTTGACNNA".as_slice(), b"CCGTA", b"A"];
+        let alphabet = crate::alphabet::ascii_dna_with_n();
+
+        let expected_index = FmIndexConfig::<i32>::new()
+            .slice_compression(SliceCompressionMode::Off)
+            .construct_index(texts, alphabet.clone());
+
+        for performance_priority in [
+            PerformancePriority::HighSpeed,
+            PerformancePriority::Balanced,
+            PerformancePriority::LowMemory,
+        ] {
+            for slice_compression_mode in [SliceCompressionMode::On, SliceCompressionMode::Auto] {
+                let index = FmIndexConfig::<u32>::new()
+                    .construction_performance_priority(performance_priority)
+                    .slice_compression(slice_compression_mode)
+                    .construct_index(texts, alphabet.clone());
+
+                for query in [b"ACG".as_slice(), b"A", b"NA", b""] {
+                    let mut hits: Vec<_> = index.locate(query).collect();
+                    let mut expected_hits: Vec<_> = expected_index.locate(query).collect();
+                    hits.sort();
+                    expected_hits.sort();
+
+                    assert_eq!(hits, expected_hits);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn slice_compression_large_alphabet() {
+        let _index = FmIndexConfig::<i32>::new()
+            .slice_compression(SliceCompressionMode::On)
+            .construct_index([b"hello world"], crate::alphabet::ascii_printable());
+    }
 }
@@ -1,7 +1,7 @@
 use super::slice_compression::{
     HalfBytesCompression, NoSliceCompression, SliceCompression, half_byte_compress_text,
 };
-use crate::{Alphabet, IndexStorage, config::PerformancePriority};
+use crate::{IndexStorage, buffer_allocator::LargeBuffer};
 use num_traits::NumCast;
 use rayon::prelude::*;
 use std::collections::HashMap;
@@ -9,15 +9,14 @@ use std::collections::HashMap;
 // I1: current_suffix array indices, I2: IndexStorage we want to use for the FM-Index
 pub(crate) fn bwt_from_suffix_array<'a, I1: IndexStorage, I2: IndexStorage>(
     suffix_array: &[I1],
-    text: &'a mut Vec<u8>,
-    maybe_bwt_buffer: &'a mut Vec<u8>,
-    performance_priority: PerformancePriority,
-    alphabet: &Alphabet,
+    text: &'a mut LargeBuffer<u8>,
+    maybe_bwt_buffer: &'a mut LargeBuffer<u8>,
+    use_slice_compression: bool,
 ) -> (&'a [u8], HashMap<usize, I2>, usize) {
     let uncompressed_text_len = text.len();
 
-    if super::should_not_use_slice_compression(performance_priority, alphabet) {
-        maybe_bwt_buffer.resize(text.len(), 0);
+    if !use_slice_compression {
+        *maybe_bwt_buffer = LargeBuffer::new_zeroed(text.len(), text.allocator());
 
         let text_border_lookup = bwt_from_suffix_array_maybe_slice_compressed::<
             NoSliceCompression,
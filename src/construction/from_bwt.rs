@@ -0,0 +1,273 @@
+use std::collections::{BTreeMap, HashMap};
+
+use num_traits::NumCast;
+use rayon::prelude::*;
+
+use super::{DataStructures, IndexStorage};
+use crate::alphabet::Alphabet;
+use crate::bwt_runs::BwtRuns;
+use crate::config::ConstructionStage;
+use crate::sampled_suffix_array::SampledSuffixArray;
+use crate::text_id_search_tree::TexdIdSearchTree;
+use crate::{FmIndexConfig, TextWithRankSupport};
+
+// The suffix array is sampled by walking backwards through every text using the LF-mapping. Every text is walked
+// from the row of its sentinel to the row of its first symbol, which is a row with a sentinel in the BWT.
+// The suffix array value of that row is given, so the values of all rows of the walk are known afterwards.
+// Every row of the BWT is visited exactly once, independent of how the sentinels are ordered.
+pub(crate) fn create_data_structures_from_bwt<I: IndexStorage, R: TextWithRankSupport<I>>(
+    bwt: &[u8],
+    sentinel_positions: &BTreeMap<usize, usize>,
+    config: &FmIndexConfig<I, R>,
+    alphabet: &Alphabet,
+) -> DataStructures<I, R> {
+    let num_dense_symbols = alphabet.num_dense_symbols();
+    let mut frequencies = vec![0; num_dense_symbols];
+
+    for &symbol in bwt {
+        assert!(
+            (symbol as usize) < num_dense_symbols,
+            "the symbols of the BWT must be in the range 0..{num_dense_symbols}"
+        );
+
+        frequencies[symbol as usize] += 1;
+    }
+
+    config.report_progress(ConstructionStage::ConstructRankSupport, 0.0);
+    let (text_with_rank_support, bwt_runs) =
+        super::construct_text_with_rank_support_and_bwt_runs_maybe_slice_compressed::<I, R>(
+            bwt,
+            bwt.len(),
+            false,
+            config.record_bwt_run_boundaries,
+            alphabet,
+        );
+    config.report_progress(ConstructionStage::ConstructRankSupport, 1.0);
+
+    create_data_structures_from_rank_support(
+        text_with_rank_support,
+        bwt_runs,
+        &frequencies,
+        sentinel_positions,
+        config,
+    )
+}
+
+// the BWT is only accessed through the rank support here, such that it doesn't have to be kept in memory
+pub(crate) fn create_data_structures_from_rank_support<
+    I: IndexStorage,
+    R: TextWithRankSupport<I>,
+>(
+    text_with_rank_support: R,
+    bwt_runs: BwtRuns,
+    frequencies: &[usize],
+    sentinel_positions: &BTreeMap<usize, usize>,
+    config: &FmIndexConfig<I, R>,
+) -> DataStructures<I, R> {
+    let bwt_len = text_with_rank_support.text_len();
+    let num_texts = frequencies[0];
+
+    assert!(
+        bwt_len <= <usize as NumCast>::from(I::max_value()).unwrap(),
+        "the length of the BWT exceeds the maximum value of the index storage type"
+    );
+
+    assert!(
+        num_texts > 0 && sentinel_positions.len() == num_texts,
+        "the BWT must contain one sentinel per text, and every sentinel needs a position"
+    );
+    assert!(
+        sentinel_positions
+            .keys()
+            .all(|&row| row < bwt_len && text_with_rank_support.symbol_at(row) == 0),
+        "the sentinel positions must belong to rows of the BWT that contain a sentinel"
+    );
+
+    let count = count_from_frequencies(frequencies);
+
+    // the texts start directly after the sentinels of the previous texts
+    let mut sorted_text_starts: Vec<_> = sentinel_positions.values().copied().collect();
+    sorted_text_starts.sort_unstable();
+
+    assert!(
+        sorted_text_starts[0] == 0
+            && sorted_text_starts
+                .windows(2)
+                .all(|starts| starts[0] < starts[1])
+            && *sorted_text_starts.last().unwrap() < bwt_len,
+        "the sentinel positions must be the distinct starts of the texts in the concatenated text"
+    );
+
+    let sentinel_indices: Vec<_> = sorted_text_starts[1..]
+        .iter()
+        .map(|&next_text_start| next_text_start - 1)
+        .chain([bwt_len - 1])
+        .collect();
+
+    let sampling_rate = config.suffix_array_sampling_rate;
+
+    config.report_progress(ConstructionStage::SampleSuffixArray, 0.0);
+
+    // the suffixes that start with a sentinel occupy the first rows
+    let walks: Vec<_> = (0..num_texts)
+        .into_par_iter()
+        .map(|sentinel_row| {
+            let mut row = sentinel_row;
+            let mut num_steps = 0;
+            let mut sampled_rows = Vec::new();
+
+            loop {
+                if row % sampling_rate == 0 {
+                    sampled_rows.push((row / sampling_rate, num_steps));
+                }
+
+                let symbol = text_with_rank_support.symbol_at(row);
+
+                if symbol == 0 {
+                    break;
+                }
+
+                row = count[symbol as usize] + text_with_rank_support.rank(symbol, row);
+                num_steps += 1;
+
+                // an invalid BWT can contain cycles without sentinels
+                assert!(num_steps < bwt_len, "the BWT is invalid");
+            }
+
+            let text_start = sentinel_positions[&row];
+
+            (text_start + num_steps, sampled_rows)
+        })
+        .collect();
+
+    let mut walked_sentinel_indices: Vec<_> = walks.iter().map(|(end, _)| *end).collect();
+    walked_sentinel_indices.sort_unstable();
+
+    assert_eq!(
+        walked_sentinel_indices, sentinel_indices,
+        "the sentinel positions don't match the BWT"
+    );
+
+    let mut samples = vec![I::zero(); bwt_len.div_ceil(sampling_rate)];
+
+    for (sentinel_idx, sampled_rows) in walks {
+        for (sample_idx, num_steps) in sampled_rows {
+            samples[sample_idx] = <I as NumCast>::from(sentinel_idx - num_steps).unwrap();
+        }
+    }
+
+    config.report_progress(ConstructionStage::SampleSuffixArray, 1.0);
+
+    let text_border_lookup: HashMap<_, _> = sentinel_positions
+        .iter()
+        .map(|(&row, &text_start)| (row, <I as NumCast>::from(text_start).unwrap()))
+        .collect();
+
+    DataStructures {
+        count,
+        sampled_suffix_array: SampledSuffixArray::from_samples(
+            samples,
+            sampling_rate,
+            text_border_lookup,
+        ),
+        text_ids: TexdIdSearchTree::new_from_sentinel_indices(sentinel_indices),
+        text_with_rank_support,
+        bwt_runs,
+    }
+}
+
+// like in the regular construction, the last entry is the total number of symbols
+pub(crate) fn count_from_frequencies(frequencies: &[usize]) -> Vec<usize> {
+    frequencies
+        .iter()
+        .chain([&0])
+        .scan(0, |sum, &frequency| {
+            let start = *sum;
+            *sum += frequency;
+            Some(start)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bwt::{self, SentinelOrder};
+    use crate::{EmptyTextPolicy, FmIndex, FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn agrees_with_regular_construction(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 0..100), 1..5),
+            queries in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 1..4), 1..20),
+            suffix_array_sampling_rate in 1usize..8,
+            seed in any::<u64>(),
+        ) {
+            let alphabet = alphabet::ascii_dna();
+            let config = || FmIndexConfig::<i32>::new().suffix_array_sampling_rate(suffix_array_sampling_rate);
+            let index = config().construct_index(&texts, alphabet.clone());
+
+            let mut ranks: Vec<_> = (0..texts.len()).collect();
+            ranks.sort_by_key(|&rank| (rank as u64 + 1).wrapping_mul(seed) % 1000003);
+
+            for sentinel_order in [SentinelOrder::ByFollowingTexts, SentinelOrder::ByTextId, SentinelOrder::ByRank(ranks)] {
+                let bwt = bwt::construct_bwt_with_sentinel_order::<i32, _>(&texts, &alphabet, false, &sentinel_order);
+                let symbols: Vec<_> = bwt.iter().collect();
+
+                let bwt_index = FmIndex::from_bwt(&symbols, bwt.sentinel_suffix_array_values(), alphabet.clone(), config());
+
+                if sentinel_order == SentinelOrder::ByFollowingTexts {
+                    prop_assert_eq!(bwt_index.component_hashes(), index.component_hashes());
+                }
+
+                for query in &queries {
+                    let mut hits: Vec<_> = bwt_index.locate(query).collect();
+                    let mut expected_hits: Vec<_> = index.locate(query).collect();
+                    hits.sort();
+                    expected_hits.sort();
+
+                    prop_assert_eq!(hits, expected_hits);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "don't match")]
+    fn wrong_sentinel_positions() {
+        let alphabet = alphabet::ascii_dna();
+        let bwt = bwt::construct_bwt::<i32, _>([b"ACGT".as_slice(), b"AC"], &alphabet, false);
+        let symbols: Vec<_> = bwt.iter().collect();
+
+        // the second text starts at 5, but the lengths of the walks through the BWT are independent of that
+        let sentinel_positions = bwt
+            .sentinel_suffix_array_values()
+            .iter()
+            .map(|(&row, &text_start)| (row, text_start.min(1)))
+            .collect();
+
+        FmIndex::<i32>::from_bwt(
+            &symbols,
+            &sentinel_positions,
+            alphabet,
+            FmIndexConfig::new(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "empty texts are rejected")]
+    fn rejected_empty_text() {
+        let alphabet = alphabet::ascii_dna();
+        let bwt = bwt::construct_bwt::<i32, _>([b"ACGT".as_slice(), b""], &alphabet, false);
+        let symbols: Vec<_> = bwt.iter().collect();
+
+        FmIndex::<i32>::from_bwt(
+            &symbols,
+            bwt.sentinel_suffix_array_values(),
+            alphabet,
+            FmIndexConfig::new().empty_text_policy(EmptyTextPolicy::Reject),
+        );
+    }
+}
@@ -1,13 +1,16 @@
-mod bwt;
+pub(crate) mod bwt;
+pub(crate) mod from_bwt;
 pub(crate) mod slice_compression;
 
 use bytemuck::Pod;
 use libsais::{OutputElement, ThreadCount};
-use num_traits::{NumCast, PrimInt};
+use num_traits::{NumCast, PrimInt, Zero};
 use rayon::prelude::*;
 
 use crate::alphabet::Alphabet;
-use crate::config::PerformancePriority;
+use crate::buffer_allocator::{BufferAllocator, LargeBuffer};
+use crate::bwt_runs::{self, BwtRuns};
+use crate::config::{ConstructionStage, PerformancePriority, SliceCompressionMode};
 use crate::construction::slice_compression::{HalfBytesCompression, NoSliceCompression};
 use crate::maybe_mem_dbg::MaybeMemDbgCopy;
 use crate::maybe_savefile::MaybeSavefile;
@@ -20,6 +23,7 @@ pub(crate) struct DataStructures<I, R> {
     pub(crate) sampled_suffix_array: SampledSuffixArray<I>,
     pub(crate) text_ids: TexdIdSearchTree,
     pub(crate) text_with_rank_support: R,
+    pub(crate) bwt_runs: BwtRuns,
 }
 
 pub(crate) fn create_data_structures<I: IndexStorage, R: TextWithRankSupport<I>, T: AsRef<[u8]>>(
@@ -27,19 +31,154 @@ pub(crate) fn create_data_structures<I: IndexStorage, R: TextWithRankSupport<I>,
     config: &FmIndexConfig<I, R>,
     alphabet: &Alphabet,
 ) -> DataStructures<I, R> {
-    // the frequency table is used for libsais, and turned into the count data structure of the fmindex
-    let (mut text, mut frequency_table, sentinel_indices) =
-        create_concatenated_densely_encoded_text(texts, alphabet);
+    let generic_texts: Vec<_> = texts.into_iter().collect();
+    let texts = text_slices(&generic_texts);
+
+    config.in_thread_pool(|| {
+        // the frequency table is used for libsais, and turned into the count data structure of the fmindex
+        config.report_progress(ConstructionStage::EncodeTexts, 0.0);
+        let (text, frequency_table, sentinel_indices) =
+            create_concatenated_densely_encoded_text(texts, alphabet, &config.buffer_allocator);
+        config.report_progress(ConstructionStage::EncodeTexts, 1.0);
+
+        create_data_structures_from_encoded_text(
+            text,
+            frequency_table,
+            sentinel_indices,
+            config,
+            alphabet,
+        )
+    })
+}
+
+// The reversed texts have the same symbols and text borders as the texts, so the encoding, the frequency table and
+// the text ids are shared. Only the order of the symbols inside of every text is reversed.
+pub(crate) fn create_forward_and_reverse_data_structures<
+    I: IndexStorage,
+    R: TextWithRankSupport<I>,
+    T: AsRef<[u8]>,
+>(
+    texts: impl IntoIterator<Item = T>,
+    config: &FmIndexConfig<I, R>,
+    alphabet: &Alphabet,
+) -> (DataStructures<I, R>, DataStructures<I, R>) {
+    let generic_texts: Vec<_> = texts.into_iter().collect();
+    let texts = text_slices(&generic_texts);
 
-    assert!(text.len() <= <usize as NumCast>::from(I::max_value()).unwrap());
+    config.in_thread_pool(|| {
+        create_forward_and_reverse_data_structures_from_texts(texts, config, alphabet)
+    })
+}
+
+fn create_forward_and_reverse_data_structures_from_texts<
+    I: IndexStorage,
+    R: TextWithRankSupport<I>,
+>(
+    texts: Vec<&[u8]>,
+    config: &FmIndexConfig<I, R>,
+    alphabet: &Alphabet,
+) -> (DataStructures<I, R>, DataStructures<I, R>) {
+    config.report_progress(ConstructionStage::EncodeTexts, 0.0);
+    let (text, frequency_table, sentinel_indices) =
+        create_concatenated_densely_encoded_text(texts, alphabet, &config.buffer_allocator);
+    config.report_progress(ConstructionStage::EncodeTexts, 1.0);
+
+    // the same extra capacity as for the forward text
+    let mut reversed_text = LargeBuffer::new_zeroed(text.len() + 1, &config.buffer_allocator);
+    reversed_text.truncate(text.len());
+    reversed_text.copy_from_slice(&text);
+
+    let mut text_start = 0;
+
+    for &sentinel_idx in &sentinel_indices {
+        reversed_text[text_start..sentinel_idx].reverse();
+        text_start = sentinel_idx + 1;
+    }
+
+    let forward_data_structures = create_data_structures_from_encoded_text(
+        text,
+        frequency_table.clone(),
+        sentinel_indices.clone(),
+        config,
+        alphabet,
+    );
+
+    let reverse_data_structures = create_data_structures_from_encoded_text(
+        reversed_text,
+        frequency_table,
+        sentinel_indices,
+        config,
+        alphabet,
+    );
+
+    (forward_data_structures, reverse_data_structures)
+}
+
+// the texts are only copied and counted, because they are already in dense representation
+pub(crate) fn create_data_structures_from_dense_texts<
+    I: IndexStorage,
+    R: TextWithRankSupport<I>,
+    T: AsRef<[u8]>,
+>(
+    texts: impl IntoIterator<Item = T>,
+    config: &FmIndexConfig<I, R>,
+    alphabet: &Alphabet,
+) -> DataStructures<I, R> {
+    let generic_texts: Vec<_> = texts.into_iter().collect();
+    let texts = text_slices(&generic_texts);
+
+    config.in_thread_pool(|| {
+        config.report_progress(ConstructionStage::EncodeTexts, 0.0);
+        let (text, mut frequency_table, sentinel_indices) =
+            create_concatenated_text(texts, |symbol| symbol, &config.buffer_allocator);
+        config.report_progress(ConstructionStage::EncodeTexts, 1.0);
+
+        assert!(
+            frequency_table[0] == I::LibsaisOutput::zero()
+                && frequency_table[alphabet.num_dense_symbols()..]
+                    .iter()
+                    .all(|&frequency| frequency == I::LibsaisOutput::zero()),
+            "the symbols of dense texts must be in the range 1..{}, because 0 is the sentinel",
+            alphabet.num_dense_symbols()
+        );
+
+        frequency_table[0] = <I::LibsaisOutput as NumCast>::from(sentinel_indices.len()).unwrap();
+
+        create_data_structures_from_encoded_text(
+            text,
+            frequency_table,
+            sentinel_indices,
+            config,
+            alphabet,
+        )
+    })
+}
+
+// the generic texts are not required to be Send, so only slices of them are passed into the thread pool
+fn text_slices<T: AsRef<[u8]>>(texts: &[T]) -> Vec<&[u8]> {
+    texts.iter().map(|text| text.as_ref()).collect()
+}
+
+fn create_data_structures_from_encoded_text<I: IndexStorage, R: TextWithRankSupport<I>>(
+    mut text: LargeBuffer<u8>,
+    mut frequency_table: Vec<I::LibsaisOutput>,
+    sentinel_indices: Vec<usize>,
+    config: &FmIndexConfig<I, R>,
+    alphabet: &Alphabet,
+) -> DataStructures<I, R> {
+    assert!(
+        text.len() <= <usize as NumCast>::from(I::max_value()).unwrap(),
+        "the total length of the texts (including one sentinel per text) exceeds the maximum value of the \
+        index storage type, use a larger index storage type or a multi-volume index (see FmIndexConfig::check_inputs)"
+    );
 
     let text_ids = TexdIdSearchTree::new_from_sentinel_indices(sentinel_indices);
 
     let count = frequency_table_to_count(&frequency_table, alphabet.num_dense_symbols());
 
-    let mut maybe_bwt_buffer = Vec::new();
+    let mut maybe_bwt_buffer = LargeBuffer::new_zeroed(0, &config.buffer_allocator);
 
-    let (sampled_suffix_array, text_with_rank_support) =
+    let (sampled_suffix_array, text_with_rank_support, bwt_runs) =
         I::construct_sampled_suffix_array_and_text_with_rank_support(
             &mut text,
             &mut maybe_bwt_buffer,
@@ -53,6 +192,7 @@ pub(crate) fn create_data_structures<I: IndexStorage, R: TextWithRankSupport<I>,
         sampled_suffix_array,
         text_ids,
         text_with_rank_support,
+        bwt_runs,
     }
 }
 
@@ -71,17 +211,32 @@ pub(crate) fn create_data_structures<I: IndexStorage, R: TextWithRankSupport<I>,
 pub trait IndexStorage:
     PrimInt + Pod + MaybeSavefile + MaybeMemDbgCopy + sealed::Sealed + Send + Sync + 'static
 {
+    #[doc(hidden)]
+    const NAME: &'static str;
+
     #[doc(hidden)]
     type LibsaisOutput: OutputElement + IndexStorage;
 
+    // the number of bytes of the buffer of the suffix array per symbol of the text during the construction
+    #[doc(hidden)]
+    fn suffix_array_construction_bytes_per_symbol(
+        _performance_priority: PerformancePriority,
+    ) -> usize {
+        size_of::<Self::LibsaisOutput>()
+    }
+
     #[doc(hidden)]
     fn construct_libsais_suffix_array(
         text: &[u8],
         frequency_table: &mut [Self::LibsaisOutput],
-    ) -> Vec<u32> {
+        generalized_suffix_array: bool,
+        allocator: &BufferAllocator,
+    ) -> LargeBuffer<u32> {
         // allocate the buffer in bytes, because maybe we want to muck around with integer types later (compress i64 into u32)
-        let mut suffix_array_data =
-            vec![0u32; text.len() * size_of::<Self::LibsaisOutput>() / size_of::<u32>()];
+        let mut suffix_array_data = LargeBuffer::new_zeroed(
+            text.len() * size_of::<Self::LibsaisOutput>() / size_of::<u32>(),
+            allocator,
+        );
         let suffix_array_buffer: &mut [Self::LibsaisOutput] =
             bytemuck::cast_slice_mut(&mut suffix_array_data);
 
@@ -98,6 +253,10 @@ pub trait IndexStorage:
             construction = construction.with_frequency_table(frequency_table);
         }
 
+        if generalized_suffix_array {
+            construction = construction.generalized_suffix_array();
+        }
+
         construction
             .run()
             .expect("libsais suffix array construction");
@@ -110,42 +269,56 @@ pub trait IndexStorage:
         'a,
         R: TextWithRankSupport<Self>,
     >(
-        text: &'a mut Vec<u8>,
-        maybe_bwt_buffer: &'a mut Vec<u8>,
+        text: &'a mut LargeBuffer<u8>,
+        maybe_bwt_buffer: &'a mut LargeBuffer<u8>,
         frequency_table: &mut [Self::LibsaisOutput],
         config: &FmIndexConfig<Self, R>,
         alphabet: &Alphabet,
-    ) -> (SampledSuffixArray<Self>, R) {
-        let suffix_array_data = Self::construct_libsais_suffix_array(text, frequency_table);
+    ) -> (SampledSuffixArray<Self>, R, BwtRuns) {
+        config.report_progress(ConstructionStage::ConstructSuffixArray, 0.0);
+        let suffix_array_data = Self::construct_libsais_suffix_array(
+            text,
+            frequency_table,
+            false,
+            &config.buffer_allocator,
+        );
         let suffix_array_buffer: &[Self::LibsaisOutput] = bytemuck::cast_slice(&suffix_array_data);
+        config.report_progress(ConstructionStage::ConstructSuffixArray, 1.0);
 
+        config.report_progress(ConstructionStage::ConstructBwt, 0.0);
         let (bwt, text_border_lookup, uncompressed_text_len) = bwt::bwt_from_suffix_array(
             suffix_array_buffer,
             text,
             maybe_bwt_buffer,
-            config.performance_priority,
-            alphabet,
+            use_slice_compression(config, alphabet),
         );
+        config.report_progress(ConstructionStage::ConstructBwt, 1.0);
 
+        config.report_progress(ConstructionStage::SampleSuffixArray, 0.0);
         let sampled_suffix_array = Self::sample_suffix_array_maybe_u32_compressed(
             suffix_array_data,
             config.suffix_array_sampling_rate,
             text_border_lookup,
         );
-
-        let text_with_rank_support = construct_text_with_rank_support_maybe_slice_compressed(
-            bwt,
-            uncompressed_text_len,
-            config.performance_priority,
-            alphabet,
-        );
-
-        (sampled_suffix_array, text_with_rank_support)
+        config.report_progress(ConstructionStage::SampleSuffixArray, 1.0);
+
+        config.report_progress(ConstructionStage::ConstructRankSupport, 0.0);
+        let (text_with_rank_support, bwt_runs) =
+            construct_text_with_rank_support_and_bwt_runs_maybe_slice_compressed(
+                bwt,
+                uncompressed_text_len,
+                use_slice_compression(config, alphabet),
+                config.record_bwt_run_boundaries,
+                alphabet,
+            );
+        config.report_progress(ConstructionStage::ConstructRankSupport, 1.0);
+
+        (sampled_suffix_array, text_with_rank_support, bwt_runs)
     }
 
     #[doc(hidden)]
     fn sample_suffix_array_maybe_u32_compressed(
-        suffix_array_data: Vec<u32>,
+        suffix_array_data: LargeBuffer<u32>,
         sampling_rate: usize,
         text_border_lookup: std::collections::HashMap<usize, Self>,
     ) -> SampledSuffixArray<Self> {
@@ -156,6 +329,8 @@ pub trait IndexStorage:
 impl sealed::Sealed for i32 {}
 
 impl IndexStorage for i32 {
+    const NAME: &'static str = "i32";
+
     type LibsaisOutput = i32;
 }
 
@@ -163,81 +338,114 @@ impl sealed::Sealed for u32 {}
 
 // special handling for u32: use psacak in low memory mode, use libsasi64 and u32 compression otherwise
 impl IndexStorage for u32 {
+    const NAME: &'static str = "u32";
+
     type LibsaisOutput = i64;
 
+    #[cfg(feature = "u32-saca")]
+    fn suffix_array_construction_bytes_per_symbol(
+        performance_priority: PerformancePriority,
+    ) -> usize {
+        match performance_priority {
+            PerformancePriority::HighSpeed | PerformancePriority::Balanced => size_of::<i64>(),
+            PerformancePriority::LowMemory => size_of::<u32>(),
+        }
+    }
+
     #[cfg(feature = "u32-saca")]
     fn construct_sampled_suffix_array_and_text_with_rank_support<
         'a,
         R: TextWithRankSupport<Self>,
     >(
-        text: &'a mut Vec<u8>,
-        maybe_bwt_buffer: &'a mut Vec<u8>,
+        text: &'a mut LargeBuffer<u8>,
+        maybe_bwt_buffer: &'a mut LargeBuffer<u8>,
         frequency_table: &mut [Self::LibsaisOutput],
         config: &FmIndexConfig<Self, R>,
         alphabet: &Alphabet,
-    ) -> (SampledSuffixArray<Self>, R) {
+    ) -> (SampledSuffixArray<Self>, R, BwtRuns) {
         let (sampled_suffix_array, bwt, uncompressed_text_len) = match config.performance_priority {
             PerformancePriority::HighSpeed | PerformancePriority::Balanced => {
-                let suffix_array_data = Self::construct_libsais_suffix_array(text, frequency_table);
+                config.report_progress(ConstructionStage::ConstructSuffixArray, 0.0);
+                let suffix_array_data = Self::construct_libsais_suffix_array(
+                    text,
+                    frequency_table,
+                    false,
+                    &config.buffer_allocator,
+                );
                 let suffix_array_buffer: &[Self::LibsaisOutput] =
                     bytemuck::cast_slice(&suffix_array_data);
+                config.report_progress(ConstructionStage::ConstructSuffixArray, 1.0);
 
+                config.report_progress(ConstructionStage::ConstructBwt, 0.0);
                 let (bwt, text_border_lookup, uncompressed_text_len) = bwt::bwt_from_suffix_array(
                     suffix_array_buffer,
                     text,
                     maybe_bwt_buffer,
-                    config.performance_priority,
-                    alphabet,
+                    use_slice_compression(config, alphabet),
                 );
+                config.report_progress(ConstructionStage::ConstructBwt, 1.0);
 
+                config.report_progress(ConstructionStage::SampleSuffixArray, 0.0);
                 let sampled_suffix_array = Self::sample_suffix_array_maybe_u32_compressed(
                     suffix_array_data,
                     config.suffix_array_sampling_rate,
                     text_border_lookup,
                 );
+                config.report_progress(ConstructionStage::SampleSuffixArray, 1.0);
 
                 (sampled_suffix_array, bwt, uncompressed_text_len)
             }
             PerformancePriority::LowMemory => {
-                let mut suffix_array_data =
-                    vec![0u32; text.len() * size_of::<Self>() / size_of::<u32>()];
+                config.report_progress(ConstructionStage::ConstructSuffixArray, 0.0);
+                let mut suffix_array_data = LargeBuffer::<u32>::new_zeroed(
+                    text.len() * size_of::<Self>() / size_of::<u32>(),
+                    &config.buffer_allocator,
+                );
                 let suffix_array_buffer: &mut [Self] =
                     bytemuck::cast_slice_mut(&mut suffix_array_data);
 
                 psacak::psacak_inplace(text, suffix_array_buffer);
+                config.report_progress(ConstructionStage::ConstructSuffixArray, 1.0);
 
+                config.report_progress(ConstructionStage::ConstructBwt, 0.0);
                 let (bwt, text_border_lookup, uncompressed_text_len) = bwt::bwt_from_suffix_array(
                     suffix_array_buffer,
                     text,
                     maybe_bwt_buffer,
-                    config.performance_priority,
-                    alphabet,
+                    use_slice_compression(config, alphabet),
                 );
+                config.report_progress(ConstructionStage::ConstructBwt, 1.0);
 
                 // NOT call Self::sample_suffix_array_maybe_u32_compressed, because after using u32 saca
                 // the suffix array does not need ot be compressed
+                config.report_progress(ConstructionStage::SampleSuffixArray, 0.0);
                 let sampled_suffix_array = SampledSuffixArray::new_uncompressed(
                     suffix_array_data,
                     config.suffix_array_sampling_rate,
                     text_border_lookup,
                 );
+                config.report_progress(ConstructionStage::SampleSuffixArray, 1.0);
 
                 (sampled_suffix_array, bwt, uncompressed_text_len)
             }
         };
 
-        let text_with_rank_support = construct_text_with_rank_support_maybe_slice_compressed(
-            bwt,
-            uncompressed_text_len,
-            config.performance_priority,
-            alphabet,
-        );
-
-        (sampled_suffix_array, text_with_rank_support)
+        config.report_progress(ConstructionStage::ConstructRankSupport, 0.0);
+        let (text_with_rank_support, bwt_runs) =
+            construct_text_with_rank_support_and_bwt_runs_maybe_slice_compressed(
+                bwt,
+                uncompressed_text_len,
+                use_slice_compression(config, alphabet),
+                config.record_bwt_run_boundaries,
+                alphabet,
+            );
+        config.report_progress(ConstructionStage::ConstructRankSupport, 1.0);
+
+        (sampled_suffix_array, text_with_rank_support, bwt_runs)
     }
 
     fn sample_suffix_array_maybe_u32_compressed(
-        suffix_array_data: Vec<u32>,
+        suffix_array_data: LargeBuffer<u32>,
         sampling_rate: usize,
         text_border_lookup: std::collections::HashMap<usize, Self>,
     ) -> SampledSuffixArray<Self> {
@@ -248,14 +456,34 @@ impl IndexStorage for u32 {
 impl sealed::Sealed for i64 {}
 
 impl IndexStorage for i64 {
+    const NAME: &'static str = "i64";
+
     type LibsaisOutput = i64;
 }
 
-// making this parallel is hilarious premature optimization, but it was fun
 pub(crate) fn create_concatenated_densely_encoded_text<I: OutputElement, T: AsRef<[u8]>>(
     texts: impl IntoIterator<Item = T>,
     alphabet: &Alphabet,
-) -> (Vec<u8>, Vec<I>, Vec<usize>) {
+    allocator: &BufferAllocator,
+) -> (LargeBuffer<u8>, Vec<I>, Vec<usize>) {
+    let (concatenated_text, mut frequency_table, sentinel_indices) = create_concatenated_text(
+        texts,
+        |symbol| alphabet.io_to_dense_representation(symbol),
+        allocator,
+    );
+
+    frequency_table[0] = <I as NumCast>::from(sentinel_indices.len()).unwrap();
+
+    (concatenated_text, frequency_table, sentinel_indices)
+}
+
+// making this parallel is hilarious premature optimization, but it was fun.
+// the frequency of the sentinel is not set, it contains the number of text symbols that are encoded as 0
+fn create_concatenated_text<I: OutputElement, T: AsRef<[u8]>>(
+    texts: impl IntoIterator<Item = T>,
+    encode: impl Fn(u8) -> u8 + Sync,
+    allocator: &BufferAllocator,
+) -> (LargeBuffer<u8>, Vec<I>, Vec<usize>) {
     // this generic texts owned vec is needed for the as_ref interface
     let generic_texts: Vec<_> = texts.into_iter().collect();
     let texts: Vec<&[u8]> = generic_texts.iter().map(|t| t.as_ref()).collect();
@@ -274,11 +502,11 @@ pub(crate) fn create_concatenated_densely_encoded_text<I: OutputElement, T: AsRe
 
     // add one extra capacity to make sure that there does not need to be reallocation when on byte is added to the
     // text to make its size even for the slice compression in the lower memory mode for small alphabets
-    let mut concatenated_text = vec![0; needed_capacity + 1];
-    concatenated_text.pop();
+    let mut concatenated_text = LargeBuffer::new_zeroed(needed_capacity + 1, allocator);
+    concatenated_text.truncate(needed_capacity);
 
     let mut concatenated_text_splits = Vec::with_capacity(num_texts);
-    let mut remaining_slice = concatenated_text.as_mut_slice();
+    let mut remaining_slice = &mut concatenated_text[..];
 
     for t in texts.iter() {
         let (this, remaining) = remaining_slice.split_at_mut(t.len() + 1);
@@ -286,14 +514,14 @@ pub(crate) fn create_concatenated_densely_encoded_text<I: OutputElement, T: AsRe
         remaining_slice = remaining;
     }
 
-    let mut frequency_table = texts
+    let frequency_table = texts
         .into_par_iter()
         .zip(concatenated_text_splits)
         .map(|(text, concatenated_text_split)| {
             let mut frequency_table = vec![I::zero(); 256];
 
             for (source, target) in text.iter().zip(concatenated_text_split) {
-                *target = alphabet.io_to_dense_representation(*source);
+                *target = encode(*source);
                 frequency_table[*target as usize] = frequency_table[*target as usize] + I::one();
             }
 
@@ -302,8 +530,6 @@ pub(crate) fn create_concatenated_densely_encoded_text<I: OutputElement, T: AsRe
         .reduce_with(merge_frequency_tables)
         .expect("There should be at least one texts");
 
-    frequency_table[0] = <I as NumCast>::from(num_texts).unwrap();
-
     (concatenated_text, frequency_table, sentinel_indices)
 }
 
@@ -335,35 +561,64 @@ fn frequency_table_to_count<I: OutputElement>(
     count
 }
 
-fn construct_text_with_rank_support_maybe_slice_compressed<
+pub(crate) fn construct_text_with_rank_support_and_bwt_runs_maybe_slice_compressed<
     I: IndexStorage,
     R: TextWithRankSupport<I>,
 >(
     bwt: &[u8],
     uncompressed_bwt_len: usize,
-    performance_priority: PerformancePriority,
+    use_slice_compression: bool,
+    record_bwt_run_boundaries: bool,
     alphabet: &Alphabet,
-) -> R {
-    if should_not_use_slice_compression(performance_priority, alphabet) {
-        R::construct_from_maybe_slice_compressed_text::<NoSliceCompression>(
-            bwt,
-            uncompressed_bwt_len,
-            alphabet.num_dense_symbols(),
+) -> (R, BwtRuns) {
+    if use_slice_compression {
+        (
+            R::construct_from_maybe_slice_compressed_text::<HalfBytesCompression>(
+                bwt,
+                uncompressed_bwt_len,
+                alphabet.num_dense_symbols(),
+            ),
+            bwt_runs::collect_bwt_runs::<HalfBytesCompression>(
+                bwt,
+                uncompressed_bwt_len,
+                record_bwt_run_boundaries,
+            ),
         )
     } else {
-        R::construct_from_maybe_slice_compressed_text::<HalfBytesCompression>(
-            bwt,
-            uncompressed_bwt_len,
-            alphabet.num_dense_symbols(),
+        (
+            R::construct_from_maybe_slice_compressed_text::<NoSliceCompression>(
+                bwt,
+                uncompressed_bwt_len,
+                alphabet.num_dense_symbols(),
+            ),
+            bwt_runs::collect_bwt_runs::<NoSliceCompression>(
+                bwt,
+                uncompressed_bwt_len,
+                record_bwt_run_boundaries,
+            ),
         )
     }
 }
 
-fn should_not_use_slice_compression(
-    performance_priority: PerformancePriority,
+pub(crate) fn use_slice_compression<I, R>(
+    config: &FmIndexConfig<I, R>,
     alphabet: &Alphabet,
 ) -> bool {
-    performance_priority == PerformancePriority::HighSpeed || alphabet.num_dense_symbols() > 16
+    let alphabet_fits = alphabet.num_dense_symbols() <= 16;
+
+    match config.slice_compression_mode {
+        SliceCompressionMode::Auto => {
+            config.performance_priority != PerformancePriority::HighSpeed && alphabet_fits
+        }
+        SliceCompressionMode::On => {
+            assert!(
+                alphabet_fits,
+                "slice compression is only possible for alphabets with at most 16 dense symbols"
+            );
+            true
+        }
+        SliceCompressionMode::Off => false,
+    }
 }
 
 #[cfg(test)]
@@ -375,11 +630,15 @@ mod tests {
     fn concat_text() {
         let texts = [b"cccaaagggttt".as_slice(), b"acgtacgtacgt"];
         let alph = alphabet::ascii_dna();
-        let (text, frequency_table, sentinel_indices) =
-            create_concatenated_densely_encoded_text::<i32, _>(texts, &alph);
+        let (text, frequency_table, sentinel_indices) = create_concatenated_densely_encoded_text::<
+            i32,
+            _,
+        >(
+            texts, &alph, &BufferAllocator::global()
+        );
 
         assert_eq!(
-            text,
+            &text[..],
             [
                 2, 2, 2, 1, 1, 1, 3, 3, 3, 4, 4, 4, 0, 1, 2, 3, 4, 1, 2, 3, 4, 1, 2, 3, 4, 0
             ]
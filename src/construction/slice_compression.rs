@@ -90,6 +90,52 @@ impl SliceCompression for HalfBytesCompression {
     }
 }
 
+// not used by the construction of the FM-Index, only for constructing the text with rank support
+// from texts that are already packed by the user. The first symbol is stored in the highest bits of every byte.
+pub(crate) struct TwoBitsCompression {}
+
+impl SliceCompression for TwoBitsCompression {
+    fn get(idx: usize, slice: &[u8]) -> u8 {
+        (slice[idx / 4] >> two_bits_shift(idx)) & 0b11
+    }
+
+    fn set(idx: usize, slice: &mut [u8], value: u8) {
+        let shift = two_bits_shift(idx);
+        let byte = &mut slice[idx / 4];
+
+        *byte = (*byte & !(0b11 << shift)) | ((value & 0b11) << shift);
+    }
+
+    fn transform_chunk_size(chunk_size: usize) -> usize {
+        chunk_size / 4
+    }
+
+    fn transformed_slice_len(slice: &[u8]) -> usize {
+        slice.len() * 4
+    }
+
+    fn iter(slice: &[u8]) -> impl Iterator<Item = u8> {
+        slice.iter().flat_map(|&byte| {
+            [
+                byte >> 6,
+                (byte >> 4) & 0b11,
+                (byte >> 2) & 0b11,
+                byte & 0b11,
+            ]
+        })
+    }
+
+    fn iter_zero_indices(slice: &[u8]) -> impl Iterator<Item = usize> {
+        Self::iter(slice)
+            .enumerate()
+            .filter_map(|(idx, byte)| if byte == 0 { Some(idx) } else { None })
+    }
+}
+
+fn two_bits_shift(idx: usize) -> usize {
+    6 - (idx % 4) * 2
+}
+
 fn pack_into_left_half_of_byte(byte: &mut u8, value: u8) {
     *byte = (value << 4) | (*byte & 0b00001111);
 }
@@ -159,4 +205,31 @@ mod tests {
             HalfBytesCompression::iter_zero_indices(compressed_text).collect();
         assert_eq!(zero_indices, [1]);
     }
+
+    #[test]
+    fn two_bits_compression() {
+        let text = [3, 0, 1, 2, 2, 1];
+        let compressed_text = [0b11000110, 0b10010000];
+
+        for (idx, &expected_symbol) in text.iter().enumerate() {
+            assert_eq!(
+                expected_symbol,
+                TwoBitsCompression::get(idx, &compressed_text)
+            );
+        }
+
+        let mut second_compressed_text = [0u8; 2];
+
+        for (idx, &symbol) in text.iter().enumerate() {
+            TwoBitsCompression::set(idx, &mut second_compressed_text, symbol);
+        }
+        assert_eq!(second_compressed_text, compressed_text);
+
+        let collected: Vec<_> = TwoBitsCompression::iter(&compressed_text).collect();
+        assert_eq!(collected, [3, 0, 1, 2, 2, 1, 0, 0]);
+
+        let zero_indices: Vec<_> =
+            TwoBitsCompression::iter_zero_indices(&compressed_text).collect();
+        assert_eq!(zero_indices, [1, 6, 7]);
+    }
 }
@@ -0,0 +1,216 @@
+use std::{cmp::Reverse, ops::Range};
+
+use crate::{FmIndex, HalfOpenInterval, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+/// Describes how well a query sequence is contained in the indexed texts, computed by
+/// [`FmIndex::containment_report`].
+///
+/// A position of the query is covered if it is part of an exact match of at least the minimum length that occurs
+/// in the indexed texts. The matches are found using the [matching statistics](FmIndex::matching_statistics)
+/// of the query.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContainmentReport {
+    /// The length of the query.
+    pub query_len: usize,
+    /// The number of positions of the query that are covered by at least one match.
+    pub num_covered_positions: usize,
+    /// The text ids of the texts in which the matches occur, together with the number of query positions covered
+    /// by matches in the respective text. Sorted by the number of covered positions in descending order, such that
+    /// the best matching text comes first.
+    ///
+    /// Only the maximal matches are considered here, i.e. the longest matches of the query positions that are not
+    /// part of the longest match of the previous position.
+    pub targets: Vec<(usize, usize)>,
+    /// The maximal ranges of consecutive uncovered positions of the query, sorted by length in descending order.
+    pub gaps: Vec<Range<usize>>,
+}
+
+impl ContainmentReport {
+    /// The fraction of positions of the query that are covered by matches. Returns `0.0` for an empty query.
+    pub fn covered_fraction(&self) -> f64 {
+        if self.query_len == 0 {
+            0.0
+        } else {
+            self.num_covered_positions as f64 / self.query_len as f64
+        }
+    }
+}
+
+pub(crate) fn containment_report<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+    min_len: usize,
+) -> ContainmentReport {
+    let statistics = index.matching_statistics(query);
+
+    // an empty match would locate the whole suffix array interval
+    let min_len = min_len.max(1);
+
+    let mut num_covered_positions = 0;
+    let mut gaps = Vec::new();
+    let mut covered_until = 0;
+    let mut matched_ranges = Vec::new();
+
+    for (start, statistic) in statistics.iter().enumerate() {
+        if statistic.len < min_len {
+            continue;
+        }
+
+        let end = start + statistic.len;
+
+        // the match of the previous position ends at least as far to the back as this one
+        if end <= covered_until {
+            continue;
+        }
+
+        if start > covered_until {
+            gaps.push(covered_until..start);
+        }
+
+        num_covered_positions += end - start.max(covered_until);
+        covered_until = end;
+
+        let interval = HalfOpenInterval {
+            start: statistic.sa_interval.start,
+            end: statistic.sa_interval.end,
+        };
+
+        matched_ranges.extend(
+            index
+                .locate_interval(index.reported_interval(interval))
+                .map(|hit| (hit.text_id, start..end)),
+        );
+    }
+
+    if covered_until < query.len() {
+        gaps.push(covered_until..query.len());
+    }
+
+    gaps.sort_by_key(|gap| (Reverse(gap.len()), gap.start));
+
+    ContainmentReport {
+        query_len: query.len(),
+        num_covered_positions,
+        targets: covered_positions_per_text(matched_ranges),
+        gaps,
+    }
+}
+
+// the matched ranges of every text are merged, because a text can contain overlapping or repeated matches
+fn covered_positions_per_text(
+    mut matched_ranges: Vec<(usize, Range<usize>)>,
+) -> Vec<(usize, usize)> {
+    matched_ranges.sort_by_key(|(text_id, range)| (*text_id, range.start));
+
+    let mut targets: Vec<(usize, usize)> = Vec::new();
+    let mut previous_text_id = None;
+    let mut covered_until = 0;
+
+    for (text_id, range) in matched_ranges {
+        if previous_text_id != Some(text_id) {
+            targets.push((text_id, 0));
+            previous_text_id = Some(text_id);
+            covered_until = 0;
+        }
+
+        let start = range.start.max(covered_until);
+
+        if range.end > start {
+            targets.last_mut().unwrap().1 += range.end - start;
+            covered_until = range.end;
+        }
+    }
+
+    targets.sort_by_key(|&(text_id, num_covered)| (Reverse(num_covered), text_id));
+
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    #[test]
+    fn targets_and_gaps() {
+        let texts = [b"AAACCCGGG".as_slice(), b"CCCGGGTTTT", b"TTTACGT"];
+        let index = FmIndexConfig::<i32>::new().construct_index(texts, alphabet::ascii_dna());
+
+        let reports =
+            index.containment_report([b"CCCGGGTTTTAAAAAAGACA".as_slice(), b"AAACCCGTTTACG"], 4);
+
+        // the match in the second text contains the one in the first text, so the first text is not reported
+        assert_eq!(reports[0].query_len, 20);
+        assert_eq!(reports[0].num_covered_positions, 11);
+        assert_eq!(reports[0].targets, [(1, 10), (2, 4)]);
+        assert_eq!(reports[0].gaps, vec![11..20]);
+        assert_eq!(reports[0].covered_fraction(), 0.55);
+
+        // the match "CGT" of the third text is too short
+        assert_eq!(reports[1].num_covered_positions, 13);
+        assert_eq!(reports[1].targets, [(0, 7), (2, 6), (1, 4)]);
+        assert!(reports[1].gaps.is_empty());
+    }
+
+    #[test]
+    fn zero_min_len() {
+        let index = FmIndexConfig::<i32>::new()
+            .construct_index([b"AAAA".as_slice(), b"CCCC"], alphabet::ascii_dna_with_n());
+
+        let reports = index.containment_report([b"ANNA".as_slice(), b"NNNN"], 0);
+
+        assert_eq!(
+            reports,
+            index.containment_report([b"ANNA".as_slice(), b"NNNN"], 1)
+        );
+        assert_eq!(reports[0].num_covered_positions, 2);
+        assert_eq!(reports[0].targets, [(0, 2)]);
+        assert_eq!(reports[0].gaps, vec![1..3]);
+        assert!(reports[1].targets.is_empty());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn coverage_and_gaps_agree_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..40), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 0..30),
+            min_len in 0usize..6,
+        ) {
+            let index = FmIndexConfig::<i32>::new().construct_index(&texts, alphabet::ascii_dna_with_n());
+
+            let occurs = |range: std::ops::Range<usize>| {
+                !query[range.clone()].contains(&b'N') && index.count(&query[range]) > 0
+            };
+
+            let covered: Vec<_> = (0..query.len())
+                .map(|position| {
+                    (0..=position).any(|start| {
+                        let end = (start + min_len.max(1)).max(position + 1);
+                        end <= query.len() && occurs(start..end)
+                    })
+                })
+                .collect();
+
+            let report = index.containment_report([&query], min_len).remove(0);
+
+            prop_assert_eq!(report.num_covered_positions, covered.iter().filter(|c| **c).count());
+
+            let mut gap_positions = vec![false; query.len()];
+
+            for gap in &report.gaps {
+                prop_assert!(!gap.is_empty());
+                gap_positions[gap.clone()].fill(true);
+            }
+
+            prop_assert!(gap_positions.iter().zip(&covered).all(|(gap, covered)| gap != covered));
+            prop_assert!(report.gaps.windows(2).all(|gaps| gaps[0].len() >= gaps[1].len()));
+
+            for &(text_id, num_covered) in &report.targets {
+                prop_assert!(text_id < texts.len());
+                prop_assert!(num_covered > 0 && num_covered <= report.num_covered_positions);
+            }
+        }
+    }
+}
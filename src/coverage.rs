@@ -0,0 +1,273 @@
+use std::ops::Range;
+
+use crate::{FmIndex, Hit, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+/// Describes which parts of a query are covered by exact matches of length `k` in an index.
+///
+/// It is the result of [`FmIndex::coverage`](crate::FmIndex::coverage) and can be used as a quick estimate of how
+/// much of the query is contained in the indexed texts, similar to the containment score of Mash screen
+/// or sourmash.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryCoverage {
+    k: usize,
+    kmer_matches: Vec<bool>,
+    covered_positions: Vec<bool>,
+}
+
+impl QueryCoverage {
+    pub(crate) fn new(k: usize, query_len: usize, kmer_matches: Vec<bool>) -> Self {
+        let mut covered_positions = vec![false; query_len];
+        let mut covered_until = 0;
+
+        for (kmer_start, _) in kmer_matches.iter().enumerate().filter(|(_, m)| **m) {
+            let covered_from = covered_until.max(kmer_start);
+            covered_until = kmer_start + k;
+            covered_positions[covered_from..covered_until].fill(true);
+        }
+
+        Self {
+            k,
+            kmer_matches,
+            covered_positions,
+        }
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Contains an entry for every k-mer of the query, in order of their start positions.
+    /// An entry is `true` if the respective k-mer occurs in the indexed texts.
+    pub fn kmer_matches(&self) -> &[bool] {
+        &self.kmer_matches
+    }
+
+    pub fn num_kmers(&self) -> usize {
+        self.kmer_matches.len()
+    }
+
+    pub fn num_matching_kmers(&self) -> usize {
+        self.kmer_matches.iter().filter(|m| **m).count()
+    }
+
+    /// The fraction of k-mers of the query that occur in the indexed texts. Returns `0.0` if the query
+    /// is shorter than `k`.
+    pub fn containment(&self) -> f64 {
+        if self.num_kmers() == 0 {
+            0.0
+        } else {
+            self.num_matching_kmers() as f64 / self.num_kmers() as f64
+        }
+    }
+
+    /// Contains an entry for every position of the query. An entry is `true` if the position is
+    /// part of at least one k-mer that occurs in the indexed texts.
+    pub fn covered_positions(&self) -> &[bool] {
+        &self.covered_positions
+    }
+
+    pub fn num_covered_positions(&self) -> usize {
+        self.covered_positions.iter().filter(|c| **c).count()
+    }
+
+    /// The fraction of positions of the query that are covered by matching k-mers. Returns `0.0` for
+    /// an empty query.
+    pub fn covered_fraction(&self) -> f64 {
+        if self.covered_positions.is_empty() {
+            0.0
+        } else {
+            self.num_covered_positions() as f64 / self.covered_positions.len() as f64
+        }
+    }
+
+    /// Returns the maximal ranges of consecutive covered positions of the query, in ascending order.
+    pub fn covered_ranges(&self) -> impl Iterator<Item = Range<usize>> {
+        let mut position = 0;
+
+        std::iter::from_fn(move || {
+            let start = position + self.covered_positions[position..].iter().position(|c| *c)?;
+
+            let end = start
+                + self.covered_positions[start..]
+                    .iter()
+                    .position(|c| !*c)
+                    .unwrap_or(self.covered_positions.len() - start);
+
+            position = end;
+
+            Some(start..end)
+        })
+    }
+}
+
+/// Accumulates how often every position of the indexed texts is covered by hits, across many queries.
+///
+/// The hits can be added directly by [`FmIndex::locate_many_into_coverage`], such that they are never
+/// materialized all at once. Hits from other sources can be added using [`Self::add_hits`].
+///
+/// Internally, a difference array is stored, using 4 bytes per position of the texts. Adding a hit takes
+/// constant time, independent of its length. The coverage values are computed when they are read.
+///
+/// ```
+/// use genedex::{CoverageTracks, FmIndexConfig, alphabet};
+///
+/// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGA"], alphabet::ascii_dna());
+///
+/// let mut tracks = CoverageTracks::new(&index);
+/// index.locate_many_into_coverage([b"ACG".as_slice(), b"CGT"], &mut tracks);
+///
+/// assert_eq!(tracks.coverage(0), [1, 2, 2, 1, 1, 1, 1, 0]);
+/// assert_eq!(tracks.run_length_track(0), [(0..1, 1), (1..3, 2), (3..7, 1)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageTracks {
+    // per text, the coverage at position i is the (wrapping) sum of the first i + 1 entries
+    differences: Vec<Vec<u32>>,
+}
+
+impl CoverageTracks {
+    /// Creates empty tracks for all texts of `index`.
+    pub fn new<I: IndexStorage, R: TextWithRankSupport<I>>(index: &FmIndex<I, R>) -> Self {
+        let differences = (0..index.num_texts())
+            .map(|text_id| vec![0; index.text_len(text_id) + 1])
+            .collect();
+
+        Self { differences }
+    }
+
+    pub fn num_texts(&self) -> usize {
+        self.differences.len()
+    }
+
+    pub fn text_len(&self, text_id: usize) -> usize {
+        self.differences[text_id].len() - 1
+    }
+
+    /// Adds a hit that covers `len` positions. The covered range is clipped at the end of the text.
+    pub fn add_hit(&mut self, hit: Hit, len: usize) {
+        let differences = &mut self.differences[hit.text_id];
+        let text_len = differences.len() - 1;
+
+        assert!(hit.position <= text_len, "hit position is out of bounds");
+
+        let end = hit.position.saturating_add(len).min(text_len);
+
+        differences[hit.position] = differences[hit.position].wrapping_add(1);
+        differences[end] = differences[end].wrapping_sub(1);
+    }
+
+    /// Adds all hits, each of which covers `len` positions. See [`Self::add_hit`].
+    pub fn add_hits(&mut self, hits: impl IntoIterator<Item = Hit>, len: usize) {
+        for hit in hits {
+            self.add_hit(hit, len);
+        }
+    }
+
+    /// Returns the coverage of every position of the text with id `text_id`.
+    pub fn coverage(&self, text_id: usize) -> Vec<u32> {
+        let differences = &self.differences[text_id];
+
+        differences[..differences.len() - 1]
+            .iter()
+            .scan(0u32, |coverage, &difference| {
+                *coverage = coverage.wrapping_add(difference);
+                Some(*coverage)
+            })
+            .collect()
+    }
+
+    /// Returns the maximal ranges of positions of the text with id `text_id` that have the same, non-zero
+    /// coverage, in ascending order. This is similar to the bedGraph format.
+    pub fn run_length_track(&self, text_id: usize) -> Vec<(Range<usize>, u32)> {
+        let mut track: Vec<(Range<usize>, u32)> = Vec::new();
+
+        for (position, coverage) in self.coverage(text_id).into_iter().enumerate() {
+            if coverage == 0 {
+                continue;
+            }
+
+            match track.last_mut() {
+                Some((range, last_coverage))
+                    if range.end == position && *last_coverage == coverage =>
+                {
+                    range.end += 1;
+                }
+                _ => track.push((position..position + 1, coverage)),
+            }
+        }
+
+        track
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_kmers() {
+        // query of length 10 with k = 3 -> 8 k-mers
+        let kmer_matches = vec![true, true, false, false, false, true, false, false];
+        let coverage = QueryCoverage::new(3, 10, kmer_matches);
+
+        assert_eq!(coverage.num_kmers(), 8);
+        assert_eq!(coverage.num_matching_kmers(), 3);
+        assert_eq!(coverage.containment(), 3.0 / 8.0);
+
+        assert_eq!(
+            coverage.covered_positions(),
+            [
+                true, true, true, true, false, true, true, true, false, false
+            ]
+        );
+        assert_eq!(coverage.num_covered_positions(), 7);
+        assert_eq!(coverage.covered_ranges().collect::<Vec<_>>(), [0..4, 5..8]);
+    }
+
+    #[test]
+    fn query_shorter_than_k() {
+        let coverage = QueryCoverage::new(5, 3, Vec::new());
+
+        assert_eq!(coverage.containment(), 0.0);
+        assert_eq!(coverage.covered_fraction(), 0.0);
+        assert_eq!(coverage.covered_ranges().count(), 0);
+    }
+
+    #[test]
+    fn coverage_tracks_agree_with_naive() {
+        use crate::{FmIndexConfig, alphabet};
+
+        let texts = [b"ACGTACGTTTACG".as_slice(), b"", b"GGACGACGT"];
+        let queries = [b"ACG".as_slice(), b"CGT", b"T", b"ACGTAC", b"GAC"];
+
+        let index = FmIndexConfig::<i32>::new().construct_index(texts, alphabet::ascii_dna());
+
+        let mut tracks = CoverageTracks::new(&index);
+        index.locate_many_into_coverage(queries, &mut tracks);
+
+        let mut expected: Vec<_> = texts.iter().map(|text| vec![0u32; text.len()]).collect();
+
+        for query in queries {
+            for hit in index.locate(query) {
+                for coverage in &mut expected[hit.text_id][hit.position..hit.position + query.len()]
+                {
+                    *coverage += 1;
+                }
+            }
+        }
+
+        assert_eq!(tracks.num_texts(), texts.len());
+
+        for (text_id, expected_coverage) in expected.iter().enumerate() {
+            assert_eq!(tracks.text_len(text_id), texts[text_id].len());
+            assert_eq!(&tracks.coverage(text_id), expected_coverage);
+
+            let mut decompressed = vec![0; texts[text_id].len()];
+            for (range, coverage) in tracks.run_length_track(text_id) {
+                decompressed[range].fill(coverage);
+            }
+
+            assert_eq!(&decompressed, expected_coverage);
+        }
+    }
+}
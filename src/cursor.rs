@@ -1,5 +1,6 @@
 use crate::{
-    FmIndex, HalfOpenInterval, Hit, IndexStorage, text_with_rank_support::TextWithRankSupport,
+    FmIndex, HalfOpenInterval, Hit, HitSet, IndexStorage, pattern,
+    text_with_rank_support::TextWithRankSupport,
 };
 
 /// A cursor to the FM-Index.
@@ -38,16 +39,9 @@ impl<'a, I: IndexStorage, R: TextWithRankSupport<I>> Cursor<'a, I, R> {
     }
 
     pub(crate) fn extend_front_without_alphabet_translation(&mut self, symbol: u8) {
-        let (start, end) = if self.interval.start != self.interval.end {
-            (
-                self.index.lf_mapping_step(symbol, self.interval.start),
-                self.index.lf_mapping_step(symbol, self.interval.end),
-            )
-        } else {
-            (self.interval.start, self.interval.end)
-        };
-
-        self.interval = HalfOpenInterval { start, end };
+        if self.interval.start != self.interval.end {
+            self.interval = self.index.lf_mapping_step_interval(symbol, self.interval);
+        }
     }
 
     // returns half open interval [start, end)
@@ -59,16 +53,256 @@ impl<'a, I: IndexStorage, R: TextWithRankSupport<I>> Cursor<'a, I, R> {
     ///
     /// The running time is in O(1).
     pub fn count(&self) -> usize {
-        self.interval.end - self.interval.start
+        let interval = self.index.reported_interval(self.interval);
+
+        interval.end - interval.start
+    }
+
+    /// Returns the counts that extending the currently searched query at the front by each searchable symbol
+    /// of the alphabet would yield, without changing the cursor.
+    ///
+    /// The symbols are returned in IO representation (the first IO symbol of every dense symbol, see
+    /// [`Alphabet`](crate::Alphabet)), ordered by their dense representation. Symbols that don't occur in front of
+    /// the query are included with a count of `0`. This is useful for greedy traversals of the index and for
+    /// statistical models of the texts, e.g. to estimate the probability of the next symbol.
+    ///
+    /// The running time is in O(σ), where σ is the number of symbols of the alphabet.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGAAG"], alphabet::ascii_dna());
+    ///
+    /// let cursor = index.cursor_for_query(b"G");
+    ///
+    /// assert_eq!(
+    ///     cursor.count_per_symbol(),
+    ///     [(b'A', 1), (b'C', 2), (b'G', 0), (b'T', 0)]
+    /// );
+    /// ```
+    pub fn count_per_symbol(&self) -> Vec<(u8, usize)> {
+        self.extend_all()
+            .into_iter()
+            .map(|(symbol, cursor)| (symbol, cursor.count()))
+            .collect()
+    }
+
+    /// Returns the cursors that result from extending the currently searched query at the front by each searchable
+    /// symbol of the alphabet, without changing this cursor.
+    ///
+    /// The symbols are returned like for [`count_per_symbol`](Self::count_per_symbol), including the ones that
+    /// lead to cursors without occurrences. This is the basic step of branching searches, such as the enumeration
+    /// of all k-mers of the texts. The ranks of all symbols are computed together, which is faster than calling
+    /// [`extend_query_front`](Self::extend_query_front) for each symbol. If the query occurs only once, only the
+    /// symbol in front of the occurrence is looked up.
+    ///
+    /// The running time is in O(σ), where σ is the number of symbols of the alphabet.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGAAG"], alphabet::ascii_dna());
+    ///
+    /// let children: Vec<_> = index
+    ///     .cursor_for_query(b"G")
+    ///     .extend_all()
+    ///     .into_iter()
+    ///     .filter(|(_, cursor)| cursor.count() > 0)
+    ///     .collect();
+    ///
+    /// assert_eq!(children.len(), 2);
+    /// assert_eq!(children[1].0, b'C');
+    /// assert_eq!(children[1].1.count(), index.count(b"CG"));
+    /// ```
+    pub fn extend_all(&self) -> Vec<(u8, Self)> {
+        let alphabet = self.index.alphabet();
+
+        self.extend_all_without_alphabet_translation()
+            .into_iter()
+            .map(|(symbol, cursor)| (alphabet.dense_to_io_representation(symbol), cursor))
+            .collect()
+    }
+
+    pub(crate) fn extend_all_without_alphabet_translation(&self) -> Vec<(u8, Self)> {
+        let intervals = self.index.lf_mapping_step_all(self.interval);
+
+        pattern::searchable_symbols(self.index.alphabet())
+            .map(|symbol| {
+                (
+                    symbol,
+                    Cursor {
+                        index: self.index,
+                        interval: intervals[symbol as usize],
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the cursor for the longest prefix of the currently searched query that occurs more often than the query.
+    ///
+    /// This shortens the query at the back, which corresponds to moving to the parent node in the suffix tree of
+    /// the texts. Together with [`extend_all`](Self::extend_all), this allows traversals of the suffix tree without
+    /// storing it. Returns `None` for the empty query and for queries without occurrences, because they don't
+    /// have a parent.
+    ///
+    /// The query itself is not stored in the cursor. Instead, the length of the prefix is derived from the longest
+    /// common prefixes of the suffixes next to the occurrences, and the prefix is searched again. The running time is
+    /// in O((l + d) · log n), where `l` is the length of the query, `d` is the length of the prefix and `n` is the
+    /// total length of the texts.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGAAG"], alphabet::ascii_dna());
+    ///
+    /// let cursor = index.cursor_for_query(b"ACGT");
+    /// assert_eq!(cursor.count(), 1);
+    ///
+    /// // "ACG" occurs twice, so it is the parent of "ACGT"
+    /// let parent = cursor.parent().unwrap();
+    /// assert_eq!(parent.count(), index.count(b"ACG"));
+    ///
+    /// // "AC" occurs as often as "ACG", but "A" occurs more often
+    /// let grandparent = parent.parent().unwrap();
+    /// assert_eq!(grandparent.count(), index.count(b"A"));
+    ///
+    /// let root = grandparent.parent().unwrap();
+    /// assert_eq!(root.count(), index.cursor_empty().count());
+    /// assert!(root.parent().is_none());
+    /// ```
+    pub fn parent(&self) -> Option<Self> {
+        let HalfOpenInterval { start, end } = self.interval;
+        let total_text_len = self.index.total_text_len();
+
+        if start == end || (start == 0 && end == total_text_len) {
+            return None;
+        }
+
+        // the suffixes next to the interval don't start with the query, but share the longest possible prefix with it
+        let left_len = if start > 0 {
+            self.index.longest_common_prefix_of_rows(start - 1, start)
+        } else {
+            0
+        };
+
+        let right_len = if end < total_text_len {
+            self.index.longest_common_prefix_of_rows(end - 1, end)
+        } else {
+            0
+        };
+
+        let prefix = self.index.prefix_of_row(start, left_len.max(right_len));
+
+        Some(
+            self.index
+                .cursor_for_query_without_alphabet_translation(&prefix),
+        )
     }
 
-    /// Returns the occurrences of `query` in the set of indexed texts. The occurrences are not sorted by text id or position.
+    /// Returns the cursor for the currently searched query without its first symbol.
+    ///
+    /// This corresponds to following a suffix link in the suffix tree of the texts. The query itself is not stored
+    /// in the cursor, so its length `query_len` has to be given. Returns `None` for the empty query and for queries
+    /// without occurrences, because the shortened query can't be derived from them.
+    ///
+    /// The query is read from its first occurrence using the inverse of the LF-mapping, and the shortened query
+    /// is searched again from scratch. The running time is therefore in O(l · log n), where `l` is the length of
+    /// the query and `n` is the total length of the texts. This is not the constant-time suffix link of a
+    /// compressed suffix tree, so sliding a window of length `l` over a query of length `m` takes O(m · l · log n)
+    /// time instead of O(m).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `query_len` doesn't match the cursor, i.e. if the first `query_len` symbols of its first
+    /// occurrence don't lead to the interval of the cursor. A length that selects the same interval as the
+    /// searched query can't be told apart from it and is accepted.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGAAG"], alphabet::ascii_dna());
+    ///
+    /// let cursor = index.cursor_for_query(b"TACG");
+    /// assert_eq!(cursor.count(), 1);
+    ///
+    /// let shortened = cursor.drop_front(4).unwrap();
+    /// assert_eq!(shortened.count(), index.count(b"ACG"));
+    ///
+    /// let root = index.cursor_for_query(b"G").drop_front(1).unwrap();
+    /// assert_eq!(root.count(), index.cursor_empty().count());
+    /// ```
+    pub fn drop_front(&self, query_len: usize) -> Option<Self> {
+        let HalfOpenInterval { start, end } = self.interval;
+
+        if start == end || query_len == 0 {
+            return None;
+        }
+
+        // the query is read symbol by symbol, because the inverse LF-mapping must not step over a sentinel
+        let mut query = Vec::with_capacity(query_len);
+        let mut row = start;
+
+        for _ in 0..query_len {
+            let symbol = self.index.first_symbol_of_row(row);
+            assert!(
+                symbol != 0,
+                "query_len {query_len} is larger than the length of the currently searched query"
+            );
+
+            query.push(symbol);
+            row = self.index.inverse_lf_mapping_step(row);
+        }
+
+        let shortened = self
+            .index
+            .cursor_for_query_without_alphabet_translation(&query[1..]);
+
+        // extending the shortened query by the dropped symbol has to lead back to this cursor
+        let mut restored = shortened;
+        restored.extend_front_without_alphabet_translation(query[0]);
+        assert!(
+            restored.interval == self.interval,
+            "query_len {query_len} is not the length of the currently searched query"
+        );
+
+        Some(shortened)
+    }
+
+    /// Returns the occurrences of `query` in the set of indexed texts. The occurrences are not sorted by text id or position,
+    /// but in suffix array order (see [`FmIndex::locate`]).
     ///
     /// The initial running time is in O(1).
-    /// For each hit pulled from the iterator, a sampled suffix array lookup is performed.
+    /// For each hit, a sampled suffix array lookup is performed.
     /// This operation needs `s / 2` steps on average, where `s` is the suffix array
-    /// sampling rate of the index.
+    /// sampling rate of the index. The hits are resolved in batches of growing size and the memory usage of the
+    /// iterator does not depend on the number of occurrences (see [`FmIndex::locate`]).
     pub fn locate(&self) -> impl Iterator<Item = Hit> {
-        self.index.locate_interval(self.interval)
+        self.index
+            .locate_interval(self.index.reported_interval(self.interval))
+    }
+
+    /// The results of [`Self::locate`], annotated with the suffix array index of each hit.
+    ///
+    /// See [`FmIndex::locate_with_sa_index`] for details.
+    pub fn locate_with_sa_index(&self) -> impl Iterator<Item = (usize, Hit)> + use<'a, I, R> {
+        let interval = self.index.reported_interval(self.interval);
+
+        (interval.start..interval.end)
+            .zip(
+                self.index
+                    .locate_interval_including_disabled_texts(interval),
+            )
+            .filter(|(_, hit)| !self.index.disabled_texts.contains(hit.text_id))
+    }
+
+    /// Returns a lazy handle to the occurrences of the currently searched query.
+    ///
+    /// The running time is in O(1). See [`HitSet`] for details.
+    pub fn hit_set(&self) -> HitSet<'a, I, R> {
+        HitSet {
+            index: self.index,
+            interval: self.index.reported_interval(self.interval),
+        }
     }
 }
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+// The distinct queries of a query set, and for every query of the set, the id of its distinct query.
+pub(crate) struct DeduplicatedQueries {
+    // index of the first occurrence in the original queries for every distinct query
+    pub(crate) first_occurrences: Vec<usize>,
+    pub(crate) distinct_ids: Vec<usize>,
+}
+
+impl DeduplicatedQueries {
+    pub(crate) fn new<Q: AsRef<[u8]>>(queries: &[Q]) -> Self {
+        let mut distinct_ids_by_query: HashMap<&[u8], usize> = HashMap::new();
+        let mut first_occurrences = Vec::new();
+
+        let distinct_ids = queries
+            .iter()
+            .enumerate()
+            .map(|(i, query)| {
+                *distinct_ids_by_query
+                    .entry(query.as_ref())
+                    .or_insert_with(|| {
+                        first_occurrences.push(i);
+                        first_occurrences.len() - 1
+                    })
+            })
+            .collect();
+
+        Self {
+            first_occurrences,
+            distinct_ids,
+        }
+    }
+
+    pub(crate) fn distinct_queries<'a, Q: AsRef<[u8]>>(
+        &self,
+        queries: &'a [Q],
+    ) -> impl Iterator<Item = &'a [u8]> {
+        self.first_occurrences.iter().map(|&i| queries[i].as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fan_out_restores_original_queries() {
+        let queries = [b"ACGT".as_slice(), b"AA", b"ACGT", b"", b"AA", b"ACGT", b""];
+
+        let deduplicated = DeduplicatedQueries::new(&queries);
+        let distinct: Vec<_> = deduplicated.distinct_queries(&queries).collect();
+
+        assert_eq!(distinct, [b"ACGT".as_slice(), b"AA", b""]);
+        assert_eq!(deduplicated.distinct_ids, [0, 1, 0, 2, 1, 0, 2]);
+
+        for (query, &distinct_id) in queries.iter().zip(&deduplicated.distinct_ids) {
+            assert_eq!(*query, distinct[distinct_id]);
+        }
+    }
+}
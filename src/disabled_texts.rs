@@ -0,0 +1,97 @@
+// A set of text ids whose hits are hidden from the results of queries. It is only kept in memory and not
+// stored together with the index.
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DisabledTexts {
+    words: Vec<u64>,
+    num_disabled: usize,
+}
+
+// the field of the index is ignored by savefile, but the derive still checks the memory layout of all fields
+#[cfg(feature = "savefile")]
+impl savefile::Packed for DisabledTexts {}
+
+impl DisabledTexts {
+    pub(crate) fn heap_size_bytes(&self) -> usize {
+        size_of_val(self.words.as_slice())
+    }
+
+    // returns false if the text was already disabled
+    pub(crate) fn insert(&mut self, text_id: usize) -> bool {
+        let word_idx = text_id / 64;
+
+        if word_idx >= self.words.len() {
+            self.words.resize(word_idx + 1, 0);
+        }
+
+        let mask = 1 << (text_id % 64);
+        let was_disabled = self.words[word_idx] & mask != 0;
+
+        self.words[word_idx] |= mask;
+        self.num_disabled += !was_disabled as usize;
+
+        !was_disabled
+    }
+
+    // returns false if the text was not disabled
+    pub(crate) fn remove(&mut self, text_id: usize) -> bool {
+        let was_disabled = self.contains(text_id);
+
+        if was_disabled {
+            self.words[text_id / 64] &= !(1 << (text_id % 64));
+            self.num_disabled -= 1;
+        }
+
+        was_disabled
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.words.clear();
+        self.num_disabled = 0;
+    }
+
+    pub(crate) fn contains(&self, text_id: usize) -> bool {
+        self.num_disabled > 0
+            && self
+                .words
+                .get(text_id / 64)
+                .is_some_and(|word| (word >> (text_id % 64)) & 1 == 1)
+    }
+
+    // in ascending order
+    pub(crate) fn iter(&self) -> impl Iterator<Item = usize> {
+        self.words.iter().enumerate().flat_map(|(word_idx, &word)| {
+            (0..64)
+                .filter(move |bit_idx| (word >> bit_idx) & 1 == 1)
+                .map(move |bit_idx| word_idx * 64 + bit_idx)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_remove_and_clear() {
+        let mut disabled_texts = DisabledTexts::default();
+
+        assert!(!disabled_texts.contains(3));
+        assert!(disabled_texts.insert(130));
+        assert!(disabled_texts.insert(3));
+        assert!(!disabled_texts.insert(3));
+
+        assert!(disabled_texts.contains(3));
+        assert!(!disabled_texts.contains(4));
+        assert!(!disabled_texts.contains(1000));
+        assert_eq!(disabled_texts.iter().collect::<Vec<_>>(), [3, 130]);
+
+        assert!(disabled_texts.remove(130));
+        assert!(!disabled_texts.remove(130));
+        assert_eq!(disabled_texts.iter().collect::<Vec<_>>(), [3]);
+
+        disabled_texts.clear();
+        assert!(!disabled_texts.contains(3));
+        assert_eq!(disabled_texts.iter().count(), 0);
+    }
+}
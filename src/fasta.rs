@@ -0,0 +1,50 @@
+use std::io::BufRead;
+
+// A minimal FASTA parser that only retains the names and sequences of the records. The name is the first word
+// of the header, like in SAM files. Sequences may span multiple lines. Lines before the first header are ignored,
+// as well as empty lines and trailing whitespace.
+pub(crate) fn read_records(reader: impl BufRead) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+    let mut records = Vec::new();
+    let mut current_record = None;
+
+    for line in reader.split(b'\n') {
+        let line = line?;
+        let line = line.trim_ascii_end();
+
+        if let Some(header) = line.strip_prefix(b">") {
+            let name = header
+                .split(u8::is_ascii_whitespace)
+                .next()
+                .unwrap_or_default();
+
+            records.extend(current_record.take());
+            current_record = Some((String::from_utf8_lossy(name).into_owned(), Vec::new()));
+        } else if let Some((_, sequence)) = &mut current_record {
+            sequence.extend_from_slice(line);
+        }
+    }
+
+    records.extend(current_record);
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiline_records() {
+        let fasta = b"ignored\n>first record\nACGT\nAC\r\n\n>second\n>third\nTTTT\nG";
+        let records = read_records(fasta.as_slice()).unwrap();
+
+        assert_eq!(
+            records,
+            [
+                ("first".to_string(), b"ACGTAC".to_vec()),
+                ("second".to_string(), Vec::new()),
+                ("third".to_string(), b"TTTTG".to_vec())
+            ]
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::ops::Range;
+
+// Maps the texts of the index to the files they were read from. The texts of a file always
+// have consecutive text ids. If the index was not created from files, all texts belong to a single file.
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FileGroups {
+    // the first text id of every file, except for the first file
+    file_starts: Vec<usize>,
+}
+
+impl FileGroups {
+    pub(crate) fn single_file() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn heap_size_bytes(&self) -> usize {
+        size_of_val(self.file_starts.as_slice())
+    }
+
+    pub(crate) fn from_num_texts_per_file(
+        num_texts_per_file: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        let mut file_starts: Vec<_> = num_texts_per_file
+            .into_iter()
+            .scan(0, |start, num_texts| {
+                *start += num_texts;
+                Some(*start)
+            })
+            .collect();
+
+        // the end of the last file is not the start of another file
+        file_starts.pop();
+
+        Self { file_starts }
+    }
+
+    pub(crate) fn num_files(&self) -> usize {
+        self.file_starts.len() + 1
+    }
+
+    pub(crate) fn file_id(&self, text_id: usize) -> usize {
+        self.file_starts.partition_point(|&start| start <= text_id)
+    }
+
+    pub(crate) fn text_ids(&self, file_id: usize, num_texts: usize) -> Range<usize> {
+        assert!(file_id < self.num_files(), "file id out of bounds");
+
+        let start = if file_id == 0 {
+            0
+        } else {
+            self.file_starts[file_id - 1]
+        };
+        let end = self.file_starts.get(file_id).copied().unwrap_or(num_texts);
+
+        start..end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_files() {
+        let groups = FileGroups::from_num_texts_per_file([2, 0, 3, 0]);
+
+        assert_eq!(groups.num_files(), 4);
+
+        let file_ids: Vec<_> = (0..5).map(|text_id| groups.file_id(text_id)).collect();
+        assert_eq!(file_ids, [0, 0, 2, 2, 2]);
+
+        assert_eq!(groups.text_ids(0, 5), 0..2);
+        assert_eq!(groups.text_ids(1, 5), 2..2);
+        assert_eq!(groups.text_ids(2, 5), 2..5);
+        assert_eq!(groups.text_ids(3, 5), 5..5);
+    }
+}
@@ -0,0 +1,521 @@
+use std::fmt;
+
+use crate::{
+    Alphabet, Cursor, FmIndex, FmIndexConfig, HalfOpenInterval, Hit, IndexStorage, Mem, Smem, mems,
+    smem,
+    text_with_rank_support::{Block64, CondensedTextWithRankSupport, TextWithRankSupport},
+};
+
+/// The strand of the query that a match was found for.
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Strand {
+    Forward,
+    /// The reverse complement of the query matched.
+    Reverse,
+}
+
+/// An occurrence of a query on either strand of an indexed text, found using an [`FmdIndex`].
+///
+/// For hits on the [`Reverse`](Strand::Reverse) strand, the reverse complement of the query occurs
+/// at `position` of the (forward) text.
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StrandedHit {
+    pub text_id: usize,
+    pub position: usize,
+    pub strand: Strand,
+}
+
+impl fmt::Display for Strand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strand::Forward => write!(f, "forward"),
+            Strand::Reverse => write!(f, "reverse"),
+        }
+    }
+}
+
+impl fmt::Display for StrandedHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "text {}, position {}, {} strand",
+            self.text_id, self.position, self.strand
+        )
+    }
+}
+
+/// Drops the strand of the hit.
+impl From<StrandedHit> for Hit {
+    fn from(hit: StrandedHit) -> Self {
+        Hit {
+            text_id: hit.text_id,
+            position: hit.position,
+        }
+    }
+}
+
+/// An FM-Index of DNA texts and their reverse complements, also known as FMD-Index ([original paper]).
+///
+/// Since the set of indexed texts is closed under reverse complementation, the suffix array interval of a query and the
+/// interval of its reverse complement always have the same size. The [`FmdCursor`] maintains both intervals, which allows
+/// extending the searched query at the front and at the back, and finding occurrences on both strands in a single search.
+///
+/// The index can only be constructed for alphabets that define the complement of every symbol, like the DNA alphabets
+/// of this library and all alphabets that only consist of nucleotides (see [`Alphabet::with_complement`]).
+/// It needs twice the memory of an FM-Index of the texts.
+///
+/// ```
+/// use genedex::{FmIndexConfig, Strand, alphabet};
+///
+/// let index = FmIndexConfig::<i32>::new().construct_fmd_index([b"AACGTT"], alphabet::ascii_dna());
+///
+/// let mut cursor = index.cursor_for_query(b"C");
+/// cursor.extend_query_back(b'G');
+/// cursor.extend_query_front(b'A');
+///
+/// assert_eq!(cursor.count(), 2);
+///
+/// let mut hits: Vec<_> = cursor.locate().collect();
+/// hits.sort();
+///
+/// assert_eq!(hits[0].strand, Strand::Forward);
+/// assert_eq!(hits[1].strand, Strand::Reverse);
+/// assert_eq!(hits[1].position, 2);
+/// ```
+///
+/// [original paper]: https://doi.org/10.1093/bioinformatics/bts280
+#[derive(Clone)]
+pub struct FmdIndex<I, R = CondensedTextWithRankSupport<I, Block64>> {
+    // text 2 * i is the original text i, text 2 * i + 1 is its reverse complement
+    index: FmIndex<I, R>,
+    // dense representation, the sentinel is its own complement
+    complement: Vec<u8>,
+}
+
+impl<I: IndexStorage, R: TextWithRankSupport<I>> FmdIndex<I, R> {
+    pub(crate) fn new<T: AsRef<[u8]>>(
+        texts: impl IntoIterator<Item = T>,
+        alphabet: Alphabet,
+        config: FmIndexConfig<I, R>,
+    ) -> Self {
+        let complement = alphabet
+            .dense_complement()
+            .expect("the FMD-Index can only be constructed for alphabets with a complement")
+            .to_vec();
+
+        let texts_and_reverse_complements: Vec<_> = texts
+            .into_iter()
+            .flat_map(|text| {
+                let text = text.as_ref();
+
+                [text.to_vec(), alphabet.reverse_complement(text)]
+            })
+            .collect();
+
+        let index = FmIndex::new(texts_and_reverse_complements, alphabet, config);
+
+        Self { index, complement }
+    }
+
+    /// Returns the number of occurrences of `query` on both strands of the indexed texts.
+    ///
+    /// The running time is the same as for [`FmIndex::count`].
+    pub fn count(&self, query: &[u8]) -> usize {
+        self.index.count(query)
+    }
+
+    /// Returns the occurrences of `query` on both strands of the indexed texts, in suffix array order.
+    ///
+    /// The running time is the same as for [`FmIndex::locate`].
+    pub fn locate(&self, query: &[u8]) -> impl Iterator<Item = StrandedHit> {
+        let query_len = query.len();
+
+        self.index
+            .locate(query)
+            .map(move |hit| self.stranded_hit(hit, query_len))
+    }
+
+    /// Returns the super-maximal exact matches (SMEMs) between `query` and the indexed texts that are at least
+    /// `min_len` symbols long, sorted by their start in the query.
+    ///
+    /// The SMEMs are found using the algorithm of the FMD-Index paper, which is also used by BWA-MEM. Symbols of the
+    /// query that are not searchable never match. See [`Smem`] for details.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_fmd_index([b"AACCGGTTAC"], alphabet::ascii_dna());
+    ///
+    /// let smems = index.find_smems(b"CCGGATTAC", 3);
+    ///
+    /// assert_eq!(smems.len(), 2);
+    /// assert_eq!(smems[0].query_range, 0..4);
+    /// assert_eq!(smems[1].query_range, 5..9);
+    /// ```
+    pub fn find_smems(&self, query: &[u8], min_len: usize) -> Vec<Smem> {
+        smem::find_smems(self, query, min_len)
+    }
+
+    /// Returns the maximal exact matches (MEMs) between `query` and the indexed texts that are at least `min_len`
+    /// symbols long and have at most `max_occ` maximal occurrences.
+    ///
+    /// The MEMs are computed lazily while the iterator is advanced, and their occurrences can be located lazily
+    /// using [`Self::locate_mem`]. This allows implementing custom seeding strategies without keeping the
+    /// occurrences of repetitive matches in memory. The MEMs are sorted by their end in the query, and MEMs with
+    /// the same end by descending start. For every end position, the matches are searched by backward search,
+    /// so the running time is in O(`query.len()` * `L` * σ), where `L` is the length of the longest match and σ is
+    /// the number of dense symbols of the alphabet. See [`Mem`] for details.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_fmd_index([b"AACCGGTTAC"], alphabet::ascii_dna());
+    ///
+    /// let ranges: Vec<_> = index.mems(b"CCGGATTAC", 2, 10).map(|mem| mem.query_range).collect();
+    ///
+    /// assert_eq!(ranges, [0..4, 5..7, 6..8, 7..9, 5..9]);
+    /// ```
+    pub fn mems(
+        &self,
+        query: &[u8],
+        min_len: usize,
+        max_occ: usize,
+    ) -> impl Iterator<Item = Mem> + use<'_, I, R> {
+        mems::mems(self, query, min_len, max_occ)
+    }
+
+    /// Returns the occurrences of a MEM that was found using this index, where the match is maximal.
+    ///
+    /// See [`Self::locate`] for the running time.
+    pub fn locate_mem(&self, mem: &Mem) -> impl Iterator<Item = StrandedHit> {
+        mems::locate_mem(self, mem)
+    }
+
+    /// Returns the occurrences of an SMEM that was found using this index.
+    ///
+    /// See [`Self::locate`] for the running time.
+    pub fn locate_smem(&self, smem: &Smem) -> impl Iterator<Item = StrandedHit> {
+        let query_len = smem.query_range.len();
+
+        self.index
+            .locate_interval(HalfOpenInterval {
+                start: smem.sa_interval.start,
+                end: smem.sa_interval.end,
+            })
+            .map(move |hit| self.stranded_hit(hit, query_len))
+    }
+
+    /// Returns a cursor to the index with the empty query currently searched.
+    ///
+    /// See [`FmdCursor`] for details. Running time is in `O(1)`.
+    pub fn cursor_empty<'a>(&'a self) -> FmdCursor<'a, I, R> {
+        FmdCursor {
+            index: self,
+            forward_start: 0,
+            reverse_complement_start: 0,
+            size: self.index.total_text_len(),
+            query_len: 0,
+        }
+    }
+
+    /// Returns a cursor to the index with `query` currently searched.
+    ///
+    /// The query is searched by extending an empty cursor at the front. Unlike [`FmIndex::cursor_for_query`],
+    /// the lookup table of the index can't be used, because it only provides the interval of the query itself.
+    pub fn cursor_for_query<'a>(&'a self, query: &[u8]) -> FmdCursor<'a, I, R> {
+        let mut cursor = self.cursor_empty();
+
+        for &symbol in query.iter().rev() {
+            cursor.extend_query_front(symbol);
+        }
+
+        cursor
+    }
+
+    /// The number of indexed texts, not counting the reverse complements.
+    pub fn num_texts(&self) -> usize {
+        self.index.num_texts() / 2
+    }
+
+    /// The length of the text with id `text_id`, without its sentinel.
+    pub fn text_len(&self, text_id: usize) -> usize {
+        self.index.text_len(2 * text_id)
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        self.index.alphabet()
+    }
+
+    /// The underlying FM-Index. The text with id `i` of the FMD-Index has the id `2 * i` in this index,
+    /// and its reverse complement has the id `2 * i + 1`.
+    pub fn index(&self) -> &FmIndex<I, R> {
+        &self.index
+    }
+
+    /// Returns the underlying FM-Index, see [`Self::index`].
+    pub fn into_index(self) -> FmIndex<I, R> {
+        self.index
+    }
+
+    pub(crate) fn stranded_hit(&self, hit: Hit, query_len: usize) -> StrandedHit {
+        let text_id = hit.text_id / 2;
+
+        if hit.text_id.is_multiple_of(2) {
+            StrandedHit {
+                text_id,
+                position: hit.position,
+                strand: Strand::Forward,
+            }
+        } else {
+            StrandedHit {
+                text_id,
+                position: self.text_len(text_id) - hit.position - query_len,
+                strand: Strand::Reverse,
+            }
+        }
+    }
+}
+
+/// A bidirectional cursor to the [`FmdIndex`].
+///
+/// Like the [`Cursor`], it implicitly maintains a currently searched query. Symbols can be added to the front
+/// and to the back of the query in any order. The occurrences found by the cursor are the occurrences of
+/// the query on both strands of the indexed texts.
+///
+/// Internally, the cursor stores the suffix array intervals of the query and of its reverse complement in the
+/// underlying FM-Index, which always have the same size. Extending at the back is implemented as extending the
+/// reverse complement at the front, as described in the FMD-Index paper.
+pub struct FmdCursor<'a, I, R> {
+    index: &'a FmdIndex<I, R>,
+    forward_start: usize,
+    reverse_complement_start: usize,
+    size: usize,
+    query_len: usize,
+}
+
+// the derive is too restrictive
+impl<'a, I, R> Clone for FmdCursor<'a, I, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, I, R> Copy for FmdCursor<'a, I, R> {}
+
+impl<'a, I: IndexStorage, R: TextWithRankSupport<I>> FmdCursor<'a, I, R> {
+    /// Extends the currently searched query at the front by one symbol.
+    ///
+    /// The running time is in O(σ), where σ is the number of dense symbols of the alphabet, because the sizes
+    /// of the intervals of all possible extensions are needed to update the interval of the reverse complement.
+    pub fn extend_query_front(&mut self, symbol: u8) {
+        let symbol = self.index.alphabet().io_to_dense_representation(symbol);
+
+        self.extend_front_without_alphabet_translation(symbol);
+    }
+
+    /// Extends the currently searched query at the back by one symbol.
+    ///
+    /// The running time is the same as for [`Self::extend_query_front`].
+    pub fn extend_query_back(&mut self, symbol: u8) {
+        let symbol = self.index.alphabet().io_to_dense_representation(symbol);
+
+        self.extend_back_without_alphabet_translation(symbol);
+    }
+
+    pub(crate) fn extend_back_without_alphabet_translation(&mut self, symbol: u8) {
+        self.swap_strands();
+        self.extend_front_without_alphabet_translation(self.index.complement[symbol as usize]);
+        self.swap_strands();
+    }
+
+    pub(crate) fn extend_front_without_alphabet_translation(&mut self, symbol: u8) {
+        self.query_len += 1;
+
+        if self.size == 0 {
+            return;
+        }
+
+        let index = &self.index.index;
+        let complement = &self.index.complement;
+        let start = self.forward_start;
+        let end = self.forward_start + self.size;
+
+        // the reverse complement interval is partitioned by the symbol that follows the reverse complement of the
+        // query. Sentinels come first, then the other symbols in the order of their dense representation.
+        let mut num_non_sentinel = 0;
+        let mut num_before_extension = 0;
+        let mut new_size = 0;
+
+        for other_symbol in 1..index.alphabet.num_dense_symbols() as u8 {
+            let other_size = index.text_with_rank_support.rank(other_symbol, end)
+                - index.text_with_rank_support.rank(other_symbol, start);
+
+            num_non_sentinel += other_size;
+
+            if complement[other_symbol as usize] < complement[symbol as usize] {
+                num_before_extension += other_size;
+            } else if other_symbol == symbol {
+                new_size = other_size;
+            }
+        }
+
+        let num_sentinel = self.size - num_non_sentinel;
+
+        self.forward_start = index.lf_mapping_step(symbol, start);
+        self.reverse_complement_start += num_sentinel + num_before_extension;
+        self.size = new_size;
+    }
+
+    fn swap_strands(&mut self) {
+        std::mem::swap(&mut self.forward_start, &mut self.reverse_complement_start);
+    }
+
+    /// Returns the number of occurrences of the currently searched query on both strands of the indexed texts.
+    ///
+    /// The running time is in O(1).
+    pub fn count(&self) -> usize {
+        self.size
+    }
+
+    /// The length of the currently searched query.
+    pub fn query_len(&self) -> usize {
+        self.query_len
+    }
+
+    /// Returns the occurrences of the currently searched query on both strands of the indexed texts, in suffix
+    /// array order of the query.
+    ///
+    /// See [`Cursor::locate`] for the running time.
+    pub fn locate(&self) -> impl Iterator<Item = StrandedHit> + use<'a, I, R> {
+        let index = self.index;
+        let query_len = self.query_len;
+
+        index
+            .index
+            .locate_interval(self.forward_cursor().interval())
+            .map(move |hit| index.stranded_hit(hit, query_len))
+    }
+
+    /// Returns a [`Cursor`] to the underlying FM-Index (see [`FmdIndex::index`]) with the currently
+    /// searched query.
+    pub fn forward_cursor(&self) -> Cursor<'a, I, R> {
+        Cursor {
+            index: &self.index.index,
+            interval: HalfOpenInterval {
+                start: self.forward_start,
+                end: self.forward_start + self.size,
+            },
+        }
+    }
+
+    /// Returns a [`Cursor`] to the underlying FM-Index (see [`FmdIndex::index`]) with the reverse complement
+    /// of the currently searched query.
+    pub fn reverse_complement_cursor(&self) -> Cursor<'a, I, R> {
+        Cursor {
+            index: &self.index.index,
+            interval: HalfOpenInterval {
+                start: self.reverse_complement_start,
+                end: self.reverse_complement_start + self.size,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet;
+    use proptest::prelude::*;
+
+    fn reverse_complement(text: &[u8]) -> Vec<u8> {
+        text.iter()
+            .rev()
+            .map(|&symbol| match symbol {
+                b'A' => b'T',
+                b'C' => b'G',
+                b'G' => b'C',
+                b'T' => b'A',
+                _ => b'N',
+            })
+            .collect()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn bidirectional_search_agrees_with_fm_index(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..60), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 1..6),
+            extend_back in prop::collection::vec(any::<bool>(), 6),
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .construct_fmd_index(&texts, alphabet::ascii_dna_with_n());
+            let both_strands: Vec<_> = texts
+                .iter()
+                .flat_map(|text| [text.clone(), reverse_complement(text)])
+                .collect();
+            let plain_index = FmIndexConfig::<i32>::new()
+                .construct_index(&both_strands, alphabet::ascii_dna_with_n());
+
+            // build the query from a seed symbol in the middle, extending at both sides in a random order
+            let mut cursor = index.cursor_empty();
+            let (mut front, mut back) = (query.len() / 2, query.len() / 2);
+            cursor.extend_query_back(query[back]);
+            back += 1;
+
+            for &prefer_back in &extend_back {
+                if (prefer_back && back < query.len()) || front == 0 {
+                    if back == query.len() {
+                        break;
+                    }
+                    cursor.extend_query_back(query[back]);
+                    back += 1;
+                } else {
+                    front -= 1;
+                    cursor.extend_query_front(query[front]);
+                }
+            }
+
+            let searched = &query[front..back];
+            prop_assert_eq!(cursor.query_len(), searched.len());
+
+            prop_assert_eq!(cursor.count(), plain_index.count(searched));
+            prop_assert_eq!(cursor.count(), index.count(searched));
+
+            // the positions of empty intervals depend on the search order
+            if cursor.count() > 0 {
+                prop_assert_eq!(
+                    cursor.forward_cursor().interval(),
+                    plain_index.cursor_for_query(searched).interval()
+                );
+                prop_assert_eq!(
+                    cursor.reverse_complement_cursor().interval(),
+                    plain_index.cursor_for_query(&reverse_complement(searched)).interval()
+                );
+            }
+
+            let mut hits: Vec<_> = cursor.locate().collect();
+            hits.sort();
+
+            let mut expected_hits = Vec::new();
+            for (text_id, text) in texts.iter().enumerate() {
+                for (position, window) in text.windows(searched.len()).enumerate() {
+                    if window == searched {
+                        expected_hits.push(StrandedHit { text_id, position, strand: Strand::Forward });
+                    }
+                    if window == reverse_complement(searched) {
+                        expected_hits.push(StrandedHit { text_id, position, strand: Strand::Reverse });
+                    }
+                }
+            }
+            expected_hits.sort();
+
+            prop_assert_eq!(hits, expected_hits);
+        }
+    }
+}
@@ -0,0 +1,397 @@
+use crate::{
+    EmptyQueryMatches, EmptyTextPolicy, FmIndex, Hit, IndexStorage,
+    text_with_rank_support::{Block64, CondensedTextWithRankSupport, TextWithRankSupport},
+};
+
+/// An FM-Index together with a buffer of recently added texts that are not indexed yet.
+///
+/// The FM-Index is a static data structure. This type provides practical "online" behavior by searching
+/// the pending texts naively, in addition to the index. When the buffer becomes large, searching gets slower,
+/// and [`compact`](Self::compact) should be called to rebuild the index with all texts.
+///
+/// New texts get the text ids following the ids of the texts in the index, in the order in which they were added.
+/// These ids do not change when the buffer is compacted.
+///
+/// ```
+/// use genedex::{FmIndexConfig, GrowableFmIndex, alphabet};
+///
+/// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+/// let mut growable = GrowableFmIndex::new(index);
+///
+/// let text_id = growable.add_text(b"TTACGTT");
+/// assert_eq!(text_id, 1);
+/// assert_eq!(growable.count(b"ACGT"), 3);
+///
+/// growable.compact();
+/// assert_eq!(growable.num_pending_texts(), 0);
+/// assert_eq!(growable.count(b"ACGT"), 3);
+/// ```
+#[derive(Clone)]
+pub struct GrowableFmIndex<I, R = CondensedTextWithRankSupport<I, Block64>> {
+    index: FmIndex<I, R>,
+    // in dense representation, such that the search is equivalent to the search in the index
+    pending_texts: Vec<Vec<u8>>,
+}
+
+impl<I: IndexStorage, R: TextWithRankSupport<I>> GrowableFmIndex<I, R> {
+    pub fn new(index: FmIndex<I, R>) -> Self {
+        Self {
+            index,
+            pending_texts: Vec::new(),
+        }
+    }
+
+    /// Adds a text to the buffer of pending texts and returns its text id.
+    ///
+    /// The text must be in IO representation of the alphabet of the index. Panics if it contains
+    /// symbols that are not part of the alphabet, or if it is empty and the [`EmptyTextPolicy`] of the index is
+    /// [`Reject`](EmptyTextPolicy::Reject).
+    pub fn add_text(&mut self, text: impl AsRef<[u8]>) -> usize {
+        // otherwise, the compaction would panic later
+        assert!(
+            self.index.empty_text_policy() == EmptyTextPolicy::Keep || !text.as_ref().is_empty(),
+            "empty texts are rejected by the empty text policy of the index"
+        );
+
+        let alphabet = self.index.alphabet();
+        let dense_text = text
+            .as_ref()
+            .iter()
+            .map(|&symbol| alphabet.io_to_dense_representation(symbol))
+            .collect();
+
+        self.pending_texts.push(dense_text);
+
+        self.num_texts() - 1
+    }
+
+    /// Rebuilds the index with all indexed and pending texts, using the same configuration.
+    ///
+    /// The indexed texts are extracted from the index (see [`FmIndex::extract_text`]), so the original
+    /// texts are not needed. The running time is the same as for constructing a new index over all texts.
+    ///
+    /// The order of the sentinels (see [`SentinelOrder`](crate::bwt::SentinelOrder)), the text names, the disabled
+    /// texts, the file groups and the [`TextSampling`](crate::TextSampling) record of the index are kept. The added
+    /// texts get empty names if the texts have names, belong to the last file and get the original text ids
+    /// following the last original text id of the index. Bookmarks are removed, because their intervals change.
+    pub fn compact(&mut self) {
+        if self.pending_texts.is_empty() {
+            return;
+        }
+
+        let alphabet = self.index.alphabet();
+
+        let new_texts: Vec<Vec<u8>> = self
+            .pending_texts
+            .iter()
+            .map(|dense_text| {
+                dense_text
+                    .iter()
+                    .map(|&symbol| alphabet.dense_to_io_representation(symbol))
+                    .collect()
+            })
+            .collect();
+
+        self.index = self.index.with_appended_texts(&new_texts);
+        self.pending_texts.clear();
+    }
+
+    /// The number of occurrences of `query` in the indexed and the pending texts.
+    ///
+    /// Panics if `query` contains symbols that are not searchable (see [`Alphabet`](crate::Alphabet)).
+    pub fn count(&self, query: &[u8]) -> usize {
+        self.index.count(query) + self.locate_pending(query).count()
+    }
+
+    /// Returns the occurrences of `query` in the indexed and the pending texts.
+    ///
+    /// The hits in the indexed texts are returned first, in the same order as by [`FmIndex::locate`].
+    /// They are followed by the hits in the pending texts, sorted by text id and position.
+    ///
+    /// Panics if `query` contains symbols that are not searchable (see [`Alphabet`](crate::Alphabet)).
+    pub fn locate(&self, query: &[u8]) -> impl Iterator<Item = Hit> {
+        let pending_hits: Vec<_> = self.locate_pending(query).collect();
+
+        self.index.locate(query).chain(pending_hits)
+    }
+
+    /// The index of the texts that were already compacted.
+    pub fn index(&self) -> &FmIndex<I, R> {
+        &self.index
+    }
+
+    /// The total number of texts, including the pending texts.
+    pub fn num_texts(&self) -> usize {
+        self.index.num_texts() + self.pending_texts.len()
+    }
+
+    pub fn num_pending_texts(&self) -> usize {
+        self.pending_texts.len()
+    }
+
+    /// Returns the index, after compacting all pending texts into it.
+    pub fn into_index(mut self) -> FmIndex<I, R> {
+        self.compact();
+        self.index
+    }
+
+    fn locate_pending(&self, query: &[u8]) -> impl Iterator<Item = Hit> {
+        let alphabet = self.index.alphabet();
+        let dense_query: Vec<_> = query
+            .iter()
+            .map(|&symbol| alphabet.io_to_dense_representation(symbol))
+            .collect();
+
+        // the index gives no guarantees for such queries, so the results would change after compacting
+        assert!(
+            dense_query
+                .iter()
+                .all(|&dense_symbol| alphabet.is_searchable(dense_symbol)),
+            "queries must only contain searchable symbols"
+        );
+
+        let first_pending_text_id = self.index.num_texts();
+        let empty_query_matches = self.index.empty_query_matches();
+
+        self.pending_texts
+            .iter()
+            .enumerate()
+            .flat_map(move |(pending_id, dense_text)| {
+                let text_id = first_pending_text_id + pending_id;
+
+                // the empty query is reported at the same positions as by the index
+                let positions: Vec<_> = if dense_query.is_empty() {
+                    match empty_query_matches {
+                        EmptyQueryMatches::AllPositions => (0..=dense_text.len()).collect(),
+                        EmptyQueryMatches::TextPositions => (0..dense_text.len()).collect(),
+                        EmptyQueryMatches::NoPositions => Vec::new(),
+                    }
+                } else {
+                    find_overlapping(dense_text, &dense_query)
+                };
+
+                positions
+                    .into_iter()
+                    .map(move |position| Hit { text_id, position })
+            })
+    }
+}
+
+// memmem only reports non-overlapping occurrences, so the search is restarted after the start of every hit
+fn find_overlapping(text: &[u8], query: &[u8]) -> Vec<usize> {
+    let finder = memchr::memmem::Finder::new(query);
+    let mut positions = Vec::new();
+    let mut start = 0;
+
+    while let Some(offset) = finder.find(&text[start..]) {
+        positions.push(start + offset);
+        start += offset + 1;
+    }
+
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, TextSampling, alphabet, bwt};
+
+    #[test]
+    fn pending_and_compacted_agree() {
+        let index = FmIndexConfig::<i32>::new()
+            .suffix_array_sampling_rate(3)
+            .construct_index(
+                [b"ACGTACGTNNACGA".as_slice(), b"TTACG"],
+                alphabet::ascii_dna_with_n(),
+            );
+
+        let mut growable = GrowableFmIndex::new(index);
+        assert_eq!(growable.add_text(b"acgtnACG"), 2);
+        assert_eq!(growable.add_text(b""), 3);
+        assert_eq!(growable.add_text(b"GGGACGA"), 4);
+
+        let queries = [b"ACG".as_slice(), b"", b"GA", b"TTT"];
+
+        let pending_results: Vec<Vec<_>> = queries
+            .iter()
+            .map(|query| {
+                let mut hits: Vec<_> = growable.locate(query).collect();
+                hits.sort();
+                hits
+            })
+            .collect();
+
+        let pending_counts: Vec<_> = queries.iter().map(|query| growable.count(query)).collect();
+
+        growable.compact();
+
+        assert_eq!(growable.num_texts(), 5);
+        assert_eq!(growable.num_pending_texts(), 0);
+        assert_eq!(
+            growable
+                .index()
+                .construction_params()
+                .unwrap()
+                .suffix_array_sampling_rate,
+            3
+        );
+
+        for ((query, pending_hits), pending_count) in
+            queries.iter().zip(pending_results).zip(pending_counts)
+        {
+            let mut hits: Vec<_> = growable.locate(query).collect();
+            hits.sort();
+
+            assert_eq!(hits, pending_hits);
+            assert_eq!(growable.count(query), pending_count);
+            assert_eq!(pending_count, pending_hits.len());
+        }
+    }
+
+    #[test]
+    fn compaction_keeps_index_metadata() {
+        let records = [
+            ("chr1", b"ACGTT".as_slice()),
+            ("chr2", b"GGAC"),
+            ("chr3", b"TACGA"),
+            ("chr4", b"CCCA"),
+        ];
+        let mut index = FmIndexConfig::<i32>::new()
+            .text_sampling(TextSampling::EveryNth(2))
+            .construct_index_from_records(records, alphabet::ascii_dna());
+        index.disable_text(1);
+
+        let mut growable = GrowableFmIndex::new(index);
+        growable.add_text(b"TTACGA");
+        growable.compact();
+
+        let index = growable.index();
+        assert_eq!(index.text_names().unwrap(), ["chr1", "chr3", ""]);
+        assert_eq!(index.original_text_ids().unwrap(), [0, 2, 3]);
+        assert_eq!(index.disabled_texts().collect::<Vec<_>>(), [1]);
+        assert_eq!(index.num_files(), 1);
+        assert_eq!(index.locate(b"ACG").count(), 2);
+    }
+
+    #[test]
+    fn compaction_keeps_sentinel_order() {
+        let texts = [b"CA".as_slice(), b"GA", b"TA"];
+
+        fn index_with_sentinel_order(
+            texts: &[&[u8]],
+            sentinel_order: &bwt::SentinelOrder,
+        ) -> FmIndex<i32> {
+            let bwt = bwt::construct_bwt_with_sentinel_order::<i32, _>(
+                texts,
+                &alphabet::ascii_dna(),
+                false,
+                sentinel_order,
+            );
+            let symbols: Vec<_> = bwt.iter().collect();
+
+            FmIndex::<i32>::from_bwt(
+                &symbols,
+                bwt.sentinel_suffix_array_values(),
+                alphabet::ascii_dna(),
+                FmIndexConfig::new(),
+            )
+        }
+
+        for sentinel_order in [
+            bwt::SentinelOrder::ByTextId,
+            bwt::SentinelOrder::ByRank(vec![2, 0, 1]),
+        ] {
+            let mut growable =
+                GrowableFmIndex::new(index_with_sentinel_order(&texts, &sentinel_order));
+            growable.add_text(b"AA");
+            growable.compact();
+
+            let expected_sentinel_order = match sentinel_order {
+                bwt::SentinelOrder::ByRank(mut ranks) => {
+                    ranks.push(3);
+                    bwt::SentinelOrder::ByRank(ranks)
+                }
+                sentinel_order => sentinel_order,
+            };
+            let expected = index_with_sentinel_order(
+                &[b"CA".as_slice(), b"GA", b"TA", b"AA"],
+                &expected_sentinel_order,
+            );
+
+            assert!(growable.index().bwt_iter().eq(expected.bwt_iter()));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "empty texts are rejected")]
+    fn rejected_empty_text() {
+        let index = FmIndexConfig::<i32>::new()
+            .empty_text_policy(EmptyTextPolicy::Reject)
+            .construct_index([b"ACGT"], alphabet::ascii_dna());
+
+        GrowableFmIndex::new(index).add_text(b"");
+    }
+
+    #[test]
+    fn empty_query_matches() {
+        for empty_query_matches in [
+            EmptyQueryMatches::AllPositions,
+            EmptyQueryMatches::TextPositions,
+            EmptyQueryMatches::NoPositions,
+        ] {
+            let index = FmIndexConfig::<i32>::new()
+                .empty_query_matches(empty_query_matches)
+                .construct_index([b"ACGT"], alphabet::ascii_dna());
+
+            let mut growable = GrowableFmIndex::new(index);
+            growable.add_text(b"GGA");
+
+            let mut pending_hits: Vec<_> = growable.locate(b"").collect();
+            pending_hits.sort();
+            let pending_count = growable.count(b"");
+
+            growable.compact();
+
+            let mut hits: Vec<_> = growable.locate(b"").collect();
+            hits.sort();
+
+            assert_eq!(pending_hits, hits);
+            assert_eq!(pending_count, growable.count(b""));
+        }
+    }
+
+    #[test]
+    fn self_overlapping_query() {
+        let index = FmIndexConfig::<i32>::new().construct_index([b"CAAAC"], alphabet::ascii_dna());
+
+        let mut growable = GrowableFmIndex::new(index);
+        growable.add_text(b"AAAA");
+
+        assert_eq!(growable.count(b"AA"), 5);
+
+        let pending_hits: Vec<_> = growable
+            .locate(b"AA")
+            .filter(|hit| hit.text_id == 1)
+            .collect();
+
+        assert_eq!(
+            pending_hits,
+            [0, 1, 2].map(|position| Hit {
+                text_id: 1,
+                position
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "searchable")]
+    fn non_searchable_query() {
+        let index =
+            FmIndexConfig::<i32>::new().construct_index([b"ACGTNA"], alphabet::ascii_dna_with_n());
+
+        let mut growable = GrowableFmIndex::new(index);
+        growable.add_text(b"NNA");
+
+        growable.count(b"NA");
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{FmIndex, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+/// The number of bytes that the components of an index use on the heap, returned by [`FmIndex::heap_size_bytes`].
+///
+/// This is available without the `mem_dbg` feature, such that applications can report the memory usage of an
+/// index cheaply. Unused capacity of the internal buffers is not counted, and hash maps are counted by the size of
+/// their entries. The size of the index struct itself is not included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HeapSizes {
+    pub text_with_rank_support: usize,
+    /// The sampled suffix array, including the lookup of the suffix array values at text borders.
+    pub suffix_array: usize,
+    pub lookup_tables: usize,
+    /// The data structure that maps positions of the concatenated texts to text ids.
+    pub text_ids: usize,
+    /// `0` if the run boundaries of the BWT are not recorded.
+    pub bwt_runs: usize,
+    /// All other components, such as the alphabet, the text names and the bookmarks. They are usually small.
+    pub other: usize,
+}
+
+impl HeapSizes {
+    pub fn total(&self) -> usize {
+        self.text_with_rank_support
+            + self.suffix_array
+            + self.lookup_tables
+            + self.text_ids
+            + self.bwt_runs
+            + self.other
+    }
+}
+
+pub(crate) fn heap_sizes<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+) -> HeapSizes {
+    let text_names = size_of_val(index.text_names.as_slice())
+        + index.text_names.iter().map(String::len).sum::<usize>();
+
+    let other = index.alphabet.heap_size_bytes()
+        + size_of_val(index.count.as_slice())
+        + index.file_groups.heap_size_bytes()
+        + text_names
+        + index.bookmarks.heap_size_bytes()
+        + size_of_val(index.original_text_ids.as_slice())
+        + index.disabled_texts.heap_size_bytes();
+
+    HeapSizes {
+        text_with_rank_support: index.text_with_rank_support.heap_size_bytes(),
+        suffix_array: index.suffix_array.heap_size_bytes(),
+        lookup_tables: index.lookup_tables.heap_size_bytes(),
+        text_ids: index.text_ids.heap_size_bytes(),
+        bwt_runs: index
+            .bwt_runs
+            .as_ref()
+            .and_then(|runs| runs.memory_region())
+            .map_or(0, |region| region.len()),
+        other,
+    }
+}
@@ -0,0 +1,113 @@
+use std::ops::Range;
+
+use crate::{FmIndex, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+/// The regions of a text that are covered by occurrences of a query, found by [`FmIndex::locate_as_ranges`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextRanges {
+    pub text_id: usize,
+    /// Sorted, non-overlapping ranges of positions in the text.
+    pub ranges: Vec<Range<usize>>,
+}
+
+pub(crate) fn locate_as_ranges<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+    merge_gap: usize,
+) -> Vec<TextRanges> {
+    let mut hits: Vec<_> = index.locate(query).collect();
+    hits.sort_unstable();
+
+    let mut text_ranges: Vec<TextRanges> = Vec::new();
+
+    for hit in hits {
+        let range = hit.position..hit.position + query.len();
+
+        match text_ranges.last_mut() {
+            Some(current) if current.text_id == hit.text_id => {
+                // there is always at least one range per text
+                let last_range = current.ranges.last_mut().unwrap();
+
+                if range.start <= last_range.end.saturating_add(merge_gap) {
+                    last_range.end = last_range.end.max(range.end);
+                } else {
+                    current.ranges.push(range);
+                }
+            }
+            _ => text_ranges.push(TextRanges {
+                text_id: hit.text_id,
+                ranges: vec![range],
+            }),
+        }
+    }
+
+    text_ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, alphabet};
+
+    use super::*;
+
+    #[test]
+    fn ranges_agree_with_covered_positions() {
+        let texts = [
+            b"AAAAACGTCGTCGTTTTTTCGT".as_slice(),
+            b"GGGG",
+            b"CGTACGTAAACGT",
+        ];
+
+        let index = FmIndexConfig::<i32>::new().construct_index(texts, alphabet::ascii_dna());
+
+        for query in [b"CGT".as_slice(), b"A", b"GG", b"TTTT"] {
+            for merge_gap in [0, 1, 3, 10] {
+                let text_ranges = locate_as_ranges(&index, query, merge_gap);
+
+                // every position is covered by a range iff it is covered by a hit
+                for (text_id, text) in texts.iter().enumerate() {
+                    let ranges = text_ranges
+                        .iter()
+                        .find(|text_ranges| text_ranges.text_id == text_id)
+                        .map_or([].as_slice(), |text_ranges| &text_ranges.ranges);
+
+                    let covered_by_hits: Vec<_> = (0..text.len())
+                        .map(|position| {
+                            index.locate(query).any(|hit| {
+                                hit.text_id == text_id
+                                    && (hit.position..hit.position + query.len())
+                                        .contains(&position)
+                            })
+                        })
+                        .collect();
+
+                    for (position, &covered) in covered_by_hits.iter().enumerate() {
+                        let in_range = ranges.iter().any(|range| range.contains(&position));
+
+                        if covered {
+                            assert!(in_range);
+                        } else if in_range {
+                            // only gaps of at most merge_gap positions may be bridged
+                            let gap_len = covered_by_hits[position..]
+                                .iter()
+                                .take_while(|&&c| !c)
+                                .count()
+                                + covered_by_hits[..position]
+                                    .iter()
+                                    .rev()
+                                    .take_while(|&&c| !c)
+                                    .count();
+                            assert!(gap_len <= merge_gap);
+                        }
+                    }
+
+                    for window in ranges.windows(2) {
+                        assert!(window[0].end + merge_gap < window[1].start);
+                    }
+                }
+
+                assert!(text_ranges.is_sorted_by_key(|text_ranges| text_ranges.text_id));
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::BinaryHeap;
+
+use rayon::prelude::*;
+
+use crate::{
+    FmIndex, HalfOpenInterval, Hit, IndexStorage, text_with_rank_support::TextWithRankSupport,
+};
+
+/// A lazy handle to all occurrences of a searched query.
+///
+/// Obtaining the handle is as cheap as counting the query. The hits are only resolved (located) when requested,
+/// which allows deciding after seeing the number of hits whether none, some or all of them should be resolved.
+/// Resolving a single hit needs `s / 2` steps on average, where `s` is the suffix array sampling rate of the index.
+///
+/// ```
+/// use genedex::{FmIndexConfig, alphabet};
+///
+/// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGTACGT"], alphabet::ascii_dna());
+///
+/// let hit_set = index.hit_set(b"CG");
+///
+/// if hit_set.count() < 1000 {
+///     for hit in hit_set.take_sorted(2) {
+///         println!("{hit:?}");
+///     }
+/// }
+/// ```
+pub struct HitSet<'a, I, R> {
+    pub(crate) index: &'a FmIndex<I, R>,
+    pub(crate) interval: HalfOpenInterval,
+}
+
+// the derive is too restrictive
+impl<'a, I, R> Clone for HitSet<'a, I, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, I, R> Copy for HitSet<'a, I, R> {}
+
+impl<'a, I: IndexStorage, R: TextWithRankSupport<I>> HitSet<'a, I, R> {
+    /// The number of hits, including the hits in disabled texts (see [`FmIndex::disable_text`]). The running time
+    /// is in O(1).
+    pub fn count(&self) -> usize {
+        self.interval.end - self.interval.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// Lazily resolves the hits. They are not sorted by text id or position, but in suffix array order
+    /// (see [`FmIndex::locate`]).
+    pub fn iter(&self) -> impl Iterator<Item = Hit> + use<'a, I, R> {
+        self.index.locate_interval(self.interval)
+    }
+
+    /// Returns the (at most) `n` smallest hits, sorted by text id and position.
+    ///
+    /// All hits have to be resolved for this, but only `n` of them are kept in memory at the same time.
+    pub fn take_sorted(&self, n: usize) -> Vec<Hit> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let mut smallest_hits = BinaryHeap::with_capacity(n.min(self.count()) + 1);
+
+        for hit in self.iter() {
+            if smallest_hits.len() < n {
+                smallest_hits.push(hit);
+            } else if hit < *smallest_hits.peek().unwrap() {
+                smallest_hits.pop();
+                smallest_hits.push(hit);
+            }
+        }
+
+        smallest_hits.into_sorted_vec()
+    }
+
+    /// Resolves all hits in parallel, which can be much faster for very large sets of hits.
+    ///
+    /// The hits are returned in the same order as by [`Self::iter`]. The number of threads is controlled by [`rayon`].
+    pub fn resolve_all_par(&self) -> Vec<Hit> {
+        (self.interval.start..self.interval.end)
+            .into_par_iter()
+            .with_min_len(1024)
+            .filter_map(|row| {
+                self.index
+                    .locate_interval(HalfOpenInterval {
+                        start: row,
+                        end: row + 1,
+                    })
+                    .next()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,100 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{FmIndex, Hit, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+pub(crate) fn render_hits<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+    hits: impl IntoIterator<Item = Hit>,
+    flank_len: usize,
+) -> String {
+    let alphabet = index.alphabet();
+    let mut texts: HashMap<usize, Vec<u8>> = HashMap::new();
+    let mut view = String::new();
+
+    for hit in hits {
+        let text = texts
+            .entry(hit.text_id)
+            .or_insert_with(|| index.extract_text(hit.text_id));
+
+        assert!(hit.position <= text.len(), "hit position out of bounds");
+
+        let region_start = hit.position.saturating_sub(flank_len);
+        let match_end = (hit.position + query.len()).min(text.len());
+        let region_end = (match_end + flank_len).min(text.len());
+        let left_flank_len = hit.position - region_start;
+
+        // symbols are compared by their dense representation, such that e.g. case differences are not mismatches
+        let markers: String = query
+            .iter()
+            .zip(&text[hit.position..match_end])
+            .map(|(&query_symbol, &text_symbol)| {
+                let is_match = alphabet.try_io_to_dense_representation(query_symbol)
+                    == alphabet.try_io_to_dense_representation(text_symbol);
+
+                if is_match { '|' } else { '*' }
+            })
+            .collect();
+
+        let padding = " ".repeat(left_flank_len);
+
+        // writing to a String can't fail
+        writeln!(
+            view,
+            "text {}, positions {}..{}",
+            hit.text_id, hit.position, match_end
+        )
+        .unwrap();
+        writeln!(
+            view,
+            "  text: {}",
+            String::from_utf8_lossy(&text[region_start..region_end])
+        )
+        .unwrap();
+        writeln!(view, "        {padding}{markers}").unwrap();
+        writeln!(view, " query: {padding}{}", String::from_utf8_lossy(query)).unwrap();
+        writeln!(view).unwrap();
+    }
+
+    view
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+
+    #[test]
+    fn mismatches_and_clipped_flanks() {
+        let index = FmIndexConfig::<i32>::new()
+            .construct_index([b"ACGTTTAC".as_slice(), b"GGAC"], alphabet::ascii_dna());
+
+        let hits = [
+            Hit {
+                text_id: 0,
+                position: 2,
+            },
+            Hit {
+                text_id: 1,
+                position: 2,
+            },
+        ];
+
+        let view = render_hits(&index, b"gtat", hits, 3);
+
+        let expected_lines = [
+            "text 0, positions 2..6",
+            "  text: ACGTTTAC",
+            "          ||*|",
+            " query:   gtat",
+            "",
+            "text 1, positions 2..4",
+            "  text: GGAC",
+            "          **",
+            " query:   gtat",
+            "",
+        ];
+
+        assert_eq!(view.lines().collect::<Vec<_>>(), expected_lines);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    Cursor, FmIndex, HalfOpenInterval, Hit, IndexStorage,
+    text_with_rank_support::TextWithRankSupport,
+};
+
+/// A query for which two indices disagree, found by [`FmIndex::diff_with`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryDiscrepancy {
+    /// The position of the query in the workload.
+    pub query_idx: usize,
+    /// The number of occurrences of the query in the first and the second index.
+    pub counts: [usize; 2],
+    /// The position in the query of the first backward search step after which the numbers of occurrences in the
+    /// two indices differ. It is `None` if the backward searches agree and only the located hits differ.
+    pub diverging_query_position: Option<usize>,
+    /// The sorted hits that were only found in the first index.
+    pub hits_only_in_first: Vec<Hit>,
+    /// The sorted hits that were only found in the second index.
+    pub hits_only_in_second: Vec<Hit>,
+}
+
+pub(crate) fn diff_indices<I1, R1, I2, R2, Q>(
+    first: &FmIndex<I1, R1>,
+    second: &FmIndex<I2, R2>,
+    queries: impl IntoIterator<Item = Q>,
+) -> Vec<QueryDiscrepancy>
+where
+    I1: IndexStorage,
+    R1: TextWithRankSupport<I1>,
+    I2: IndexStorage,
+    R2: TextWithRankSupport<I2>,
+    Q: AsRef<[u8]>,
+{
+    let mut discrepancies = Vec::new();
+
+    for (query_idx, query) in queries.into_iter().enumerate() {
+        let query = query.as_ref();
+
+        let first_counts = suffix_counts(first, query);
+        let second_counts = suffix_counts(second, query);
+
+        // the counts are compared instead of the intervals, because the suffix array order of equivalent
+        // collections can differ, e.g. when the texts were added in a different order
+        let diverging_step = first_counts
+            .iter()
+            .zip(&second_counts)
+            .position(|(first_count, second_count)| first_count != second_count);
+
+        let counts = [
+            count_of_query(first, &first_counts),
+            count_of_query(second, &second_counts),
+        ];
+
+        let mut first_hits = locate_if_found(first, query, counts[0]);
+        let mut second_hits = locate_if_found(second, query, counts[1]);
+        first_hits.sort_unstable();
+        second_hits.sort_unstable();
+
+        let (hits_only_in_first, hits_only_in_second) = sorted_differences(first_hits, second_hits);
+
+        if diverging_step.is_none()
+            && hits_only_in_first.is_empty()
+            && hits_only_in_second.is_empty()
+        {
+            continue;
+        }
+
+        discrepancies.push(QueryDiscrepancy {
+            query_idx,
+            counts,
+            diverging_query_position: diverging_step.map(|step| query.len() - 1 - step),
+            hits_only_in_first,
+            hits_only_in_second,
+        });
+    }
+
+    discrepancies
+}
+
+// Returns the number of occurrences of every suffix of the query, ordered by suffix length. The lookup table is
+// not used, such that indices with different lookup table depths are compared step by step. Symbols that are not
+// part of the alphabet or not searchable are treated as not occurring.
+fn suffix_counts<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+) -> Vec<usize> {
+    let alphabet = index.alphabet();
+    let mut cursor = index.cursor_empty();
+    let mut counts = Vec::with_capacity(query.len());
+
+    for &symbol in query.iter().rev() {
+        match alphabet
+            .try_io_to_dense_representation(symbol)
+            .filter(|&dense_symbol| alphabet.is_searchable(dense_symbol))
+        {
+            Some(dense_symbol) => cursor.extend_front_without_alphabet_translation(dense_symbol),
+            None => {
+                cursor = Cursor {
+                    index,
+                    interval: HalfOpenInterval { start: 0, end: 0 },
+                }
+            }
+        }
+
+        counts.push(cursor.count());
+    }
+
+    counts
+}
+
+fn count_of_query<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    suffix_counts: &[usize],
+) -> usize {
+    suffix_counts
+        .last()
+        .copied()
+        .unwrap_or_else(|| index.cursor_empty().count())
+}
+
+// the query can only be located if all of its symbols are valid for the index, which is implied by a positive count
+fn locate_if_found<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+    count: usize,
+) -> Vec<Hit> {
+    if count > 0 {
+        index.locate(query).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+fn sorted_differences(first: Vec<Hit>, second: Vec<Hit>) -> (Vec<Hit>, Vec<Hit>) {
+    let mut only_in_first = Vec::new();
+    let mut only_in_second = Vec::new();
+
+    let mut first = first.into_iter().peekable();
+    let mut second = second.into_iter().peekable();
+
+    loop {
+        match (first.peek(), second.peek()) {
+            (Some(first_hit), Some(second_hit)) => match first_hit.cmp(second_hit) {
+                std::cmp::Ordering::Less => only_in_first.extend(first.next()),
+                std::cmp::Ordering::Greater => only_in_second.extend(second.next()),
+                std::cmp::Ordering::Equal => {
+                    first.next();
+                    second.next();
+                }
+            },
+            (Some(_), None) => only_in_first.extend(first.next()),
+            (None, Some(_)) => only_in_second.extend(second.next()),
+            (None, None) => break,
+        }
+    }
+
+    (only_in_first, only_in_second)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, alphabet};
+
+    #[test]
+    fn equivalent_indices_have_no_discrepancies() {
+        let texts = [b"ACGTACGTNNACGT".as_slice(), b"TTGCA"];
+        let queries = [b"ACG".as_slice(), b"", b"N", b"TTGCAT", b"GU", b"CGTNNA"];
+
+        let first = FmIndexConfig::<i32>::new()
+            .lookup_table_depth(0)
+            .construct_index(texts, alphabet::ascii_dna_with_n());
+        let second = FmIndexConfig::<u32>::new()
+            .lookup_table_depth(3)
+            .suffix_array_sampling_rate(2)
+            .construct_index(texts, alphabet::ascii_dna_with_n());
+
+        assert!(first.diff_with(&second, queries).is_empty());
+    }
+
+    #[test]
+    fn diverging_step_and_hits() {
+        let first = FmIndexConfig::<i32>::new()
+            .construct_index([b"ACGTAC".as_slice(), b"GTAC"], alphabet::ascii_dna());
+        let second = FmIndexConfig::<i32>::new()
+            .construct_index([b"ACGTAC".as_slice(), b"GGAC"], alphabet::ascii_dna());
+
+        let discrepancies = first.diff_with(&second, [b"AC".as_slice(), b"GTAC"]);
+
+        assert_eq!(discrepancies.len(), 1);
+
+        let discrepancy = &discrepancies[0];
+        assert_eq!(discrepancy.query_idx, 1);
+        assert_eq!(discrepancy.counts, [2, 1]);
+        // "C" and "AC" occur equally often, "TAC" does not
+        assert_eq!(discrepancy.diverging_query_position, Some(1));
+        assert_eq!(discrepancy.hits_only_in_first.len(), 1);
+        assert_eq!(discrepancy.hits_only_in_first[0].text_id, 1);
+        assert!(discrepancy.hits_only_in_second.is_empty());
+    }
+}
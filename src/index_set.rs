@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    FmIndex, Hit, IndexStorage,
+    text_with_rank_support::{Block64, CondensedTextWithRankSupport, TextWithRankSupport},
+};
+
+/// A collection of several versions of an FM-Index that can be searched together.
+///
+/// This is useful when multiple releases of the same reference (e.g. monthly builds) should be queried at once.
+/// Every version is a complete, independent [`FmIndex`]. The versions are identified by the order in which
+/// they were added, starting at `0`, and carry a user-defined label.
+///
+/// ```
+/// use genedex::{FmIndexConfig, IndexSet, alphabet};
+///
+/// let old = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+/// let new = FmIndexConfig::<i32>::new().construct_index([b"ACGTTCGT"], alphabet::ascii_dna());
+///
+/// let mut index_set = IndexSet::new();
+/// index_set.add_version("2024-01", old);
+/// index_set.add_version("2024-02", new);
+///
+/// assert_eq!(index_set.count(b"TACG"), [1, 0]);
+///
+/// for versioned_hit in index_set.locate(b"CGT") {
+///     println!("{:?} is contained in versions {:?}", versioned_hit.hit, versioned_hit.version_ids);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct IndexSet<I, R = CondensedTextWithRankSupport<I, Block64>> {
+    labels: Vec<String>,
+    indices: Vec<FmIndex<I, R>>,
+}
+
+/// A hit of a query in an [`IndexSet`], together with the ids of all versions that contain it.
+///
+/// The version ids are sorted in ascending order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VersionedHit {
+    pub hit: Hit,
+    pub version_ids: Vec<usize>,
+}
+
+impl<I: IndexStorage, R: TextWithRankSupport<I>> IndexSet<I, R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a new version to the set and returns its version id.
+    pub fn add_version(&mut self, label: impl Into<String>, index: FmIndex<I, R>) -> usize {
+        self.labels.push(label.into());
+        self.indices.push(index);
+
+        self.indices.len() - 1
+    }
+
+    pub fn num_versions(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Panics if `version_id` is not a valid version id of this set.
+    pub fn version_label(&self, version_id: usize) -> &str {
+        &self.labels[version_id]
+    }
+
+    /// Panics if `version_id` is not a valid version id of this set.
+    pub fn version(&self, version_id: usize) -> &FmIndex<I, R> {
+        &self.indices[version_id]
+    }
+
+    /// Returns the number of occurrences of `query` in every version, ordered by version id.
+    pub fn count(&self, query: &[u8]) -> Vec<usize> {
+        self.indices
+            .iter()
+            .map(|index| index.count(query))
+            .collect()
+    }
+
+    /// Returns all distinct hits of `query` over all versions, together with the versions that contain each hit.
+    ///
+    /// The hits are sorted by text id and position. Since all hits have to be collected to group them by version,
+    /// the memory usage of this function is linear in the total number of hits.
+    pub fn locate(&self, query: &[u8]) -> Vec<VersionedHit> {
+        let mut version_ids_per_hit: BTreeMap<Hit, Vec<usize>> = BTreeMap::new();
+
+        for (version_id, index) in self.indices.iter().enumerate() {
+            for hit in index.locate(query) {
+                version_ids_per_hit.entry(hit).or_default().push(version_id);
+            }
+        }
+
+        version_ids_per_hit
+            .into_iter()
+            .map(|(hit, version_ids)| VersionedHit { hit, version_ids })
+            .collect()
+    }
+
+    /// Loads every file in `labeled_filepaths` as a version of the set, using the label given with the path.
+    #[cfg(feature = "savefile")]
+    pub fn load_from_files<L: Into<String>, P: AsRef<std::path::Path>>(
+        labeled_filepaths: impl IntoIterator<Item = (L, P)>,
+    ) -> Result<Self, savefile::SavefileError> {
+        let mut index_set = Self::new();
+
+        for (label, filepath) in labeled_filepaths {
+            index_set.add_version(label, FmIndex::load_from_file(filepath)?);
+        }
+
+        Ok(index_set)
+    }
+}
+
+// the derive is too restrictive
+impl<I, R> Default for IndexSet<I, R> {
+    fn default() -> Self {
+        Self {
+            labels: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+
+    #[test]
+    fn hits_are_grouped_by_version() {
+        let versions = [
+            [b"ACGTACGT".as_slice(), b"TTTT"],
+            [b"ACGTACGA".as_slice(), b"TTTT"],
+            [b"CCCC".as_slice(), b"ACGT"],
+        ];
+
+        let mut index_set = IndexSet::new();
+
+        for (i, texts) in versions.iter().enumerate() {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_index(texts, alphabet::ascii_dna());
+
+            assert_eq!(index_set.add_version(format!("v{i}"), index), i);
+        }
+
+        assert_eq!(index_set.num_versions(), 3);
+        assert_eq!(index_set.version_label(1), "v1");
+        assert_eq!(index_set.count(b"ACGT"), [2, 1, 1]);
+
+        let expected = vec![
+            VersionedHit {
+                hit: Hit {
+                    text_id: 0,
+                    position: 0,
+                },
+                version_ids: vec![0, 1],
+            },
+            VersionedHit {
+                hit: Hit {
+                    text_id: 0,
+                    position: 4,
+                },
+                version_ids: vec![0],
+            },
+            VersionedHit {
+                hit: Hit {
+                    text_id: 1,
+                    position: 0,
+                },
+                version_ids: vec![2],
+            },
+        ];
+
+        assert_eq!(index_set.locate(b"ACGT"), expected);
+    }
+}
@@ -0,0 +1,246 @@
+use std::io::{self, Write};
+
+use crate::{FmIndex, Hit, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+#[doc(no_inline)]
+pub use crate::Strand;
+
+/// A match of (a part of) a query to an indexed text.
+///
+/// The matched part of the query is `query_start..query_end`, given in coordinates of the original (forward) query.
+/// The match covers `query_end - query_start` positions of the text, starting at `hit.position`. Insertions and
+/// deletions are not supported, so approximate matches can only contain substitutions, which are counted
+/// by `edit_distance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AlignmentRecord<'a> {
+    pub query_name: &'a str,
+    pub query_len: usize,
+    pub query_start: usize,
+    pub query_end: usize,
+    pub hit: Hit,
+    pub strand: Strand,
+    pub edit_distance: usize,
+}
+
+impl<'a> AlignmentRecord<'a> {
+    /// A record for an exact match of the whole query, e.g. a hit returned by [`FmIndex::locate`].
+    pub fn exact(query_name: &'a str, query_len: usize, hit: Hit, strand: Strand) -> Self {
+        Self {
+            query_name,
+            query_len,
+            query_start: 0,
+            query_end: query_len,
+            hit,
+            strand,
+            edit_distance: 0,
+        }
+    }
+
+    fn match_len(&self) -> usize {
+        self.query_end - self.query_start
+    }
+}
+
+/// The output formats supported by the [`AlignmentWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlignmentFormat {
+    /// Minimal SAM records without sequences and qualities. The header contains `@HD` and one `@SQ` line per text.
+    Sam,
+    /// PAF records with the `NM` tag. PAF files have no header.
+    Paf,
+}
+
+/// Writes [`AlignmentRecord`]s as minimal SAM or PAF lines.
+///
+/// The texts of the index are used as the reference sequences. Their names have to be supplied in the order of
+/// the text ids, e.g. using [`FmIndex::text_names`](crate::FmIndex::text_names) if the index was constructed from
+/// named records.
+///
+/// The mapping quality is always reported as unavailable (`255`).
+///
+/// ```
+/// use genedex::{FmIndexConfig, alphabet};
+/// use genedex::io_align::{AlignmentFormat, AlignmentRecord, AlignmentWriter, Strand};
+///
+/// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+///
+/// let mut writer = AlignmentWriter::new(Vec::new(), AlignmentFormat::Paf, &index, ["chr1"]).unwrap();
+///
+/// for hit in index.locate(b"GTAC") {
+///     writer.write_record(&AlignmentRecord::exact("read1", 4, hit, Strand::Forward)).unwrap();
+/// }
+///
+/// let output = String::from_utf8(writer.into_inner()).unwrap();
+/// assert_eq!(output, "read1\t4\t0\t4\t+\tchr1\t8\t2\t6\t4\t4\t255\tNM:i:0\n");
+/// ```
+pub struct AlignmentWriter<W> {
+    writer: W,
+    format: AlignmentFormat,
+    reference_names: Vec<String>,
+    reference_lengths: Vec<usize>,
+}
+
+impl<W: Write> AlignmentWriter<W> {
+    /// Creates the writer and writes the header, if the format has one.
+    ///
+    /// Panics if the number of reference names is not the number of texts of the index.
+    pub fn new<I: IndexStorage, R: TextWithRankSupport<I>>(
+        mut writer: W,
+        format: AlignmentFormat,
+        index: &FmIndex<I, R>,
+        reference_names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> io::Result<Self> {
+        let reference_names: Vec<String> = reference_names.into_iter().map(Into::into).collect();
+
+        assert_eq!(
+            reference_names.len(),
+            index.num_texts(),
+            "there must be exactly one reference name per text of the index"
+        );
+
+        let reference_lengths: Vec<_> = (0..index.num_texts())
+            .map(|text_id| index.text_len(text_id))
+            .collect();
+
+        if format == AlignmentFormat::Sam {
+            writeln!(writer, "@HD\tVN:1.6\tSO:unsorted")?;
+
+            for (name, len) in reference_names.iter().zip(&reference_lengths) {
+                writeln!(writer, "@SQ\tSN:{name}\tLN:{len}")?;
+            }
+        }
+
+        Ok(Self {
+            writer,
+            format,
+            reference_names,
+            reference_lengths,
+        })
+    }
+
+    pub fn write_record(&mut self, record: &AlignmentRecord) -> io::Result<()> {
+        assert!(
+            record.query_start <= record.query_end && record.query_end <= record.query_len,
+            "the matched part of the query must be inside of the query"
+        );
+
+        match self.format {
+            AlignmentFormat::Sam => self.write_sam_record(record),
+            AlignmentFormat::Paf => self.write_paf_record(record),
+        }
+    }
+
+    /// Returns the underlying writer. It is not flushed.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_sam_record(&mut self, record: &AlignmentRecord) -> io::Result<()> {
+        let flag = match record.strand {
+            Strand::Forward => 0,
+            Strand::Reverse => 16,
+        };
+
+        // SAM describes the reverse complement of the query for the reverse strand, so the clips are swapped
+        let (front_clip, back_clip) = match record.strand {
+            Strand::Forward => (record.query_start, record.query_len - record.query_end),
+            Strand::Reverse => (record.query_len - record.query_end, record.query_start),
+        };
+
+        let mut cigar = String::new();
+
+        for (len, operation) in [
+            (front_clip, 'S'),
+            (record.match_len(), 'M'),
+            (back_clip, 'S'),
+        ] {
+            if len > 0 {
+                cigar.push_str(&format!("{len}{operation}"));
+            }
+        }
+
+        if cigar.is_empty() {
+            cigar.push('*');
+        }
+
+        writeln!(
+            self.writer,
+            "{}\t{flag}\t{}\t{}\t255\t{cigar}\t*\t0\t0\t*\t*\tNM:i:{}",
+            record.query_name,
+            self.reference_names[record.hit.text_id],
+            record.hit.position + 1,
+            record.edit_distance
+        )
+    }
+
+    fn write_paf_record(&mut self, record: &AlignmentRecord) -> io::Result<()> {
+        let strand = match record.strand {
+            Strand::Forward => '+',
+            Strand::Reverse => '-',
+        };
+
+        let match_len = record.match_len();
+
+        writeln!(
+            self.writer,
+            "{}\t{}\t{}\t{}\t{strand}\t{}\t{}\t{}\t{}\t{}\t{match_len}\t255\tNM:i:{}",
+            record.query_name,
+            record.query_len,
+            record.query_start,
+            record.query_end,
+            self.reference_names[record.hit.text_id],
+            self.reference_lengths[record.hit.text_id],
+            record.hit.position,
+            record.hit.position + match_len,
+            match_len.saturating_sub(record.edit_distance),
+            record.edit_distance
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+
+    #[test]
+    fn sam_output() {
+        let index = FmIndexConfig::<i32>::new()
+            .construct_index([b"ACGTACGT".as_slice(), b"TTGCA"], alphabet::ascii_dna());
+
+        let mut writer =
+            AlignmentWriter::new(Vec::new(), AlignmentFormat::Sam, &index, ["chr1", "chr2"])
+                .unwrap();
+
+        writer
+            .write_record(&AlignmentRecord {
+                query_name: "read1",
+                query_len: 6,
+                query_start: 1,
+                query_end: 4,
+                hit: Hit {
+                    text_id: 1,
+                    position: 1,
+                },
+                strand: Strand::Reverse,
+                edit_distance: 1,
+            })
+            .unwrap();
+
+        let hit = index.locate(b"CGTA").next().unwrap();
+        writer
+            .write_record(&AlignmentRecord::exact("read2", 4, hit, Strand::Forward))
+            .unwrap();
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!(
+            output,
+            "@HD\tVN:1.6\tSO:unsorted\n\
+             @SQ\tSN:chr1\tLN:8\n\
+             @SQ\tSN:chr2\tLN:5\n\
+             read1\t16\tchr2\t2\t255\t2S3M1S\t*\t0\t0\t*\t*\tNM:i:1\n\
+             read2\t0\tchr1\t2\t255\t4M\t*\t0\t0\t*\t*\tNM:i:0\n"
+        );
+    }
+}
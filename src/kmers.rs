@@ -0,0 +1,169 @@
+use crate::{Alphabet, Cursor, FmIndex, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+/// Calls `f` for every distinct k-mer of the indexed texts that consists only of searchable symbols.
+///
+/// The k-mers are passed in dense representation, together with a cursor that has the k-mer searched.
+/// The enumeration is a depth-first search using backwards extension, which only descends into
+/// non-empty intervals. Therefore, the running time is linear in the number of nodes of the implicit
+/// suffix trie of the texts up to depth `k`.
+pub(crate) fn for_each_kmer<'a, I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &'a FmIndex<I, R>,
+    k: usize,
+    mut f: impl FnMut(&[u8], Cursor<'a, I, R>),
+) {
+    assert!(k > 0, "k must be greater than 0");
+
+    let mut kmer = vec![0; k];
+
+    for_each_kmer_recursive(index.cursor_empty(), k, &mut kmer, &mut f);
+}
+
+// the k-mer is filled from the back, remaining_len is the number of symbols still missing at the front
+fn for_each_kmer_recursive<'a, I: IndexStorage, R: TextWithRankSupport<I>>(
+    cursor: Cursor<'a, I, R>,
+    remaining_len: usize,
+    kmer: &mut [u8],
+    f: &mut impl FnMut(&[u8], Cursor<'a, I, R>),
+) {
+    if remaining_len == 0 {
+        f(kmer, cursor);
+        return;
+    }
+
+    for (symbol, next_cursor) in cursor.extend_all_without_alphabet_translation() {
+        if next_cursor.count() == 0 {
+            continue;
+        }
+
+        kmer[remaining_len - 1] = symbol;
+
+        for_each_kmer_recursive(next_cursor, remaining_len - 1, kmer, f);
+    }
+}
+
+// The same depth-first search as above, but with an explicit stack, such that the k-mers can be produced lazily.
+pub(crate) struct KmerIter<'a, I, R> {
+    alphabet: &'a Alphabet,
+    // the cursors that still need to be visited, together with the position of their first symbol in the k-mer
+    // and that symbol. all symbols behind that position belong to the ancestors of the cursor, which were
+    // visited before and are still valid in `kmer`
+    stack: Vec<(Cursor<'a, I, R>, usize, u8)>,
+    kmer: Vec<u8>,
+}
+
+impl<'a, I: IndexStorage, R: TextWithRankSupport<I>> KmerIter<'a, I, R> {
+    pub(crate) fn new(index: &'a FmIndex<I, R>, k: usize) -> Self {
+        assert!(k > 0, "k must be greater than 0");
+
+        let mut iter = Self {
+            alphabet: index.alphabet(),
+            stack: Vec::new(),
+            kmer: vec![0; k],
+        };
+
+        iter.push_extensions(index.cursor_empty(), k);
+
+        iter
+    }
+
+    // pushed in reverse order, such that the smallest symbol is visited first
+    fn push_extensions(&mut self, cursor: Cursor<'a, I, R>, start: usize) {
+        for (symbol, next_cursor) in cursor
+            .extend_all_without_alphabet_translation()
+            .into_iter()
+            .rev()
+        {
+            if next_cursor.count() > 0 {
+                self.stack.push((next_cursor, start - 1, symbol));
+            }
+        }
+    }
+}
+
+impl<I: IndexStorage, R: TextWithRankSupport<I>> Iterator for KmerIter<'_, I, R> {
+    type Item = (Vec<u8>, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((cursor, start, symbol)) = self.stack.pop() {
+            self.kmer[start] = symbol;
+
+            if start == 0 {
+                let kmer = self
+                    .kmer
+                    .iter()
+                    .map(|&symbol| self.alphabet.dense_to_io_representation(symbol))
+                    .collect();
+
+                return Some((kmer, cursor.count()));
+            }
+
+            self.push_extensions(cursor, start);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    #[test]
+    fn enumerates_distinct_kmers_with_counts() {
+        let texts = [b"ACGTACNGT".as_slice(), b"TAC"];
+        let index =
+            FmIndexConfig::<i32>::new().construct_index(texts, alphabet::ascii_dna_with_n());
+
+        let mut kmers = HashMap::new();
+
+        for_each_kmer(&index, 2, |kmer, cursor| {
+            let kmer: Vec<_> = kmer
+                .iter()
+                .map(|&s| index.alphabet().dense_to_io_representation(s))
+                .collect();
+
+            kmers.insert(kmer, cursor.count());
+        });
+
+        let expected = HashMap::from_iter([
+            (b"AC".to_vec(), 3),
+            (b"CG".to_vec(), 1),
+            (b"GT".to_vec(), 2),
+            (b"TA".to_vec(), 2),
+        ]);
+
+        assert_eq!(kmers, expected);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn kmer_iter_agrees_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 0..40), 1..4),
+            k in 1usize..5,
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_index(&texts, alphabet::ascii_dna_with_n());
+
+            let mut expected = BTreeMap::new();
+
+            for text in &texts {
+                for kmer in text.windows(k).filter(|kmer| !kmer.contains(&b'N')) {
+                    *expected.entry(kmer.to_vec()).or_insert(0) += 1;
+                }
+            }
+
+            let kmers: Vec<_> = index.iter_kmers(k).collect();
+
+            // colexicographic order
+            prop_assert!(kmers.is_sorted_by_key(|(kmer, _)| kmer.iter().rev().copied().collect::<Vec<_>>()));
+            prop_assert_eq!(kmers.into_iter().collect::<BTreeMap<_, _>>(), expected);
+        }
+    }
+}
@@ -47,45 +47,212 @@
 /// Contains functions to create various commonly used alphabets.
 pub mod alphabet;
 
+/// Reproducible synthetic workloads for measuring the performance of the FM-Index. Requires the `bench` feature.
+///
+/// The workloads allow comparing the performance of different machines, configurations and versions of this library
+/// using the same workload definitions.
+#[cfg(feature = "bench")]
+pub mod bench;
+
+/// Standalone construction of the Burrows-Wheeler transform, using the same algorithms as the FM-Index construction.
+pub mod bwt;
+
+/// Writing hits as minimal SAM or PAF records. Requires the `io-align` feature.
+///
+/// This is meant for applications that only need to report exact or approximate matches in a standard format,
+/// not for full alignments with insertions and deletions.
+#[cfg(feature = "io-align")]
+pub mod io_align;
+
+/// Utilities for buffers of 4-bit symbols packed into bytes, as used by the construction for small alphabets.
+///
+/// Two symbols are packed into each byte, the first one into the upper four bits.
+pub mod packing;
+
+/// Search schemes for approximate search with the [`BidirectionalFmIndex`].
+pub mod search_schemes;
+
 /// Different implementations of the text with rank support (a.k.a. occurrence table) data structure that powers the FM-Index.
 ///
 /// The [`TextWithRankSupport`] and [`Block`](text_with_rank_support::Block) traits are good places to start
 ///  learning about this module.
 pub mod text_with_rank_support;
 
+/// Reference implementations and random input generators for testing code that builds on this library.
+/// Requires the `test-utils` feature.
+///
+/// These are the trusted oracles that the tests of this library are checked against. They are simple and slow,
+/// and therefore only meant for tests.
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+
+/// Minimal bindings for using the FM-Index from JavaScript, e.g. for interactive web tools. Requires the `wasm` feature.
+///
+/// The bindings are generated by [`wasm_bindgen`]. On targets without thread support, such as `wasm32-unknown-unknown`,
+/// [`rayon`] falls back to running everything on the current thread.
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+mod any_fm_index;
+mod approximate;
 mod batch_computed_cursors;
+mod bidirectional;
+mod bookmarks;
+mod buffer_allocator;
+mod bwt_runs;
+mod capacity;
+mod component_hashes;
 mod config;
 mod construction;
+mod containment;
+mod coverage;
 mod cursor;
+mod deduplication;
+mod disabled_texts;
+mod fasta;
+mod file_groups;
+mod fmd_index;
+mod growable;
+mod heap_size;
+mod hit_ranges;
+mod hit_set;
+mod hit_view;
+mod index_diff;
+mod index_set;
+mod kmers;
 mod lookup_table;
+mod markov;
+mod matching_statistics;
+mod memory_region;
+mod mems;
+mod motif;
+mod multi_volume;
+mod nearest_strings;
+mod pairs;
+mod pattern;
+mod pipeline;
+mod random;
 mod sampled_suffix_array;
+mod search_trace;
+mod seeds;
+mod semi_external;
+mod sketch;
+mod smem;
+mod text_border_lookup;
 mod text_id_search_tree;
+mod wildcards;
 
 use num_traits::NumCast;
+use std::collections::BTreeMap;
 
 #[doc(inline)]
 pub use alphabet::Alphabet;
 #[doc(inline)]
+pub use any_fm_index::{AnyFmIndex, AutoRankSupportConfig};
+#[doc(inline)]
+pub use approximate::ApproximateHit;
+#[doc(inline)]
+pub use bidirectional::{BidirectionalCursor, BidirectionalFmIndex};
+#[doc(inline)]
+pub use buffer_allocator::BufferAllocator;
+#[doc(inline)]
+pub use bwt_runs::BwtRuns;
+#[doc(inline)]
+pub use capacity::{IndexSizeEstimate, InputCheck};
+#[doc(inline)]
+pub use component_hashes::ComponentHashes;
+#[doc(inline)]
+pub use config::ConstructionParams;
+#[doc(inline)]
+pub use config::ConstructionStage;
+#[doc(inline)]
+pub use config::EmptyQueryMatches;
+#[doc(inline)]
+pub use config::EmptyTextPolicy;
+#[doc(inline)]
 pub use config::FmIndexConfig;
 #[doc(inline)]
 pub use config::PerformancePriority;
 #[doc(inline)]
+pub use config::SliceCompressionMode;
+#[doc(inline)]
+pub use config::TextSampling;
+#[doc(inline)]
 pub use construction::IndexStorage;
 #[doc(inline)]
+pub use containment::ContainmentReport;
+#[doc(inline)]
+pub use coverage::{CoverageTracks, QueryCoverage};
+#[doc(inline)]
 pub use cursor::Cursor;
+#[doc(inline)]
+pub use fmd_index::{FmdCursor, FmdIndex, Strand, StrandedHit};
+#[doc(inline)]
+pub use growable::GrowableFmIndex;
+#[doc(inline)]
+pub use heap_size::HeapSizes;
+#[doc(inline)]
+pub use hit_ranges::TextRanges;
+#[doc(inline)]
+pub use hit_set::HitSet;
+#[doc(inline)]
+pub use index_diff::QueryDiscrepancy;
+#[doc(inline)]
+pub use index_set::{IndexSet, VersionedHit};
+#[doc(inline)]
+pub use matching_statistics::MatchingStatistic;
+#[cfg(feature = "memory-advice")]
+#[doc(inline)]
+pub use memory_region::{IndexMemoryAdvice, MemoryAdvice};
+#[doc(inline)]
+pub use mems::Mem;
+#[doc(inline)]
+pub use motif::MotifHit;
+#[doc(inline)]
+pub use multi_volume::MultiVolumeFmIndex;
+#[doc(inline)]
+pub use nearest_strings::NearestString;
+#[doc(inline)]
+pub use pairs::{DistanceJoin, HitPair};
+#[doc(inline)]
+pub use pattern::PatternTooAmbiguous;
+#[doc(inline)]
+pub use pipeline::Pipeline;
+#[doc(inline)]
+pub use search_trace::{LookupJump, SearchStep, SearchTrace};
+#[doc(inline)]
+pub use seeds::MismatchHit;
+#[doc(inline)]
+pub use semi_external::SemiExternalConstruction;
+#[doc(inline)]
+pub use sketch::FracMinHashSketch;
+#[doc(inline)]
+pub use smem::Smem;
+#[doc(inline)]
+pub use wildcards::Wildcards;
 
-use batch_computed_cursors::BatchComputedCursors;
+use batch_computed_cursors::{BatchComputedCursors, LengthGroupedCursors};
+use bookmarks::Bookmarks;
 use construction::DataStructures;
+use deduplication::DeduplicatedQueries;
+use disabled_texts::DisabledTexts;
+use file_groups::FileGroups;
 use lookup_table::LookupTables;
+use memory_region::MemoryRegion;
 use sampled_suffix_array::SampledSuffixArray;
 use text_id_search_tree::TexdIdSearchTree;
 use text_with_rank_support::{
-    Block64, Block512, CondensedTextWithRankSupport, FlatTextWithRankSupport, TextWithRankSupport,
+    Block64, Block512, CondensedTextWithRankSupport, FlatTextWithRankSupport,
+    PackedTextWithRankSupport, TextWithRankSupport, WaveletTreeTextWithRankSupport,
 };
 
 /// The FM-Index data structure.
 ///
 /// See [crate-level documentation](self) for details.
+///
+/// For all type parameters, the FM-Index is [`Send`] and [`Sync`], and all query functions take `&self`.
+/// Therefore, a single index can be shared by many threads (e.g. using a reference or an [`Arc`](std::sync::Arc))
+/// and queried concurrently, without cloning it per thread.
 #[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
 #[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
 #[cfg_attr(feature = "savefile", savefile_doc_hidden)]
@@ -97,6 +264,28 @@ pub struct FmIndex<I, R = CondensedTextWithRankSupport<I, Block64>> {
     suffix_array: SampledSuffixArray<I>,
     text_ids: TexdIdSearchTree,
     lookup_tables: LookupTables<I>,
+    #[cfg_attr(feature = "savefile", savefile_versions = "1..")]
+    file_groups: FileGroups,
+    #[cfg_attr(feature = "savefile", savefile_versions = "2..")]
+    construction_params: Option<ConstructionParams>,
+    #[cfg_attr(feature = "savefile", savefile_versions = "3..")]
+    bwt_runs: Option<BwtRuns>,
+    #[cfg_attr(feature = "savefile", savefile_versions = "5..")]
+    empty_query_matches: EmptyQueryMatches,
+    #[cfg_attr(feature = "savefile", savefile_versions = "7..")]
+    empty_text_policy: EmptyTextPolicy,
+    // empty if the texts have no names
+    #[cfg_attr(feature = "savefile", savefile_versions = "7..")]
+    text_names: Vec<String>,
+    #[cfg_attr(feature = "savefile", savefile_versions = "8..")]
+    bookmarks: Bookmarks,
+    #[cfg_attr(feature = "savefile", savefile_versions = "9..")]
+    text_sampling: TextSampling,
+    // the ids of the texts in the input of the construction, empty if all texts were indexed
+    #[cfg_attr(feature = "savefile", savefile_versions = "9..")]
+    original_text_ids: Vec<usize>,
+    #[cfg_attr(feature = "savefile", savefile_ignore, savefile_introspect_ignore)]
+    disabled_texts: DisabledTexts,
 }
 
 /// A little faster than [`FmIndexCondensed512`], and still space efficient for larger alphabets.
@@ -112,6 +301,14 @@ pub type FmIndexFlat64<I> = FmIndex<I, FlatTextWithRankSupport<I, Block64>>;
 /// A little smaller and slower than [`FmIndexFlat64`]. [`FmIndexCondensed64`] should be a better trade-off for most applications.
 pub type FmIndexFlat512<I> = FmIndex<I, FlatTextWithRankSupport<I, Block512>>;
 
+/// Keeps the BWT half byte packed, for alphabets with at most 16 dense symbols. Slower than the other versions,
+/// but smaller than [`FmIndexCondensed64`] for alphabets with more than 8 dense symbols.
+pub type FmIndexPacked<I> = FmIndex<I, PackedTextWithRankSupport<I>>;
+
+/// Stores the BWT in a Huffman-shaped wavelet tree. Slower than the other versions, but the smallest version for
+/// large alphabets like [`ascii_printable`](alphabet::ascii_printable).
+pub type FmIndexWaveletTree<I> = FmIndex<I, WaveletTreeTextWithRankSupport<I>>;
+
 const BATCH_SIZE: usize = 64;
 
 impl<I: IndexStorage, R: TextWithRankSupport<I>> FmIndex<I, R> {
@@ -119,13 +316,39 @@ impl<I: IndexStorage, R: TextWithRankSupport<I>> FmIndex<I, R> {
         texts: impl IntoIterator<Item = T>,
         alphabet: Alphabet,
         config: FmIndexConfig<I, R>,
+    ) -> Self {
+        let texts = check_empty_text_policy(texts, config.empty_text_policy);
+        let data_structures =
+            construction::create_data_structures::<I, R, T>(texts, &config, &alphabet);
+
+        Self::from_data_structures(data_structures, alphabet, &config)
+    }
+
+    fn new_from_dense_texts<T: AsRef<[u8]>>(
+        texts: impl IntoIterator<Item = T>,
+        alphabet: Alphabet,
+        config: FmIndexConfig<I, R>,
+    ) -> Self {
+        let texts = check_empty_text_policy(texts, config.empty_text_policy);
+        let data_structures = construction::create_data_structures_from_dense_texts::<I, R, T>(
+            texts, &config, &alphabet,
+        );
+
+        Self::from_data_structures(data_structures, alphabet, &config)
+    }
+
+    fn from_data_structures(
+        data_structures: DataStructures<I, R>,
+        alphabet: Alphabet,
+        config: &FmIndexConfig<I, R>,
     ) -> Self {
         let DataStructures {
             count,
             sampled_suffix_array,
             text_ids,
             text_with_rank_support,
-        } = construction::create_data_structures::<I, R, T>(texts, &config, &alphabet);
+            bwt_runs,
+        } = data_structures;
 
         let mut index = FmIndex {
             alphabet,
@@ -134,13 +357,95 @@ impl<I: IndexStorage, R: TextWithRankSupport<I>> FmIndex<I, R> {
             suffix_array: sampled_suffix_array,
             text_ids,
             lookup_tables: LookupTables::new_empty(),
+            file_groups: FileGroups::single_file(),
+            construction_params: Some(ConstructionParams::from_config(config)),
+            bwt_runs: Some(bwt_runs),
+            empty_query_matches: config.empty_query_matches,
+            empty_text_policy: config.empty_text_policy,
+            text_names: Vec::new(),
+            bookmarks: Bookmarks::default(),
+            text_sampling: TextSampling::All,
+            original_text_ids: Vec::new(),
+            disabled_texts: DisabledTexts::default(),
         };
 
-        lookup_table::fill_lookup_tables(&mut index, config.lookup_table_depth);
+        config.in_thread_pool(|| {
+            config.report_progress(ConstructionStage::FillLookupTables, 0.0);
+            lookup_table::fill_lookup_tables(&mut index, config.lookup_table_depth, |fraction| {
+                config.report_progress(ConstructionStage::FillLookupTables, fraction)
+            })
+        });
 
         index
     }
 
+    /// Constructs the FM-Index from an externally computed BWT, for example one computed by ropebwt2 or grlBWT.
+    ///
+    /// The BWT must be in dense representation of the alphabet and contain one sentinel (`0`) per text. For every
+    /// row of the BWT that contains a sentinel, `sentinel_positions` must contain the suffix array value of the
+    /// row, i.e. the start of a text in the concatenation of the texts, where every text is followed by its
+    /// sentinel. This is the format of [`Bwt`](bwt::Bwt) and its
+    /// [`sentinel_suffix_array_values`](bwt::Bwt::sentinel_suffix_array_values). The sentinels can be ordered in
+    /// any of the ways described by [`SentinelOrder`](bwt::SentinelOrder).
+    ///
+    /// Only the rank support, the count table, the lookup tables and the sampled suffix array are built. The
+    /// suffix array is sampled by walking backwards through all texts, which needs one LF-mapping step per
+    /// symbol. The texts are walked in parallel, controlled by [`rayon`]. Some settings of `config` that only
+    /// affect the suffix array construction are ignored. Panics if the BWT and the sentinel positions don't
+    /// fit together or if a text is empty and rejected by the [`EmptyTextPolicy`] of `config`.
+    ///
+    /// ```
+    /// use genedex::{FmIndex, FmIndexConfig, alphabet, bwt};
+    ///
+    /// let alphabet = alphabet::ascii_dna();
+    /// let texts = [b"ACGTACGT".as_slice(), b"TTACG"];
+    /// let bwt = bwt::construct_bwt::<i32, _>(texts, &alphabet, false);
+    ///
+    /// let symbols: Vec<_> = bwt.iter().collect();
+    /// let index = FmIndex::<i32>::from_bwt(
+    ///     &symbols,
+    ///     bwt.sentinel_suffix_array_values(),
+    ///     alphabet,
+    ///     FmIndexConfig::new(),
+    /// );
+    ///
+    /// let mut hits: Vec<_> = index.locate(b"ACG").map(|hit| (hit.text_id, hit.position)).collect();
+    /// hits.sort();
+    ///
+    /// assert_eq!(hits, [(0, 0), (0, 4), (1, 2)]);
+    /// ```
+    pub fn from_bwt(
+        bwt: &[u8],
+        sentinel_positions: &BTreeMap<usize, usize>,
+        alphabet: Alphabet,
+        config: FmIndexConfig<I, R>,
+    ) -> Self {
+        if config.empty_text_policy == EmptyTextPolicy::Reject {
+            // a text is empty if its sentinel directly follows the sentinel of the previous text
+            let mut text_starts: Vec<_> = sentinel_positions.values().copied().collect();
+            text_starts.sort_unstable();
+
+            assert!(
+                text_starts
+                    .iter()
+                    .zip(text_starts.iter().skip(1).chain([&bwt.len()]))
+                    .all(|(&start, &next_start)| start + 1 != next_start),
+                "empty texts are rejected by the empty text policy of the configuration"
+            );
+        }
+
+        let data_structures = config.in_thread_pool(|| {
+            construction::from_bwt::create_data_structures_from_bwt(
+                bwt,
+                sentinel_positions,
+                &config,
+                &alphabet,
+            )
+        });
+
+        Self::from_data_structures(data_structures, alphabet, &config)
+    }
+
     /// Returns the number of occurrences of `query` in the set of indexed texts.
     ///
     /// Running time is in O(`query.len() - d`), where d is the depth of the lookup table of the index.
@@ -148,6 +453,126 @@ impl<I: IndexStorage, R: TextWithRankSupport<I>> FmIndex<I, R> {
         self.cursor_for_query(query).count()
     }
 
+    /// Like [`Self::count`], but additionally returns the length of the longest suffix of `query` that occurs
+    /// in the set of indexed texts.
+    ///
+    /// If the count is larger than zero, the match length is `query.len()`. Otherwise, it is the number
+    /// of symbols that the backward search consumed before the count dropped to zero.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+    ///
+    /// assert_eq!(index.count_with_match_length(b"GTAC"), (1, 4));
+    /// assert_eq!(index.count_with_match_length(b"TTACG"), (0, 4));
+    /// ```
+    pub fn count_with_match_length(&self, query: &[u8]) -> (usize, usize) {
+        let (mut remaining_query, query_suffix) = self.split_query_for_lookup(query);
+        let mut interval = self.lookup_tables.lookup(query_suffix, &self.alphabet);
+        let mut match_length = query_suffix.len();
+
+        // the lookup table can't tell how much of its suffix matched, so the whole query is searched step by step
+        if interval.start == interval.end {
+            remaining_query = query;
+            interval = self.lookup_tables.lookup(&[], &self.alphabet);
+            match_length = 0;
+        }
+
+        let mut cursor = Cursor {
+            index: self,
+            interval,
+        };
+
+        for &symbol in remaining_query.iter().rev() {
+            cursor.extend_query_front(symbol);
+
+            if cursor.count() == 0 {
+                return (0, match_length);
+            }
+
+            match_length += 1;
+        }
+
+        (cursor.count(), match_length)
+    }
+
+    /// Returns the matching statistics of `query` against the set of indexed texts.
+    ///
+    /// For every position `i` of the query, the result contains the length of the longest prefix of `query[i..]`
+    /// that occurs in the indexed texts, together with its suffix array interval. Symbols that are not part of the
+    /// alphabet or not searchable are never part of a match.
+    ///
+    /// The query is searched by backward search from its end. Running time is in O(`query.len()`) if the
+    /// matches of neighboring positions overlap well, and in O(`query.len()` · L) in the worst case, where L is the
+    /// length of the longest match.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+    ///
+    /// let lengths: Vec<_> = index
+    ///     .matching_statistics(b"CGTTAC")
+    ///     .iter()
+    ///     .map(|statistic| statistic.len)
+    ///     .collect();
+    ///
+    /// assert_eq!(lengths, [3, 2, 1, 3, 2, 1]);
+    /// ```
+    pub fn matching_statistics(&self, query: &[u8]) -> Vec<MatchingStatistic> {
+        matching_statistics::matching_statistics(self, query)
+    }
+
+    /// Returns the longest match that starts in each chunk of length `chunk_len` of `query`.
+    ///
+    /// For every chunk, the result contains the start position in the query and the matching statistic (see
+    /// [`Self::matching_statistics`]) of the position with the longest match. If multiple positions have the
+    /// longest match, the first one is used. The matching statistics are computed in a single pass over the whole
+    /// query, so the search state is reused across chunk boundaries and matches can extend beyond the end of their
+    /// chunk. The last chunk is shorter if the query length is not a multiple of `chunk_len`. This gives a coarse
+    /// overview of which regions of a long query, such as an assembled contig, are supported by the indexed texts.
+    /// Panics if `chunk_len` is `0`.
+    ///
+    /// The running time is the same as for [`Self::matching_statistics`].
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+    ///
+    /// let profile: Vec<_> = index
+    ///     .match_profile(b"CGTTAC", 2)
+    ///     .into_iter()
+    ///     .map(|(start, statistic)| (start, statistic.len))
+    ///     .collect();
+    ///
+    /// assert_eq!(profile, [(0, 3), (3, 3), (4, 2)]);
+    /// ```
+    pub fn match_profile(&self, query: &[u8], chunk_len: usize) -> Vec<(usize, MatchingStatistic)> {
+        matching_statistics::match_profile(self, query, chunk_len)
+    }
+
+    /// Returns the number of occurrences of each chunk of length `chunk_len` of `query`.
+    ///
+    /// The last chunk is shorter if the query length is not a multiple of `chunk_len`. The chunks are searched
+    /// independently in batches like in [`Self::count_many`], without copying them. No search state is reused across
+    /// chunk boundaries, so this is only a shorthand for searching the chunks. Use [`Self::match_profile`] for
+    /// matches that extend beyond the chunks. Panics if `chunk_len` is `0`.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+    ///
+    /// assert_eq!(index.count_chunked(b"CGTTAC", 2), [2, 0, 2]);
+    /// ```
+    pub fn count_chunked(&self, query: &[u8], chunk_len: usize) -> Vec<usize> {
+        assert!(chunk_len > 0, "chunk_len must be greater than 0");
+
+        self.count_many(query.chunks(chunk_len)).collect()
+    }
+
     /// The results of [`Self::count`] for multiple queries.
     ///
     /// The order of the queries is preserved for the counts. This function can improve the running
@@ -160,154 +585,1996 @@ impl<I: IndexStorage, R: TextWithRankSupport<I>> FmIndex<I, R> {
             .map(|cursor| cursor.count())
     }
 
+    /// Like [`Self::count_many`], but every distinct query is only searched once.
+    ///
+    /// The queries are hashed to find duplicates, and the counts are fanned out to the original positions.
+    /// This is faster when the queries contain many duplicates, for example seeds of a large read set.
+    /// All queries are collected into memory before the search starts.
+    pub fn count_many_deduplicated<Q: AsRef<[u8]>>(
+        &self,
+        queries: impl IntoIterator<Item = Q>,
+    ) -> impl Iterator<Item = usize> {
+        self.cursors_for_many_queries_deduplicated(queries)
+            .map(|cursor| cursor.count())
+    }
+
     /// Returns the occurrences of `query` in the set of indexed texts. The occurrences are not sorted by text id or position.
     ///
+    /// Instead, the occurrences are guaranteed to be returned in suffix array order. This means that they are sorted
+    /// lexicographically by the context that follows them in the texts (including the query itself).
+    /// Symbols are compared by their dense representation (see [`Alphabet`]) and the end of a text is smaller than
+    /// all other symbols. More precisely, the order is the order of the
+    /// suffixes of the concatenated texts in the suffix array of the index. This order is deterministic
+    /// and does not depend on the number of threads used for construction.
+    ///
+    /// Suffixes that are equal up to the end of their texts are ordered by the [`SentinelOrder`](bwt::SentinelOrder)
+    /// of the BWT of the index. This is [`ByFollowingTexts`](bwt::SentinelOrder::ByFollowingTexts) for indices that
+    /// are constructed from texts, but it can differ for indices from [`Self::from_bwt`] and from the semi-external
+    /// construction. Therefore, the order of such hits depends on how the index was constructed.
+    ///
     /// The initial running time is the same as for [`count`](Self::count).
-    /// For each hit pulled from the iterator, a sampled suffix array lookup is performed.
-    /// This operation needs `s / 2` steps on average, where `s` is the suffix array
-    /// sampling rate of the index.
+    /// For each hit, a sampled suffix array lookup is performed. This operation needs `s / 2` steps on average,
+    /// where `s` is the suffix array sampling rate of the index.
+    ///
+    /// The hits are resolved lazily in batches, whose lookups are interleaved to hide memory latency. The first batch
+    /// contains a single hit and every following batch is twice as large, up to a small maximum size. Therefore,
+    /// pulling only the first few hits from the iterator is cheap, and the memory usage of the iterator does not
+    /// depend on the number of occurrences, even for queries with huge numbers of occurrences (e.g. poly-A).
+    /// Only collecting the hits needs memory proportional to their number.
     pub fn locate(&self, query: &[u8]) -> impl Iterator<Item = Hit> {
         let cursor = self.cursor_for_query(query);
 
-        self.locate_interval(cursor.interval())
+        self.locate_interval(self.reported_interval(cursor.interval()))
     }
 
-    /// The results of [`Self::locate`] for multiple queries.
+    /// The results of [`Self::locate`], annotated with the suffix array index of each hit.
     ///
-    /// The order of the queries is preserved for the hits. This function can improve the running
-    /// time when many queries are searched.
-    pub fn locate_many<Q: AsRef<[u8]>>(
-        &self,
-        queries: impl IntoIterator<Item = Q>,
-    ) -> impl Iterator<Item: Iterator<Item = Hit>> {
-        self.cursors_for_many_queries(queries)
-            .map(|cursor| self.locate_interval(cursor.interval()))
+    /// The suffix array indices of the hits of a query form a contiguous range, and the hits are returned
+    /// in ascending order of the suffix array index. This can be used for deterministic tie-breaking, sampling
+    /// or to cross-reference hits with external data based on the suffix array. Providing the index has no
+    /// additional cost.
+    pub fn locate_with_sa_index(&self, query: &[u8]) -> impl Iterator<Item = (usize, Hit)> {
+        self.cursor_for_query(query).locate_with_sa_index()
     }
 
-    fn locate_interval(&self, interval: HalfOpenInterval) -> impl Iterator<Item = Hit> {
-        self.suffix_array
-            .recover_range(interval.start..interval.end, self)
-            .map(|idx| {
-                let (text_id, position) = self
-                    .text_ids
-                    .backtransfrom_concatenated_text_index(<usize as NumCast>::from(idx).unwrap());
+    /// Returns the id of a text that is equal to `record`, or `None` if no such text exists.
+    ///
+    /// Unlike a search for `record`, which finds it anywhere in the texts, this only finds complete texts. This is
+    /// useful for collections of short records, like a whitelist of barcodes. If multiple texts are equal to `record`,
+    /// the smallest text id is returned.
+    ///
+    /// The running time is in O(`record.len()`), plus a suffix array lookup for each text that is equal to `record`.
+    /// If `record` is a complete text, the texts that end with `record` are also scanned. The lookup table of the
+    /// index can't be used.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_index([b"ACGT".as_slice(), b"CG", b"ACG"], alphabet::ascii_dna());
+    ///
+    /// assert_eq!(index.contains_text(b"CG"), Some(1));
+    /// assert_eq!(index.contains_text(b"ACG"), Some(2));
+    /// assert_eq!(index.contains_text(b"GT"), None);
+    /// ```
+    pub fn contains_text(&self, record: &[u8]) -> Option<usize> {
+        let interval = self.cursor_for_query_at_text_ends(record).interval();
 
-                Hit { text_id, position }
-            })
+        self.hits_at_text_starts(interval)
+            .map(|hit| hit.text_id)
+            .min()
     }
 
-    /// Returns a cursor to the index with the empty query currently searched.
+    /// The results of [`Self::contains_text`] for multiple records.
     ///
-    /// See [`Cursor`] for details. Running time is in `O(1)`.
-    pub fn cursor_empty<'a>(&'a self) -> Cursor<'a, I, R> {
-        Cursor {
-            index: self,
-            interval: HalfOpenInterval {
-                start: 0,
-                end: self.total_text_len(),
-            },
-        }
+    /// The order of the records is preserved. The records are searched in batches like in [`Self::count_many`].
+    pub fn contains_texts<Q: AsRef<[u8]>>(
+        &self,
+        records: impl IntoIterator<Item = Q>,
+    ) -> impl Iterator<Item = Option<usize>> {
+        BatchComputedCursors::<I, R, Q, _, BATCH_SIZE>::new_anchored_at_text_ends(
+            self,
+            records.into_iter(),
+        )
+        .map(|cursor| {
+            self.hits_at_text_starts(cursor.interval())
+                .map(|hit| hit.text_id)
+                .min()
+        })
     }
 
-    /// Returns a cursor to the index with `query` currently searched.
+    /// Returns the occurrences of `query` at the starts of the indexed texts, in suffix array order.
     ///
-    /// See [`Cursor`] for details. Running time is the same as for [`count`](Self::count).
-    /// This allows using a lookup table jump and therefore can be more efficient than creating
-    /// an empty cursor and repeatedly calling [`Cursor::extend_query_front`].
-    pub fn cursor_for_query<'a>(&'a self, query: &[u8]) -> Cursor<'a, I, R> {
-        let (remaining_query, query_suffix) = self.split_query_for_lookup(query);
-        let interval = self.lookup_tables.lookup(query_suffix, &self.alphabet);
-
-        let mut cursor = Cursor {
-            index: self,
-            interval,
-        };
+    /// All hits have the position `0`. Instead of locating all occurrences of `query` and filtering them, the
+    /// occurrences that are preceded by the start of a text are determined using the BWT. Only their suffix array
+    /// entries are looked up. The suffix array interval of `query` is scanned for this, so the running time is
+    /// linear in the number of occurrences of `query`, but with a much smaller constant than [`Self::locate`].
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_index([b"ACGTACG".as_slice(), b"CGTA", b"TACG"], alphabet::ascii_dna());
+    ///
+    /// let text_ids: Vec<_> = index.locate_prefix_matches(b"CG").map(|hit| hit.text_id).collect();
+    /// assert_eq!(text_ids, [1]);
+    /// ```
+    pub fn locate_prefix_matches(&self, query: &[u8]) -> impl Iterator<Item = Hit> {
+        let interval = self.reported_interval(self.cursor_for_query(query).interval());
 
-        for &symbol in remaining_query.iter().rev() {
-            cursor.extend_query_front(symbol);
+        self.hits_at_text_starts(interval)
+    }
 
-            if cursor.count() == 0 {
-                break;
-            }
-        }
+    /// Returns the occurrences of `query` at the ends of the indexed texts, in suffix array order.
+    ///
+    /// The search starts at the ends of the texts (the sentinels) instead of at all suffixes, so only the occurrences
+    /// at the ends of texts are found. The running time is the same as for [`Self::locate`], but the lookup table of
+    /// the index can't be used.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_index([b"ACGTACG".as_slice(), b"CGTA", b"TACG"], alphabet::ascii_dna());
+    ///
+    /// let mut hits: Vec<_> = index
+    ///     .locate_suffix_matches(b"ACG")
+    ///     .map(|hit| (hit.text_id, hit.position))
+    ///     .collect();
+    /// hits.sort();
+    ///
+    /// assert_eq!(hits, [(0, 4), (2, 1)]);
+    /// ```
+    pub fn locate_suffix_matches(&self, query: &[u8]) -> impl Iterator<Item = Hit> {
+        let interval = self.reported_interval(self.cursor_for_query_at_text_ends(query).interval());
 
-        cursor
+        self.locate_interval(interval)
     }
 
-    /// The results of [`Self::cursor_for_query`] for multiple queries.
+    /// Returns the number of occurrences of `query` and its reverse complement in the set of indexed texts.
     ///
-    /// The order of the queries is preserved for the cursors. This function can improve the running
-    /// time when many queries are searched.
-    pub fn cursors_for_many_queries<'a, Q: AsRef<[u8]>>(
-        &'a self,
-        queries: impl IntoIterator<Item = Q>,
-    ) -> impl Iterator<Item = Cursor<'a, I, R>> {
-        BatchComputedCursors::<I, R, Q, _, BATCH_SIZE>::new(self, queries.into_iter())
+    /// This searches the forward and the reverse strand of DNA texts with an index of only the forward strand,
+    /// using two searches. The occurrences of palindromic queries are counted twice, like for the [`FmdIndex`],
+    /// which does this in a single search. Panics if the alphabet has no complement (see
+    /// [`Alphabet::with_complement`]) or if the query contains symbols that are not part of the alphabet.
+    pub fn count_with_revcomp(&self, query: &[u8]) -> usize {
+        self.count(query) + self.count(&self.alphabet.reverse_complement(query))
     }
 
-    fn cursor_for_query_without_alphabet_translation<'a>(
-        &'a self,
+    /// Returns the occurrences of `query` and its reverse complement in the set of indexed texts, annotated with
+    /// the strand.
+    ///
+    /// For hits on the [`Reverse`](Strand::Reverse) strand, the reverse complement of the query occurs at the
+    /// position of the hit, like for the [`FmdIndex`]. The hits on the forward strand are returned first, each strand
+    /// in suffix array order. See [`Self::count_with_revcomp`] for details.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, Strand, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"AACGTTGGAC"], alphabet::ascii_dna());
+    ///
+    /// let hits: Vec<_> = index.locate_with_revcomp(b"GTC").collect();
+    ///
+    /// assert_eq!(index.count_with_revcomp(b"GTC"), 1);
+    /// assert_eq!(hits[0].strand, Strand::Reverse);
+    /// assert_eq!(hits[0].position, 7);
+    /// ```
+    pub fn locate_with_revcomp(
+        &self,
         query: &[u8],
-    ) -> Cursor<'a, I, R> {
-        let (remaining_query, query_suffix) = self.split_query_for_lookup(query);
-        let interval = self
-            .lookup_tables
-            .lookup_without_alphabet_translation(query_suffix);
+    ) -> impl Iterator<Item = StrandedHit> + use<'_, I, R> {
+        let forward_interval = self.reported_interval(self.cursor_for_query(query).interval());
+        let reverse_interval = self.reported_interval(
+            self.cursor_for_query(&self.alphabet.reverse_complement(query))
+                .interval(),
+        );
 
-        let mut cursor = Cursor {
-            index: self,
-            interval,
+        let with_strand = |strand| {
+            move |hit: Hit| StrandedHit {
+                text_id: hit.text_id,
+                position: hit.position,
+                strand,
+            }
         };
 
-        for &symbol in remaining_query.iter().rev() {
-            cursor.extend_front_without_alphabet_translation(symbol);
+        self.locate_interval(forward_interval)
+            .map(with_strand(Strand::Forward))
+            .chain(
+                self.locate_interval(reverse_interval)
+                    .map(with_strand(Strand::Reverse)),
+            )
+    }
 
-            if cursor.count() == 0 {
-                break;
-            }
-        }
+    /// The results of [`Self::locate`], restricted to the texts for which `text_id_filter` returns `true`.
+    ///
+    /// The filter is applied to the text id of each hit directly after it was resolved, before the rest of the hit
+    /// is computed. This is useful to restrict the hits to a set of allowed texts, e.g. a whitelist of contigs.
+    /// The suffix array lookup still has to be performed for every hit, so the running time is similar to
+    /// [`Self::locate`].
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_index([b"ACGT".as_slice(), b"ACGA", b"TACG"], alphabet::ascii_dna());
+    ///
+    /// let allowed_texts = [0, 2];
+    /// let hits: Vec<_> = index
+    ///     .locate_filtered(b"ACG", |text_id| allowed_texts.contains(&text_id))
+    ///     .collect();
+    ///
+    /// assert_eq!(hits.len(), 2);
+    /// assert!(hits.iter().all(|hit| hit.text_id != 1));
+    /// ```
+    pub fn locate_filtered<'a, F: FnMut(usize) -> bool + 'a>(
+        &'a self,
+        query: &[u8],
+        mut text_id_filter: F,
+    ) -> impl Iterator<Item = Hit> + use<'a, I, R, F> {
+        let interval = self.reported_interval(self.cursor_for_query(query).interval());
 
-        cursor
+        self.suffix_array
+            .recover_range(interval.start..interval.end, self)
+            .filter_map(move |idx| {
+                let idx = <usize as NumCast>::from(idx).unwrap();
+                let text_id = self.text_ids.lookup_text_id(idx);
+
+                (!self.disabled_texts.contains(text_id) && text_id_filter(text_id)).then(|| Hit {
+                    text_id,
+                    position: self.text_ids.text_index(text_id, idx),
+                })
+            })
     }
 
-    fn lf_mapping_step(&self, symbol: u8, idx: usize) -> usize {
-        self.count[symbol as usize] + self.text_with_rank_support.rank(symbol, idx)
+    /// Hides the hits in the text `text_id` from the results of queries, without rebuilding the index. Returns
+    /// `false` if the text was already disabled.
+    ///
+    /// This is useful for excluding contaminants interactively or for leave-one-out analyses. All functions that
+    /// report hits with text ids, like [`Self::locate`], [`Self::locate_many`] or [`Self::count_per_file`], skip the
+    /// hits in disabled texts. Functions that only count occurrences, like [`Self::count`] or
+    /// [`Cursor::count`], are not affected, because they don't resolve the hits to texts.
+    ///
+    /// The disabled texts are not saved together with the index. Panics if `text_id` is not a valid text id.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let mut index = FmIndexConfig::<i32>::new()
+    ///     .construct_index([b"ACGT".as_slice(), b"ACGA", b"TACG"], alphabet::ascii_dna());
+    ///
+    /// index.disable_text(1);
+    ///
+    /// let hits: Vec<_> = index.locate(b"ACG").collect();
+    /// assert_eq!(hits.len(), 2);
+    /// assert!(hits.iter().all(|hit| hit.text_id != 1));
+    /// assert_eq!(index.count(b"ACG"), 3);
+    ///
+    /// index.enable_text(1);
+    /// assert_eq!(index.locate(b"ACG").count(), 3);
+    /// ```
+    pub fn disable_text(&mut self, text_id: usize) -> bool {
+        assert!(text_id < self.num_texts(), "invalid text id");
+        self.disabled_texts.insert(text_id)
     }
 
-    fn split_query_for_lookup<'a>(&self, query: &'a [u8]) -> (&'a [u8], &'a [u8]) {
-        let lookup_depth = std::cmp::min(query.len(), self.lookup_tables.max_depth());
-        let suffix_idx = query.len() - lookup_depth;
-        query.split_at(suffix_idx)
+    /// Makes the hits in the text `text_id` visible again. Returns `false` if the text was not disabled.
+    /// See [`Self::disable_text`].
+    pub fn enable_text(&mut self, text_id: usize) -> bool {
+        self.disabled_texts.remove(text_id)
     }
 
-    pub fn alphabet(&self) -> &Alphabet {
-        &self.alphabet
+    /// Makes the hits in all texts visible again. See [`Self::disable_text`].
+    pub fn enable_all_texts(&mut self) {
+        self.disabled_texts.clear();
     }
 
-    pub fn num_texts(&self) -> usize {
-        self.text_ids.sentinel_indices.len()
+    /// Returns whether the hits in the text `text_id` are hidden by [`Self::disable_text`]. Panics if `text_id`
+    /// is not a valid text id.
+    pub fn is_text_disabled(&self, text_id: usize) -> bool {
+        assert!(text_id < self.num_texts(), "invalid text id");
+        self.disabled_texts.contains(text_id)
     }
 
-    /// The length of all the texts that this index is built on. The value includes a sentinel symbol for each text.
+    /// Returns the ids of all disabled texts in ascending order. See [`Self::disable_text`].
+    pub fn disabled_texts(&self) -> impl Iterator<Item = usize> {
+        self.disabled_texts.iter()
+    }
+
+    /// Returns the number of occurrences of a pattern with character classes and wildcards in the set of indexed texts.
+    ///
+    /// The pattern consists of the following elements:
+    /// - a symbol of the alphabet, which matches itself
+    /// - a character class like `[ACG]`, which matches any of the contained symbols
+    /// - the wildcard `.`, which matches any searchable symbol of the alphabet
+    ///
+    /// Every element can be followed by a fixed number of repetitions like `{3}`. For example, the PROSITE pattern
+    /// `C-x(2)-[DE]` corresponds to `C.{2}[DE]`. Panics if the pattern is malformed or contains symbols that are not
+    /// searchable.
+    ///
+    /// The pattern is searched by a backward search that branches at character classes and wildcards. The suffix array
+    /// intervals of the branches are merged when they are adjacent, so the number of branches is bounded by the number
+    /// of occurrences. Still, patterns with many wildcards can lead to many branches on large texts. Use
+    /// [`Self::try_count_pattern`] to limit the number of branches.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTAAGTCCGA"], alphabet::ascii_dna());
+    ///
+    /// assert_eq!(index.count_pattern(b"[AC][CA]G"), 3);
+    /// assert_eq!(index.count_pattern(b"G.{2}"), 2);
+    /// ```
+    pub fn count_pattern(&self, pattern: &[u8]) -> usize {
+        self.try_count_pattern(pattern, usize::MAX)
+            .expect("the number of branches is not limited")
+    }
+
+    /// Version of [`Self::count_pattern`] with a configurable limit of the number of branches (distinct suffix array
+    /// intervals) of the search.
+    ///
+    /// Returns an error if the search was aborted, because the number of branches exceeded `max_branches`.
+    /// Panics if `max_branches` is `0`.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, PatternTooAmbiguous, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTAAGTCCGA"], alphabet::ascii_dna());
+    ///
+    /// assert_eq!(index.try_count_pattern(b"[AC]G", 2), Ok(3));
+    /// assert_eq!(
+    ///     index.try_count_pattern(b".A", 2),
+    ///     Err(PatternTooAmbiguous { max_branches: 2 })
+    /// );
+    /// ```
+    pub fn try_count_pattern(
+        &self,
+        pattern: &[u8],
+        max_branches: usize,
+    ) -> Result<usize, PatternTooAmbiguous> {
+        pattern::search_pattern(self, pattern, max_branches).map(|intervals| {
+            intervals
+                .iter()
+                .map(|interval| interval.end - interval.start)
+                .sum()
+        })
+    }
+
+    /// Returns the occurrences of a pattern with character classes and wildcards in the set of indexed texts.
+    ///
+    /// See [`Self::count_pattern`] for the syntax of the pattern. The occurrences are not sorted by text id or
+    /// position.
+    pub fn locate_pattern<'a>(
+        &'a self,
+        pattern: &[u8],
+    ) -> impl Iterator<Item = Hit> + use<'a, I, R> {
+        self.try_locate_pattern(pattern, usize::MAX)
+            .expect("the number of branches is not limited")
+    }
+
+    /// Version of [`Self::locate_pattern`] with a limit of the number of branches, see [`Self::try_count_pattern`].
+    pub fn try_locate_pattern<'a>(
+        &'a self,
+        pattern: &[u8],
+        max_branches: usize,
+    ) -> Result<impl Iterator<Item = Hit> + use<'a, I, R>, PatternTooAmbiguous> {
+        pattern::search_pattern(self, pattern, max_branches).map(|intervals| {
+            intervals
+                .into_iter()
+                .flat_map(|interval| self.locate_interval(interval))
+        })
+    }
+
+    /// Returns the number of occurrences of a query in which the symbols designated by `wildcards` match any
+    /// searchable symbol of the alphabet.
+    ///
+    /// Returns an error if the search was aborted, because the number of branches exceeded the limit of
+    /// `wildcards`. See [`Wildcards`] for details. Queries without wildcards are searched like in [`Self::count`].
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, Wildcards, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACCTAGGT"], alphabet::ascii_dna());
+    /// let wildcards = Wildcards::new(b"N");
+    ///
+    /// assert_eq!(index.try_count_with_wildcards(b"ACN", &wildcards), Ok(2));
+    /// assert_eq!(index.try_count_with_wildcards(b"NNGT", &wildcards), Ok(2));
+    /// assert!(index.try_count_with_wildcards(b"NNN", &wildcards.max_branches(1)).is_err());
+    /// ```
+    pub fn try_count_with_wildcards(
+        &self,
+        query: &[u8],
+        wildcards: &Wildcards,
+    ) -> Result<usize, PatternTooAmbiguous> {
+        self.try_cursors_for_wildcard_query(query, wildcards)
+            .map(|cursors| cursors.iter().map(Cursor::count).sum())
+    }
+
+    /// The results of [`Self::try_count_with_wildcards`] for multiple queries.
+    ///
+    /// The queries without wildcards are searched in batches like in [`Self::count_many`].
+    pub fn try_count_many_with_wildcards<'a, Q: AsRef<[u8]>>(
+        &'a self,
+        queries: impl IntoIterator<Item = Q>,
+        wildcards: &'a Wildcards,
+    ) -> impl Iterator<Item = Result<usize, PatternTooAmbiguous>> {
+        self.try_cursors_for_many_wildcard_queries(queries, wildcards)
+            .map(|cursors| cursors.map(|cursors| cursors.iter().map(Cursor::count).sum()))
+    }
+
+    /// Returns the occurrences of a query in which the symbols designated by `wildcards` match any searchable
+    /// symbol of the alphabet.
+    ///
+    /// Returns an error if the search was aborted, see [`Self::try_count_with_wildcards`]. The occurrences are not sorted
+    /// by text id or position.
+    pub fn try_locate_with_wildcards<'a>(
+        &'a self,
+        query: &[u8],
+        wildcards: &Wildcards,
+    ) -> Result<impl Iterator<Item = Hit> + use<'a, I, R>, PatternTooAmbiguous> {
+        self.try_cursors_for_wildcard_query(query, wildcards)
+            .map(|cursors| {
+                cursors.into_iter().flat_map(|cursor| {
+                    self.locate_interval(self.reported_interval(cursor.interval()))
+                })
+            })
+    }
+
+    /// Returns the occurrences of a motif in PROSITE syntax in the set of indexed texts, sorted by text id and range.
+    ///
+    /// The motif consists of elements separated by `-`. An element is a symbol of the alphabet, the wildcard `x`,
+    /// a set of allowed symbols like `[DE]` or a set of forbidden symbols like `{PG}`. Every element can be followed
+    /// by a number of repetitions like `(3)` or a range of repetitions like `(2,4)` (both inclusive). The motif can
+    /// start with `<` and end with `>` to anchor it at the start or end of the texts, and it can end with a `.`.
+    /// Panics if the motif is malformed or contains symbols that are not searchable.
+    ///
+    /// The motif is searched like in [`Self::count_pattern`], with one branch per repetition count. Since matches
+    /// can have different lengths, every hit contains the matched range of its text. Empty matches are not reported.
+    /// Use [`Self::try_locate_prosite_motif`] to limit the number of branches.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_index([b"MKCAADHWCGGGEH".as_slice(), b"CPPE"], alphabet::ascii_amino_acid());
+    ///
+    /// let hits = index.locate_prosite_motif(b"C-x(1,3)-[DE]-{P}.");
+    ///
+    /// assert_eq!(hits.len(), 2);
+    /// assert_eq!((hits[0].text_id, hits[0].range.clone()), (0, 2..7));
+    /// assert_eq!((hits[1].text_id, hits[1].range.clone()), (0, 8..14));
+    /// ```
+    pub fn locate_prosite_motif(&self, motif: &[u8]) -> Vec<MotifHit> {
+        self.try_locate_prosite_motif(motif, usize::MAX)
+            .expect("the number of branches is not limited")
+    }
+
+    /// Version of [`Self::locate_prosite_motif`] with a limit of the number of branches, see
+    /// [`Self::try_count_pattern`].
+    pub fn try_locate_prosite_motif(
+        &self,
+        motif: &[u8],
+        max_branches: usize,
+    ) -> Result<Vec<MotifHit>, PatternTooAmbiguous> {
+        motif::locate_prosite_motif(self, motif, max_branches)
+    }
+
+    /// Returns the approximate occurrences of `query` with an edit distance of at most `max_edit_distance`,
+    /// sorted by text id and range.
+    ///
+    /// The edit distance counts substitutions, insertions and deletions. Symbols of the query that are not part of
+    /// the alphabet or not searchable never match. Since a single occurrence can usually be matched with slightly
+    /// shifted start positions, only the match with the lowest edit distance (and then the earliest start) is reported
+    /// for every end position in a text.
+    ///
+    /// The search is a depth-first search over cursor extensions with all symbols of the alphabet, which is pruned
+    /// when the edit distance can't stay within the budget anymore. The running time grows exponentially with
+    /// `max_edit_distance`, so this is intended for small budgets.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_index([b"AAAACGTTAAAA".as_slice(), b"GGCGATT"], alphabet::ascii_dna());
+    ///
+    /// let hits = index.locate_approximate(b"CGTT", 1);
+    ///
+    /// let hits: Vec<_> = hits
+    ///     .into_iter()
+    ///     .map(|hit| (hit.text_id, hit.range, hit.edit_distance))
+    ///     .collect();
+    ///
+    /// assert_eq!(
+    ///     hits,
+    ///     [(0, 4..7, 1), (0, 4..8, 0), (0, 4..9, 1), (1, 2..6, 1), (1, 2..7, 1)]
+    /// );
+    /// ```
+    pub fn locate_approximate(
+        &self,
+        query: &[u8],
+        max_edit_distance: usize,
+    ) -> Vec<ApproximateHit> {
+        approximate::locate_approximate(self, query, max_edit_distance)
+    }
+
+    /// Returns the occurrences of `query` with at most `max_mismatches` substitutions (Hamming distance), sorted by
+    /// text id and position.
+    ///
+    /// The query is split into `max_mismatches + 1` seeds of roughly equal length. By the pigeonhole principle, every
+    /// occurrence contains at least one of the seeds without mismatches. The exact hits of the seeds are located
+    /// and the resulting candidate positions are verified against the text around the seed hits, which is extracted
+    /// from the index starting at the suffix array row of the hit. The verification of a candidate needs one
+    /// LF-mapping step per symbol in front of the seed and one inverse LF-mapping step (a binary search over ranks)
+    /// per symbol behind it, independent of the length of the text. This is efficient when the seeds are long
+    /// enough to be specific. Symbols of the query that are not searchable never match.
+    ///
+    /// Only substitutions are supported, not insertions and deletions. Use [`Self::locate_approximate`] to search
+    /// with the edit distance.
+    ///
+    /// Panics if `query` is not longer than `max_mismatches`, or if it contains symbols that are not part of the alphabet.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_index([b"AAACGTTAAAA".as_slice(), b"GGCGATT"], alphabet::ascii_dna());
+    ///
+    /// let hits: Vec<_> = index
+    ///     .search_with_seeds(b"CGTT", 1)
+    ///     .into_iter()
+    ///     .map(|hit| (hit.text_id, hit.position, hit.mismatches))
+    ///     .collect();
+    ///
+    /// assert_eq!(hits, [(0, 3, 0), (1, 2, 1)]);
+    /// ```
+    pub fn search_with_seeds(&self, query: &[u8], max_mismatches: usize) -> Vec<MismatchHit> {
+        seeds::search_with_seeds(self, query, max_mismatches)
+    }
+
+    /// Returns the (at most) `max_results` distinct substrings of the indexed texts that are closest to `query`
+    /// under edit distance, up to `max_edit_distance`.
+    ///
+    /// The results are sorted by edit distance, ties are broken by the lexicographical order of the strings.
+    /// Symbols of the query that are not part of the alphabet or not searchable never match.
+    ///
+    /// The search expands the strings of the texts in the order of the smallest edit distance they can still reach,
+    /// so it stops early when enough close strings exist. In the worst case, the running time grows exponentially
+    /// with `max_edit_distance` like for [`Self::locate_approximate`]. See [`Self::nearest_texts`] for collections of
+    /// short records like barcodes.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGTTA"], alphabet::ascii_dna());
+    ///
+    /// let nearest = index.nearest_strings(b"CGTT", 1, 3);
+    ///
+    /// assert_eq!(nearest[0].string, b"CGTT");
+    /// assert_eq!(nearest[0].edit_distance, 0);
+    /// assert_eq!(nearest[1].string, b"ACGTT");
+    /// assert_eq!(nearest[1].edit_distance, 1);
+    /// ```
+    pub fn nearest_strings(
+        &self,
+        query: &[u8],
+        max_edit_distance: usize,
+        max_results: usize,
+    ) -> Vec<NearestString> {
+        nearest_strings::nearest_strings(self, query, max_edit_distance, max_results, false)
+    }
+
+    /// Like [`Self::nearest_strings`], but only whole texts are considered.
+    ///
+    /// This is meant for indices of many short records, like a whitelist of barcodes or UMIs. Then, a sequenced
+    /// barcode can be corrected to its closest valid barcode. The count of a result is the number of texts that are
+    /// equal to it.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let whitelist = [b"ACGTAC", b"TTGCAA", b"GGATCC"];
+    /// let index = FmIndexConfig::<i32>::new().construct_index(whitelist, alphabet::ascii_dna());
+    ///
+    /// let corrected = index.nearest_texts(b"TTGGAA", 1, 1);
+    ///
+    /// assert_eq!(corrected[0].string, b"TTGCAA");
+    /// assert!(index.nearest_texts(b"AAAAAA", 1, 1).is_empty());
+    /// ```
+    pub fn nearest_texts(
+        &self,
+        query: &[u8],
+        max_edit_distance: usize,
+        max_results: usize,
+    ) -> Vec<NearestString> {
+        nearest_strings::nearest_strings(self, query, max_edit_distance, max_results, true)
+    }
+
+    /// Finds pairs of occurrences of `first_query` and `second_query` on the same text, where the second
+    /// occurrence starts between `min_distance` and `max_distance` (both inclusive) positions after the first one.
+    ///
+    /// This is useful e.g. for anchoring paired-end reads. The hits of both queries are located and sorted,
+    /// and the pairs are found by merging the sorted hits, without computing the full cross product.
+    /// The pairs are returned sorted by the first and then the second hit. Other distance predicates and joins
+    /// of arbitrary hits are supported by [`DistanceJoin`].
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_index([b"ACGTTTTTGGCC".as_slice(), b"ACGTGGCC"], alphabet::ascii_dna());
+    ///
+    /// let pairs = index.locate_pairs(b"ACG", b"GGCC", 5, 10);
+    ///
+    /// assert_eq!(pairs.len(), 1);
+    /// assert_eq!(pairs[0].first.text_id, 0);
+    /// assert_eq!(pairs[0].distance(), 8);
+    /// ```
+    pub fn locate_pairs(
+        &self,
+        first_query: &[u8],
+        second_query: &[u8],
+        min_distance: usize,
+        max_distance: usize,
+    ) -> Vec<HitPair> {
+        pairs::locate_pairs(self, first_query, second_query, min_distance, max_distance)
+    }
+
+    /// Returns the regions of the texts that are covered by occurrences of `query`, instead of individual hits.
+    ///
+    /// Every hit covers the positions `position..position + query.len()` of its text. These ranges are merged
+    /// if they overlap or if at most `merge_gap` positions lie between them. Only texts with at least one hit
+    /// are returned, sorted by text id. This is useful for coverage tracks and masking.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGACGTTACG"], alphabet::ascii_dna());
+    ///
+    /// let text_ranges = index.locate_as_ranges(b"ACG", 1);
+    ///
+    /// assert_eq!(text_ranges.len(), 1);
+    /// assert_eq!(text_ranges[0].ranges, [0..6, 8..11]);
+    /// ```
+    pub fn locate_as_ranges(&self, query: &[u8], merge_gap: usize) -> Vec<TextRanges> {
+        hit_ranges::locate_as_ranges(self, query, merge_gap)
+    }
+
+    /// Renders a simple text view of the hits of `query`, for inspecting search results in logs or notebooks.
+    ///
+    /// For each hit, the matched region of the text is extracted together with up to `flank_len` symbols on
+    /// both sides and printed above the query. Matching symbols are marked by `|` and mismatches by `*`, so the
+    /// hits are not required to be exact matches. Every involved text is extracted once using [`Self::extract_text`],
+    /// so this function is meant for debugging and reporting, not for large numbers of hits.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"TTACGTAA"], alphabet::ascii_dna());
+    ///
+    /// let view = index.render_hits(b"CGT", index.locate(b"CGT"), 2);
+    ///
+    /// assert_eq!(
+    ///     view.lines().collect::<Vec<_>>(),
+    ///     ["text 0, positions 3..6", "  text: TACGTAA", "          |||", " query:   CGT", ""]
+    /// );
+    /// ```
+    pub fn render_hits(
+        &self,
+        query: &[u8],
+        hits: impl IntoIterator<Item = Hit>,
+        flank_len: usize,
+    ) -> String {
+        hit_view::render_hits(self, query, hits, flank_len)
+    }
+
+    /// Returns a lazy handle to the occurrences of `query` in the set of indexed texts.
+    ///
+    /// The running time is the same as for [`count`](Self::count). See [`HitSet`] for details.
+    pub fn hit_set<'a>(&'a self, query: &[u8]) -> HitSet<'a, I, R> {
+        self.cursor_for_query(query).hit_set()
+    }
+
+    /// The results of [`Self::locate`] for multiple queries.
+    ///
+    /// The order of the queries is preserved for the hits, and the hits of each query are in suffix array order.
+    /// This function can improve the running time when many queries are searched.
+    pub fn locate_many<Q: AsRef<[u8]>>(
+        &self,
+        queries: impl IntoIterator<Item = Q>,
+    ) -> impl Iterator<Item: Iterator<Item = Hit>> {
+        self.cursors_for_many_queries(queries)
+            .map(|cursor| self.locate_interval(self.reported_interval(cursor.interval())))
+    }
+
+    /// Like [`Self::locate_many`], but every distinct query is only searched and located once.
+    ///
+    /// The hits of a distinct query are collected and copied for each of its duplicates. This avoids the
+    /// repeated suffix array lookups, but needs memory for the hits of all distinct queries.
+    /// See also [`Self::count_many_deduplicated`].
+    pub fn locate_many_deduplicated<Q: AsRef<[u8]>>(
+        &self,
+        queries: impl IntoIterator<Item = Q>,
+    ) -> impl Iterator<Item: Iterator<Item = Hit>> {
+        let queries: Vec<_> = queries.into_iter().collect();
+        let deduplicated = DeduplicatedQueries::new(&queries);
+
+        let distinct_hits: Vec<Vec<_>> = self
+            .locate_many(deduplicated.distinct_queries(&queries))
+            .map(|hits| hits.collect())
+            .collect();
+
+        deduplicated
+            .distinct_ids
+            .into_iter()
+            .map(move |distinct_id| distinct_hits[distinct_id].clone().into_iter())
+    }
+
+    /// Locates all queries and postprocesses their hits on other threads, while the search continues.
+    ///
+    /// The queries are searched in batches like in [`Self::locate_many`] on the calling thread. Their hits are
+    /// passed to `postprocess` on the threads of `pipeline`, together with the id (position) and the query. This
+    /// keeps both the memory-bound search and a CPU-bound postprocessing (e.g. the verification of candidate hits)
+    /// busy. The search is paused when the postprocessing falls behind, so the number of buffered hits is bounded
+    /// (see [`Pipeline`]). The results of `postprocess` are returned in the order of the queries.
+    ///
+    /// All queries are collected into memory before the search starts.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, Pipeline, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGAAG"], alphabet::ascii_dna());
+    ///
+    /// let last_positions = index.locate_many_pipelined(
+    ///     [b"ACG".as_slice(), b"AA", b"TT"],
+    ///     &Pipeline::new().num_postprocessing_threads(2),
+    ///     |_, _, hits| hits.iter().map(|hit| hit.position).max(),
+    /// );
+    ///
+    /// assert_eq!(last_positions, [Some(4), Some(7), None]);
+    /// ```
+    pub fn locate_many_pipelined<Q, T, F>(
+        &self,
+        queries: impl IntoIterator<Item = Q>,
+        pipeline: &Pipeline,
+        postprocess: F,
+    ) -> Vec<T>
+    where
+        Q: AsRef<[u8]> + Sync,
+        T: Send,
+        F: Fn(usize, &Q, Vec<Hit>) -> T + Sync,
+    {
+        pipeline::locate_many_pipelined(self, queries, pipeline, postprocess)
+    }
+
+    /// Locates all queries and adds their hits to `coverage_tracks`, without collecting the hits.
+    ///
+    /// Each hit covers `query.len()` positions. The tracks must have been created for this index.
+    /// See [`CoverageTracks`] for details.
+    pub fn locate_many_into_coverage<Q: AsRef<[u8]>>(
+        &self,
+        queries: impl IntoIterator<Item = Q>,
+        coverage_tracks: &mut CoverageTracks,
+    ) {
+        assert_eq!(
+            coverage_tracks.num_texts(),
+            self.num_texts(),
+            "the coverage tracks must belong to this index"
+        );
+
+        let queries: Vec<_> = queries.into_iter().collect();
+
+        for (query, hits) in queries.iter().zip(self.locate_many(&queries)) {
+            coverage_tracks.add_hits(hits, query.as_ref().len());
+        }
+    }
+
+    /// Returns which k-mers and positions of `query` are covered by exact matches of length `k` in the indexed texts.
+    ///
+    /// This can be used as a quick estimate of how much of the query is contained in the index.
+    /// All k-mers of the query are searched using [`Self::count_many`]. Panics if `k` is `0`.
+    pub fn coverage(&self, query: &[u8], k: usize) -> QueryCoverage {
+        assert!(k > 0, "k must be greater than 0");
+
+        let kmer_matches = self.count_many(query.windows(k)).map(|c| c > 0).collect();
+
+        QueryCoverage::new(k, query.len(), kmer_matches)
+    }
+
+    /// Returns a report of how well each of the `query_texts` is contained in the indexed texts, for example to
+    /// check an assembly against a reference.
+    ///
+    /// The report contains the number of query positions covered by exact matches of length at least `min_len`,
+    /// the texts in which the matches occur ordered by how much of the query they cover, and the uncovered gaps
+    /// of the query ordered by length. See [`ContainmentReport`] for details. The matches are found using
+    /// the [matching statistics](Self::matching_statistics) of each query, and the maximal matches are located.
+    /// The running time therefore also depends on the number of occurrences of the maximal matches.
+    ///
+    /// Matches are never empty, so a `min_len` of `0` behaves like a `min_len` of `1`.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index(
+    ///     [b"TTTTACGTACGATT".as_slice(), b"GGCCGGCC"],
+    ///     alphabet::ascii_dna(),
+    /// );
+    ///
+    /// let report = index.containment_report([b"ACGTACGACCGGCCA"], 5).remove(0);
+    ///
+    /// assert_eq!(report.num_covered_positions, 14);
+    /// assert_eq!(report.targets, [(0, 8), (1, 6)]);
+    /// assert_eq!(report.gaps, vec![14..15]);
+    /// ```
+    pub fn containment_report<Q: AsRef<[u8]>>(
+        &self,
+        query_texts: impl IntoIterator<Item = Q>,
+        min_len: usize,
+    ) -> Vec<ContainmentReport> {
+        query_texts
+            .into_iter()
+            .map(|query| containment::containment_report(self, query.as_ref(), min_len))
+            .collect()
+    }
+
+    /// Returns the natural logarithm of the likelihood of `query` under the empirical Markov model of order `order`
+    /// of the indexed texts.
+    ///
+    /// The probability of a symbol is its relative frequency in front of the (at most) `order` symbols that follow
+    /// it in the query, as returned by [`Cursor::count_per_symbol`]. This corresponds to the usual model that predicts
+    /// a symbol from the preceding symbols, because the resulting likelihood is the same (up to effects at the
+    /// borders of the texts). Contexts of the last symbols of the query are shorter than `order`. The score
+    /// can be used for composition-based classification, e.g. by comparing the scores of a read for several indices.
+    ///
+    /// The result is negative infinity if the query contains a symbol that is not searchable or a symbol that never
+    /// occurs in its context. The running time is in O(`query.len() * order`).
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACACACGT"], alphabet::ascii_dna());
+    ///
+    /// assert!(index.score_sequence(b"CACA", 1) > index.score_sequence(b"CAGT", 1));
+    /// assert_eq!(index.score_sequence(b"CATA", 1), f64::NEG_INFINITY);
+    /// ```
+    pub fn score_sequence(&self, query: &[u8], order: usize) -> f64 {
+        markov::score_sequence(self, query, order)
+    }
+
+    /// Returns every distinct substring of length `k` of the indexed texts, together with its number of occurrences.
+    ///
+    /// This is the k-mer spectrum of the texts, computed without a separate hash table. Only k-mers that consist of
+    /// searchable symbols are returned, in IO representation. The k-mers are produced lazily by a depth-first search
+    /// over the extensions of cursors, in colexicographic order of their dense representations (i.e. sorted by the
+    /// reversed k-mers). The running time is linear in the number of distinct substrings of length at most `k`.
+    ///
+    /// Panics if `k` is 0.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACG".as_slice(), b"TAC"], alphabet::ascii_dna());
+    ///
+    /// let kmers: Vec<_> = index.iter_kmers(3).collect();
+    ///
+    /// assert_eq!(
+    ///     kmers,
+    ///     [
+    ///         (b"GTA".to_vec(), 1),
+    ///         (b"TAC".to_vec(), 2),
+    ///         (b"ACG".to_vec(), 2),
+    ///         (b"CGT".to_vec(), 1),
+    ///     ]
+    /// );
+    /// ```
+    pub fn iter_kmers(&self, k: usize) -> impl Iterator<Item = (Vec<u8>, usize)> {
+        kmers::KmerIter::new(self, k)
+    }
+
+    // The search starts at the suffixes that only consist of a sentinel, which are the first rows of the
+    // suffix array. Therefore, only occurrences of the query that are followed by a sentinel are found.
+    fn cursor_for_query_at_text_ends<'a>(&'a self, query: &[u8]) -> Cursor<'a, I, R> {
+        let mut cursor = Cursor {
+            index: self,
+            interval: HalfOpenInterval {
+                start: 0,
+                end: self.num_texts(),
+            },
+        };
+
+        for &symbol in query.iter().rev() {
+            cursor.extend_query_front(symbol);
+
+            if cursor.count() == 0 {
+                break;
+            }
+        }
+
+        cursor
+    }
+
+    // The occurrences in the interval that start at the beginning of a text are exactly the rows at which the BWT
+    // contains a sentinel. Their number is known from the ranks of the sentinel, so the scan can stop early.
+    // The sentinel can't be used for an LF-mapping step, because the order of the sentinels in the suffix array
+    // does not depend on the texts that follow them.
+    fn hits_at_text_starts(&self, interval: HalfOpenInterval) -> impl Iterator<Item = Hit> {
+        let num_sentinels = self.text_with_rank_support.rank(0, interval.end)
+            - self.text_with_rank_support.rank(0, interval.start);
+
+        (interval.start..interval.end)
+            .filter(|&row| self.text_with_rank_support.symbol_at(row) == 0)
+            .take(num_sentinels)
+            .flat_map(|row| {
+                self.locate_interval(HalfOpenInterval {
+                    start: row,
+                    end: row + 1,
+                })
+            })
+    }
+
+    // The suffixes that only consist of a sentinel are the first `num_texts` rows of the suffix array. Only the
+    // interval of the empty query contains them, so it is the only one that is restricted here.
+    fn reported_interval(&self, interval: HalfOpenInterval) -> HalfOpenInterval {
+        let num_texts = self.num_texts();
+
+        match self.empty_query_matches {
+            EmptyQueryMatches::AllPositions => interval,
+            EmptyQueryMatches::TextPositions => HalfOpenInterval {
+                start: interval.start.max(num_texts),
+                end: interval.end.max(num_texts),
+            },
+            EmptyQueryMatches::NoPositions if interval.start < num_texts => HalfOpenInterval {
+                start: interval.start,
+                end: interval.start,
+            },
+            EmptyQueryMatches::NoPositions => interval,
+        }
+    }
+
+    fn locate_interval(&self, interval: HalfOpenInterval) -> impl Iterator<Item = Hit> {
+        self.locate_interval_including_disabled_texts(interval)
+            .filter(|hit| !self.disabled_texts.contains(hit.text_id))
+    }
+
+    // yields exactly one hit for every row of the interval
+    fn locate_interval_including_disabled_texts(
+        &self,
+        interval: HalfOpenInterval,
+    ) -> impl Iterator<Item = Hit> {
+        self.suffix_array
+            .recover_range(interval.start..interval.end, self)
+            .map(|idx| {
+                let (text_id, position) = self
+                    .text_ids
+                    .backtransfrom_concatenated_text_index(<usize as NumCast>::from(idx).unwrap());
+
+                Hit { text_id, position }
+            })
+    }
+
+    /// Runs a workload of queries against this index and `other` and returns the queries for which they disagree.
+    ///
+    /// This is intended for checking that two indices over supposedly equivalent collections, for example before and
+    /// after a migration of the index format or configuration, give the same results. For every query, the numbers of
+    /// occurrences after every backward search step are compared, and all hits are located in both indices. Hits are
+    /// compared by text id and position, so the texts have to be in the same order in both indices.
+    ///
+    /// Symbols that are not part of the alphabet of an index or not searchable are treated as not occurring,
+    /// so indices with different alphabets can be compared without panicking.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let texts = [b"ACGTACGT".as_slice(), b"TTGCA"];
+    /// let old_index = FmIndexConfig::<i32>::new().construct_index(texts, alphabet::ascii_dna());
+    /// let new_index = FmIndexConfig::<u32>::new()
+    ///     .suffix_array_sampling_rate(8)
+    ///     .construct_index(texts, alphabet::ascii_dna());
+    ///
+    /// let discrepancies = old_index.diff_with(&new_index, [b"ACG".as_slice(), b"GCA", b"TT"]);
+    /// assert!(discrepancies.is_empty());
+    /// ```
+    pub fn diff_with<I2: IndexStorage, R2: TextWithRankSupport<I2>, Q: AsRef<[u8]>>(
+        &self,
+        other: &FmIndex<I2, R2>,
+        queries: impl IntoIterator<Item = Q>,
+    ) -> Vec<QueryDiscrepancy> {
+        index_diff::diff_indices(self, other, queries)
+    }
+
+    /// Returns the number of occurrences of `query` in each of the files that the index was constructed from.
+    ///
+    /// The counts are ordered by file id. This function has to locate all occurrences of `query`,
+    /// so it is much slower than [`Self::count`]. See [`FmIndexConfig::construct_index_from_fasta_files`]
+    /// for details about files.
+    pub fn count_per_file(&self, query: &[u8]) -> Vec<usize> {
+        let mut counts = vec![0; self.num_files()];
+
+        for hit in self.locate(query) {
+            counts[self.file_id(hit.text_id)] += 1;
+        }
+
+        counts
+    }
+
+    /// Returns a cursor to the index with the empty query currently searched.
+    ///
+    /// See [`Cursor`] for details. Running time is in `O(1)`.
+    pub fn cursor_empty<'a>(&'a self) -> Cursor<'a, I, R> {
+        Cursor {
+            index: self,
+            interval: HalfOpenInterval {
+                start: 0,
+                end: self.total_text_len(),
+            },
+        }
+    }
+
+    /// Returns a cursor to the index with `query` currently searched.
+    ///
+    /// See [`Cursor`] for details. Running time is the same as for [`count`](Self::count).
+    /// This allows using a lookup table jump and therefore can be more efficient than creating
+    /// an empty cursor and repeatedly calling [`Cursor::extend_query_front`].
+    pub fn cursor_for_query<'a>(&'a self, query: &[u8]) -> Cursor<'a, I, R> {
+        let (remaining_query, query_suffix) = self.split_query_for_lookup(query);
+        let interval = self.lookup_tables.lookup(query_suffix, &self.alphabet);
+
+        let mut cursor = Cursor {
+            index: self,
+            interval,
+        };
+
+        for &symbol in remaining_query.iter().rev() {
+            cursor.extend_query_front(symbol);
+
+            if cursor.count() == 0 {
+                break;
+            }
+        }
+
+        cursor
+    }
+
+    /// Searches `query` and stores the result in the index under `name`, replacing an existing bookmark with the
+    /// same name.
+    ///
+    /// The bookmarks are saved together with the index. After loading, a cursor for the query can be obtained in
+    /// `O(1)` using [`Self::cursor_for_bookmark`]. This is useful for queries that are needed by every user of an
+    /// index, like adapter or vector sequences, because their search doesn't have to be repeated and the results
+    /// always belong to the exact index they were computed for.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let mut index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGTTT"], alphabet::ascii_dna());
+    /// index.add_bookmark("adapter", b"ACGT");
+    ///
+    /// let mut cursor = index.cursor_for_bookmark("adapter").unwrap();
+    /// assert_eq!(cursor.count(), 2);
+    ///
+    /// cursor.extend_query_front(b'T');
+    /// assert_eq!(cursor.count(), 1);
+    ///
+    /// assert!(index.cursor_for_bookmark("vector").is_none());
+    /// ```
+    pub fn add_bookmark(&mut self, name: impl Into<String>, query: &[u8]) {
+        let interval = self.cursor_for_query(query).interval();
+        self.bookmarks.insert(name.into(), interval);
+    }
+
+    /// Returns a cursor with the query of the bookmark `name` currently searched, or `None` if there is no such
+    /// bookmark. See [`Self::add_bookmark`].
+    pub fn cursor_for_bookmark<'a>(&'a self, name: &str) -> Option<Cursor<'a, I, R>> {
+        self.bookmarks.get(name).map(|interval| Cursor {
+            index: self,
+            interval,
+        })
+    }
+
+    /// Removes the bookmark `name` and returns whether it existed.
+    pub fn remove_bookmark(&mut self, name: &str) -> bool {
+        self.bookmarks.remove(name)
+    }
+
+    /// Returns the names of all bookmarks in lexicographic order.
+    pub fn bookmark_names(&self) -> impl Iterator<Item = &str> {
+        self.bookmarks.names()
+    }
+
+    /// Like [`Self::cursor_for_query`], but additionally records every step of the search in a [`SearchTrace`].
+    ///
+    /// Unlike the other search functions, this function does not panic if the query contains symbols that are
+    /// not part of the alphabet. Instead, the search stops at such a symbol and it is marked in the trace.
+    /// The lookup table is only used if its query suffix contains only valid, searchable symbols.
+    pub fn cursor_for_query_traced<'a>(&'a self, query: &[u8]) -> (Cursor<'a, I, R>, SearchTrace) {
+        search_trace::cursor_for_query_traced(self, query)
+    }
+
+    /// The results of [`Self::cursor_for_query`] for multiple queries.
+    ///
+    /// The order of the queries is preserved for the cursors. This function can improve the running
+    /// time when many queries are searched.
+    ///
+    /// The queries are searched in batches of 64. Windows of 1024 queries are pulled from the iterator, and if
+    /// their lengths differ a lot, for example in a mix of 12-mers and 300-mers, the queries of a window are
+    /// searched in the order of their lengths. This way, short queries don't have to wait for the long queries of
+    /// their batch to finish. Queries that are answered by the lookup table alone form their own length class.
+    /// Only the order of the queries depends on the length classes, the batch size and the use of the lookup
+    /// table are the same for all of them.
+    pub fn cursors_for_many_queries<'a, Q: AsRef<[u8]>>(
+        &'a self,
+        queries: impl IntoIterator<Item = Q>,
+    ) -> impl Iterator<Item = Cursor<'a, I, R>> {
+        LengthGroupedCursors::<I, R, Q, _, BATCH_SIZE>::new(self, queries.into_iter())
+    }
+
+    /// Like [`Self::cursors_for_many_queries`], but every distinct query is only searched once.
+    ///
+    /// See [`Self::count_many_deduplicated`] for details.
+    pub fn cursors_for_many_queries_deduplicated<'a, Q: AsRef<[u8]>>(
+        &'a self,
+        queries: impl IntoIterator<Item = Q>,
+    ) -> impl Iterator<Item = Cursor<'a, I, R>> {
+        let queries: Vec<_> = queries.into_iter().collect();
+        let deduplicated = DeduplicatedQueries::new(&queries);
+
+        let distinct_cursors: Vec<_> = self
+            .cursors_for_many_queries(deduplicated.distinct_queries(&queries))
+            .collect();
+
+        deduplicated
+            .distinct_ids
+            .into_iter()
+            .map(move |distinct_id| distinct_cursors[distinct_id])
+    }
+
+    /// Like [`Self::cursor_for_query`], but the symbols designated by `wildcards` match any searchable symbol of
+    /// the alphabet.
+    ///
+    /// The search branches at the wildcards, so one cursor is returned for every branch. Branches without
+    /// occurrences are omitted. Returns an error if the search was aborted, see [`Wildcards`] for details.
+    pub fn try_cursors_for_wildcard_query<'a>(
+        &'a self,
+        query: &[u8],
+        wildcards: &Wildcards,
+    ) -> Result<Vec<Cursor<'a, I, R>>, PatternTooAmbiguous> {
+        wildcards::cursors_for_wildcard_query(self, query, wildcards)
+    }
+
+    /// The results of [`Self::try_cursors_for_wildcard_query`] for multiple queries.
+    ///
+    /// The order of the queries is preserved. The queries without wildcards are searched in batches like in
+    /// [`Self::cursors_for_many_queries`].
+    pub fn try_cursors_for_many_wildcard_queries<'a, Q: AsRef<[u8]>>(
+        &'a self,
+        queries: impl IntoIterator<Item = Q>,
+        wildcards: &'a Wildcards,
+    ) -> impl Iterator<Item = Result<Vec<Cursor<'a, I, R>>, PatternTooAmbiguous>> {
+        wildcards::cursors_for_many_wildcard_queries(self, queries, wildcards)
+    }
+
+    fn cursor_for_query_without_alphabet_translation<'a>(
+        &'a self,
+        query: &[u8],
+    ) -> Cursor<'a, I, R> {
+        let (remaining_query, query_suffix) = self.split_query_for_lookup(query);
+        let interval = self
+            .lookup_tables
+            .lookup_without_alphabet_translation(query_suffix, &self.alphabet);
+
+        let mut cursor = Cursor {
+            index: self,
+            interval,
+        };
+
+        for &symbol in remaining_query.iter().rev() {
+            cursor.extend_front_without_alphabet_translation(symbol);
+
+            if cursor.count() == 0 {
+                break;
+            }
+        }
+
+        cursor
+    }
+
+    fn lf_mapping_step(&self, symbol: u8, idx: usize) -> usize {
+        self.count[symbol as usize] + self.text_with_rank_support.rank(symbol, idx)
+    }
+
+    // LF-mapping steps of many independent rows at once. the symbols of the BWT at the rows are written into symbols.
+    // the rows of sentinel symbols are not changed, because the LF-mapping is not rank preserving for them.
+    // like in the batched search, the memory of all ranks is prefetched before any of them is computed.
+    fn lf_mapping_step_many(&self, rows: &mut [usize], symbols: &mut [u8]) {
+        self.text_with_rank_support.symbols_at_many(rows, symbols);
+
+        for (&row, &symbol) in rows.iter().zip(symbols.iter()) {
+            if symbol != 0 {
+                self.text_with_rank_support.prefetch_rank(symbol, row);
+            }
+        }
+
+        for (row, &symbol) in rows.iter_mut().zip(symbols.iter()) {
+            if symbol != 0 {
+                // SAFETY: the row is smaller than the text length, because the symbol at it could be
+                // recovered, and the symbol was recovered from the text
+                let rank = unsafe { self.text_with_rank_support.rank_unchecked(symbol, *row) };
+                *row = self.count[symbol as usize] + rank;
+            }
+        }
+    }
+
+    // an LF-mapping step of both borders of the interval, which share the rank work if they are close to each other
+    fn lf_mapping_step_interval(&self, symbol: u8, interval: HalfOpenInterval) -> HalfOpenInterval {
+        let (start_rank, end_rank) =
+            self.text_with_rank_support
+                .rank_two(symbol, interval.start, interval.end);
+        let count = self.count[symbol as usize];
+
+        HalfOpenInterval {
+            start: count + start_rank,
+            end: count + end_rank,
+        }
+    }
+
+    // the first symbol of the suffix at the given row of the suffix array, which is determined by the counts
+    fn first_symbol_of_row(&self, row: usize) -> u8 {
+        let num_symbols = self.alphabet.num_dense_symbols();
+
+        (self.count[..num_symbols].partition_point(|&count| count <= row) - 1) as u8
+    }
+
+    // the inverse of the LF-mapping, which moves from the row of a suffix to the row of the suffix that is one
+    // symbol shorter. the position of the first symbol of the suffix in the BWT is found using a binary search over
+    // its ranks. must not be called for the rows of suffixes that start with a sentinel.
+    fn inverse_lf_mapping_step(&self, row: usize) -> usize {
+        let symbol = self.first_symbol_of_row(row);
+        let rank = row - self.count[symbol as usize];
+
+        let mut low = 0;
+        let mut high = self.total_text_len();
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+
+            if self.text_with_rank_support.rank(symbol, mid + 1) > rank {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+
+        low
+    }
+
+    // the length of the longest common prefix of the suffixes at the two rows, which never contains a sentinel
+    fn longest_common_prefix_of_rows(&self, mut row_a: usize, mut row_b: usize) -> usize {
+        let mut len = 0;
+
+        loop {
+            let symbol = self.first_symbol_of_row(row_a);
+
+            if symbol == 0 || symbol != self.first_symbol_of_row(row_b) {
+                return len;
+            }
+
+            row_a = self.inverse_lf_mapping_step(row_a);
+            row_b = self.inverse_lf_mapping_step(row_b);
+            len += 1;
+        }
+    }
+
+    // the `before` symbols in front of the suffix at the given row, followed by its first `after` symbols, in dense
+    // representation. the range must not contain a sentinel.
+    fn symbols_around_row(&self, row: usize, before: usize, after: usize) -> Vec<u8> {
+        let mut symbols = Vec::with_capacity(before + after);
+        let mut curr_row = row;
+
+        for _ in 0..before {
+            let symbol = self.text_with_rank_support.symbol_at(curr_row);
+            symbols.push(symbol);
+            curr_row = self.lf_mapping_step(symbol, curr_row);
+        }
+
+        symbols.reverse();
+        symbols.extend(self.prefix_of_row(row, after));
+
+        symbols
+    }
+
+    // the first len symbols of the suffix at the given row, in dense representation
+    fn prefix_of_row(&self, mut row: usize, len: usize) -> Vec<u8> {
+        let mut prefix = Vec::with_capacity(len);
+
+        for _ in 0..len {
+            prefix.push(self.first_symbol_of_row(row));
+            row = self.inverse_lf_mapping_step(row);
+        }
+
+        prefix
+    }
+
+    // the intervals that result from an LF-mapping step of the interval for every dense symbol
+    fn lf_mapping_step_all(&self, interval: HalfOpenInterval) -> Vec<HalfOpenInterval> {
+        let num_symbols = self.alphabet.num_dense_symbols();
+
+        if interval.start == interval.end {
+            return vec![interval; num_symbols];
+        }
+
+        // only the symbol in front of the single suffix leads to a non-empty interval
+        if interval.end - interval.start == 1 {
+            let mut intervals: Vec<_> = self.count[..num_symbols]
+                .iter()
+                .map(|&count| HalfOpenInterval {
+                    start: count,
+                    end: count,
+                })
+                .collect();
+
+            let symbol = self.text_with_rank_support.symbol_at(interval.start);
+            let start = self.lf_mapping_step(symbol, interval.start);
+            intervals[symbol as usize] = HalfOpenInterval {
+                start,
+                end: start + 1,
+            };
+
+            return intervals;
+        }
+
+        let mut start_ranks = vec![0; num_symbols];
+        let mut end_ranks = vec![0; num_symbols];
+        self.text_with_rank_support
+            .rank_all_into(interval.start, &mut start_ranks);
+        self.text_with_rank_support
+            .rank_all_into(interval.end, &mut end_ranks);
+
+        self.count
+            .iter()
+            .zip(start_ranks.into_iter().zip(end_ranks))
+            .map(|(&count, (start_rank, end_rank))| HalfOpenInterval {
+                start: count + start_rank,
+                end: count + end_rank,
+            })
+            .collect()
+    }
+
+    fn split_query_for_lookup<'a>(&self, query: &'a [u8]) -> (&'a [u8], &'a [u8]) {
+        let lookup_depth = std::cmp::min(query.len(), self.lookup_tables.max_depth());
+        let suffix_idx = query.len() - lookup_depth;
+        query.split_at(suffix_idx)
+    }
+
+    /// Replaces the alphabet of the index with a different, but compatible alphabet.
+    ///
+    /// This allows searching queries that use different IO conventions than the texts the index was built from,
+    /// without rebuilding the index. For example, RNA queries can be searched in an index of DNA texts.
+    /// The compatibility is validated once, when the alphabet is attached. See [`Alphabet::is_compatible_with`]
+    /// for details. Panics if the alphabets are not compatible.
+    ///
+    /// Afterwards, all functions of the index use the new alphabet, and it is also saved together with the index.
+    /// The previous alphabet is returned.
+    ///
+    /// ```
+    /// use genedex::{Alphabet, FmIndexConfig, alphabet};
+    ///
+    /// let mut index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+    ///
+    /// let dna_or_rna = Alphabet::from_ambiguous_io_symbols([b"Aa".as_slice(), b"Cc", b"Gg", b"TtUu"], 0);
+    /// index.attach_query_alphabet(dna_or_rna);
+    ///
+    /// assert_eq!(index.count(b"ACGU"), 2);
+    /// assert_eq!(index.count(b"acgt"), 2);
+    /// ```
+    pub fn attach_query_alphabet(&mut self, alphabet: Alphabet) -> Alphabet {
+        assert!(
+            alphabet.is_compatible_with(&self.alphabet),
+            "The query alphabet must be compatible with the alphabet of the index."
+        );
+
+        std::mem::replace(&mut self.alphabet, alphabet)
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub fn num_texts(&self) -> usize {
+        self.text_ids.sentinel_indices.len()
+    }
+
+    /// Returns the parameters that were used to construct this index.
+    ///
+    /// Returns `None` for indices that were saved by older versions of this library, which did not
+    /// store the parameters.
+    pub fn construction_params(&self) -> Option<&ConstructionParams> {
+        self.construction_params.as_ref()
+    }
+
+    /// Returns information about the runs of equal symbols in the BWT, see [`BwtRuns`].
+    ///
+    /// Returns `None` for indices that were saved by older versions of this library, which did not
+    /// store this information.
+    pub fn bwt_runs(&self) -> Option<&BwtRuns> {
+        self.bwt_runs.as_ref()
+    }
+
+    /// Iterates over the symbols of the BWT of the indexed texts, in dense representation (see [`Alphabet`]).
+    ///
+    /// The BWT contains one sentinel (`0`) per text and is the same as the one constructed by
+    /// [`bwt::construct_bwt`] for the indexed texts. Together with [`Self::sentinel_suffix_array_values`],
+    /// it can be used to construct the index again with [`Self::from_bwt`]. Every symbol is extracted from the
+    /// rank support data structure. For the wavelet tree version, this needs a rank query for every level of the
+    /// tree on the path of the symbol.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let alphabet = alphabet::ascii_dna();
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGT".as_slice(), b"AC"], alphabet.clone());
+    ///
+    /// let bwt: Vec<_> = index
+    ///     .bwt_iter()
+    ///     .map(|symbol| alphabet.try_dense_to_io_representation(symbol).unwrap_or(b'$'))
+    ///     .collect();
+    ///
+    /// assert_eq!(bwt, b"CT$$AACG");
+    /// ```
+    pub fn bwt_iter(&self) -> impl Iterator<Item = u8> {
+        (0..self.text_with_rank_support.text_len())
+            .map(|idx| self.text_with_rank_support.symbol_at(idx))
+    }
+
+    /// Returns the suffix array values of the rows of the BWT that contain a sentinel, keyed by their rows.
+    ///
+    /// These are the starts of the texts in the concatenation of the texts, where every text is followed by its
+    /// sentinel. Together with [`Self::bwt_iter`], this is the input of [`Self::from_bwt`], so an index can be
+    /// exported and constructed again without the original texts.
+    ///
+    /// ```
+    /// use genedex::{FmIndex, FmIndexConfig, alphabet};
+    ///
+    /// let alphabet = alphabet::ascii_dna();
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGT".as_slice(), b"AC"], alphabet.clone());
+    ///
+    /// let sentinel_values = index.sentinel_suffix_array_values();
+    /// assert_eq!(sentinel_values, [(2, 5), (3, 0)].into());
+    ///
+    /// let bwt: Vec<_> = index.bwt_iter().collect();
+    /// let rebuilt = FmIndex::<i32>::from_bwt(&bwt, &sentinel_values, alphabet, FmIndexConfig::new());
+    ///
+    /// assert_eq!(rebuilt.count(b"AC"), 2);
+    /// ```
+    pub fn sentinel_suffix_array_values(&self) -> BTreeMap<usize, usize> {
+        self.suffix_array
+            .text_border_entries()
+            .into_iter()
+            .collect()
+    }
+
+    /// Iterates over the samples of the suffix array of the index, as pairs of rows and suffix array values.
+    ///
+    /// The suffix array values are indices into the concatenation of the texts, where every text is followed by a
+    /// sentinel. The rows are the multiples of the suffix array sampling rate, in ascending order.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .suffix_array_sampling_rate(3)
+    ///     .construct_index([b"ACGT".as_slice(), b"AC"], alphabet::ascii_dna());
+    ///
+    /// let entries: Vec<_> = index.sampled_suffix_array_entries().collect();
+    ///
+    /// assert_eq!(entries, [(0, 7), (3, 0), (6, 2)]);
+    /// ```
+    pub fn sampled_suffix_array_entries(&self) -> impl Iterator<Item = (usize, usize)> {
+        let sampling_rate = self.suffix_array.sampling_rate();
+
+        self.suffix_array
+            .samples()
+            .iter()
+            .enumerate()
+            .map(move |(sample_idx, &value)| {
+                (
+                    sample_idx * sampling_rate,
+                    <usize as NumCast>::from(value).unwrap(),
+                )
+            })
+    }
+
+    /// Returns which occurrences of the empty query are reported by this index, see [`EmptyQueryMatches`].
+    ///
+    /// Indices that were saved by older versions of this library use [`EmptyQueryMatches::AllPositions`].
+    pub fn empty_query_matches(&self) -> EmptyQueryMatches {
+        self.empty_query_matches
+    }
+
+    /// Returns how empty texts were handled during the construction of this index, see [`EmptyTextPolicy`].
+    ///
+    /// Indices that were saved by older versions of this library use [`EmptyTextPolicy::Keep`].
+    pub fn empty_text_policy(&self) -> EmptyTextPolicy {
+        self.empty_text_policy
+    }
+
+    /// Returns the name of the text with id `text_id`, or `None` if the texts of the index have no names.
+    ///
+    /// Texts have names if the index was constructed using [`FmIndexConfig::construct_index_from_records`] or
+    /// [`FmIndexConfig::construct_index_from_fasta_files`]. Panics if `text_id` is not a valid text id of this index.
+    pub fn text_name(&self, text_id: usize) -> Option<&str> {
+        assert!(text_id < self.num_texts(), "text id out of bounds");
+
+        self.text_names.get(text_id).map(String::as_str)
+    }
+
+    /// Returns the names of all texts in the order of the text ids, or `None` if the texts of the index have no names.
+    ///
+    /// See [`Self::text_name`] for details.
+    pub fn text_names(&self) -> Option<&[String]> {
+        (!self.text_names.is_empty()).then_some(self.text_names.as_slice())
+    }
+
+    /// Returns how the indexed texts were selected from the input of the construction, see [`TextSampling`].
+    ///
+    /// Indices that were saved by older versions of this library use [`TextSampling::All`].
+    pub fn text_sampling(&self) -> TextSampling {
+        self.text_sampling
+    }
+
+    /// Returns the id of the text with id `text_id` in the input of the construction.
+    ///
+    /// The ids only differ if a subset of the texts was indexed, see [`TextSampling`]. Panics if `text_id` is not
+    /// a valid text id of this index.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, TextSampling, alphabet};
+    ///
+    /// let reads = [b"ACGT".as_slice(), b"GGTA", b"TTAC", b"CAGT", b"ACCA"];
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .text_sampling(TextSampling::EveryNth(2))
+    ///     .construct_index(reads, alphabet::ascii_dna());
+    ///
+    /// assert_eq!(index.num_texts(), 3);
+    /// assert_eq!(index.original_text_id(1), 2);
+    /// assert_eq!(index.count(b"CA"), 1);
+    /// ```
+    pub fn original_text_id(&self, text_id: usize) -> usize {
+        assert!(text_id < self.num_texts(), "text id out of bounds");
+
+        self.original_text_ids
+            .get(text_id)
+            .copied()
+            .unwrap_or(text_id)
+    }
+
+    /// Returns the ids of all texts in the input of the construction in the order of the text ids, or `None` if
+    /// all texts of the input were indexed.
+    ///
+    /// See [`Self::original_text_id`] for details.
+    pub fn original_text_ids(&self) -> Option<&[usize]> {
+        (self.text_sampling != TextSampling::All).then_some(self.original_text_ids.as_slice())
+    }
+
+    pub(crate) fn record_text_sampling(
+        &mut self,
+        text_sampling: TextSampling,
+        original_text_ids: Vec<usize>,
+    ) {
+        self.text_sampling = text_sampling;
+
+        if text_sampling != TextSampling::All {
+            self.original_text_ids = original_text_ids;
+        }
+    }
+
+    /// The number of files that the index was constructed from.
+    ///
+    /// If the index was constructed from in-memory texts, all texts belong to a single file.
+    pub fn num_files(&self) -> usize {
+        self.file_groups.num_files()
+    }
+
+    /// Returns the id of the file that contains the text with id `text_id`.
+    ///
+    /// This can be used to resolve the file of a [`Hit`]. Files have ids in the order in which they were supplied
+    /// to the construction.
+    pub fn file_id(&self, text_id: usize) -> usize {
+        assert!(text_id < self.num_texts(), "text id out of bounds");
+
+        self.file_groups.file_id(text_id)
+    }
+
+    /// Returns the ids of the texts that were read from the file with id `file_id`.
+    ///
+    /// Panics if `file_id` is not a valid file id of this index.
+    pub fn text_ids_of_file(&self, file_id: usize) -> std::ops::Range<usize> {
+        self.file_groups.text_ids(file_id, self.num_texts())
+    }
+
+    /// The length of the text with id `text_id`, without its sentinel.
+    pub fn text_len(&self, text_id: usize) -> usize {
+        self.text_ids.text_len(text_id)
+    }
+
+    /// The length of all the texts that this index is built on. The value includes a sentinel symbol for each text.
     pub fn total_text_len(&self) -> usize {
         self.text_with_rank_support.text_len()
     }
 
+    /// Reconstructs the text with id `text_id` from the index, in IO representation.
+    ///
+    /// Each symbol is translated into the first IO symbol of its dense symbol (see [`Alphabet`]). For example,
+    /// with an alphabet that ignores case, lowercase symbols of the original text are returned as uppercase symbols.
+    /// The running time is linear in the length of the text, plus one suffix array lookup per indexed text.
+    /// Panics if `text_id` is not a valid text id of this index.
+    pub fn extract_text(&self, text_id: usize) -> Vec<u8> {
+        assert!(text_id < self.num_texts(), "text id out of bounds");
+
+        let sentinel_rows = self.sentinel_rows();
+        self.extract_text_from_sentinel_row(sentinel_rows[text_id])
+    }
+
+    /// Builds a new index over the texts with the given ids, in the given order.
+    ///
+    /// The texts are extracted from this index (see [`Self::extract_text`]), so the original texts are not needed.
+    /// The new index uses the same alphabet and the same configuration as this index. The text with id `text_ids[i]`
+    /// of this index becomes the text with id `i` of the new index. For indices that were saved by older versions of
+    /// this library, the configuration is unknown and the default configuration is used. Settings that are not
+    /// stored in the index, like the [`BufferAllocator`] or the progress callback, also use their defaults.
+    ///
+    /// The sentinels of the new index are ordered like the sentinels of this index (see
+    /// [`SentinelOrder`](bwt::SentinelOrder)), for example for indices that were constructed by
+    /// [`Self::from_bwt`]. Indices whose sentinels are not ordered
+    /// [`ByFollowingTexts`](bwt::SentinelOrder::ByFollowingTexts) are constructed via their BWT, which takes
+    /// longer.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .construct_index([b"ACGT".as_slice(), b"TTTT", b"ACGA"], alphabet::ascii_dna());
+    ///
+    /// let sub_index = index.subset(&[2, 0]);
+    ///
+    /// assert_eq!(sub_index.num_texts(), 2);
+    /// assert_eq!(sub_index.count(b"TT"), 0);
+    /// assert_eq!(sub_index.extract_text(0), b"ACGA");
+    /// ```
+    pub fn subset(&self, text_ids: &[usize]) -> Self {
+        assert!(!text_ids.is_empty(), "at least one text must be selected");
+        assert!(
+            text_ids.iter().all(|&text_id| text_id < self.num_texts()),
+            "text id out of bounds"
+        );
+
+        let sentinel_rows = self.sentinel_rows();
+        let texts = text_ids
+            .iter()
+            .map(|&text_id| self.extract_text_from_sentinel_row(sentinel_rows[text_id]));
+
+        let sub_sentinel_order = match self.sentinel_order(&sentinel_rows) {
+            bwt::SentinelOrder::ByRank(ranks) => {
+                let mut sub_text_ids: Vec<_> = (0..text_ids.len()).collect();
+                sub_text_ids.sort_by_key(|&sub_text_id| ranks[text_ids[sub_text_id]]);
+
+                let mut sub_ranks = vec![0; text_ids.len()];
+                for (rank, sub_text_id) in sub_text_ids.into_iter().enumerate() {
+                    sub_ranks[sub_text_id] = rank;
+                }
+
+                bwt::SentinelOrder::ByRank(sub_ranks)
+            }
+            sentinel_order => sentinel_order,
+        };
+
+        let mut sub_index = self.construct_with_same_config(texts, &sub_sentinel_order);
+
+        if !self.text_names.is_empty() {
+            sub_index.text_names = text_ids
+                .iter()
+                .map(|&text_id| self.text_names[text_id].clone())
+                .collect();
+        }
+
+        sub_index.record_text_sampling(
+            self.text_sampling,
+            text_ids
+                .iter()
+                .map(|&text_id| self.original_text_id(text_id))
+                .collect(),
+        );
+
+        sub_index
+    }
+
+    // The texts of this index are followed by the new texts, which are in IO representation. The sentinels of the
+    // new texts are ordered like the ones of the existing texts, and for explicit ranks, they are larger than all
+    // existing sentinels. New texts get an empty name if the texts have names, belong to the last file and get
+    // the original text ids following the last original text id. Bookmarks are not kept, because their intervals
+    // change.
+    pub(crate) fn with_appended_texts(&self, new_texts: &[Vec<u8>]) -> Self {
+        let sentinel_rows = self.sentinel_rows();
+        let num_texts = self.num_texts();
+
+        let texts = sentinel_rows
+            .iter()
+            .map(|&row| self.extract_text_from_sentinel_row(row))
+            .chain(new_texts.iter().cloned());
+
+        let sentinel_order = match self.sentinel_order(&sentinel_rows) {
+            bwt::SentinelOrder::ByRank(mut ranks) => {
+                ranks.extend(num_texts..num_texts + new_texts.len());
+                bwt::SentinelOrder::ByRank(ranks)
+            }
+            sentinel_order => sentinel_order,
+        };
+
+        let mut index = self.construct_with_same_config(texts, &sentinel_order);
+
+        if !self.text_names.is_empty() {
+            index.text_names = self.text_names.clone();
+            index.text_names.resize(index.num_texts(), String::new());
+        }
+
+        let mut original_text_ids: Vec<_> = (0..num_texts)
+            .map(|text_id| self.original_text_id(text_id))
+            .collect();
+        let next_original_text_id = original_text_ids.last().map_or(0, |&id| id + 1);
+        original_text_ids.extend(next_original_text_id..next_original_text_id + new_texts.len());
+
+        index.record_text_sampling(self.text_sampling, original_text_ids);
+        index.file_groups = self.file_groups.clone();
+        index.disabled_texts = self.disabled_texts.clone();
+
+        index
+    }
+
+    fn construct_with_same_config<T: AsRef<[u8]>>(
+        &self,
+        texts: impl IntoIterator<Item = T>,
+        sentinel_order: &bwt::SentinelOrder,
+    ) -> Self {
+        let config = self.same_config();
+
+        if *sentinel_order == bwt::SentinelOrder::ByFollowingTexts {
+            return config.construct_index(texts, self.alphabet.clone());
+        }
+
+        let texts: Vec<_> = texts.into_iter().collect();
+        let bwt = bwt::construct_bwt_with_sentinel_order::<I, _>(
+            &texts,
+            &self.alphabet,
+            false,
+            sentinel_order,
+        );
+        let symbols: Vec<_> = bwt.iter().collect();
+
+        Self::from_bwt(
+            &symbols,
+            bwt.sentinel_suffix_array_values(),
+            self.alphabet.clone(),
+            config,
+        )
+    }
+
+    // the configuration that was used to construct this index, or the default for indices of older versions
+    fn same_config(&self) -> FmIndexConfig<I, R> {
+        let record_bwt_run_boundaries = self
+            .bwt_runs
+            .as_ref()
+            .is_some_and(|runs| runs.has_run_boundaries());
+
+        let config = match &self.construction_params {
+            Some(params) => FmIndexConfig::new()
+                .suffix_array_sampling_rate(params.suffix_array_sampling_rate)
+                .lookup_table_depth(params.lookup_table_depth)
+                .construction_performance_priority(params.performance_priority)
+                .slice_compression(params.slice_compression_mode),
+            None => FmIndexConfig::new(),
+        };
+
+        config
+            .record_bwt_run_boundaries(record_bwt_run_boundaries)
+            .empty_query_matches(self.empty_query_matches)
+            .empty_text_policy(self.empty_text_policy)
+    }
+
+    // The order of the sentinels is not stored, but it can be recognized from the rows of the sentinels.
+    // ByFollowingTexts is preferred if multiple orders fit, because it allows the faster construction.
+    fn sentinel_order(&self, sentinel_rows: &[usize]) -> bwt::SentinelOrder {
+        let num_texts = self.num_texts();
+
+        // the rows of the suffixes that start at the beginning of each text
+        let mut text_start_rows = vec![0; num_texts];
+
+        for (row, text_start) in self.suffix_array.text_border_entries() {
+            text_start_rows[self.text_ids.lookup_text_id(text_start)] = row;
+        }
+
+        // the sentinel at the end of the last text is the smallest, the others are ordered by the following texts
+        let mut text_ids_by_following_texts: Vec<_> = (0..num_texts).collect();
+        text_ids_by_following_texts.sort_by_key(|&text_id| {
+            (text_id + 1 < num_texts).then(|| text_start_rows[text_id + 1])
+        });
+
+        if text_ids_by_following_texts
+            .iter()
+            .enumerate()
+            .all(|(row, &text_id)| sentinel_rows[text_id] == row)
+        {
+            bwt::SentinelOrder::ByFollowingTexts
+        } else if sentinel_rows
+            .iter()
+            .enumerate()
+            .all(|(text_id, &row)| text_id == row)
+        {
+            bwt::SentinelOrder::ByTextId
+        } else {
+            bwt::SentinelOrder::ByRank(sentinel_rows.to_vec())
+        }
+    }
+
+    // the rows of the suffix array whose suffixes start with the sentinel of each text, indexed by text id
+    fn sentinel_rows(&self) -> Vec<usize> {
+        let mut sentinel_rows = vec![0; self.num_texts()];
+
+        for (row, idx) in self
+            .suffix_array
+            .recover_range(0..self.num_texts(), self)
+            .enumerate()
+        {
+            let text_id = self
+                .text_ids
+                .lookup_text_id(<usize as NumCast>::from(idx).unwrap());
+            sentinel_rows[text_id] = row;
+        }
+
+        sentinel_rows
+    }
+
+    // walks backwards through the text using the LF-mapping, until the previous sentinel is reached
+    fn extract_text_from_sentinel_row(&self, mut row: usize) -> Vec<u8> {
+        let mut text = Vec::new();
+
+        loop {
+            let symbol = self.text_with_rank_support.symbol_at(row);
+
+            if symbol == 0 {
+                break;
+            }
+
+            text.push(self.alphabet.dense_to_io_representation(symbol));
+            row = self.lf_mapping_step(symbol, row);
+        }
+
+        text.reverse();
+        text
+    }
+
+    /// Locks the memory of the large data structures of the index (text with rank support, sampled suffix array,
+    /// lookup tables and BWT run boundaries) into RAM, such that the operating system never pages them out.
+    ///
+    /// This is useful for latency-critical applications that keep the index loaded during long idle periods.
+    /// It should be called directly after constructing or loading the index. Locked memory is usually limited
+    /// by the operating system (e.g. `ulimit -l` on Linux), in which case an error is returned and no memory
+    /// stays locked. Currently, this is only supported on unix systems.
+    pub fn lock_memory(&self) -> std::io::Result<()> {
+        memory_region::lock(&self.memory_regions())
+    }
+
+    /// Reverts [`Self::lock_memory`].
+    pub fn unlock_memory(&self) -> std::io::Result<()> {
+        memory_region::unlock(&self.memory_regions())
+    }
+
+    /// Gives the operating system hints about how the memory of the large data structures of the index is accessed.
+    ///
+    /// This should be called directly after constructing or loading the index. The hints can improve the latency of
+    /// queries when the memory of the index is not yet (or no longer) in RAM. See [`IndexMemoryAdvice`] for details.
+    /// Currently, this is only supported on Linux, Android and Apple systems. Requires the `memory-advice` feature.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, IndexMemoryAdvice, MemoryAdvice, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGT"], alphabet::ascii_dna());
+    ///
+    /// let advice = IndexMemoryAdvice {
+    ///     suffix_array: MemoryAdvice::WillNeed,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// # #[cfg(target_os = "linux")]
+    /// index.advise_memory(&advice).unwrap();
+    /// ```
+    #[cfg(feature = "memory-advice")]
+    pub fn advise_memory(&self, advice: &IndexMemoryAdvice) -> std::io::Result<()> {
+        memory_region::advise(
+            &self.text_with_rank_support.memory_regions(),
+            advice.text_with_rank_support,
+        )?;
+        memory_region::advise(&self.suffix_array.memory_regions(), advice.suffix_array)?;
+
+        let bwt_run_regions: Vec<_> = self
+            .bwt_runs
+            .iter()
+            .filter_map(|runs| runs.memory_region())
+            .collect();
+        memory_region::advise(&bwt_run_regions, advice.bwt_run_boundaries)
+    }
+
+    /// Returns content hashes of the text with rank support and the sampled suffix array.
+    ///
+    /// The hashes can be stored together with artifacts derived from the index, to detect when an artifact no
+    /// longer matches the index. See [`ComponentHashes`] for details. This function reads the whole BWT,
+    /// so its running time is linear in the total length of the texts. It runs in parallel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGT"], alphabet::ascii_dna());
+    /// let other_index = FmIndexConfig::<i32>::new().construct_index([b"ACGA"], alphabet::ascii_dna());
+    ///
+    /// assert_eq!(index.component_hashes(), index.clone().component_hashes());
+    /// assert_ne!(index.component_hashes(), other_index.component_hashes());
+    /// ```
+    pub fn component_hashes(&self) -> ComponentHashes {
+        component_hashes::component_hashes(self)
+    }
+
+    /// Returns the number of bytes that the components of the index use on the heap.
+    ///
+    /// Unlike the `mem_dbg` feature, this needs no additional dependency. The running time is in O(1), except for
+    /// small components like the text names. See [`HeapSizes`] for details.
+    ///
+    /// ```
+    /// use genedex::{FmIndexConfig, alphabet};
+    ///
+    /// let index = FmIndexConfig::<i32>::new()
+    ///     .suffix_array_sampling_rate(1)
+    ///     .construct_index([b"ACGTACGTACGT"], alphabet::ascii_dna());
+    ///
+    /// let sizes = index.heap_size_bytes();
+    ///
+    /// // one 4-byte value per suffix, including the sentinel
+    /// assert!(sizes.suffix_array >= 13 * 4);
+    /// assert_eq!(sizes.bwt_runs, 0);
+    /// assert!(sizes.total() > sizes.text_with_rank_support + sizes.suffix_array);
+    /// ```
+    pub fn heap_size_bytes(&self) -> HeapSizes {
+        heap_size::heap_sizes(self)
+    }
+
+    fn memory_regions(&self) -> Vec<MemoryRegion> {
+        let mut regions = self.text_with_rank_support.memory_regions();
+        regions.extend(self.suffix_array.memory_regions());
+        regions.extend(self.lookup_tables.memory_regions());
+        regions.extend(self.bwt_runs.iter().filter_map(|runs| runs.memory_region()));
+        regions
+    }
+
     #[cfg(feature = "savefile")]
-    const VERSION_FOR_SAVEFILE: u32 = 0;
+    const VERSION_FOR_SAVEFILE: u32 = 10;
 
     #[cfg(feature = "savefile")]
     pub fn load_from_reader(
         reader: &mut impl std::io::Read,
     ) -> Result<Self, savefile::SavefileError> {
-        savefile::load(reader, Self::VERSION_FOR_SAVEFILE)
+        let mut index: Self = savefile::load(reader, Self::VERSION_FOR_SAVEFILE)?;
+        index.alphabet.restore_missing_searchable_codes();
+        index.alphabet.restore_missing_complement();
+
+        Ok(index)
     }
 
     #[cfg(feature = "savefile")]
     pub fn load_from_file(
         filepath: impl AsRef<std::path::Path>,
     ) -> Result<Self, savefile::SavefileError> {
-        savefile::load_file(filepath, Self::VERSION_FOR_SAVEFILE)
+        let mut index: Self = savefile::load_file(filepath, Self::VERSION_FOR_SAVEFILE)?;
+        index.alphabet.restore_missing_searchable_codes();
+        index.alphabet.restore_missing_complement();
+
+        Ok(index)
     }
 
     #[cfg(feature = "savefile")]
@@ -328,18 +2595,71 @@ impl<I: IndexStorage, R: TextWithRankSupport<I>> FmIndex<I, R> {
 }
 
 /// Represents an occurrence of a searched query in the set of indexed texts.
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Hit {
     pub text_id: usize,
     pub position: usize,
 }
 
+impl std::fmt::Display for Hit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "text {}, position {}", self.text_id, self.position)
+    }
+}
+
+/// Converts a tuple of text id and position into a hit.
+impl From<(usize, usize)> for Hit {
+    fn from((text_id, position): (usize, usize)) -> Self {
+        Self { text_id, position }
+    }
+}
+
+/// Converts a hit into a tuple of text id and position.
+impl From<Hit> for (usize, usize) {
+    fn from(hit: Hit) -> Self {
+        (hit.text_id, hit.position)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) struct HalfOpenInterval {
     pub start: usize,
     pub end: usize,
 }
 
+// the texts are checked lazily, while they are consumed by the construction
+fn check_empty_text_policy<T: AsRef<[u8]>>(
+    texts: impl IntoIterator<Item = T>,
+    empty_text_policy: EmptyTextPolicy,
+) -> impl Iterator<Item = T> {
+    texts.into_iter().inspect(move |text| {
+        assert!(
+            empty_text_policy == EmptyTextPolicy::Keep || !text.as_ref().is_empty(),
+            "empty texts are rejected by the empty text policy of the configuration"
+        );
+    })
+}
+
+// compile-time guarantee that the index and the types borrowing from it can be shared between threads,
+// for all type parameters
+#[allow(dead_code)]
+fn assert_send_sync<I: IndexStorage, R: TextWithRankSupport<I>>() {
+    fn is_send_sync<T: Send + Sync>() {}
+
+    is_send_sync::<FmIndex<I, R>>();
+    is_send_sync::<Cursor<'_, I, R>>();
+    is_send_sync::<HitSet<'_, I, R>>();
+    is_send_sync::<AnyFmIndex>();
+    is_send_sync::<IndexSet<I, R>>();
+    is_send_sync::<GrowableFmIndex<I, R>>();
+    is_send_sync::<BidirectionalFmIndex<I, R>>();
+    is_send_sync::<BidirectionalCursor<'_, I, R>>();
+    is_send_sync::<FmdIndex<I, R>>();
+    is_send_sync::<FmdCursor<'_, I, R>>();
+}
+
 mod maybe_savefile {
     #[cfg(feature = "savefile")]
     pub trait MaybeSavefile: savefile::Savefile {}
@@ -347,6 +2667,8 @@ mod maybe_savefile {
     #[cfg(not(feature = "savefile"))]
     pub trait MaybeSavefile {}
 
+    impl MaybeSavefile for u8 {}
+    impl MaybeSavefile for u16 {}
     impl MaybeSavefile for i32 {}
     impl MaybeSavefile for u32 {}
     impl MaybeSavefile for i64 {}
@@ -368,6 +2690,8 @@ mod maybe_mem_dbg {
     #[cfg(not(feature = "mem_dbg"))]
     pub trait MaybeMemDbgCopy {}
 
+    impl MaybeMemDbgCopy for u8 {}
+    impl MaybeMemDbgCopy for u16 {}
     impl MaybeMemDbgCopy for i32 {}
     impl MaybeMemDbgCopy for u32 {}
     impl MaybeMemDbgCopy for i64 {}
@@ -375,4 +2699,7 @@ mod maybe_mem_dbg {
 
 mod sealed {
     pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
 }
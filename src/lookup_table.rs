@@ -1,7 +1,8 @@
 use num_traits::NumCast;
 
 use crate::{
-    Alphabet, FmIndex, HalfOpenInterval, IndexStorage, text_with_rank_support::TextWithRankSupport,
+    Alphabet, FmIndex, HalfOpenInterval, IndexStorage, memory_region::MemoryRegion,
+    text_with_rank_support::TextWithRankSupport,
 };
 
 // the lookup table allows obtaining the interval for a query suffix directly, without LF-mappings
@@ -48,6 +49,22 @@ impl<I: IndexStorage> LookupTables<I> {
         }
     }
 
+    pub(crate) fn heap_size_bytes(&self) -> usize {
+        size_of_val(self.factors.as_slice())
+            + size_of_val(self.tables.as_slice())
+            + self
+                .tables
+                .iter()
+                .map(|table| size_of_val(table.data.as_slice()))
+                .sum::<usize>()
+    }
+
+    pub(crate) fn memory_regions(&self) -> impl Iterator<Item = MemoryRegion> {
+        self.tables
+            .iter()
+            .map(|table| MemoryRegion::of_slice(&table.data))
+    }
+
     pub(crate) fn lookup(&self, query_suffix: &[u8], alphabet: &Alphabet) -> HalfOpenInterval {
         let idx = self.compute_lookup_idx(query_suffix, alphabet);
         self.lookup_idx(query_suffix.len(), idx)
@@ -56,8 +73,9 @@ impl<I: IndexStorage> LookupTables<I> {
     pub(crate) fn lookup_without_alphabet_translation(
         &self,
         query_suffix: &[u8],
+        alphabet: &Alphabet,
     ) -> HalfOpenInterval {
-        let idx = self.compute_lookup_idx_without_alphabet_transition(query_suffix);
+        let idx = self.compute_lookup_idx_without_alphabet_transition(query_suffix, alphabet);
         self.lookup_idx(query_suffix.len(), idx)
     }
 
@@ -104,9 +122,8 @@ impl<I: IndexStorage> LookupTables<I> {
         let mut idx = 0;
 
         for (&symbol, &factor) in query_suffix.iter().zip(&self.factors) {
-            // subtract one, because the sentinel is not stored in the table
-            let dense_symbol = alphabet.io_to_dense_representation(symbol) - 1;
-            idx += dense_symbol as usize * factor;
+            let dense_symbol = alphabet.io_to_dense_representation(symbol);
+            idx += searchable_code(dense_symbol, alphabet) * factor;
         }
 
         idx
@@ -115,12 +132,12 @@ impl<I: IndexStorage> LookupTables<I> {
     pub(crate) fn compute_lookup_idx_without_alphabet_transition(
         &self,
         query_suffix: &[u8],
+        alphabet: &Alphabet,
     ) -> usize {
         let mut idx = 0;
 
         for (&symbol, &factor) in query_suffix.iter().zip(&self.factors) {
-            // subtract one, because the sentinel is not stored in the table
-            idx += (symbol as usize - 1) * factor;
+            idx += searchable_code(symbol, alphabet) * factor;
         }
 
         idx
@@ -152,17 +169,24 @@ pub(crate) fn compute_lookup_idx_static_len<const N: usize>(
     let mut idx = 0;
 
     for (&symbol, &factor) in query_suffix.iter().zip(factors) {
-        // subtract one, because the sentinel is not stored in the table
-        let dense_symbol = alphabet.io_to_dense_representation(symbol) - 1;
-        idx += dense_symbol as usize * factor;
+        let dense_symbol = alphabet.io_to_dense_representation(symbol);
+        idx += searchable_code(dense_symbol, alphabet) * factor;
     }
 
     idx
 }
 
+// the table only stores the searchable symbols, using their compact codes
+fn searchable_code(dense_symbol: u8, alphabet: &Alphabet) -> usize {
+    alphabet
+        .searchable_code(dense_symbol)
+        .expect("symbols in the query suffix of the lookup table must be searchable") as usize
+}
+
 pub(crate) fn fill_lookup_tables<I: IndexStorage, R: TextWithRankSupport<I>>(
     index: &mut FmIndex<I, R>,
     max_depth: usize,
+    report_progress: impl Fn(f32),
 ) {
     let num_symbols = index.alphabet.num_searchable_dense_symbols();
     index.lookup_tables.num_symbols = num_symbols;
@@ -171,12 +195,19 @@ pub(crate) fn fill_lookup_tables<I: IndexStorage, R: TextWithRankSupport<I>>(
         .map(|exponent| num_symbols.pow(exponent as u32))
         .collect();
 
+    // the progress is the fraction of filled entries
+    let total_num_values: usize = index.lookup_tables.factors.iter().sum();
+    let mut num_filled_values = 0;
+
     // iteratively fill lookup tables, to allow using the smaller tables in the search already for the larger tables
     for depth in 0..=max_depth {
         index
             .lookup_tables
             .tables
             .push(LookupTable::new(depth, num_symbols, index));
+
+        num_filled_values += index.lookup_tables.factors[depth];
+        report_progress(num_filled_values as f32 / total_num_values as f32);
     }
 }
 
@@ -185,6 +216,8 @@ pub(crate) fn fill_lookup_tables<I: IndexStorage, R: TextWithRankSupport<I>>(
 #[derive(Debug, Clone)]
 struct LookupTable<I> {
     data: Vec<(I, I)>,
+    // only read by the serialization
+    #[cfg_attr(not(feature = "savefile"), allow(dead_code))]
     depth: usize,
 }
 
@@ -232,7 +265,7 @@ fn fill_table<I: IndexStorage, R: TextWithRankSupport<I>>(
 ) {
     if curr_depth == max_depth {
         for symbol in 0..num_symbols {
-            query[curr_depth - 1] = symbol as u8 + 1; // +1 to offset sentinel
+            query[curr_depth - 1] = index.alphabet.dense_symbol_of_searchable_code(symbol as u8);
 
             let interval = index
                 .cursor_for_query_without_alphabet_translation(query)
@@ -240,7 +273,7 @@ fn fill_table<I: IndexStorage, R: TextWithRankSupport<I>>(
 
             let idx = index
                 .lookup_tables
-                .compute_lookup_idx_without_alphabet_transition(query);
+                .compute_lookup_idx_without_alphabet_transition(query, &index.alphabet);
 
             data[idx] = (
                 <I as NumCast>::from(interval.start).unwrap(),
@@ -252,7 +285,7 @@ fn fill_table<I: IndexStorage, R: TextWithRankSupport<I>>(
     }
 
     for symbol in 0..num_symbols {
-        query[curr_depth - 1] = symbol as u8 + 1; // +1 to offset sentinel
+        query[curr_depth - 1] = index.alphabet.dense_symbol_of_searchable_code(symbol as u8);
         fill_table(curr_depth + 1, max_depth, num_symbols, data, query, index);
     }
 }
@@ -0,0 +1,120 @@
+use crate::{FmIndex, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+// Every symbol of the query is predicted from the (at most) `order` symbols that follow it, because the cursor
+// extends queries at the front. The product of these probabilities is the same as for the usual left-to-right
+// factorization, because both are the product of the counts of all (order + 1)-mers of the query, divided by
+// the counts of the inner order-mers (up to effects at the borders of the texts).
+pub(crate) fn score_sequence<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+    order: usize,
+) -> f64 {
+    let alphabet = index.alphabet();
+
+    let all_symbols_searchable = query.iter().all(|&symbol| {
+        alphabet
+            .try_io_to_dense_representation(symbol)
+            .is_some_and(|dense_symbol| alphabet.is_searchable(dense_symbol))
+    });
+
+    if !all_symbols_searchable {
+        return f64::NEG_INFINITY;
+    }
+
+    let mut log_likelihood = 0.0;
+
+    for i in (0..query.len()).rev() {
+        let context = &query[i + 1..(i + 1 + order).min(query.len())];
+        let counts = index.cursor_for_query(context).count_per_symbol();
+
+        let dense_symbol = alphabet.io_to_dense_representation(query[i]);
+        let symbol_count = counts
+            .iter()
+            .find(|&&(symbol, _)| alphabet.io_to_dense_representation(symbol) == dense_symbol)
+            .map_or(0, |&(_, count)| count);
+
+        if symbol_count == 0 {
+            return f64::NEG_INFINITY;
+        }
+
+        let total_count: usize = counts.iter().map(|&(_, count)| count).sum();
+
+        log_likelihood += (symbol_count as f64 / total_count as f64).ln();
+    }
+
+    log_likelihood
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    fn count_occurrences(texts: &[Vec<u8>], string: &[u8]) -> usize {
+        texts
+            .iter()
+            .map(|text| {
+                text.windows(string.len())
+                    .filter(|window| *window == string)
+                    .count()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn zeroth_order_is_composition() {
+        let index = FmIndexConfig::<i32>::new().construct_index([b"AACG"], alphabet::ascii_dna());
+
+        let expected = 2.0 * 0.5f64.ln() + 0.25f64.ln();
+
+        assert!((index.score_sequence(b"ACA", 0) - expected).abs() < 1e-9);
+        assert_eq!(index.score_sequence(b"ACT", 0), f64::NEG_INFINITY);
+        assert_eq!(index.score_sequence(b"ACU", 0), f64::NEG_INFINITY);
+        assert_eq!(index.score_sequence(b"", 2), 0.0);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn score_agrees_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 0..40), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 0..8),
+            order in 0usize..4,
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_index(&texts, alphabet::ascii_dna());
+
+            let mut expected = 0.0;
+
+            for i in 0..query.len() {
+                let context_end = (i + 1 + order).min(query.len());
+                let symbol_count = count_occurrences(&texts, &query[i..context_end]);
+
+                let total_count: usize = b"ACGT"
+                    .iter()
+                    .map(|&symbol| {
+                        let mut string = vec![symbol];
+                        string.extend_from_slice(&query[i + 1..context_end]);
+                        count_occurrences(&texts, &string)
+                    })
+                    .sum();
+
+                expected += if symbol_count == 0 {
+                    f64::NEG_INFINITY
+                } else {
+                    (symbol_count as f64 / total_count as f64).ln()
+                };
+            }
+
+            let score = index.score_sequence(&query, order);
+
+            if expected == f64::NEG_INFINITY {
+                prop_assert_eq!(score, f64::NEG_INFINITY);
+            } else {
+                prop_assert!((score - expected).abs() < 1e-9, "{} vs {}", score, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::{cmp::Reverse, ops::Range};
+
+use crate::{Cursor, FmIndex, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+/// The matching statistic of a single position of a query, computed by [`FmIndex::matching_statistics`].
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MatchingStatistic {
+    /// The length of the longest prefix of the query suffix starting at this position that occurs in the
+    /// indexed texts.
+    pub len: usize,
+    /// The suffix array interval of this longest prefix. If `len` is `0`, it contains all suffixes.
+    pub sa_interval: Range<usize>,
+}
+
+impl MatchingStatistic {
+    /// The number of occurrences of the longest prefix in the indexed texts.
+    pub fn count(&self) -> usize {
+        self.sa_interval.len()
+    }
+}
+
+pub(crate) fn matching_statistics<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+) -> Vec<MatchingStatistic> {
+    let alphabet = index.alphabet();
+
+    // symbols that are not searchable can't be part of a match
+    let dense_query: Vec<_> = query
+        .iter()
+        .map(|&symbol| {
+            alphabet
+                .try_io_to_dense_representation(symbol)
+                .filter(|&dense_symbol| alphabet.is_searchable(dense_symbol))
+        })
+        .collect();
+
+    let mut statistics = vec![
+        MatchingStatistic {
+            len: 0,
+            sa_interval: 0..0,
+        };
+        query.len()
+    ];
+
+    // the cursor always contains the match dense_query[start + 1..end] of the previous position
+    let mut cursor = index.cursor_empty();
+    let mut end = query.len();
+
+    for start in (0..query.len()).rev() {
+        match dense_query[start] {
+            None => {
+                cursor = index.cursor_empty();
+                end = start;
+            }
+            Some(symbol) => {
+                let mut extended = cursor;
+                extended.extend_front_without_alphabet_translation(symbol);
+
+                // the match of this position ends at most where the match of the previous position ends,
+                // so the end only moves to the front and the search is restarted for every removed symbol
+                while extended.count() == 0 {
+                    end -= 1;
+                    extended = backward_search(index, &dense_query[start..end]);
+                }
+
+                cursor = extended;
+            }
+        }
+
+        let interval = cursor.interval();
+
+        statistics[start] = MatchingStatistic {
+            len: end - start,
+            sa_interval: interval.start..interval.end,
+        };
+    }
+
+    statistics
+}
+
+// the matching statistics are computed for the whole query, so the matches can extend beyond the chunks
+pub(crate) fn match_profile<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+    chunk_len: usize,
+) -> Vec<(usize, MatchingStatistic)> {
+    assert!(chunk_len > 0, "chunk_len must be greater than 0");
+
+    matching_statistics(index, query)
+        .chunks(chunk_len)
+        .enumerate()
+        .map(|(chunk_idx, chunk)| {
+            // the first of multiple longest matches is used
+            let (offset, statistic) = chunk
+                .iter()
+                .enumerate()
+                .max_by_key(|(offset, statistic)| (statistic.len, Reverse(*offset)))
+                .unwrap();
+
+            (chunk_idx * chunk_len + offset, statistic.clone())
+        })
+        .collect()
+}
+
+fn backward_search<'a, I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &'a FmIndex<I, R>,
+    dense_query: &[Option<u8>],
+) -> Cursor<'a, I, R> {
+    let mut cursor = index.cursor_empty();
+
+    for &symbol in dense_query.iter().rev().flatten() {
+        cursor.extend_front_without_alphabet_translation(symbol);
+
+        if cursor.count() == 0 {
+            break;
+        }
+    }
+
+    cursor
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn matching_statistics_agree_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..40), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 0..30),
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_index(&texts, alphabet::ascii_dna_with_n());
+
+            let occurs = |range: std::ops::Range<usize>| {
+                !query[range.clone()].contains(&b'N') && index.count(&query[range]) > 0
+            };
+
+            let statistics = index.matching_statistics(&query);
+            prop_assert_eq!(statistics.len(), query.len());
+
+            for (start, statistic) in statistics.iter().enumerate() {
+                let expected_len = (start..=query.len())
+                    .rev()
+                    .find(|&end| end == start || occurs(start..end))
+                    .unwrap() - start;
+
+                prop_assert_eq!(statistic.len, expected_len);
+
+                let longest_prefix = &query[start..start + statistic.len];
+                let expected_interval = index.cursor_for_query(longest_prefix).interval();
+
+                prop_assert_eq!(
+                    statistic.sa_interval.clone(),
+                    expected_interval.start..expected_interval.end
+                );
+            }
+        }
+
+        #[test]
+        fn match_profile_agrees_with_matching_statistics(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..40), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 0..30),
+            chunk_len in 1usize..8,
+        ) {
+            let index = FmIndexConfig::<i32>::new().construct_index(&texts, alphabet::ascii_dna_with_n());
+
+            let statistics = index.matching_statistics(&query);
+            let profile = index.match_profile(&query, chunk_len);
+            prop_assert_eq!(profile.len(), query.len().div_ceil(chunk_len));
+
+            for (chunk_idx, (start, statistic)) in profile.into_iter().enumerate() {
+                let chunk = chunk_idx * chunk_len..((chunk_idx + 1) * chunk_len).min(query.len());
+                let expected_len = statistics[chunk.clone()].iter().map(|statistic| statistic.len).max();
+
+                prop_assert!(chunk.contains(&start));
+                prop_assert_eq!(Some(statistic.len), expected_len);
+                prop_assert_eq!(&statistic, &statistics[start]);
+                prop_assert!(statistics[chunk.start..start].iter().all(|other| other.len < statistic.len));
+            }
+
+            let expected_counts: Vec<_> = query.chunks(chunk_len).map(|chunk| index.count(chunk)).collect();
+            prop_assert_eq!(index.count_chunked(&query, chunk_len), expected_counts);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+// A contiguous region of memory of one of the large data structures of the index. This is used to
+// apply operating system level memory operations such as locking to the index.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MemoryRegion {
+    ptr: *const u8,
+    len: usize,
+}
+
+impl MemoryRegion {
+    pub(crate) fn of_slice<T>(slice: &[T]) -> Self {
+        Self {
+            ptr: slice.as_ptr().cast(),
+            len: std::mem::size_of_val(slice),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+}
+
+// locks all regions in memory. if one region fails, the already locked regions are unlocked again.
+pub(crate) fn lock(regions: &[MemoryRegion]) -> std::io::Result<()> {
+    for (i, region) in regions.iter().enumerate() {
+        if let Err(err) = sys::lock(*region) {
+            let _ = unlock(&regions[..i]);
+            return Err(err);
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn unlock(regions: &[MemoryRegion]) -> std::io::Result<()> {
+    let mut result = Ok(());
+
+    for region in regions {
+        if let Err(err) = sys::unlock(*region) {
+            result = Err(err);
+        }
+    }
+
+    result
+}
+
+/// A hint for the operating system about how the memory of a component of the index will be accessed.
+///
+/// See [`IndexMemoryAdvice`] for details.
+#[cfg(feature = "memory-advice")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryAdvice {
+    /// No special treatment (`MADV_NORMAL`).
+    Normal,
+    /// The memory is accessed in random order, so reading ahead is not useful (`MADV_RANDOM`).
+    Random,
+    /// The memory is accessed sequentially, so it can be read ahead aggressively (`MADV_SEQUENTIAL`).
+    Sequential,
+    /// The memory will be accessed soon, so it can be read ahead now (`MADV_WILLNEED`).
+    WillNeed,
+}
+
+/// Hints for the operating system about how the large components of an index are accessed, applied by
+/// [`FmIndex::advise_memory`](crate::FmIndex::advise_memory). Requires the `memory-advice` feature.
+///
+/// The access patterns of the index are well known. The rank structure and the suffix array samples are accessed
+/// at random positions by the search and the locate functions, while the run boundaries of the BWT are scanned.
+/// The default advice reflects this. The hints mainly influence how much the operating system reads ahead when
+/// the memory is paged in, which matters most when the memory of the index is backed by a file, e.g. after it
+/// was swapped out or when the index is memory-mapped. They never change the results of queries.
+#[cfg(feature = "memory-advice")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IndexMemoryAdvice {
+    /// Advice for the text with rank support. The default is [`MemoryAdvice::Random`].
+    pub text_with_rank_support: MemoryAdvice,
+    /// Advice for the samples of the suffix array. The default is [`MemoryAdvice::Random`].
+    pub suffix_array: MemoryAdvice,
+    /// Advice for the run boundaries of the BWT, if they were recorded. The default is [`MemoryAdvice::Sequential`].
+    pub bwt_run_boundaries: MemoryAdvice,
+}
+
+#[cfg(feature = "memory-advice")]
+impl Default for IndexMemoryAdvice {
+    fn default() -> Self {
+        Self {
+            text_with_rank_support: MemoryAdvice::Random,
+            suffix_array: MemoryAdvice::Random,
+            bwt_run_boundaries: MemoryAdvice::Sequential,
+        }
+    }
+}
+
+#[cfg(feature = "memory-advice")]
+pub(crate) fn advise(regions: &[MemoryRegion], advice: MemoryAdvice) -> std::io::Result<()> {
+    for region in regions {
+        sys::advise(*region, advice)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+mod sys {
+    use super::MemoryRegion;
+    use std::ffi::{c_int, c_void};
+
+    unsafe extern "C" {
+        fn mlock(addr: *const c_void, len: usize) -> c_int;
+        fn munlock(addr: *const c_void, len: usize) -> c_int;
+        #[cfg(all(
+            feature = "memory-advice",
+            any(target_os = "linux", target_os = "android", target_vendor = "apple")
+        ))]
+        fn madvise(addr: *mut c_void, len: usize, advice: c_int) -> c_int;
+        #[cfg(all(
+            feature = "memory-advice",
+            any(target_os = "linux", target_os = "android", target_vendor = "apple")
+        ))]
+        fn sysconf(name: c_int) -> std::ffi::c_long;
+    }
+
+    // the values of these constants are the same on Linux and the BSDs, including macOS
+    #[cfg(all(
+        feature = "memory-advice",
+        any(target_os = "linux", target_os = "android", target_vendor = "apple")
+    ))]
+    fn advice_constant(advice: super::MemoryAdvice) -> c_int {
+        match advice {
+            super::MemoryAdvice::Normal => 0,
+            super::MemoryAdvice::Random => 1,
+            super::MemoryAdvice::Sequential => 2,
+            super::MemoryAdvice::WillNeed => 3,
+        }
+    }
+
+    #[cfg(all(
+        feature = "memory-advice",
+        any(target_os = "linux", target_os = "android")
+    ))]
+    const SC_PAGESIZE: c_int = 30;
+
+    #[cfg(all(feature = "memory-advice", target_vendor = "apple"))]
+    const SC_PAGESIZE: c_int = 29;
+
+    #[cfg(feature = "memory-advice")]
+    pub(super) fn advise(region: MemoryRegion, advice: super::MemoryAdvice) -> std::io::Result<()> {
+        if region.len == 0 {
+            return Ok(());
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "android", target_vendor = "apple")))]
+        {
+            let _ = advice;
+
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "memory advice is only supported on Linux, Android and Apple systems",
+            ))
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "android", target_vendor = "apple"))]
+        {
+            // SAFETY: sysconf has no preconditions
+            let page_size = unsafe { sysconf(SC_PAGESIZE) };
+
+            if page_size <= 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            // madvise needs a page aligned address. the pages at the borders are shared with other allocations,
+            // which is fine, because the advice does not change the memory.
+            let page_size = page_size as usize;
+            let start = region.ptr as usize / page_size * page_size;
+            let end = (region.ptr as usize + region.len).next_multiple_of(page_size);
+
+            // SAFETY: the pages contain a valid allocation and the advice only affects paging, not the content
+            check(unsafe { madvise(start as *mut c_void, end - start, advice_constant(advice)) })
+        }
+    }
+
+    pub(super) fn lock(region: MemoryRegion) -> std::io::Result<()> {
+        if region.len == 0 {
+            return Ok(());
+        }
+
+        // SAFETY: the region is a valid allocation. mlock does not modify the memory.
+        check(unsafe { mlock(region.ptr.cast(), region.len) })
+    }
+
+    pub(super) fn unlock(region: MemoryRegion) -> std::io::Result<()> {
+        if region.len == 0 {
+            return Ok(());
+        }
+
+        // SAFETY: the region is a valid allocation. munlock does not modify the memory.
+        check(unsafe { munlock(region.ptr.cast(), region.len) })
+    }
+
+    fn check(return_value: c_int) -> std::io::Result<()> {
+        if return_value == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use super::MemoryRegion;
+
+    pub(super) fn lock(_region: MemoryRegion) -> std::io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub(super) fn unlock(_region: MemoryRegion) -> std::io::Result<()> {
+        Err(unsupported())
+    }
+
+    #[cfg(feature = "memory-advice")]
+    pub(super) fn advise(
+        _region: MemoryRegion,
+        _advice: super::MemoryAdvice,
+    ) -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "memory advice is only supported on unix systems",
+        ))
+    }
+
+    fn unsupported() -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "locking memory is only supported on unix systems",
+        )
+    }
+}
@@ -0,0 +1,256 @@
+use std::ops::Range;
+
+use crate::{
+    Cursor, FmdIndex, HalfOpenInterval, IndexStorage, StrandedHit,
+    text_with_rank_support::TextWithRankSupport,
+};
+
+/// A maximal exact match (MEM) between a query and the texts of an [`FmdIndex`], found by [`FmdIndex::mems`].
+///
+/// A MEM is a part of the query together with the occurrences where the match can't be extended in either direction.
+/// Unlike [`Smem`](crate::Smem)s, the same part of the query can be contained in a longer match at other occurrences.
+/// Occurrences on both strands are considered.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Mem {
+    /// The matching part of the query.
+    pub query_range: Range<usize>,
+    count: usize,
+    // the rows of the suffix array interval of the match that are right-maximal
+    sa_ranges: [Range<usize>; 2],
+    // rows with this symbol in front of the match are not left-maximal
+    previous_symbol: Option<u8>,
+}
+
+impl Mem {
+    /// The number of occurrences on both strands of the indexed texts where the match is maximal.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+pub(crate) fn mems<'a, I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &'a FmdIndex<I, R>,
+    query: &[u8],
+    min_len: usize,
+    max_occ: usize,
+) -> impl Iterator<Item = Mem> + use<'a, I, R> {
+    let alphabet = index.alphabet();
+
+    // symbols that are not searchable can't be part of a match
+    let dense_query: Vec<_> = query
+        .iter()
+        .map(|&symbol| {
+            alphabet
+                .try_io_to_dense_representation(symbol)
+                .filter(|&dense_symbol| alphabet.is_searchable(dense_symbol))
+        })
+        .collect();
+
+    (1..=dense_query.len())
+        .flat_map(move |end| mems_ending_at(index, &dense_query, end, min_len, max_occ))
+}
+
+// Searches all suffixes of query[..end] by backward search. The occurrences of a suffix where the match can't be
+// extended to the right are those that are not followed by query[end], which are found by also searching
+// the suffix extended by query[end]. Of these, the left-maximal ones are counted using the BWT.
+fn mems_ending_at<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmdIndex<I, R>,
+    dense_query: &[Option<u8>],
+    end: usize,
+    min_len: usize,
+    max_occ: usize,
+) -> Vec<Mem> {
+    let fm_index = index.index();
+    let mut cursor = fm_index.cursor_empty();
+    let mut extended_cursor = dense_query.get(end).copied().flatten().map(|next_symbol| {
+        let mut extended_cursor = fm_index.cursor_empty();
+        extended_cursor.extend_front_without_alphabet_translation(next_symbol);
+        extended_cursor
+    });
+
+    let mut mems = Vec::new();
+
+    for start in (0..end).rev() {
+        let Some(symbol) = dense_query[start] else {
+            break;
+        };
+
+        cursor.extend_front_without_alphabet_translation(symbol);
+
+        if let Some(extended_cursor) = &mut extended_cursor {
+            extended_cursor.extend_front_without_alphabet_translation(symbol);
+        }
+
+        let sa_ranges = right_maximal_ranges(cursor, extended_cursor);
+        let num_right_maximal = sa_ranges[0].len() + sa_ranges[1].len();
+
+        // all occurrences of longer suffixes are also occurrences of this suffix
+        if num_right_maximal == 0 {
+            break;
+        }
+
+        if end - start < min_len {
+            continue;
+        }
+
+        let previous_symbol = start.checked_sub(1).and_then(|i| dense_query[i]);
+        let num_not_left_maximal = previous_symbol.map_or(0, |previous_symbol| {
+            sa_ranges
+                .iter()
+                .map(|range| {
+                    fm_index
+                        .text_with_rank_support
+                        .rank(previous_symbol, range.end)
+                        - fm_index
+                            .text_with_rank_support
+                            .rank(previous_symbol, range.start)
+                })
+                .sum()
+        });
+
+        let count = num_right_maximal - num_not_left_maximal;
+
+        if count > 0 && count <= max_occ {
+            mems.push(Mem {
+                query_range: start..end,
+                count,
+                sa_ranges,
+                previous_symbol,
+            });
+        }
+    }
+
+    mems
+}
+
+fn right_maximal_ranges<I, R>(
+    cursor: Cursor<I, R>,
+    extended_cursor: Option<Cursor<I, R>>,
+) -> [Range<usize>; 2] {
+    let HalfOpenInterval { start, end } = cursor.interval;
+
+    match extended_cursor.map(|extended_cursor| extended_cursor.interval) {
+        Some(extended) if extended.start < extended.end => {
+            [start..extended.start, extended.end..end]
+        }
+        _ => [start..end, end..end],
+    }
+}
+
+pub(crate) fn locate_mem<'a, I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &'a FmdIndex<I, R>,
+    mem: &Mem,
+) -> impl Iterator<Item = StrandedHit> + use<'a, I, R> {
+    let fm_index = index.index();
+    let previous_symbol = mem.previous_symbol;
+    let query_len = mem.query_range.len();
+
+    mem.sa_ranges
+        .clone()
+        .into_iter()
+        .flatten()
+        .filter(move |&row| {
+            previous_symbol
+                .is_none_or(|symbol| fm_index.text_with_rank_support.symbol_at(row) != symbol)
+        })
+        .flat_map(move |row| {
+            fm_index.locate_interval(HalfOpenInterval {
+                start: row,
+                end: row + 1,
+            })
+        })
+        .map(move |hit| index.stranded_hit(hit, query_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{FmIndexConfig, Strand, alphabet};
+    use proptest::prelude::*;
+
+    fn reverse_complement(text: &[u8]) -> Vec<u8> {
+        text.iter()
+            .rev()
+            .map(|&symbol| match symbol {
+                b'A' => b'T',
+                b'C' => b'G',
+                b'G' => b'C',
+                b'T' => b'A',
+                _ => b'N',
+            })
+            .collect()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn mems_agree_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..40), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..30),
+            min_len in 1usize..4,
+            max_occ in 1usize..6,
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .construct_fmd_index(&texts, alphabet::ascii_dna_with_n());
+
+            let matches = |a: Option<&u8>, b: Option<&u8>| {
+                matches!((a, b), (Some(a), Some(b)) if a == b && *a != b'N')
+            };
+
+            let mut expected: HashMap<_, Vec<_>> = HashMap::new();
+
+            for (text_id, text) in texts.iter().enumerate() {
+                for (strand, strand_text) in [
+                    (Strand::Forward, text.clone()),
+                    (Strand::Reverse, reverse_complement(text)),
+                ] {
+                    for start in 0..query.len() {
+                        for end in start + min_len..=query.len() {
+                            let len = end - start;
+                            if query[start..end].contains(&b'N') {
+                                continue;
+                            }
+
+                            for position in 0..(strand_text.len() + 1).saturating_sub(len) {
+                                let is_maximal = strand_text[position..position + len] == query[start..end]
+                                    && (start == 0 || position == 0
+                                        || !matches(query.get(start - 1), strand_text.get(position - 1)))
+                                    && !matches(query.get(end), strand_text.get(position + len));
+
+                                if is_maximal {
+                                    let position = match strand {
+                                        Strand::Forward => position,
+                                        Strand::Reverse => text.len() - position - len,
+                                    };
+
+                                    expected
+                                        .entry(start..end)
+                                        .or_default()
+                                        .push(StrandedHit { text_id, position, strand });
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            expected.retain(|_, hits| hits.len() <= max_occ);
+
+            let mems: Vec<_> = index.mems(&query, min_len, max_occ).collect();
+            prop_assert_eq!(mems.len(), expected.len());
+
+            for mem in &mems {
+                let mut hits: Vec<_> = index.locate_mem(mem).collect();
+                hits.sort();
+                let expected_hits = expected.get_mut(&mem.query_range).unwrap();
+                expected_hits.sort();
+
+                prop_assert_eq!(mem.count(), hits.len());
+                prop_assert_eq!(&hits, expected_hits);
+            }
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::{fmt, ops::Range};
+
+use crate::{
+    Alphabet, FmIndex, IndexStorage,
+    pattern::{self, PatternElement, PatternTooAmbiguous},
+    text_with_rank_support::TextWithRankSupport,
+};
+
+/// An occurrence of a motif, found by [`FmIndex::locate_prosite_motif`].
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MotifHit {
+    pub text_id: usize,
+    /// The matched positions in the text. Matches of motifs with variable repetitions can have different lengths.
+    pub range: Range<usize>,
+}
+
+impl fmt::Display for MotifHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "text {}, positions {}..{}",
+            self.text_id, self.range.start, self.range.end
+        )
+    }
+}
+
+struct Motif {
+    elements: Vec<PatternElement>,
+    anchored_at_start: bool,
+    anchored_at_end: bool,
+}
+
+fn parse_prosite_motif(motif: &[u8], alphabet: &Alphabet) -> Motif {
+    let mut motif = motif.trim_ascii();
+    motif = motif.strip_suffix(b".").unwrap_or(motif);
+
+    let mut elements = Vec::new();
+    let mut anchored_at_start = false;
+    let mut anchored_at_end = false;
+
+    let num_elements = motif.split(|&symbol| symbol == b'-').count();
+
+    for (i, mut element) in motif.split(|&symbol| symbol == b'-').enumerate() {
+        if i == 0
+            && let Some(rest) = element.strip_prefix(b"<")
+        {
+            anchored_at_start = true;
+            element = rest;
+        }
+
+        if i == num_elements - 1
+            && let Some(rest) = element.strip_suffix(b">")
+        {
+            anchored_at_end = true;
+            element = rest;
+        }
+
+        let (symbols, repetitions) = match element.iter().position(|&symbol| symbol == b'(') {
+            Some(paren_idx) => (&element[..paren_idx], &element[paren_idx..]),
+            None => (element, [].as_slice()),
+        };
+
+        let symbols: Vec<_> = match symbols {
+            [b'x' | b'X'] => pattern::searchable_symbols(alphabet).collect(),
+            [b'[', class @ .., b']'] if !class.is_empty() => class
+                .iter()
+                .map(|&symbol| pattern::dense_symbol(symbol, alphabet))
+                .collect(),
+            [b'{', excluded @ .., b'}'] => {
+                let excluded: Vec<_> = excluded
+                    .iter()
+                    .map(|&symbol| pattern::dense_symbol(symbol, alphabet))
+                    .collect();
+
+                pattern::searchable_symbols(alphabet)
+                    .filter(|symbol| !excluded.contains(symbol))
+                    .collect()
+            }
+            [symbol] => vec![pattern::dense_symbol(*symbol, alphabet)],
+            _ => panic!(
+                "invalid element '{}' in PROSITE motif",
+                String::from_utf8_lossy(element)
+            ),
+        };
+
+        let mut element = PatternElement::new(symbols, 1);
+
+        if !repetitions.is_empty() {
+            (element.min_repetitions, element.max_repetitions) = parse_repetitions(repetitions);
+        }
+
+        elements.push(element);
+    }
+
+    Motif {
+        elements,
+        anchored_at_start,
+        anchored_at_end,
+    }
+}
+
+// parses "(n)" or "(n,m)"
+fn parse_repetitions(repetitions: &[u8]) -> (usize, usize) {
+    let parse = |number: &[u8]| -> usize {
+        std::str::from_utf8(number)
+            .ok()
+            .and_then(|number| number.trim().parse().ok())
+            .expect("repetitions in PROSITE motif must be non-negative integers")
+    };
+
+    let inner = repetitions
+        .strip_prefix(b"(")
+        .and_then(|inner| inner.strip_suffix(b")"))
+        .expect("repetitions in PROSITE motif must be enclosed in parentheses");
+
+    let (min_repetitions, max_repetitions) = match inner.iter().position(|&symbol| symbol == b',') {
+        Some(comma_idx) => (parse(&inner[..comma_idx]), parse(&inner[comma_idx + 1..])),
+        None => (parse(inner), parse(inner)),
+    };
+
+    assert!(
+        min_repetitions <= max_repetitions,
+        "minimum number of repetitions in PROSITE motif must not be larger than the maximum"
+    );
+
+    (min_repetitions, max_repetitions)
+}
+
+pub(crate) fn locate_prosite_motif<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    motif: &[u8],
+    max_branches: usize,
+) -> Result<Vec<MotifHit>, PatternTooAmbiguous> {
+    assert!(max_branches > 0, "max_branches must be greater than 0");
+
+    let motif = parse_prosite_motif(motif, index.alphabet());
+
+    let mut hits: Vec<_> = pattern::search_elements(index, &motif.elements, max_branches)?
+        .into_iter()
+        .flat_map(|(len, interval)| {
+            index.locate_interval(interval).map(move |hit| MotifHit {
+                text_id: hit.text_id,
+                range: hit.position..hit.position + len,
+            })
+        })
+        .filter(|hit| !hit.range.is_empty())
+        .filter(|hit| !motif.anchored_at_start || hit.range.start == 0)
+        .filter(|hit| !motif.anchored_at_end || hit.range.end == index.text_len(hit.text_id))
+        .collect();
+
+    hits.sort_unstable_by_key(sort_key);
+
+    Ok(hits)
+}
+
+fn sort_key(hit: &MotifHit) -> (usize, usize, usize) {
+    (hit.text_id, hit.range.start, hit.range.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    #[test]
+    fn parse_prosite_syntax() {
+        let alphabet = alphabet::ascii_amino_acid();
+        let dense = |symbols: &[u8]| -> Vec<u8> {
+            let mut dense: Vec<_> = symbols
+                .iter()
+                .map(|&symbol| alphabet.io_to_dense_representation(symbol))
+                .collect();
+            dense.sort_unstable();
+            dense
+        };
+
+        let motif = parse_prosite_motif(b"<C-x(2,4)-[DE]-{PG}-H(3)>.", &alphabet);
+
+        assert!(motif.anchored_at_start);
+        assert!(motif.anchored_at_end);
+        assert_eq!(motif.elements.len(), 5);
+
+        assert_eq!(motif.elements[0].symbols, dense(b"C"));
+        assert_eq!(motif.elements[1].symbols.len(), 22);
+        assert_eq!(
+            (
+                motif.elements[1].min_repetitions,
+                motif.elements[1].max_repetitions
+            ),
+            (2, 4)
+        );
+        assert_eq!(motif.elements[2].symbols, dense(b"DE"));
+        assert_eq!(motif.elements[3].symbols.len(), 20);
+        assert!(!motif.elements[3].symbols.contains(&dense(b"P")[0]));
+        assert_eq!(
+            (
+                motif.elements[4].min_repetitions,
+                motif.elements[4].max_repetitions
+            ),
+            (3, 3)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid element")]
+    fn invalid_element() {
+        parse_prosite_motif(b"C-[DE", &alphabet::ascii_amino_acid());
+    }
+
+    // naive matching of the elements starting at the given position, returns all possible match ends
+    fn match_ends(
+        text: &[u8],
+        elements: &[PatternElement],
+        start: usize,
+        alphabet: &Alphabet,
+    ) -> Vec<usize> {
+        let Some((element, rest)) = elements.split_first() else {
+            return vec![start];
+        };
+
+        let mut ends = Vec::new();
+
+        for repetitions in element.min_repetitions..=element.max_repetitions {
+            let Some(window) = text.get(start..start + repetitions) else {
+                break;
+            };
+
+            if window.iter().all(|&symbol| {
+                element
+                    .symbols
+                    .contains(&alphabet.io_to_dense_representation(symbol))
+            }) {
+                ends.extend(match_ends(text, rest, start + repetitions, alphabet));
+            }
+        }
+
+        ends
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn motif_search_agrees_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACDEGHK".as_slice()), 1..60), 1..4),
+            elements in prop::collection::vec(
+                prop::sample::select(["A", "C", "x", "[AC]", "{DE}", "x(0,2)", "[DEG](1,3)", "H(2)"].as_slice()),
+                1..5
+            ),
+            anchored_at_start in any::<bool>(),
+            anchored_at_end in any::<bool>(),
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_index(&texts, alphabet::ascii_amino_acid());
+
+            let mut motif = elements.join("-");
+            if anchored_at_start {
+                motif.insert(0, '<');
+            }
+            if anchored_at_end {
+                motif.push('>');
+            }
+
+            let parsed = parse_prosite_motif(motif.as_bytes(), index.alphabet());
+
+            let mut expected = Vec::new();
+
+            for (text_id, text) in texts.iter().enumerate() {
+                for start in 0..=text.len() {
+                    if anchored_at_start && start > 0 {
+                        break;
+                    }
+
+                    let mut ends = match_ends(text, &parsed.elements, start, index.alphabet());
+                    ends.sort_unstable();
+                    ends.dedup();
+
+                    for end in ends {
+                        if (!anchored_at_end || end == text.len()) && end > start {
+                            expected.push(MotifHit { text_id, range: start..end });
+                        }
+                    }
+                }
+            }
+
+            expected.sort_unstable_by_key(sort_key);
+
+            prop_assert_eq!(index.locate_prosite_motif(motif.as_bytes()), expected);
+        }
+    }
+}
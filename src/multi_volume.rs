@@ -0,0 +1,238 @@
+use num_traits::NumCast;
+
+use crate::{
+    Alphabet, FmIndex, FmIndexConfig, Hit, IndexStorage,
+    text_with_rank_support::{Block64, CondensedTextWithRankSupport, TextWithRankSupport},
+};
+
+/// An FM-Index of texts that are split into several volumes, each of which is an independent [`FmIndex`].
+///
+/// It is constructed using [`FmIndexConfig::construct_multi_volume_index`]. This is useful when the total length of
+/// the texts exceeds the maximum value of the index storage type, for example when a large collection of genomes
+/// should be indexed using `i32` instead of `i64` to save memory. The texts are numbered globally, in the order
+/// in which they were given to the construction, and all hits are reported with these global text ids.
+///
+/// An occurrence of a query can not span multiple texts, so the results are the same as for a single index
+/// of all texts. Every query is searched in every volume.
+///
+/// ```
+/// use genedex::{FmIndexConfig, Hit, alphabet};
+///
+/// let index = FmIndexConfig::<i32>::new()
+///     .max_volume_len(10)
+///     .construct_multi_volume_index([b"ACGTACGT".as_slice(), b"TACGA", b"GGTACC"], alphabet::ascii_dna());
+///
+/// assert_eq!(index.num_volumes(), 3);
+/// assert_eq!(index.count(b"TAC"), 3);
+///
+/// let mut hits: Vec<_> = index.locate(b"TAC").collect();
+/// hits.sort();
+///
+/// assert_eq!(hits[2], Hit { text_id: 2, position: 2 });
+/// ```
+#[derive(Clone)]
+pub struct MultiVolumeFmIndex<I, R = CondensedTextWithRankSupport<I, Block64>> {
+    volumes: Vec<FmIndex<I, R>>,
+    // the global id of the first text of every volume, and the total number of texts at the end
+    first_text_ids: Vec<usize>,
+}
+
+impl<I: IndexStorage, R: TextWithRankSupport<I>> MultiVolumeFmIndex<I, R> {
+    pub(crate) fn new<T: AsRef<[u8]>>(
+        texts: impl IntoIterator<Item = T>,
+        alphabet: Alphabet,
+        config: FmIndexConfig<I, R>,
+    ) -> Self {
+        let max_volume_len = config
+            .max_volume_len
+            .min(<usize as NumCast>::from(I::max_value()).unwrap_or(usize::MAX));
+
+        let mut index = Self {
+            volumes: Vec::new(),
+            first_text_ids: vec![0],
+        };
+
+        let mut volume_texts = Vec::new();
+        let mut volume_len = 0;
+
+        for text in texts {
+            // every text is followed by a sentinel
+            let text_len = text.as_ref().len() + 1;
+
+            assert!(
+                text_len <= max_volume_len,
+                "a single text (including its sentinel) is longer than the maximum length of a volume"
+            );
+
+            if volume_len + text_len > max_volume_len {
+                index.push_volume(volume_texts.drain(..), &alphabet, &config);
+                volume_len = 0;
+            }
+
+            volume_texts.push(text);
+            volume_len += text_len;
+        }
+
+        // like for a single index, an empty collection of texts leads to a panic here
+        if !volume_texts.is_empty() || index.volumes.is_empty() {
+            index.push_volume(volume_texts.into_iter(), &alphabet, &config);
+        }
+
+        index
+    }
+
+    fn push_volume<T: AsRef<[u8]>>(
+        &mut self,
+        texts: impl ExactSizeIterator<Item = T>,
+        alphabet: &Alphabet,
+        config: &FmIndexConfig<I, R>,
+    ) {
+        let num_texts = self.num_texts() + texts.len();
+
+        self.volumes.push(FmIndex::new(
+            texts,
+            alphabet.clone(),
+            config.with_rank_support(),
+        ));
+        self.first_text_ids.push(num_texts);
+    }
+
+    pub fn num_volumes(&self) -> usize {
+        self.volumes.len()
+    }
+
+    /// Panics if `volume_id` is not a valid volume id of this index.
+    pub fn volume(&self, volume_id: usize) -> &FmIndex<I, R> {
+        &self.volumes[volume_id]
+    }
+
+    /// Returns the global id of the first text of the volume. The text with id `i` of the volume has the
+    /// global id `first_text_id(volume_id) + i`.
+    ///
+    /// Panics if `volume_id` is not a valid volume id of this index.
+    pub fn first_text_id(&self, volume_id: usize) -> usize {
+        assert!(volume_id < self.num_volumes(), "volume id out of bounds");
+
+        self.first_text_ids[volume_id]
+    }
+
+    /// Returns the id of the volume that contains the text with the global id `text_id`, and the id
+    /// of the text inside of that volume.
+    ///
+    /// Panics if `text_id` is not a valid text id of this index.
+    pub fn volume_of_text(&self, text_id: usize) -> (usize, usize) {
+        assert!(text_id < self.num_texts(), "text id out of bounds");
+
+        let volume_id = self
+            .first_text_ids
+            .partition_point(|&first| first <= text_id)
+            - 1;
+
+        (volume_id, text_id - self.first_text_ids[volume_id])
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        self.volumes[0].alphabet()
+    }
+
+    pub fn num_texts(&self) -> usize {
+        *self.first_text_ids.last().unwrap()
+    }
+
+    /// Panics if `text_id` is not a valid text id of this index.
+    pub fn text_len(&self, text_id: usize) -> usize {
+        let (volume_id, volume_text_id) = self.volume_of_text(text_id);
+
+        self.volumes[volume_id].text_len(volume_text_id)
+    }
+
+    /// The length of all the texts of all volumes. The value includes a sentinel symbol for each text.
+    pub fn total_text_len(&self) -> usize {
+        self.volumes.iter().map(FmIndex::total_text_len).sum()
+    }
+
+    /// Returns the number of occurrences of `query` in all volumes.
+    pub fn count(&self, query: &[u8]) -> usize {
+        self.volumes.iter().map(|volume| volume.count(query)).sum()
+    }
+
+    /// Returns the occurrences of `query` in all volumes, with global text ids.
+    ///
+    /// The hits are reported volume by volume, in the order of [`FmIndex::locate`] inside of each volume.
+    pub fn locate(&self, query: &[u8]) -> impl Iterator<Item = Hit> {
+        self.volumes
+            .iter()
+            .zip(&self.first_text_ids)
+            .flat_map(move |(volume, &first_text_id)| {
+                volume.locate(query).map(move |hit| Hit {
+                    text_id: first_text_id + hit.text_id,
+                    position: hit.position,
+                })
+            })
+    }
+
+    /// Reconstructs the text with the global id `text_id`, see [`FmIndex::extract_text`].
+    ///
+    /// Panics if `text_id` is not a valid text id of this index.
+    pub fn extract_text(&self, text_id: usize) -> Vec<u8> {
+        let (volume_id, volume_text_id) = self.volume_of_text(text_id);
+
+        self.volumes[volume_id].extract_text(volume_text_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet;
+    use proptest::prelude::*;
+
+    #[test]
+    #[should_panic(expected = "longer than the maximum length of a volume")]
+    fn text_longer_than_volume() {
+        FmIndexConfig::<i32>::new()
+            .max_volume_len(4)
+            .construct_multi_volume_index([b"ACG".as_slice(), b"ACGT"], alphabet::ascii_dna());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn volumes_agree_with_single_index(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 0..20), 1..8),
+            queries in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 0..4), 1..20),
+            max_volume_len in 21usize..60,
+        ) {
+            let single = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_index(&texts, alphabet::ascii_dna());
+            let multi = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .max_volume_len(max_volume_len)
+                .construct_multi_volume_index(&texts, alphabet::ascii_dna());
+
+            prop_assert_eq!(multi.num_texts(), texts.len());
+            prop_assert_eq!(multi.total_text_len(), single.total_text_len());
+
+            for volume_id in 0..multi.num_volumes() {
+                prop_assert!(multi.volume(volume_id).total_text_len() <= max_volume_len);
+            }
+
+            for (text_id, text) in texts.iter().enumerate() {
+                prop_assert_eq!(multi.text_len(text_id), text.len());
+                prop_assert_eq!(&multi.extract_text(text_id), text);
+            }
+
+            for query in &queries {
+                let mut expected: Vec<_> = single.locate(query).collect();
+                let mut hits: Vec<_> = multi.locate(query).collect();
+                expected.sort();
+                hits.sort();
+
+                prop_assert_eq!(multi.count(query), single.count(query));
+                prop_assert_eq!(hits, expected);
+            }
+        }
+    }
+}
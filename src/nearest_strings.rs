@@ -0,0 +1,295 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+use crate::{
+    FmIndex, HalfOpenInterval, IndexStorage, approximate::Search,
+    text_with_rank_support::TextWithRankSupport,
+};
+
+/// A string from the indexed texts that is close to a query, found by [`FmIndex::nearest_strings`] and
+/// [`FmIndex::nearest_texts`].
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NearestString {
+    /// The string in IO representation. Every symbol is the first IO symbol of its dense symbol (see
+    /// [`Alphabet`](crate::Alphabet)).
+    pub string: Vec<u8>,
+    pub edit_distance: usize,
+    /// The number of occurrences of the string as a substring of the texts for [`FmIndex::nearest_strings`], and the
+    /// number of texts that are equal to the string for [`FmIndex::nearest_texts`].
+    pub count: usize,
+}
+
+// A string of the texts (in dense representation and reversed, because it is extended at the front) that is either
+// to be reported or to be extended. The priority is a lower bound of the edit distance of all strings
+// that can result from it.
+struct Candidate {
+    priority: usize,
+    is_expansion: bool,
+    reversed_string: Vec<u8>,
+    interval: HalfOpenInterval,
+    column: Vec<usize>,
+}
+
+impl Candidate {
+    fn key(&self) -> (usize, bool) {
+        (self.priority, self.is_expansion)
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+// Best-first search over the strings of the texts, extended at the front. The edit distance of a candidate can't
+// decrease when it is extended, so the strings are reported in the order of their edit distances and the search
+// stops as soon as enough strings were found. In whole-text mode, the search starts at the ends of the texts
+// and a string is only reported if it is preceded by a sentinel.
+pub(crate) fn nearest_strings<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+    max_edit_distance: usize,
+    max_results: usize,
+    whole_texts: bool,
+) -> Vec<NearestString> {
+    let search = Search::new(index.alphabet(), query, max_edit_distance);
+    let query_len = query.len();
+
+    let initial_interval = if whole_texts {
+        HalfOpenInterval {
+            start: 0,
+            end: index.num_texts(),
+        }
+    } else {
+        index.cursor_empty().interval()
+    };
+
+    let initial_column = search.initial_column();
+
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse(Candidate {
+        priority: *initial_column.iter().min().unwrap(),
+        is_expansion: true,
+        reversed_string: Vec::new(),
+        interval: initial_interval,
+        column: initial_column,
+    }));
+
+    let mut results: Vec<NearestString> = Vec::new();
+
+    while let Some(Reverse(candidate)) = queue.pop() {
+        // all strings with the same edit distance as the last result are collected, such that ties are broken
+        // deterministically below
+        if results.len() >= max_results
+            && results
+                .last()
+                .is_none_or(|last| candidate.priority > last.edit_distance)
+        {
+            break;
+        }
+
+        if !candidate.is_expansion {
+            results.push(NearestString {
+                string: candidate
+                    .reversed_string
+                    .iter()
+                    .rev()
+                    .map(|&symbol| index.alphabet().dense_to_io_representation(symbol))
+                    .collect(),
+                edit_distance: candidate.priority,
+                count: candidate.interval.end - candidate.interval.start,
+            });
+
+            continue;
+        }
+
+        let edit_distance = candidate.column[query_len];
+
+        if edit_distance <= max_edit_distance {
+            let reported_interval = if whole_texts {
+                HalfOpenInterval {
+                    start: index.lf_mapping_step(0, candidate.interval.start),
+                    end: index.lf_mapping_step(0, candidate.interval.end),
+                }
+            } else if candidate.reversed_string.is_empty() {
+                HalfOpenInterval { start: 0, end: 0 }
+            } else {
+                candidate.interval
+            };
+
+            if reported_interval.start < reported_interval.end {
+                queue.push(Reverse(Candidate {
+                    priority: edit_distance,
+                    is_expansion: false,
+                    reversed_string: candidate.reversed_string.clone(),
+                    interval: reported_interval,
+                    column: Vec::new(),
+                }));
+            }
+        }
+
+        for &symbol in &search.symbols {
+            let interval = index.lf_mapping_step_interval(symbol, candidate.interval);
+
+            if interval.start == interval.end {
+                continue;
+            }
+
+            let mut column = vec![0; query_len + 1];
+            search.next_column(&candidate.column, symbol, &mut column);
+            let priority = *column.iter().min().unwrap();
+
+            if priority > max_edit_distance {
+                continue;
+            }
+
+            let mut reversed_string = candidate.reversed_string.clone();
+            reversed_string.push(symbol);
+
+            queue.push(Reverse(Candidate {
+                priority,
+                is_expansion: true,
+                reversed_string,
+                interval,
+                column,
+            }));
+        }
+    }
+
+    results.sort_unstable_by(|a, b| {
+        a.edit_distance
+            .cmp(&b.edit_distance)
+            .then_with(|| a.string.cmp(&b.string))
+    });
+    results.truncate(max_results);
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+        let mut column: Vec<_> = (0..=b.len()).collect();
+
+        for (i, &a_symbol) in a.iter().enumerate() {
+            let mut next_column = vec![i + 1; b.len() + 1];
+
+            for (j, &b_symbol) in b.iter().enumerate() {
+                next_column[j + 1] = (column[j] + (a_symbol != b_symbol) as usize)
+                    .min(column[j + 1] + 1)
+                    .min(next_column[j] + 1);
+            }
+
+            column = next_column;
+        }
+
+        column[b.len()]
+    }
+
+    fn expected_results(
+        strings: HashMap<Vec<u8>, usize>,
+        query: &[u8],
+        max_edit_distance: usize,
+        max_results: usize,
+    ) -> Vec<NearestString> {
+        let mut expected: Vec<_> = strings
+            .into_iter()
+            .map(|(string, count)| NearestString {
+                edit_distance: edit_distance(query, &string),
+                string,
+                count,
+            })
+            .filter(|result| result.edit_distance <= max_edit_distance)
+            .collect();
+
+        expected.sort_unstable_by(|a, b| {
+            a.edit_distance
+                .cmp(&b.edit_distance)
+                .then_with(|| a.string.cmp(&b.string))
+        });
+        expected.truncate(max_results);
+
+        expected
+    }
+
+    #[test]
+    fn barcode_correction() {
+        let barcodes = [b"ACGTAC".as_slice(), b"TTGCAA", b"ACGTTC", b"GGATCC"];
+        let index = FmIndexConfig::<i32>::new().construct_index(barcodes, alphabet::ascii_dna());
+
+        let corrected = index.nearest_texts(b"ACGAAC", 1, 1);
+
+        assert_eq!(
+            corrected,
+            [NearestString {
+                string: b"ACGTAC".to_vec(),
+                edit_distance: 1,
+                count: 1,
+            }]
+        );
+        assert!(index.nearest_texts(b"CCCCCC", 2, 5).is_empty());
+        assert!(index.nearest_texts(b"ACGTAC", 1, 0).is_empty());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn nearest_strings_agree_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 0..20), 1..6),
+            query in prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 0..7),
+            max_edit_distance in 0usize..3,
+            max_results in 0usize..8,
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_index(&texts, alphabet::ascii_dna_with_n());
+
+            let mut substrings = HashMap::new();
+            let mut whole_texts = HashMap::new();
+
+            for text in &texts {
+                for start in 0..text.len() {
+                    for end in start + 1..=text.len() {
+                        *substrings.entry(text[start..end].to_vec()).or_insert(0) += 1;
+                    }
+                }
+
+                *whole_texts.entry(text.clone()).or_insert(0) += 1;
+            }
+
+            prop_assert_eq!(
+                index.nearest_strings(&query, max_edit_distance, max_results),
+                expected_results(substrings, &query, max_edit_distance, max_results)
+            );
+            prop_assert_eq!(
+                index.nearest_texts(&query, max_edit_distance, max_results),
+                expected_results(whole_texts, &query, max_edit_distance, max_results)
+            );
+        }
+    }
+}
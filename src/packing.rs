@@ -0,0 +1,94 @@
+use crate::construction::slice_compression::{
+    HalfBytesCompression, SliceCompression, half_byte_compress_text,
+};
+
+/// The number of bytes needed to store `num_symbols` packed symbols.
+pub fn packed_len(num_symbols: usize) -> usize {
+    num_symbols.div_ceil(2)
+}
+
+/// Packs `symbols` into a new buffer, two symbols per byte.
+///
+/// Panics if a symbol does not fit into four bits.
+pub fn pack(symbols: &[u8]) -> Vec<u8> {
+    let mut packed = symbols.to_vec();
+    let packed_len = pack_in_place(&mut packed);
+    packed.truncate(packed_len);
+
+    packed
+}
+
+/// Packs the symbols of `buffer` into the front of the same buffer and returns the length of the packed data.
+///
+/// The remaining part of the buffer contains unspecified values afterwards. This is how the FM-Index construction
+/// halves the memory usage of the text. Panics if a symbol does not fit into four bits.
+pub fn pack_in_place(buffer: &mut [u8]) -> usize {
+    assert!(
+        buffer.iter().all(|&symbol| symbol < 16),
+        "symbols must fit into four bits to be packed"
+    );
+
+    half_byte_compress_text(buffer);
+
+    if buffer.len() % 2 == 1 {
+        let last_symbol = buffer[buffer.len() - 1];
+        buffer[buffer.len() / 2] = 0;
+        HalfBytesCompression::set(buffer.len() - 1, buffer, last_symbol);
+    }
+
+    packed_len(buffer.len())
+}
+
+/// Unpacks the first `num_symbols` symbols of `packed` into a new buffer.
+pub fn unpack(packed: &[u8], num_symbols: usize) -> Vec<u8> {
+    assert!(
+        num_symbols <= packed.len() * 2,
+        "the packed buffer is too small"
+    );
+
+    iter(packed).take(num_symbols).collect()
+}
+
+/// Returns the symbol at index `idx` of the packed buffer.
+pub fn get(packed: &[u8], idx: usize) -> u8 {
+    HalfBytesCompression::get(idx, packed)
+}
+
+/// Sets the symbol at index `idx` of the packed buffer. Panics if `symbol` does not fit into four bits.
+pub fn set(packed: &mut [u8], idx: usize, symbol: u8) {
+    assert!(symbol < 16, "symbols must fit into four bits to be packed");
+
+    HalfBytesCompression::set(idx, packed, symbol);
+}
+
+/// Iterates over all symbols of the packed buffer. If an odd number of symbols was packed,
+/// the last value of the iterator does not belong to the symbols.
+pub fn iter(packed: &[u8]) -> impl Iterator<Item = u8> {
+    HalfBytesCompression::iter(packed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn pack_unpack_roundtrip(symbols in prop::collection::vec(0u8..16, 0..100)) {
+            let packed = pack(&symbols);
+
+            prop_assert_eq!(packed.len(), packed_len(symbols.len()));
+            prop_assert_eq!(&unpack(&packed, symbols.len()), &symbols);
+
+            let mut set_packed = vec![0; packed.len()];
+
+            for (idx, &symbol) in symbols.iter().enumerate() {
+                prop_assert_eq!(get(&packed, idx), symbol);
+                set(&mut set_packed, idx, symbol);
+            }
+
+            prop_assert_eq!(set_packed, packed);
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use crate::{FmIndex, Hit, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+/// Two occurrences on the same text, found by [`FmIndex::locate_pairs`] or [`DistanceJoin::join`].
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HitPair {
+    /// The occurrence of the first query.
+    pub first: Hit,
+    /// The occurrence of the second query.
+    pub second: Hit,
+}
+
+impl HitPair {
+    /// The distance between the starts of both hits. For pairs of unordered joins, the second hit might start
+    /// before the first one.
+    pub fn distance(&self) -> usize {
+        self.second.position.abs_diff(self.first.position)
+    }
+}
+
+/// A join of two sets of hits on their text id, with a predicate on the distance between the hits.
+///
+/// Many co-occurrence analyses reduce to this join, for example finding motifs near promoters, primer pairs or
+/// the parts of split reads. The distance is measured between the starts of the hits (see [`HitPair::distance`]).
+/// By default, the join is unordered, so the second hit may start before or after the first hit. Using
+/// [`Self::ordered`], the second hit has to start at or after the first hit.
+///
+/// The hits are sorted and the pairs are found by merging the sorted hits per text, without computing the full
+/// cross product. The running time is in O(n log n + k), where `n` is the number of hits and `k` is the number
+/// of returned pairs.
+///
+/// ```
+/// use genedex::{DistanceJoin, FmIndexConfig, alphabet};
+///
+/// let index = FmIndexConfig::<i32>::new()
+///     .construct_index([b"TATAAAGGCCACGTTATA".as_slice(), b"CACGTTTT"], alphabet::ascii_dna());
+///
+/// let pairs = DistanceJoin::within(5).join(index.locate(b"TATA"), index.locate(b"CACG"));
+///
+/// // the second TATA box is within 5 bases of the CACG motif, but before it
+/// assert_eq!(pairs.len(), 1);
+/// assert_eq!(pairs[0].first.position, 14);
+/// assert_eq!(pairs[0].second.position, 9);
+///
+/// assert!(DistanceJoin::within(5).ordered().join(index.locate(b"TATA"), index.locate(b"CACG")).is_empty());
+/// assert_eq!(DistanceJoin::beyond(5).join(index.locate(b"TATA"), index.locate(b"CACG")).len(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DistanceJoin {
+    min_distance: usize,
+    max_distance: usize,
+    ordered: bool,
+}
+
+impl DistanceJoin {
+    /// Joins hits whose distance is between `min_distance` and `max_distance` (both inclusive).
+    /// Panics if `min_distance` is larger than `max_distance`.
+    pub fn between(min_distance: usize, max_distance: usize) -> Self {
+        assert!(
+            min_distance <= max_distance,
+            "the minimum distance must not be larger than the maximum distance"
+        );
+
+        Self {
+            min_distance,
+            max_distance,
+            ordered: false,
+        }
+    }
+
+    /// Joins hits whose distance is at most `max_distance`.
+    pub fn within(max_distance: usize) -> Self {
+        Self::between(0, max_distance)
+    }
+
+    /// Joins hits whose distance is larger than `distance`.
+    pub fn beyond(distance: usize) -> Self {
+        Self::between(distance.saturating_add(1), usize::MAX)
+    }
+
+    /// Only joins hits where the second hit starts at or after the first hit.
+    pub fn ordered(self) -> Self {
+        Self {
+            ordered: true,
+            ..self
+        }
+    }
+
+    /// Returns all pairs of a hit from `first_hits` and a hit from `second_hits` on the same text that fulfill
+    /// the distance predicate.
+    ///
+    /// The pairs are returned sorted by the first and then the second hit.
+    pub fn join(
+        &self,
+        first_hits: impl IntoIterator<Item = Hit>,
+        second_hits: impl IntoIterator<Item = Hit>,
+    ) -> Vec<HitPair> {
+        let mut first_hits: Vec<_> = first_hits.into_iter().collect();
+        let mut second_hits: Vec<_> = second_hits.into_iter().collect();
+
+        first_hits.sort_unstable();
+        second_hits.sort_unstable();
+
+        // the second hits in range of a first hit form up to two windows, one before and one after the first hit.
+        // since the first hits are sorted, the starts of the windows only move forward
+        let mut before_window = Window::default();
+        let mut after_window = Window::default();
+
+        let mut pairs = Vec::new();
+
+        for first in first_hits {
+            let position = first.position;
+
+            if !self.ordered {
+                // the distance 0 is part of the window after the first hit
+                if let Some(end) = position.checked_sub(self.min_distance.max(1)) {
+                    before_window.extend_pairs(
+                        &mut pairs,
+                        &second_hits,
+                        first,
+                        position.saturating_sub(self.max_distance),
+                        end,
+                    );
+                }
+            }
+
+            after_window.extend_pairs(
+                &mut pairs,
+                &second_hits,
+                first,
+                position.saturating_add(self.min_distance),
+                position.saturating_add(self.max_distance),
+            );
+        }
+
+        pairs
+    }
+}
+
+#[derive(Default)]
+struct Window {
+    start: usize,
+}
+
+impl Window {
+    // adds the pairs of the first hit with all second hits on its text with a position in start..=end
+    fn extend_pairs(
+        &mut self,
+        pairs: &mut Vec<HitPair>,
+        second_hits: &[Hit],
+        first: Hit,
+        start: usize,
+        end: usize,
+    ) {
+        let min_second = Hit {
+            text_id: first.text_id,
+            position: start,
+        };
+
+        while self.start < second_hits.len() && second_hits[self.start] < min_second {
+            self.start += 1;
+        }
+
+        pairs.extend(
+            second_hits[self.start..]
+                .iter()
+                .take_while(|second| second.text_id == first.text_id && second.position <= end)
+                .map(|&second| HitPair { first, second }),
+        );
+    }
+}
+
+pub(crate) fn locate_pairs<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    first_query: &[u8],
+    second_query: &[u8],
+    min_distance: usize,
+    max_distance: usize,
+) -> Vec<HitPair> {
+    DistanceJoin::between(min_distance, max_distance)
+        .ordered()
+        .join(index.locate(first_query), index.locate(second_query))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn pairs_agree_with_cross_product() {
+        let texts = [
+            b"ACGTTGCAACGTACGTTTGCAACGT".as_slice(),
+            b"TGCAACGTACGT",
+            b"AAAA",
+        ];
+
+        let index = FmIndexConfig::<i32>::new().construct_index(texts, alphabet::ascii_dna());
+
+        for (first_query, second_query) in [
+            (b"ACG".as_slice(), b"TGCA".as_slice()),
+            (b"ACG", b"ACG"),
+            (b"A", b"C"),
+        ] {
+            for (min_distance, max_distance) in [(0, 0), (0, 5), (3, 10), (5, 100)] {
+                let pairs =
+                    index.locate_pairs(first_query, second_query, min_distance, max_distance);
+
+                let mut expected = Vec::new();
+
+                for first in index.locate(first_query) {
+                    for second in index.locate(second_query) {
+                        if first.text_id == second.text_id
+                            && second.position >= first.position + min_distance
+                            && second.position <= first.position + max_distance
+                        {
+                            expected.push(HitPair { first, second });
+                        }
+                    }
+                }
+
+                expected.sort();
+
+                assert_eq!(pairs, expected);
+                assert!(
+                    pairs
+                        .iter()
+                        .all(|pair| (min_distance..=max_distance).contains(&pair.distance()))
+                );
+            }
+        }
+    }
+
+    // distinct hits, like the results of a search
+    fn hits() -> impl Strategy<Value = Vec<Hit>> {
+        prop::collection::btree_set((0usize..3, 0usize..40), 0..30).prop_map(|hits| {
+            hits.into_iter()
+                .map(|(text_id, position)| Hit { text_id, position })
+                .collect()
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn join_agrees_with_cross_product(
+            first_hits in hits(),
+            second_hits in hits(),
+            min_distance in 0usize..10,
+            distance_range in 0usize..20,
+            ordered in any::<bool>(),
+        ) {
+            let mut join = DistanceJoin::between(min_distance, min_distance + distance_range);
+
+            if ordered {
+                join = join.ordered();
+            }
+
+            let pairs = join.join(first_hits.iter().copied(), second_hits.iter().copied());
+
+            let mut expected = Vec::new();
+
+            for &first in &first_hits {
+                for &second in &second_hits {
+                    let pair = HitPair { first, second };
+
+                    if first.text_id == second.text_id
+                        && (!ordered || second.position >= first.position)
+                        && (min_distance..=min_distance + distance_range).contains(&pair.distance())
+                    {
+                        expected.push(pair);
+                    }
+                }
+            }
+
+            expected.sort();
+
+            prop_assert_eq!(pairs, expected);
+        }
+    }
+
+    #[test]
+    fn within_and_beyond_are_complementary() {
+        let first_hits = [
+            Hit {
+                text_id: 0,
+                position: 0,
+            },
+            Hit {
+                text_id: 0,
+                position: 20,
+            },
+        ];
+        let second_hits = [
+            Hit {
+                text_id: 0,
+                position: 5,
+            },
+            Hit {
+                text_id: 0,
+                position: 6,
+            },
+            Hit {
+                text_id: 1,
+                position: 6,
+            },
+        ];
+
+        let within = DistanceJoin::within(5).join(first_hits, second_hits);
+        let beyond = DistanceJoin::beyond(5).join(first_hits, second_hits);
+
+        assert_eq!(within.len(), 1);
+        assert_eq!(beyond.len(), 3);
+        assert!(
+            within
+                .iter()
+                .chain(&beyond)
+                .all(|pair| pair.first.text_id == 0)
+        );
+    }
+}
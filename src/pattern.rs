@@ -0,0 +1,335 @@
+use std::fmt;
+
+use crate::{
+    Alphabet, FmIndex, HalfOpenInterval, IndexStorage, text_with_rank_support::TextWithRankSupport,
+};
+
+/// The error of a branching search, like the search of a pattern with wildcards, when the number of branches
+/// (distinct suffix array intervals) exceeded the limit of the search.
+///
+/// It is returned for example by [`FmIndex::try_count_pattern`]. Queries that are too ambiguous, like thirty wildcards in
+/// a row, would otherwise lead to exponential work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternTooAmbiguous {
+    /// The limit of the number of branches that was exceeded.
+    pub max_branches: usize,
+}
+
+impl fmt::Display for PatternTooAmbiguous {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the search exceeded the limit of {} branches",
+            self.max_branches
+        )
+    }
+}
+
+impl std::error::Error for PatternTooAmbiguous {}
+
+// A set of symbols in dense representation that matches between `min_repetitions` and `max_repetitions`
+// (both inclusive) consecutive positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PatternElement {
+    pub(crate) symbols: Vec<u8>,
+    pub(crate) min_repetitions: usize,
+    pub(crate) max_repetitions: usize,
+}
+
+impl PatternElement {
+    pub(crate) fn new(mut symbols: Vec<u8>, repetitions: usize) -> Self {
+        symbols.sort_unstable();
+        symbols.dedup();
+
+        Self {
+            symbols,
+            min_repetitions: repetitions,
+            max_repetitions: repetitions,
+        }
+    }
+}
+
+fn parse_pattern(pattern: &[u8], alphabet: &Alphabet) -> Vec<PatternElement> {
+    let mut elements = Vec::new();
+    let mut i = 0;
+
+    while i < pattern.len() {
+        let symbols = match pattern[i] {
+            b'.' => {
+                i += 1;
+
+                searchable_symbols(alphabet).collect()
+            }
+            b'[' => {
+                let class_len = pattern[i + 1..]
+                    .iter()
+                    .position(|&symbol| symbol == b']')
+                    .expect("character class in pattern must be closed by ']'");
+
+                assert!(
+                    class_len > 0,
+                    "character class in pattern must not be empty"
+                );
+
+                let class = pattern[i + 1..i + 1 + class_len]
+                    .iter()
+                    .map(|&symbol| dense_symbol(symbol, alphabet))
+                    .collect();
+
+                i += class_len + 2;
+
+                class
+            }
+            b']' | b'{' | b'}' => panic!(
+                "unexpected symbol '{}' in pattern at position {i}",
+                pattern[i] as char
+            ),
+            symbol => {
+                i += 1;
+
+                vec![dense_symbol(symbol, alphabet)]
+            }
+        };
+
+        let mut repetitions = 1;
+
+        if pattern.get(i) == Some(&b'{') {
+            let number_len = pattern[i + 1..]
+                .iter()
+                .position(|&symbol| symbol == b'}')
+                .expect("repetition in pattern must be closed by '}'");
+
+            repetitions = std::str::from_utf8(&pattern[i + 1..i + 1 + number_len])
+                .ok()
+                .and_then(|number| number.parse().ok())
+                .expect("repetition in pattern must be a non-negative integer");
+
+            i += number_len + 2;
+        }
+
+        elements.push(PatternElement::new(symbols, repetitions));
+    }
+
+    elements
+}
+
+pub(crate) fn searchable_symbols(alphabet: &Alphabet) -> impl Iterator<Item = u8> {
+    (1..alphabet.num_dense_symbols() as u8).filter(|&symbol| alphabet.is_searchable(symbol))
+}
+
+pub(crate) fn dense_symbol(symbol: u8, alphabet: &Alphabet) -> u8 {
+    let dense_symbol = alphabet.io_to_dense_representation(symbol);
+
+    assert!(
+        alphabet.is_searchable(dense_symbol),
+        "symbols in a pattern must be searchable"
+    );
+
+    dense_symbol
+}
+
+pub(crate) fn search_pattern<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    pattern: &[u8],
+    max_branches: usize,
+) -> Result<Vec<HalfOpenInterval>, PatternTooAmbiguous> {
+    assert!(max_branches > 0, "max_branches must be greater than 0");
+
+    let elements = parse_pattern(pattern, index.alphabet());
+
+    let intervals = search_elements(index, &elements, max_branches)?
+        .into_iter()
+        .map(|(_, interval)| index.reported_interval(interval))
+        .filter(|interval| interval.start < interval.end)
+        .collect();
+
+    Ok(intervals)
+}
+
+// Branching backward search, returns the suffix array intervals of the matches together with their lengths.
+// The intervals of matches with the same length are disjoint or equal, and they are merged when they are adjacent,
+// because a single LF-mapping step of the merged interval yields the union of the extensions.
+// The search is aborted and an error is returned when the number of intervals exceeds max_branches.
+pub(crate) fn search_elements<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    elements: &[PatternElement],
+    max_branches: usize,
+) -> Result<Vec<(usize, HalfOpenInterval)>, PatternTooAmbiguous> {
+    let too_ambiguous = PatternTooAmbiguous { max_branches };
+    let mut matches = vec![(0, index.cursor_empty().interval())];
+
+    for element in elements.iter().rev() {
+        for _ in 0..element.min_repetitions {
+            matches = extend_front(index, &matches, &element.symbols);
+
+            if matches.len() > max_branches {
+                return Err(too_ambiguous);
+            }
+        }
+
+        let mut extended = matches.clone();
+
+        for _ in element.min_repetitions..element.max_repetitions {
+            extended = extend_front(index, &extended, &element.symbols);
+
+            if extended.is_empty() {
+                break;
+            }
+
+            if extended.len() > max_branches {
+                return Err(too_ambiguous);
+            }
+
+            matches.extend_from_slice(&extended);
+        }
+
+        matches = merge(matches);
+
+        if matches.len() > max_branches {
+            return Err(too_ambiguous);
+        }
+
+        if matches.is_empty() {
+            break;
+        }
+    }
+
+    Ok(matches)
+}
+
+fn extend_front<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    matches: &[(usize, HalfOpenInterval)],
+    symbols: &[u8],
+) -> Vec<(usize, HalfOpenInterval)> {
+    let mut extended = Vec::new();
+
+    for &symbol in symbols {
+        for &(len, interval) in matches {
+            let interval = index.lf_mapping_step_interval(symbol, interval);
+
+            if interval.start < interval.end {
+                extended.push((len + 1, interval));
+            }
+        }
+    }
+
+    merge(extended)
+}
+
+fn merge(mut matches: Vec<(usize, HalfOpenInterval)>) -> Vec<(usize, HalfOpenInterval)> {
+    matches.sort_unstable_by_key(|&(len, interval)| (len, interval.start));
+
+    let mut merged: Vec<(usize, HalfOpenInterval)> = Vec::with_capacity(matches.len());
+
+    for (len, interval) in matches {
+        match merged.last_mut() {
+            Some((last_len, last)) if *last_len == len && last.end >= interval.start => {
+                last.end = last.end.max(interval.end);
+            }
+            _ => merged.push((len, interval)),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    #[test]
+    fn parse_classes_wildcards_and_repetitions() {
+        let alphabet = alphabet::ascii_dna();
+        let element = |symbols: &[u8], repetitions| {
+            let symbols = symbols
+                .iter()
+                .map(|&symbol| alphabet.io_to_dense_representation(symbol))
+                .collect();
+
+            PatternElement::new(symbols, repetitions)
+        };
+
+        let elements = parse_pattern(b"a[GCg].{2}T{0}", &alphabet);
+
+        assert_eq!(
+            elements,
+            [
+                element(b"A", 1),
+                element(b"CG", 1),
+                element(b"ACGT", 2),
+                element(b"T", 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn branch_limit() {
+        let index = FmIndexConfig::<i32>::new()
+            .construct_index([b"ACGTTGCAAGCTAGCTTACG"], alphabet::ascii_dna());
+
+        assert_eq!(index.try_count_pattern(b"..", 2), Ok(19));
+        assert_eq!(
+            index.try_count_pattern(b"[CG]T", 1),
+            Err(PatternTooAmbiguous { max_branches: 1 })
+        );
+        assert_eq!(index.try_count_pattern(b"[CG]T", 2), Ok(3));
+        assert!(index.try_locate_pattern(b"[CG]T", 1).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "must be closed")]
+    fn unclosed_class() {
+        parse_pattern(b"A[CG", &alphabet::ascii_dna());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn pattern_search_agrees_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..60), 1..4),
+            elements in prop::collection::vec(
+                prop::sample::select(["A", "C", "G", "T", ".", "[AC]", "[GT]", "[ACG]", ".{2}"].as_slice()),
+                1..5
+            ),
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_index(&texts, alphabet::ascii_dna_with_n());
+
+            let pattern = elements.concat();
+            let parsed = parse_pattern(pattern.as_bytes(), index.alphabet());
+            let parsed: Vec<_> = parsed
+                .iter()
+                .flat_map(|element| std::iter::repeat_n(&element.symbols, element.min_repetitions))
+                .collect();
+            let matches = |window: &[u8]| {
+                window.iter().zip(&parsed).all(|(&symbol, symbols)| {
+                    symbols.contains(&index.alphabet().io_to_dense_representation(symbol))
+                })
+            };
+
+            let mut expected = Vec::new();
+
+            for (text_id, text) in texts.iter().enumerate() {
+                for (position, window) in text.windows(parsed.len()).enumerate() {
+                    if matches(window) {
+                        expected.push((text_id, position));
+                    }
+                }
+            }
+
+            let mut hits: Vec<_> = index
+                .locate_pattern(pattern.as_bytes())
+                .map(|hit| (hit.text_id, hit.position))
+                .collect();
+            hits.sort();
+
+            prop_assert_eq!(index.count_pattern(pattern.as_bytes()), expected.len());
+            prop_assert_eq!(hits, expected);
+        }
+    }
+}
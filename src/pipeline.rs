@@ -0,0 +1,218 @@
+use std::sync::{Arc, Mutex, mpsc};
+use std::{panic, thread};
+
+use crate::{BATCH_SIZE, FmIndex, Hit, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+/// Configuration of the threads and the buffering of [`FmIndex::locate_many_pipelined`].
+///
+/// The search runs on the calling thread and sends the hits in chunks of queries to the postprocessing threads.
+/// The channel between them holds at most [`Self::channel_capacity`] chunks. When the postprocessing is slower than
+/// the search, the search waits until the channel has space again, so the memory usage for buffered hits stays
+/// bounded. When the search is slower, the postprocessing threads wait for new chunks.
+///
+/// By default, one postprocessing thread is used per available CPU core, minus the one of the search, and the
+/// channel can hold two chunks per postprocessing thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    num_postprocessing_threads: usize,
+    channel_capacity: usize,
+}
+
+impl Pipeline {
+    /// Creates the default configuration, which uses one postprocessing thread per available CPU core, minus the
+    /// one of the search, and a channel capacity of two chunks per postprocessing thread.
+    pub fn new() -> Self {
+        let num_postprocessing_threads = thread::available_parallelism()
+            .map_or(1, |num_threads| num_threads.get().saturating_sub(1))
+            .max(1);
+
+        Self {
+            num_postprocessing_threads,
+            channel_capacity: 2 * num_postprocessing_threads,
+        }
+    }
+
+    /// Sets the number of threads that postprocess the hits. Panics if it is `0`.
+    pub fn num_postprocessing_threads(self, num_postprocessing_threads: usize) -> Self {
+        assert!(
+            num_postprocessing_threads > 0,
+            "num_postprocessing_threads must be greater than 0"
+        );
+
+        Self {
+            num_postprocessing_threads,
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of chunks of queries with their hits that are buffered between the search and
+    /// the postprocessing. Panics if it is `0`.
+    pub fn channel_capacity(self, channel_capacity: usize) -> Self {
+        assert!(
+            channel_capacity > 0,
+            "channel_capacity must be greater than 0"
+        );
+
+        Self {
+            channel_capacity,
+            ..self
+        }
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the query ids and hits of a chunk of consecutive queries
+type Chunk = Vec<(usize, Vec<Hit>)>;
+
+pub(crate) fn locate_many_pipelined<I, R, Q, T, F>(
+    index: &FmIndex<I, R>,
+    queries: impl IntoIterator<Item = Q>,
+    pipeline: &Pipeline,
+    postprocess: F,
+) -> Vec<T>
+where
+    I: IndexStorage,
+    R: TextWithRankSupport<I>,
+    Q: AsRef<[u8]> + Sync,
+    T: Send,
+    F: Fn(usize, &Q, Vec<Hit>) -> T + Sync,
+{
+    let queries: Vec<_> = queries.into_iter().collect();
+    let (sender, receiver) = mpsc::sync_channel::<Chunk>(pipeline.channel_capacity);
+    // every postprocessing thread owns a handle to the receiver, such that the channel is disconnected when
+    // all of them have finished or panicked, and the search doesn't block forever
+    let receiver = Arc::new(Mutex::new(receiver));
+
+    let results_per_thread: Vec<Vec<(usize, T)>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..pipeline.num_postprocessing_threads)
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                let queries = &queries;
+                let postprocess = &postprocess;
+
+                scope.spawn(move || {
+                    let mut results = Vec::new();
+
+                    loop {
+                        // the lock is released before the chunk is postprocessed
+                        let Ok(chunk) = receiver.lock().unwrap().recv() else {
+                            break;
+                        };
+
+                        results.extend(chunk.into_iter().map(|(query_id, hits)| {
+                            (query_id, postprocess(query_id, &queries[query_id], hits))
+                        }));
+                    }
+
+                    results
+                })
+            })
+            .collect();
+
+        drop(receiver);
+
+        let mut chunk = Vec::with_capacity(BATCH_SIZE);
+
+        for (query_id, hits) in index.locate_many(&queries).enumerate() {
+            chunk.push((query_id, hits.collect()));
+
+            if chunk.len() == BATCH_SIZE {
+                // fails only if all postprocessing threads panicked, which is propagated when joining them
+                if sender
+                    .send(std::mem::replace(
+                        &mut chunk,
+                        Vec::with_capacity(BATCH_SIZE),
+                    ))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+
+        if !chunk.is_empty() {
+            let _ = sender.send(chunk);
+        }
+
+        drop(sender);
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|payload| panic::resume_unwind(payload))
+            })
+            .collect()
+    });
+
+    let mut results: Vec<_> = results_per_thread.into_iter().flatten().collect();
+    results.sort_unstable_by_key(|(query_id, _)| *query_id);
+
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, alphabet};
+
+    use super::*;
+
+    #[test]
+    fn pipelined_agrees_with_locate_many() {
+        let texts = [b"ACGTACGTTTGACAGATTACA".as_slice(), b"GGATCACAGT"];
+        let index = FmIndexConfig::<i32>::new().construct_index(texts, alphabet::ascii_dna());
+
+        let queries: Vec<_> = (0..500)
+            .map(|i| [b"A", b"C", b"G", b"T"][i % 4].repeat(1 + i % 3))
+            .collect();
+
+        let expected: Vec<_> = index
+            .locate_many(&queries)
+            .map(|hits| {
+                let mut hits: Vec<_> = hits.collect();
+                hits.sort();
+                hits
+            })
+            .collect();
+
+        for pipeline in [
+            Pipeline::new(),
+            Pipeline::new()
+                .num_postprocessing_threads(1)
+                .channel_capacity(1),
+            Pipeline::new().num_postprocessing_threads(3),
+        ] {
+            let results =
+                index.locate_many_pipelined(&queries, &pipeline, |query_id, query, mut hits| {
+                    assert_eq!(*query, &queries[query_id]);
+                    hits.sort();
+                    hits
+                });
+
+            assert_eq!(results, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "postprocessing failed")]
+    fn panicking_postprocessing_is_propagated() {
+        let index =
+            FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+
+        let queries = vec![b"ACG"; 1000];
+        let pipeline = Pipeline::new()
+            .num_postprocessing_threads(1)
+            .channel_capacity(1);
+
+        // the search must not block on the full channel after the only postprocessing thread panicked
+        index.locate_many_pipelined(&queries, &pipeline, |_, _, _| -> usize {
+            panic!("postprocessing failed")
+        });
+    }
+}
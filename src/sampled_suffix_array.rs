@@ -3,7 +3,10 @@ use num_traits::{NumCast, PrimInt};
 
 use std::{collections::HashMap, marker::PhantomData, ops::Range};
 
-use crate::{IndexStorage, text_with_rank_support::TextWithRankSupport};
+use crate::{
+    BATCH_SIZE, IndexStorage, memory_region::MemoryRegion, text_border_lookup::TextBorderLookup,
+    text_with_rank_support::TextWithRankSupport,
+};
 
 use super::FmIndex;
 
@@ -17,7 +20,7 @@ use super::FmIndex;
 #[derive(Clone)]
 pub struct SampledSuffixArray<I> {
     suffix_array_data: Vec<u32>,
-    text_border_lookup: HashMap<usize, I>,
+    text_border_lookup: TextBorderLookup<I>,
     sampling_rate: usize,
     _compression_marker: PhantomData<I>,
 }
@@ -25,11 +28,11 @@ pub struct SampledSuffixArray<I> {
 impl<I: PrimInt + Pod> SampledSuffixArray<I> {
     // uncompressed means not the special case of u32 compression
     pub(crate) fn new_uncompressed(
-        mut suffix_array_data: Vec<u32>,
+        mut suffix_array_data: impl AsMut<[u32]>,
         sampling_rate: usize,
         text_border_lookup: HashMap<usize, I>,
     ) -> Self {
-        let suffix_array_view: &mut [I] = bytemuck::cast_slice_mut(&mut suffix_array_data);
+        let suffix_array_view: &mut [I] = bytemuck::cast_slice_mut(suffix_array_data.as_mut());
 
         let mut num_retained_values = 0;
         let mut write_index = 0;
@@ -42,12 +45,30 @@ impl<I: PrimInt + Pod> SampledSuffixArray<I> {
             }
         }
 
-        suffix_array_data.truncate(num_retained_values * size_of::<I>() / size_of::<u32>());
-        suffix_array_data.shrink_to_fit();
+        // copying the retained values allows the large buffer to be freed
+        let suffix_array_data = suffix_array_data.as_mut()
+            [..num_retained_values * size_of::<I>() / size_of::<u32>()]
+            .to_vec();
 
         Self {
             suffix_array_data,
-            text_border_lookup,
+            text_border_lookup: text_border_lookup.into(),
+            sampling_rate,
+            _compression_marker: PhantomData,
+        }
+    }
+}
+
+impl<I: Pod> SampledSuffixArray<I> {
+    // for samples that were computed without a full suffix array. the samples of u32 are stored as u32 anyway
+    pub(crate) fn from_samples(
+        samples: Vec<I>,
+        sampling_rate: usize,
+        text_border_lookup: HashMap<usize, I>,
+    ) -> Self {
+        Self {
+            suffix_array_data: bytemuck::cast_slice(&samples).to_vec(),
+            text_border_lookup: text_border_lookup.into(),
             sampling_rate,
             _compression_marker: PhantomData,
         }
@@ -56,11 +77,11 @@ impl<I: PrimInt + Pod> SampledSuffixArray<I> {
 
 impl SampledSuffixArray<u32> {
     pub(crate) fn new_u32_compressed(
-        mut suffix_array_data: Vec<u32>,
+        mut suffix_array_data: impl AsMut<[u32]>,
         sampling_rate: usize,
         text_border_lookup: HashMap<usize, u32>,
     ) -> Self {
-        let suffix_array_view: &mut [i64] = bytemuck::cast_slice_mut(&mut suffix_array_data);
+        let suffix_array_view: &mut [i64] = bytemuck::cast_slice_mut(suffix_array_data.as_mut());
 
         let mut num_retained_values: usize = 0;
 
@@ -94,12 +115,11 @@ impl SampledSuffixArray<u32> {
             }
         }
 
-        suffix_array_data.truncate(num_retained_values);
-        suffix_array_data.shrink_to_fit();
+        let suffix_array_data = suffix_array_data.as_mut()[..num_retained_values].to_vec();
 
         Self {
             suffix_array_data,
-            text_border_lookup,
+            text_border_lookup: text_border_lookup.into(),
             sampling_rate,
             _compression_marker: PhantomData,
         }
@@ -107,37 +127,167 @@ impl SampledSuffixArray<u32> {
 }
 
 impl<I: IndexStorage> SampledSuffixArray<I> {
-    pub(crate) fn recover_range<R: TextWithRankSupport<I>>(
-        &self,
+    pub(crate) fn memory_regions(&self) -> Vec<MemoryRegion> {
+        vec![MemoryRegion::of_slice(&self.suffix_array_data)]
+    }
+
+    // the entries of the hash map are counted without its unused buckets and control bytes
+    pub(crate) fn heap_size_bytes(&self) -> usize {
+        size_of_val(self.suffix_array_data.as_slice())
+            + self.text_border_lookup.len() * size_of::<(usize, I)>()
+    }
+
+    pub(crate) fn sampling_rate(&self) -> usize {
+        self.sampling_rate
+    }
+
+    pub(crate) fn samples(&self) -> &[I] {
+        bytemuck::cast_slice(&self.suffix_array_data)
+    }
+
+    // sorted by row
+    pub(crate) fn text_border_entries(&self) -> Vec<(usize, usize)> {
+        self.text_border_lookup
+            .sorted_entries()
+            .into_iter()
+            .map(|(&row, &value)| (row, <usize as NumCast>::from(value).unwrap()))
+            .collect()
+    }
+
+    // The entries are resolved lazily in batches of a bounded size, so the memory usage of the returned iterator does not
+    // depend on the size of the range, not even for huge intervals of queries like poly-A. The batch size starts at 1
+    // and doubles with every batch, such that pulling only a few entries doesn't resolve a full batch.
+    pub(crate) fn recover_range<'a, R: TextWithRankSupport<I>>(
+        &'a self,
         range: Range<usize>,
-        index: &FmIndex<I, R>,
-    ) -> impl Iterator<Item = usize> {
-        range.map(|mut i| {
-            let mut num_steps_done = I::zero();
+        index: &'a FmIndex<I, R>,
+    ) -> RecoveredRange<'a, I, R> {
+        RecoveredRange {
+            suffix_array: self,
+            index,
+            next_row: range.start,
+            end: range.end,
+            buffer: [0; BATCH_SIZE],
+            buffer_pos: 0,
+            buffer_len: 0,
+            batch_size: 1,
+        }
+    }
+}
+
+// Like for the batched search of many queries, the LF-mapping steps of all entries of a batch are interleaved,
+// such that the memory accesses of different entries can be executed in parallel by the CPU.
+pub(crate) struct RecoveredRange<'a, I, R> {
+    suffix_array: &'a SampledSuffixArray<I>,
+    index: &'a FmIndex<I, R>,
+    next_row: usize,
+    end: usize,
+    buffer: [usize; BATCH_SIZE],
+    buffer_pos: usize,
+    buffer_len: usize,
+    batch_size: usize,
+}
+
+impl<I: IndexStorage, R: TextWithRankSupport<I>> RecoveredRange<'_, I, R> {
+    fn recover_next_batch(&mut self) {
+        let batch_len = (self.end - self.next_row).min(self.batch_size);
+        let sampling_rate = self.suffix_array.sampling_rate;
+        let suffix_array_view: &[I] = bytemuck::cast_slice(&self.suffix_array.suffix_array_data);
+
+        let mut rows = [0; BATCH_SIZE];
+        let mut num_steps_done = [0; BATCH_SIZE];
+        let mut unresolved = [0; BATCH_SIZE];
+
+        // the rows and BWT symbols of the unresolved entries, in the order of unresolved
+        let mut unresolved_rows = [0; BATCH_SIZE];
+        let mut symbols = [0; BATCH_SIZE];
+
+        for i in 0..batch_len {
+            rows[i] = self.next_row + i;
+            unresolved[i] = i;
+        }
+
+        let mut num_unresolved = batch_len;
+
+        while num_unresolved > 0 {
+            let mut num_still_unresolved = 0;
 
-            while i % self.sampling_rate != 0 {
-                let bwt_symbol = index.text_with_rank_support.symbol_at(i);
+            for k in 0..num_unresolved {
+                let i = unresolved[k];
+
+                if rows[i] % sampling_rate == 0 {
+                    let value = suffix_array_view[rows[i] / sampling_rate];
+                    self.buffer[i] = <usize as NumCast>::from(value).unwrap() + num_steps_done[i];
+                } else {
+                    unresolved[num_still_unresolved] = i;
+                    unresolved_rows[num_still_unresolved] = rows[i];
+                    num_still_unresolved += 1;
+                }
+            }
+
+            num_unresolved = num_still_unresolved;
+
+            // the steps of all unresolved entries are done together, such that the memory accesses of different
+            // entries can be executed in parallel by the CPU
+            self.index.lf_mapping_step_many(
+                &mut unresolved_rows[..num_unresolved],
+                &mut symbols[..num_unresolved],
+            );
+
+            num_still_unresolved = 0;
+
+            for k in 0..num_unresolved {
+                let i = unresolved[k];
 
                 // this special case is needed, because the implicit sentinel of the libsais suffix array
                 // breaks the rank preservation property of the FM-Index.
-                if bwt_symbol == 0 {
-                    return <usize as NumCast>::from(self.text_border_lookup[&i] + num_steps_done)
-                        .unwrap();
+                if symbols[k] == 0 {
+                    let value = self.suffix_array.text_border_lookup[&rows[i]];
+                    self.buffer[i] = <usize as NumCast>::from(value).unwrap() + num_steps_done[i];
+                } else {
+                    rows[i] = unresolved_rows[k];
+                    num_steps_done[i] += 1;
+                    unresolved[num_still_unresolved] = i;
+                    num_still_unresolved += 1;
                 }
+            }
+
+            num_unresolved = num_still_unresolved;
+        }
 
-                i = index.lf_mapping_step(bwt_symbol, i);
+        self.next_row += batch_len;
+        self.buffer_pos = 0;
+        self.buffer_len = batch_len;
+        self.batch_size = (2 * self.batch_size).min(BATCH_SIZE);
+    }
+}
 
-                num_steps_done = num_steps_done + I::one();
+impl<I: IndexStorage, R: TextWithRankSupport<I>> Iterator for RecoveredRange<'_, I, R> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer_pos == self.buffer_len {
+            if self.next_row == self.end {
+                return None;
             }
 
-            let suffix_array_view: &[I] = bytemuck::cast_slice(&self.suffix_array_data);
+            self.recover_next_batch();
+        }
 
-            <usize as NumCast>::from(suffix_array_view[i / self.sampling_rate] + num_steps_done)
-                .unwrap()
-        })
+        self.buffer_pos += 1;
+
+        Some(self.buffer[self.buffer_pos - 1])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.buffer_len - self.buffer_pos + self.end - self.next_row;
+
+        (len, Some(len))
     }
 }
 
+impl<I: IndexStorage, R: TextWithRankSupport<I>> ExactSizeIterator for RecoveredRange<'_, I, R> {}
+
 #[cfg(test)]
 mod tests {
     use crate::{FmIndexConfig, alphabet};
@@ -178,6 +328,28 @@ mod tests {
         copied_and_recovered_array_must_equal(&texts, sampling_rate);
     }
 
+    #[test]
+    fn batch_size_grows() {
+        let text = b"ACGT".repeat(100);
+        let index = FmIndexConfig::<i32>::new()
+            .suffix_array_sampling_rate(4)
+            .construct_index([&text], alphabet::ascii_dna());
+
+        let mut recovered = index.suffix_array.recover_range(0..text.len(), &index);
+
+        recovered.next();
+        assert_eq!(recovered.buffer_len, 1);
+
+        let batch_lens: Vec<_> = std::iter::from_fn(|| {
+            recovered.next()?;
+            Some(recovered.buffer_len)
+        })
+        .collect();
+
+        assert_eq!(batch_lens[0], 2);
+        assert_eq!(batch_lens.iter().max(), Some(&crate::BATCH_SIZE));
+    }
+
     proptest! {
         // default is 256 and I'd like some more test cases that need to pass
         #![proptest_config(ProptestConfig::with_cases(2048))]
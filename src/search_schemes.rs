@@ -0,0 +1,450 @@
+use std::collections::HashMap;
+
+use crate::{
+    ApproximateHit, BidirectionalCursor, BidirectionalFmIndex, HalfOpenInterval, IndexStorage,
+    pattern, text_with_rank_support::TextWithRankSupport,
+};
+
+/// A single search of a [`SearchScheme`].
+///
+/// The query is divided into parts of (almost) equal length. The search starts with the first part of
+/// `parts_order` and then extends the searched region part by part in the given order, either to the left or to the
+/// right. Therefore, every prefix of the order must be a contiguous range of parts.
+///
+/// The bounds limit the accumulated number of errors. The upper bound of a part applies while the part is
+/// searched, and the lower bound has to be reached after the part was searched completely.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Search {
+    parts_order: Vec<usize>,
+    lower_bounds: Vec<usize>,
+    upper_bounds: Vec<usize>,
+}
+
+impl Search {
+    /// Creates a search from the order of the parts and the bounds of the accumulated errors.
+    ///
+    /// Panics if the order is not a permutation of the parts, if a prefix of the order is not contiguous,
+    /// if the lengths differ, or if the bounds are decreasing or a lower bound is larger than its upper bound.
+    pub fn new(
+        parts_order: Vec<usize>,
+        lower_bounds: Vec<usize>,
+        upper_bounds: Vec<usize>,
+    ) -> Self {
+        let num_parts = parts_order.len();
+
+        assert!(num_parts > 0, "a search must contain at least one part");
+        assert!(
+            lower_bounds.len() == num_parts && upper_bounds.len() == num_parts,
+            "a search must have one lower and one upper bound per part"
+        );
+
+        let (mut min_part, mut max_part) = (parts_order[0], parts_order[0]);
+
+        for &part in &parts_order[1..] {
+            if min_part > 0 && part == min_part - 1 {
+                min_part = part;
+            } else if part == max_part + 1 {
+                max_part = part;
+            } else {
+                panic!(
+                    "every prefix of the parts order of a search must be a contiguous range of parts"
+                );
+            }
+        }
+
+        assert!(
+            min_part == 0 && max_part == num_parts - 1,
+            "the parts order of a search must be a permutation of the parts"
+        );
+
+        assert!(
+            lower_bounds.is_sorted() && upper_bounds.is_sorted(),
+            "the error bounds of a search must not decrease"
+        );
+        assert!(
+            lower_bounds.iter().zip(&upper_bounds).all(|(l, u)| l <= u),
+            "the lower error bounds of a search must not be larger than the upper bounds"
+        );
+
+        Self {
+            parts_order,
+            lower_bounds,
+            upper_bounds,
+        }
+    }
+
+    pub fn parts_order(&self) -> &[usize] {
+        &self.parts_order
+    }
+
+    pub fn lower_bounds(&self) -> &[usize] {
+        &self.lower_bounds
+    }
+
+    pub fn upper_bounds(&self) -> &[usize] {
+        &self.upper_bounds
+    }
+
+    // whether the search finds an occurrence with the given number of errors per part
+    fn covers(&self, errors_per_part: &[usize]) -> bool {
+        let mut num_errors = 0;
+
+        self.parts_order.iter().enumerate().all(|(i, &part)| {
+            num_errors += errors_per_part[part];
+            self.lower_bounds[i] <= num_errors && num_errors <= self.upper_bounds[i]
+        })
+    }
+}
+
+/// A set of [`Search`]es that distributes the allowed errors of an approximate search over parts of the query.
+///
+/// Search schemes make approximate search with a [`BidirectionalFmIndex`] efficient, because the errors can be
+/// restricted to the parts that are searched last, when the suffix array intervals are already small. This is
+/// the approach of read mappers like Columba, and of SeqAn. See
+/// [`BidirectionalFmIndex::locate_with_search_scheme`] for how the schemes are executed.
+///
+/// Errors are substitutions only (Hamming distance).
+///
+/// ```
+/// use genedex::search_schemes::SearchScheme;
+///
+/// let scheme = SearchScheme::zero_one_star_zero(2);
+///
+/// assert_eq!(scheme.num_parts(), 4);
+/// assert!(scheme.is_lossless(2));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SearchScheme {
+    searches: Vec<Search>,
+}
+
+impl SearchScheme {
+    /// Creates a search scheme from user-supplied searches. Panics if the searches have different numbers of parts.
+    ///
+    /// Whether the scheme finds all occurrences with a given number of errors can be checked using
+    /// [`Self::is_lossless`].
+    pub fn new(searches: Vec<Search>) -> Self {
+        assert!(
+            !searches.is_empty(),
+            "a search scheme must contain at least one search"
+        );
+        assert!(
+            searches
+                .iter()
+                .all(|search| search.parts_order.len() == searches[0].parts_order.len()),
+            "all searches of a search scheme must have the same number of parts"
+        );
+
+        Self { searches }
+    }
+
+    /// The scheme based on the pigeonhole principle: with `max_errors + 1` parts, one of the parts must
+    /// be matched exactly. There is one search per part, which starts with that part.
+    pub fn pigeonhole(max_errors: usize) -> Self {
+        let num_parts = max_errors + 1;
+
+        let searches = (0..num_parts)
+            .map(|first_part| {
+                let mut upper_bounds = vec![max_errors; num_parts];
+                upper_bounds[0] = 0;
+
+                Search::new(
+                    parts_order_starting_at(first_part, num_parts),
+                    vec![0; num_parts],
+                    upper_bounds,
+                )
+            })
+            .collect();
+
+        Self::new(searches)
+    }
+
+    /// The scheme based on 01*0 seeds (Vroland et al.) with `max_errors + 2` parts.
+    ///
+    /// For every distribution of the errors, there is a part without errors, such that every range of
+    /// `t + 1` parts starting at it and extending to the right contains at most `t` errors. There is one search per
+    /// part, which starts with that part and first extends to the right.
+    pub fn zero_one_star_zero(max_errors: usize) -> Self {
+        let num_parts = max_errors + 2;
+
+        let searches = (0..num_parts)
+            .map(|first_part| {
+                let num_parts_to_the_right = num_parts - first_part;
+
+                let upper_bounds = (0..num_parts)
+                    .map(|i| {
+                        if i < num_parts_to_the_right {
+                            i.min(max_errors)
+                        } else {
+                            max_errors
+                        }
+                    })
+                    .collect();
+
+                Search::new(
+                    parts_order_starting_at(first_part, num_parts),
+                    vec![0; num_parts],
+                    upper_bounds,
+                )
+            })
+            .collect();
+
+        Self::new(searches)
+    }
+
+    pub fn searches(&self) -> &[Search] {
+        &self.searches
+    }
+
+    pub fn num_parts(&self) -> usize {
+        self.searches[0].parts_order.len()
+    }
+
+    /// Returns whether the scheme finds every occurrence with at most `max_errors` errors.
+    ///
+    /// All distributions of the errors over the parts are checked, so this is only feasible for small numbers of
+    /// errors and parts.
+    pub fn is_lossless(&self, max_errors: usize) -> bool {
+        let mut errors_per_part = vec![0; self.num_parts()];
+
+        self.all_distributions_covered(&mut errors_per_part, 0, max_errors)
+    }
+
+    fn all_distributions_covered(
+        &self,
+        errors_per_part: &mut [usize],
+        part: usize,
+        remaining_errors: usize,
+    ) -> bool {
+        if part == errors_per_part.len() {
+            return self
+                .searches
+                .iter()
+                .any(|search| search.covers(errors_per_part));
+        }
+
+        (0..=remaining_errors).all(|num_errors| {
+            errors_per_part[part] = num_errors;
+            self.all_distributions_covered(errors_per_part, part + 1, remaining_errors - num_errors)
+        })
+    }
+}
+
+// the given part, then all parts to its right, then all parts to its left
+fn parts_order_starting_at(first_part: usize, num_parts: usize) -> Vec<usize> {
+    (first_part..num_parts)
+        .chain((0..first_part).rev())
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+struct Step {
+    query_position: usize,
+    extend_back: bool,
+    upper_bound: usize,
+    // only set for the last step of a part
+    lower_bound: Option<usize>,
+}
+
+struct SearchExecution<'q> {
+    dense_query: &'q [Option<u8>],
+    symbols: &'q [u8],
+    steps: Vec<Step>,
+}
+
+pub(crate) fn locate_with_search_scheme<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &BidirectionalFmIndex<I, R>,
+    query: &[u8],
+    scheme: &SearchScheme,
+) -> Vec<ApproximateHit> {
+    let num_parts = scheme.num_parts();
+
+    assert!(
+        query.len() >= num_parts,
+        "the query must not be shorter than the number of parts of the search scheme"
+    );
+
+    let alphabet = index.alphabet();
+
+    // symbols that are not searchable never match and always cause an error
+    let dense_query: Vec<_> = query
+        .iter()
+        .map(|&symbol| {
+            alphabet
+                .try_io_to_dense_representation(symbol)
+                .filter(|&dense_symbol| alphabet.is_searchable(dense_symbol))
+        })
+        .collect();
+
+    let symbols: Vec<_> = pattern::searchable_symbols(alphabet).collect();
+    let part_range =
+        |part: usize| part * query.len() / num_parts..(part + 1) * query.len() / num_parts;
+
+    let mut matches = Vec::new();
+
+    for search in &scheme.searches {
+        let mut steps = Vec::with_capacity(query.len());
+        let mut covered_start = part_range(search.parts_order[0]).start;
+
+        for (i, &part) in search.parts_order.iter().enumerate() {
+            let range = part_range(part);
+            let extend_back = range.start >= covered_start;
+            covered_start = covered_start.min(range.start);
+
+            let positions: Vec<_> = if extend_back {
+                range.collect()
+            } else {
+                range.rev().collect()
+            };
+
+            for &query_position in &positions {
+                steps.push(Step {
+                    query_position,
+                    extend_back,
+                    upper_bound: search.upper_bounds[i],
+                    lower_bound: None,
+                });
+            }
+
+            // the query is not shorter than the number of parts, so no part is empty
+            steps.last_mut().unwrap().lower_bound = Some(search.lower_bounds[i]);
+        }
+
+        let execution = SearchExecution {
+            dense_query: &dense_query,
+            symbols: &symbols,
+            steps,
+        };
+
+        execution.extend(index.cursor_empty(), 0, 0, &mut matches);
+    }
+
+    // the same occurrence can be found by multiple searches
+    let mut hits: HashMap<_, _> = HashMap::new();
+
+    for (interval, num_errors) in matches {
+        for hit in index.forward_index().locate_interval(interval) {
+            hits.insert((hit.text_id, hit.position), num_errors);
+        }
+    }
+
+    let mut hits: Vec<_> = hits
+        .into_iter()
+        .map(|((text_id, position), num_errors)| ApproximateHit {
+            text_id,
+            range: position..position + query.len(),
+            edit_distance: num_errors,
+        })
+        .collect();
+
+    hits.sort_unstable_by_key(|hit| (hit.text_id, hit.range.start));
+
+    hits
+}
+
+impl SearchExecution<'_> {
+    fn extend<I: IndexStorage, R: TextWithRankSupport<I>>(
+        &self,
+        cursor: BidirectionalCursor<'_, I, R>,
+        step_idx: usize,
+        num_errors: usize,
+        matches: &mut Vec<(HalfOpenInterval, usize)>,
+    ) {
+        let Some(step) = self.steps.get(step_idx) else {
+            matches.push((cursor.forward_cursor().interval(), num_errors));
+            return;
+        };
+
+        let query_symbol = self.dense_query[step.query_position];
+
+        for &symbol in self.symbols {
+            let num_errors = num_errors + (query_symbol != Some(symbol)) as usize;
+
+            if num_errors > step.upper_bound
+                || step
+                    .lower_bound
+                    .is_some_and(|lower_bound| num_errors < lower_bound)
+            {
+                continue;
+            }
+
+            let mut next_cursor = cursor;
+
+            if step.extend_back {
+                next_cursor.extend_back_without_alphabet_translation(symbol);
+            } else {
+                next_cursor.extend_front_without_alphabet_translation(symbol);
+            }
+
+            if next_cursor.count() > 0 {
+                self.extend(next_cursor, step_idx + 1, num_errors, matches);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    #[test]
+    fn built_in_schemes_are_lossless() {
+        for max_errors in 0..5 {
+            assert!(SearchScheme::pigeonhole(max_errors).is_lossless(max_errors));
+            assert!(SearchScheme::zero_one_star_zero(max_errors).is_lossless(max_errors));
+        }
+
+        let single_search =
+            SearchScheme::new(vec![Search::new(vec![1, 0], vec![0, 0], vec![0, 2])]);
+        assert!(single_search.is_lossless(0));
+        assert!(!single_search.is_lossless(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "contiguous")]
+    fn non_contiguous_parts_order() {
+        Search::new(vec![0, 2, 1], vec![0; 3], vec![0, 1, 1]);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn search_schemes_agree_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 1..60), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 5..10),
+            max_errors in 0usize..3,
+            use_pigeonhole in any::<bool>(),
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_bidirectional_index(&texts, alphabet::ascii_dna_with_n());
+
+            let scheme = if use_pigeonhole {
+                SearchScheme::pigeonhole(max_errors)
+            } else {
+                SearchScheme::zero_one_star_zero(max_errors)
+            };
+
+            let mut expected = Vec::new();
+
+            for (text_id, text) in texts.iter().enumerate() {
+                for (position, window) in text.windows(query.len()).enumerate() {
+                    let num_errors = window.iter().zip(&query).filter(|(a, b)| a != b).count();
+
+                    if num_errors <= max_errors {
+                        expected.push(ApproximateHit {
+                            text_id,
+                            range: position..position + query.len(),
+                            edit_distance: num_errors,
+                        });
+                    }
+                }
+            }
+
+            prop_assert_eq!(index.locate_with_search_scheme(&query, &scheme), expected);
+        }
+    }
+}
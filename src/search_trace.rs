@@ -0,0 +1,269 @@
+use std::{fmt, ops::Range};
+
+use crate::{
+    Cursor, FmIndex, HalfOpenInterval, IndexStorage, text_with_rank_support::TextWithRankSupport,
+};
+
+/// A record of all steps of a backward search, created by [`FmIndex::cursor_for_query_traced`].
+///
+/// The trace can be pretty-printed using its [`Display`](fmt::Display) implementation. This is useful for debugging
+/// unexpected results, such as zero counts due to symbols that are not part of the alphabet of the index.
+/// It can also be used to illustrate how the FM-Index works.
+///
+/// ```
+/// use genedex::{FmIndexConfig, alphabet};
+///
+/// let index = FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+///
+/// let (cursor, trace) = index.cursor_for_query_traced(b"CGU");
+/// assert_eq!(cursor.count(), 0);
+/// assert_eq!(trace.steps().last().unwrap().dense_symbol, None);
+///
+/// println!("{trace}");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchTrace {
+    query: Vec<u8>,
+    lookup_jump: Option<LookupJump>,
+    steps: Vec<SearchStep>,
+    final_interval: Range<usize>,
+}
+
+/// The usage of the lookup table of the index at the start of a backward search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupJump {
+    /// The length of the suffix of the query that was searched using the lookup table.
+    pub suffix_len: usize,
+    pub interval: Range<usize>,
+}
+
+/// A single backward search step, in which the searched query was extended by one symbol at the front.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchStep {
+    /// The position of the symbol in the query.
+    pub query_position: usize,
+    /// The symbol in IO representation.
+    pub symbol: u8,
+    /// The symbol in dense representation, or `None` if it is not part of the alphabet of the index.
+    pub dense_symbol: Option<u8>,
+    /// Whether the symbol is part of the alphabet and searchable.
+    pub is_searchable: bool,
+    pub interval_before: Range<usize>,
+    pub interval_after: Range<usize>,
+}
+
+impl SearchTrace {
+    /// The searched query in IO representation.
+    pub fn query(&self) -> &[u8] {
+        &self.query
+    }
+
+    pub fn lookup_jump(&self) -> Option<&LookupJump> {
+        self.lookup_jump.as_ref()
+    }
+
+    /// The search steps in the order in which they were executed, i.e. from the back of the query to the front.
+    ///
+    /// The search stops early if the interval becomes empty.
+    pub fn steps(&self) -> &[SearchStep] {
+        &self.steps
+    }
+
+    /// The interval of the query in the suffix array of the index after the search.
+    pub fn final_interval(&self) -> Range<usize> {
+        self.final_interval.clone()
+    }
+}
+
+impl fmt::Display for SearchTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "backward search for query ")?;
+        write_symbols(f, &self.query)?;
+        writeln!(f, " (length {})", self.query.len())?;
+
+        match &self.lookup_jump {
+            Some(jump) => {
+                write!(f, "  lookup table jump for suffix ")?;
+                write_symbols(f, &self.query[self.query.len() - jump.suffix_len..])?;
+                writeln!(f, ": {}", DisplayInterval(&jump.interval))?;
+            }
+            None => writeln!(f, "  no lookup table jump")?,
+        }
+
+        for step in &self.steps {
+            write!(f, "  position {}: extend with ", step.query_position)?;
+            write_symbols(f, &[step.symbol])?;
+
+            match step.dense_symbol {
+                Some(dense_symbol) if step.is_searchable => write!(f, " (dense {dense_symbol})")?,
+                Some(dense_symbol) => write!(f, " (dense {dense_symbol}, NOT searchable)")?,
+                None => write!(f, " (NOT part of the alphabet)")?,
+            }
+
+            writeln!(
+                f,
+                ": {} -> {}",
+                DisplayInterval(&step.interval_before),
+                DisplayInterval(&step.interval_after)
+            )?;
+        }
+
+        let num_skipped_symbols = self.query.len()
+            - self.steps.len()
+            - self.lookup_jump.as_ref().map_or(0, |jump| jump.suffix_len);
+
+        if num_skipped_symbols > 0 {
+            writeln!(
+                f,
+                "  search stopped early, {num_skipped_symbols} symbol(s) not searched"
+            )?;
+        }
+
+        write!(f, "  result: {}", DisplayInterval(&self.final_interval))
+    }
+}
+
+struct DisplayInterval<'a>(&'a Range<usize>);
+
+impl fmt::Display for DisplayInterval<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}, {}) ({} occurrences)",
+            self.0.start,
+            self.0.end,
+            self.0.len()
+        )
+    }
+}
+
+fn write_symbols(f: &mut fmt::Formatter<'_>, symbols: &[u8]) -> fmt::Result {
+    if symbols.iter().all(|s| s.is_ascii_graphic()) {
+        write!(f, "\"{}\"", symbols.escape_ascii())
+    } else {
+        write!(f, "{symbols:?}")
+    }
+}
+
+fn to_range(interval: HalfOpenInterval) -> Range<usize> {
+    interval.start..interval.end
+}
+
+pub(crate) fn cursor_for_query_traced<'a, I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &'a FmIndex<I, R>,
+    query: &[u8],
+) -> (Cursor<'a, I, R>, SearchTrace) {
+    let alphabet = &index.alphabet;
+
+    let (mut remaining_query, query_suffix) = index.split_query_for_lookup(query);
+
+    let mut lookup_jump = None;
+
+    // the lookup table can only be used with searchable symbols
+    let mut cursor = if query_suffix.is_empty()
+        || query_suffix.iter().any(|&s| {
+            !alphabet
+                .try_io_to_dense_representation(s)
+                .is_some_and(|s| alphabet.is_searchable(s))
+        }) {
+        remaining_query = query;
+        index.cursor_empty()
+    } else {
+        let interval = index.lookup_tables.lookup(query_suffix, alphabet);
+
+        lookup_jump = Some(LookupJump {
+            suffix_len: query_suffix.len(),
+            interval: to_range(interval),
+        });
+
+        Cursor { index, interval }
+    };
+
+    let mut steps = Vec::new();
+
+    for (query_position, &symbol) in remaining_query.iter().enumerate().rev() {
+        let interval_before = to_range(cursor.interval());
+        let dense_symbol = alphabet.try_io_to_dense_representation(symbol);
+
+        match dense_symbol {
+            Some(dense_symbol) => cursor.extend_front_without_alphabet_translation(dense_symbol),
+            None => {
+                cursor.interval = HalfOpenInterval {
+                    start: cursor.interval.start,
+                    end: cursor.interval.start,
+                }
+            }
+        }
+
+        steps.push(SearchStep {
+            query_position,
+            symbol,
+            dense_symbol,
+            is_searchable: dense_symbol.is_some_and(|s| alphabet.is_searchable(s)),
+            interval_before,
+            interval_after: to_range(cursor.interval()),
+        });
+
+        if cursor.count() == 0 {
+            break;
+        }
+    }
+
+    let trace = SearchTrace {
+        query: query.to_vec(),
+        lookup_jump,
+        steps,
+        final_interval: to_range(cursor.interval()),
+    };
+
+    (cursor, trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, alphabet};
+
+    #[test]
+    fn trace_agrees_with_search() {
+        let texts = [b"ACGTACGTNNACGA".as_slice(), b"TTACG"];
+
+        for lookup_table_depth in 0..4 {
+            let index = FmIndexConfig::<i32>::new()
+                .lookup_table_depth(lookup_table_depth)
+                .construct_index(texts, alphabet::ascii_dna_with_n());
+
+            for query in [b"ACG".as_slice(), b"", b"TACG", b"NACG", b"GACG", b"ACGX"] {
+                let (cursor, trace) = index.cursor_for_query_traced(query);
+
+                if !query.contains(&b'X') {
+                    assert_eq!(cursor.count(), index.count(query));
+                }
+
+                assert_eq!(trace.final_interval().len(), cursor.count());
+
+                let uses_lookup = trace.lookup_jump().is_some();
+                assert_eq!(
+                    uses_lookup,
+                    lookup_table_depth > 0 && !query.is_empty() && query.last() != Some(&b'X')
+                );
+
+                // should not panic
+                let _ = trace.to_string();
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_symbol_stops_search() {
+        let index =
+            FmIndexConfig::<i32>::new().construct_index([b"ACGTACGT"], alphabet::ascii_dna());
+
+        let (cursor, trace) = index.cursor_for_query_traced(b"AXCG");
+
+        assert_eq!(cursor.count(), 0);
+        assert_eq!(trace.steps().len(), 3);
+        assert_eq!(trace.steps()[2].dense_symbol, None);
+        assert_eq!(trace.steps()[1].interval_after.len(), 2);
+        assert!(trace.to_string().contains("NOT part of the alphabet"));
+    }
+}
@@ -0,0 +1,164 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{FmIndex, Hit, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+/// An occurrence of a query with at most a given number of mismatches, found by [`FmIndex::search_with_seeds`].
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MismatchHit {
+    pub text_id: usize,
+    /// The start position of the match in the text. The match has the same length as the query.
+    pub position: usize,
+    /// The number of positions at which the query and the matched text differ.
+    pub mismatches: usize,
+}
+
+impl fmt::Display for MismatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "text {}, position {}, {} mismatches",
+            self.text_id, self.position, self.mismatches
+        )
+    }
+}
+
+/// Drops the number of mismatches of the hit.
+impl From<MismatchHit> for Hit {
+    fn from(hit: MismatchHit) -> Self {
+        Hit {
+            text_id: hit.text_id,
+            position: hit.position,
+        }
+    }
+}
+
+// Pigeonhole principle: if the query occurs with at most k mismatches, at least one of k + 1 disjoint parts of the
+// query occurs without mismatches. The exact occurrences of the parts (seeds) yield candidate start positions. Every
+// candidate is verified by extracting its window around the suffix array row of a seed occurrence, so the texts
+// are never extracted as a whole.
+pub(crate) fn search_with_seeds<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    query: &[u8],
+    max_mismatches: usize,
+) -> Vec<MismatchHit> {
+    assert!(
+        query.len() > max_mismatches,
+        "the query must be longer than the number of allowed mismatches"
+    );
+
+    let num_seeds = max_mismatches + 1;
+    let seed_starts: Vec<_> = (0..=num_seeds)
+        .map(|i| i * query.len() / num_seeds)
+        .collect();
+
+    let seeds = seed_starts
+        .windows(2)
+        .map(|bounds| &query[bounds[0]..bounds[1]]);
+
+    // the start of the seed in the query and the row of its occurrence, for every candidate
+    let mut candidates = BTreeMap::new();
+
+    for (cursor, &seed_start) in index.cursors_for_many_queries(seeds).zip(&seed_starts) {
+        let interval = cursor.interval;
+        let seed_hits = index.locate_interval_including_disabled_texts(interval);
+
+        for (row, hit) in (interval.start..interval.end).zip(seed_hits) {
+            if index.disabled_texts.contains(hit.text_id) {
+                continue;
+            }
+
+            let Some(position) = hit.position.checked_sub(seed_start) else {
+                continue;
+            };
+
+            // later seeds are preferred, because fewer inverse LF-mapping steps are needed behind them
+            if position + query.len() <= index.text_len(hit.text_id) {
+                candidates.insert((hit.text_id, position), (seed_start, row));
+            }
+        }
+    }
+
+    let alphabet = index.alphabet();
+
+    // symbols are compared by their dense representation, and symbols of the query that are not searchable
+    // never match, like in the exact search
+    let dense_query: Vec<_> = query
+        .iter()
+        .map(|&symbol| {
+            alphabet
+                .try_io_to_dense_representation(symbol)
+                .filter(|&dense_symbol| alphabet.is_searchable(dense_symbol))
+        })
+        .collect();
+
+    let mut hits = Vec::new();
+
+    for ((text_id, position), (seed_start, row)) in candidates {
+        let window = index.symbols_around_row(row, seed_start, query.len() - seed_start);
+
+        let mismatches = dense_query
+            .iter()
+            .zip(window)
+            .filter(|&(&query_symbol, text_symbol)| query_symbol != Some(text_symbol))
+            .count();
+
+        if mismatches <= max_mismatches {
+            hits.push(MismatchHit {
+                text_id,
+                position,
+                mismatches,
+            });
+        }
+    }
+
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    #[test]
+    #[should_panic(expected = "longer than the number of allowed mismatches")]
+    fn query_too_short() {
+        let index = FmIndexConfig::<i32>::new().construct_index([b"ACGT"], alphabet::ascii_dna());
+        index.search_with_seeds(b"AC", 2);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn seed_search_agrees_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 0..60), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 4..10),
+            max_mismatches in 0usize..4,
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .construct_index(&texts, alphabet::ascii_dna_with_n());
+
+            let mut expected = Vec::new();
+
+            for (text_id, text) in texts.iter().enumerate() {
+                for (position, window) in text.windows(query.len()).enumerate() {
+                    let mismatches = window
+                        .iter()
+                        .zip(&query)
+                        .filter(|&(&text_symbol, &query_symbol)| query_symbol == b'N' || text_symbol != query_symbol)
+                        .count();
+
+                    if mismatches <= max_mismatches {
+                        expected.push(MismatchHit { text_id, position, mismatches });
+                    }
+                }
+            }
+
+            prop_assert_eq!(index.search_with_seeds(&query, max_mismatches), expected);
+        }
+    }
+}
@@ -0,0 +1,553 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::prelude::*;
+
+use crate::bwt::{self, Bwt, SentinelOrder};
+use crate::bwt_runs;
+use crate::config::ConstructionStage;
+use crate::construction::{self, from_bwt};
+use crate::{
+    Alphabet, BwtRuns, FmIndex, FmIndexConfig, IndexStorage,
+    text_with_rank_support::{TextBlocks, TextWithRankSupport},
+};
+
+use num_traits::NumCast;
+
+/// Configuration of the temporary files and the memory usage of [`FmIndexConfig::construct_index_semi_external`].
+///
+/// The texts are split into chunks, such that the suffix array construction of a chunk fits into the memory
+/// budget. The BWT of every chunk is constructed in memory and merged into the BWT of the previous chunks,
+/// which is stored in a temporary file in [`Self::temp_dir`]. Finally, the index is built from the merged BWT.
+///
+/// The memory budget is in bytes and is an estimate, not a hard limit. It includes the rank support data
+/// structure of the merged BWT, which is needed for merging, so the chunks get smaller as more texts are merged.
+/// The merged BWT is read from the temporary file block by block and is never held in memory completely. At
+/// least one text is merged at a time, so a budget that is smaller than the rank support of the final index
+/// leads to a chunk per text. A single text that is larger than a chunk is constructed in its own chunk and
+/// exceeds the budget. The final index itself is not part of the budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemiExternalConstruction {
+    temp_dir: PathBuf,
+    memory_budget: usize,
+}
+
+impl SemiExternalConstruction {
+    /// Panics if `memory_budget` is `0`.
+    pub fn new(temp_dir: impl Into<PathBuf>, memory_budget: usize) -> Self {
+        assert!(memory_budget > 0, "memory_budget must be greater than 0");
+
+        Self {
+            temp_dir: temp_dir.into(),
+            memory_budget,
+        }
+    }
+
+    pub fn temp_dir(&self) -> &Path {
+        &self.temp_dir
+    }
+
+    pub fn memory_budget(&self) -> usize {
+        self.memory_budget
+    }
+
+    /// The maximum total length of the texts of the first chunk, including one sentinel per text. Later chunks
+    /// are smaller, because the rank support of the merged BWT is part of the budget.
+    pub fn max_chunk_len(&self) -> usize {
+        (self.memory_budget / BYTES_PER_CHUNK_SYMBOL).max(1)
+    }
+
+    // the part of the budget that is left for the chunk after the rank support of the merged BWT
+    fn max_chunk_len_after_merged<I: IndexStorage, R: TextWithRankSupport<I>>(
+        &self,
+        merged_len: usize,
+        alphabet_size: usize,
+    ) -> usize {
+        let merged_size = if merged_len == 0 {
+            0
+        } else {
+            R::estimated_size_in_bytes(merged_len, alphabet_size)
+        };
+
+        (self.memory_budget.saturating_sub(merged_size) / BYTES_PER_CHUNK_SYMBOL).max(1)
+    }
+}
+
+// the texts, the generalized suffix array and the BWT during the construction of the BWT of a chunk, and the BWT,
+// its rank support and the ranks of its rows in the merged BWT during merging
+const BYTES_PER_CHUNK_SYMBOL: usize = 12;
+
+const IO_BLOCK_SIZE: usize = 1 << 20;
+
+// used to give concurrent constructions of the same process different temporary files
+static NEXT_CONSTRUCTION_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn construct_index_semi_external<I, R, T>(
+    texts: impl IntoIterator<Item = T>,
+    alphabet: Alphabet,
+    config: FmIndexConfig<I, R>,
+    semi_external: &SemiExternalConstruction,
+) -> io::Result<FmIndex<I, R>>
+where
+    I: IndexStorage,
+    R: TextWithRankSupport<I>,
+    T: AsRef<[u8]>,
+{
+    let max_total_len = <usize as NumCast>::from(I::max_value()).unwrap_or(usize::MAX);
+    let mut texts = crate::check_empty_text_policy(texts, config.empty_text_policy).peekable();
+    let mut temp_files = TempFiles::new(&semi_external.temp_dir);
+    let mut merged = MergedBwt::new(alphabet.num_dense_symbols());
+
+    // the total number of chunks is unknown, so only the start and end of merging are reported
+    config.report_progress(ConstructionStage::ConstructBwt, 0.0);
+
+    loop {
+        let max_chunk_len = semi_external
+            .max_chunk_len_after_merged::<I, R>(merged.len, alphabet.num_dense_symbols());
+        let mut chunk = Vec::new();
+        let mut chunk_len = 0;
+
+        while let Some(text) = texts
+            .next_if(|text| chunk.is_empty() || chunk_len + text.as_ref().len() < max_chunk_len)
+        {
+            chunk_len += text.as_ref().len() + 1;
+            chunk.push(text);
+        }
+
+        if chunk.is_empty() {
+            break;
+        }
+
+        // checked before the chunk is constructed, to not waste the work of merging the previous chunks
+        if merged.len + chunk_len > max_total_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the total length of the texts (including one sentinel per text) exceeds the maximum value of the \
+                index storage type, use a larger index storage type or a multi-volume index",
+            ));
+        }
+
+        // the generic texts are not required to be Send, so only slices of them are passed into the thread pool
+        let chunk_slices: Vec<&[u8]> = chunk.iter().map(|text| text.as_ref()).collect();
+
+        // the sentinels are ordered by text id, such that the texts of the chunk are larger than the merged ones
+        let chunk_bwt = config.in_thread_pool(|| {
+            if chunk_len <= i32::MAX as usize {
+                bwt::construct_bwt_with_sentinel_order::<i32, _>(
+                    chunk_slices,
+                    &alphabet,
+                    false,
+                    &SentinelOrder::ByTextId,
+                )
+            } else {
+                bwt::construct_bwt_with_sentinel_order::<i64, _>(
+                    chunk_slices,
+                    &alphabet,
+                    false,
+                    &SentinelOrder::ByTextId,
+                )
+            }
+        });
+
+        drop(chunk);
+
+        merged = config.in_thread_pool(|| {
+            merged.merge_chunk::<I, R>(&chunk_bwt, &alphabet, &mut temp_files)
+        })?;
+    }
+
+    assert!(merged.len > 0, "There should be at least one texts");
+
+    config.report_progress(ConstructionStage::ConstructBwt, 1.0);
+
+    let data_structures = config.in_thread_pool(|| {
+        config.report_progress(ConstructionStage::ConstructRankSupport, 0.0);
+        let (text_with_rank_support, bwt_runs) = read_rank_support(
+            &temp_files.current,
+            merged.len,
+            &alphabet,
+            config.record_bwt_run_boundaries,
+        )?;
+        config.report_progress(ConstructionStage::ConstructRankSupport, 1.0);
+
+        io::Result::Ok(from_bwt::create_data_structures_from_rank_support(
+            text_with_rank_support,
+            bwt_runs,
+            &merged.frequencies,
+            &merged.sentinel_positions,
+            &config,
+        ))
+    })?;
+
+    Ok(FmIndex::from_data_structures(
+        data_structures,
+        alphabet,
+        &config,
+    ))
+}
+
+// The BWT of all texts of the previous chunks, with the sentinels ordered by text id. Only the BWT itself is
+// stored in the temporary file.
+struct MergedBwt {
+    len: usize,
+    frequencies: Vec<usize>,
+    sentinel_positions: BTreeMap<usize, usize>,
+}
+
+impl MergedBwt {
+    fn new(num_dense_symbols: usize) -> Self {
+        Self {
+            len: 0,
+            frequencies: vec![0; num_dense_symbols],
+            sentinel_positions: BTreeMap::new(),
+        }
+    }
+
+    // Every suffix of the chunk is searched backwards in the merged BWT to find the number of merged suffixes that
+    // are smaller. The rows of the chunk are walked at the same time to know to which row the number belongs.
+    // Since both orders are consistent, these numbers are non-decreasing in the order of the rows of the chunk,
+    // and the BWTs can be interleaved in a single pass over the temporary file.
+    fn merge_chunk<I: IndexStorage, R: TextWithRankSupport<I>>(
+        self,
+        chunk_bwt: &Bwt,
+        alphabet: &Alphabet,
+        temp_files: &mut TempFiles,
+    ) -> io::Result<Self> {
+        let chunk_symbols = &chunk_bwt.as_bytes()[..chunk_bwt.len()];
+        let chunk_frequencies = frequencies_of(chunk_symbols, alphabet);
+
+        let frequencies: Vec<_> = self
+            .frequencies
+            .iter()
+            .zip(&chunk_frequencies)
+            .map(|(merged_frequency, chunk_frequency)| merged_frequency + chunk_frequency)
+            .collect();
+
+        if self.len == 0 {
+            fs::write(&temp_files.current, chunk_symbols)?;
+
+            return Ok(Self {
+                len: chunk_symbols.len(),
+                frequencies,
+                sentinel_positions: chunk_bwt.sentinel_suffix_array_values().clone(),
+            });
+        }
+
+        let merged_bwt = BwtFile {
+            path: &temp_files.current,
+            len: self.len,
+        };
+        let merged_rank_support =
+            R::construct_from_text_blocks(&merged_bwt, alphabet.num_dense_symbols())?;
+        let merged_count = from_bwt::count_from_frequencies(&self.frequencies);
+
+        let (chunk_rank_support, _) =
+            construction::construct_text_with_rank_support_and_bwt_runs_maybe_slice_compressed::<
+                I,
+                R,
+            >(chunk_symbols, chunk_symbols.len(), false, false, alphabet);
+        let chunk_count = from_bwt::count_from_frequencies(&chunk_frequencies);
+
+        let merged_ranks: Vec<_> = (0..chunk_symbols.len())
+            .map(|_| AtomicUsize::new(0))
+            .collect();
+
+        // the suffixes that start with the sentinels of the chunk are larger than the ones of the merged texts
+        let num_merged_texts = self.frequencies[0];
+        let num_chunk_texts = chunk_frequencies[0];
+
+        (0..num_chunk_texts).into_par_iter().for_each(|text_id| {
+            let mut row = text_id;
+            let mut merged_rank = num_merged_texts;
+
+            loop {
+                merged_ranks[row].store(merged_rank, Ordering::Relaxed);
+
+                let symbol = chunk_symbols[row];
+
+                if symbol == 0 {
+                    break;
+                }
+
+                merged_rank =
+                    merged_count[symbol as usize] + merged_rank_support.rank(symbol, merged_rank);
+                row = chunk_count[symbol as usize] + chunk_rank_support.rank(symbol, row);
+            }
+        });
+
+        drop(merged_rank_support);
+        drop(chunk_rank_support);
+
+        let merged_ranks: Vec<_> = merged_ranks
+            .into_iter()
+            .map(AtomicUsize::into_inner)
+            .collect();
+
+        let mut reader = BufReader::new(File::open(&temp_files.current)?);
+        let mut writer = BufWriter::new(File::create(&temp_files.next)?);
+        let mut block = vec![0; IO_BLOCK_SIZE];
+
+        let chunk_sentinel_positions = chunk_bwt.sentinel_suffix_array_values();
+        let offset = self.len;
+        let mut sentinel_positions = BTreeMap::new();
+        let mut next_row = 0;
+        let mut next_chunk_row = 0;
+        let mut merged_row = 0;
+
+        // the rows of the chunk that are smaller than the given row of the merged BWT
+        let num_chunk_rows_before = |merged_row: usize, next_chunk_row: usize| {
+            merged_ranks[next_chunk_row..]
+                .iter()
+                .take_while(|&&rank| rank <= merged_row)
+                .count()
+        };
+
+        while merged_row < self.len {
+            let block_len = reader.read(&mut block)?;
+
+            if block_len == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+
+            let mut run_start = 0;
+
+            for (i, &symbol) in block[..block_len].iter().enumerate() {
+                let num_chunk_rows = num_chunk_rows_before(merged_row, next_chunk_row);
+
+                if num_chunk_rows > 0 {
+                    writer.write_all(&block[run_start..i])?;
+                    run_start = i;
+
+                    let chunk_rows = next_chunk_row..next_chunk_row + num_chunk_rows;
+                    writer.write_all(&chunk_symbols[chunk_rows.clone()])?;
+
+                    for chunk_row in chunk_rows {
+                        if let Some(&text_start) = chunk_sentinel_positions.get(&chunk_row) {
+                            sentinel_positions.insert(next_row, offset + text_start);
+                        }
+
+                        next_row += 1;
+                    }
+
+                    next_chunk_row += num_chunk_rows;
+                }
+
+                if symbol == 0 {
+                    sentinel_positions.insert(next_row, self.sentinel_positions[&merged_row]);
+                }
+
+                next_row += 1;
+                merged_row += 1;
+            }
+
+            writer.write_all(&block[run_start..block_len])?;
+        }
+
+        for chunk_row in next_chunk_row..chunk_symbols.len() {
+            if let Some(&text_start) = chunk_sentinel_positions.get(&chunk_row) {
+                sentinel_positions.insert(next_row, offset + text_start);
+            }
+
+            next_row += 1;
+        }
+
+        writer.write_all(&chunk_symbols[next_chunk_row..])?;
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&temp_files.next, &temp_files.current)?;
+
+        Ok(Self {
+            len: next_row,
+            frequencies,
+            sentinel_positions,
+        })
+    }
+}
+
+fn frequencies_of(symbols: &[u8], alphabet: &Alphabet) -> Vec<usize> {
+    let mut frequencies = vec![0; alphabet.num_dense_symbols()];
+
+    for &symbol in symbols {
+        frequencies[symbol as usize] += 1;
+    }
+
+    frequencies
+}
+
+fn read_rank_support<I: IndexStorage, R: TextWithRankSupport<I>>(
+    path: &Path,
+    len: usize,
+    alphabet: &Alphabet,
+    record_bwt_run_boundaries: bool,
+) -> io::Result<(R, BwtRuns)> {
+    let bwt = BwtFile { path, len };
+
+    let text_with_rank_support = R::construct_from_text_blocks(&bwt, alphabet.num_dense_symbols())?;
+    let bwt_runs = bwt_runs::collect_bwt_runs_from_text_blocks(&bwt, record_bwt_run_boundaries)?;
+
+    Ok((text_with_rank_support, bwt_runs))
+}
+
+// the merged BWT in a temporary file, which is read in blocks of about IO_BLOCK_SIZE symbols
+struct BwtFile<'a> {
+    path: &'a Path,
+    len: usize,
+}
+
+impl TextBlocks for BwtFile<'_> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn max_block_len(&self) -> usize {
+        IO_BLOCK_SIZE
+    }
+
+    fn try_for_each_block(
+        &self,
+        block_len: usize,
+        mut f: impl FnMut(usize, &[u8]),
+    ) -> io::Result<()> {
+        let mut file = File::open(self.path)?;
+        let mut block = vec![0; block_len.min(self.len)];
+        let mut start = 0;
+
+        while start < self.len {
+            let len = (self.len - start).min(block_len);
+            file.read_exact(&mut block[..len])?;
+            f(start, &block[..len]);
+            start += len;
+        }
+
+        Ok(())
+    }
+}
+
+// the temporary file of the merged BWT and the one that the next merged BWT is written to.
+// they are removed when the construction ends, also if it fails
+struct TempFiles {
+    current: PathBuf,
+    next: PathBuf,
+}
+
+impl TempFiles {
+    fn new(temp_dir: &Path) -> Self {
+        let construction_id = NEXT_CONSTRUCTION_ID.fetch_add(1, Ordering::Relaxed);
+        let file_name = |suffix| {
+            temp_dir.join(format!(
+                "genedex-semi-external-{}-{construction_id}-{suffix}.bwt",
+                std::process::id()
+            ))
+        };
+
+        Self {
+            current: file_name("current"),
+            next: file_name("next"),
+        }
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.current);
+        let _ = fs::remove_file(&self.next);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet;
+    use proptest::prelude::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn agrees_with_in_memory_construction(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 0..60), 1..8),
+            queries in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 1..4), 1..10),
+            memory_budget in 1usize..1000,
+        ) {
+            let alphabet = alphabet::ascii_dna_with_n();
+            let temp_dir = std::env::temp_dir();
+            let semi_external = SemiExternalConstruction::new(&temp_dir, memory_budget);
+
+            let config = || FmIndexConfig::<i32>::new().suffix_array_sampling_rate(3).lookup_table_depth(2);
+            let index = config()
+                .construct_index_semi_external(&texts, alphabet.clone(), &semi_external)
+                .unwrap();
+            let expected_index = config().construct_index(&texts, alphabet.clone());
+
+            let expected_bwt = bwt::construct_bwt_with_sentinel_order::<i32, _>(&texts, &alphabet, false, &SentinelOrder::ByTextId);
+            prop_assert!(index.bwt_iter().eq(expected_bwt.iter()));
+
+            for query in &queries {
+                let mut hits: Vec<_> = index.locate(query).collect();
+                let mut expected_hits: Vec<_> = expected_index.locate(query).collect();
+                hits.sort();
+                expected_hits.sort();
+
+                prop_assert_eq!(hits, expected_hits);
+            }
+
+            for (text_id, text) in texts.iter().enumerate() {
+                prop_assert_eq!(index.text_len(text_id), text.len());
+            }
+        }
+    }
+
+    #[test]
+    fn temporary_files_are_removed() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("genedex_semi_external_test_{}", std::process::id()));
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let semi_external = SemiExternalConstruction::new(&temp_dir, 50);
+        let texts = [b"ACGTACGTTT".as_slice(), b"GGATCACAGT", b"ACGT"];
+
+        let index = FmIndexConfig::<u32>::new()
+            .construct_index_semi_external(texts, alphabet::ascii_dna(), &semi_external)
+            .unwrap();
+
+        assert_eq!(index.count(b"ACGT"), 3);
+        assert_eq!(fs::read_dir(&temp_dir).unwrap().count(), 0);
+
+        fs::remove_dir(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn too_long_texts() {
+        let semi_external = SemiExternalConstruction::new(std::env::temp_dir(), usize::MAX);
+
+        // the texts are only borrowed, so the check has to happen before anything is constructed
+        let text = vec![b'A'; 1 << 26];
+        let texts = std::iter::repeat_n(text.as_slice(), 40);
+
+        let result = FmIndexConfig::<i32>::new().construct_index_semi_external(
+            texts,
+            alphabet::ascii_dna(),
+            &semi_external,
+        );
+
+        assert!(result.is_err_and(|err| err.kind() == io::ErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn missing_temp_dir() {
+        let semi_external =
+            SemiExternalConstruction::new("/this/directory/does/not/exist/genedex", 50);
+
+        let result = FmIndexConfig::<i32>::new().construct_index_semi_external(
+            [b"ACGTACGTTT".as_slice(), b"GGATCACAGT"],
+            alphabet::ascii_dna(),
+            &semi_external,
+        );
+
+        assert!(result.is_err());
+    }
+}
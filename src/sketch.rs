@@ -0,0 +1,249 @@
+use crate::{Alphabet, FmIndex, IndexStorage, kmers, text_with_rank_support::TextWithRankSupport};
+
+/// A FracMinHash sketch of the k-mers of a sequence or an indexed collection of texts.
+///
+/// The sketch contains the hash values of all distinct k-mers whose hash is at most `u64::MAX / scaled`.
+/// Therefore, it keeps roughly a fraction of `1 / scaled` of the distinct k-mers. Sketches allow estimating the
+/// Jaccard similarity and containment of large sequence collections cheaply, for example to pre-filter
+/// candidate references before an exact search using the FM-Index.
+///
+/// The k-mers are hashed in dense representation. Sketches should therefore only be compared if they were
+/// created using the same alphabet. K-mers that contain symbols that are not searchable are skipped.
+///
+/// ```
+/// use genedex::{FmIndexConfig, FracMinHashSketch, alphabet};
+///
+/// let reference = b"ACGTTGCATGCATCGATCGATCGACTGACTAGCTAGCTAGCTACGACTAGCATCGACTAC";
+/// let index = FmIndexConfig::<i32>::new().construct_index([reference], alphabet::ascii_dna());
+///
+/// let reference_sketch = FracMinHashSketch::from_index(&index, 11, 1);
+/// let query_sketch = FracMinHashSketch::from_sequence(&reference[10..40], index.alphabet(), 11, 1);
+///
+/// assert_eq!(query_sketch.containment(&reference_sketch), 1.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FracMinHashSketch {
+    k: usize,
+    scaled: u64,
+    hashes: Vec<u64>,
+}
+
+impl FracMinHashSketch {
+    /// Creates the sketch of all distinct k-mers of the texts of `index`.
+    ///
+    /// The k-mers are enumerated using the index. Panics if `k` or `scaled` is `0`.
+    pub fn from_index<I: IndexStorage, R: TextWithRankSupport<I>>(
+        index: &FmIndex<I, R>,
+        k: usize,
+        scaled: u64,
+    ) -> Self {
+        let mut sketch = Self::new_empty(k, scaled);
+
+        kmers::for_each_kmer(index, k, |kmer, _| sketch.insert_dense_kmer(kmer));
+
+        sketch.finalize();
+
+        sketch
+    }
+
+    /// Creates the sketch of all distinct k-mers of `sequence`, which is given in IO representation of `alphabet`.
+    ///
+    /// Panics if `k` or `scaled` is `0` or if `sequence` contains a symbol that is not part of `alphabet`.
+    pub fn from_sequence(sequence: &[u8], alphabet: &Alphabet, k: usize, scaled: u64) -> Self {
+        let mut sketch = Self::new_empty(k, scaled);
+
+        let dense_sequence: Vec<_> = sequence
+            .iter()
+            .map(|&s| alphabet.io_to_dense_representation(s))
+            .collect();
+
+        for kmer in dense_sequence
+            .split(|&s| !alphabet.is_searchable(s))
+            .flat_map(|part| part.windows(k))
+        {
+            sketch.insert_dense_kmer(kmer);
+        }
+
+        sketch.finalize();
+
+        sketch
+    }
+
+    fn new_empty(k: usize, scaled: u64) -> Self {
+        assert!(k > 0, "k must be greater than 0");
+        assert!(scaled > 0, "scaled must be greater than 0");
+
+        Self {
+            k,
+            scaled,
+            hashes: Vec::new(),
+        }
+    }
+
+    fn insert_dense_kmer(&mut self, kmer: &[u8]) {
+        let hash = hash_kmer(kmer);
+
+        if hash <= u64::MAX / self.scaled {
+            self.hashes.push(hash);
+        }
+    }
+
+    fn finalize(&mut self) {
+        self.hashes.sort_unstable();
+        self.hashes.dedup();
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn scaled(&self) -> u64 {
+        self.scaled
+    }
+
+    /// The sorted hash values of the sketch.
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// An estimate of the number of distinct k-mers of the sketched sequence(s).
+    pub fn estimated_num_distinct_kmers(&self) -> u64 {
+        self.hashes.len() as u64 * self.scaled
+    }
+
+    /// Estimates the Jaccard similarity of the k-mer sets of the two sketched sequences.
+    ///
+    /// Returns `0.0` if both sketches are empty. Panics if the sketches were created using different
+    /// values for `k` or `scaled`.
+    pub fn jaccard(&self, other: &Self) -> f64 {
+        let intersection_size = self.intersection_size(other);
+        let union_size = self.hashes.len() + other.hashes.len() - intersection_size;
+
+        if union_size == 0 {
+            0.0
+        } else {
+            intersection_size as f64 / union_size as f64
+        }
+    }
+
+    /// Estimates the fraction of the k-mers of `self` that are contained in `other`.
+    ///
+    /// Returns `0.0` if `self` is empty. Panics if the sketches were created using different
+    /// values for `k` or `scaled`.
+    pub fn containment(&self, other: &Self) -> f64 {
+        let intersection_size = self.intersection_size(other);
+
+        if self.hashes.is_empty() {
+            0.0
+        } else {
+            intersection_size as f64 / self.hashes.len() as f64
+        }
+    }
+
+    /// The number of hash values that are contained in both sketches.
+    ///
+    /// Panics if the sketches were created using different values for `k` or `scaled`.
+    pub fn intersection_size(&self, other: &Self) -> usize {
+        assert!(
+            self.k == other.k && self.scaled == other.scaled,
+            "sketches must be created using the same parameters to be compared"
+        );
+
+        let mut self_iter = self.hashes.iter().peekable();
+        let mut other_iter = other.hashes.iter().peekable();
+        let mut intersection_size = 0;
+
+        while let (Some(&&a), Some(&&b)) = (self_iter.peek(), other_iter.peek()) {
+            if a <= b {
+                self_iter.next();
+            }
+
+            if b <= a {
+                other_iter.next();
+            }
+
+            if a == b {
+                intersection_size += 1;
+            }
+        }
+
+        intersection_size
+    }
+}
+
+// FNV-1a followed by the SplitMix64 finalizer to spread the bits over the whole range of u64
+fn hash_kmer(kmer: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    for &symbol in kmer {
+        hash ^= symbol as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+
+    use proptest::prelude::*;
+
+    #[test]
+    fn jaccard_and_containment() {
+        let alphabet = alphabet::ascii_dna();
+        let a = FracMinHashSketch::from_sequence(b"AAAACCCC", &alphabet, 4, 1);
+        let b = FracMinHashSketch::from_sequence(b"CCCCGGGG", &alphabet, 4, 1);
+
+        assert_eq!(a.len(), 5);
+        assert_eq!(a.intersection_size(&b), 1);
+        assert_eq!(a.jaccard(&b), 1.0 / 9.0);
+        assert_eq!(a.containment(&b), 1.0 / 5.0);
+        assert_eq!(a.jaccard(&a), 1.0);
+    }
+
+    #[test]
+    fn unsearchable_symbols_are_skipped() {
+        let alphabet = alphabet::ascii_dna_with_n();
+        let sketch = FracMinHashSketch::from_sequence(b"ACGNACG", &alphabet, 3, 1);
+
+        assert_eq!(sketch.len(), 1);
+    }
+
+    proptest! {
+        #[test]
+        fn index_and_sequence_sketches_agree(
+            texts in prop::collection::vec(
+                prop::collection::vec((0usize..5).prop_map(|i| b"ACGTN"[i]), 0..300),
+                1..4
+            ),
+            k in 1usize..8,
+            scaled in 1u64..4,
+        ) {
+            let alphabet = alphabet::ascii_dna_with_n();
+            let index = FmIndexConfig::<i32>::new().construct_index(&texts, alphabet.clone());
+
+            let index_sketch = FracMinHashSketch::from_index(&index, k, scaled);
+
+            let mut sequence_hashes: Vec<_> = texts
+                .iter()
+                .flat_map(|text| FracMinHashSketch::from_sequence(text, &alphabet, k, scaled).hashes)
+                .collect();
+            sequence_hashes.sort_unstable();
+            sequence_hashes.dedup();
+
+            prop_assert_eq!(index_sketch.hashes(), sequence_hashes.as_slice());
+        }
+    }
+}
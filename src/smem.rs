@@ -0,0 +1,219 @@
+use std::ops::Range;
+
+use crate::{FmdCursor, FmdIndex, IndexStorage, text_with_rank_support::TextWithRankSupport};
+
+/// A super-maximal exact match (SMEM) between a query and the texts of an [`FmdIndex`], found by
+/// [`FmdIndex::find_smems`].
+///
+/// An SMEM is an exact match that can't be extended in either direction and that is not contained in
+/// another such match. Occurrences on both strands are considered.
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Smem {
+    /// The matching part of the query.
+    pub query_range: Range<usize>,
+    /// The suffix array interval of the matching part of the query in the underlying FM-Index
+    /// (see [`FmdIndex::index`]).
+    pub sa_interval: Range<usize>,
+}
+
+impl Smem {
+    /// The number of occurrences on both strands of the indexed texts.
+    pub fn count(&self) -> usize {
+        self.sa_interval.len()
+    }
+}
+
+pub(crate) fn find_smems<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmdIndex<I, R>,
+    query: &[u8],
+    min_len: usize,
+) -> Vec<Smem> {
+    let alphabet = index.alphabet();
+
+    // symbols that are not searchable can't be part of a match
+    let dense_query: Vec<_> = query
+        .iter()
+        .map(|&symbol| {
+            alphabet
+                .try_io_to_dense_representation(symbol)
+                .filter(|&dense_symbol| alphabet.is_searchable(dense_symbol))
+        })
+        .collect();
+
+    let mut smems = Vec::new();
+    let mut position = 0;
+
+    while position < dense_query.len() {
+        position = smems_around_position(index, &dense_query, position, &mut smems);
+    }
+
+    smems.retain(|smem| smem.query_range.len() >= min_len);
+
+    smems
+}
+
+// The algorithm of the FMD-Index paper. Finds the SMEMs that contain `position` and returns the position
+// after the longest match starting at `position`, where the next search should start.
+fn smems_around_position<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmdIndex<I, R>,
+    dense_query: &[Option<u8>],
+    position: usize,
+    smems: &mut Vec<Smem>,
+) -> usize {
+    let Some(symbol) = dense_query[position] else {
+        return position + 1;
+    };
+
+    let mut cursor = index.cursor_empty();
+    cursor.extend_front_without_alphabet_translation(symbol);
+
+    if cursor.count() == 0 {
+        return position + 1;
+    }
+
+    // forward extension, keeping the matches at which the number of occurrences changes, with their end positions
+    let mut previous: Vec<(FmdCursor<I, R>, usize)> = Vec::new();
+    let mut end = position + 1;
+
+    for next_end in position + 1..=dense_query.len() {
+        let extended = dense_query.get(next_end).copied().flatten().map(|symbol| {
+            let mut extended = cursor;
+            extended.extend_back_without_alphabet_translation(symbol);
+            extended
+        });
+
+        match extended {
+            Some(extended) if extended.count() == cursor.count() => {}
+            Some(extended) if extended.count() > 0 => previous.push((cursor, end)),
+            _ => {
+                previous.push((cursor, end));
+                break;
+            }
+        }
+
+        // the loop always stops at the end of the query
+        cursor = extended.unwrap();
+        end = next_end + 1;
+    }
+
+    // longest matches first
+    previous.reverse();
+    let next_position = previous[0].1;
+
+    // backward extension of all candidates, a match is reported when it can't be extended and no longer match exists
+    let first_new_smem = smems.len();
+    let mut current = Vec::new();
+
+    for start in (0..=position).rev() {
+        let symbol = start.checked_sub(1).and_then(|i| dense_query[i]);
+
+        for &(candidate, candidate_end) in &previous {
+            let extended = symbol
+                .map(|symbol| {
+                    let mut extended = candidate;
+                    extended.extend_front_without_alphabet_translation(symbol);
+                    extended
+                })
+                .filter(|extended| extended.count() > 0);
+
+            match extended {
+                None => {
+                    let is_contained = smems[first_new_smem..]
+                        .last()
+                        .is_some_and(|last: &Smem| start >= last.query_range.start);
+
+                    if current.is_empty() && !is_contained {
+                        let interval = candidate.forward_cursor().interval();
+
+                        smems.push(Smem {
+                            query_range: start..candidate_end,
+                            sa_interval: interval.start..interval.end,
+                        });
+                    }
+                }
+                Some(extended) => {
+                    if current
+                        .last()
+                        .is_none_or(|(last, _): &(FmdCursor<I, R>, _)| {
+                            last.count() != extended.count()
+                        })
+                    {
+                        current.push((extended, candidate_end));
+                    }
+                }
+            }
+        }
+
+        if current.is_empty() {
+            break;
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+        current.clear();
+    }
+
+    // sorted by start position
+    smems[first_new_smem..].reverse();
+
+    next_position
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn smems_agree_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..40), 1..4),
+            query in prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 1..30),
+            min_len in 1usize..4,
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .construct_fmd_index(&texts, alphabet::ascii_dna_with_n());
+
+            let occurs = |range: std::ops::Range<usize>| {
+                !query[range.clone()].contains(&b'N') && index.count(&query[range]) > 0
+            };
+
+            let mut mems = Vec::new();
+            for start in 0..query.len() {
+                for end in start + 1..=query.len() {
+                    let is_mem = occurs(start..end)
+                        && (start == 0 || !occurs(start - 1..end))
+                        && (end == query.len() || !occurs(start..end + 1));
+
+                    if is_mem {
+                        mems.push(start..end);
+                    }
+                }
+            }
+
+            let expected: Vec<_> = mems
+                .iter()
+                .filter(|mem| {
+                    !mems.iter().any(|other| {
+                        other != *mem && other.start <= mem.start && mem.end <= other.end
+                    })
+                })
+                .filter(|mem| mem.len() >= min_len)
+                .cloned()
+                .collect();
+
+            let smems = index.find_smems(&query, min_len);
+            let ranges: Vec<_> = smems.iter().map(|smem| smem.query_range.clone()).collect();
+
+            prop_assert_eq!(ranges, expected);
+
+            for smem in &smems {
+                prop_assert_eq!(smem.count(), index.count(&query[smem.query_range.clone()]));
+                prop_assert_eq!(index.locate_smem(smem).count(), smem.count());
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{EmptyQueryMatches, Hit, random::SplitMix64};
+
+/// A naive text with rank support that stores the rank of every symbol at every position.
+///
+/// It answers the same queries as the implementations of
+/// [`TextWithRankSupport`](crate::text_with_rank_support::TextWithRankSupport), using a lot more memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NaiveTextWithRankSupport {
+    text: Vec<u8>,
+    occurrence_columns: Vec<Vec<usize>>,
+}
+
+impl NaiveTextWithRankSupport {
+    /// All symbols of the text must be smaller than `alphabet_size`.
+    pub fn construct(text: &[u8], alphabet_size: usize) -> Self {
+        assert!(
+            text.iter().all(|&symbol| (symbol as usize) < alphabet_size),
+            "all symbols of the text must be smaller than the alphabet size"
+        );
+
+        let occurrence_columns = (0..alphabet_size)
+            .map(|target_symbol| {
+                let mut column = Vec::with_capacity(text.len() + 1);
+                let mut count = 0;
+                column.push(count);
+
+                for &symbol in text {
+                    if symbol as usize == target_symbol {
+                        count += 1;
+                    }
+
+                    column.push(count);
+                }
+
+                column
+            })
+            .collect();
+
+        Self {
+            text: text.to_vec(),
+            occurrence_columns,
+        }
+    }
+
+    /// Returns the number of occurrences of `symbol` in `text[0..idx]`.
+    pub fn rank(&self, symbol: u8, idx: usize) -> usize {
+        self.occurrence_columns[symbol as usize][idx]
+    }
+
+    pub fn symbol_at(&self, idx: usize) -> u8 {
+        self.text[idx]
+    }
+
+    pub fn text_len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn alphabet_size(&self) -> usize {
+        self.occurrence_columns.len()
+    }
+}
+
+/// Returns all occurrences of `query` in `texts`, sorted by text id and position.
+///
+/// The symbols are compared byte by byte. Therefore, the results are only the same as for an index if every dense
+/// symbol of its alphabet has a single IO symbol and the query only contains searchable symbols. The empty query
+/// is handled according to `empty_query_matches`, like by an index that was constructed with this setting.
+pub fn naive_search<T: AsRef<[u8]>>(
+    texts: &[T],
+    query: &[u8],
+    empty_query_matches: EmptyQueryMatches,
+) -> Vec<Hit> {
+    let mut hits = Vec::new();
+
+    for (text_id, text) in texts.iter().enumerate() {
+        let text = text.as_ref();
+
+        if query.is_empty() {
+            let positions = match empty_query_matches {
+                EmptyQueryMatches::AllPositions => 0..text.len() + 1,
+                EmptyQueryMatches::TextPositions => 0..text.len(),
+                EmptyQueryMatches::NoPositions => 0..0,
+            };
+
+            hits.extend(positions.map(|position| Hit { text_id, position }));
+
+            continue;
+        }
+
+        for (position, window) in text.windows(query.len()).enumerate() {
+            if window == query {
+                hits.push(Hit { text_id, position });
+            }
+        }
+    }
+
+    hits
+}
+
+/// A generator of random texts and queries for tests.
+///
+/// The same seed always produces the same inputs, independent of the platform and the version of this library.
+/// In property tests, the seed can be generated by the testing framework.
+///
+/// ```
+/// use genedex::{EmptyQueryMatches, FmIndexConfig, alphabet, test_utils::{RandomInputs, naive_search}};
+///
+/// let mut inputs = RandomInputs::new(42);
+/// let texts = inputs.texts(b"ACGT", 5, 100);
+/// let index = FmIndexConfig::<i32>::new().construct_index(&texts, alphabet::ascii_dna());
+///
+/// for _ in 0..10 {
+///     let query = inputs.query(b"ACGT", 6);
+///
+///     let mut hits: Vec<_> = index.locate(&query).collect();
+///     hits.sort();
+///
+///     assert_eq!(hits, naive_search(&texts, &query, EmptyQueryMatches::AllPositions));
+/// }
+/// ```
+pub struct RandomInputs {
+    rng: SplitMix64,
+}
+
+impl RandomInputs {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: SplitMix64::new(seed),
+        }
+    }
+
+    /// Returns a text of length `len` with symbols that are drawn uniformly from `symbols`.
+    pub fn text(&mut self, symbols: &[u8], len: usize) -> Vec<u8> {
+        assert!(!symbols.is_empty(), "at least one symbol is needed");
+
+        (0..len)
+            .map(|_| symbols[self.rng.next_below(symbols.len())])
+            .collect()
+    }
+
+    /// Returns `num_texts` texts with lengths that are drawn uniformly from `0..max_len`.
+    pub fn texts(&mut self, symbols: &[u8], num_texts: usize, max_len: usize) -> Vec<Vec<u8>> {
+        (0..num_texts)
+            .map(|_| self.query(symbols, max_len))
+            .collect()
+    }
+
+    /// Returns a query with a length that is drawn uniformly from `0..max_len`. The query might not occur
+    /// in any text.
+    pub fn query(&mut self, symbols: &[u8], max_len: usize) -> Vec<u8> {
+        assert!(max_len > 0, "the maximum length must be greater than 0");
+
+        let len = self.rng.next_below(max_len);
+        self.text(symbols, len)
+    }
+
+    /// Returns a substring of a random text, together with the position where it was taken from. The substring
+    /// is shorter than `max_len`. `None` is returned if there are no texts or if the chosen text is empty.
+    pub fn sampled_query<'t, T: AsRef<[u8]>>(
+        &mut self,
+        texts: &'t [T],
+        max_len: usize,
+    ) -> Option<(Hit, &'t [u8])> {
+        assert!(max_len > 0, "the maximum length must be greater than 0");
+
+        if texts.is_empty() {
+            return None;
+        }
+
+        let text_id = self.rng.next_below(texts.len());
+        let text = texts[text_id].as_ref();
+
+        if text.is_empty() {
+            return None;
+        }
+
+        let position = self.rng.next_below(text.len());
+        let len = self.rng.next_below(max_len.min(text.len() - position + 1));
+
+        Some((Hit { text_id, position }, &text[position..position + len]))
+    }
+}
@@ -0,0 +1,138 @@
+use std::{collections::HashMap, ops::Deref};
+
+// The suffix array values of the rows of the BWT that contain a sentinel, i.e. the starts of the texts.
+//
+// It is a hash map, because it is accessed on the hot path of locating occurrences. The savefile representation
+// is the same as that of a plain HashMap, but the entries are written sorted by row. This way, the saved index
+// does not depend on the random state of the hash map or the order of insertion, and indices that were saved
+// before this type existed can still be loaded.
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TextBorderLookup<I>(HashMap<usize, I>);
+
+impl<I> From<HashMap<usize, I>> for TextBorderLookup<I> {
+    fn from(map: HashMap<usize, I>) -> Self {
+        Self(map)
+    }
+}
+
+impl<I> Deref for TextBorderLookup<I> {
+    type Target = HashMap<usize, I>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<I> TextBorderLookup<I> {
+    pub(crate) fn sorted_entries(&self) -> Vec<(&usize, &I)> {
+        let mut entries: Vec<_> = self.0.iter().collect();
+        entries.sort_unstable_by_key(|&(&row, _)| row);
+        entries
+    }
+}
+
+#[cfg(feature = "savefile")]
+mod savefile_impls {
+    use savefile::{
+        Deserialize, Deserializer, Introspect, IntrospectItem, Packed, SavefileError, Schema,
+        Serialize, Serializer, WithSchema, WithSchemaContext,
+    };
+    use std::{
+        collections::HashMap,
+        io::{Read, Write},
+    };
+
+    use super::TextBorderLookup;
+
+    impl<I: WithSchema> WithSchema for TextBorderLookup<I> {
+        fn schema(version: u32, context: &mut WithSchemaContext) -> Schema {
+            HashMap::<usize, I>::schema(version, context)
+        }
+    }
+
+    impl<I: Introspect> Introspect for TextBorderLookup<I> {
+        fn introspect_value(&self) -> String {
+            self.0.introspect_value()
+        }
+
+        fn introspect_child(&self, index: usize) -> Option<Box<dyn IntrospectItem<'_> + '_>> {
+            self.0.introspect_child(index)
+        }
+
+        fn introspect_len(&self) -> usize {
+            self.0.introspect_len()
+        }
+    }
+
+    impl<I> Packed for TextBorderLookup<I> {}
+
+    impl<I: Serialize> Serialize for TextBorderLookup<I> {
+        fn serialize(&self, serializer: &mut Serializer<impl Write>) -> Result<(), SavefileError> {
+            serializer.write_usize(self.0.len())?;
+
+            for (row, value) in self.sorted_entries() {
+                row.serialize(serializer)?;
+                value.serialize(serializer)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    impl<I: Deserialize> Deserialize for TextBorderLookup<I> {
+        fn deserialize(deserializer: &mut Deserializer<impl Read>) -> Result<Self, SavefileError> {
+            HashMap::deserialize(deserializer).map(Self)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "savefile"))]
+mod tests {
+    use super::*;
+    use savefile::{WithSchema, WithSchemaContext};
+
+    fn serialize<T: savefile::Savefile>(value: &T) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        savefile::Serializer::bare_serialize(&mut bytes, 0, value).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn sorted_layout() {
+        let entries = [(7, 3u32), (0, 12), (25, 0), (3, 9)];
+
+        let lookup: TextBorderLookup<u32> = HashMap::from(entries).into();
+        let reversed: TextBorderLookup<u32> =
+            entries.into_iter().rev().collect::<HashMap<_, _>>().into();
+
+        let bytes = serialize(&lookup);
+        assert_eq!(bytes, serialize(&reversed));
+
+        let mut sorted_entries = entries;
+        sorted_entries.sort();
+        let mut expected = 4u64.to_le_bytes().to_vec();
+
+        for (row, value) in sorted_entries {
+            expected.extend((row as u64).to_le_bytes());
+            expected.extend(value.to_le_bytes());
+        }
+
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn compatible_with_hash_map() {
+        assert_eq!(
+            TextBorderLookup::<i64>::schema(0, &mut WithSchemaContext::new()),
+            HashMap::<usize, i64>::schema(0, &mut WithSchemaContext::new())
+        );
+
+        let map = HashMap::from([(4, 1i64), (11, 0), (2, 6)]);
+        let bytes = serialize(&map);
+
+        let loaded: TextBorderLookup<i64> =
+            savefile::Deserializer::bare_deserialize(&mut bytes.as_slice(), 0).unwrap();
+        assert_eq!(*loaded, map);
+    }
+}
@@ -9,6 +9,10 @@ pub(crate) struct TexdIdSearchTree {
 }
 
 impl TexdIdSearchTree {
+    pub(crate) fn heap_size_bytes(&self) -> usize {
+        size_of_val(self.nodes.as_slice()) + size_of_val(self.sentinel_indices.as_slice())
+    }
+
     // indices assumed to be sorted
     pub(crate) fn new_from_sentinel_indices(sentinel_indices: Vec<usize>) -> Self {
         assert!(!sentinel_indices.is_empty());
@@ -37,14 +41,21 @@ impl TexdIdSearchTree {
         concatenated_text_index: usize,
     ) -> (usize, usize) {
         let text_id = self.lookup_text_id(concatenated_text_index);
+        let text_index = self.text_index(text_id, concatenated_text_index);
+
+        (text_id, text_index)
+    }
 
-        let text_index = if text_id == 0 {
+    pub(crate) fn text_index(&self, text_id: usize, concatenated_text_index: usize) -> usize {
+        if text_id == 0 {
             concatenated_text_index
         } else {
             concatenated_text_index - self.sentinel_indices[text_id - 1] - 1
-        };
+        }
+    }
 
-        (text_id, text_index)
+    pub(crate) fn text_len(&self, text_id: usize) -> usize {
+        self.text_index(text_id, self.sentinel_indices[text_id])
     }
 
     pub(crate) fn lookup_text_id(&self, concatenated_text_index: usize) -> usize {
@@ -0,0 +1,262 @@
+use crate::memory_region::MemoryRegion;
+
+use super::block::{Block, Block64, NUM_BLOCK_OFFSET_BITS};
+use super::little_endian_vec::LittleEndianVec;
+use super::prefetch::prefetch_element;
+
+/// A bitvector with rank and select support, using the same blocks as the texts with rank support.
+///
+/// This is the specialization of [`FlatTextWithRankSupport`](super::FlatTextWithRankSupport) to an alphabet of
+/// size 2, which only stores the blocks of the ones. It can be constructed by collecting an iterator of `bool`s.
+/// With [`Block64`], it uses about 1.33 bits per bit, with [`Block512`](super::Block512) about 1.03 bits per bit.
+///
+/// ```
+/// use genedex::text_with_rank_support::BitVectorWithRankSupport;
+///
+/// let bits: BitVectorWithRankSupport = [true, false, false, true, true].into_iter().collect();
+///
+/// assert_eq!(bits.len(), 5);
+/// assert!(bits.get(3));
+/// assert_eq!(bits.rank1(4), 2);
+/// assert_eq!(bits.rank0(4), 2);
+/// assert_eq!(bits.select1(2), Some(4));
+/// assert_eq!(bits.select0(2), None);
+/// ```
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "savefile", savefile_doc_hidden)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitVectorWithRankSupport<B = Block64> {
+    len: usize,
+    num_ones: usize,
+    blocks: LittleEndianVec<B>,
+    superblock_offsets: LittleEndianVec<u64>,
+}
+
+impl<B: Block> BitVectorWithRankSupport<B> {
+    const USED_BITS_PER_BLOCK: usize = B::NUM_BITS - NUM_BLOCK_OFFSET_BITS;
+
+    // the block offsets are relative to the superblock, so they must fit into the offset bits of the blocks
+    const SUPERBLOCK_SIZE: usize =
+        ((1 << NUM_BLOCK_OFFSET_BITS) / Self::USED_BITS_PER_BLOCK) * Self::USED_BITS_PER_BLOCK;
+
+    fn empty() -> Self {
+        Self {
+            len: 0,
+            num_ones: 0,
+            blocks: Vec::new().into(),
+            superblock_offsets: Vec::new().into(),
+        }
+    }
+
+    // there is always a block for the index len, such that rank queries at len don't need a special case
+    fn push_block_if_needed(&mut self) {
+        if !self.len.is_multiple_of(Self::USED_BITS_PER_BLOCK) {
+            return;
+        }
+
+        if self.len.is_multiple_of(Self::SUPERBLOCK_SIZE) {
+            self.superblock_offsets.push(self.num_ones as u64);
+        }
+
+        let superblock_offset = *self.superblock_offsets.last().unwrap() as usize;
+
+        let mut block = B::zeroes();
+        block.integrate_block_offset_assuming_zero((self.num_ones - superblock_offset) as u64);
+        self.blocks.push(block);
+    }
+
+    fn push(&mut self, bit: bool) {
+        if bit {
+            let idx_in_block = self.len % Self::USED_BITS_PER_BLOCK + NUM_BLOCK_OFFSET_BITS;
+            self.blocks
+                .last_mut()
+                .unwrap()
+                .set_bit_assuming_zero(idx_in_block, 1);
+            self.num_ones += 1;
+        }
+
+        self.len += 1;
+        self.push_block_if_needed();
+    }
+
+    /// Returns the number of bits.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the bitvector contains no bits.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the total number of ones.
+    pub fn num_ones(&self) -> usize {
+        self.num_ones
+    }
+
+    /// Returns the bit at `idx`. Panics if `idx` is not smaller than the length.
+    pub fn get(&self, idx: usize) -> bool {
+        assert!(idx < self.len, "index out of bounds");
+
+        let block = &self.blocks[idx / Self::USED_BITS_PER_BLOCK];
+
+        block.get_bit(idx % Self::USED_BITS_PER_BLOCK + NUM_BLOCK_OFFSET_BITS) == 1
+    }
+
+    /// Returns the number of ones in `bits[0..idx]`. Panics if `idx` is larger than the length.
+    ///
+    /// The running time is in O(1).
+    pub fn rank1(&self, idx: usize) -> usize {
+        assert!(idx <= self.len, "index out of bounds");
+
+        let superblock_offset = self.superblock_offsets[idx / Self::SUPERBLOCK_SIZE] as usize;
+
+        let mut block = self.blocks[idx / Self::USED_BITS_PER_BLOCK];
+        let block_offset = block.extract_block_offset_and_then_zeroize_it();
+        let block_count =
+            block.count_ones_before(idx % Self::USED_BITS_PER_BLOCK + NUM_BLOCK_OFFSET_BITS);
+
+        superblock_offset + block_offset + block_count
+    }
+
+    /// Returns the number of zeros in `bits[0..idx]`. Panics if `idx` is larger than the length.
+    ///
+    /// The running time is in O(1).
+    pub fn rank0(&self, idx: usize) -> usize {
+        idx - self.rank1(idx)
+    }
+
+    /// Returns the position of the one with the given zero-based rank, or `None` if there are not enough ones.
+    ///
+    /// The running time is in O(log n), where `n` is the length of the bitvector.
+    pub fn select1(&self, rank: usize) -> Option<usize> {
+        (rank < self.num_ones).then(|| self.select_with(rank, |idx| self.rank1(idx)))
+    }
+
+    /// Returns the position of the zero with the given zero-based rank, or `None` if there are not enough zeros.
+    ///
+    /// The running time is in O(log n), where `n` is the length of the bitvector.
+    pub fn select0(&self, rank: usize) -> Option<usize> {
+        (rank < self.len - self.num_ones).then(|| self.select_with(rank, |idx| self.rank0(idx)))
+    }
+
+    // the bits are given as words, with the first bit of a word in its lowest bit
+    pub(crate) fn from_words(words: &[u64], len: usize) -> Self {
+        (0..len)
+            .map(|idx| (words[idx / u64::BITS as usize] >> (idx % u64::BITS as usize)) & 1 == 1)
+            .collect()
+    }
+
+    // the number of bytes of a bitvector of the given length, without constructing it
+    pub(crate) fn size_in_bytes(len: usize) -> usize {
+        (len / Self::USED_BITS_PER_BLOCK + 1) * size_of::<B>()
+            + (len / Self::SUPERBLOCK_SIZE + 1) * size_of::<u64>()
+    }
+
+    pub(crate) fn prefetch_rank1(&self, idx: usize) {
+        prefetch_element(&self.superblock_offsets, idx / Self::SUPERBLOCK_SIZE);
+        prefetch_element(&self.blocks, idx / Self::USED_BITS_PER_BLOCK);
+    }
+
+    // the bit itself is stored in the same block that is used for its rank
+    pub(crate) fn prefetch_get(&self, idx: usize) {
+        prefetch_element(&self.blocks, idx / Self::USED_BITS_PER_BLOCK);
+    }
+
+    pub(crate) fn memory_regions(&self) -> [MemoryRegion; 2] {
+        [
+            MemoryRegion::of_slice(&self.blocks),
+            MemoryRegion::of_slice(&self.superblock_offsets),
+        ]
+    }
+
+    // binary search for the smallest position whose rank including itself is larger than the given rank
+    fn select_with(&self, rank: usize, rank_fn: impl Fn(usize) -> usize) -> usize {
+        let mut low = 0;
+        let mut high = self.len;
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+
+            if rank_fn(mid + 1) > rank {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+
+        low
+    }
+}
+
+impl<B: Block> FromIterator<bool> for BitVectorWithRankSupport<B> {
+    fn from_iter<T: IntoIterator<Item = bool>>(bits: T) -> Self {
+        let mut bit_vector = Self::empty();
+        bit_vector.push_block_if_needed();
+
+        for bit in bits {
+            bit_vector.push(bit);
+        }
+
+        bit_vector
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_with_rank_support::Block512;
+    use proptest::prelude::*;
+
+    fn check_against_naive<B: Block>(bits: &[bool]) {
+        let bit_vector: BitVectorWithRankSupport<B> = bits.iter().copied().collect();
+
+        assert_eq!(bit_vector.len(), bits.len());
+        assert_eq!(
+            bit_vector.num_ones(),
+            bits.iter().filter(|&&bit| bit).count()
+        );
+
+        let mut num_ones = 0;
+
+        for (idx, &bit) in bits.iter().enumerate() {
+            assert_eq!(bit_vector.get(idx), bit);
+            assert_eq!(bit_vector.rank1(idx), num_ones);
+            assert_eq!(bit_vector.rank0(idx), idx - num_ones);
+
+            if bit {
+                assert_eq!(bit_vector.select1(num_ones), Some(idx));
+            } else {
+                assert_eq!(bit_vector.select0(idx - num_ones), Some(idx));
+            }
+
+            num_ones += bit as usize;
+        }
+
+        assert_eq!(bit_vector.rank1(bits.len()), num_ones);
+        assert_eq!(bit_vector.select1(num_ones), None);
+        assert_eq!(bit_vector.select0(bits.len() - num_ones), None);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn agrees_with_naive(bits in prop::collection::vec(any::<bool>(), 0..2000)) {
+            check_against_naive::<Block64>(&bits);
+            check_against_naive::<Block512>(&bits);
+        }
+    }
+
+    // long enough for multiple superblocks
+    #[test]
+    fn multiple_superblocks() {
+        let bits: Vec<_> = (0..150_000usize)
+            .map(|idx| idx % 7 != 3 && idx % 1000 < 900)
+            .collect();
+
+        check_against_naive::<Block64>(&bits);
+        check_against_naive::<Block512>(&bits);
+    }
+}
@@ -1,3 +1,4 @@
+use super::simd;
 use crate::{maybe_mem_dbg::MaybeMemDbgCopy, maybe_savefile::MaybeSavefile, sealed};
 
 pub(crate) const NUM_BLOCK_OFFSET_BITS: usize = 16;
@@ -9,7 +10,11 @@ pub(crate) const NUM_BLOCK_OFFSET_BITS: usize = 16;
 /// Currently, this can either be [`Block64`] or [`Block512`], with [`Block64`] being the default.
 ///
 /// The larger blocks lead to higher running times of operations, but consume slightly less memory.
-/// On machines with AVX-512, the performance of [`Block512`] might be close to the one of [`Block64`].
+/// The operations on [`Block512`] use AVX-512 or AVX2 on x86_64 (detected at runtime) and NEON on aarch64.
+/// When the instruction sets are enabled at compile time, for example via `-C target-cpu=native`, the runtime
+/// detection is skipped and the operations can be inlined. On machines with AVX-512, the performance of
+/// [`Block512`] might be close to the one of [`Block64`]. The running times of the block configurations can be
+/// compared using the `block_benchmark` example.
 ///
 /// The difference in memory usage depends on the number of dense symbols of the alphabet used.
 /// For small alphabets like DNA alphabets, the difference in memory usage is almost irrelevant, so
@@ -25,6 +30,8 @@ pub trait Block:
     + MaybeMemDbgCopy
     + 'static
 {
+    #[doc(hidden)]
+    const NAME: &'static str;
     #[doc(hidden)]
     const NUM_BITS: usize;
     #[doc(hidden)]
@@ -62,7 +69,7 @@ pub trait Block:
 #[cfg_attr(feature = "mem_dbg", copy_type)]
 #[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
 #[cfg_attr(feature = "savefile", savefile_doc_hidden)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(align(64))]
 pub struct Block512 {
     data: [u64; 8],
@@ -75,6 +82,7 @@ impl MaybeMemDbgCopy for Block512 {}
 impl MaybeSavefile for Block512 {}
 
 impl Block for Block512 {
+    const NAME: &'static str = "Block512";
     const NUM_BITS: usize = 512;
 
     fn zeroes() -> Self {
@@ -90,9 +98,7 @@ impl Block for Block512 {
 
     #[doc(hidden)]
     fn set_to_self_and(&mut self, other: Self) {
-        for i in 0..8 {
-            self.data[i] &= other.data[i];
-        }
+        simd::and_assign(&mut self.data, &other.data);
     }
 
     fn get_bit(&self, idx: usize) -> u8 {
@@ -108,15 +114,7 @@ impl Block for Block512 {
     }
 
     fn count_ones_before(&self, idx: usize) -> usize {
-        let mut sum = 0;
-
-        let mask = BLOCK512_MASKS[idx];
-
-        for (data_part, mask_part) in self.data.iter().zip(mask) {
-            sum += (data_part & mask_part).count_ones();
-        }
-
-        sum as usize
+        simd::count_ones_masked(&self.data, &BLOCK512_MASKS[idx])
     }
 
     fn integrate_block_offset_assuming_zero(&mut self, block_offset: u64) {
@@ -138,7 +136,7 @@ impl Block for Block512 {
 #[cfg_attr(feature = "mem_dbg", copy_type)]
 #[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
 #[cfg_attr(feature = "savefile", savefile_doc_hidden)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Block64 {
     data: u64,
 }
@@ -150,6 +148,7 @@ impl MaybeMemDbgCopy for Block64 {}
 impl MaybeSavefile for Block64 {}
 
 impl Block for Block64 {
+    const NAME: &'static str = "Block64";
     const NUM_BITS: usize = 64;
 
     fn zeroes() -> Self {
@@ -191,6 +190,65 @@ impl Block for Block64 {
     }
 }
 
+/// The integer types used for the block offsets of [`CondensedTextWithRankSupport`](super::CondensedTextWithRankSupport).
+/// This trait should not and cannot be implemented by you.
+///
+/// The block offsets are relative to superblocks, whose size is determined by the largest value of the offset type.
+/// Currently, this can be [`u8`] (superblocks of 256 symbols), [`u16`] (65,536 symbols, the default) or, on 64-bit
+/// platforms, [`u32`] (2^32 symbols). Wider offsets need more memory per block, but fewer superblock offsets, which
+/// use the index storage type. For example, with `i32` index storage and [`Block64`], the offsets of [`u8`] and
+/// [`u16`] need about the same memory, and with `i64` the ones of [`u16`] need less. [`u32`] offsets practically
+/// don't need superblock offsets, so they keep the superblock offsets of very large texts in the cache.
+///
+/// The superblocks must not be smaller than the blocks, so [`u8`] can only be used with [`Block64`].
+pub trait BlockOffset:
+    sealed::Sealed
+    + std::fmt::Debug
+    + Clone
+    + Copy
+    + Default
+    + PartialEq
+    + Eq
+    + Send
+    + Sync
+    + MaybeSavefile
+    + MaybeMemDbgCopy
+    + 'static
+{
+    #[doc(hidden)]
+    const CONDENSED_NAME: &'static str;
+    #[doc(hidden)]
+    const SUPERBLOCK_SIZE: usize;
+
+    #[doc(hidden)]
+    fn wrapping_increment(self) -> Self;
+
+    #[doc(hidden)]
+    fn into_usize(self) -> usize;
+}
+
+macro_rules! impl_block_offset {
+    ($offset_type:ty, $condensed_name:literal) => {
+        impl BlockOffset for $offset_type {
+            const CONDENSED_NAME: &'static str = $condensed_name;
+            const SUPERBLOCK_SIZE: usize = <$offset_type>::MAX as usize + 1;
+
+            fn wrapping_increment(self) -> Self {
+                self.wrapping_add(1)
+            }
+
+            fn into_usize(self) -> usize {
+                self as usize
+            }
+        }
+    };
+}
+
+impl_block_offset!(u8, "CondensedTextWithRankSupport<u8>");
+impl_block_offset!(u16, "CondensedTextWithRankSupport");
+#[cfg(target_pointer_width = "64")]
+impl_block_offset!(u32, "CondensedTextWithRankSupport<u32>");
+
 static BLOCK512_MASKS: [[u64; 8]; 512] = const {
     let mut masks = [[0; 8]; 512];
 
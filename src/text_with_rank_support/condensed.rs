@@ -1,15 +1,26 @@
 use std::ops::Range;
 
 use crate::{
-    IndexStorage, TextWithRankSupport, batch_computed_cursors::Buffers,
-    construction::slice_compression::SliceCompression, maybe_mem_dbg::MaybeMemDbg,
-    maybe_savefile::MaybeSavefile, sealed::Sealed,
+    IndexStorage, TextWithRankSupport,
+    batch_computed_cursors::Buffers,
+    construction::slice_compression::{NoSliceCompression, SliceCompression},
+    maybe_mem_dbg::MaybeMemDbg,
+    maybe_savefile::MaybeSavefile,
+    memory_region::MemoryRegion,
+    sealed::Sealed,
 };
 
-use super::block::{Block, Block64};
+use super::{
+    TextBlocks, accumulate_superblock_offsets,
+    block::{Block, Block64, BlockOffset},
+    little_endian_vec::LittleEndianVec,
+    prefetch::prefetch_element,
+    verification::{RankSupportMismatch, check_alphabet_size, check_min_len, check_offsets},
+};
 
 use num_traits::{NumCast, PrimInt};
 use rayon::prelude::*;
+use std::io;
 
 // Interleaved means that the respective values for different symbols of the alphabet
 // for the same text position are next to each other.
@@ -17,22 +28,23 @@ use rayon::prelude::*;
 // (Super)block offsets are only interleaved for faster (parallel) construction.
 
 /// The more memory-efficient implementation of [`TextWithRankSupport`].
+///
+/// The width of the block offsets can be configured using the type parameter `O` (see [`BlockOffset`]).
 #[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
 #[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
 #[cfg_attr(feature = "savefile", savefile_doc_hidden)]
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct CondensedTextWithRankSupport<I, B = Block64> {
+pub struct CondensedTextWithRankSupport<I, B = Block64, O = u16> {
     text_len: usize,
     alphabet_size: usize,
-    interleaved_blocks: Vec<B>,
-    interleaved_block_offsets: Vec<u16>,
-    interleaved_superblock_offsets: Vec<I>,
+    interleaved_blocks: LittleEndianVec<B>,
+    interleaved_block_offsets: LittleEndianVec<O>,
+    interleaved_superblock_offsets: LittleEndianVec<I>,
 }
 
-impl<I: IndexStorage, B: Block> CondensedTextWithRankSupport<I, B> {
+impl<I: IndexStorage, B: Block, O: BlockOffset> CondensedTextWithRankSupport<I, B, O> {
     fn superblock_offset_idx(&self, symbol: u8, idx: usize) -> usize {
-        let superblock_size = u16::MAX as usize + 1;
-        (idx / superblock_size) * self.alphabet_size + symbol as usize
+        (idx / O::SUPERBLOCK_SIZE) * self.alphabet_size + symbol as usize
     }
 
     fn block_offset_idx(&self, symbol: u8, idx: usize) -> usize {
@@ -45,47 +57,108 @@ impl<I: IndexStorage, B: Block> CondensedTextWithRankSupport<I, B> {
         let interleaved_blocks_end = interleaved_blocks_start + alphabet_num_bits;
         interleaved_blocks_start..interleaved_blocks_end
     }
-}
 
-impl<I: IndexStorage, B: Block> MaybeMemDbg for CondensedTextWithRankSupport<I, B> {}
+    // returns the sum of the superblock and block offsets and the block in which the bits of symbol are set
+    // SAFETY: idx must be at most text.len() and symbol must be smaller than the alphabet size
+    unsafe fn offset_and_symbol_block(&self, mut symbol: u8, idx: usize) -> (usize, B) {
+        // SAFETY: all of the index accesses are in the valid range if idx is at most text.len()
+        // and since the alphabet has a size of at least 2
+        let superblock_offset_idx = self.superblock_offset_idx(symbol, idx);
 
-impl<I: IndexStorage, B: Block> MaybeSavefile for CondensedTextWithRankSupport<I, B> {}
+        let superblock_offset = unsafe {
+            *self
+                .interleaved_superblock_offsets
+                .get_unchecked(superblock_offset_idx)
+        };
 
-impl<I: IndexStorage, B: Block> Sealed for CondensedTextWithRankSupport<I, B> {}
+        // SAFETY: must succeed, otherwise the construction function would have crashed
+        let superblock_offset =
+            unsafe { <usize as NumCast>::from(superblock_offset).unwrap_unchecked() };
 
-impl<I: IndexStorage, B: Block> super::PrivateTextWithRankSupport<I>
-    for CondensedTextWithRankSupport<I, B>
-{
-    fn construct_from_maybe_slice_compressed_text<S: SliceCompression>(
-        text: &[u8],
-        uncompressed_text_len: usize,
-        alphabet_size: usize,
-    ) -> Self {
+        let block_offset_idx = self.block_offset_idx(symbol, idx);
+        let block_offset = unsafe {
+            *self
+                .interleaved_block_offsets
+                .get_unchecked(block_offset_idx)
+        }
+        .into_usize();
+
+        let block_range = self.block_range(idx);
+
+        let interleaved_blocks = unsafe { self.interleaved_blocks.get_unchecked(block_range) };
+
+        // SAFETY: there must be at least one block, because the alphabet size is at least 2
+        let (first_block, other_blocks) =
+            unsafe { interleaved_blocks.split_first().unwrap_unchecked() };
+
+        let mut accumulator_block = *first_block;
+
+        if symbol & 1 == 0 {
+            accumulator_block.negate();
+        }
+
+        for mut block in other_blocks.iter().copied() {
+            symbol >>= 1;
+
+            if symbol & 1 == 0 {
+                block.negate();
+            }
+
+            accumulator_block.set_to_self_and(block);
+        }
+
+        (superblock_offset + block_offset, accumulator_block)
+    }
+
+    // len is the number of bits of the indicator bit vectors, which is one larger than the text
+    fn zeroed(len: usize, uncompressed_text_len: usize, alphabet_size: usize) -> Self {
         assert!(alphabet_size >= 2);
+        const {
+            assert!(
+                O::SUPERBLOCK_SIZE >= B::NUM_BITS,
+                "the superblocks must not be smaller than the blocks"
+            )
+        };
 
         let alphabet_num_bits = ilog2_ceil_for_nonzero(alphabet_size);
-
-        // we might be storing one character b'1' to many if the text is half byte compressed and had odd length.
-        let len: usize = S::transformed_slice_len(text) + 1;
-        let superblock_size = u16::MAX as usize + 1;
+        let superblock_size = O::SUPERBLOCK_SIZE;
 
         let num_indicator_blocks = len.div_ceil(B::NUM_BITS) * alphabet_num_bits;
         let num_block_offsets = len.div_ceil(B::NUM_BITS) * alphabet_size;
         let num_superblock_offsets = len.div_ceil(superblock_size) * alphabet_size;
 
-        let mut interleaved_blocks = vec![B::zeroes(); num_indicator_blocks];
-        let mut interleaved_block_offsets = vec![0; num_block_offsets];
-        let mut interleaved_superblock_offsets = vec![I::zero(); num_superblock_offsets];
+        Self {
+            text_len: uncompressed_text_len,
+            alphabet_size,
+            interleaved_blocks: vec![B::zeroes(); num_indicator_blocks].into(),
+            interleaved_block_offsets: vec![O::default(); num_block_offsets].into(),
+            interleaved_superblock_offsets: vec![I::zero(); num_superblock_offsets].into(),
+        }
+    }
+
+    // fills the superblocks starting at first_superblock with the text, which starts at the border of this superblock.
+    // the superblock offsets are the counts of the symbols in the superblocks afterwards.
+    fn fill_superblocks<S: SliceCompression>(&mut self, first_superblock: usize, text: &[u8]) {
+        let alphabet_size = self.alphabet_size;
+        let alphabet_num_bits = ilog2_ceil_for_nonzero(alphabet_size);
+        let superblock_size = O::SUPERBLOCK_SIZE;
 
         let num_blocks_per_superblock = (superblock_size / B::NUM_BITS) * alphabet_num_bits;
-        let blocks_per_superblock_iter =
-            interleaved_blocks.par_chunks_mut(num_blocks_per_superblock);
+        let blocks_per_superblock_iter = self
+            .interleaved_blocks
+            .par_chunks_mut(num_blocks_per_superblock)
+            .skip(first_superblock);
 
         let num_block_offsets_per_superblock = (superblock_size / B::NUM_BITS) * alphabet_size;
-        let block_offsets_per_superblock_iter =
-            interleaved_block_offsets.par_chunks_mut(num_block_offsets_per_superblock);
+        let block_offsets_per_superblock_iter = self
+            .interleaved_block_offsets
+            .par_chunks_mut(num_block_offsets_per_superblock)
+            .skip(first_superblock);
 
-        let superblock_offsets_iter = interleaved_superblock_offsets.par_chunks_mut(alphabet_size);
+        let superblock_offsets_iter = self
+            .interleaved_superblock_offsets
+            .par_chunks_mut(alphabet_size)
+            .skip(first_superblock);
 
         let text_chunk_size = S::transform_chunk_size(superblock_size);
         let text_superblock_iter = text.par_chunks(text_chunk_size);
@@ -98,29 +171,133 @@ impl<I: IndexStorage, B: Block> super::PrivateTextWithRankSupport<I>
         )
             .into_par_iter();
 
-        interleaved_superblock_iter
-            .for_each(|tup| fill_superblock::<I, B, S>(tup.0, tup.1, tup.2, tup.3, alphabet_size));
+        interleaved_superblock_iter.for_each(|tup| {
+            let mut block_offsets_sum = vec![O::default(); alphabet_size];
+
+            fill_superblock::<I, B, O, S>(
+                tup.0,
+                tup.1,
+                tup.2,
+                tup.3,
+                &mut block_offsets_sum,
+                alphabet_size,
+            )
+        });
+    }
 
-        // accumulate superblocks in single thread
-        let mut temp_offsets = vec![I::zero(); alphabet_size];
-        let mut sum_of_previous = vec![I::zero(); alphabet_size];
+    // fills the part of a superblock that starts at start with the uncompressed text. start must be a multiple
+    // of the block size and the part must not cross the border of the superblock. block_offsets_sum contains
+    // the counts of the symbols in the superblock before the part.
+    fn fill_superblock_part(&mut self, start: usize, text: &[u8], block_offsets_sum: &mut [O]) {
+        let alphabet_size = self.alphabet_size;
+        let alphabet_num_bits = ilog2_ceil_for_nonzero(alphabet_size);
 
-        for superblock_offsets in interleaved_superblock_offsets.chunks_mut(alphabet_size) {
-            temp_offsets.copy_from_slice(superblock_offsets);
-            superblock_offsets.copy_from_slice(&sum_of_previous);
+        let superblock_idx = start / O::SUPERBLOCK_SIZE;
+        let superblock_end_block = (superblock_idx + 1) * (O::SUPERBLOCK_SIZE / B::NUM_BITS);
+        let num_blocks = self.interleaved_block_offsets.len() / alphabet_size;
 
-            for (sum, temp) in sum_of_previous.iter_mut().zip(&temp_offsets) {
-                *sum = *sum + *temp;
-            }
-        }
+        // the last part also gets the additional block at the end of the text, if it belongs to the superblock
+        let first_block = start / B::NUM_BITS;
+        let end_block = if start + text.len() == self.text_len {
+            num_blocks.min(superblock_end_block)
+        } else {
+            (start + text.len()) / B::NUM_BITS
+        };
 
-        Self {
-            text_len: uncompressed_text_len,
+        fill_superblock::<I, B, O, NoSliceCompression>(
+            text,
+            &mut self.interleaved_superblock_offsets
+                [superblock_idx * alphabet_size..(superblock_idx + 1) * alphabet_size],
+            &mut self.interleaved_block_offsets
+                [first_block * alphabet_size..end_block * alphabet_size],
+            &mut self.interleaved_blocks
+                [first_block * alphabet_num_bits..end_block * alphabet_num_bits],
+            block_offsets_sum,
             alphabet_size,
-            interleaved_blocks,
-            interleaved_block_offsets,
-            interleaved_superblock_offsets,
+        );
+    }
+}
+
+impl<I: IndexStorage, B: Block, O: BlockOffset> MaybeMemDbg
+    for CondensedTextWithRankSupport<I, B, O>
+{
+}
+
+impl<I: IndexStorage, B: Block, O: BlockOffset> MaybeSavefile
+    for CondensedTextWithRankSupport<I, B, O>
+{
+}
+
+impl<I: IndexStorage, B: Block, O: BlockOffset> Sealed for CondensedTextWithRankSupport<I, B, O> {}
+
+impl<I: IndexStorage, B: Block, O: BlockOffset> super::PrivateTextWithRankSupport<I>
+    for CondensedTextWithRankSupport<I, B, O>
+{
+    const NAME: &'static str = O::CONDENSED_NAME;
+
+    const BLOCK_NAME: &'static str = B::NAME;
+
+    fn construct_from_maybe_slice_compressed_text<S: SliceCompression>(
+        text: &[u8],
+        uncompressed_text_len: usize,
+        alphabet_size: usize,
+    ) -> Self {
+        // we might be storing one character b'1' to many if the text is half byte compressed and had odd length.
+        let len: usize = S::transformed_slice_len(text) + 1;
+
+        let mut ranks = Self::zeroed(len, uncompressed_text_len, alphabet_size);
+        ranks.fill_superblocks::<S>(0, text);
+        accumulate_superblock_offsets(&mut ranks.interleaved_superblock_offsets, alphabet_size);
+
+        ranks
+    }
+
+    fn construct_from_text_blocks(
+        text: &impl TextBlocks,
+        alphabet_size: usize,
+    ) -> io::Result<Self> {
+        let mut ranks = Self::zeroed(text.len() + 1, text.len(), alphabet_size);
+        let superblock_size = O::SUPERBLOCK_SIZE;
+
+        if superblock_size <= text.max_block_len() {
+            text.try_for_each_block(
+                text.block_len_multiple_of(superblock_size),
+                |start, block| {
+                    ranks.fill_superblocks::<NoSliceCompression>(start / superblock_size, block)
+                },
+            )?;
+        } else {
+            // the superblocks are filled in parts. the length of the parts is a power of two, such that they
+            // don't cross superblock borders.
+            let block_len = (1 << text.max_block_len().ilog2()).max(B::NUM_BITS);
+            let mut block_offsets_sum = vec![O::default(); alphabet_size];
+
+            text.try_for_each_block(block_len, |start, block| {
+                if start.is_multiple_of(superblock_size) {
+                    block_offsets_sum.fill(O::default());
+                }
+
+                ranks.fill_superblock_part(start, block, &mut block_offsets_sum);
+            })?;
         }
+
+        accumulate_superblock_offsets(&mut ranks.interleaved_superblock_offsets, alphabet_size);
+
+        Ok(ranks)
+    }
+
+    fn estimated_size_in_bytes(text_len: usize, alphabet_size: usize) -> usize {
+        let alphabet_num_bits = ilog2_ceil_for_nonzero(alphabet_size);
+        let len = text_len + 1;
+        let superblock_size = O::SUPERBLOCK_SIZE;
+
+        let num_indicator_blocks = len.div_ceil(B::NUM_BITS) * alphabet_num_bits;
+        let num_block_offsets = len.div_ceil(B::NUM_BITS) * alphabet_size;
+        let num_superblock_offsets = len.div_ceil(superblock_size) * alphabet_size;
+
+        num_indicator_blocks * size_of::<B>()
+            + num_block_offsets * size_of::<O>()
+            + num_superblock_offsets * size_of::<I>()
     }
 
     fn _alphabet_size(&self) -> usize {
@@ -131,6 +308,81 @@ impl<I: IndexStorage, B: Block> super::PrivateTextWithRankSupport<I>
         self.text_len
     }
 
+    fn memory_regions(&self) -> Vec<MemoryRegion> {
+        vec![
+            MemoryRegion::of_slice(&self.interleaved_blocks),
+            MemoryRegion::of_slice(&self.interleaved_block_offsets),
+            MemoryRegion::of_slice(&self.interleaved_superblock_offsets),
+        ]
+    }
+
+    fn check_structure(&self) -> Result<(), RankSupportMismatch> {
+        check_alphabet_size(self.alphabet_size, u8::MAX as usize + 1)?;
+
+        // rank queries at text_len are allowed, so there is always one more block than needed for the text
+        let num_blocks = self.text_len / B::NUM_BITS + 1;
+
+        check_min_len(
+            "blocks",
+            self.interleaved_blocks.len(),
+            num_blocks.saturating_mul(ilog2_ceil_for_nonzero(self.alphabet_size)),
+        )?;
+        check_min_len(
+            "block offsets",
+            self.interleaved_block_offsets.len(),
+            num_blocks.saturating_mul(self.alphabet_size),
+        )?;
+        check_min_len(
+            "superblock offsets",
+            self.interleaved_superblock_offsets.len(),
+            (self.text_len / O::SUPERBLOCK_SIZE + 1).saturating_mul(self.alphabet_size),
+        )?;
+        check_offsets("superblock offsets", &self.interleaved_superblock_offsets)
+    }
+
+    // the offsets of all symbols are next to each other and all symbols share the same indicator blocks,
+    // so everything is loaded only once
+    fn rank_all_into(&self, idx: usize, ranks: &mut [usize]) {
+        assert!(idx <= self.text_len && ranks.len() == self.alphabet_size);
+
+        let superblock_offsets_start = self.superblock_offset_idx(0, idx);
+        let superblock_offsets = &self.interleaved_superblock_offsets
+            [superblock_offsets_start..superblock_offsets_start + self.alphabet_size];
+
+        let block_offsets_start = self.block_offset_idx(0, idx);
+        let block_offsets = &self.interleaved_block_offsets
+            [block_offsets_start..block_offsets_start + self.alphabet_size];
+
+        let interleaved_blocks = &self.interleaved_blocks[self.block_range(idx)];
+        let index_in_block = idx % B::NUM_BITS;
+
+        for (symbol, ((rank, &superblock_offset), &block_offset)) in ranks
+            .iter_mut()
+            .zip(superblock_offsets)
+            .zip(block_offsets)
+            .enumerate()
+        {
+            let mut accumulator_block = B::zeroes();
+            accumulator_block.negate();
+
+            for (bit_idx, &block) in interleaved_blocks.iter().enumerate() {
+                let mut block = block;
+
+                if (symbol >> bit_idx) & 1 == 0 {
+                    block.negate();
+                }
+
+                accumulator_block.set_to_self_and(block);
+            }
+
+            let block_count = accumulator_block.count_ones_before(index_in_block);
+
+            *rank = <usize as NumCast>::from(superblock_offset).unwrap()
+                + block_offset.into_usize()
+                + block_count;
+        }
+    }
+
     // TODO: maybe refactor this to get rid of all of the doubling for start and end of intervals
     // this functions essentially does the same thing as Self::rank_unchecked for all of the
     // intervals border in the buffers struct
@@ -193,13 +445,15 @@ impl<I: IndexStorage, B: Block> super::PrivateTextWithRankSupport<I>
                 *self
                     .interleaved_block_offsets
                     .get_unchecked(block_offsets_starts[i])
-            } as usize;
+            }
+            .into_usize();
 
             block_offsets_ends[i] = unsafe {
                 *self
                     .interleaved_block_offsets
                     .get_unchecked(block_offsets_ends[i])
-            } as usize;
+            }
+            .into_usize();
         }
 
         let mut block_slices_starts: [Option<&[B]>; N] = [None; N];
@@ -287,57 +541,69 @@ impl<I: IndexStorage, B: Block> super::PrivateTextWithRankSupport<I>
     }
 }
 
-impl<I: IndexStorage, B: Block> TextWithRankSupport<I> for CondensedTextWithRankSupport<I, B> {
-    unsafe fn rank_unchecked(&self, mut symbol: u8, idx: usize) -> usize {
-        // SAFETY: all of the index accesses are in the valid range if idx is at most text.len()
-        // and since the alphabet has a size of at least 2
-        let superblock_offset_idx = self.superblock_offset_idx(symbol, idx);
-
-        let superblock_offset = unsafe {
-            *self
-                .interleaved_superblock_offsets
-                .get_unchecked(superblock_offset_idx)
-        };
-
-        // SAFETY: must succeed, otherwise the construction function would have crashed
-        let superblock_offset =
-            unsafe { <usize as NumCast>::from(superblock_offset).unwrap_unchecked() };
-
-        let block_offset_idx = self.block_offset_idx(symbol, idx);
-        let block_offset = unsafe {
-            *self
-                .interleaved_block_offsets
-                .get_unchecked(block_offset_idx)
-        } as usize;
+impl<I: IndexStorage, B: Block, O: BlockOffset> TextWithRankSupport<I>
+    for CondensedTextWithRankSupport<I, B, O>
+{
+    unsafe fn rank_unchecked(&self, symbol: u8, idx: usize) -> usize {
+        // SAFETY: same requirements as this function
+        let (offset, block) = unsafe { self.offset_and_symbol_block(symbol, idx) };
 
-        let block_range = self.block_range(idx);
+        offset + block.count_ones_before(idx % B::NUM_BITS)
+    }
 
-        let interleaved_blocks = unsafe { self.interleaved_blocks.get_unchecked(block_range) };
+    unsafe fn rank_two_unchecked(&self, symbol: u8, start: usize, end: usize) -> (usize, usize) {
+        if start / B::NUM_BITS != end / B::NUM_BITS {
+            // SAFETY: same requirements as this function
+            return unsafe {
+                (
+                    self.rank_unchecked(symbol, start),
+                    self.rank_unchecked(symbol, end),
+                )
+            };
+        }
 
-        // SAFETY: there must be at least one block, because the alphabet size is at least 2
-        let (first_block, other_blocks) =
-            unsafe { interleaved_blocks.split_first().unwrap_unchecked() };
+        // the superblock size is a multiple of the block size, so both indices share all offsets and blocks
+        // SAFETY: same requirements as this function
+        let (offset, block) = unsafe { self.offset_and_symbol_block(symbol, start) };
 
-        let mut accumulator_block = *first_block;
+        (
+            offset + block.count_ones_before(start % B::NUM_BITS),
+            offset + block.count_ones_before(end % B::NUM_BITS),
+        )
+    }
 
-        if symbol & 1 == 0 {
-            accumulator_block.negate();
+    fn prefetch_rank(&self, symbol: u8, idx: usize) {
+        if symbol as usize >= self.alphabet_size || idx > self.text_len {
+            return;
         }
 
-        for mut block in other_blocks.iter().copied() {
-            symbol >>= 1;
+        prefetch_element(
+            &self.interleaved_superblock_offsets,
+            self.superblock_offset_idx(symbol, idx),
+        );
+        prefetch_element(
+            &self.interleaved_block_offsets,
+            self.block_offset_idx(symbol, idx),
+        );
 
-            if symbol & 1 == 0 {
-                block.negate();
-            }
+        // the blocks of all bits of the symbol are next to each other, so they span at most a few cache lines
+        let block_range = self.block_range(idx);
+        prefetch_element(&self.interleaved_blocks, block_range.start);
+        prefetch_element(&self.interleaved_blocks, block_range.end - 1);
+    }
 
-            accumulator_block.set_to_self_and(block);
-        }
+    fn symbols_at_many(&self, indices: &[usize], symbols: &mut [u8]) {
+        assert_eq!(indices.len(), symbols.len());
 
-        let index_in_block = idx % B::NUM_BITS;
-        let block_count = accumulator_block.count_ones_before(index_in_block);
+        for &idx in indices {
+            let block_range = self.block_range(idx);
+            prefetch_element(&self.interleaved_blocks, block_range.start);
+            prefetch_element(&self.interleaved_blocks, block_range.end - 1);
+        }
 
-        superblock_offset + block_offset + block_count
+        for (symbol, &idx) in symbols.iter_mut().zip(indices) {
+            *symbol = self.symbol_at(idx);
+        }
     }
 
     fn symbol_at(&self, idx: usize) -> u8 {
@@ -362,15 +628,15 @@ impl<I: IndexStorage, B: Block> TextWithRankSupport<I> for CondensedTextWithRank
     }
 }
 
-fn fill_superblock<I: PrimInt, B: Block, S: SliceCompression>(
+fn fill_superblock<I: PrimInt, B: Block, O: BlockOffset, S: SliceCompression>(
     text: &[u8],
     interleaved_superblock_offsets: &mut [I],
-    interleaved_block_offsets: &mut [u16],
+    interleaved_block_offsets: &mut [O],
     interleaved_blocks: &mut [B],
+    block_offsets_sum: &mut [O],
     alphabet_size: usize,
 ) {
     let alphabet_num_bits = ilog2_ceil_for_nonzero(alphabet_size);
-    let mut block_offsets_sum = vec![0; alphabet_size];
 
     let text_chunk_size = S::transform_chunk_size(B::NUM_BITS);
     let text_block_iter = text.chunks(text_chunk_size);
@@ -382,7 +648,7 @@ fn fill_superblock<I: PrimInt, B: Block, S: SliceCompression>(
     let block_package_iter = text_block_iter.zip(block_offsets_iter).zip(blocks_iter);
 
     for ((text_block, block_offsets), blocks) in block_package_iter {
-        block_offsets.copy_from_slice(&block_offsets_sum);
+        block_offsets.copy_from_slice(block_offsets_sum);
 
         for (index_in_block, mut symbol) in S::iter(text_block).enumerate() {
             let symbol_usize = <usize as NumCast>::from(symbol).unwrap();
@@ -393,7 +659,7 @@ fn fill_superblock<I: PrimInt, B: Block, S: SliceCompression>(
             // A wrapping add here is necessary for the case where a superblock consists exclusively of a single symbol.
             // The wrapping only makes sure that no panic happens, the value does not make it to the FM-Index anyways,
             // because it can only happen in the last iteration.
-            block_offsets_sum[symbol_usize] = block_offsets_sum[symbol_usize].wrapping_add(1);
+            block_offsets_sum[symbol_usize] = block_offsets_sum[symbol_usize].wrapping_increment();
 
             for block in blocks.iter_mut() {
                 block.set_bit_assuming_zero(index_in_block, symbol & 1);
@@ -410,7 +676,7 @@ fn fill_superblock<I: PrimInt, B: Block, S: SliceCompression>(
             .rchunks_mut(alphabet_size)
             .next()
             .unwrap()
-            .copy_from_slice(&block_offsets_sum);
+            .copy_from_slice(block_offsets_sum);
     }
 }
 
@@ -1,15 +1,20 @@
 use crate::IndexStorage;
 use crate::batch_computed_cursors::Buffers;
-use crate::construction::slice_compression::SliceCompression;
+use crate::construction::slice_compression::{NoSliceCompression, SliceCompression};
 use crate::maybe_mem_dbg::MaybeMemDbg;
 use crate::maybe_savefile::MaybeSavefile;
+use crate::memory_region::MemoryRegion;
 use crate::sealed::Sealed;
 
-use super::TextWithRankSupport;
 use super::block::{Block, Block64, NUM_BLOCK_OFFSET_BITS};
+use super::little_endian_vec::LittleEndianVec;
+use super::prefetch::prefetch_element;
+use super::verification::{RankSupportMismatch, check_alphabet_size, check_min_len, check_offsets};
+use super::{TextBlocks, TextWithRankSupport, accumulate_superblock_offsets};
 
 use num_traits::{NumCast, PrimInt};
 use rayon::prelude::*;
+use std::io;
 
 // Interleaved means that the respective values for different symbols of the alphabet
 // for the same text position are next to each other.
@@ -28,8 +33,8 @@ pub struct FlatTextWithRankSupport<I, B = Block64> {
     text_len: usize,
     alphabet_size: usize,
     superblock_size: usize,
-    interleaved_blocks: Vec<B>,
-    interleaved_superblock_offsets: Vec<I>,
+    interleaved_blocks: LittleEndianVec<B>,
+    interleaved_superblock_offsets: LittleEndianVec<I>,
 }
 
 impl<I: IndexStorage, B: Block> FlatTextWithRankSupport<I, B> {
@@ -48,26 +53,11 @@ impl<I: IndexStorage, B: Block> FlatTextWithRankSupport<I, B> {
         let used_bits_per_block = B::NUM_BITS - NUM_BLOCK_OFFSET_BITS;
         idx % used_bits_per_block
     }
-}
-
-impl<I: IndexStorage, B: Block> MaybeMemDbg for FlatTextWithRankSupport<I, B> {}
-
-impl<I: IndexStorage, B: Block> MaybeSavefile for FlatTextWithRankSupport<I, B> {}
-
-impl<I: IndexStorage, B: Block> Sealed for FlatTextWithRankSupport<I, B> {}
 
-impl<I: IndexStorage, B: Block> super::PrivateTextWithRankSupport<I>
-    for FlatTextWithRankSupport<I, B>
-{
-    fn construct_from_maybe_slice_compressed_text<S: SliceCompression>(
-        text: &[u8],
-        uncompressed_text_len: usize,
-        alphabet_size: usize,
-    ) -> Self {
+    // len is the number of bits of the indicator bit vectors, which is one larger than the text
+    fn zeroed(len: usize, uncompressed_text_len: usize, alphabet_size: usize) -> Self {
         assert!(alphabet_size >= 2);
 
-        // we might be storing one character b'1' to many if the text is half byte compressed and had odd length.
-        let len: usize = S::transformed_slice_len(text) + 1;
         let used_bits_per_block = B::NUM_BITS - NUM_BLOCK_OFFSET_BITS;
 
         let max_superblock_size = 1 << NUM_BLOCK_OFFSET_BITS;
@@ -76,16 +66,34 @@ impl<I: IndexStorage, B: Block> super::PrivateTextWithRankSupport<I>
         let num_indicator_blocks = len.div_ceil(used_bits_per_block) * alphabet_size;
         let num_superblock_offsets = len.div_ceil(superblock_size) * alphabet_size;
 
-        let mut interleaved_blocks = vec![B::zeroes(); num_indicator_blocks];
-        let mut interleaved_superblock_offsets = vec![I::zero(); num_superblock_offsets];
+        Self {
+            text_len: uncompressed_text_len,
+            alphabet_size,
+            superblock_size,
+            interleaved_blocks: vec![B::zeroes(); num_indicator_blocks].into(),
+            interleaved_superblock_offsets: vec![I::zero(); num_superblock_offsets].into(),
+        }
+    }
+
+    // fills the superblocks starting at first_superblock with the text, which starts at the border of this superblock.
+    // the superblock offsets are the counts of the symbols in the superblocks afterwards.
+    fn fill_superblocks<S: SliceCompression>(&mut self, first_superblock: usize, text: &[u8]) {
+        let alphabet_size = self.alphabet_size;
+        let used_bits_per_block = B::NUM_BITS - NUM_BLOCK_OFFSET_BITS;
 
-        let num_blocks_per_superblock = (superblock_size / used_bits_per_block) * alphabet_size;
-        let blocks_per_superblock_iter =
-            interleaved_blocks.par_chunks_mut(num_blocks_per_superblock);
+        let num_blocks_per_superblock =
+            (self.superblock_size / used_bits_per_block) * alphabet_size;
+        let blocks_per_superblock_iter = self
+            .interleaved_blocks
+            .par_chunks_mut(num_blocks_per_superblock)
+            .skip(first_superblock);
 
-        let superblock_offsets_iter = interleaved_superblock_offsets.par_chunks_mut(alphabet_size);
+        let superblock_offsets_iter = self
+            .interleaved_superblock_offsets
+            .par_chunks_mut(alphabet_size)
+            .skip(first_superblock);
 
-        let text_chunk_size = S::transform_chunk_size(superblock_size);
+        let text_chunk_size = S::transform_chunk_size(self.superblock_size);
 
         let text_superblock_iter = text.par_chunks(text_chunk_size);
 
@@ -98,27 +106,67 @@ impl<I: IndexStorage, B: Block> super::PrivateTextWithRankSupport<I>
 
         interleaved_superblock_iter
             .for_each(|tup| fill_superblock::<I, B, S>(tup.0, tup.1, tup.2, alphabet_size));
+    }
+}
+
+impl<I: IndexStorage, B: Block> MaybeMemDbg for FlatTextWithRankSupport<I, B> {}
 
-        // accumulate superblocks in single thread
-        let mut temp_offsets = vec![I::zero(); alphabet_size];
-        let mut sum_of_previous = vec![I::zero(); alphabet_size];
+impl<I: IndexStorage, B: Block> MaybeSavefile for FlatTextWithRankSupport<I, B> {}
 
-        for superblock_offsets in interleaved_superblock_offsets.chunks_mut(alphabet_size) {
-            temp_offsets.copy_from_slice(superblock_offsets);
-            superblock_offsets.copy_from_slice(&sum_of_previous);
+impl<I: IndexStorage, B: Block> Sealed for FlatTextWithRankSupport<I, B> {}
 
-            for (sum, temp) in sum_of_previous.iter_mut().zip(&temp_offsets) {
-                *sum = *sum + *temp;
-            }
-        }
+impl<I: IndexStorage, B: Block> super::PrivateTextWithRankSupport<I>
+    for FlatTextWithRankSupport<I, B>
+{
+    const NAME: &'static str = "FlatTextWithRankSupport";
 
-        Self {
-            text_len: uncompressed_text_len,
-            alphabet_size,
-            superblock_size,
-            interleaved_blocks,
-            interleaved_superblock_offsets,
-        }
+    const BLOCK_NAME: &'static str = B::NAME;
+
+    fn construct_from_maybe_slice_compressed_text<S: SliceCompression>(
+        text: &[u8],
+        uncompressed_text_len: usize,
+        alphabet_size: usize,
+    ) -> Self {
+        // we might be storing one character b'1' to many if the text is half byte compressed and had odd length.
+        let len: usize = S::transformed_slice_len(text) + 1;
+
+        let mut ranks = Self::zeroed(len, uncompressed_text_len, alphabet_size);
+        ranks.fill_superblocks::<S>(0, text);
+        accumulate_superblock_offsets(&mut ranks.interleaved_superblock_offsets, alphabet_size);
+
+        ranks
+    }
+
+    fn construct_from_text_blocks(
+        text: &impl TextBlocks,
+        alphabet_size: usize,
+    ) -> io::Result<Self> {
+        let mut ranks = Self::zeroed(text.len() + 1, text.len(), alphabet_size);
+        let superblock_size = ranks.superblock_size;
+
+        text.try_for_each_block(
+            text.block_len_multiple_of(superblock_size),
+            |start, block| {
+                ranks.fill_superblocks::<NoSliceCompression>(start / superblock_size, block)
+            },
+        )?;
+
+        accumulate_superblock_offsets(&mut ranks.interleaved_superblock_offsets, alphabet_size);
+
+        Ok(ranks)
+    }
+
+    fn estimated_size_in_bytes(text_len: usize, alphabet_size: usize) -> usize {
+        let len = text_len + 1;
+        let used_bits_per_block = B::NUM_BITS - NUM_BLOCK_OFFSET_BITS;
+
+        let max_superblock_size = 1 << NUM_BLOCK_OFFSET_BITS;
+        let superblock_size = (max_superblock_size / used_bits_per_block) * used_bits_per_block;
+
+        let num_indicator_blocks = len.div_ceil(used_bits_per_block) * alphabet_size;
+        let num_superblock_offsets = len.div_ceil(superblock_size) * alphabet_size;
+
+        num_indicator_blocks * size_of::<B>() + num_superblock_offsets * size_of::<I>()
     }
 
     fn _alphabet_size(&self) -> usize {
@@ -129,6 +177,65 @@ impl<I: IndexStorage, B: Block> super::PrivateTextWithRankSupport<I>
         self.text_len
     }
 
+    fn memory_regions(&self) -> Vec<MemoryRegion> {
+        vec![
+            MemoryRegion::of_slice(&self.interleaved_blocks),
+            MemoryRegion::of_slice(&self.interleaved_superblock_offsets),
+        ]
+    }
+
+    fn check_structure(&self) -> Result<(), RankSupportMismatch> {
+        check_alphabet_size(self.alphabet_size, u8::MAX as usize + 1)?;
+
+        let used_bits_per_block = B::NUM_BITS - NUM_BLOCK_OFFSET_BITS;
+        let max_superblock_size = 1 << NUM_BLOCK_OFFSET_BITS;
+
+        if self.superblock_size != (max_superblock_size / used_bits_per_block) * used_bits_per_block
+        {
+            return Err(RankSupportMismatch::Structure {
+                component: "superblock size",
+            });
+        }
+
+        // rank queries at text_len are allowed, so there is always one more block than needed for the text
+        check_min_len(
+            "blocks",
+            self.interleaved_blocks.len(),
+            (self.text_len / used_bits_per_block + 1).saturating_mul(self.alphabet_size),
+        )?;
+        check_min_len(
+            "superblock offsets",
+            self.interleaved_superblock_offsets.len(),
+            (self.text_len / self.superblock_size + 1).saturating_mul(self.alphabet_size),
+        )?;
+        check_offsets("superblock offsets", &self.interleaved_superblock_offsets)
+    }
+
+    // the superblock offsets and blocks of all symbols are next to each other, so they are loaded together
+    fn rank_all_into(&self, idx: usize, ranks: &mut [usize]) {
+        assert!(idx <= self.text_len && ranks.len() == self.alphabet_size);
+
+        let superblock_offsets_start = self.superblock_offset_idx(0, idx);
+        let superblock_offsets = &self.interleaved_superblock_offsets
+            [superblock_offsets_start..superblock_offsets_start + self.alphabet_size];
+
+        let blocks_start = self.block_idx(0, idx);
+        let blocks = &self.interleaved_blocks[blocks_start..blocks_start + self.alphabet_size];
+
+        let idx_in_block = Self::idx_in_block(idx);
+
+        for ((rank, &superblock_offset), &block) in
+            ranks.iter_mut().zip(superblock_offsets).zip(blocks)
+        {
+            let mut block = block;
+            let block_offset = block.extract_block_offset_and_then_zeroize_it();
+            let block_count = block.count_ones_before(idx_in_block + NUM_BLOCK_OFFSET_BITS);
+
+            *rank =
+                <usize as NumCast>::from(superblock_offset).unwrap() + block_offset + block_count;
+        }
+    }
+
     // TODO: maybe refactor this to get rid of all of the doubling for start and end of intervals
     // this functions essentially does the same thing as Self::rank_unchecked for all of the
     // intervals border in the buffers struct
@@ -245,6 +352,71 @@ impl<I: IndexStorage, B: Block> TextWithRankSupport<I> for FlatTextWithRankSuppo
         superblock_offset + block_offset + block_count
     }
 
+    unsafe fn rank_two_unchecked(&self, symbol: u8, start: usize, end: usize) -> (usize, usize) {
+        let block_idx = self.block_idx(symbol, start);
+
+        if block_idx != self.block_idx(symbol, end) {
+            // SAFETY: same requirements as this function
+            return unsafe {
+                (
+                    self.rank_unchecked(symbol, start),
+                    self.rank_unchecked(symbol, end),
+                )
+            };
+        }
+
+        // the superblock size is a multiple of the number of used bits per block,
+        // so both indices share the superblock offset and the block
+        // SAFETY: all of the index accesses are in the valid range if end is at most text.len()
+        let superblock_offset = unsafe {
+            *self
+                .interleaved_superblock_offsets
+                .get_unchecked(self.superblock_offset_idx(symbol, start))
+        };
+
+        // SAFETY: must succeed, otherwise the construction function would have crashed
+        let superblock_offset =
+            unsafe { <usize as NumCast>::from(superblock_offset).unwrap_unchecked() };
+
+        let mut block = unsafe { *self.interleaved_blocks.get_unchecked(block_idx) };
+        let offset = superblock_offset + block.extract_block_offset_and_then_zeroize_it();
+
+        (
+            offset + block.count_ones_before(Self::idx_in_block(start) + NUM_BLOCK_OFFSET_BITS),
+            offset + block.count_ones_before(Self::idx_in_block(end) + NUM_BLOCK_OFFSET_BITS),
+        )
+    }
+
+    fn prefetch_rank(&self, symbol: u8, idx: usize) {
+        if symbol as usize >= self.alphabet_size || idx > self.text_len {
+            return;
+        }
+
+        prefetch_element(
+            &self.interleaved_superblock_offsets,
+            self.superblock_offset_idx(symbol, idx),
+        );
+        prefetch_element(&self.interleaved_blocks, self.block_idx(symbol, idx));
+    }
+
+    // the blocks of all symbols at an index are next to each other, so they span at most a few cache lines
+    fn symbols_at_many(&self, indices: &[usize], symbols: &mut [u8]) {
+        assert_eq!(indices.len(), symbols.len());
+
+        for &idx in indices {
+            let blocks_start = self.block_idx(0, idx);
+            prefetch_element(&self.interleaved_blocks, blocks_start);
+            prefetch_element(
+                &self.interleaved_blocks,
+                blocks_start + self.alphabet_size - 1,
+            );
+        }
+
+        for (symbol, &idx) in symbols.iter_mut().zip(indices) {
+            *symbol = self.symbol_at(idx);
+        }
+    }
+
     fn symbol_at(&self, idx: usize) -> u8 {
         assert!(idx < self.text_len);
 
@@ -313,3 +485,41 @@ fn fill_superblock<I: PrimInt, B: Block, S: SliceCompression>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupted_buffers() {
+        let text = vec![1; 1000];
+        let mut ranks = FlatTextWithRankSupport::<i32>::construct(&text, 3);
+        assert_eq!(ranks.verify(&text), Ok(()));
+
+        ranks.interleaved_superblock_offsets[1] = -1;
+
+        assert_eq!(
+            ranks.verify(&text),
+            Err(RankSupportMismatch::Structure {
+                component: "superblock offsets"
+            })
+        );
+
+        // the ranks at the end of the text would be read from outside of the blocks
+        ranks.interleaved_blocks.truncate(3);
+
+        assert_eq!(
+            ranks.verify(&text),
+            Err(RankSupportMismatch::Structure {
+                component: "blocks"
+            })
+        );
+
+        ranks.alphabet_size = 1;
+
+        assert_eq!(
+            ranks.verify(&text[..0]),
+            Err(RankSupportMismatch::AlphabetSize { actual: 1 })
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::ops::{Deref, DerefMut};
+
+// A vector of blocks or offsets whose savefile representation does not depend on the platform.
+//
+// Savefile writes single integers in little-endian byte order, but copies vectors of "packed" types like u64
+// or the blocks as raw memory. This raw memory would be big-endian on big-endian platforms. Therefore, vectors
+// of this type are always serialized as their length, followed by every element in little-endian byte order
+// (the blocks as their u64 words, starting at the lowest bits). On little-endian platforms, this is exactly the
+// raw memory, so the fast path of savefile is used there and the format stays the same as for a plain Vec.
+// Alignment does not influence the format, because the elements are written without padding.
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LittleEndianVec<T>(Vec<T>);
+
+impl<T> From<Vec<T>> for LittleEndianVec<T> {
+    fn from(vec: Vec<T>) -> Self {
+        Self(vec)
+    }
+}
+
+impl<T> Deref for LittleEndianVec<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for LittleEndianVec<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "savefile")]
+mod savefile_impls {
+    use savefile::{
+        Deserialize, Deserializer, Introspect, IntrospectItem, Packed, SavefileError, Schema,
+        Serialize, Serializer, WithSchema, WithSchemaContext,
+    };
+    use std::io::{Read, Write};
+
+    use super::LittleEndianVec;
+
+    impl<T: WithSchema> WithSchema for LittleEndianVec<T> {
+        fn schema(version: u32, context: &mut WithSchemaContext) -> Schema {
+            Vec::<T>::schema(version, context)
+        }
+    }
+
+    impl<T: Introspect> Introspect for LittleEndianVec<T> {
+        fn introspect_value(&self) -> String {
+            self.0.introspect_value()
+        }
+
+        fn introspect_child(&self, index: usize) -> Option<Box<dyn IntrospectItem<'_> + '_>> {
+            self.0.introspect_child(index)
+        }
+
+        fn introspect_len(&self) -> usize {
+            self.0.introspect_len()
+        }
+    }
+
+    impl<T> Packed for LittleEndianVec<T> {}
+
+    impl<T: Serialize + Packed> Serialize for LittleEndianVec<T> {
+        fn serialize(&self, serializer: &mut Serializer<impl Write>) -> Result<(), SavefileError> {
+            if cfg!(target_endian = "little") {
+                return self.0.serialize(serializer);
+            }
+
+            serializer.write_usize(self.0.len())?;
+
+            for element in &self.0 {
+                element.serialize(serializer)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    impl<T: Deserialize + Packed> Deserialize for LittleEndianVec<T> {
+        fn deserialize(deserializer: &mut Deserializer<impl Read>) -> Result<Self, SavefileError> {
+            if cfg!(target_endian = "little") {
+                return Vec::deserialize(deserializer).map(Self);
+            }
+
+            let len = deserializer.read_usize()?;
+            let mut vec = Vec::with_capacity(len);
+
+            for _ in 0..len {
+                vec.push(T::deserialize(deserializer)?);
+            }
+
+            Ok(Self(vec))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "savefile"))]
+mod tests {
+    use super::*;
+    use crate::text_with_rank_support::{Block, Block64, Block512};
+
+    fn serialize<T: savefile::Savefile>(value: &T) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        savefile::Serializer::bare_serialize(&mut bytes, 0, value).unwrap();
+        bytes
+    }
+
+    fn block_with_offset<B: Block>(offset: u64) -> B {
+        let mut block = B::zeroes();
+        block.integrate_block_offset_assuming_zero(offset);
+        block.set_bit_assuming_zero(B::NUM_BITS - 1, 1);
+        block
+    }
+
+    // the length is the number of elements, not the number of words
+    fn expected_bytes(len: u64, words: &[u64]) -> Vec<u8> {
+        let mut bytes = len.to_le_bytes().to_vec();
+        bytes.extend(words.iter().flat_map(|word| word.to_le_bytes()));
+        bytes
+    }
+
+    #[test]
+    fn little_endian_layout() {
+        let offsets: LittleEndianVec<u64> = vec![1, 0x0102_0304_0506_0708].into();
+        let mut offset_bytes = 2u64.to_le_bytes().to_vec();
+        offset_bytes.extend([1, 0, 0, 0, 0, 0, 0, 0, 8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(serialize(&offsets), offset_bytes);
+
+        let small_offsets: LittleEndianVec<u16> = vec![0x0102].into();
+        assert_eq!(serialize(&small_offsets), [1, 0, 0, 0, 0, 0, 0, 0, 2, 1]);
+
+        let high_bit = 1 << 63;
+
+        let blocks64: LittleEndianVec<Block64> = vec![block_with_offset(3)].into();
+        assert_eq!(serialize(&blocks64), expected_bytes(1, &[3 | high_bit]));
+
+        let blocks512: LittleEndianVec<Block512> = vec![block_with_offset(3)].into();
+        assert_eq!(
+            serialize(&blocks512),
+            expected_bytes(1, &[3, 0, 0, 0, 0, 0, 0, high_bit])
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let offsets: LittleEndianVec<i64> = vec![-1, 0, i64::MAX].into();
+        let bytes = serialize(&offsets);
+
+        let loaded: LittleEndianVec<i64> =
+            savefile::Deserializer::bare_deserialize(&mut bytes.as_slice(), 0).unwrap();
+        assert_eq!(loaded, offsets);
+    }
+}
@@ -1,19 +1,34 @@
 use crate::{
     IndexStorage,
     batch_computed_cursors::Buffers,
-    construction::slice_compression::{NoSliceCompression, SliceCompression},
+    construction::slice_compression::{NoSliceCompression, SliceCompression, TwoBitsCompression},
     maybe_mem_dbg::MaybeMemDbg,
     maybe_savefile::MaybeSavefile,
+    memory_region::MemoryRegion,
     sealed::Sealed,
 };
 
+use num_traits::PrimInt;
+use std::io;
+
+mod bit_vector;
+
 /// The FM-Index and text with rank support data structures can be used with two different block configurations.
 mod block;
 mod condensed;
 mod flat;
+mod little_endian_vec;
+mod packed;
+mod prefetch;
+mod simd;
+mod verification;
+mod wavelet_tree;
 
 #[doc(inline)]
-pub use block::{Block, Block64, Block512};
+pub use bit_vector::BitVectorWithRankSupport;
+
+#[doc(inline)]
+pub use block::{Block, Block64, Block512, BlockOffset};
 
 #[doc(inline)]
 pub use condensed::CondensedTextWithRankSupport;
@@ -21,18 +36,59 @@ pub use condensed::CondensedTextWithRankSupport;
 #[doc(inline)]
 pub use flat::FlatTextWithRankSupport;
 
+#[doc(inline)]
+pub use packed::PackedTextWithRankSupport;
+
+#[doc(inline)]
+pub use verification::RankSupportMismatch;
+
+#[doc(inline)]
+pub use wavelet_tree::WaveletTreeTextWithRankSupport;
+
 // these specific optimizations are not something I want to expose to the public API, for now
 pub(crate) trait PrivateTextWithRankSupport<I: IndexStorage>: Sealed {
+    const NAME: &'static str;
+
+    const BLOCK_NAME: &'static str;
+
     fn construct_from_maybe_slice_compressed_text<S: SliceCompression>(
         text: &[u8],
         uncompressed_text_len: usize,
         alphabet_size: usize,
     ) -> Self;
 
+    // constructs the data structure from a text that is only available block by block, e.g. because it is read
+    // from a file. at most one block of the text is in memory at the same time.
+    fn construct_from_text_blocks(text: &impl TextBlocks, alphabet_size: usize) -> io::Result<Self>
+    where
+        Self: Sized;
+
+    // the number of bytes of the data structure for a text of the given length, without constructing it
+    fn estimated_size_in_bytes(text_len: usize, alphabet_size: usize) -> usize;
+
     fn _alphabet_size(&self) -> usize;
 
     fn _text_len(&self) -> usize;
 
+    fn memory_regions(&self) -> Vec<MemoryRegion>;
+
+    // checks that the buffers fit the text length and the alphabet size, such that all queries with valid
+    // arguments stay in bounds. only corrupted data structures, e.g. from damaged files, fail this check.
+    fn check_structure(&self) -> Result<(), RankSupportMismatch>;
+
+    // writes the ranks of all symbols at idx into ranks, such that the buffer can be reused by the caller
+    fn rank_all_into(&self, idx: usize, ranks: &mut [usize])
+    where
+        Self: TextWithRankSupport<I>,
+    {
+        assert!(idx <= self._text_len() && ranks.len() == self._alphabet_size());
+
+        for (symbol, rank) in ranks.iter_mut().enumerate() {
+            // SAFETY: checked above
+            *rank = unsafe { self.rank_unchecked(symbol as u8, idx) };
+        }
+    }
+
     fn replace_many_interval_borders_with_ranks<Q, const N: usize>(
         &self,
         buffers: &mut Buffers<Q, N>,
@@ -67,26 +123,97 @@ pub(crate) trait PrivateTextWithRankSupport<I: IndexStorage>: Sealed {
     );
 }
 
+// A text in dense representation that is read in consecutive blocks, such that it never has to be held in
+// memory completely.
+pub(crate) trait TextBlocks {
+    fn len(&self) -> usize;
+
+    // the number of symbols that should be in memory at the same time
+    fn max_block_len(&self) -> usize;
+
+    // calls f with the start position and the symbols of every block in order. all blocks have the given
+    // length, except for the last one, which can be shorter. the text can be read multiple times.
+    fn try_for_each_block(&self, block_len: usize, f: impl FnMut(usize, &[u8])) -> io::Result<()>;
+
+    // the largest multiple of unit that is at most the maximum block length, but at least unit
+    fn block_len_multiple_of(&self, unit: usize) -> usize {
+        self.max_block_len().max(unit) / unit * unit
+    }
+}
+
+// a text in memory that is read in small blocks, to test the construction from blocks
+#[cfg(test)]
+pub(crate) struct SliceTextBlocks<'a> {
+    pub(crate) text: &'a [u8],
+    pub(crate) max_block_len: usize,
+}
+
+#[cfg(test)]
+impl TextBlocks for SliceTextBlocks<'_> {
+    fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    fn max_block_len(&self) -> usize {
+        self.max_block_len
+    }
+
+    fn try_for_each_block(
+        &self,
+        block_len: usize,
+        mut f: impl FnMut(usize, &[u8]),
+    ) -> io::Result<()> {
+        for (block_idx, block) in self.text.chunks(block_len).enumerate() {
+            f(block_idx * block_len, block);
+        }
+
+        Ok(())
+    }
+}
+
+// the superblock offsets contain the counts of the symbols in their superblocks after filling the superblocks.
+// they are replaced by the counts of the symbols before the superblocks, in a single thread.
+fn accumulate_superblock_offsets<I: PrimInt>(
+    interleaved_superblock_offsets: &mut [I],
+    alphabet_size: usize,
+) {
+    let mut temp_offsets = vec![I::zero(); alphabet_size];
+    let mut sum_of_previous = vec![I::zero(); alphabet_size];
+
+    for superblock_offsets in interleaved_superblock_offsets.chunks_mut(alphabet_size) {
+        temp_offsets.copy_from_slice(superblock_offsets);
+        superblock_offsets.copy_from_slice(&sum_of_previous);
+
+        for (sum, temp) in sum_of_previous.iter_mut().zip(&temp_offsets) {
+            *sum = *sum + *temp;
+        }
+    }
+}
+
 /// A trait for data structures central to the FM-Index of this library. This trait should not and cannot be implemented by you.
 ///
 /// They can answer rank queries similar to the ones for bitvectors with rank support,
 /// but for a text with a given number of different symbols.
 ///
-/// Currently, two different implementations exist, [`CondensedTextWithRankSupport`] and
-/// [`FlatTextWithRankSupport`]. Both of them can also be used with different block sizes (more info [here](Block)).
+/// Currently, four different implementations exist, [`CondensedTextWithRankSupport`],
+/// [`FlatTextWithRankSupport`], [`PackedTextWithRankSupport`] and [`WaveletTreeTextWithRankSupport`]. The first
+/// two of them can also be used with different block sizes (more info [here](Block)).
 ///
 /// The condensed version is more space efficient, which is especially relevant for larger alphabets.
-/// The flat version is a bit faster, but has a higher memory usage.
+/// The flat version is a bit faster, but has a higher memory usage. The packed version stores the text itself
+/// and is only available for alphabets with at most 16 dense symbols. It trades speed for a small memory usage.
+/// The wavelet tree version compresses the text to about its zeroth order entropy and is meant for large
+/// alphabets, where the memory usage of the other versions grows with the number of symbols.
 ///
 /// In total, [`FlatTextWithRankSupport<Block64>`] is the fastest, and [`CondensedTextWithRankSupport<Block512>`]
-/// is the smallest configuration.
+/// is the smallest configuration of the interleaved versions.
 ///
 /// An example of how these data structures are used can be found
 /// [here](https://github.com/feldroop/genedex/blob/master/examples/text_with_rank_support.rs).
 // I don't want to make the slice compression API public
 #[allow(private_bounds)]
 pub trait TextWithRankSupport<I: IndexStorage>:
-    MaybeSavefile + MaybeMemDbg + PrivateTextWithRankSupport<I> + 'static
+    Sized + MaybeSavefile + MaybeMemDbg + PrivateTextWithRankSupport<I> + Send + Sync + 'static
 {
     /// Construct the data structure for the given text.
     ///
@@ -100,6 +227,92 @@ pub trait TextWithRankSupport<I: IndexStorage>:
         )
     }
 
+    /// Construct the data structure for a text whose symbols are packed into two bits each.
+    ///
+    /// Every byte of `packed_text` contains four symbols, the first one in its two highest bits. Only the first
+    /// `text_len` symbols are used, the remaining bits of the last byte are ignored. This is meant for texts
+    /// like genomes that are already stored in this form, such that they don't have to be unpacked first.
+    /// At most a quarter of the text length is copied.
+    ///
+    /// `alphabet_size` must be at most 4 and all symbols are assumed to be smaller than it. Panics if
+    /// `packed_text` is too short. The running time of this operation is linear in the text length.
+    fn construct_from_two_bit_packed(
+        packed_text: &[u8],
+        text_len: usize,
+        alphabet_size: usize,
+    ) -> Self {
+        assert!(
+            alphabet_size <= 4,
+            "two bit packed texts can only contain at most 4 dense symbols"
+        );
+
+        let packed_len = text_len.div_ceil(4);
+        assert!(
+            packed_text.len() >= packed_len,
+            "the packed text is too short"
+        );
+
+        let packed_text = &packed_text[..packed_len];
+
+        if text_len.is_multiple_of(4) {
+            return Self::construct_from_maybe_slice_compressed_text::<TwoBitsCompression>(
+                packed_text,
+                text_len,
+                alphabet_size,
+            );
+        }
+
+        // the padding symbols are counted by the construction, so they have to be valid symbols
+        let mut packed_text = packed_text.to_vec();
+
+        for idx in text_len..packed_len * 4 {
+            TwoBitsCompression::set(idx, &mut packed_text, 0);
+        }
+
+        Self::construct_from_maybe_slice_compressed_text::<TwoBitsCompression>(
+            &packed_text,
+            text_len,
+            alphabet_size,
+        )
+    }
+
+    /// Like [`construct_from_two_bit_packed`](Self::construct_from_two_bit_packed), but every word of
+    /// `packed_text` contains `usize::BITS / 2` symbols, the first one in its two highest bits.
+    ///
+    /// The words are converted into bytes before the construction, which copies a quarter of the text length.
+    fn construct_from_two_bit_packed_words(
+        packed_text: &[usize],
+        text_len: usize,
+        alphabet_size: usize,
+    ) -> Self {
+        assert!(
+            alphabet_size <= 4,
+            "two bit packed texts can only contain at most 4 dense symbols"
+        );
+
+        let packed_len = text_len.div_ceil(4);
+        assert!(
+            size_of_val(packed_text) >= packed_len,
+            "the packed text is too short"
+        );
+
+        let mut packed_bytes: Vec<_> = packed_text
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .take(packed_len)
+            .collect();
+
+        for idx in text_len..packed_len * 4 {
+            TwoBitsCompression::set(idx, &mut packed_bytes, 0);
+        }
+
+        Self::construct_from_maybe_slice_compressed_text::<TwoBitsCompression>(
+            &packed_bytes,
+            text_len,
+            alphabet_size,
+        )
+    }
+
     /// Returns the number of occurrences of `symbol` in `text[0..idx]`.
     ///
     /// The running time is in O(1).
@@ -118,11 +331,147 @@ pub trait TextWithRankSupport<I: IndexStorage>:
     /// `idx` must be in the interval `[0, text.len()]` and `symbol` must be smaller than alphabet size.
     unsafe fn rank_unchecked(&self, symbol: u8, idx: usize) -> usize;
 
+    /// Returns the ranks of `symbol` at `start` and at `end`, like two calls of [`rank`](Self::rank).
+    ///
+    /// This is the operation needed to update a suffix array interval in the backward search. When both indices
+    /// fall into the same block, the block is only decoded once. The running time is in O(1).
+    fn rank_two(&self, symbol: u8, start: usize, end: usize) -> (usize, usize) {
+        let is_safe =
+            (symbol as usize) < self.alphabet_size() && start <= end && end <= self.text_len();
+        assert!(is_safe);
+        unsafe { self.rank_two_unchecked(symbol, start, end) }
+    }
+
+    /// Version of [`rank_two`](Self::rank_two) without bounds checks.
+    ///
+    /// The running time is in O(1).
+    ///
+    /// # Safety
+    ///
+    /// `start` must not be greater than `end`, `end` must be in the interval `[0, text.len()]` and `symbol` must be
+    /// smaller than alphabet size.
+    unsafe fn rank_two_unchecked(&self, symbol: u8, start: usize, end: usize) -> (usize, usize) {
+        unsafe {
+            (
+                self.rank_unchecked(symbol, start),
+                self.rank_unchecked(symbol, end),
+            )
+        }
+    }
+
+    /// Issues software prefetches for the memory that [`rank`](Self::rank) of `symbol` at `idx` accesses, without
+    /// waiting for it to arrive.
+    ///
+    /// This allows batched search implementations to hide memory latency: first prefetch for all queries of a
+    /// batch, then compute the ranks, when the memory is hopefully already in the cache. The prefetches are only
+    /// hints and don't change any results, so invalid arguments are ignored instead of causing a panic. For the
+    /// wavelet tree, only the memory of the first level is prefetched, because the later levels depend on it.
+    /// On platforms other than x86_64 and aarch64, this does nothing.
+    ///
+    /// ```
+    /// use genedex::text_with_rank_support::{FlatTextWithRankSupport, TextWithRankSupport};
+    ///
+    /// let text = [0, 1, 1, 0, 2, 1];
+    /// let rank_support = FlatTextWithRankSupport::<i32>::construct(&text, 3);
+    ///
+    /// let queries = [(1, 3), (0, 6), (2, 5)];
+    ///
+    /// for &(symbol, idx) in &queries {
+    ///     rank_support.prefetch_rank(symbol, idx);
+    /// }
+    ///
+    /// let ranks: Vec<_> = queries
+    ///     .iter()
+    ///     .map(|&(symbol, idx)| rank_support.rank(symbol, idx))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranks, [2, 2, 1]);
+    /// ```
+    fn prefetch_rank(&self, symbol: u8, idx: usize);
+
     /// Recoveres the symbol of the text at given index `idx`.
     ///
     /// The running time is in O(1).
     fn symbol_at(&self, idx: usize) -> u8;
 
+    /// Recovers the symbols of the text at all `indices` and writes them into `symbols`.
+    ///
+    /// The lookups of different indices don't depend on each other. The memory of all indices is prefetched before
+    /// the first lookup, such that the memory accesses are executed in parallel by the CPU. This is useful for algorithms that follow many independent walks through the text,
+    /// like the recovery of suffix array values. Panics if the slices have different lengths.
+    ///
+    /// The running time is in O(1) per index.
+    fn symbols_at_many(&self, indices: &[usize], symbols: &mut [u8]) {
+        assert_eq!(indices.len(), symbols.len());
+
+        for (symbol, &idx) in symbols.iter_mut().zip(indices) {
+            *symbol = self.symbol_at(idx);
+        }
+    }
+
+    /// Returns the number of occurrences of every symbol in `text[0..idx]`, indexed by symbol.
+    ///
+    /// This is faster than calling [`rank`](Self::rank) for every symbol, because the data of all symbols at the
+    /// same text position is stored next to each other and loaded only once. It is useful for algorithms that
+    /// branch into every symbol, like the enumeration of all children of a [`Cursor`](crate::Cursor).
+    ///
+    /// The running time is in O(alphabet size).
+    fn rank_all(&self, idx: usize) -> impl AsRef<[usize]> {
+        let mut ranks = vec![0; self.alphabet_size()];
+        self.rank_all_into(idx, &mut ranks);
+        ranks
+    }
+
+    /// Returns the number of bytes that the data structure uses on the heap.
+    ///
+    /// This is available without the `mem_dbg` feature. Unused capacity of the internal buffers is not counted.
+    ///
+    /// ```
+    /// use genedex::text_with_rank_support::{FlatTextWithRankSupport, TextWithRankSupport};
+    ///
+    /// let text = vec![0; 100_000];
+    /// let rank_support = FlatTextWithRankSupport::<i32>::construct(&text, 4);
+    ///
+    /// assert!(rank_support.heap_size_bytes() > text.len() * 4 / 8);
+    /// ```
+    fn heap_size_bytes(&self) -> usize {
+        self.memory_regions().iter().map(MemoryRegion::len).sum()
+    }
+
+    /// Checks that the data structure represents `text`, by recomputing all ranks and symbols naively.
+    ///
+    /// Returns the first difference in the order of the text positions. This is meant for debugging and for
+    /// detecting corrupted files after deserialization, when the original text is still available. Panics if
+    /// `text` contains symbols that are not smaller than the alphabet size.
+    ///
+    /// Before any rank is computed, the lengths of the internal buffers are checked against the text length and the
+    /// alphabet size of the data structure. If they don't fit, [`RankSupportMismatch::AlphabetSize`] or
+    /// [`RankSupportMismatch::Structure`] is returned, because the queries would access memory out of bounds.
+    ///
+    /// The running time is in O(n · σ), where `n` is the length of the text and `σ` is the alphabet size.
+    ///
+    /// ```
+    /// use genedex::text_with_rank_support::{
+    ///     FlatTextWithRankSupport, RankSupportMismatch, TextWithRankSupport,
+    /// };
+    ///
+    /// let text = [0, 1, 1, 0, 2, 1];
+    /// let rank_support = FlatTextWithRankSupport::<i32>::construct(&text, 3);
+    ///
+    /// assert_eq!(rank_support.verify(&text), Ok(()));
+    /// assert_eq!(
+    ///     rank_support.verify(&[0, 1, 2, 0, 2, 1]),
+    ///     Err(RankSupportMismatch::SymbolAt {
+    ///         idx: 2,
+    ///         expected: 2,
+    ///         actual: 1
+    ///     })
+    /// );
+    /// ```
+    fn verify(&self, text: &[u8]) -> Result<(), RankSupportMismatch> {
+        verification::verify(self, text)
+    }
+
     fn text_len(&self) -> usize {
         self._text_len()
     }
@@ -142,7 +491,9 @@ mod tests {
             HalfBytesCompression, NoSliceCompression, half_byte_compress_text,
         },
         text_with_rank_support::{
-            CondensedTextWithRankSupport, FlatTextWithRankSupport, TextWithRankSupport,
+            Block64, Block512, CondensedTextWithRankSupport, FlatTextWithRankSupport,
+            PackedTextWithRankSupport, SliceTextBlocks, TextWithRankSupport,
+            WaveletTreeTextWithRankSupport,
         },
     };
     use proptest::prelude::*;
@@ -224,6 +575,65 @@ mod tests {
         }
     }
 
+    fn test_rank_all_same_as_rank<R: TextWithRankSupport<u32>>(text: &[u8], alphabet_size: usize) {
+        let ranks = R::construct(text, alphabet_size);
+
+        for idx in 0..=text.len() {
+            let all_ranks = ranks.rank_all(idx);
+            assert_eq!(all_ranks.as_ref().len(), alphabet_size);
+
+            for (symbol, &rank) in all_ranks.as_ref().iter().enumerate() {
+                assert_eq!(rank, ranks.rank(symbol as u8, idx));
+            }
+        }
+    }
+
+    fn test_construction_from_text_blocks<R: TextWithRankSupport<u32>>(alphabet_size: usize) {
+        // the lengths around the borders of blocks and superblocks of the different implementations
+        for text_len in [
+            0,
+            1,
+            255,
+            256,
+            511,
+            512,
+            65535,
+            65536,
+            65537,
+            3 * 65536 + 100,
+        ] {
+            let text: Vec<_> = (0..text_len)
+                .map(|_| rand::random_range(0..alphabet_size as u8))
+                .collect();
+
+            for max_block_len in [1000, 1 << 16, 1 << 20] {
+                let text_blocks = SliceTextBlocks {
+                    text: &text,
+                    max_block_len,
+                };
+                let ranks = R::construct_from_text_blocks(&text_blocks, alphabet_size).unwrap();
+
+                assert_eq!(ranks.text_len(), text_len);
+                assert_eq!(
+                    ranks.verify(&text),
+                    Ok(()),
+                    "max_block_len: {max_block_len}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn construction_from_text_blocks() {
+        test_construction_from_text_blocks::<FlatTextWithRankSupport<u32>>(5);
+        test_construction_from_text_blocks::<FlatTextWithRankSupport<u32, Block512>>(5);
+        test_construction_from_text_blocks::<CondensedTextWithRankSupport<u32>>(5);
+        test_construction_from_text_blocks::<CondensedTextWithRankSupport<u32, Block64, u8>>(5);
+        test_construction_from_text_blocks::<CondensedTextWithRankSupport<u32, Block512, u32>>(5);
+        test_construction_from_text_blocks::<PackedTextWithRankSupport<u32>>(5);
+        test_construction_from_text_blocks::<WaveletTreeTextWithRankSupport<u32>>(5);
+    }
+
     proptest! {
         // default is 256 and I'd like some more test cases that need to pass
         #![proptest_config(ProptestConfig::with_cases(2048))]
@@ -236,12 +646,33 @@ mod tests {
 
             test_with_and_without_half_byte_compression::<FlatTextWithRankSupport<u32>>(&text, compressed);
             test_with_and_without_half_byte_compression::<CondensedTextWithRankSupport<u32>>(&text, compressed);
+            test_with_and_without_half_byte_compression::<CondensedTextWithRankSupport<u32, Block64, u8>>(&text, compressed);
+            test_with_and_without_half_byte_compression::<PackedTextWithRankSupport<u32>>(&text, compressed);
+            test_with_and_without_half_byte_compression::<WaveletTreeTextWithRankSupport<u32>>(&text, compressed);
         }
 
         #[test]
         fn replace_many_intervals_same_as_rank((text, alphabet_size) in text_and_alphabet_size()) {
             test_replace_many_intervals_same_as_rank::<FlatTextWithRankSupport<u32>>(&text, alphabet_size);
             test_replace_many_intervals_same_as_rank::<CondensedTextWithRankSupport<u32>>(&text, alphabet_size);
+            test_replace_many_intervals_same_as_rank::<CondensedTextWithRankSupport<u32, Block64, u8>>(&text, alphabet_size);
+            test_replace_many_intervals_same_as_rank::<WaveletTreeTextWithRankSupport<u32>>(&text, alphabet_size);
+
+            if alphabet_size <= 16 {
+                test_replace_many_intervals_same_as_rank::<PackedTextWithRankSupport<u32>>(&text, alphabet_size);
+            }
+        }
+
+        #[test]
+        fn rank_all_same_as_rank((text, alphabet_size) in text_and_alphabet_size()) {
+            test_rank_all_same_as_rank::<FlatTextWithRankSupport<u32>>(&text, alphabet_size);
+            test_rank_all_same_as_rank::<CondensedTextWithRankSupport<u32>>(&text, alphabet_size);
+            test_rank_all_same_as_rank::<CondensedTextWithRankSupport<u32, Block64, u8>>(&text, alphabet_size);
+            test_rank_all_same_as_rank::<WaveletTreeTextWithRankSupport<u32>>(&text, alphabet_size);
+
+            if alphabet_size <= 16 {
+                test_rank_all_same_as_rank::<PackedTextWithRankSupport<u32>>(&text, alphabet_size);
+            }
         }
     }
 }
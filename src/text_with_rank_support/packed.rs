@@ -0,0 +1,458 @@
+use crate::IndexStorage;
+use crate::batch_computed_cursors::Buffers;
+use crate::construction::slice_compression::{NoSliceCompression, SliceCompression};
+use crate::maybe_mem_dbg::MaybeMemDbg;
+use crate::maybe_savefile::MaybeSavefile;
+use crate::memory_region::MemoryRegion;
+use crate::sealed::Sealed;
+
+use super::little_endian_vec::LittleEndianVec;
+use super::prefetch::prefetch_element;
+use super::verification::{RankSupportMismatch, check_alphabet_size, check_min_len, check_offsets};
+use super::{TextBlocks, TextWithRankSupport, accumulate_superblock_offsets};
+
+use num_traits::{NumCast, PrimInt};
+use rayon::prelude::*;
+use std::io;
+
+// The text is stored as half bytes, 16 symbols per u64 word. The first symbol of a word is stored
+// in its lowest 4 bits. The rank inside of a block is computed by counting the matching half bytes
+// of the words directly, without ever decompressing the text.
+
+// Block offsets are stored relative to the start of the superblock.
+// (Super)block offsets are interleaved like in the other implementations.
+
+const SYMBOLS_PER_WORD: usize = 16;
+const SYMBOLS_PER_BLOCK: usize = 256;
+const WORDS_PER_BLOCK: usize = SYMBOLS_PER_BLOCK / SYMBOLS_PER_WORD;
+const SUPERBLOCK_SIZE: usize = u16::MAX as usize + 1;
+const BLOCKS_PER_SUPERBLOCK: usize = SUPERBLOCK_SIZE / SYMBOLS_PER_BLOCK;
+const WORDS_PER_SUPERBLOCK: usize = SUPERBLOCK_SIZE / SYMBOLS_PER_WORD;
+
+const LOWEST_BIT_OF_EVERY_HALF_BYTE: u64 = 0x1111_1111_1111_1111;
+
+/// An implementation of [`TextWithRankSupport`] that keeps the text in half byte packed form.
+///
+/// It can only be used for alphabets with at most 16 dense symbols. It uses about half a byte per symbol
+/// plus `2 * alphabet_size` bytes for every 256 symbols. This is less than
+/// [`FlatTextWithRankSupport`](super::FlatTextWithRankSupport) for all such alphabets
+/// and less than [`CondensedTextWithRankSupport`](super::CondensedTextWithRankSupport) with
+/// [`Block64`](super::Block64) for alphabets with more than 8 symbols. Rank queries are slower than for the
+/// other implementations, because the symbols of up to one block have to be counted.
+///
+/// When an index with this implementation is constructed with [`PerformancePriority::Balanced`] or
+/// [`PerformancePriority::LowMemory`], the half byte compressed BWT of the construction is used directly.
+///
+/// [`PerformancePriority::Balanced`]: crate::PerformancePriority::Balanced
+/// [`PerformancePriority::LowMemory`]: crate::PerformancePriority::LowMemory
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "savefile", savefile_doc_hidden)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedTextWithRankSupport<I> {
+    text_len: usize,
+    alphabet_size: usize,
+    packed_text: LittleEndianVec<u64>,
+    interleaved_block_offsets: LittleEndianVec<u16>,
+    interleaved_superblock_offsets: LittleEndianVec<I>,
+}
+
+impl<I: IndexStorage> PackedTextWithRankSupport<I> {
+    fn superblock_offset_idx(&self, symbol: u8, idx: usize) -> usize {
+        (idx / SUPERBLOCK_SIZE) * self.alphabet_size + symbol as usize
+    }
+
+    fn block_offset_idx(&self, symbol: u8, idx: usize) -> usize {
+        (idx / SYMBOLS_PER_BLOCK) * self.alphabet_size + symbol as usize
+    }
+
+    fn zeroed(text_len: usize, alphabet_size: usize) -> Self {
+        assert!(alphabet_size >= 2);
+        assert!(
+            alphabet_size <= 16,
+            "the packed text with rank support can only be used for at most 16 dense symbols"
+        );
+
+        let num_superblocks = text_len / SUPERBLOCK_SIZE + 1;
+
+        // one block offset more than needed for the text, because rank queries at text.len() are allowed
+        let num_block_offsets = (text_len / SYMBOLS_PER_BLOCK + 1) * alphabet_size;
+        let num_superblock_offsets = num_superblocks * alphabet_size;
+
+        // allocated for full superblocks to simplify the parallel construction, truncated afterwards
+        Self {
+            text_len,
+            alphabet_size,
+            packed_text: vec![0; num_superblocks * WORDS_PER_SUPERBLOCK].into(),
+            interleaved_block_offsets: vec![0; num_block_offsets].into(),
+            interleaved_superblock_offsets: vec![I::zero(); num_superblock_offsets].into(),
+        }
+    }
+
+    // fills the superblocks starting at first_superblock with the text, which starts at the border of this superblock.
+    // the superblock offsets are the counts of the symbols in the superblocks afterwards.
+    fn fill_superblocks<S: SliceCompression>(&mut self, first_superblock: usize, text: &[u8]) {
+        let alphabet_size = self.alphabet_size;
+        let text_len = self.text_len;
+        let text_chunk_size = S::transform_chunk_size(SUPERBLOCK_SIZE);
+
+        // the superblocks after the text don't contain symbols, so they stay zeroed
+        let num_superblocks = text.len().div_ceil(text_chunk_size);
+
+        (
+            self.packed_text
+                .par_chunks_mut(WORDS_PER_SUPERBLOCK)
+                .skip(first_superblock),
+            self.interleaved_block_offsets
+                .par_chunks_mut(BLOCKS_PER_SUPERBLOCK * alphabet_size)
+                .skip(first_superblock),
+            self.interleaved_superblock_offsets
+                .par_chunks_mut(alphabet_size)
+                .skip(first_superblock),
+        )
+            .into_par_iter()
+            .take(num_superblocks)
+            .enumerate()
+            .for_each(|(idx, (words, block_offsets, superblock_counts))| {
+                let superblock_idx = first_superblock + idx;
+                let text_chunk_start = idx * text_chunk_size;
+                let text_chunk_end = (text_chunk_start + text_chunk_size).min(text.len());
+
+                // the half byte compressed text might contain one padding symbol at the end
+                let num_symbols = text_len
+                    .saturating_sub(superblock_idx * SUPERBLOCK_SIZE)
+                    .min(SUPERBLOCK_SIZE);
+
+                fill_superblock::<I, S>(
+                    &text[text_chunk_start..text_chunk_end],
+                    num_symbols,
+                    words,
+                    block_offsets,
+                    superblock_counts,
+                    alphabet_size,
+                );
+            });
+    }
+
+    fn finish(&mut self) {
+        self.packed_text
+            .truncate(self.text_len.div_ceil(SYMBOLS_PER_WORD));
+        self.packed_text.shrink_to_fit();
+
+        accumulate_superblock_offsets(&mut self.interleaved_superblock_offsets, self.alphabet_size);
+    }
+}
+
+impl<I: IndexStorage> MaybeMemDbg for PackedTextWithRankSupport<I> {}
+
+impl<I: IndexStorage> MaybeSavefile for PackedTextWithRankSupport<I> {}
+
+impl<I: IndexStorage> Sealed for PackedTextWithRankSupport<I> {}
+
+impl<I: IndexStorage> super::PrivateTextWithRankSupport<I> for PackedTextWithRankSupport<I> {
+    const NAME: &'static str = "PackedTextWithRankSupport";
+
+    // this implementation does not use the block types
+    const BLOCK_NAME: &'static str = "none";
+
+    fn construct_from_maybe_slice_compressed_text<S: SliceCompression>(
+        text: &[u8],
+        uncompressed_text_len: usize,
+        alphabet_size: usize,
+    ) -> Self {
+        let mut ranks = Self::zeroed(uncompressed_text_len, alphabet_size);
+        ranks.fill_superblocks::<S>(0, text);
+        ranks.finish();
+
+        ranks
+    }
+
+    fn construct_from_text_blocks(
+        text: &impl TextBlocks,
+        alphabet_size: usize,
+    ) -> io::Result<Self> {
+        let mut ranks = Self::zeroed(text.len(), alphabet_size);
+
+        text.try_for_each_block(
+            text.block_len_multiple_of(SUPERBLOCK_SIZE),
+            |start, block| {
+                ranks.fill_superblocks::<NoSliceCompression>(start / SUPERBLOCK_SIZE, block)
+            },
+        )?;
+
+        ranks.finish();
+
+        Ok(ranks)
+    }
+
+    fn estimated_size_in_bytes(text_len: usize, alphabet_size: usize) -> usize {
+        let num_words = text_len.div_ceil(SYMBOLS_PER_WORD);
+        let num_block_offsets = (text_len / SYMBOLS_PER_BLOCK + 1) * alphabet_size;
+        let num_superblock_offsets = (text_len / SUPERBLOCK_SIZE + 1) * alphabet_size;
+
+        num_words * size_of::<u64>()
+            + num_block_offsets * size_of::<u16>()
+            + num_superblock_offsets * size_of::<I>()
+    }
+
+    fn _alphabet_size(&self) -> usize {
+        self.alphabet_size
+    }
+
+    fn _text_len(&self) -> usize {
+        self.text_len
+    }
+
+    fn memory_regions(&self) -> Vec<MemoryRegion> {
+        vec![
+            MemoryRegion::of_slice(&self.packed_text),
+            MemoryRegion::of_slice(&self.interleaved_block_offsets),
+            MemoryRegion::of_slice(&self.interleaved_superblock_offsets),
+        ]
+    }
+
+    fn check_structure(&self) -> Result<(), RankSupportMismatch> {
+        check_alphabet_size(self.alphabet_size, 16)?;
+
+        let num_superblocks = self.text_len / SUPERBLOCK_SIZE + 1;
+
+        // the words are only accessed if they contain a symbol before the queried index
+        check_min_len(
+            "packed text",
+            self.packed_text.len(),
+            self.text_len.div_ceil(SYMBOLS_PER_WORD),
+        )?;
+        check_min_len(
+            "block offsets",
+            self.interleaved_block_offsets.len(),
+            (self.text_len / SYMBOLS_PER_BLOCK + 1).saturating_mul(self.alphabet_size),
+        )?;
+        check_min_len(
+            "superblock offsets",
+            self.interleaved_superblock_offsets.len(),
+            num_superblocks.saturating_mul(self.alphabet_size),
+        )?;
+        check_offsets("superblock offsets", &self.interleaved_superblock_offsets)
+    }
+
+    // every word of the block is loaded once and counted for all symbols
+    fn rank_all_into(&self, idx: usize, ranks: &mut [usize]) {
+        assert!(idx <= self.text_len && ranks.len() == self.alphabet_size);
+
+        let superblock_offsets =
+            &self.interleaved_superblock_offsets[self.superblock_offset_idx(0, idx)..];
+        let block_offsets = &self.interleaved_block_offsets[self.block_offset_idx(0, idx)..];
+
+        for ((rank, &superblock_offset), &block_offset) in
+            ranks.iter_mut().zip(superblock_offsets).zip(block_offsets)
+        {
+            *rank = <usize as NumCast>::from(superblock_offset).unwrap() + block_offset as usize;
+        }
+
+        let block_start_word = (idx / SYMBOLS_PER_BLOCK) * WORDS_PER_BLOCK;
+        let num_full_words = (idx % SYMBOLS_PER_BLOCK) / SYMBOLS_PER_WORD;
+        let num_remaining_symbols = idx % SYMBOLS_PER_WORD;
+
+        let words = self.packed_text[block_start_word..block_start_word + num_full_words]
+            .iter()
+            .map(|&word| (word, SYMBOLS_PER_WORD));
+
+        // the word is only accessed if it contains a symbol before idx, so it exists
+        let last_word = (num_remaining_symbols > 0).then(|| {
+            (
+                self.packed_text[block_start_word + num_full_words],
+                num_remaining_symbols,
+            )
+        });
+
+        for (word, num_symbols) in words.chain(last_word) {
+            for (symbol, rank) in ranks.iter_mut().enumerate() {
+                *rank += count_symbol_in_word(word, symbol as u8, num_symbols);
+            }
+        }
+    }
+
+    // the rank computation is dominated by counting inside of the blocks,
+    // so there is nothing to gain from interleaving the memory accesses here
+    unsafe fn replace_many_interval_borders_with_ranks_unchecked<Q, const N: usize>(
+        &self,
+        buffers: &mut Buffers<Q, N>,
+        num_remaining_unfinished_queries: usize,
+    ) {
+        assert!(num_remaining_unfinished_queries <= N);
+
+        for i in 0..num_remaining_unfinished_queries {
+            let symbol = buffers.symbols[i];
+            let interval = &mut buffers.intervals[i];
+
+            // SAFETY: the caller guarantees the same preconditions as for rank_unchecked
+            unsafe {
+                interval.start = self.rank_unchecked(symbol, interval.start);
+                interval.end = self.rank_unchecked(symbol, interval.end);
+            }
+        }
+    }
+}
+
+impl<I: IndexStorage> TextWithRankSupport<I> for PackedTextWithRankSupport<I> {
+    unsafe fn rank_unchecked(&self, symbol: u8, idx: usize) -> usize {
+        // SAFETY: all of the index accesses are in the valid range if idx is at most text.len()
+        // and symbol is smaller than the alphabet size
+
+        let superblock_offset = unsafe {
+            *self
+                .interleaved_superblock_offsets
+                .get_unchecked(self.superblock_offset_idx(symbol, idx))
+        };
+
+        // SAFETY: must succeed, otherwise the construction function would have crashed
+        let superblock_offset =
+            unsafe { <usize as NumCast>::from(superblock_offset).unwrap_unchecked() };
+
+        let block_offset = unsafe {
+            *self
+                .interleaved_block_offsets
+                .get_unchecked(self.block_offset_idx(symbol, idx))
+        } as usize;
+
+        let block_start_word = (idx / SYMBOLS_PER_BLOCK) * WORDS_PER_BLOCK;
+        let idx_in_block = idx % SYMBOLS_PER_BLOCK;
+        let num_full_words = idx_in_block / SYMBOLS_PER_WORD;
+        let num_remaining_symbols = idx % SYMBOLS_PER_WORD;
+
+        let mut block_count = 0;
+
+        for word_idx in block_start_word..block_start_word + num_full_words {
+            let word = unsafe { *self.packed_text.get_unchecked(word_idx) };
+            block_count += count_symbol_in_word(word, symbol, SYMBOLS_PER_WORD);
+        }
+
+        // the word is only accessed if it contains a symbol before idx, so it exists
+        if num_remaining_symbols > 0 {
+            let word = unsafe {
+                *self
+                    .packed_text
+                    .get_unchecked(block_start_word + num_full_words)
+            };
+            block_count += count_symbol_in_word(word, symbol, num_remaining_symbols);
+        }
+
+        superblock_offset + block_offset + block_count
+    }
+
+    unsafe fn rank_two_unchecked(&self, symbol: u8, start: usize, end: usize) -> (usize, usize) {
+        // SAFETY: same requirements as this function
+        let start_rank = unsafe { self.rank_unchecked(symbol, start) };
+
+        if start / SYMBOLS_PER_BLOCK != end / SYMBOLS_PER_BLOCK {
+            let end_rank = unsafe { self.rank_unchecked(symbol, end) };
+            return (start_rank, end_rank);
+        }
+
+        // only the words between the indices are counted, instead of all words of the block before end
+        let mut end_rank = start_rank;
+        let mut idx = start;
+
+        while idx < end {
+            let word_idx = idx / SYMBOLS_PER_WORD;
+            let word_start = word_idx * SYMBOLS_PER_WORD;
+            let word_end = (word_start + SYMBOLS_PER_WORD).min(end);
+
+            // SAFETY: the word contains the symbol at idx < end <= text.len(), so it exists
+            let word = unsafe { *self.packed_text.get_unchecked(word_idx) };
+
+            end_rank += count_symbol_in_word(word, symbol, word_end - word_start)
+                - count_symbol_in_word(word, symbol, idx - word_start);
+
+            idx = word_end;
+        }
+
+        (start_rank, end_rank)
+    }
+
+    fn prefetch_rank(&self, symbol: u8, idx: usize) {
+        if symbol as usize >= self.alphabet_size || idx > self.text_len {
+            return;
+        }
+
+        prefetch_element(
+            &self.interleaved_superblock_offsets,
+            self.superblock_offset_idx(symbol, idx),
+        );
+        prefetch_element(
+            &self.interleaved_block_offsets,
+            self.block_offset_idx(symbol, idx),
+        );
+
+        // the words of the block before idx are counted, they span at most two cache lines
+        let block_start_word = (idx / SYMBOLS_PER_BLOCK) * WORDS_PER_BLOCK;
+        prefetch_element(&self.packed_text, block_start_word);
+        prefetch_element(&self.packed_text, block_start_word + WORDS_PER_BLOCK - 1);
+    }
+
+    fn symbols_at_many(&self, indices: &[usize], symbols: &mut [u8]) {
+        assert_eq!(indices.len(), symbols.len());
+
+        for &idx in indices {
+            prefetch_element(&self.packed_text, idx / SYMBOLS_PER_WORD);
+        }
+
+        for (symbol, &idx) in symbols.iter_mut().zip(indices) {
+            *symbol = self.symbol_at(idx);
+        }
+    }
+
+    fn symbol_at(&self, idx: usize) -> u8 {
+        assert!(idx < self.text_len);
+
+        let word = self.packed_text[idx / SYMBOLS_PER_WORD];
+        ((word >> (4 * (idx % SYMBOLS_PER_WORD))) & 0b1111) as u8
+    }
+}
+
+// counts the occurrences of symbol in the first num_symbols half bytes of the word
+fn count_symbol_in_word(word: u64, symbol: u8, num_symbols: usize) -> usize {
+    // half bytes that equal the symbol become zero
+    let diff = word ^ (symbol as u64 * LOWEST_BIT_OF_EVERY_HALF_BYTE);
+
+    // the lowest bit of every half byte is set if the half byte is not zero
+    let non_zero = (diff | (diff >> 1) | (diff >> 2) | (diff >> 3)) & LOWEST_BIT_OF_EVERY_HALF_BYTE;
+
+    let mask = if num_symbols == SYMBOLS_PER_WORD {
+        u64::MAX
+    } else {
+        (1 << (4 * num_symbols)) - 1
+    };
+
+    num_symbols - (non_zero & mask).count_ones() as usize
+}
+
+fn fill_superblock<I: PrimInt, S: SliceCompression>(
+    text: &[u8],
+    num_symbols: usize,
+    words: &mut [u64],
+    interleaved_block_offsets: &mut [u16],
+    superblock_counts: &mut [I],
+    alphabet_size: usize,
+) {
+    // the counts fit into u16, except for the total count of a full superblock
+    let mut counts = vec![0usize; alphabet_size];
+    let mut symbols = S::iter(text).take(num_symbols).enumerate();
+
+    // the last block offsets might belong to a block that does not contain any symbols
+    for block_offsets in interleaved_block_offsets.chunks_mut(alphabet_size) {
+        for (block_offset, &count) in block_offsets.iter_mut().zip(&counts) {
+            *block_offset = count as u16;
+        }
+
+        for (idx_in_superblock, symbol) in symbols.by_ref().take(SYMBOLS_PER_BLOCK) {
+            words[idx_in_superblock / SYMBOLS_PER_WORD] |=
+                (symbol as u64) << (4 * (idx_in_superblock % SYMBOLS_PER_WORD));
+            counts[symbol as usize] += 1;
+        }
+    }
+
+    for (superblock_count, count) in superblock_counts.iter_mut().zip(counts) {
+        *superblock_count = <I as NumCast>::from(count).unwrap();
+    }
+}
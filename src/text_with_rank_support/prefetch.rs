@@ -0,0 +1,32 @@
+// Software prefetches into all cache levels. Prefetch instructions never fault, not even for invalid addresses,
+// but only references are prefetched here anyway. On platforms other than x86_64 and aarch64, nothing is done.
+
+pub(super) fn prefetch_element<T>(values: &[T], idx: usize) {
+    if let Some(value) = values.get(idx) {
+        prefetch(value);
+    }
+}
+
+fn prefetch<T>(value: &T) {
+    let ptr = (value as *const T).cast::<i8>();
+
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: SSE is always available on x86_64 and the prefetch doesn't access the memory architecturally
+    unsafe {
+        use std::arch::x86_64::{_MM_HINT_T0, _mm_prefetch};
+        _mm_prefetch::<_MM_HINT_T0>(ptr)
+    };
+
+    #[cfg(target_arch = "aarch64")]
+    // SAFETY: the prefetch doesn't access the memory architecturally and has no other side effects
+    unsafe {
+        std::arch::asm!(
+            "prfm pldl1keep, [{ptr}]",
+            ptr = in(reg) ptr,
+            options(nostack, readonly, preserves_flags)
+        )
+    };
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let _ = ptr;
+}
@@ -0,0 +1,320 @@
+// Explicitly vectorized kernels for the operations on the data of 512 bit blocks. The instruction sets of x86_64
+// are detected at runtime, such that the library can still be compiled for generic targets. If the instruction sets
+// are enabled at compile time (e.g. via `-C target-cpu=native`), the detection is resolved by the compiler and the
+// kernels can be inlined into the rank queries. NEON is always available on aarch64. On all other platforms,
+// the scalar loops are used.
+
+// The conjunction is called several times per rank query of the condensed layout. A call of a kernel that was
+// selected at runtime costs more than it saves compared to the scalar loop, which LLVM vectorizes with SSE2.
+// Therefore, the AVX kernels are only used for it when the instruction sets are enabled at compile time.
+#[inline(always)]
+pub(super) fn and_assign(data: &mut [u64; 8], other: &[u64; 8]) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if cfg!(all(target_feature = "avx512f", target_feature = "avx512bw")) {
+            // SAFETY: the required instruction sets are enabled at compile time
+            return unsafe { x86::and_assign_avx512(data, other) };
+        }
+
+        if cfg!(target_feature = "avx2") {
+            // SAFETY: the required instruction sets are enabled at compile time
+            return unsafe { x86::and_assign_avx2(data, other) };
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    return neon::and_assign(data, other);
+
+    #[cfg(not(target_arch = "aarch64"))]
+    and_assign_scalar(data, other)
+}
+
+#[inline(always)]
+pub(super) fn count_ones_masked(data: &[u64; 8], mask: &[u64; 8]) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if x86::has_avx512_popcnt() {
+            // SAFETY: the required instruction sets were detected
+            return unsafe { x86::count_ones_masked_avx512_popcnt(data, mask) };
+        }
+
+        if x86::has_avx512() {
+            // SAFETY: the required instruction sets were detected
+            return unsafe { x86::count_ones_masked_avx512(data, mask) };
+        }
+
+        if x86::has_avx2() {
+            // SAFETY: the required instruction sets were detected
+            return unsafe { x86::count_ones_masked_avx2(data, mask) };
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    return neon::count_ones_masked(data, mask);
+
+    #[cfg(not(target_arch = "aarch64"))]
+    count_ones_masked_scalar(data, mask)
+}
+
+#[cfg_attr(target_arch = "aarch64", allow(dead_code))]
+#[inline(always)]
+fn and_assign_scalar(data: &mut [u64; 8], other: &[u64; 8]) {
+    for (data_part, other_part) in data.iter_mut().zip(other) {
+        *data_part &= other_part;
+    }
+}
+
+#[cfg_attr(target_arch = "aarch64", allow(dead_code))]
+#[inline(always)]
+fn count_ones_masked_scalar(data: &[u64; 8], mask: &[u64; 8]) -> usize {
+    data.iter()
+        .zip(mask)
+        .map(|(data_part, mask_part)| (data_part & mask_part).count_ones() as usize)
+        .sum()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    // the check is a constant if the features are enabled at compile time, otherwise the cached result of
+    // the runtime detection is used
+    macro_rules! has_features {
+        ($($feature:tt),+) => {
+            cfg!(all($(target_feature = $feature),+)) || ($(is_x86_feature_detected!($feature))&&+)
+        };
+    }
+
+    // the byte shuffles of AVX-512 need the BW extension
+    #[inline(always)]
+    pub(super) fn has_avx512() -> bool {
+        has_features!("avx512f", "avx512bw")
+    }
+
+    #[inline(always)]
+    pub(super) fn has_avx512_popcnt() -> bool {
+        has_features!("avx512f", "avx512vpopcntdq")
+    }
+
+    #[inline(always)]
+    pub(super) fn has_avx2() -> bool {
+        has_features!("avx2")
+    }
+
+    // number of ones of every nibble value, used to count the ones of all bytes using byte shuffles
+    const NIBBLE_COUNTS: [i8; 16] = [0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4];
+
+    #[target_feature(enable = "avx512f,avx512bw")]
+    pub(super) unsafe fn and_assign_avx512(data: &mut [u64; 8], other: &[u64; 8]) {
+        unsafe {
+            let result = _mm512_and_si512(
+                _mm512_loadu_si512(data.as_ptr().cast()),
+                _mm512_loadu_si512(other.as_ptr().cast()),
+            );
+            _mm512_storeu_si512(data.as_mut_ptr().cast(), result);
+        }
+    }
+
+    #[target_feature(enable = "avx512f,avx512vpopcntdq")]
+    pub(super) unsafe fn count_ones_masked_avx512_popcnt(
+        data: &[u64; 8],
+        mask: &[u64; 8],
+    ) -> usize {
+        let masked = unsafe {
+            _mm512_and_si512(
+                _mm512_loadu_si512(data.as_ptr().cast()),
+                _mm512_loadu_si512(mask.as_ptr().cast()),
+            )
+        };
+
+        _mm512_reduce_add_epi64(_mm512_popcnt_epi64(masked)) as usize
+    }
+
+    #[target_feature(enable = "avx512f,avx512bw")]
+    pub(super) unsafe fn count_ones_masked_avx512(data: &[u64; 8], mask: &[u64; 8]) -> usize {
+        let masked = unsafe {
+            _mm512_and_si512(
+                _mm512_loadu_si512(data.as_ptr().cast()),
+                _mm512_loadu_si512(mask.as_ptr().cast()),
+            )
+        };
+
+        let nibble_counts =
+            _mm512_broadcast_i32x4(unsafe { _mm_loadu_si128(NIBBLE_COUNTS.as_ptr().cast()) });
+        let low_nibbles_mask = _mm512_set1_epi8(0x0f);
+
+        let low_nibbles = _mm512_and_si512(masked, low_nibbles_mask);
+        let high_nibbles = _mm512_and_si512(_mm512_srli_epi16::<4>(masked), low_nibbles_mask);
+
+        let byte_counts = _mm512_add_epi8(
+            _mm512_shuffle_epi8(nibble_counts, low_nibbles),
+            _mm512_shuffle_epi8(nibble_counts, high_nibbles),
+        );
+
+        // sums up the counts of the 8 bytes of every u64
+        let counts = _mm512_sad_epu8(byte_counts, _mm512_setzero_si512());
+
+        _mm512_reduce_add_epi64(counts) as usize
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn and_assign_avx2(data: &mut [u64; 8], other: &[u64; 8]) {
+        for half in 0..2 {
+            unsafe {
+                let data_ptr = data.as_mut_ptr().add(half * 4).cast();
+                let result = _mm256_and_si256(
+                    _mm256_loadu_si256(data_ptr),
+                    _mm256_loadu_si256(other.as_ptr().add(half * 4).cast()),
+                );
+                _mm256_storeu_si256(data_ptr, result);
+            }
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn count_ones_masked_avx2(data: &[u64; 8], mask: &[u64; 8]) -> usize {
+        let nibble_counts =
+            _mm256_broadcastsi128_si256(unsafe { _mm_loadu_si128(NIBBLE_COUNTS.as_ptr().cast()) });
+        let low_nibbles_mask = _mm256_set1_epi8(0x0f);
+
+        let mut counts = _mm256_setzero_si256();
+
+        for half in 0..2 {
+            let masked = unsafe {
+                _mm256_and_si256(
+                    _mm256_loadu_si256(data.as_ptr().add(half * 4).cast()),
+                    _mm256_loadu_si256(mask.as_ptr().add(half * 4).cast()),
+                )
+            };
+
+            let low_nibbles = _mm256_and_si256(masked, low_nibbles_mask);
+            let high_nibbles = _mm256_and_si256(_mm256_srli_epi16::<4>(masked), low_nibbles_mask);
+
+            let byte_counts = _mm256_add_epi8(
+                _mm256_shuffle_epi8(nibble_counts, low_nibbles),
+                _mm256_shuffle_epi8(nibble_counts, high_nibbles),
+            );
+
+            counts = _mm256_add_epi64(counts, _mm256_sad_epu8(byte_counts, _mm256_setzero_si256()));
+        }
+
+        let mut lanes = [0u64; 4];
+        unsafe { _mm256_storeu_si256(lanes.as_mut_ptr().cast(), counts) };
+
+        lanes.iter().sum::<u64>() as usize
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    #[inline(always)]
+    pub(super) fn and_assign(data: &mut [u64; 8], other: &[u64; 8]) {
+        for quarter in 0..4 {
+            // SAFETY: NEON is always available on aarch64 and all accesses are in bounds
+            unsafe {
+                let data_ptr = data.as_mut_ptr().add(quarter * 2);
+                let result = vandq_u64(
+                    vld1q_u64(data_ptr),
+                    vld1q_u64(other.as_ptr().add(quarter * 2)),
+                );
+                vst1q_u64(data_ptr, result);
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(super) fn count_ones_masked(data: &[u64; 8], mask: &[u64; 8]) -> usize {
+        // SAFETY: NEON is always available on aarch64 and all accesses are in bounds
+        unsafe {
+            // every byte count is at most 4 * 8, so the sums can't overflow
+            let mut byte_counts = vdupq_n_u8(0);
+
+            for quarter in 0..4 {
+                let masked = vandq_u64(
+                    vld1q_u64(data.as_ptr().add(quarter * 2)),
+                    vld1q_u64(mask.as_ptr().add(quarter * 2)),
+                );
+                byte_counts = vaddq_u8(byte_counts, vcntq_u8(vreinterpretq_u8_u64(masked)));
+            }
+
+            vaddlvq_u8(byte_counts) as usize
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    type AndAssign = fn(&mut [u64; 8], &[u64; 8]);
+    type CountOnesMasked = fn(&[u64; 8], &[u64; 8]) -> usize;
+
+    // all vectorized kernels that can be executed on the current machine, such that every one of them is tested
+    // and not only the one that is selected by the dispatch
+    #[allow(unused_mut)]
+    fn kernels() -> (Vec<AndAssign>, Vec<CountOnesMasked>) {
+        let mut and_assign_kernels: Vec<AndAssign> = vec![and_assign];
+        let mut count_ones_masked_kernels: Vec<CountOnesMasked> = vec![count_ones_masked];
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if x86::has_avx512() {
+                and_assign_kernels
+                    .push(|data, other| unsafe { x86::and_assign_avx512(data, other) });
+                count_ones_masked_kernels
+                    .push(|data, mask| unsafe { x86::count_ones_masked_avx512(data, mask) });
+            }
+
+            if x86::has_avx512_popcnt() {
+                count_ones_masked_kernels
+                    .push(|data, mask| unsafe { x86::count_ones_masked_avx512_popcnt(data, mask) });
+            }
+
+            if x86::has_avx2() {
+                and_assign_kernels.push(|data, other| unsafe { x86::and_assign_avx2(data, other) });
+                count_ones_masked_kernels
+                    .push(|data, mask| unsafe { x86::count_ones_masked_avx2(data, mask) });
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            and_assign_kernels.push(neon::and_assign);
+            count_ones_masked_kernels.push(neon::count_ones_masked);
+        }
+
+        (and_assign_kernels, count_ones_masked_kernels)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn kernels_agree_with_scalar(
+            data in prop::array::uniform8(any::<u64>()),
+            other in prop::array::uniform8(any::<u64>()),
+        ) {
+            let mut expected_and = data;
+            and_assign_scalar(&mut expected_and, &other);
+
+            let (and_assign_kernels, count_ones_masked_kernels) = kernels();
+
+            for and_assign_kernel in and_assign_kernels {
+                let mut and = data;
+                and_assign_kernel(&mut and, &other);
+                prop_assert_eq!(and, expected_and);
+            }
+
+            for count_ones_masked_kernel in count_ones_masked_kernels {
+                prop_assert_eq!(
+                    count_ones_masked_kernel(&data, &other),
+                    count_ones_masked_scalar(&data, &other)
+                );
+                prop_assert_eq!(count_ones_masked_kernel(&[u64::MAX; 8], &[u64::MAX; 8]), 512);
+            }
+        }
+    }
+}
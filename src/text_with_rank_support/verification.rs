@@ -0,0 +1,218 @@
+use std::fmt;
+
+use num_traits::NumCast;
+
+use crate::IndexStorage;
+
+use super::TextWithRankSupport;
+
+/// The first difference between a text with rank support and the text it should represent, reported by
+/// [`TextWithRankSupport::verify`].
+///
+/// The expected values are the ones computed from the given text, the actual ones are the ones answered by the
+/// data structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankSupportMismatch {
+    /// The alphabet size of the data structure is not supported by its implementation. This can only happen
+    /// for corrupted data structures.
+    AlphabetSize { actual: usize },
+    /// An internal buffer of the data structure doesn't fit its text length and alphabet size, or contains values
+    /// that can't be produced by the construction. This can only happen for corrupted data structures.
+    ///
+    /// `component` is the name of the buffer. The ranks are not computed in this case, because the queries
+    /// would access memory outside of the buffers.
+    Structure { component: &'static str },
+    /// The text length of the data structure differs from the length of the text.
+    TextLen { expected: usize, actual: usize },
+    /// The data structure returns a wrong symbol at position `idx` of the text.
+    SymbolAt {
+        idx: usize,
+        expected: u8,
+        actual: u8,
+    },
+    /// The data structure returns a wrong rank of `symbol` at position `idx` of the text.
+    Rank {
+        symbol: u8,
+        idx: usize,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for RankSupportMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlphabetSize { actual } => write!(f, "alphabet size {actual} is not supported"),
+            Self::Structure { component } => write!(f, "corrupted component: {component}"),
+            Self::TextLen { expected, actual } => {
+                write!(f, "text length is {actual}, expected {expected}")
+            }
+            Self::SymbolAt {
+                idx,
+                expected,
+                actual,
+            } => write!(f, "symbol at {idx} is {actual}, expected {expected}"),
+            Self::Rank {
+                symbol,
+                idx,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "rank of symbol {symbol} at {idx} is {actual}, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RankSupportMismatch {}
+
+pub(super) fn verify<I: IndexStorage, R: TextWithRankSupport<I>>(
+    text_with_rank_support: &R,
+    text: &[u8],
+) -> Result<(), RankSupportMismatch> {
+    // the queries don't check their memory accesses, so they can only be executed on intact data structures
+    text_with_rank_support.check_structure()?;
+
+    let alphabet_size = text_with_rank_support.alphabet_size();
+
+    assert!(
+        text.iter().all(|&symbol| (symbol as usize) < alphabet_size),
+        "the symbols of the text must be smaller than the alphabet size"
+    );
+
+    if text.len() != text_with_rank_support.text_len() {
+        return Err(RankSupportMismatch::TextLen {
+            expected: text.len(),
+            actual: text_with_rank_support.text_len(),
+        });
+    }
+
+    let mut counts = vec![0; alphabet_size];
+
+    for idx in 0..=text.len() {
+        for (symbol, &expected) in counts.iter().enumerate() {
+            let symbol = symbol as u8;
+            let actual = text_with_rank_support.rank(symbol, idx);
+
+            if actual != expected {
+                return Err(RankSupportMismatch::Rank {
+                    symbol,
+                    idx,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        let Some(&expected) = text.get(idx) else {
+            break;
+        };
+
+        let actual = text_with_rank_support.symbol_at(idx);
+
+        if actual != expected {
+            return Err(RankSupportMismatch::SymbolAt {
+                idx,
+                expected,
+                actual,
+            });
+        }
+
+        counts[expected as usize] += 1;
+    }
+
+    Ok(())
+}
+
+// the buffers of the data structures are allowed to be longer than needed, e.g. for padding
+pub(super) fn check_min_len(
+    component: &'static str,
+    len: usize,
+    min_len: usize,
+) -> Result<(), RankSupportMismatch> {
+    if len < min_len {
+        Err(RankSupportMismatch::Structure { component })
+    } else {
+        Ok(())
+    }
+}
+
+pub(super) fn check_alphabet_size(
+    alphabet_size: usize,
+    max_alphabet_size: usize,
+) -> Result<(), RankSupportMismatch> {
+    if (2..=max_alphabet_size).contains(&alphabet_size) {
+        Ok(())
+    } else {
+        Err(RankSupportMismatch::AlphabetSize {
+            actual: alphabet_size,
+        })
+    }
+}
+
+// the queries convert the offsets to usize without checks
+pub(super) fn check_offsets<I: IndexStorage>(
+    component: &'static str,
+    offsets: &[I],
+) -> Result<(), RankSupportMismatch> {
+    if offsets
+        .iter()
+        .all(|&offset| <usize as NumCast>::from(offset).is_some())
+    {
+        Ok(())
+    } else {
+        Err(RankSupportMismatch::Structure { component })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_with_rank_support::{
+        CondensedTextWithRankSupport, FlatTextWithRankSupport, WaveletTreeTextWithRankSupport,
+    };
+
+    fn check_mismatches<R: TextWithRankSupport<i32>>() {
+        let text = [0, 1, 1, 0, 2, 1, 0, 0];
+        let text_with_rank_support = R::construct(&text, 3);
+
+        assert_eq!(text_with_rank_support.verify(&text), Ok(()));
+
+        assert_eq!(
+            text_with_rank_support.verify(&text[..7]),
+            Err(RankSupportMismatch::TextLen {
+                expected: 7,
+                actual: 8
+            })
+        );
+
+        let mut other_text = text;
+        other_text[3] = 2;
+
+        // the ranks up to the changed position are the same
+        assert_eq!(
+            text_with_rank_support.verify(&other_text),
+            Err(RankSupportMismatch::SymbolAt {
+                idx: 3,
+                expected: 2,
+                actual: 0
+            })
+        );
+    }
+
+    #[test]
+    fn mismatches() {
+        check_mismatches::<CondensedTextWithRankSupport<i32>>();
+        check_mismatches::<FlatTextWithRankSupport<i32>>();
+        check_mismatches::<WaveletTreeTextWithRankSupport<i32>>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn symbol_outside_of_alphabet() {
+        let text_with_rank_support = FlatTextWithRankSupport::<i32>::construct(&[0, 1, 1], 2);
+
+        let _ = text_with_rank_support.verify(&[0, 2, 1]);
+    }
+}
@@ -0,0 +1,504 @@
+use std::{cmp::Reverse, collections::BinaryHeap, convert::Infallible, io, marker::PhantomData};
+
+use crate::IndexStorage;
+use crate::batch_computed_cursors::Buffers;
+use crate::construction::slice_compression::SliceCompression;
+use crate::maybe_mem_dbg::MaybeMemDbg;
+use crate::maybe_savefile::MaybeSavefile;
+use crate::memory_region::MemoryRegion;
+use crate::sealed::Sealed;
+
+use super::little_endian_vec::LittleEndianVec;
+use super::verification::{RankSupportMismatch, check_alphabet_size, check_min_len};
+use super::{BitVectorWithRankSupport, Block512, TextBlocks, TextWithRankSupport};
+
+// The tree has the shape of the Huffman code of the symbol frequencies of the text. Every internal node stores
+// one bit for every symbol of the text that belongs to its subtree. The bit is the direction of the symbol at
+// this node. The bits of all nodes are concatenated into a single bitvector with rank support, so the rank
+// inside of a node is the global rank minus the number of ones before the node.
+
+// Symbols that don't occur in the text have no code and their rank is always 0. If only a single symbol occurs,
+// the root is a leaf and the code of the symbol is empty.
+
+const BITS_PER_WORD: usize = 64;
+
+// a reference to a child (or the root) is either the index of an internal node or a leaf with this flag and
+// the symbol in the lower bits
+const LEAF_FLAG: usize = 1 << (usize::BITS - 1);
+
+const NO_CODE: u8 = u8::MAX;
+
+/// An implementation of [`TextWithRankSupport`] based on a Huffman-shaped wavelet tree.
+///
+/// The text is stored using about as many bits per symbol as its zeroth order entropy, plus around 3% for the
+/// rank support. Therefore, it is the smallest implementation for large alphabets like
+/// [`ascii_printable`](crate::alphabet::ascii_printable), where the interleaved layout of the other
+/// implementations becomes wide. Rank queries are slower than for the other implementations, because
+/// one binary rank query is needed for every bit of the code of a symbol.
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[cfg_attr(feature = "savefile", savefile_doc_hidden)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaveletTreeTextWithRankSupport<I> {
+    text_len: usize,
+    alphabet_size: usize,
+    root: usize,
+    nodes: LittleEndianVec<Node>,
+    codes: LittleEndianVec<Code>,
+    bits: BitVectorWithRankSupport<Block512>,
+    _index_storage: PhantomData<I>,
+}
+
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "mem_dbg", copy_type)]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Node {
+    bits_start: usize,
+    ones_before: usize,
+    children: [usize; 2],
+}
+
+// bit i of the code is the direction of the symbol at depth i. The length of a Huffman code is logarithmic in
+// the text length with a base of the golden ratio, so the codes of all texts that can be indexed fit into 128 bits.
+#[cfg_attr(feature = "mem_dbg", derive(mem_dbg::MemSize, mem_dbg::MemDbg))]
+#[cfg_attr(feature = "mem_dbg", copy_type)]
+#[cfg_attr(feature = "savefile", derive(savefile::savefile_derive::Savefile))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Code {
+    bits: u128,
+    len: u8,
+}
+
+impl Code {
+    fn direction_at(&self, depth: u8) -> usize {
+        ((self.bits >> depth) & 1) as usize
+    }
+}
+
+impl<I: IndexStorage> WaveletTreeTextWithRankSupport<I> {
+    // the number of ones among the first idx bits of the node
+    fn node_rank(&self, node: &Node, idx: usize) -> usize {
+        self.bits.rank1(node.bits_start + idx) - node.ones_before
+    }
+
+    // maps the index into the node to the index into the child in the given direction
+    fn child_idx(&self, node: &Node, idx: usize, direction: usize) -> usize {
+        let ones = self.node_rank(node, idx);
+
+        if direction == 1 { ones } else { idx - ones }
+    }
+
+    // for_each_symbol calls the given function for every symbol of the text. it is called twice, once to count
+    // the symbols and once to fill the nodes.
+    fn construct_from_symbols<E>(
+        text_len: usize,
+        alphabet_size: usize,
+        mut for_each_symbol: impl FnMut(&mut dyn FnMut(u8)) -> Result<(), E>,
+    ) -> Result<Self, E> {
+        assert!(alphabet_size >= 2);
+
+        let mut counts = vec![0; alphabet_size];
+        for_each_symbol(&mut |symbol| counts[symbol as usize] += 1)?;
+
+        let (root, children, node_lens) = build_huffman_tree(&counts);
+        let codes = codes_of_tree(root, &children, alphabet_size);
+
+        let mut bits_starts = Vec::with_capacity(node_lens.len());
+        let mut num_bits = 0;
+
+        for node_len in node_lens {
+            bits_starts.push(num_bits);
+            num_bits += node_len;
+        }
+
+        let mut words = vec![0u64; num_bits.div_ceil(BITS_PER_WORD)];
+        let mut next_bit_positions = bits_starts.clone();
+
+        for_each_symbol(&mut |symbol| {
+            let code = codes[symbol as usize];
+            let mut node_ref = root;
+
+            for depth in 0..code.len {
+                let direction = code.direction_at(depth);
+                let position = &mut next_bit_positions[node_ref];
+
+                words[*position / BITS_PER_WORD] |=
+                    (direction as u64) << (*position % BITS_PER_WORD);
+                *position += 1;
+
+                node_ref = children[node_ref][direction];
+            }
+        })?;
+
+        let bits = BitVectorWithRankSupport::from_words(&words, num_bits);
+        drop(words);
+
+        let nodes: Vec<_> = bits_starts
+            .into_iter()
+            .zip(children)
+            .map(|(bits_start, children)| Node {
+                bits_start,
+                ones_before: bits.rank1(bits_start),
+                children,
+            })
+            .collect();
+
+        Ok(Self {
+            text_len,
+            alphabet_size,
+            root,
+            nodes: nodes.into(),
+            codes: codes.into(),
+            bits,
+            _index_storage: PhantomData,
+        })
+    }
+}
+
+impl<I: IndexStorage> MaybeMemDbg for WaveletTreeTextWithRankSupport<I> {}
+
+impl<I: IndexStorage> MaybeSavefile for WaveletTreeTextWithRankSupport<I> {}
+
+impl<I: IndexStorage> Sealed for WaveletTreeTextWithRankSupport<I> {}
+
+impl<I: IndexStorage> super::PrivateTextWithRankSupport<I> for WaveletTreeTextWithRankSupport<I> {
+    const NAME: &'static str = "WaveletTreeTextWithRankSupport";
+
+    // this implementation does not use the block types
+    const BLOCK_NAME: &'static str = "none";
+
+    fn construct_from_maybe_slice_compressed_text<S: SliceCompression>(
+        text: &[u8],
+        uncompressed_text_len: usize,
+        alphabet_size: usize,
+    ) -> Self {
+        let Ok(ranks) =
+            Self::construct_from_symbols::<Infallible>(uncompressed_text_len, alphabet_size, |f| {
+                S::iter(text).take(uncompressed_text_len).for_each(f);
+                Ok(())
+            });
+
+        ranks
+    }
+
+    fn construct_from_text_blocks(
+        text: &impl TextBlocks,
+        alphabet_size: usize,
+    ) -> io::Result<Self> {
+        Self::construct_from_symbols(text.len(), alphabet_size, |f| {
+            text.try_for_each_block(text.max_block_len(), |_, block| {
+                block.iter().copied().for_each(&mut *f)
+            })
+        })
+    }
+
+    // the size depends on the symbol frequencies, which are not known here. The number of bits per symbol
+    // of a balanced tree is used, which is an upper bound for the Huffman-shaped tree.
+    fn estimated_size_in_bytes(text_len: usize, alphabet_size: usize) -> usize {
+        let bits_per_symbol = usize::BITS as usize - (alphabet_size - 1).leading_zeros() as usize;
+        let num_bits = text_len * bits_per_symbol;
+
+        BitVectorWithRankSupport::<Block512>::size_in_bytes(num_bits)
+            + (alphabet_size - 1) * size_of::<Node>()
+            + alphabet_size * size_of::<Code>()
+    }
+
+    fn _alphabet_size(&self) -> usize {
+        self.alphabet_size
+    }
+
+    fn _text_len(&self) -> usize {
+        self.text_len
+    }
+
+    fn memory_regions(&self) -> Vec<MemoryRegion> {
+        let [blocks, superblock_offsets] = self.bits.memory_regions();
+
+        vec![
+            MemoryRegion::of_slice(&self.nodes),
+            MemoryRegion::of_slice(&self.codes),
+            blocks,
+            superblock_offsets,
+        ]
+    }
+
+    // the queries only use checked accesses, but corrupted nodes could lead to endless traversals
+    fn check_structure(&self) -> Result<(), RankSupportMismatch> {
+        check_alphabet_size(self.alphabet_size, u8::MAX as usize + 1)?;
+        check_min_len("codes", self.codes.len(), self.alphabet_size)?;
+
+        if self
+            .codes
+            .iter()
+            .any(|code| code.len != NO_CODE && code.len as u32 > u128::BITS)
+        {
+            return Err(RankSupportMismatch::Structure { component: "codes" });
+        }
+
+        // the children of a node are always constructed before the node, so there are no cycles
+        let is_valid_ref = |node_ref: usize, parent: usize| {
+            if node_ref & LEAF_FLAG != 0 {
+                node_ref & !LEAF_FLAG < self.alphabet_size
+            } else {
+                node_ref < parent
+            }
+        };
+
+        let nodes_are_valid = is_valid_ref(self.root, self.nodes.len())
+            && self.nodes.iter().enumerate().all(|(node_ref, node)| {
+                node.children
+                    .iter()
+                    .all(|&child| is_valid_ref(child, node_ref))
+            });
+
+        if nodes_are_valid {
+            Ok(())
+        } else {
+            Err(RankSupportMismatch::Structure { component: "nodes" })
+        }
+    }
+
+    // every node is visited once, instead of once for every symbol in its subtree
+    fn rank_all_into(&self, idx: usize, ranks: &mut [usize]) {
+        assert!(idx <= self.text_len && ranks.len() == self.alphabet_size);
+
+        ranks.fill(0);
+
+        let mut stack = vec![(self.root, idx)];
+
+        while let Some((node_ref, idx)) = stack.pop() {
+            if node_ref & LEAF_FLAG != 0 {
+                ranks[node_ref & !LEAF_FLAG] = idx;
+                continue;
+            }
+
+            let node = &self.nodes[node_ref];
+            let ones = self.node_rank(node, idx);
+
+            stack.push((node.children[0], idx - ones));
+            stack.push((node.children[1], ones));
+        }
+    }
+
+    // the rank computation consists of dependent memory accesses along the path of the symbol,
+    // so there is nothing to gain from interleaving the queries here
+    unsafe fn replace_many_interval_borders_with_ranks_unchecked<Q, const N: usize>(
+        &self,
+        buffers: &mut Buffers<Q, N>,
+        num_remaining_unfinished_queries: usize,
+    ) {
+        assert!(num_remaining_unfinished_queries <= N);
+
+        for i in 0..num_remaining_unfinished_queries {
+            let symbol = buffers.symbols[i];
+            let interval = &mut buffers.intervals[i];
+
+            // SAFETY: the caller guarantees the same preconditions as for rank_unchecked
+            unsafe {
+                interval.start = self.rank_unchecked(symbol, interval.start);
+                interval.end = self.rank_unchecked(symbol, interval.end);
+            }
+        }
+    }
+}
+
+impl<I: IndexStorage> TextWithRankSupport<I> for WaveletTreeTextWithRankSupport<I> {
+    unsafe fn rank_unchecked(&self, symbol: u8, idx: usize) -> usize {
+        let code = self.codes[symbol as usize];
+
+        if code.len == NO_CODE {
+            return 0;
+        }
+
+        let mut node_ref = self.root;
+        let mut idx = idx;
+
+        for depth in 0..code.len {
+            let node = &self.nodes[node_ref];
+            let direction = code.direction_at(depth);
+
+            idx = self.child_idx(node, idx, direction);
+            node_ref = node.children[direction];
+        }
+
+        idx
+    }
+
+    // both indices are mapped along the path of the symbol in the same traversal
+    unsafe fn rank_two_unchecked(&self, symbol: u8, start: usize, end: usize) -> (usize, usize) {
+        let code = self.codes[symbol as usize];
+
+        if code.len == NO_CODE {
+            return (0, 0);
+        }
+
+        let mut node_ref = self.root;
+        let mut start = start;
+        let mut end = end;
+
+        for depth in 0..code.len {
+            let node = &self.nodes[node_ref];
+            let direction = code.direction_at(depth);
+
+            start = self.child_idx(node, start, direction);
+            end = self.child_idx(node, end, direction);
+            node_ref = node.children[direction];
+        }
+
+        (start, end)
+    }
+
+    fn prefetch_rank(&self, symbol: u8, idx: usize) {
+        if symbol as usize >= self.alphabet_size || idx > self.text_len {
+            return;
+        }
+
+        // the later nodes depend on the rank in the root, which is a leaf without bits if only one symbol occurs
+        if let Some(node) = self.nodes.get(self.root) {
+            self.bits.prefetch_rank1(node.bits_start + idx);
+        }
+    }
+
+    // like for the rank, only the bits of the root can be prefetched, because the later nodes depend on them
+    fn symbols_at_many(&self, indices: &[usize], symbols: &mut [u8]) {
+        assert_eq!(indices.len(), symbols.len());
+
+        if let Some(root) = self.nodes.get(self.root) {
+            for &idx in indices {
+                self.bits.prefetch_get(root.bits_start + idx);
+            }
+        }
+
+        for (symbol, &idx) in symbols.iter_mut().zip(indices) {
+            *symbol = self.symbol_at(idx);
+        }
+    }
+
+    fn symbol_at(&self, idx: usize) -> u8 {
+        assert!(idx < self.text_len);
+
+        let mut node_ref = self.root;
+        let mut idx = idx;
+
+        while node_ref & LEAF_FLAG == 0 {
+            let node = &self.nodes[node_ref];
+            let direction = self.bits.get(node.bits_start + idx) as usize;
+
+            idx = self.child_idx(node, idx, direction);
+            node_ref = node.children[direction];
+        }
+
+        (node_ref & !LEAF_FLAG) as u8
+    }
+}
+
+// returns the root, the children of the internal nodes and the number of bits of every internal node.
+// ties are broken by the references of the nodes, such that the tree is deterministic.
+fn build_huffman_tree(counts: &[usize]) -> (usize, Vec<[usize; 2]>, Vec<usize>) {
+    let mut queue: BinaryHeap<_> = counts
+        .iter()
+        .enumerate()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(symbol, &count)| Reverse((count, symbol | LEAF_FLAG)))
+        .collect();
+
+    let mut children = Vec::new();
+    let mut node_lens = Vec::new();
+
+    while queue.len() > 1 {
+        let Reverse((count0, node_ref0)) = queue.pop().unwrap();
+        let Reverse((count1, node_ref1)) = queue.pop().unwrap();
+
+        children.push([node_ref0, node_ref1]);
+        node_lens.push(count0 + count1);
+
+        queue.push(Reverse((count0 + count1, children.len() - 1)));
+    }
+
+    // for an empty text, the root is an arbitrary leaf that is never reached by a rank query
+    let root = queue
+        .pop()
+        .map_or(LEAF_FLAG, |Reverse((_, node_ref))| node_ref);
+
+    (root, children, node_lens)
+}
+
+fn codes_of_tree(root: usize, children: &[[usize; 2]], alphabet_size: usize) -> Vec<Code> {
+    let mut codes = vec![
+        Code {
+            bits: 0,
+            len: NO_CODE,
+        };
+        alphabet_size
+    ];
+
+    let mut stack = vec![(root, Code { bits: 0, len: 0 })];
+
+    while let Some((node_ref, code)) = stack.pop() {
+        if node_ref & LEAF_FLAG != 0 {
+            codes[node_ref & !LEAF_FLAG] = code;
+            continue;
+        }
+
+        for (direction, &child) in children[node_ref].iter().enumerate() {
+            stack.push((
+                child,
+                Code {
+                    bits: code.bits | ((direction as u128) << code.len),
+                    len: code.len + 1,
+                },
+            ));
+        }
+    }
+
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huffman_code_lengths() {
+        // symbol 2 doesn't occur, the frequent symbol 0 gets the shortest code
+        let text = [0, 0, 0, 0, 0, 0, 1, 1, 3, 4, 0, 0];
+        let ranks = WaveletTreeTextWithRankSupport::<u32>::construct(&text, 5);
+
+        let code_lens: Vec<_> = ranks.codes.iter().map(|code| code.len).collect();
+        assert_eq!(code_lens, [1, 2, NO_CODE, 3, 3]);
+
+        assert_eq!(ranks.rank(2, 12), 0);
+        assert_eq!(ranks.rank_two(0, 3, 12), (3, 8));
+        assert_eq!(ranks.symbol_at(9), 4);
+
+        let single_symbol = WaveletTreeTextWithRankSupport::<u32>::construct(&[1, 1, 1], 3);
+        assert!(single_symbol.nodes.is_empty());
+        assert_eq!(single_symbol.rank(1, 2), 2);
+        assert_eq!(single_symbol.rank(0, 3), 0);
+        assert_eq!(single_symbol.symbol_at(1), 1);
+    }
+
+    #[test]
+    fn corrupted_nodes() {
+        let text = [0, 0, 1, 2, 2, 2, 3];
+        let mut ranks = WaveletTreeTextWithRankSupport::<u32>::construct(&text, 4);
+        assert_eq!(ranks.verify(&text), Ok(()));
+
+        // a cycle would lead to an endless traversal in symbol_at
+        let root = ranks.root;
+        ranks.nodes[root].children[0] = root;
+
+        assert_eq!(
+            ranks.verify(&text),
+            Err(RankSupportMismatch::Structure { component: "nodes" })
+        );
+
+        ranks.codes.pop();
+
+        assert_eq!(
+            ranks.verify(&text),
+            Err(RankSupportMismatch::Structure { component: "codes" })
+        );
+    }
+}
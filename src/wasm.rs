@@ -0,0 +1,135 @@
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+use crate::{Alphabet, FmIndex, FmIndexConfig, alphabet};
+
+/// An FM-Index that can be used from JavaScript.
+///
+/// This is a thin wrapper around an [`FmIndex<u32>`] with the default configuration. The total length of
+/// the texts is therefore limited to about 4 GiB, which is more than enough for browser applications.
+#[wasm_bindgen]
+pub struct WasmFmIndex {
+    index: FmIndex<u32>,
+}
+
+#[wasm_bindgen]
+impl WasmFmIndex {
+    /// Constructs the index from an array of `Uint8Array`s.
+    ///
+    /// The `alphabet_name` can be one of `"dna"`, `"dna_n"`, `"dna_iupac"`, `"amino_acid"`,
+    /// `"amino_acid_iupac"` and `"ascii_printable"`. The construction runs on a single thread in the browser.
+    #[wasm_bindgen(constructor)]
+    pub fn new(texts: Array, alphabet_name: &str) -> Result<WasmFmIndex, JsError> {
+        let alphabet = alphabet_from_name(alphabet_name)
+            .ok_or_else(|| JsError::new(&format!("unknown alphabet: {alphabet_name}")))?;
+
+        let texts: Vec<_> = texts
+            .iter()
+            .map(|text| {
+                text.dyn_into::<Uint8Array>()
+                    .map(|text| text.to_vec())
+                    .map_err(|_| JsError::new("all texts must be Uint8Arrays"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        for text in &texts {
+            if let Some(&symbol) = text
+                .iter()
+                .find(|&&s| alphabet.try_io_to_dense_representation(s).is_none())
+            {
+                return Err(JsError::new(&format!(
+                    "text contains symbol {symbol} that is not part of the alphabet"
+                )));
+            }
+        }
+
+        let index = FmIndexConfig::<u32>::new().construct_index(texts, alphabet);
+
+        Ok(Self { index })
+    }
+
+    /// Returns the number of occurrences of `query`. Symbols that are not part of the alphabet lead to zero occurrences.
+    pub fn count(&self, query: &[u8]) -> usize {
+        if self.is_valid_query(query) {
+            self.index.count(query)
+        } else {
+            0
+        }
+    }
+
+    /// Returns all occurrences of `query`, sorted by text id and position.
+    pub fn locate(&self, query: &[u8]) -> WasmHits {
+        let mut hits: Vec<_> = if self.is_valid_query(query) {
+            self.index.locate(query).collect()
+        } else {
+            Vec::new()
+        };
+
+        hits.sort();
+
+        WasmHits {
+            text_ids: hits.iter().map(|hit| hit.text_id as u32).collect(),
+            positions: hits.iter().map(|hit| hit.position as u32).collect(),
+        }
+    }
+
+    #[wasm_bindgen(getter, js_name = numTexts)]
+    pub fn num_texts(&self) -> usize {
+        self.index.num_texts()
+    }
+
+    #[wasm_bindgen(getter, js_name = totalTextLen)]
+    pub fn total_text_len(&self) -> usize {
+        self.index.total_text_len()
+    }
+}
+
+impl WasmFmIndex {
+    fn is_valid_query(&self, query: &[u8]) -> bool {
+        query.iter().all(|&s| {
+            self.index
+                .alphabet()
+                .try_io_to_dense_representation(s)
+                .is_some()
+        })
+    }
+}
+
+/// The occurrences of a query, as two typed arrays of the same length.
+#[wasm_bindgen]
+pub struct WasmHits {
+    text_ids: Vec<u32>,
+    positions: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl WasmHits {
+    #[wasm_bindgen(getter, js_name = textIds)]
+    pub fn text_ids(&self) -> Vec<u32> {
+        self.text_ids.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn positions(&self) -> Vec<u32> {
+        self.positions.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.text_ids.len()
+    }
+}
+
+fn alphabet_from_name(name: &str) -> Option<Alphabet> {
+    let alphabet = match name {
+        "dna" => alphabet::ascii_dna(),
+        "dna_n" => alphabet::ascii_dna_with_n(),
+        "dna_iupac" => alphabet::ascii_dna_iupac(),
+        "amino_acid" => alphabet::ascii_amino_acid(),
+        "amino_acid_iupac" => alphabet::ascii_amino_acid_iupac(),
+        "ascii_printable" => alphabet::ascii_printable(),
+        _ => return None,
+    };
+
+    Some(alphabet)
+}
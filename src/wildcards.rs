@@ -0,0 +1,259 @@
+use crate::{
+    BATCH_SIZE, Cursor, FmIndex, HalfOpenInterval, IndexStorage, PatternTooAmbiguous, pattern,
+    text_with_rank_support::TextWithRankSupport,
+};
+
+/// Designates IO symbols that match any searchable symbol of the alphabet when they occur in a query.
+///
+/// For example, an `N` in a DNA read usually means that the base is unknown. When `N` is designated as a wildcard,
+/// the query `ANT` matches `AAT`, `ACT`, `AGT` and `ATT`. The wildcard symbols don't have to be part of the alphabet.
+///
+/// The search branches at every wildcard, and the branches are merged when their suffix array intervals are
+/// adjacent. The number of branches is bounded by the number of occurrences, but queries with many wildcards can
+/// still lead to many branches. Therefore, the search is aborted with a [`PatternTooAmbiguous`] error when the
+/// number of branches exceeds a limit, which can be configured using [`Self::max_branches`]. The default is 2^20. It does not reject queries with a
+/// few wildcards, like `NNNNNNA`, because the number of branches is also bounded by the length of the texts.
+///
+/// It is used by functions like [`FmIndex::try_count_with_wildcards`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wildcards {
+    is_wildcard: [bool; 256],
+    max_branches: usize,
+}
+
+// large enough for queries with a few wildcards, but it prevents exponential work for long runs of wildcards
+const DEFAULT_MAX_BRANCHES: usize = 1 << 20;
+
+impl Wildcards {
+    pub fn new(io_symbols: &[u8]) -> Self {
+        let mut is_wildcard = [false; 256];
+
+        for &symbol in io_symbols {
+            is_wildcard[symbol as usize] = true;
+        }
+
+        Self {
+            is_wildcard,
+            max_branches: DEFAULT_MAX_BRANCHES,
+        }
+    }
+
+    /// Sets the maximum number of branches (distinct suffix array intervals) of the search of a single query.
+    /// Panics if `max_branches` is `0`.
+    pub fn max_branches(self, max_branches: usize) -> Self {
+        assert!(max_branches > 0, "max_branches must be greater than 0");
+
+        Self {
+            max_branches,
+            ..self
+        }
+    }
+
+    pub fn is_wildcard(&self, io_symbol: u8) -> bool {
+        self.is_wildcard[io_symbol as usize]
+    }
+
+    fn contains_wildcard(&self, query: &[u8]) -> bool {
+        query.iter().any(|&symbol| self.is_wildcard(symbol))
+    }
+}
+
+pub(crate) fn cursors_for_wildcard_query<'a, I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &'a FmIndex<I, R>,
+    query: &[u8],
+    wildcards: &Wildcards,
+) -> Result<Vec<Cursor<'a, I, R>>, PatternTooAmbiguous> {
+    let Some(last_wildcard_idx) = query
+        .iter()
+        .rposition(|&symbol| wildcards.is_wildcard(symbol))
+    else {
+        return Ok(vec![index.cursor_for_query(query)]);
+    };
+
+    // the part of the query behind the last wildcard is searched normally, such that the lookup table can be used
+    let mut intervals = vec![
+        index
+            .cursor_for_query(&query[last_wildcard_idx + 1..])
+            .interval(),
+    ];
+
+    let searchable_symbols: Vec<_> = pattern::searchable_symbols(index.alphabet()).collect();
+
+    for &symbol in query[..=last_wildcard_idx].iter().rev() {
+        if intervals.is_empty() {
+            break;
+        }
+
+        if wildcards.is_wildcard(symbol) {
+            intervals = extend_front(index, &intervals, &searchable_symbols);
+
+            if intervals.len() > wildcards.max_branches {
+                return Err(PatternTooAmbiguous {
+                    max_branches: wildcards.max_branches,
+                });
+            }
+        } else {
+            let symbol = index.alphabet().io_to_dense_representation(symbol);
+            intervals = extend_front(index, &intervals, &[symbol]);
+        }
+    }
+
+    Ok(intervals
+        .into_iter()
+        .map(|interval| Cursor { index, interval })
+        .collect())
+}
+
+// The extended intervals are sorted, because the intervals of a symbol are sorted and the intervals of
+// smaller symbols come first in the suffix array. Therefore, adjacent intervals can be merged directly.
+fn extend_front<I: IndexStorage, R: TextWithRankSupport<I>>(
+    index: &FmIndex<I, R>,
+    intervals: &[HalfOpenInterval],
+    symbols: &[u8],
+) -> Vec<HalfOpenInterval> {
+    let mut extended: Vec<HalfOpenInterval> = Vec::new();
+
+    for &symbol in symbols {
+        for &interval in intervals {
+            let interval = index.lf_mapping_step_interval(symbol, interval);
+
+            if interval.start == interval.end {
+                continue;
+            }
+
+            match extended.last_mut() {
+                Some(last) if last.end == interval.start => last.end = interval.end,
+                _ => extended.push(interval),
+            }
+        }
+    }
+
+    extended
+}
+
+// Queries without wildcards are searched using the batched search, the others are searched one by one.
+pub(crate) fn cursors_for_many_wildcard_queries<'a, I, R, Q>(
+    index: &'a FmIndex<I, R>,
+    queries: impl IntoIterator<Item = Q>,
+    wildcards: &'a Wildcards,
+) -> impl Iterator<Item = Result<Vec<Cursor<'a, I, R>>, PatternTooAmbiguous>>
+where
+    I: IndexStorage,
+    R: TextWithRankSupport<I>,
+    Q: AsRef<[u8]>,
+{
+    let mut queries = queries.into_iter();
+
+    std::iter::from_fn(move || {
+        let batch: Vec<_> = queries.by_ref().take(BATCH_SIZE).collect();
+
+        if batch.is_empty() {
+            return None;
+        }
+
+        let mut plain_cursors = index.cursors_for_many_queries(
+            batch
+                .iter()
+                .map(AsRef::as_ref)
+                .filter(|query| !wildcards.contains_wildcard(query)),
+        );
+
+        let cursors: Vec<_> = batch
+            .iter()
+            .map(|query| {
+                let query = query.as_ref();
+
+                if wildcards.contains_wildcard(query) {
+                    cursors_for_wildcard_query(index, query, wildcards)
+                } else {
+                    Ok(vec![plain_cursors.next().unwrap()])
+                }
+            })
+            .collect();
+
+        Some(cursors)
+    })
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FmIndexConfig, alphabet};
+    use proptest::prelude::*;
+
+    #[test]
+    fn branch_limit() {
+        let index = FmIndexConfig::<i32>::new()
+            .construct_index([b"ACGTTGCAAGCTAGCTTACG"], alphabet::ascii_dna());
+
+        let wildcards = Wildcards::new(b"N");
+
+        assert_eq!(index.try_count_with_wildcards(b"NN", &wildcards), Ok(19));
+        // "G" is the only symbol at the end of the text, so the interval of "GN" is split from "CN"
+        assert_eq!(
+            index.try_count_with_wildcards(b"NN", &wildcards.clone().max_branches(1)),
+            Err(PatternTooAmbiguous { max_branches: 1 })
+        );
+        // the branches of the last wildcard are merged into a single interval
+        assert_eq!(
+            index.try_count_with_wildcards(b"AN", &wildcards.max_branches(1)),
+            Ok(5)
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn wildcard_search_agrees_with_naive(
+            texts in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGT".as_slice()), 0..60), 1..4),
+            queries in prop::collection::vec(prop::collection::vec(prop::sample::select(b"ACGTN".as_slice()), 0..6), 1..80),
+            lookup_table_depth in 0usize..4,
+        ) {
+            let index = FmIndexConfig::<i32>::new()
+                .suffix_array_sampling_rate(3)
+                .lookup_table_depth(lookup_table_depth)
+                .construct_index(&texts, alphabet::ascii_dna());
+
+            let wildcards = Wildcards::new(b"N");
+
+            let matches = |window: &[u8], query: &[u8]| {
+                window
+                    .iter()
+                    .zip(query)
+                    .all(|(&text_symbol, &query_symbol)| query_symbol == b'N' || text_symbol == query_symbol)
+            };
+
+            let many_counts: Vec<_> = index.try_count_many_with_wildcards(&queries, &wildcards).collect();
+
+            for (query, many_count) in queries.iter().zip(many_counts) {
+                let mut expected = Vec::new();
+
+                for (text_id, text) in texts.iter().enumerate() {
+                    if query.is_empty() {
+                        expected.extend((0..=text.len()).map(|position| (text_id, position)));
+                        continue;
+                    }
+
+                    for (position, window) in text.windows(query.len()).enumerate() {
+                        if matches(window, query) {
+                            expected.push((text_id, position));
+                        }
+                    }
+                }
+
+                let mut hits: Vec<_> = index
+                    .try_locate_with_wildcards(query, &wildcards)
+                    .unwrap()
+                    .map(|hit| (hit.text_id, hit.position))
+                    .collect();
+                hits.sort();
+
+                prop_assert_eq!(index.try_count_with_wildcards(query, &wildcards), Ok(expected.len()));
+                prop_assert_eq!(many_count, Ok(expected.len()));
+                prop_assert_eq!(hits, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// Every example is included as a module and its main function is executed as a test, such that the examples
+// are guaranteed to work with the features that they are compiled with. Examples that need optional features
+// only run if the features are enabled, e.g. with `cargo test --test examples --features io-align`.
+
+macro_rules! example_tests {
+    ($($(#[$attr:meta])* $name:ident),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            mod $name {
+                include!(concat!("../examples/", stringify!($name), ".rs"));
+
+                #[test]
+                fn runs() {
+                    main();
+                }
+            }
+        )*
+    };
+}
+
+example_tests!(
+    basic_usage,
+    bulk_search,
+    construction,
+    cursor,
+    custom_alphabet,
+    text_with_rank_support,
+    #[cfg(feature = "savefile")]
+    save_and_load,
+    #[cfg(feature = "io-align")]
+    alignment_output,
+    #[cfg(feature = "bench")]
+    block_benchmark,
+);
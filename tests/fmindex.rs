@@ -1,4 +1,14 @@
-use genedex::{FmIndex, FmIndexConfig, Hit, IndexStorage, PerformancePriority, alphabet};
+use genedex::{
+    Alphabet, ConstructionStage, EmptyQueryMatches, EmptyTextPolicy, FmIndex, FmIndexConfig, Hit,
+    IndexStorage, PatternTooAmbiguous, PerformancePriority, SliceCompressionMode, TextSampling,
+    Wildcards, alphabet,
+    bwt::{self, SentinelOrder},
+    test_utils::{RandomInputs, naive_search},
+    text_with_rank_support::{
+        Block512, FlatTextWithRankSupport, PackedTextWithRankSupport,
+        WaveletTreeTextWithRankSupport,
+    },
+};
 use proptest::prelude::*;
 use rand::SeedableRng;
 use rand_chacha::ChaCha8Rng;
@@ -153,77 +163,1578 @@ fn u8_alphabet() {
     assert_eq!(results, expected_results);
 }
 
-struct QuerySampler<'t, 'r> {
-    texts: &'t [Vec<u8>],
-    rng: &'r mut ChaCha8Rng,
-    max_extent: usize,
+#[test]
+fn query_coverage() {
+    let texts = [b"cccaaagggttt".as_slice(), b"acgtacgtacgt"];
+
+    let index = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(3)
+        .construct_index(texts, alphabet::ascii_dna());
+
+    let coverage = index.coverage(b"gggtTTAAAAcgta", 4);
+
+    assert_eq!(coverage.num_kmers(), 11);
+    assert_eq!(coverage.num_matching_kmers(), 5);
+    assert_eq!(coverage.covered_ranges().collect::<Vec<_>>(), [0..6, 9..14]);
+}
+
+#[test]
+fn fasta_files_grouping() {
+    let dir = std::env::temp_dir().join(format!(
+        "genedex_fasta_files_grouping_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let filepaths = [dir.join("genome1.fa"), dir.join("genome2.fa")];
+    std::fs::write(&filepaths[0], ">contig1\nACGTAC\nGTAC\n>contig2\nTTTT\n").unwrap();
+    std::fs::write(&filepaths[1], ">contig1\nGTACCC\n").unwrap();
+
+    let index = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(3)
+        .construct_index_from_fasta_files(&filepaths, alphabet::ascii_dna())
+        .unwrap();
+
+    let sampled_index = FmIndexConfig::<i32>::new()
+        .text_sampling(TextSampling::EveryNth(2))
+        .construct_index_from_fasta_files(&filepaths, alphabet::ascii_dna())
+        .unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(sampled_index.text_ids_of_file(0), 0..1);
+    assert_eq!(sampled_index.text_ids_of_file(1), 1..2);
+    assert_eq!(sampled_index.original_text_ids().unwrap(), [0, 2]);
+
+    assert_eq!(index.num_texts(), 3);
+    assert_eq!(index.num_files(), 2);
+    assert_eq!(index.text_ids_of_file(0), 0..2);
+    assert_eq!(index.text_ids_of_file(1), 2..3);
+
+    let file_ids: HashSet<_> = index
+        .locate(b"GTAC")
+        .map(|hit| (index.file_id(hit.text_id), hit.text_id, hit.position))
+        .collect();
+
+    assert_eq!(
+        file_ids,
+        HashSet::from_iter([(0, 0, 2), (0, 0, 6), (1, 2, 0)])
+    );
+    assert_eq!(index.count_per_file(b"GTAC"), [2, 1]);
+    assert_eq!(index.count_per_file(b"TT"), [3, 0]);
+
+    #[cfg(feature = "savefile")]
+    {
+        let mut buffer = Vec::new();
+        index.save_to_writer(&mut buffer).unwrap();
+        let loaded_index = FmIndex::<i32>::load_from_reader(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(loaded_index.text_ids_of_file(1), 2..3);
+        assert_eq!(
+            loaded_index.construction_params(),
+            index.construction_params()
+        );
+        assert_eq!(
+            loaded_index.text_names().unwrap(),
+            ["contig1", "contig2", "contig1"]
+        );
+    }
+}
+
+#[test]
+fn text_sampling() {
+    let mut inputs = RandomInputs::new(3);
+    let reads = inputs.texts(b"ACGT", 200, 30);
+    let records: Vec<_> = reads
+        .iter()
+        .enumerate()
+        .map(|(id, read)| (format!("read{id}"), read))
+        .collect();
+
+    let sampling = TextSampling::Random {
+        one_in: 4,
+        seed: 17,
+    };
+    let config = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(3)
+        .text_sampling(sampling);
+
+    let index = config
+        .clone()
+        .construct_index_from_records(records, alphabet::ascii_dna());
+    let original_text_ids = index.original_text_ids().unwrap().to_vec();
+
+    // the same seed selects the same texts
+    assert_eq!(
+        config
+            .construct_index(&reads, alphabet::ascii_dna())
+            .original_text_ids()
+            .unwrap(),
+        original_text_ids
+    );
+    assert!(original_text_ids.len() > 20 && original_text_ids.len() < 80);
+    assert!(original_text_ids.is_sorted());
+
+    let sampled_reads: Vec<_> = original_text_ids.iter().map(|&id| &reads[id]).collect();
+
+    for text_id in 0..index.num_texts() {
+        let original_text_id = index.original_text_id(text_id);
+
+        assert_eq!(
+            index.text_name(text_id).unwrap(),
+            format!("read{original_text_id}")
+        );
+        assert_eq!(&index.extract_text(text_id), &reads[original_text_id]);
+    }
+
+    for query in [b"A".as_slice(), b"CG", b"TTA"] {
+        let mut hits: Vec<_> = index.locate(query).collect();
+        hits.sort();
+        assert_eq!(
+            hits,
+            naive_search(&sampled_reads, query, EmptyQueryMatches::AllPositions)
+        );
+    }
+
+    #[cfg(feature = "savefile")]
+    {
+        let mut buffer = Vec::new();
+        index.save_to_writer(&mut buffer).unwrap();
+        let loaded_index = FmIndex::<i32>::load_from_reader(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(loaded_index.text_sampling(), sampling);
+        assert_eq!(loaded_index.original_text_ids().unwrap(), original_text_ids);
+    }
+
+    let sub_index = index.subset(&[1, 3]);
+    assert_eq!(sub_index.original_text_id(1), original_text_ids[3]);
+
+    let every_third = FmIndexConfig::<i32>::new()
+        .text_sampling(TextSampling::EveryNth(3))
+        .construct_index(&reads, alphabet::ascii_dna());
+    assert_eq!(every_third.num_texts(), 67);
+    assert_eq!(every_third.original_text_id(5), 15);
+
+    let all = FmIndexConfig::<i32>::new().construct_index(&reads, alphabet::ascii_dna());
+    assert_eq!(all.text_sampling(), TextSampling::All);
+    assert_eq!(all.original_text_ids(), None);
+    assert_eq!(all.original_text_id(5), 5);
+}
+
+#[test]
+fn subset_keeps_configuration() {
+    let texts = RandomInputs::new(3).texts(b"ACGT", 5, 300);
+
+    let index = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(3)
+        .lookup_table_depth(2)
+        .construction_performance_priority(PerformancePriority::LowMemory)
+        .slice_compression(SliceCompressionMode::On)
+        .record_bwt_run_boundaries(true)
+        .empty_query_matches(EmptyQueryMatches::NoPositions)
+        .empty_text_policy(EmptyTextPolicy::Reject)
+        .construct_index(&texts, alphabet::ascii_dna());
+
+    let sub_index = index.subset(&[4, 1]);
+
+    assert_eq!(sub_index.construction_params(), index.construction_params());
+    assert_eq!(
+        sub_index
+            .construction_params()
+            .unwrap()
+            .slice_compression_mode,
+        SliceCompressionMode::On
+    );
+    assert_eq!(
+        sub_index.empty_query_matches(),
+        EmptyQueryMatches::NoPositions
+    );
+    assert_eq!(sub_index.empty_text_policy(), EmptyTextPolicy::Reject);
+    assert!(sub_index.bwt_runs().unwrap().has_run_boundaries());
+}
+
+#[test]
+fn subset_keeps_sentinel_order() {
+    // the empty query matches all positions, in the order of the suffix array
+    fn suffix_order(index: &FmIndex<i32>) -> Vec<(usize, usize)> {
+        index
+            .locate(b"")
+            .map(|hit| (hit.text_id, hit.position))
+            .collect()
+    }
+
+    let alphabet = alphabet::ascii_dna();
+    // the texts share the suffix "A", so the order of their sentinels matters
+    let texts = [b"CA".as_slice(), b"GA", b"TA", b"AA"];
+    let text_ids = [2, 0, 3];
+    let sub_texts: Vec<_> = text_ids.iter().map(|&text_id| texts[text_id]).collect();
+
+    let index_from_bwt = |texts: &[&[u8]], sentinel_order: &SentinelOrder| {
+        let bwt = bwt::construct_bwt_with_sentinel_order::<i32, _>(
+            texts,
+            &alphabet,
+            false,
+            sentinel_order,
+        );
+        let symbols: Vec<_> = bwt.iter().collect();
+
+        FmIndex::<i32>::from_bwt(
+            &symbols,
+            bwt.sentinel_suffix_array_values(),
+            alphabet.clone(),
+            FmIndexConfig::new(),
+        )
+    };
+
+    for (sentinel_order, sub_sentinel_order) in [
+        (
+            SentinelOrder::ByFollowingTexts,
+            SentinelOrder::ByFollowingTexts,
+        ),
+        (SentinelOrder::ByTextId, SentinelOrder::ByTextId),
+        (
+            SentinelOrder::ByRank(vec![0, 3, 2, 1]),
+            SentinelOrder::ByRank(vec![2, 0, 1]),
+        ),
+    ] {
+        let sub_index = index_from_bwt(&texts, &sentinel_order).subset(&text_ids);
+        let expected = index_from_bwt(&sub_texts, &sub_sentinel_order);
+
+        assert_eq!(suffix_order(&sub_index), suffix_order(&expected));
+    }
+}
+
+#[test]
+fn named_records_with_empty_texts() {
+    let records = [
+        ("chr1", b"ACGTAC".as_slice()),
+        ("empty", b""),
+        ("chr2", b"GTACGG"),
+    ];
+
+    let index = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(3)
+        .construct_index_from_records(records, alphabet::ascii_dna());
+
+    assert_eq!(index.empty_text_policy(), EmptyTextPolicy::Keep);
+    assert_eq!(index.num_texts(), 3);
+    assert_eq!(index.text_len(1), 0);
+    assert_eq!(index.text_names().unwrap(), ["chr1", "empty", "chr2"]);
+
+    let names: HashSet<_> = index
+        .locate(b"GTAC")
+        .map(|hit| (index.text_name(hit.text_id).unwrap(), hit.position))
+        .collect();
+
+    assert_eq!(names, HashSet::from_iter([("chr1", 2), ("chr2", 0)]));
+
+    let sub_index = index.subset(&[2, 1]);
+    assert_eq!(sub_index.text_names().unwrap(), ["chr2", "empty"]);
+
+    #[cfg(feature = "savefile")]
+    {
+        let mut buffer = Vec::new();
+        index.save_to_writer(&mut buffer).unwrap();
+        let loaded_index = FmIndex::<i32>::load_from_reader(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(loaded_index.text_name(2), Some("chr2"));
+        assert_eq!(loaded_index.empty_text_policy(), EmptyTextPolicy::Keep);
+    }
+
+    let unnamed_index = FmIndexConfig::<i32>::new()
+        .empty_text_policy(EmptyTextPolicy::Reject)
+        .construct_index([b"ACGT"], alphabet::ascii_dna());
+
+    assert_eq!(unnamed_index.text_name(0), None);
+    assert_eq!(unnamed_index.text_names(), None);
+    assert_eq!(unnamed_index.empty_text_policy(), EmptyTextPolicy::Reject);
 }
 
-impl<'t, 'r> Iterator for QuerySampler<'t, 'r> {
-    type Item = (Hit, &'t [u8]);
+#[test]
+#[should_panic(expected = "empty texts are rejected")]
+fn reject_empty_texts() {
+    FmIndexConfig::<i32>::new()
+        .empty_text_policy(EmptyTextPolicy::Reject)
+        .construct_index_from_records(
+            [("chr1", b"ACGT".as_slice()), ("empty", b"")],
+            alphabet::ascii_dna(),
+        );
+}
+
+#[test]
+fn degenerate_pattern_branch_limit() {
+    let text = RandomInputs::new(7).text(b"ACGT", 100_000);
+    let index = FmIndexConfig::<i32>::new().construct_index([&text], alphabet::ascii_dna());
+
+    // every combination of the wildcards leads to a separate suffix array interval
+    let pattern = b"......A";
+    let expected = text.windows(7).filter(|window| window[6] == b'A').count();
+
+    assert_eq!(
+        index.try_count_pattern(pattern, 1024),
+        Err(PatternTooAmbiguous { max_branches: 1024 })
+    );
+    assert_eq!(index.try_count_pattern(pattern, 4096), Ok(expected));
+    assert_eq!(index.count_pattern(pattern), expected);
+    assert!(index.try_locate_prosite_motif(b"x(6)-A", 1024).is_err());
+}
+
+#[test]
+fn degenerate_wildcard_query_default_branch_limit() {
+    let text = RandomInputs::new(7).text(b"ACGT", 100_000);
+    let index = FmIndexConfig::<i32>::new().construct_index([&text], alphabet::ascii_dna());
+    let wildcards = Wildcards::new(b"N");
+
+    let expected = text.windows(7).filter(|window| window[6] == b'A').count();
+
+    assert_eq!(
+        index.try_count_with_wildcards(b"NNNNNNA", &wildcards),
+        Ok(expected)
+    );
+    assert_eq!(
+        index.try_count_with_wildcards(b"NNNNNNA", &wildcards.max_branches(1024)),
+        Err(PatternTooAmbiguous { max_branches: 1024 })
+    );
+}
+
+#[cfg(feature = "savefile")]
+#[test]
+fn bookmarks_after_load() {
+    let texts = [b"ACGTTGCAAGCTAGCTTACG".as_slice(), b"GCTAAGCT"];
+    let mut index = FmIndexConfig::<i32>::new()
+        .lookup_table_depth(2)
+        .construct_index(texts, alphabet::ascii_dna());
+
+    index.add_bookmark("adapter", b"AGCT");
+    index.add_bookmark("missing", b"GGGG");
+    index.add_bookmark("removed", b"A");
+    assert!(index.remove_bookmark("removed"));
+
+    let mut buffer = Vec::new();
+    index.save_to_writer(&mut buffer).unwrap();
+    let loaded_index = FmIndex::<i32>::load_from_reader(&mut buffer.as_slice()).unwrap();
+
+    assert_eq!(
+        loaded_index.bookmark_names().collect::<Vec<_>>(),
+        ["adapter", "missing"]
+    );
+    assert!(loaded_index.cursor_for_bookmark("removed").is_none());
+    assert_eq!(
+        loaded_index.cursor_for_bookmark("missing").unwrap().count(),
+        0
+    );
+
+    let mut cursor = loaded_index.cursor_for_bookmark("adapter").unwrap();
+    let mut hits: Vec<_> = cursor.locate().collect();
+    hits.sort();
+    assert_eq!(
+        hits,
+        naive_search(&texts, b"AGCT", EmptyQueryMatches::AllPositions)
+    );
+
+    cursor.extend_query_front(b'A');
+    assert_eq!(cursor.count(), loaded_index.count(b"AAGCT"));
+}
+
+#[cfg(feature = "savefile")]
+#[test]
+fn component_hashes_after_load() {
+    let texts = [b"ACGTTGCAAGCTAGCTTACG".as_slice(), b"GCTAAGCT"];
+    let index = FmIndexConfig::<u32>::new()
+        .suffix_array_sampling_rate(4)
+        .construct_index(texts, alphabet::ascii_dna());
+
+    let mut buffer = Vec::new();
+    index.save_to_writer(&mut buffer).unwrap();
+    let loaded_index = FmIndex::<u32>::load_from_reader(&mut buffer.as_slice()).unwrap();
+
+    assert_eq!(loaded_index.component_hashes(), index.component_hashes());
+
+    let i64_index = FmIndexConfig::<i64>::new()
+        .suffix_array_sampling_rate(4)
+        .construct_index(texts, alphabet::ascii_dna());
+    assert_eq!(i64_index.component_hashes(), index.component_hashes());
+}
+
+#[test]
+fn construction_from_dense_texts() {
+    let texts = [b"ACGTNTGCAAGCTAGCTTACG".as_slice(), b"", b"GCTAAGCTN"];
+    let alphabet = alphabet::ascii_dna_with_n();
+
+    let dense_texts: Vec<Vec<u8>> = texts
+        .iter()
+        .map(|text| {
+            text.iter()
+                .map(|&symbol| alphabet.io_to_dense_representation(symbol))
+                .collect()
+        })
+        .collect();
+
+    let config = || {
+        FmIndexConfig::<i32>::new()
+            .suffix_array_sampling_rate(3)
+            .lookup_table_depth(2)
+    };
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.texts.is_empty() {
-            return None;
+    let index = config().construct_index(texts, alphabet.clone());
+    let dense_index = config().construct_index_from_dense_texts(&dense_texts, alphabet);
+
+    assert_eq!(dense_index.component_hashes(), index.component_hashes());
+    assert_eq!(dense_index.count(b"GCT"), 4);
+}
+
+#[test]
+#[should_panic]
+fn dense_texts_with_sentinel() {
+    FmIndexConfig::<i32>::new()
+        .construct_index_from_dense_texts([[1, 2, 0, 3]], alphabet::ascii_dna());
+}
+
+#[test]
+#[should_panic]
+fn dense_texts_outside_of_alphabet() {
+    FmIndexConfig::<i32>::new()
+        .construct_index_from_dense_texts([[1, 2, 5, 3]], alphabet::ascii_dna());
+}
+
+#[test]
+fn bwt_and_suffix_array_samples() {
+    let texts = [b"ACGTTGCAAGCTAGCTTACG".as_slice(), b"", b"GCTAAGCT"];
+    let alphabet = alphabet::ascii_dna();
+
+    let index = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(3)
+        .construct_index(texts, alphabet.clone());
+    let u32_index = FmIndexConfig::<u32>::new()
+        .suffix_array_sampling_rate(3)
+        .construct_index(texts, alphabet.clone());
+    let i64_index = FmIndexConfig::<i64>::new()
+        .suffix_array_sampling_rate(3)
+        .construct_index(texts, alphabet.clone());
+
+    let bwt = genedex::bwt::construct_bwt::<i32, _>(texts, &alphabet, false);
+    assert!(index.bwt_iter().eq(bwt.iter()));
+    assert!(u32_index.bwt_iter().eq(bwt.iter()));
+
+    let entries: Vec<_> = index.sampled_suffix_array_entries().collect();
+    assert_eq!(entries.len(), 11);
+    assert!(
+        u32_index
+            .sampled_suffix_array_entries()
+            .eq(entries.iter().copied())
+    );
+    assert!(
+        i64_index
+            .sampled_suffix_array_entries()
+            .eq(entries.iter().copied())
+    );
+
+    // the sampled rows can be recovered from the BWT and the sentinel rows
+    let symbols: Vec<_> = index.bwt_iter().collect();
+    let rebuilt_index = FmIndex::<i32>::from_bwt(
+        &symbols,
+        bwt.sentinel_suffix_array_values(),
+        alphabet,
+        FmIndexConfig::new().suffix_array_sampling_rate(3),
+    );
+    assert!(rebuilt_index.sampled_suffix_array_entries().eq(entries));
+}
+
+#[test]
+fn rebuild_from_exported_bwt() {
+    let mut inputs = RandomInputs::new(23);
+
+    for _ in 0..10 {
+        let texts = inputs.texts(b"ACGT", 4, 200);
+        let index = FmIndexConfig::<i32, WaveletTreeTextWithRankSupport<i32>>::new()
+            .suffix_array_sampling_rate(3)
+            .construct_index(&texts, alphabet::ascii_dna());
+
+        // only the index is used, not the texts or their BWT
+        let symbols: Vec<_> = index.bwt_iter().collect();
+        let rebuilt_index = FmIndex::<i32>::from_bwt(
+            &symbols,
+            &index.sentinel_suffix_array_values(),
+            alphabet::ascii_dna(),
+            FmIndexConfig::new().suffix_array_sampling_rate(3),
+        );
+
+        assert!(rebuilt_index.bwt_iter().eq(symbols.iter().copied()));
+        assert!(
+            rebuilt_index
+                .sampled_suffix_array_entries()
+                .eq(index.sampled_suffix_array_entries())
+        );
+
+        for _ in 0..10 {
+            let Some((_, query)) = inputs.sampled_query(&texts, 8) else {
+                continue;
+            };
+
+            let mut hits: Vec<_> = rebuilt_index.locate(query).collect();
+            let mut expected_hits: Vec<_> = index.locate(query).collect();
+            hits.sort();
+            expected_hits.sort();
+
+            assert_eq!(hits, expected_hits);
         }
+    }
+}
+
+#[test]
+fn heap_size_bytes() {
+    let texts = [b"ACGTTGCAAGCTAGCTTACG".repeat(50), b"GCTAAGCT".to_vec()];
+
+    let sizes = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(1)
+        .lookup_table_depth(0)
+        .construct_index(&texts, alphabet::ascii_dna())
+        .heap_size_bytes();
+
+    // one value per suffix, including the sentinels
+    assert!(sizes.suffix_array >= (1000 + 8 + 2) * 4);
+    assert_eq!(sizes.bwt_runs, 0);
+    assert!(sizes.text_with_rank_support > 0 && sizes.text_ids > 0 && sizes.other > 0);
+
+    let other_sizes = FmIndexConfig::<i64>::new()
+        .suffix_array_sampling_rate(4)
+        .lookup_table_depth(4)
+        .record_bwt_run_boundaries(true)
+        .construct_index(&texts, alphabet::ascii_dna())
+        .heap_size_bytes();
+
+    assert!(other_sizes.suffix_array < sizes.suffix_array);
+    assert!(other_sizes.lookup_tables > sizes.lookup_tables);
+    assert!(other_sizes.bwt_runs > 0);
+    assert_eq!(
+        other_sizes.total(),
+        other_sizes.text_with_rank_support
+            + other_sizes.suffix_array
+            + other_sizes.lookup_tables
+            + other_sizes.text_ids
+            + other_sizes.bwt_runs
+            + other_sizes.other
+    );
+}
+
+#[test]
+fn hit_set_resolution() {
+    let texts = [b"cccaaagggttt".as_slice(), b"acgtacgtacgt"];
+
+    let index = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(3)
+        .construct_index(texts, alphabet::ascii_dna());
+
+    let hit_set = index.hit_set(MULTI_QUERY);
+    let mut expected: Vec<_> = index.locate(MULTI_QUERY).collect();
+
+    assert_eq!(hit_set.count(), 4);
+    assert_eq!(hit_set.resolve_all_par(), expected);
+    assert_eq!(hit_set.iter().collect::<Vec<_>>(), expected);
+
+    expected.sort();
+
+    assert_eq!(hit_set.take_sorted(2), expected[..2]);
+    assert_eq!(hit_set.take_sorted(10), expected);
+    assert_eq!(hit_set.take_sorted(usize::MAX), expected);
+    assert!(index.hit_set(b"aaaa").is_empty());
+}
+
+#[test]
+fn locate_with_sa_index() {
+    let index = create_index::<u32>();
+
+    let annotated_hits: Vec<_> = index.locate_with_sa_index(MULTI_QUERY).collect();
+    let hits: Vec<_> = index.locate(MULTI_QUERY).collect();
+
+    assert_eq!(annotated_hits.len(), 1);
+    assert_eq!(annotated_hits[0].1, hits[0]);
+
+    let all_annotated_hits: Vec<_> = index.locate_with_sa_index(b"").collect();
+
+    for (expected_sa_index, (sa_index, hit)) in all_annotated_hits.into_iter().enumerate() {
+        assert_eq!(sa_index, expected_sa_index);
+
+        let suffix = &b"cccaaagggttt"[hit.position..];
+        let sa_index_of_suffix = index.locate_with_sa_index(suffix).next().unwrap().0;
+
+        // the empty suffix is a prefix of all suffixes, so it is the only one that can't be found uniquely
+        if !suffix.is_empty() {
+            assert_eq!(sa_index_of_suffix, sa_index);
+        }
+    }
+}
+
+#[test]
+fn disabled_texts() {
+    let texts = [b"ACGTACGT".as_slice(), b"TTACGA", b"GACGTT"];
+    let mut index = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(3)
+        .construct_index(texts, alphabet::ascii_dna());
+
+    assert!(index.disable_text(1));
+    assert!(!index.disable_text(1));
+    assert!(index.is_text_disabled(1));
+    assert_eq!(index.disabled_texts().collect::<Vec<_>>(), [1]);
+
+    let mut expected: Vec<_> = naive_search(&texts, b"ACG", EmptyQueryMatches::AllPositions)
+        .into_iter()
+        .filter(|hit| hit.text_id != 1)
+        .collect();
+    expected.sort();
+
+    let mut hits: Vec<_> = index.locate(b"ACG").collect();
+    hits.sort();
+    assert_eq!(hits, expected);
+
+    let mut hits: Vec<_> = index.locate_many([b"ACG"]).flatten().collect();
+    hits.sort();
+    assert_eq!(hits, expected);
+
+    let mut hits = index.hit_set(b"ACG").resolve_all_par();
+    hits.sort();
+    assert_eq!(hits, expected);
+
+    let mut hits: Vec<_> = index
+        .cursor_for_query(b"ACG")
+        .locate_with_sa_index()
+        .map(|(_, hit)| hit)
+        .collect();
+    hits.sort();
+    assert_eq!(hits, expected);
+
+    assert_eq!(index.count(b"ACG"), 4);
+    assert_eq!(index.count_per_file(b"ACG"), [3]);
+
+    #[cfg(feature = "savefile")]
+    {
+        let mut buffer = Vec::new();
+        index.save_to_writer(&mut buffer).unwrap();
+        let loaded_index = FmIndex::<i32>::load_from_reader(&mut buffer.as_slice()).unwrap();
+        assert_eq!(loaded_index.disabled_texts().count(), 0);
+        assert_eq!(loaded_index.locate(b"ACG").count(), 4);
+    }
+
+    index.disable_text(2);
+    assert!(index.enable_text(1));
+    assert!(!index.enable_text(1));
+    assert_eq!(index.locate(b"ACG").count(), 3);
+
+    index.enable_all_texts();
+    assert_eq!(index.disabled_texts().count(), 0);
+    assert_eq!(index.locate(b"ACG").count(), 4);
+}
+
+#[test]
+fn cursor_parent() {
+    let mut inputs = RandomInputs::new(17);
+
+    for _ in 0..20 {
+        let texts = inputs.texts(b"ACGT", 3, 300);
+        let index = FmIndexConfig::<i32>::new()
+            .lookup_table_depth(3)
+            .suffix_array_sampling_rate(4)
+            .construct_index(&texts, alphabet::ascii_dna_with_n());
+
+        for _ in 0..20 {
+            let Some((_, query)) = inputs.sampled_query(&texts, 12) else {
+                continue;
+            };
+
+            let cursor = index.cursor_for_query(query);
+            let parent = cursor.parent();
+
+            if query.is_empty() {
+                assert!(parent.is_none());
+                continue;
+            }
+
+            // the longest prefix with more occurrences, searched naively
+            let parent_query = (0..query.len())
+                .rev()
+                .map(|len| &query[..len])
+                .find(|prefix| {
+                    naive_search(&texts, prefix, EmptyQueryMatches::AllPositions).len()
+                        > cursor.count()
+                })
+                .unwrap();
+
+            let parent = parent.unwrap();
+            assert_eq!(parent.count(), index.count(parent_query));
+
+            let mut hits: Vec<_> = parent.locate().collect();
+            hits.sort();
+            assert_eq!(
+                hits,
+                naive_search(&texts, parent_query, EmptyQueryMatches::AllPositions)
+            );
+        }
+    }
+
+    let index = FmIndexConfig::<i32>::new().construct_index([b"ACGT"], alphabet::ascii_dna());
+    assert!(index.cursor_for_query(b"GG").parent().is_none());
+    assert!(index.cursor_empty().parent().is_none());
+}
+
+#[test]
+fn cursor_drop_front() {
+    let mut inputs = RandomInputs::new(19);
+
+    for _ in 0..20 {
+        let texts = inputs.texts(b"ACGT", 3, 300);
+        let index = FmIndexConfig::<i32>::new()
+            .lookup_table_depth(3)
+            .suffix_array_sampling_rate(4)
+            .construct_index(&texts, alphabet::ascii_dna_with_n());
+
+        for _ in 0..20 {
+            let Some((_, query)) = inputs.sampled_query(&texts, 12) else {
+                continue;
+            };
+
+            let cursor = index.cursor_for_query(query);
+            let shortened = cursor.drop_front(query.len());
+
+            if query.is_empty() {
+                assert!(shortened.is_none());
+                continue;
+            }
+
+            let shortened = shortened.unwrap();
+            assert_eq!(shortened.count(), index.count(&query[1..]));
+
+            let mut hits: Vec<_> = shortened.locate().collect();
+            hits.sort();
+            assert_eq!(
+                hits,
+                naive_search(&texts, &query[1..], EmptyQueryMatches::AllPositions)
+            );
+        }
+    }
+
+    // dropping all symbols one after another, compared to searching every suffix of the query from scratch
+    let index =
+        FmIndexConfig::<i32>::new().construct_index([b"ACGTACGAAGT"], alphabet::ascii_dna());
+    let query = b"TACGAAG";
+    let mut cursor = index.cursor_for_query(query);
+
+    for len in (0..query.len()).rev() {
+        cursor = cursor.drop_front(len + 1).unwrap();
+        assert_eq!(cursor.count(), index.count(&query[query.len() - len..]));
+    }
+
+    assert!(index.cursor_for_query(b"GG").drop_front(2).is_none());
+    assert!(index.cursor_empty().drop_front(0).is_none());
+}
+
+#[test]
+#[should_panic]
+fn cursor_drop_front_wrong_query_len() {
+    let index =
+        FmIndexConfig::<i32>::new().construct_index([b"ACGTACGAAGT"], alphabet::ascii_dna());
+
+    // the first occurrence of ACG is followed by A, the other one by T
+    let _ = index.cursor_for_query(b"ACG").drop_front(4);
+}
+
+#[test]
+#[should_panic]
+fn cursor_drop_front_query_len_beyond_text() {
+    let index =
+        FmIndexConfig::<i32>::new().construct_index([b"ACGTACGAAGT"], alphabet::ascii_dna());
+
+    let _ = index.cursor_for_query(b"AGT").drop_front(5);
+}
+
+#[test]
+fn whole_text_membership() {
+    let mut rng = ChaCha8Rng::seed_from_u64(17);
+    let mut records: Vec<Vec<u8>> = (0..200)
+        .map(|_| {
+            let len = rng.random_range(0..8);
+            (0..len).map(|_| b"ACGT"[rng.random_range(0..4)]).collect()
+        })
+        .collect();
+    records.push(records[3].clone());
+
+    let index = FmIndexConfig::<i32>::new()
+        .lookup_table_depth(3)
+        .construct_index(&records, alphabet::ascii_dna());
+
+    let queries: Vec<Vec<u8>> = (0..300)
+        .map(|_| {
+            let len = rng.random_range(0..8);
+            (0..len).map(|_| b"ACGT"[rng.random_range(0..4)]).collect()
+        })
+        .chain(records.iter().cloned())
+        .collect();
+
+    let batched: Vec<_> = index.contains_texts(&queries).collect();
+
+    for (query, batched_text_id) in queries.iter().zip(batched) {
+        let expected = records.iter().position(|record| record == query);
+
+        assert_eq!(index.contains_text(query), expected);
+        assert_eq!(batched_text_id, expected);
+    }
+}
+
+#[test]
+fn anchored_search() {
+    let mut rng = ChaCha8Rng::seed_from_u64(23);
+    let texts: Vec<Vec<u8>> = (0..50)
+        .map(|_| {
+            let len = rng.random_range(0..30);
+            (0..len).map(|_| b"ACGT"[rng.random_range(0..4)]).collect()
+        })
+        .collect();
+
+    let index = FmIndexConfig::<i32>::new()
+        .suffix_array_sampling_rate(4)
+        .construct_index(&texts, alphabet::ascii_dna());
+
+    for query_len in 0..4 {
+        for _ in 0..20 {
+            let query: Vec<_> = (0..query_len)
+                .map(|_| b"ACGT"[rng.random_range(0..4)])
+                .collect();
 
-        let text_id = self.rng.random_range(0..self.texts.len());
-        let text = &self.texts[text_id];
+            let hits = naive_search(&texts, &query, EmptyQueryMatches::AllPositions);
+            let expected_prefix_matches: HashSet<_> = hits
+                .iter()
+                .filter(|hit| hit.position == 0)
+                .copied()
+                .collect();
+            let expected_suffix_matches: HashSet<_> = hits
+                .iter()
+                .filter(|hit| hit.position + query.len() == texts[hit.text_id].len())
+                .copied()
+                .collect();
 
-        if text.is_empty() {
-            return None;
+            assert_eq!(
+                index.locate_prefix_matches(&query).collect::<HashSet<_>>(),
+                expected_prefix_matches
+            );
+            assert_eq!(
+                index.locate_suffix_matches(&query).collect::<HashSet<_>>(),
+                expected_suffix_matches
+            );
         }
+    }
+}
+
+#[test]
+fn reverse_complement_search() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5);
+    let texts: Vec<Vec<u8>> = (0..4)
+        .map(|_| (0..300).map(|_| b"ACGT"[rng.random_range(0..4)]).collect())
+        .collect();
+
+    let index = FmIndexConfig::<i32>::new().construct_index(&texts, alphabet::ascii_dna());
+    let fmd_index = FmIndexConfig::<i32>::new().construct_fmd_index(&texts, alphabet::ascii_dna());
 
-        let position = self.rng.random_range(0..text.len());
-        let extent_range = 0..std::cmp::min(self.max_extent, text.len() - position + 1);
-        let extent = self.rng.random_range(extent_range);
+    for query in [b"ACG".as_slice(), b"ACGT", b"TTAGC", b"g", b"GGGGGGGGGG"] {
+        assert_eq!(index.count_with_revcomp(query), fmd_index.count(query));
 
-        Some((
-            Hit { text_id, position },
-            &text[position..position + extent],
-        ))
+        let mut hits: Vec<_> = index.locate_with_revcomp(query).collect();
+        let mut fmd_hits: Vec<_> = fmd_index.locate(query).collect();
+        hits.sort();
+        fmd_hits.sort();
+
+        assert_eq!(hits, fmd_hits);
     }
 }
 
-struct RandomQueryGenerator<'r> {
-    max_len: usize,
-    rng: &'r mut ChaCha8Rng,
+#[test]
+fn reverse_complement_search_custom_nucleotide_alphabet() {
+    let texts = [b"AACGTTNGGAC".as_slice(), b"GTCN"];
+    let alphabet = Alphabet::from_io_symbols(b"ACGTN", 1);
+
+    let index = FmIndexConfig::<i32>::new().construct_index(texts, alphabet.clone());
+    let fmd_index = FmIndexConfig::<i32>::new().construct_fmd_index(texts, alphabet);
+
+    for query in [b"GTC".as_slice(), b"AC", b"CGT"] {
+        assert_eq!(index.count_with_revcomp(query), fmd_index.count(query));
+    }
+
+    assert_eq!(index.count_with_revcomp(b"GTC"), 2);
 }
 
-impl<'r> Iterator for RandomQueryGenerator<'r> {
-    type Item = Vec<u8>;
+#[test]
+fn hit_traits() {
+    let index = FmIndexConfig::<i32>::new()
+        .construct_fmd_index([b"AACGTT".as_slice(), b"GGACG"], alphabet::ascii_dna());
+
+    let mut stranded_hits: Vec<_> = index.locate(b"ACG").collect();
+    stranded_hits.sort();
+
+    let displayed: Vec<_> = stranded_hits.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        displayed,
+        [
+            "text 0, position 1, forward strand",
+            "text 0, position 2, reverse strand",
+            "text 1, position 2, forward strand",
+        ]
+    );
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let len = self.rng.random_range(0..self.max_len);
-        let mut query = vec![0; len];
-        for q in query.iter_mut() {
-            *q = b"ACGT"[self.rng.random_range(0..4)];
+    let hits: Vec<Hit> = stranded_hits.into_iter().map(Hit::from).collect();
+    let tuples: Vec<(usize, usize)> = hits.iter().copied().map(Into::into).collect();
+    assert_eq!(tuples, [(0, 1), (0, 2), (1, 2)]);
+    assert_eq!(Hit::from((1, 2)), hits[2]);
+    assert_eq!(hits[0].to_string(), "text 0, position 1");
+
+    #[cfg(feature = "savefile")]
+    {
+        let mut buffer = Vec::new();
+        savefile::save(&mut buffer, 0, &hits).unwrap();
+        let loaded_hits: Vec<Hit> = savefile::load(&mut buffer.as_slice(), 0).unwrap();
+        assert_eq!(loaded_hits, hits);
+    }
+}
+
+#[test]
+fn empty_query_semantics() {
+    let texts = [b"ACGTACGT".to_vec(), b"".to_vec(), b"GGA".to_vec()];
+
+    for empty_query_matches in [
+        EmptyQueryMatches::AllPositions,
+        EmptyQueryMatches::TextPositions,
+        EmptyQueryMatches::NoPositions,
+    ] {
+        let index = FmIndexConfig::<i32>::new()
+            .suffix_array_sampling_rate(3)
+            .empty_query_matches(empty_query_matches)
+            .construct_index(&texts, alphabet::ascii_dna());
+
+        assert_eq!(index.empty_query_matches(), empty_query_matches);
+
+        for query in [b"".as_slice(), b"A", b"GA", b"CGTAC"] {
+            let expected: HashSet<_> = naive_search(&texts, query, index.empty_query_matches())
+                .into_iter()
+                .collect();
+
+            assert_eq!(index.count(query), expected.len());
+            assert_eq!(index.cursor_for_query(query).count(), expected.len());
+            assert_eq!(index.hit_set(query).count(), expected.len());
+            assert_eq!(index.count_pattern(query), expected.len());
+            assert_eq!(index.locate(query).collect::<HashSet<_>>(), expected);
+            assert_eq!(
+                index.locate_pattern(query).collect::<HashSet<_>>(),
+                expected
+            );
+            assert_eq!(
+                index
+                    .locate_with_sa_index(query)
+                    .map(|(_, hit)| hit)
+                    .collect::<HashSet<_>>(),
+                expected
+            );
         }
 
-        Some(query)
+        #[cfg(feature = "savefile")]
+        {
+            let mut buffer = Vec::new();
+            index.save_to_writer(&mut buffer).unwrap();
+            let loaded_index = FmIndex::<i32>::load_from_reader(&mut buffer.as_slice()).unwrap();
+
+            assert_eq!(loaded_index.empty_query_matches(), empty_query_matches);
+            assert_eq!(loaded_index.count(b""), index.count(b""));
+        }
+        assert_eq!(
+            index.subset(&[2, 0]).empty_query_matches(),
+            empty_query_matches
+        );
     }
 }
 
-fn naive_search(texts: &[Vec<u8>], query: &[u8]) -> HashSet<Hit> {
-    let mut hits = HashSet::new();
+#[cfg(unix)]
+#[test]
+fn lock_memory() {
+    use std::io::ErrorKind;
+
+    // the amount of locked memory of this process in bytes, only available on Linux
+    fn locked_bytes() -> Option<usize> {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|line| line.starts_with("VmLck:"))?;
+        let kilobytes: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kilobytes * 1024)
+    }
 
-    for (text_id, text) in texts.iter().enumerate() {
-        if query.is_empty() {
-            for position in 0..=text.len() {
-                hits.insert(Hit { text_id, position });
+    let text = RandomInputs::new(5).text(b"ACGT", 100_000);
+    let index = FmIndexConfig::<i32>::new()
+        .lookup_table_depth(8)
+        .construct_index([&text], alphabet::ascii_dna());
+    let sizes = index.heap_size_bytes();
+
+    let locked_before = locked_bytes();
+
+    match index.lock_memory() {
+        Ok(()) => {
+            // the lookup tables are the largest part of this index, so they must be locked, too
+            if let (Some(before), Some(after)) = (locked_before, locked_bytes()) {
+                assert!(after - before >= sizes.lookup_tables + sizes.text_with_rank_support);
             }
 
-            continue;
+            assert_eq!(index.count(b"ACGTACGT"), index.locate(b"ACGTACGT").count());
+            index.unlock_memory().unwrap();
+        }
+        // locking can fail in restricted environments due to resource limits
+        Err(err) => assert!(
+            matches!(
+                err.kind(),
+                ErrorKind::OutOfMemory | ErrorKind::PermissionDenied | ErrorKind::WouldBlock
+            ),
+            "unexpected error: {err}"
+        ),
+    }
+
+    // after unlocking or a failed attempt, no memory of the index stays locked
+    assert_eq!(locked_bytes(), locked_before);
+}
+
+#[cfg(all(feature = "memory-advice", target_os = "linux"))]
+#[test]
+fn advise_memory() {
+    use genedex::{IndexMemoryAdvice, MemoryAdvice};
+
+    let index = FmIndexConfig::<i32>::new()
+        .record_bwt_run_boundaries(true)
+        .construct_index([b"cccaaagggttt"], alphabet::ascii_dna());
+
+    for advice in [
+        MemoryAdvice::Normal,
+        MemoryAdvice::Random,
+        MemoryAdvice::Sequential,
+        MemoryAdvice::WillNeed,
+    ] {
+        index
+            .advise_memory(&IndexMemoryAdvice {
+                text_with_rank_support: advice,
+                suffix_array: advice,
+                bwt_run_boundaries: advice,
+            })
+            .unwrap();
+    }
+
+    index.advise_memory(&IndexMemoryAdvice::default()).unwrap();
+    assert_eq!(index.count(BASIC_QUERY), 2);
+}
+
+#[test]
+fn bwt_runs() {
+    let texts = [b"ACGTACGTACGTAAAA".as_slice(), b"ACGTACGTACGTAAAC", b"TTTT"];
+    let alphabet = alphabet::ascii_dna();
+
+    let bwt: Vec<_> = genedex::bwt::construct_bwt::<i32, _>(texts, &alphabet, false)
+        .iter()
+        .collect();
+    let expected_run_starts: Vec<_> = (0..bwt.len())
+        .filter(|&row| row == 0 || bwt[row] != bwt[row - 1])
+        .collect();
+
+    for performance_priority in [
+        PerformancePriority::HighSpeed,
+        PerformancePriority::Balanced,
+    ] {
+        let index = FmIndexConfig::<i32>::new()
+            .construction_performance_priority(performance_priority)
+            .construct_index(texts, alphabet.clone());
+
+        let runs = index.bwt_runs().unwrap();
+        assert_eq!(runs.num_runs(), expected_run_starts.len());
+        assert_eq!(runs.bwt_len(), bwt.len());
+        assert!(!runs.has_run_boundaries());
+        assert_eq!(runs.is_run_start(0), None);
+
+        let index = FmIndexConfig::<i32>::new()
+            .construction_performance_priority(performance_priority)
+            .record_bwt_run_boundaries(true)
+            .construct_index(texts, alphabet.clone());
+
+        let runs = index.bwt_runs().unwrap();
+        assert_eq!(
+            runs.run_starts().unwrap().collect::<Vec<_>>(),
+            expected_run_starts
+        );
+
+        let subset = index.subset(&[0, 2]);
+        assert!(subset.bwt_runs().unwrap().has_run_boundaries());
+    }
+}
+
+#[test]
+fn packed_rank_support() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    let texts: Vec<Vec<u8>> = (0..3)
+        .map(|_| {
+            (0..70_000)
+                .map(|_| b"ACGTNRY"[rng.random_range(0..7)])
+                .collect()
+        })
+        .collect();
+
+    let alphabet = alphabet::ascii_dna_iupac();
+    let expected_index = FmIndexConfig::<u32>::new().construct_index(&texts, alphabet.clone());
+
+    for performance_priority in [
+        PerformancePriority::HighSpeed,
+        PerformancePriority::Balanced,
+        PerformancePriority::LowMemory,
+    ] {
+        let index = FmIndexConfig::<u32, PackedTextWithRankSupport<u32>>::new()
+            .construction_performance_priority(performance_priority)
+            .construct_index(&texts, alphabet.clone());
+
+        assert_eq!(
+            index.construction_params().unwrap().text_with_rank_support,
+            "PackedTextWithRankSupport"
+        );
+
+        for query in [b"ACGTA".as_slice(), b"NNN", b"RYAC", b"T", b""] {
+            let mut hits: Vec<_> = index.locate(query).collect();
+            let mut expected_hits: Vec<_> = expected_index.locate(query).collect();
+
+            hits.sort();
+            expected_hits.sort();
+
+            assert_eq!(hits, expected_hits);
         }
+    }
+}
+
+#[test]
+fn wavelet_tree_rank_support() {
+    // skewed symbol frequencies, such that the codes of the Huffman-shaped tree have different lengths
+    let mut rng = ChaCha8Rng::seed_from_u64(13);
+    let texts: Vec<Vec<u8>> = (0..3)
+        .map(|_| {
+            (0..70_000)
+                .map(|_| {
+                    if rng.random_bool(0.6) {
+                        b'e'
+                    } else {
+                        rng.random_range(b' '..=b'~')
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let alphabet = alphabet::ascii_printable();
+    let expected_index = FmIndexConfig::<u32>::new().construct_index(&texts, alphabet.clone());
+
+    let index = FmIndexConfig::<u32, WaveletTreeTextWithRankSupport<u32>>::new()
+        .construct_index(&texts, alphabet.clone());
 
-        for (position, window) in text.windows(query.len()).enumerate() {
-            if window == query {
-                hits.insert(Hit { text_id, position });
+    assert_eq!(
+        index.construction_params().unwrap().text_with_rank_support,
+        "WaveletTreeTextWithRankSupport"
+    );
+
+    for query in [b"eee".as_slice(), b"e~e", b"Hi", b"e", b"{", b""] {
+        let mut hits: Vec<_> = index.locate(query).collect();
+        let mut expected_hits: Vec<_> = expected_index.locate(query).collect();
+
+        hits.sort();
+        expected_hits.sort();
+
+        assert_eq!(hits, expected_hits);
+    }
+
+    assert_eq!(index.extract_text(1), texts[1]);
+}
+
+#[test]
+fn interleaved_non_searchable_symbols() {
+    let mut rng = ChaCha8Rng::seed_from_u64(11);
+    let texts: Vec<Vec<u8>> = (0..3)
+        .map(|_| {
+            (0..2000)
+                .map(|_| b"ACGTN"[rng.random_range(0..5)])
+                .collect()
+        })
+        .collect();
+
+    let interleaved = Alphabet::from_io_symbols(b"ANCGT", 0).with_non_searchable_io_symbols(b"N");
+    let index = FmIndexConfig::<i32>::new()
+        .lookup_table_depth(4)
+        .construct_index(&texts, interleaved);
+
+    let expected_index =
+        FmIndexConfig::<i32>::new().construct_index(&texts, alphabet::ascii_dna_with_n());
+
+    for query in [b"ACGTA".as_slice(), b"CG", b"TTTT", b"GATTACA", b"T", b""] {
+        assert_eq!(index.count(query), expected_index.count(query));
+    }
+
+    #[cfg(feature = "savefile")]
+    {
+        let mut buffer = Vec::new();
+        index.save_to_writer(&mut buffer).unwrap();
+        let loaded_index = FmIndex::<i32>::load_from_reader(&mut buffer.as_slice()).unwrap();
+
+        assert!(loaded_index.alphabet() == index.alphabet());
+        assert_eq!(loaded_index.count(b"GATT"), index.count(b"GATT"));
+    }
+}
+
+#[cfg(feature = "savefile")]
+#[test]
+fn any_fm_index_load_detection() {
+    use genedex::{AnyFmIndex, text_with_rank_support::CondensedTextWithRankSupport};
+
+    let dir = std::env::temp_dir().join(format!(
+        "genedex_any_fm_index_load_detection_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let texts = [b"cccaaagggttt".as_slice(), b"acgtacgtacgt"];
+
+    let filepaths = [
+        dir.join("i32_condensed64.savefile"),
+        dir.join("u32_flat512.savefile"),
+        dir.join("i64_condensed512.savefile"),
+        dir.join("u32_wavelet_tree.savefile"),
+    ];
+
+    FmIndexConfig::<i32>::new()
+        .construct_index(texts, alphabet::ascii_dna())
+        .save_to_file(&filepaths[0])
+        .unwrap();
+    FmIndexConfig::<u32, FlatTextWithRankSupport<u32, Block512>>::new()
+        .construct_index(texts, alphabet::ascii_dna())
+        .save_to_file(&filepaths[1])
+        .unwrap();
+    FmIndexConfig::<i64, CondensedTextWithRankSupport<i64, Block512>>::new()
+        .construct_index(texts, alphabet::ascii_dna())
+        .save_to_file(&filepaths[2])
+        .unwrap();
+    FmIndexConfig::<u32, WaveletTreeTextWithRankSupport<u32>>::new()
+        .construct_index(texts, alphabet::ascii_dna())
+        .save_to_file(&filepaths[3])
+        .unwrap();
+
+    let indices: Vec<_> = filepaths
+        .iter()
+        .map(|filepath| AnyFmIndex::load_from_file(filepath).unwrap())
+        .collect();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(matches!(indices[0], AnyFmIndex::I32Condensed64(_)));
+    assert!(matches!(indices[1], AnyFmIndex::U32Flat512(_)));
+    assert!(matches!(indices[2], AnyFmIndex::I64Condensed512(_)));
+    assert!(matches!(indices[3], AnyFmIndex::U32WaveletTree(_)));
+
+    for index in indices {
+        assert_eq!(index.count(MULTI_QUERY), 4);
+        assert_eq!(index.locate(MULTI_QUERY).count(), 4);
+        assert_eq!(
+            index
+                .locate_many([MULTI_QUERY, b"ccc"])
+                .map(|hits| hits.count())
+                .collect::<Vec<_>>(),
+            [4, 1]
+        );
+    }
+}
+
+#[cfg(feature = "savefile")]
+#[test]
+fn any_fm_index_block_offset_round_trip() {
+    use genedex::{
+        AnyFmIndex,
+        text_with_rank_support::{Block64, CondensedTextWithRankSupport},
+    };
+
+    let dir = std::env::temp_dir().join(format!(
+        "genedex_any_fm_index_block_offset_round_trip_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let texts = [b"cccaaagggttt".as_slice(), b"acgtacgtacgt"];
+
+    let u8_filepath = dir.join("i32_condensed64_u8.savefile");
+    FmIndexConfig::<i32, CondensedTextWithRankSupport<i32, Block64, u8>>::new()
+        .construct_index(texts, alphabet::ascii_dna())
+        .save_to_file(&u8_filepath)
+        .unwrap();
+
+    let index = AnyFmIndex::load_from_file(&u8_filepath).unwrap();
+    assert!(matches!(index, AnyFmIndex::I32Condensed64U8(_)));
+    assert_eq!(index.count(MULTI_QUERY), 4);
+
+    let saved_filepath = dir.join("i32_condensed64_u8_saved_again.savefile");
+    index.save_to_file(&saved_filepath).unwrap();
+    assert!(matches!(
+        AnyFmIndex::load_from_file(&saved_filepath).unwrap(),
+        AnyFmIndex::I32Condensed64U8(_)
+    ));
+
+    #[cfg(target_pointer_width = "64")]
+    {
+        let u32_filepath = dir.join("i64_condensed512_u32.savefile");
+        FmIndexConfig::<i64, CondensedTextWithRankSupport<i64, Block512, u32>>::new()
+            .construct_index(texts, alphabet::ascii_dna())
+            .save_to_file(&u32_filepath)
+            .unwrap();
+
+        let index = AnyFmIndex::load_from_file(&u32_filepath).unwrap();
+        assert!(matches!(index, AnyFmIndex::I64Condensed512U32(_)));
+        assert_eq!(index.count(MULTI_QUERY), 4);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn concurrent_queries() {
+    let mut inputs = RandomInputs::new(42);
+    let texts = inputs.texts(b"ACGT", 8, 10_000);
+    let queries: Vec<_> = std::iter::repeat_with(|| inputs.query(b"ACGT", 12))
+        .filter(|query| query.len() >= 4)
+        .take(500)
+        .collect();
+
+    let index = FmIndexConfig::<i32, FlatTextWithRankSupport<i32, Block512>>::new()
+        .lookup_table_depth(3)
+        .construct_index(&texts, alphabet::ascii_dna());
+
+    let expected: Vec<Vec<Hit>> = queries
+        .iter()
+        .map(|query| index.locate(query).collect())
+        .collect();
+
+    std::thread::scope(|scope| {
+        for thread_id in 0..16 {
+            let index = &index;
+            let queries = &queries;
+            let expected = &expected;
+
+            scope.spawn(move || {
+                for _ in 0..4 {
+                    for (query, expected_hits) in queries.iter().zip(expected).skip(thread_id) {
+                        assert_eq!(index.count(query), expected_hits.len());
+                        assert_eq!(&index.locate(query).collect::<Vec<_>>(), expected_hits);
+                    }
+
+                    let many_hits: Vec<Vec<_>> = index
+                        .locate_many(queries)
+                        .map(|hits| hits.collect())
+                        .collect();
+                    assert_eq!(&many_hits, expected);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn progress_callback() {
+    use std::sync::{Arc, Mutex};
+
+    let progress = Arc::new(Mutex::new(Vec::new()));
+
+    let texts = [b"ACGTACGTTTGA".as_slice(), b"", b"GGATCACAGT"];
+    let stages = [
+        ConstructionStage::EncodeTexts,
+        ConstructionStage::ConstructSuffixArray,
+        ConstructionStage::ConstructBwt,
+        ConstructionStage::SampleSuffixArray,
+        ConstructionStage::ConstructRankSupport,
+        ConstructionStage::FillLookupTables,
+    ];
+
+    let check_progress = |expected_stages: &[ConstructionStage]| {
+        let progress: Vec<(ConstructionStage, f32)> =
+            std::mem::take(&mut *progress.lock().unwrap());
+
+        let reported_stages: Vec<_> = progress
+            .iter()
+            .filter(|(_, fraction)| *fraction == 0.0)
+            .map(|(stage, _)| *stage)
+            .collect();
+        assert_eq!(reported_stages, expected_stages);
+
+        for window in progress.windows(2) {
+            let ((stage, fraction), (next_stage, next_fraction)) = (window[0], window[1]);
+
+            // a stage can be repeated directly, for example for the lookup tables of a bidirectional index
+            if stage == next_stage && next_fraction > 0.0 {
+                assert!(fraction <= next_fraction);
+            } else {
+                assert_eq!((fraction, next_fraction), (1.0, 0.0));
             }
         }
+
+        assert_eq!(progress.last().unwrap().1, 1.0);
+    };
+
+    for performance_priority in [
+        PerformancePriority::HighSpeed,
+        PerformancePriority::LowMemory,
+    ] {
+        let config = || {
+            let progress = Arc::clone(&progress);
+
+            FmIndexConfig::<u32>::new()
+                .lookup_table_depth(4)
+                .construction_performance_priority(performance_priority)
+                .progress_callback(move |stage, fraction| {
+                    progress.lock().unwrap().push((stage, fraction))
+                })
+        };
+
+        config().construct_index(texts, alphabet::ascii_dna());
+        check_progress(&stages);
+
+        // the texts are encoded once, and the lookup tables are filled after both data structures are constructed
+        config().construct_bidirectional_index(texts, alphabet::ascii_dna());
+        check_progress(&[&stages[..5], &stages[1..5], &stages[5..], &stages[5..]].concat());
+    }
+}
+
+#[cfg(feature = "savefile")]
+#[test]
+fn construction_independent_of_thread_count() {
+    fn saved_index_with_threads<I: IndexStorage>(
+        config: &FmIndexConfig<I>,
+        texts: &[Vec<u8>],
+        num_threads: usize,
+    ) -> Vec<u8> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap();
+
+        let index = pool.install(|| config.clone().construct_index(texts, alphabet::ascii_dna()));
+
+        let mut buffer = Vec::new();
+        index.save_to_writer(&mut buffer).unwrap();
+        buffer
     }
 
-    hits
+    fn check<I: IndexStorage>(config: FmIndexConfig<I>, texts: &[Vec<u8>]) {
+        let expected = saved_index_with_threads(&config, texts, 1);
+
+        for num_threads in [2, 3, 8] {
+            assert!(saved_index_with_threads(&config, texts, num_threads) == expected);
+        }
+    }
+
+    // many texts, such that the text borders are spread over the chunks of the parallel BWT construction
+    let texts = RandomInputs::new(7).texts(b"ACGT", 300, 200);
+
+    check(FmIndexConfig::<i32>::new().lookup_table_depth(4), &texts);
+    check(
+        FmIndexConfig::<u32>::new()
+            .construction_performance_priority(PerformancePriority::LowMemory)
+            .record_bwt_run_boundaries(true),
+        &texts,
+    );
+    check(
+        FmIndexConfig::<i64>::new()
+            .construction_performance_priority(PerformancePriority::HighSpeed)
+            .suffix_array_sampling_rate(1),
+        &texts,
+    );
+}
+
+#[test]
+fn construction_thread_pool() {
+    use std::sync::{Arc, Mutex};
+
+    let recorded_num_threads = Arc::new(Mutex::new(Vec::new()));
+
+    let texts = RandomInputs::new(11).texts(b"ACGT", 50, 200);
+    let config = || {
+        let recorded_num_threads = Arc::clone(&recorded_num_threads);
+
+        FmIndexConfig::<i32>::new()
+            .lookup_table_depth(3)
+            .num_threads(3)
+            .progress_callback(move |stage, _| {
+                recorded_num_threads
+                    .lock()
+                    .unwrap()
+                    .push((stage, rayon::current_num_threads()))
+            })
+    };
+
+    let check_num_threads = || {
+        let num_threads: Vec<(ConstructionStage, usize)> =
+            std::mem::take(&mut *recorded_num_threads.lock().unwrap());
+
+        assert!(!num_threads.is_empty());
+        assert!(num_threads.iter().all(|&(_, num_threads)| num_threads == 3));
+    };
+
+    let index = config().construct_index(&texts, alphabet::ascii_dna());
+    check_num_threads();
+
+    config().construct_bidirectional_index(&texts, alphabet::ascii_dna());
+    check_num_threads();
+
+    let expected = FmIndexConfig::<i32>::new()
+        .lookup_table_depth(3)
+        .construct_index(&texts, alphabet::ascii_dna());
+
+    assert!(index.bwt_iter().eq(expected.bwt_iter()));
+
+    #[cfg(feature = "savefile")]
+    {
+        let mut buffer = Vec::new();
+        index.save_to_writer(&mut buffer).unwrap();
+        let mut expected_buffer = Vec::new();
+        expected.save_to_writer(&mut expected_buffer).unwrap();
+
+        assert!(buffer == expected_buffer);
+    }
+
+    // an existing thread pool is used by all constructions of the configuration
+    let thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap(),
+    );
+    let in_thread_pool = Arc::new(Mutex::new(Vec::new()));
+    let recorded_in_thread_pool = Arc::clone(&in_thread_pool);
+    let callback_thread_pool = Arc::clone(&thread_pool);
+
+    let config = FmIndexConfig::<i32>::new()
+        .thread_pool(thread_pool)
+        .progress_callback(move |_, _| {
+            recorded_in_thread_pool
+                .lock()
+                .unwrap()
+                .push(callback_thread_pool.current_thread_index().is_some())
+        });
+
+    config
+        .clone()
+        .construct_index(&texts, alphabet::ascii_dna());
+    config.construct_index(&texts, alphabet::ascii_dna());
+
+    let in_thread_pool = in_thread_pool.lock().unwrap();
+    assert!(!in_thread_pool.is_empty());
+    assert!(in_thread_pool.iter().all(|&in_thread_pool| in_thread_pool));
+}
+
+fn concatenated_suffix(texts: &[Vec<u8>], hit: Hit) -> Vec<u8> {
+    // sentinel is smaller than all symbols
+    let mut suffix = texts[hit.text_id][hit.position..].to_vec();
+    suffix.push(0);
+
+    for text in &texts[hit.text_id + 1..] {
+        suffix.extend_from_slice(text);
+        suffix.push(0);
+    }
+
+    suffix
 }
 
 fn run_queries<I: IndexStorage>(
@@ -258,6 +1769,69 @@ fn run_queries<I: IndexStorage>(
 
         assert_eq!(&results, naive_results);
         assert_eq!(&many_results, naive_results);
+
+        let filtered_results: HashSet<_> = index
+            .locate_filtered(query, |text_id| text_id % 2 == 0)
+            .collect();
+        let naive_filtered_results: HashSet<_> = naive_results
+            .iter()
+            .filter(|hit| hit.text_id % 2 == 0)
+            .copied()
+            .collect();
+
+        assert_eq!(filtered_results, naive_filtered_results);
+
+        let (count, match_length) = index.count_with_match_length(query);
+        assert_eq!(count, naive_results.len());
+
+        if count > 0 {
+            assert_eq!(match_length, query.len());
+        } else {
+            assert!(index.count(&query[query.len() - match_length..]) > 0);
+            assert_eq!(index.count(&query[query.len() - match_length - 1..]), 0);
+        }
+    }
+
+    // every query twice, such that the deduplication has something to do
+    let duplicated_queries = random_queries.iter().chain(random_queries);
+    let duplicated_naive_hits: Vec<_> = random_queries_naive_hits
+        .iter()
+        .chain(random_queries_naive_hits)
+        .collect();
+
+    let deduplicated_counts: Vec<_> = index
+        .count_many_deduplicated(duplicated_queries.clone())
+        .collect();
+    let deduplicated_hits: Vec<HashSet<_>> = index
+        .locate_many_deduplicated(duplicated_queries)
+        .map(|hits| hits.collect())
+        .collect();
+
+    assert_eq!(deduplicated_counts.len(), duplicated_naive_hits.len());
+    assert_eq!(deduplicated_hits.len(), duplicated_naive_hits.len());
+
+    for ((count, hits), naive_results) in deduplicated_counts
+        .into_iter()
+        .zip(deduplicated_hits)
+        .zip(duplicated_naive_hits)
+    {
+        assert_eq!(count, naive_results.len());
+        assert_eq!(&hits, naive_results);
+    }
+}
+
+fn check_suffix_array_order<I: IndexStorage>(
+    index: &FmIndex<I>,
+    texts: &[Vec<u8>],
+    queries: &[Vec<u8>],
+) {
+    for query in queries {
+        let suffixes: Vec<_> = index
+            .locate(query)
+            .map(|hit| concatenated_suffix(texts, hit))
+            .collect();
+
+        assert!(suffixes.is_sorted());
     }
 }
 
@@ -286,12 +1860,15 @@ proptest! {
             .build()
             .unwrap();
 
-        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut inputs = RandomInputs::new(seed);
 
-        let existing_queries: Vec<_> = QuerySampler{texts: &texts, max_extent: 200, rng: &mut rng }.take(20).collect();
-        let random_queries: Vec<_> = RandomQueryGenerator{max_len: 20, rng: &mut rng}.take(100).collect();
+        let existing_queries: Vec<_> = std::iter::from_fn(|| inputs.sampled_query(&texts, 200)).take(20).collect();
+        let random_queries: Vec<_> = std::iter::repeat_with(|| inputs.query(b"ACGT", 20)).take(100).collect();
 
-        let random_queries_naive_hits: Vec<_> = random_queries.iter().map(|q| naive_search(&texts, q)).collect();
+        let random_queries_naive_hits: Vec<HashSet<_>> = random_queries
+            .iter()
+            .map(|q| naive_search(&texts, q, EmptyQueryMatches::AllPositions).into_iter().collect())
+            .collect();
 
         pool.install(|| {
             let index_i32 = FmIndexConfig::<i32>::new()
@@ -310,6 +1887,21 @@ proptest! {
             run_queries(&index_i32, &existing_queries,&random_queries, &random_queries_naive_hits);
             run_queries(&index_u32, &existing_queries,&random_queries, &random_queries_naive_hits);
             run_queries(&index_i64, &existing_queries,&random_queries, &random_queries_naive_hits);
-        });
+
+            check_suffix_array_order(&index_i32, &texts, &random_queries[..10]);
+
+            for (text_id, text) in texts.iter().enumerate() {
+                prop_assert_eq!(&index_u32.extract_text(text_id), text);
+            }
+
+            let reversed_text_ids: Vec<_> = (0..texts.len()).rev().collect();
+            let sub_index = index_i64.subset(&reversed_text_ids);
+
+            for (sub_text_id, &text_id) in reversed_text_ids.iter().enumerate() {
+                prop_assert_eq!(&sub_index.extract_text(sub_text_id), &texts[text_id]);
+            }
+
+            Ok(())
+        })?;
     }
 }
@@ -1,47 +1,6 @@
 use proptest::prelude::*;
 
-use genedex::{IndexStorage, text_with_rank_support::*};
-
-type OccurrenceColumn<T> = Vec<T>;
-
-#[derive(Debug)]
-struct NaiveTextWithRankSupport {
-    data: Vec<OccurrenceColumn<usize>>,
-}
-
-impl NaiveTextWithRankSupport {
-    pub fn construct(text: &[u8], alphabet_size: usize) -> Self {
-        let mut data = Vec::new();
-
-        for symbol in 0..alphabet_size {
-            data.push(create_occurrence_column(symbol as u8, text));
-        }
-
-        Self { data }
-    }
-
-    // occurrences of the character in bwt[0, idx)
-    pub fn rank(&self, symbol: u8, idx: usize) -> usize {
-        self.data[symbol as usize][idx]
-    }
-}
-
-fn create_occurrence_column(target_symbol: u8, bwt: &[u8]) -> Vec<usize> {
-    let mut column = Vec::with_capacity(bwt.len() + 1);
-
-    let mut count = 0;
-    column.push(count);
-
-    for &r in bwt {
-        if r == target_symbol {
-            count += 1;
-        }
-
-        column.push(count);
-    }
-
-    column
-}
+use genedex::{IndexStorage, test_utils::NaiveTextWithRankSupport, text_with_rank_support::*};
 
 fn test_against_naive<I: IndexStorage, R: TextWithRankSupport<I>>(
     text: &[u8],
@@ -51,13 +10,26 @@ fn test_against_naive<I: IndexStorage, R: TextWithRankSupport<I>>(
     let naive_text_rank = NaiveTextWithRankSupport::construct(text, alphabet_size);
 
     assert_eq!(text_rank.text_len(), text.len());
+    assert_eq!(text_rank.verify(text), Ok(()));
 
     for (i, symbol) in text.iter().copied().enumerate() {
         assert_eq!(text_rank.symbol_at(i), symbol);
     }
 
+    // reversed, such that the indices are not accessed in order
+    let indices: Vec<_> = (0..text.len()).rev().collect();
+    let mut symbols = vec![0; text.len()];
+    text_rank.symbols_at_many(&indices, &mut symbols);
+    assert!(symbols.iter().eq(text.iter().rev()));
+
+    // prefetches are only hints, so invalid arguments are ignored
+    text_rank.prefetch_rank(alphabet_size as u8, 0);
+    text_rank.prefetch_rank(0, text.len() + 1);
+
     for symbol in 0..alphabet_size as u8 {
         for idx in 0..=text.len() {
+            text_rank.prefetch_rank(symbol, idx);
+
             assert_eq!(
                 text_rank.rank(symbol, idx),
                 naive_text_rank.rank(symbol, idx),
@@ -65,13 +37,136 @@ fn test_against_naive<I: IndexStorage, R: TextWithRankSupport<I>>(
             );
         }
     }
+
+    for idx in 0..=text.len() {
+        let expected: Vec<_> = (0..alphabet_size)
+            .map(|symbol| naive_text_rank.rank(symbol as u8, idx))
+            .collect();
+
+        assert_eq!(text_rank.rank_all(idx).as_ref(), expected, "idx: {idx}");
+    }
+
+    // the borders of the intervals fall into the same and into different blocks
+    for start in 0..=text.len() {
+        let symbol = text.get(start).copied().unwrap_or(0);
+
+        for interval_len in [0, 1, 15, 63, 300] {
+            let end = (start + interval_len).min(text.len());
+
+            assert_eq!(
+                text_rank.rank_two(symbol, start, end),
+                (
+                    naive_text_rank.rank(symbol, start),
+                    naive_text_rank.rank(symbol, end)
+                ),
+                "symbol: {symbol}, start: {start}, end: {end}"
+            );
+        }
+    }
 }
 
 fn test_different_block_sizes_against_naive(text: &[u8], alphabet_size: usize) {
     test_against_naive::<i32, CondensedTextWithRankSupport<i32, Block64>>(text, alphabet_size);
     test_against_naive::<u32, CondensedTextWithRankSupport<u32, Block512>>(text, alphabet_size);
+    test_against_naive::<i32, CondensedTextWithRankSupport<i32, Block64, u8>>(text, alphabet_size);
+    test_against_naive::<i64, CondensedTextWithRankSupport<i64, Block512, u32>>(
+        text,
+        alphabet_size,
+    );
     test_against_naive::<i64, FlatTextWithRankSupport<i64, Block64>>(text, alphabet_size);
     test_against_naive::<i32, FlatTextWithRankSupport<i32, Block512>>(text, alphabet_size);
+
+    if alphabet_size <= 16 {
+        test_against_naive::<u32, PackedTextWithRankSupport<u32>>(text, alphabet_size);
+    }
+
+    test_against_naive::<i64, WaveletTreeTextWithRankSupport<i64>>(text, alphabet_size);
+}
+
+// the padding bits are set, to check that they are ignored
+fn two_bit_pack(text: &[u8]) -> (Vec<u8>, Vec<usize>) {
+    let mut bytes = vec![u8::MAX; text.len().div_ceil(4) + 1];
+    let mut words = vec![usize::MAX; text.len().div_ceil(usize::BITS as usize / 2)];
+
+    for (idx, &symbol) in text.iter().enumerate() {
+        let byte_shift = 6 - (idx % 4) * 2;
+        bytes[idx / 4] &= !(0b11 << byte_shift);
+        bytes[idx / 4] |= symbol << byte_shift;
+
+        let symbols_per_word = usize::BITS as usize / 2;
+        let word_shift = usize::BITS as usize - 2 - (idx % symbols_per_word) * 2;
+        words[idx / symbols_per_word] &= !(0b11 << word_shift);
+        words[idx / symbols_per_word] |= (symbol as usize) << word_shift;
+    }
+
+    (bytes, words)
+}
+
+fn test_two_bit_packed_against_unpacked<I: IndexStorage, R: TextWithRankSupport<I>>(
+    text: &[u8],
+    alphabet_size: usize,
+) {
+    let (bytes, words) = two_bit_pack(text);
+
+    let text_rank: R = TextWithRankSupport::construct(text, alphabet_size);
+    let from_bytes: R =
+        TextWithRankSupport::construct_from_two_bit_packed(&bytes, text.len(), alphabet_size);
+    let from_words: R =
+        TextWithRankSupport::construct_from_two_bit_packed_words(&words, text.len(), alphabet_size);
+
+    for packed_text_rank in [from_bytes, from_words] {
+        assert_eq!(packed_text_rank.text_len(), text.len());
+
+        for (i, symbol) in text.iter().copied().enumerate() {
+            assert_eq!(packed_text_rank.symbol_at(i), symbol);
+        }
+
+        for symbol in 0..alphabet_size as u8 {
+            for idx in 0..=text.len() {
+                assert_eq!(
+                    packed_text_rank.rank(symbol, idx),
+                    text_rank.rank(symbol, idx),
+                    "symbol: {symbol}, idx: {idx}"
+                );
+            }
+        }
+    }
+}
+
+fn test_two_bit_packed_all_implementations(text: &[u8], alphabet_size: usize) {
+    test_two_bit_packed_against_unpacked::<i32, CondensedTextWithRankSupport<i32, Block64>>(
+        text,
+        alphabet_size,
+    );
+    test_two_bit_packed_against_unpacked::<u32, CondensedTextWithRankSupport<u32, Block512>>(
+        text,
+        alphabet_size,
+    );
+    test_two_bit_packed_against_unpacked::<i64, FlatTextWithRankSupport<i64, Block64>>(
+        text,
+        alphabet_size,
+    );
+    test_two_bit_packed_against_unpacked::<i32, FlatTextWithRankSupport<i32, Block512>>(
+        text,
+        alphabet_size,
+    );
+    test_two_bit_packed_against_unpacked::<u32, PackedTextWithRankSupport<u32>>(
+        text,
+        alphabet_size,
+    );
+    test_two_bit_packed_against_unpacked::<i64, WaveletTreeTextWithRankSupport<i64>>(
+        text,
+        alphabet_size,
+    );
+}
+
+#[test]
+fn two_bit_packed_multiple_superblocks() {
+    let text: Vec<_> = (0..200_003u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 30) as u8)
+        .collect();
+
+    test_two_bit_packed_all_implementations(&text, 4);
 }
 
 #[test]
@@ -132,4 +227,13 @@ proptest! {
     fn correctness_random_texts((text, alphabet_size) in text_over_alphabet()) {
         test_different_block_sizes_against_naive(&text, alphabet_size);
     }
+
+    #[test]
+    fn two_bit_packed_random_texts(
+        (text, alphabet_size) in (2usize..=4).prop_flat_map(|alphabet_size| {
+            (prop::collection::vec(0..alphabet_size as u8, 0..1000), Just(alphabet_size))
+        })
+    ) {
+        test_two_bit_packed_all_implementations(&text, alphabet_size);
+    }
 }